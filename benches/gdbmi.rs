@@ -0,0 +1,136 @@
+// Benchmarks of the parser of gdb's output for large (but realistic) results and streams.
+#[macro_use]
+extern crate criterion;
+extern crate ugdb;
+
+use criterion::{Criterion, Throughput};
+use std::convert::TryFrom;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use ugdb::gdbmi::output::{self, OutOfBandRecord, ResultOutput};
+use ugdb::gdbmi::types;
+use ugdb::gdbmi::OutOfBandRecordSink;
+
+struct DiscardingSink;
+
+impl OutOfBandRecordSink for DiscardingSink {
+    fn send(&self, _: OutOfBandRecord) {}
+}
+
+// Parse the output as if it was received from gdb and return the number of result records.
+fn process(output: &str) -> usize {
+    let (result_input, result_output) = mpsc::channel();
+    output::process_output(
+        output.as_bytes(),
+        result_input,
+        DiscardingSink,
+        Arc::new(AtomicBool::new(false)),
+        Arc::new(Mutex::new(None)),
+        None,
+        Arc::new(Mutex::new(None)),
+    );
+    result_output
+        .try_iter()
+        .filter(|o| match o {
+            ResultOutput::Record(_) => true,
+            ResultOutput::Prompt => false,
+        })
+        .count()
+}
+
+fn breakpoint_table(num_breakpoints: usize) -> String {
+    let body = (1..=num_breakpoints)
+        .map(|i| {
+            format!(
+                "bkpt={{number=\"{i}\",type=\"breakpoint\",disp=\"keep\",enabled=\"y\",\
+                 addr=\"0x{addr:016x}\",func=\"function_{i}\",file=\"src/file_{file}.c\",\
+                 fullname=\"/home/user/project/src/file_{file}.c\",line=\"{line}\",\
+                 thread-groups=[\"i1\"],cond=\"x > {i}\",times=\"0\",\
+                 script={{\"silent\",\"print x\"}},original-location=\"file_{file}.c:{line}\"}}",
+                i = i,
+                addr = 0x1000 + i * 0x10,
+                file = i % 50,
+                line = i % 2000 + 1
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "1^done,BreakpointTable={{nr_rows=\"{}\",nr_cols=\"6\",hdr=[],body=[{}]}}\n(gdb) \n",
+        num_breakpoints, body
+    )
+}
+
+fn disassembly(num_instructions: usize) -> String {
+    let body = (0..num_instructions)
+        .map(|i| {
+            format!(
+                "{{address=\"0x{:016x}\",func-name=\"main\",offset=\"{}\",\
+                 inst=\"mov    0x{:x}(%rbp),%eax\"}}",
+                0x1000 + i * 4,
+                i * 4,
+                i % 256
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("1^done,asm_insns=[{}]\n(gdb) \n", body)
+}
+
+fn stream_records(num_records: usize, length: usize) -> String {
+    let line = "output of the program \\\"quoted\\\"\\t".repeat(length / 32 + 1);
+    (0..num_records)
+        .map(|_| format!("~\"{}\\n\"\n", line))
+        .collect()
+}
+
+fn bench_breakpoint_table(c: &mut Criterion) {
+    let output = breakpoint_table(1000);
+    let mut group = c.benchmark_group("breakpoint table");
+    group.throughput(Throughput::Bytes(output.len() as u64));
+    group.bench_function("parse output", |b| b.iter(|| process(&output)));
+
+    let (result_input, result_output) = mpsc::channel();
+    output::process_output(
+        output.as_bytes(),
+        result_input,
+        DiscardingSink,
+        Arc::new(AtomicBool::new(false)),
+        Arc::new(Mutex::new(None)),
+        None,
+        Arc::new(Mutex::new(None)),
+    );
+    let results = match result_output.try_recv() {
+        Ok(ResultOutput::Record(record)) => record.results,
+        _ => panic!("Breakpoint table was not parsed"),
+    };
+    group.bench_function("parse breakpoints", |b| {
+        b.iter(|| types::BreakpointTable::try_from(&results).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_disassembly(c: &mut Criterion) {
+    let output = disassembly(10000);
+    let mut group = c.benchmark_group("disassembly");
+    group.throughput(Throughput::Bytes(output.len() as u64));
+    group.bench_function("parse output", |b| b.iter(|| process(&output)));
+    group.finish();
+}
+
+fn bench_stream_records(c: &mut Criterion) {
+    let output = stream_records(10000, 200);
+    let mut group = c.benchmark_group("stream records");
+    group.throughput(Throughput::Bytes(output.len() as u64));
+    group.bench_function("parse output", |b| b.iter(|| process(&output)));
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_breakpoint_table,
+    bench_disassembly,
+    bench_stream_records
+);
+criterion_main!(benches);
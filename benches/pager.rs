@@ -0,0 +1,197 @@
+// Benchmarks of loading (i.e., highlighting) and drawing large source files and disassemblies in
+// the pager, with the decorators of ugdb's source and assembly views.
+#[macro_use]
+extern crate criterion;
+extern crate ugdb;
+extern crate unsegen;
+extern crate unsegen_pager;
+
+use criterion::Criterion;
+use std::path::{Path, PathBuf};
+use ugdb::diagnostics::{Diagnostic, Severity};
+use ugdb::gdb::{Address, BreakPoint, SrcPosition};
+use ugdb::tui::branch_arrows::BranchArrows;
+use ugdb::tui::line_decorators::{
+    opcodes_width, AssemblyDecorator, AssemblyLine, SourceDecorator, SourceLine,
+};
+use ugdb::tui::render_profile::RenderProfile;
+use unsegen::base::{Height, LineIndex, LineNumber, Width, WindowBuffer};
+use unsegen::widget::{RenderingHints, Widget};
+use unsegen_pager::{Pager, PagerContent, SyntaxSet, SyntectHighlighter, ThemeSet};
+
+const NUM_LINES: usize = 20000;
+// A breakpoint (and a diagnostic) every this many lines (or instructions).
+const MARKER_INTERVAL: usize = 100;
+
+fn source_file(num_lines: usize) -> Vec<String> {
+    let function = [
+        "/* Compute the checksum of a buffer. */",
+        "static unsigned checksum(const unsigned char *buf, size_t len) {",
+        "    unsigned sum = 0;",
+        "    for (size_t i = 0; i < len; ++i) {",
+        "        sum = (sum << 1) ^ buf[i]; // \"rotate\" and combine",
+        "    }",
+        "    return sum;",
+        "}",
+        "",
+    ];
+    function
+        .iter()
+        .cycle()
+        .take(num_lines)
+        .map(|l| l.to_string())
+        .collect()
+}
+
+fn source_lines(lines: &[String]) -> Vec<SourceLine> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, content)| SourceLine {
+            content: content.clone(),
+            number: LineNumber::new(i + 1),
+            num_folded: 0,
+        })
+        .collect()
+}
+
+fn disassembly(num_lines: usize) -> Vec<AssemblyLine> {
+    let function = [
+        ("55", "push   %rbp"),
+        ("48 89 e5", "mov    %rsp,%rbp"),
+        ("31 c0", "xor    %eax,%eax"),
+        ("48 39 f0", "cmp    %rsi,%rax"),
+        ("73 0c", "jae    0x{end:x} <checksum+42>"),
+        ("0f b6 14 07", "movzbl (%rdi,%rax,1),%edx"),
+        ("48 83 c0 01", "add    $0x1,%rax"),
+        ("eb f0", "jmp    0x{loop:x} <checksum+12>"),
+        ("5d", "pop    %rbp"),
+        ("c3", "ret"),
+    ];
+    (0..num_lines)
+        .map(|i| {
+            let start = 0x1000 + (i / function.len() * function.len() * 4);
+            let (opcodes, instruction) = function[i % function.len()];
+            let content = instruction
+                .replace("{end:x}", &format!("{:x}", start + 32))
+                .replace("{loop:x}", &format!("{:x}", start + 12));
+            AssemblyLine::new(
+                content,
+                Address(0x1000 + i * 4),
+                None,
+                None,
+                Some(opcodes.to_owned()),
+            )
+        })
+        .collect()
+}
+
+fn breakpoint(number: usize, address: Address, src_pos: Option<SrcPosition>) -> BreakPoint {
+    BreakPoint {
+        number: number.to_string().parse().unwrap(),
+        address: Some(address),
+        enabled: !number.is_multiple_of(3),
+        src_pos,
+        hit_count: number as u64 * 7,
+        ignore_count: 0,
+        commands: Vec::new(),
+        condition: None,
+    }
+}
+
+fn draw<L, D>(c: &mut Criterion, name: &str, content: PagerContent<L, D>)
+where
+    L: unsegen_pager::PagerLine,
+    D: unsegen_pager::LineDecorator<Line = L>,
+{
+    let mut pager = Pager::new();
+    pager.load(content);
+    pager.go_to_line(LineIndex::new(NUM_LINES / 2)).unwrap();
+    let mut buffer = WindowBuffer::new(Width::new(200).unwrap(), Height::new(100).unwrap());
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            pager
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default())
+        })
+    });
+}
+
+fn bench_pager(c: &mut Criterion) {
+    let syntax_set = SyntaxSet::load_defaults_nonewlines();
+    let theme_set = ThemeSet::load_defaults();
+    let theme = &theme_set.themes["base16-ocean.dark"];
+    let file = Path::new("/home/user/project/src/checksum.c");
+    let lines = source_file(NUM_LINES);
+
+    let syntax = syntax_set.find_syntax_by_extension("c").unwrap();
+    c.bench_function("pager/highlight file", |b| {
+        b.iter(|| {
+            PagerContent::from_lines(source_lines(&lines))
+                .with_highlighter(&SyntectHighlighter::new(syntax, theme))
+        })
+    });
+
+    let breakpoints = (1..NUM_LINES / MARKER_INTERVAL)
+        .map(|i| {
+            let pos = SrcPosition::new(PathBuf::from(file), LineNumber::new(i * MARKER_INTERVAL));
+            breakpoint(i, Address(0x1000 + i * 4), Some(pos))
+        })
+        .collect::<Vec<_>>();
+    let diagnostics = (1..NUM_LINES / MARKER_INTERVAL)
+        .map(|i| Diagnostic {
+            pos: SrcPosition::new(
+                PathBuf::from(file),
+                LineNumber::new(i * MARKER_INTERVAL + 2),
+            ),
+            severity: Severity::Warning,
+            message: "unused variable".to_owned(),
+        })
+        .collect::<Vec<_>>();
+    draw(
+        c,
+        "pager/draw source",
+        PagerContent::from_lines(source_lines(&lines))
+            .with_highlighter(&SyntectHighlighter::new(syntax, theme))
+            .with_decorator(SourceDecorator::new(
+                file,
+                Some(LineNumber::new(NUM_LINES / 2)),
+                breakpoints.iter(),
+                &diagnostics,
+                RenderProfile::default(),
+            )),
+    );
+
+    let instructions = disassembly(NUM_LINES);
+    let address_range = instructions[0].address..Address(0x1000 + NUM_LINES * 4);
+    let breakpoints = (1..NUM_LINES / MARKER_INTERVAL)
+        .map(|i| breakpoint(i, Address(0x1000 + i * MARKER_INTERVAL * 4), None))
+        .collect::<Vec<_>>();
+    let opcodes_width = Some(opcodes_width(instructions.iter()));
+    let arrows = BranchArrows::new(instructions.iter().map(|l| (l.address, l.content.as_str())));
+    let syntax = syntax_set
+        .find_syntax_by_extension("s")
+        .unwrap_or(syntax_set.find_syntax_plain_text());
+    draw(
+        c,
+        "pager/draw assembly",
+        PagerContent::from_lines(instructions)
+            .with_highlighter(&SyntectHighlighter::new(syntax, theme))
+            .with_decorator(AssemblyDecorator::new(
+                address_range,
+                Some(Address(0x1000 + NUM_LINES * 2)),
+                breakpoints.iter(),
+                opcodes_width,
+                arrows,
+                Box::new(|address: Address| format!("{:#x}", address.0)),
+                RenderProfile::default(),
+            )),
+    );
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_pager
+}
+criterion_main!(benches);
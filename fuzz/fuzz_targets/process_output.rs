@@ -0,0 +1,32 @@
+// Feed arbitrary output (seeded with real transcripts, see ../seeds) to the parser of gdb's
+// output. Whatever gdb (or a vendor fork) emits, parsing must never panic.
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate ugdb;
+
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use ugdb::gdbmi::output::{self, OutOfBandRecord};
+use ugdb::gdbmi::OutOfBandRecordSink;
+
+struct DiscardingSink;
+
+impl OutOfBandRecordSink for DiscardingSink {
+    fn send(&self, _: OutOfBandRecord) {}
+}
+
+fuzz_target!(|data: &[u8]| {
+    // Keep the receiving end alive, so that all lines are parsed.
+    let (result_input, _result_output) = mpsc::channel();
+    output::process_output(
+        data,
+        result_input,
+        DiscardingSink,
+        Arc::new(AtomicBool::new(false)),
+        Arc::new(Mutex::new(None)),
+        None,
+        Arc::new(Mutex::new(None)),
+    );
+});
@@ -0,0 +1,1228 @@
+// Registry of all operations that can be triggered by the user. Every action is reachable as
+// `!<id>` in the console, via its key binding in container selection mode and using the `action`
+// ipc function (`ugdb remote action`).
+use function_trace::{ExportFormat, TraceSpec};
+use gdb::{DisassemblyFlavor, SkipKind};
+use gdbmi::capabilities::Feature;
+use gdbmi::commands::MiCommand;
+use gdbmi::ExecuteError;
+use nix::sys::signal::Signal;
+use run_settings::{self, RunConfiguration, RunSettings};
+use std::path::{Path, PathBuf};
+use tui::event_bus::DebugEvent;
+use tui::memory::{dump_memory, prompt_dump, prompt_restore, restore_memory};
+use tui::memory_map::{describe_region, search_memory};
+use tui::overlay::{Confirmation, Message, Picker, Prompt};
+use tui::skips::add_skip;
+use tui::toasts::ToastLevel;
+use tui::TuiContainerType;
+use unsegen::base::LineNumber;
+use watch_triggers::{self, Trigger};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Args {
+    None,
+    Optional(&'static str),
+    /// If triggered without arguments (e.g., using the key binding) the user is asked for them.
+    Required(&'static str),
+}
+
+pub struct Action {
+    pub id: &'static str,
+    pub args: Args,
+    pub description: &'static str,
+    /// Key in container selection mode. Can be changed in the `bindings` table of the config.
+    pub binding: Option<char>,
+    /// The action is not available if the debugger does not support this (e.g., lldb-mi).
+    pub requires: Option<Feature>,
+    run: fn(&str, &mut ::Context),
+}
+
+impl Action {
+    /// Usage as a console command, e.g., "!grep <pattern>".
+    pub fn usage(&self) -> String {
+        match self.args {
+            Args::None => format!("!{}", self.id),
+            Args::Optional(args) | Args::Required(args) => format!("!{} {}", self.id, args),
+        }
+    }
+
+    pub fn run(&self, args: &str, p: &mut ::Context) {
+        if let Some(feature) = self.requires {
+            let capabilities = p.gdb.mi.capabilities();
+            if !capabilities.supports(feature) {
+                let reason = capabilities.unsupported(feature);
+                return p.log(format!("!{} is not available: {}", self.id, reason));
+            }
+        }
+        match self.args {
+            Args::None if !args.is_empty() => p.log(format!("Usage: {}", self.usage())),
+            Args::Required(_) if args.is_empty() => p.log(format!("Usage: {}", self.usage())),
+            _ => (self.run)(args, p),
+        }
+    }
+
+    /// Run the action without arguments, asking for them first if they are required.
+    pub fn trigger(&'static self, p: &mut ::Context) {
+        match self.args {
+            Args::Required(args) => p.show_overlay(Prompt::new(
+                format!("!{} {}", self.id, args),
+                "",
+                move |args: String, p: &mut ::Context| self.run(args.trim(), p),
+            )),
+            Args::None | Args::Optional(_) => self.run("", p),
+        }
+    }
+}
+
+/// Keys that container selection mode handles itself (selecting containers, zooming, switching
+/// tabs and leaving it), so they cannot be bound to actions.
+pub const SELECTION_MODE_KEYS: &[char] = &[
+    'i', 'e', 's', 't', 'r', 'T', '\n', 'z', '[', ']', 'h', 'j', 'k', 'l',
+];
+
+pub fn find(id: &str) -> Option<&'static Action> {
+    ACTIONS.iter().find(|a| a.id == id)
+}
+
+/// Key bindings of the actions, i.e., the default ones with the changes of the config applied.
+#[derive(Clone, Default)]
+pub struct Bindings {
+    keys: Vec<(char, &'static Action)>,
+}
+
+impl Bindings {
+    pub fn new(changes: &[(String, Option<char>)]) -> Self {
+        let keys = ACTIONS
+            .iter()
+            .filter_map(|action| {
+                let key = changes
+                    .iter()
+                    .find(|(id, _)| id == action.id)
+                    .map(|&(_, key)| key)
+                    .unwrap_or(action.binding);
+                key.map(|key| (key, action))
+            })
+            .collect();
+        Bindings { keys }
+    }
+
+    pub fn action(&self, key: char) -> Option<&'static Action> {
+        self.keys.iter().find(|&&(k, _)| k == key).map(|&(_, a)| a)
+    }
+
+    pub fn key(&self, action: &Action) -> Option<char> {
+        self.keys
+            .iter()
+            .find(|(_, a)| a.id == action.id)
+            .map(|&(k, _)| k)
+    }
+}
+
+pub const ACTIONS: &[Action] = &[
+    Action {
+        id: "help",
+        args: Args::None,
+        description: "List all actions and their key bindings.",
+        binding: Some('?'),
+        requires: None,
+        run: help,
+    },
+    Action {
+        id: "palette",
+        args: Args::None,
+        description: "Search the actions by (fuzzily) typing their name or description and run the selected one (also using Ctrl-p in container selection mode).",
+        binding: None,
+        requires: None,
+        run: palette,
+    },
+    Action {
+        id: "stop",
+        args: Args::None,
+        description: "Interrupt the program (or the command gdb is executing).",
+        binding: None,
+        requires: None,
+        run: |_, p| {
+            if let Err(e) = p.gdb.mi.interrupt_execution() {
+                p.log(format!("Could not interrupt gdb: {}", e));
+            }
+        },
+    },
+    Action {
+        id: "args",
+        args: Args::Optional("[<arguments>]"),
+        description: "Start the program with the arguments on the next run (or edit them).",
+        binding: None,
+        requires: None,
+        run: arguments,
+    },
+    Action {
+        id: "env",
+        args: Args::Optional("[<name>[=<value>]]"),
+        description: "Set (or unset without a value) an environment variable of the program (or edit the changed ones).",
+        binding: Some('N'),
+        requires: Some(Feature::GdbCommands),
+        run: environment,
+    },
+    Action {
+        id: "cwd",
+        args: Args::Optional("[<directory>]"),
+        description: "Start the program in the directory on the next run (or edit it).",
+        binding: None,
+        requires: None,
+        run: working_directory,
+    },
+    Action {
+        id: "run-config",
+        args: Args::Optional("[<name>]"),
+        description: "Use the arguments, environment, working directory and commands of a run configuration (or pick one from a list).",
+        binding: Some('U'),
+        requires: None,
+        run: run_configuration,
+    },
+    Action {
+        id: "signal",
+        args: Args::Optional("[<signal>]"),
+        description: "Send a signal (e.g., TERM, KILL, USR1 or USR2) to the process of the selected inferior (or pick one from a list).",
+        binding: Some('G'),
+        requires: None,
+        run: signal,
+    },
+    Action {
+        id: "layout",
+        args: Args::Required("<layout>"),
+        description: "Change the layout (a layout string or the name of a preset).",
+        binding: None,
+        requires: None,
+        run: |args, p| p.try_change_layout(args.to_owned()),
+    },
+    Action {
+        id: "show",
+        args: Args::Optional("[file]"),
+        description: "Show a file in the pager (pick a source file if none is given).",
+        binding: Some('o'),
+        requires: None,
+        run: show,
+    },
+    Action {
+        id: "grep",
+        args: Args::Required("<pattern>"),
+        description: "Search the directories of all source files.",
+        binding: Some('/'),
+        requires: None,
+        run: |args, p| match p.gdb.get_source_files() {
+            Ok(files) => p.spawn_source_search(args.to_owned(), files),
+            Err(e) => p.log(format!("Failed to get list of source files: {:?}", e)),
+        },
+    },
+    Action {
+        id: "def",
+        args: Args::Required("<symbol>"),
+        description: "Show the definition of a symbol in the pager.",
+        binding: None,
+        requires: None,
+        run: |args, p| {
+            if !p.go_to_definition(args) {
+                p.log(format!("Could not find definition of '{}'.", args));
+            }
+        },
+    },
+    Action {
+        id: "linetable",
+        args: Args::None,
+        description: "Show the line table of the current function.",
+        binding: Some('L'),
+        requires: Some(Feature::GdbCommands),
+        run: |_, p| p.show_current_line_table(),
+    },
+    Action {
+        id: "memory",
+        args: Args::Required("<address>"),
+        description: "Show the memory at an address in the memory view.",
+        binding: Some('m'),
+        requires: Some(Feature::ReadMemoryBytes),
+        run: |args, p| match p.gdb.evaluate_address(args) {
+            Ok(address) => p.show_memory(address),
+            Err(e) => p.log(format!("Invalid address '{}': {:?}", args, e)),
+        },
+    },
+    Action {
+        id: "dump",
+        args: Args::Optional("[<address> <length> <file>]"),
+        description: "Write memory to a binary file (asks for the arguments if none are given).",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: dump,
+    },
+    Action {
+        id: "restore",
+        args: Args::Optional("[<file> <address>]"),
+        description: "Write a binary file to memory (asks for the arguments if none are given).",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: restore,
+    },
+    Action {
+        id: "find",
+        args: Args::Required("<pattern>"),
+        description: "Search the mapped memory for a string, hex bytes or the value of an expression.",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: |args, p| match p.gdb.get_memory_map() {
+            Ok(regions) => search_memory(&regions, args, p),
+            Err(e) => p.log(format!("Failed to get the memory map: {:?}", e)),
+        },
+    },
+    Action {
+        id: "stack",
+        args: Args::None,
+        description: "Show the annotated stack memory of the selected frame in the memory view.",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: |_, p| p.show_frame_memory(),
+    },
+    Action {
+        id: "memorymap",
+        args: Args::Optional("[<address>]"),
+        description: "Show the mapped memory regions (and which one contains the address).",
+        binding: Some('M'),
+        requires: Some(Feature::GdbCommands),
+        run: memory_map,
+    },
+    Action {
+        id: "flavor",
+        args: Args::Optional("[att|intel]"),
+        description: "Set the disassembly flavor (toggle between att and intel if none is given).",
+        binding: Some('A'),
+        requires: Some(Feature::GdbCommands),
+        run: flavor,
+    },
+    Action {
+        id: "pretty-printers",
+        args: Args::Optional("[on|off]"),
+        description: "Use pretty printers for the expression table (toggle if no argument is given).",
+        binding: Some('P'),
+        requires: Some(Feature::PrettyPrinters),
+        run: pretty_printers,
+    },
+    Action {
+        id: "print-pretty",
+        args: Args::Optional("[on|off]"),
+        description: "Print structs with one member per line (toggle if no argument is given).",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: print_pretty,
+    },
+    Action {
+        id: "substitute-path",
+        args: Args::Optional("[<from> <to>]"),
+        description: "Find sources under <to> instead of <from> (or list the rules).",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: substitute_path,
+    },
+    Action {
+        id: "inferiors",
+        args: Args::None,
+        description: "Show the inferiors container.",
+        binding: Some('I'),
+        requires: None,
+        run: |_, p| p.focus_container(TuiContainerType::Inferiors),
+    },
+    Action {
+        id: "breakpoints",
+        args: Args::None,
+        description: "Show the breakpoints container.",
+        binding: Some('B'),
+        requires: None,
+        run: |_, p| p.focus_container(TuiContainerType::Breakpoints),
+    },
+    Action {
+        id: "threads",
+        args: Args::None,
+        description: "Show the threads container.",
+        binding: Some('H'),
+        requires: None,
+        run: |_, p| p.focus_container(TuiContainerType::Threads),
+    },
+    Action {
+        id: "backtrace",
+        args: Args::None,
+        description: "Show the backtrace container.",
+        binding: Some('K'),
+        requires: None,
+        run: |_, p| p.focus_container(TuiContainerType::Backtrace),
+    },
+    Action {
+        id: "locals",
+        args: Args::None,
+        description: "Show the arguments and locals of the selected frame.",
+        binding: Some('V'),
+        requires: None,
+        run: |_, p| p.focus_container(TuiContainerType::Locals),
+    },
+    Action {
+        id: "libraries",
+        args: Args::None,
+        description: "Show the loaded shared libraries.",
+        binding: Some('O'),
+        requires: None,
+        run: |_, p| p.focus_container(TuiContainerType::Libraries),
+    },
+    Action {
+        id: "trace",
+        args: Args::Optional("[<function> [capture <expr>, ...] [if <condition>]]"),
+        description: "Record calls of the function (with their arguments or the captured expressions) in the trace container without stopping, optionally only if the condition is true (or pick a traced function to stop tracing it).",
+        binding: Some('X'),
+        requires: None,
+        run: trace,
+    },
+    Action {
+        id: "trace-returns",
+        args: Args::None,
+        description: "Toggle recording the return values of traced calls (using finish, which is slower).",
+        binding: None,
+        requires: None,
+        run: |_, p| p.toggle_trace_returns(),
+    },
+    Action {
+        id: "trigger",
+        args: Args::Optional("[[nonzero|change] <expression> => <action>; ...]"),
+        description: "Run the actions when the expression becomes non-zero (or changes) at a stop of the program (or pick a trigger to remove it).",
+        binding: None,
+        requires: None,
+        run: trigger,
+    },
+    Action {
+        id: "trace-export",
+        args: Args::Required("<file>"),
+        description: "Write the recorded calls to the file as CSV or JSON (depending on its extension).",
+        binding: None,
+        requires: None,
+        run: trace_export,
+    },
+    Action {
+        id: "symbol-file",
+        args: Args::Required("<file>"),
+        description: "Read the symbols of the program from the file (e.g., the unstripped build of a stripped binary).",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: symbol_file,
+    },
+    Action {
+        id: "add-symbol-file",
+        args: Args::Required("<file> [<address>]"),
+        description: "Read additional symbols from the file (e.g., a separate .debug file), loaded at the address of its .text section.",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: add_symbol_file,
+    },
+    Action {
+        id: "debug-file-directory",
+        args: Args::Optional("[<directory>]"),
+        description: "Also look for separate debug files (by build-id or name) in the directory (or list the directories).",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: debug_file_directory,
+    },
+    Action {
+        id: "check-build-ids",
+        args: Args::None,
+        description: "Warn if the executable or libraries on disk differ from the ones the target (e.g., a core dump or attached process) is running.",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: |_, p| p.check_build_ids(),
+    },
+    Action {
+        id: "skip",
+        args: Args::Optional("[<function>]"),
+        description: "Never step into the function (or show the list of skips).",
+        binding: Some('S'),
+        requires: Some(Feature::GdbCommands),
+        run: skip,
+    },
+    Action {
+        id: "read",
+        args: Args::Required("<container>"),
+        description: "Select a container (pager, console, expressions, terminal, search, linetable, inferiors, memory, breakpoints, threads, backtrace or locals). In linear mode, its content is printed.",
+        binding: None,
+        requires: None,
+        run: read_container,
+    },
+    Action {
+        id: "save-breakpoints",
+        args: Args::Required("<file>"),
+        description: "Save the breakpoints as a gdb script (see `save breakpoints`).",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: |args, p| match p.gdb.save_breakpoints(Path::new(args)) {
+            Ok(()) => p.toast(
+                ToastLevel::Success,
+                format!("Saved breakpoints to {}.", args),
+            ),
+            Err(e) => p.log(format!("Could not save breakpoints: {:?}", e)),
+        },
+    },
+    Action {
+        id: "load-breakpoints",
+        args: Args::Required("<file>"),
+        description: "Insert the breakpoints of a gdb script written by !save-breakpoints.",
+        binding: None,
+        requires: Some(Feature::GdbCommands),
+        run: |args, p| match p.gdb.load_breakpoints(Path::new(args)) {
+            Ok(()) => p.toast(
+                ToastLevel::Success,
+                format!("Loaded breakpoints from {}.", args),
+            ),
+            Err(e) => p.log(format!("Could not load breakpoints: {:?}", e)),
+        },
+    },
+    Action {
+        id: "diagnostics",
+        args: Args::Required("<file>"),
+        description: "Load compiler diagnostics from a file.",
+        binding: None,
+        requires: None,
+        run: |args, p| p.load_diagnostics(Path::new(args)),
+    },
+    Action {
+        id: "check",
+        args: Args::Optional("[file]"),
+        description: "Check the syntax of a file (or the one in the pager).",
+        binding: Some('C'),
+        requires: None,
+        run: |args, p| {
+            p.check_syntax(if args.is_empty() {
+                None
+            } else {
+                Some(PathBuf::from(args))
+            })
+        },
+    },
+    Action {
+        id: "reload",
+        args: Args::None,
+        description: "Load the executable (and its symbols) again.",
+        binding: Some('R'),
+        requires: None,
+        run: reload,
+    },
+    Action {
+        id: "sync",
+        args: Args::None,
+        description: "Query breakpoints, inferiors, threads and shared libraries from gdb again.",
+        binding: None,
+        requires: None,
+        run: |_, p| p.sync_gdb_state(),
+    },
+    Action {
+        id: "cancel",
+        args: Args::None,
+        description: "Cancel the long operations shown in the status line.",
+        binding: Some('x'),
+        requires: None,
+        run: |_, p| p.cancel_tasks(),
+    },
+    Action {
+        id: "freeze",
+        args: Args::None,
+        description: "Pause (or resume) updates of all containers, e.g., to read a value.",
+        binding: Some('F'),
+        requires: None,
+        run: |_, p| p.toggle_freeze(),
+    },
+    Action {
+        id: "edit",
+        args: Args::None,
+        description: "Open the file shown in the pager at the current line in $EDITOR.",
+        binding: Some('E'),
+        requires: None,
+        run: |_, p| p.open_in_editor(),
+    },
+    Action {
+        id: "restart",
+        args: Args::None,
+        description: "Start gdb again after it exited unexpectedly.",
+        binding: None,
+        requires: None,
+        run: |_, p| p.restart_gdb(),
+    },
+];
+
+// Usage, key binding and description of the action.
+fn summary(action: &Action, bindings: &Bindings) -> String {
+    let key = bindings
+        .key(action)
+        .map(|k| format!("[{}]", k))
+        .unwrap_or_default();
+    format!("{:<24}{:>4}  {}", action.usage(), key, action.description)
+}
+
+fn help(_: &str, p: &mut ::Context) {
+    let text = ACTIONS
+        .iter()
+        .map(|a| summary(a, &p.action_bindings))
+        .collect::<Vec<_>>()
+        .join("\n");
+    p.show_overlay(Message::new(
+        "Actions (keys in container selection mode)",
+        text,
+    ));
+}
+
+fn palette(_: &str, p: &mut ::Context) {
+    // Actions that the debugger does not support would only complain when they are picked.
+    let actions = ACTIONS
+        .iter()
+        .filter(|a| a.id != "palette" && a.requires.is_none_or(|f| p.supports(f)))
+        .collect::<Vec<_>>();
+    let items = actions
+        .iter()
+        .map(|a| summary(a, &p.action_bindings))
+        .collect();
+    p.show_overlay(
+        Picker::new("Actions", items, move |i, p: &mut ::Context| {
+            actions[i].trigger(p)
+        })
+        .fuzzy(),
+    );
+}
+
+fn show(args: &str, p: &mut ::Context) {
+    if !args.is_empty() {
+        p.show_file(args.to_owned(), LineNumber::new(1));
+        return;
+    }
+    match p.gdb.get_source_files() {
+        Ok(files) => {
+            let files = files
+                .into_iter()
+                .map(|f| f.to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            p.show_overlay(Picker::new(
+                "Source files",
+                files.clone(),
+                move |i, p: &mut ::Context| p.show_file(files[i].clone(), LineNumber::new(1)),
+            ));
+        }
+        Err(e) => p.log(format!("Could not list source files: {:?}", e)),
+    }
+}
+
+// Signals that are offered by `!signal` without argument.
+const COMMON_SIGNALS: &[Signal] = &[
+    Signal::SIGTERM,
+    Signal::SIGKILL,
+    Signal::SIGUSR1,
+    Signal::SIGUSR2,
+    Signal::SIGHUP,
+    Signal::SIGINT,
+    Signal::SIGQUIT,
+    Signal::SIGALRM,
+    Signal::SIGCHLD,
+    Signal::SIGPIPE,
+    Signal::SIGWINCH,
+];
+
+// Signals are given by name, with or without the "SIG" prefix (e.g., "usr1" or "SIGUSR1").
+fn parse_signal(name: &str) -> Option<Signal> {
+    let name = name.trim().to_uppercase();
+    if name.starts_with("SIG") {
+        name.parse().ok()
+    } else {
+        format!("SIG{}", name).parse().ok()
+    }
+}
+
+fn signal(args: &str, p: &mut ::Context) {
+    fn send(signal: Signal, p: &mut ::Context) {
+        match p.gdb.signal_inferior(signal) {
+            Ok(Some(pid)) => p.log(format!("Sent {} to process {}.", signal, pid)),
+            Ok(None) => p.log(format!("Resumed the program with {}.", signal)),
+            Err(e) => p.log(format!("Could not send {}: {:?}", signal, e)),
+        }
+    }
+    if args.is_empty() {
+        p.show_overlay(Picker::new(
+            "Send signal",
+            COMMON_SIGNALS.iter().map(|s| s.to_string()).collect(),
+            |i, p: &mut ::Context| send(COMMON_SIGNALS[i], p),
+        ));
+    } else {
+        match parse_signal(args) {
+            Some(signal) => send(signal, p),
+            None => p.log(format!("Unknown signal '{}'", args)),
+        }
+    }
+}
+
+fn arguments(args: &str, p: &mut ::Context) {
+    fn set(args: String, p: &mut ::Context) {
+        match p.gdb.set_arguments(&args) {
+            Ok(()) => {
+                p.toast(ToastLevel::Info, format!("Arguments: {}", args));
+                p.run_settings.args = Some(args);
+            }
+            Err(e) => p.log(format!("Could not set arguments: {:?}", e)),
+        }
+    }
+    if args.is_empty() {
+        let current = match p.gdb.get_arguments() {
+            Ok(args) => args,
+            Err(_) => p.run_settings.args.clone().unwrap_or_default(),
+        };
+        p.show_overlay(Prompt::new(
+            "!args",
+            &current,
+            |args: String, p: &mut ::Context| set(args.trim().to_owned(), p),
+        ));
+    } else {
+        set(args.to_owned(), p);
+    }
+}
+
+fn environment(args: &str, p: &mut ::Context) {
+    fn set(assignment: &str, p: &mut ::Context) {
+        let (name, value) = match run_settings::parse_assignment(assignment) {
+            Ok(variable) => variable,
+            Err(e) => return p.log(e),
+        };
+        match p.gdb.set_environment_variable(&name, value.as_deref()) {
+            Ok(()) => {
+                p.toast(
+                    ToastLevel::Info,
+                    match &value {
+                        Some(value) => format!("{}={}", name, value),
+                        None => format!("Unset {}", name),
+                    },
+                );
+                p.run_settings.set_variable(&name, value);
+            }
+            Err(e) => p.log(format!("Could not change {}: {:?}", name, e)),
+        }
+    }
+    if !args.is_empty() {
+        return set(args, p);
+    }
+    let mut items = p
+        .run_settings
+        .environment
+        .iter()
+        .map(|(name, value)| match value {
+            Some(value) => format!("{}={}", name, value),
+            None => name.clone(),
+        })
+        .collect::<Vec<_>>();
+    items.push(String::new());
+    let initial = items.clone();
+    p.show_overlay(Picker::new(
+        "Environment (<name>=<value>, or <name> to unset)",
+        items,
+        move |i, p: &mut ::Context| {
+            p.show_overlay(Prompt::new(
+                "!env",
+                &initial[i],
+                |assignment: String, p: &mut ::Context| set(assignment.trim(), p),
+            ))
+        },
+    ));
+}
+
+fn run_configuration(args: &str, p: &mut ::Context) {
+    fn select(configuration: RunConfiguration, p: &mut ::Context) {
+        if p.run_baseline.is_none() {
+            // gdb's arguments and working directory before the first configuration, so that they
+            // are restored when switching to a configuration that does not change them.
+            let mut baseline = p.run_settings.clone();
+            if baseline.args.is_none() {
+                baseline.args = p.gdb.get_arguments().ok();
+            }
+            if baseline.cwd.is_none() {
+                baseline.cwd = p.gdb.get_working_directory().ok();
+            }
+            p.run_baseline = Some(baseline);
+        }
+        let baseline = p.run_baseline.as_ref().unwrap();
+        p.run_settings = RunSettings::switch(
+            baseline,
+            p.run_configuration.as_ref(),
+            &configuration.settings,
+        );
+        p.run_configuration = Some(configuration.settings.clone());
+        p.apply_run_settings();
+        if let Err(e) = p.gdb.execute_commands(&configuration.commands) {
+            p.log(format!(
+                "Command of run configuration '{}' failed: {:?}",
+                configuration.name, e
+            ));
+        }
+        p.toast(
+            ToastLevel::Info,
+            format!("Run configuration {}", configuration.description()),
+        );
+    }
+    if p.run_configurations.is_empty() {
+        return p.log("No run configurations are defined in the config file.");
+    }
+    if args.is_empty() {
+        let configurations = p.run_configurations.clone();
+        p.show_overlay(Picker::new(
+            "Run configuration",
+            configurations.iter().map(|c| c.description()).collect(),
+            move |i, p: &mut ::Context| select(configurations[i].clone(), p),
+        ));
+    } else {
+        match p.run_configurations.iter().find(|c| c.name == args) {
+            Some(configuration) => select(configuration.clone(), p),
+            None => p.log(format!("Unknown run configuration '{}'", args)),
+        }
+    }
+}
+
+fn working_directory(args: &str, p: &mut ::Context) {
+    fn set(dir: PathBuf, p: &mut ::Context) {
+        match p.gdb.set_working_directory(&dir) {
+            Ok(()) => {
+                p.toast(
+                    ToastLevel::Info,
+                    format!("Working directory: {}", dir.display()),
+                );
+                p.run_settings.cwd = Some(dir);
+            }
+            Err(e) => p.log(format!("Could not change the working directory: {:?}", e)),
+        }
+    }
+    if args.is_empty() {
+        let current = match p.gdb.get_working_directory() {
+            Ok(cwd) => cwd,
+            Err(_) => p.run_settings.cwd.clone().unwrap_or_default(),
+        };
+        p.show_overlay(Prompt::new(
+            "!cwd",
+            &current.to_string_lossy(),
+            |dir: String, p: &mut ::Context| set(PathBuf::from(dir.trim()), p),
+        ));
+    } else {
+        set(PathBuf::from(args), p);
+    }
+}
+
+fn flavor(args: &str, p: &mut ::Context) {
+    let flavor = if args.is_empty() {
+        match p.gdb.get_disassembly_flavor() {
+            Ok(flavor) => flavor.toggled(),
+            Err(e) => {
+                p.log(format!("Could not get disassembly flavor: {:?}", e));
+                return;
+            }
+        }
+    } else {
+        match args.parse::<DisassemblyFlavor>() {
+            Ok(flavor) => flavor,
+            Err(e) => {
+                p.log(e);
+                return;
+            }
+        }
+    };
+    match p.gdb.set_disassembly_flavor(flavor) {
+        Ok(()) => {
+            p.disassembly_flavor = Some(flavor);
+            p.toast(ToastLevel::Info, format!("Disassembly flavor: {}", flavor));
+            p.publish(DebugEvent::DisassemblyFlavorChanged);
+        }
+        Err(e) => p.log(format!("Could not set disassembly flavor: {:?}", e)),
+    }
+}
+
+fn memory_map(args: &str, p: &mut ::Context) {
+    p.focus_container(TuiContainerType::MemoryMap);
+    if args.is_empty() {
+        return;
+    }
+    let address = match p.gdb.evaluate_address(args) {
+        Ok(address) => address,
+        Err(e) => return p.log(format!("Invalid address '{}': {:?}", args, e)),
+    };
+    let format_address = p.address_formatter();
+    match p.gdb.get_memory_map() {
+        Ok(regions) => match regions.iter().find(|r| r.contains(address)) {
+            Some(region) => p.log(format!(
+                "{} is in {}",
+                format_address(address),
+                describe_region(region, &format_address)
+            )),
+            None => p.log(format!(
+                "{} is not in any mapped region.",
+                format_address(address)
+            )),
+        },
+        Err(e) => p.log(format!("Failed to get the memory map: {:?}", e)),
+    }
+}
+
+fn dump(args: &str, p: &mut ::Context) {
+    if args.is_empty() {
+        return prompt_dump("", "", p);
+    }
+    // The address may be an expression with spaces, the length and the file are single words.
+    let last_word = |s: &str| {
+        s.trim_end()
+            .rsplit_once(char::is_whitespace)
+            .map(|(rest, word)| (rest.to_owned(), word.to_owned()))
+    };
+    match last_word(args).and_then(|(rest, file)| Some((last_word(&rest)?, file))) {
+        Some(((address, length), file)) => dump_memory(&address, &length, &file, p),
+        None => p.log("Usage: !dump [<address> <length> <file>]"),
+    }
+}
+
+fn restore(args: &str, p: &mut ::Context) {
+    if args.is_empty() {
+        return prompt_restore("", p);
+    }
+    match args.split_once(char::is_whitespace) {
+        Some((file, address)) => restore_memory(file, address.trim(), p),
+        None => p.log("Usage: !restore [<file> <address>]"),
+    }
+}
+
+fn skip(args: &str, p: &mut ::Context) {
+    if args.is_empty() {
+        if let Err(e) = p.gdb.refresh_skips() {
+            p.log(format!("Failed to list skips: {:?}", e));
+        }
+        p.publish(DebugEvent::SkipsChanged);
+    } else {
+        add_skip(SkipKind::Function, args, p);
+    }
+    p.focus_container(TuiContainerType::Skips);
+}
+
+fn parse_on_off(args: &str, current: bool) -> Result<bool, String> {
+    match args {
+        "" => Ok(!current),
+        "on" => Ok(true),
+        "off" => Ok(false),
+        o => Err(format!("Expected 'on' or 'off', got '{}'", o)),
+    }
+}
+
+fn pretty_printers(args: &str, p: &mut ::Context) {
+    let enable = match parse_on_off(args, p.pretty_printers.unwrap_or(false)) {
+        Ok(enable) => enable,
+        Err(e) => {
+            p.log(e);
+            return;
+        }
+    };
+    match p.gdb.set_pretty_printers(enable) {
+        Ok(()) => {
+            p.pretty_printers = Some(enable);
+            let state = if enable { "enabled" } else { "disabled" };
+            p.toast(ToastLevel::Info, format!("Pretty printers {}", state));
+            p.publish(DebugEvent::PrintSettingsChanged);
+        }
+        Err(e) => p.log(format!("Could not change pretty printers: {:?}", e)),
+    }
+}
+
+fn print_pretty(args: &str, p: &mut ::Context) {
+    let current = match p.gdb.get_print_settings() {
+        Ok(settings) => settings.pretty,
+        Err(e) => {
+            p.log(format!("Failed to get print settings: {:?}", e));
+            return;
+        }
+    };
+    let pretty = match parse_on_off(args, current) {
+        Ok(pretty) => pretty,
+        Err(e) => {
+            p.log(e);
+            return;
+        }
+    };
+    match p.gdb.set_print_pretty(pretty) {
+        Ok(()) => {
+            p.print_pretty = Some(pretty);
+            p.publish(DebugEvent::PrintSettingsChanged);
+        }
+        Err(e) => p.log(format!("Could not set print pretty: {:?}", e)),
+    }
+}
+
+fn read_container(args: &str, p: &mut ::Context) {
+    let container = match args {
+        "pager" => TuiContainerType::SrcView,
+        "console" => TuiContainerType::Console,
+        "expressions" => TuiContainerType::ExpressionTable,
+        "terminal" => TuiContainerType::Terminal,
+        "search" => TuiContainerType::SearchResults,
+        "linetable" => TuiContainerType::LineTable,
+        "inferiors" => TuiContainerType::Inferiors,
+        "memory" => TuiContainerType::Memory,
+        "memorymap" => TuiContainerType::MemoryMap,
+        "breakpoints" => TuiContainerType::Breakpoints,
+        "threads" => TuiContainerType::Threads,
+        "backtrace" => TuiContainerType::Backtrace,
+        "locals" => TuiContainerType::Locals,
+        "skips" => TuiContainerType::Skips,
+        "libraries" => TuiContainerType::Libraries,
+        "trace" => TuiContainerType::Trace,
+        other => return p.log(format!("Unknown container '{}'", other)),
+    };
+    p.focus_container(container);
+}
+
+fn trace(args: &str, p: &mut ::Context) {
+    if args.is_empty() {
+        let functions = p.function_trace.functions().cloned().collect::<Vec<_>>();
+        if functions.is_empty() {
+            p.log("No functions are traced. Use !trace <function> to trace one.");
+            return;
+        }
+        p.show_overlay(Picker::new(
+            "Stop tracing",
+            functions.iter().map(|f| f.to_string()).collect(),
+            move |i, p: &mut ::Context| {
+                let function = &functions[i].function;
+                match p.function_trace.untrace(&mut p.gdb, function) {
+                    Ok(()) => p.toast(ToastLevel::Info, format!("Stopped tracing {}.", function)),
+                    Err(e) => p.log(format!("Could not stop tracing {}: {:?}", function, e)),
+                }
+            },
+        ));
+        return;
+    }
+    let spec = match args.parse::<TraceSpec>() {
+        Ok(spec) => spec,
+        Err(e) => return p.log(format!("Invalid trace '{}': {}", args, e)),
+    };
+    let function = spec.function.clone();
+    match p.function_trace.trace(&mut p.gdb, spec) {
+        Ok(()) => {
+            p.toast(
+                ToastLevel::Success,
+                format!("Tracing calls of {}.", function),
+            );
+            p.focus_container(TuiContainerType::Trace);
+        }
+        Err(e) => p.log(format!("Could not trace {}: {:?}", function, e)),
+    }
+}
+
+fn trigger(args: &str, p: &mut ::Context) {
+    if args.is_empty() {
+        if p.watch_triggers.is_empty() {
+            return p.log("No triggers. Use !trigger <expression> => <action>; ... to add one.");
+        }
+        let triggers = p.watch_triggers.iter().map(|t| t.to_string()).collect();
+        p.show_overlay(Picker::new(
+            "Remove trigger",
+            triggers,
+            |i, p: &mut ::Context| {
+                let trigger = watch_triggers::remove(i, p);
+                p.toast(ToastLevel::Info, format!("Removed trigger: {}", trigger));
+            },
+        ));
+        return;
+    }
+    match args.parse::<Trigger>() {
+        Ok(trigger) => {
+            p.toast(ToastLevel::Success, format!("Added trigger: {}", trigger));
+            watch_triggers::add(trigger, p);
+        }
+        Err(e) => p.log(format!("Invalid trigger '{}': {}", args, e)),
+    }
+}
+
+fn trace_export(args: &str, p: &mut ::Context) {
+    let path = Path::new(args);
+    let format = match ExportFormat::from_path(path) {
+        Some(format) => format,
+        None => return p.log("Unknown trace format. Use a file ending in .csv or .json."),
+    };
+    match p.function_trace.export(path, format) {
+        Ok(()) => p.toast(
+            ToastLevel::Success,
+            format!(
+                "Wrote {} call(s) to {}.",
+                p.function_trace.entries().count(),
+                args
+            ),
+        ),
+        Err(e) => p.log(format!("Could not write the trace to {}: {}", args, e)),
+    }
+}
+
+fn symbol_file(args: &str, p: &mut ::Context) {
+    match p.gdb.symbol_file(Path::new(args)) {
+        Ok(()) => {
+            p.toast(ToastLevel::Success, format!("Read symbols from {}.", args));
+            p.publish(DebugEvent::SymbolsReloaded);
+        }
+        Err(e) => p.log(format!("Could not read symbols from {}: {:?}", args, e)),
+    }
+}
+
+fn add_symbol_file(args: &str, p: &mut ::Context) {
+    let (file, address) = match args.split_once(char::is_whitespace) {
+        Some((file, address)) => (file, Some(address.trim())),
+        None => (args, None),
+    };
+    match p.gdb.add_symbol_file(Path::new(file), address) {
+        Ok(()) => {
+            p.toast(ToastLevel::Success, format!("Read symbols from {}.", file));
+            p.publish(DebugEvent::LibrariesChanged);
+            p.publish(DebugEvent::SymbolsReloaded);
+        }
+        Err(e) => p.log(format!("Could not read symbols from {}: {:?}", file, e)),
+    }
+}
+
+fn debug_file_directory(args: &str, p: &mut ::Context) {
+    if args.is_empty() {
+        match p.gdb.get_debug_file_directories() {
+            Ok(dirs) => p.show_overlay(Message::new(
+                "Debug file directories",
+                dirs.iter()
+                    .map(|d| d.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )),
+            Err(e) => p.log(format!("Could not get debug-file-directory: {:?}", e)),
+        }
+        return;
+    }
+    match p.gdb.add_debug_file_directory(Path::new(args)) {
+        // gdb only looks for debug files when it reads the symbols of a file.
+        Ok(()) => p.log(format!(
+            "Looking for debug files in {} from now on. Use !reload to read the symbols of the program again.",
+            args
+        )),
+        Err(e) => p.log(format!("Could not set debug-file-directory: {:?}", e)),
+    }
+}
+
+fn reload(_: &str, p: &mut ::Context) {
+    fn reload_target(target: &Path, p: &mut ::Context) {
+        match p.gdb.mi.execute(MiCommand::file_exec_and_symbols(target)) {
+            Ok(_) => {
+                p.log("Reloaded target.");
+                p.toast(ToastLevel::Success, "Reloaded target.");
+                p.gdb.forget_pointer_size();
+                p.publish(DebugEvent::SymbolsReloaded);
+            }
+            Err(e) => p.log(format!("Could not reload target: {}", execute_error(e))),
+        }
+    }
+    let target = match p.gdb.get_target() {
+        Ok(Some(target)) => target,
+        Ok(None) => {
+            p.log("No target. Use the 'file' command to specify one.");
+            return;
+        }
+        Err(e) => {
+            p.log(format!("Could not get target: {}", execute_error(e)));
+            return;
+        }
+    };
+    match p.gdb.mi.is_session_active() {
+        Ok(true) => p.show_overlay(Confirmation::new(
+            "Reload",
+            "A debugging session is active. Reload anyway?",
+            move |p: &mut ::Context| reload_target(&target, p),
+        )),
+        Ok(false) => reload_target(&target, p),
+        Err(e) => p.log(execute_error(e)),
+    }
+}
+
+fn substitute_path(args: &str, p: &mut ::Context) {
+    if args.is_empty() {
+        let rules = p
+            .path_substitutions
+            .rules()
+            .iter()
+            .map(|(from, to)| format!("{} -> {}", from.display(), to.display()))
+            .collect::<Vec<_>>();
+        if rules.is_empty() {
+            p.log("No path substitution rules.");
+        } else {
+            p.show_overlay(Message::new("Path substitutions", rules.join("\n")));
+        }
+        return;
+    }
+    let (from, to) = match args.split_whitespace().collect::<Vec<_>>()[..] {
+        [from, to] => (PathBuf::from(from), PathBuf::from(to)),
+        _ => {
+            p.log("Usage: !substitute-path [<from> <to>]");
+            return;
+        }
+    };
+    if let Err(e) = p.gdb.add_path_substitution(&from, &to) {
+        p.log(format!("Could not set substitute-path: {:?}", e));
+        return;
+    }
+    p.toast(
+        ToastLevel::Info,
+        format!(
+            "Finding sources in {} under {}.",
+            from.display(),
+            to.display()
+        ),
+    );
+    p.path_substitutions.add(from, to);
+    p.publish(DebugEvent::PathSubstitutionsChanged);
+}
+
+fn execute_error(e: ExecuteError) -> String {
+    match e {
+        ExecuteError::Quit(e) => e.to_string(),
+        ExecuteError::Busy => "GDB is running!".to_owned(),
+        ExecuteError::Timeout => "GDB did not respond in time.".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_actions_are_unique() {
+        for (i, action) in ACTIONS.iter().enumerate() {
+            assert!(ACTIONS[..i].iter().all(|a| a.id != action.id));
+            if let Some(key) = action.binding {
+                assert!(ACTIONS[..i].iter().all(|a| a.binding != Some(key)));
+                assert!(
+                    !SELECTION_MODE_KEYS.contains(&key),
+                    "'{}' is shadowed by selection mode",
+                    action.id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!(parse_signal("TERM"), Some(Signal::SIGTERM));
+        assert_eq!(parse_signal("usr1"), Some(Signal::SIGUSR1));
+        assert_eq!(parse_signal("SIGKILL"), Some(Signal::SIGKILL));
+        assert_eq!(parse_signal("FOO"), None);
+    }
+
+    #[test]
+    fn test_bindings() {
+        let bindings = Bindings::new(&[]);
+        assert_eq!(bindings.action('B').map(|a| a.id), Some("breakpoints"));
+        assert_eq!(bindings.key(find("grep").unwrap()), Some('/'));
+
+        let bindings = Bindings::new(&[
+            ("breakpoints".to_owned(), Some('b')),
+            ("grep".to_owned(), None),
+        ]);
+        assert!(bindings.action('B').is_none());
+        assert_eq!(bindings.action('b').map(|a| a.id), Some("breakpoints"));
+        assert_eq!(bindings.key(find("grep").unwrap()), None);
+    }
+}
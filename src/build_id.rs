@@ -0,0 +1,298 @@
+// Detect that the files on disk are not the ones that the target (a core dump or a process that gdb
+// attached to) was running, e.g., because the program was rebuilt in the meantime. gdb happily
+// reads the symbols of the wrong file, which results in subtly wrong source lines and values.
+// The build-id (the NT_GNU_BUILD_ID note that the linker writes into every ELF file) of each file
+// on disk is compared with the one in the first page of the file's mapping in the target, which
+// the kernel also includes in core dumps.
+use gdb::response::GDBResponseError;
+use gdb::{MemoryRegion, GDB};
+use std::convert::TryInto;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+// The build-id note is in the first (loaded) segment, right after the program headers.
+const HEADER_SIZE: usize = 4096;
+const PT_NOTE: u32 = 4;
+const NT_GNU_BUILD_ID: u32 = 3;
+
+#[derive(Clone, Copy)]
+struct Reader<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&self, offset: usize, len: usize) -> Option<&'a [u8]> {
+        self.bytes.get(offset..offset.checked_add(len)?)
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let b: [u8; 2] = self.bytes(offset, 2)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let b: [u8; 4] = self.bytes(offset, 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Option<u64> {
+        let b: [u8; 8] = self.bytes(offset, 8)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u64::from_le_bytes(b)
+        } else {
+            u64::from_be_bytes(b)
+        })
+    }
+}
+
+/// The build-id in the ELF image (starting with its header), or `None` if it is not an ELF file,
+/// has no build-id or the note is not within `image`.
+pub fn parse_build_id(image: &[u8]) -> Option<Vec<u8>> {
+    if image.get(0..4)? != b"\x7fELF" {
+        return None;
+    }
+    let is_64 = match image.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let r = Reader {
+        bytes: image,
+        little_endian: *image.get(5)? == 1,
+    };
+    let (phoff, phentsize, phnum) = if is_64 {
+        (r.u64(32)? as usize, r.u16(54)?, r.u16(56)?)
+    } else {
+        (r.u32(28)? as usize, r.u16(42)?, r.u16(44)?)
+    };
+    for i in 0..phnum as usize {
+        let header = phoff.checked_add(i * phentsize as usize)?;
+        if r.u32(header)? != PT_NOTE {
+            continue;
+        }
+        let (offset, size) = if is_64 {
+            (r.u64(header + 8)? as usize, r.u64(header + 32)? as usize)
+        } else {
+            (r.u32(header + 4)? as usize, r.u32(header + 16)? as usize)
+        };
+        if let Some(id) = find_build_id_note(r, offset, size) {
+            return Some(id);
+        }
+    }
+    None
+}
+
+fn find_build_id_note(r: Reader, offset: usize, size: usize) -> Option<Vec<u8>> {
+    let align = |n: usize| (n + 3) & !3;
+    let end = offset.checked_add(size)?;
+    let mut pos = offset;
+    while pos + 12 <= end {
+        let name_size = r.u32(pos)? as usize;
+        let desc_size = r.u32(pos + 4)? as usize;
+        let kind = r.u32(pos + 8)?;
+        let name = pos + 12;
+        let desc = name.checked_add(align(name_size))?;
+        if kind == NT_GNU_BUILD_ID && r.bytes(name, name_size)? == b"GNU\0" {
+            return r.bytes(desc, desc_size).map(|d| d.to_vec());
+        }
+        pos = desc.checked_add(align(desc_size))?;
+    }
+    None
+}
+
+fn read_file_build_id(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let mut image = Vec::with_capacity(HEADER_SIZE);
+    File::open(path)?
+        .take(HEADER_SIZE as u64)
+        .read_to_end(&mut image)?;
+    Ok(parse_build_id(&image))
+}
+
+fn hex(id: &[u8]) -> String {
+    id.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A file whose build-id on disk differs from the one in the target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// The file that gdb reads the symbols from.
+    pub file: PathBuf,
+    /// The path in the target, if it is different (e.g., for the executable given to ugdb).
+    pub target_file: Option<String>,
+    pub on_disk: Vec<u8>,
+    pub in_target: Vec<u8>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some(target_file) = &self.target_file {
+            write!(f, " (running: {})", target_file)?;
+        }
+        write!(
+            f,
+            "\n  on disk:   {}\n  in target: {}",
+            hex(&self.on_disk),
+            hex(&self.in_target)
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Check {
+    /// Number of files whose build-ids could be compared.
+    pub compared: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+// The first mapping (i.e., the one that contains the ELF header) of every file in the target.
+fn file_mappings(regions: &[MemoryRegion]) -> Vec<&MemoryRegion> {
+    let mut mappings: Vec<&MemoryRegion> = Vec::new();
+    for region in regions {
+        if region.offset == 0
+            && region.objfile.starts_with('/')
+            && !mappings.iter().any(|m| m.objfile == region.objfile)
+        {
+            mappings.push(region);
+        }
+    }
+    mappings
+}
+
+// The file on disk of a mapping, e.g., "/usr/lib/libfoo.so (deleted)" if it was replaced.
+fn disk_path(objfile: &str) -> &Path {
+    Path::new(objfile.strip_suffix(" (deleted)").unwrap_or(objfile))
+}
+
+/// Compare the build-ids of the executable (that gdb reads the symbols from) and the libraries on
+/// disk with those of the files that are mapped in the target. Files that cannot be read (on disk
+/// or in the target) or do not have a build-id are skipped.
+pub fn check(gdb: &mut GDB, executable: Option<&Path>) -> Result<Check, GDBResponseError> {
+    let regions = gdb.get_memory_map()?;
+    let mappings = file_mappings(&regions);
+    // The executable is usually mapped first, even if gdb was given a different path to it.
+    let executable_mapping = executable.and_then(|executable| {
+        mappings
+            .iter()
+            .position(|m| disk_path(&m.objfile) == executable)
+            .or(if mappings.is_empty() { None } else { Some(0) })
+    });
+    let mut result = Check::default();
+    for (i, mapping) in mappings.iter().enumerate() {
+        let (file, target_file) = match (executable, executable_mapping) {
+            (Some(executable), Some(e)) if e == i => {
+                let target_file = if disk_path(&mapping.objfile) == executable {
+                    None
+                } else {
+                    Some(mapping.objfile.clone())
+                };
+                (executable.to_owned(), target_file)
+            }
+            _ => (disk_path(&mapping.objfile).to_owned(), None),
+        };
+        let on_disk = match read_file_build_id(&file) {
+            Ok(Some(id)) => id,
+            _ => continue,
+        };
+        let size = HEADER_SIZE.min(mapping.end.0.saturating_sub(mapping.begin.0));
+        let in_target = match gdb.read_memory(mapping.begin, size) {
+            Ok(image) => match parse_build_id(&image) {
+                Some(id) => id,
+                None => continue,
+            },
+            Err(_) => continue,
+        };
+        result.compared += 1;
+        if on_disk != in_target {
+            result.mismatches.push(Mismatch {
+                file,
+                target_file,
+                on_disk,
+                in_target,
+            });
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use gdb::Address;
+
+    // A minimal 64 bit little endian ELF header with one PT_NOTE program header that contains an
+    // unrelated note followed by the build-id.
+    fn elf64(build_id: &[u8]) -> Vec<u8> {
+        let mut image = vec![0u8; 64 + 56];
+        image[0..4].copy_from_slice(b"\x7fELF");
+        image[4] = 2;
+        image[5] = 1;
+        image[32..40].copy_from_slice(&64u64.to_le_bytes());
+        image[54..56].copy_from_slice(&56u16.to_le_bytes());
+        image[56..58].copy_from_slice(&1u16.to_le_bytes());
+        let mut notes = Vec::new();
+        for (name, kind, desc) in [
+            (&b"ABI\0"[..], 1u32, &[0u8; 5][..]),
+            (b"GNU\0", 3, build_id),
+        ] {
+            notes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            notes.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+            notes.extend_from_slice(&kind.to_le_bytes());
+            notes.extend_from_slice(name);
+            notes.extend_from_slice(desc);
+            notes.resize((notes.len() + 3) & !3, 0);
+        }
+        let (header, offset) = (64, image.len() as u64);
+        image[header..header + 4].copy_from_slice(&PT_NOTE.to_le_bytes());
+        image[header + 8..header + 16].copy_from_slice(&offset.to_le_bytes());
+        image[header + 32..header + 40].copy_from_slice(&(notes.len() as u64).to_le_bytes());
+        image.extend_from_slice(&notes);
+        image
+    }
+
+    #[test]
+    fn test_parse_build_id() {
+        let image = elf64(&[0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(parse_build_id(&image), Some(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(hex(&parse_build_id(&image).unwrap()), "deadbeef");
+        // The note is cut off (e.g., only part of the mapping was readable).
+        assert_eq!(parse_build_id(&image[..image.len() - 2]), None);
+        assert_eq!(parse_build_id(b"#!/bin/sh\n"), None);
+    }
+
+    #[test]
+    fn test_file_mappings() {
+        let region = |begin, offset, objfile: &str| MemoryRegion {
+            begin: Address(begin),
+            end: Address(begin + 0x1000),
+            offset,
+            perms: None,
+            objfile: objfile.to_owned(),
+        };
+        let regions = [
+            region(0x1000, 0, "/bin/prog (deleted)"),
+            region(0x2000, 0x1000, "/bin/prog (deleted)"),
+            region(0x3000, 0, ""),
+            region(0x4000, 0, "[vdso]"),
+            region(0x5000, 0, "/lib/libc.so.6"),
+            region(0x6000, 0, "/lib/libc.so.6"),
+        ];
+        let mappings = file_mappings(&regions);
+        assert_eq!(
+            mappings.iter().map(|m| m.begin.0).collect::<Vec<_>>(),
+            vec![0x1000, 0x5000]
+        );
+        assert_eq!(disk_path(&mappings[0].objfile), Path::new("/bin/prog"));
+    }
+}
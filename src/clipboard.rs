@@ -0,0 +1,67 @@
+use std::io::{self, Write};
+
+// Encode text as an OSC52 sequence, which asks the terminal emulator to set the clipboard.
+fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64::encode(text))
+}
+
+/// Copy the text to the system clipboard.
+///
+/// The text is sent to the terminal emulator using an OSC52 escape sequence, which also works
+/// over ssh. If built with the `system-clipboard` feature, the text is additionally passed to
+/// wl-copy, xclip or xsel (whichever is available) for terminals without OSC52 support.
+pub fn copy(text: &str) -> io::Result<()> {
+    #[cfg(feature = "system-clipboard")]
+    let _ = copy_using_command(text);
+
+    let mut stdout = io::stdout();
+    stdout.write_all(osc52_sequence(text).as_bytes())?;
+    stdout.flush()
+}
+
+#[cfg(feature = "system-clipboard")]
+fn copy_using_command(text: &str) -> io::Result<()> {
+    use std::process::{Command, Stdio};
+
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let commands: &[(&str, &[&str])] = if wayland {
+        &[("wl-copy", &[])]
+    } else {
+        &[
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    };
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "No clipboard command found");
+    for (cmd, args) in commands {
+        match Command::new(cmd)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(mut child) => {
+                child
+                    .stdin
+                    .take()
+                    .expect("stdin is piped")
+                    .write_all(text.as_bytes())?;
+                child.wait()?;
+                return Ok(());
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_osc52() {
+        assert_eq!(osc52_sequence("hello"), "\x1b]52;c;aGVsbG8=\x07");
+    }
+}
@@ -1,6 +1,8 @@
-use gdbmi::commands::MiCommand;
+use gdbmi::commands::{MiCommand, PrintValues};
 use gdbmi::output::{JsonValue, ResultClass};
+use gdbmi::types;
 use log::{error, info};
+use std::convert::TryFrom;
 use std::ffi::OsString;
 use std::ops::Range;
 use std::path::Path;
@@ -33,6 +35,24 @@ impl CompletionState {
         )
     }
 
+    // Options including the part of the current word before the cursor.
+    pub fn option_words(&self) -> impl Iterator<Item = (bool, String)> + '_ {
+        let before_cursor = &self.original[..self.cursor_pos];
+        let word_begin = before_cursor
+            .rfind(char::is_whitespace)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let word_prefix = &before_cursor[word_begin..];
+        self.completion_options
+            .iter()
+            .enumerate()
+            .map(move |(i, o)| (i == self.current_option, format!("{}{}", word_prefix, o)))
+    }
+
+    pub fn num_candidates(&self) -> usize {
+        self.completion_options.len()
+    }
+
     pub fn current_option(&self) -> &str {
         self.completion_options
             .get(self.current_option)
@@ -116,14 +136,12 @@ struct VarObject {
 
 impl VarObject {
     fn from_val(o: &JsonValue) -> Result<Self, String> {
-        let name = if let Some(name) = o["name"].as_str() {
-            name.to_string()
-        } else {
-            return Err(format!("Missing field 'name'"));
-        };
-        let expr = o["exp"].as_str().map(|s| s.to_owned());
-        let typ = o["type"].as_str().map(|s| s.to_owned());
-        Ok(VarObject { name, expr, typ })
+        let var = types::VarObject::try_from(o).map_err(|e| e.to_string())?;
+        Ok(VarObject {
+            name: var.name,
+            expr: var.exp,
+            typ: var.type_,
+        })
     }
     fn create(p: &mut ::Context, expr: &str) -> Result<Self, String> {
         let res = p
@@ -218,7 +236,11 @@ fn get_variables(p: &mut ::Context) -> Result<Vec<String>, String> {
     let res = p
         .gdb
         .mi
-        .execute(MiCommand::stack_list_variables(None, None))
+        .execute(MiCommand::stack_list_variables(
+            None,
+            None,
+            PrintValues::Simple,
+        ))
         .map_err(|e| format!("{:?}", e))?;
 
     match res.class {
@@ -257,9 +279,32 @@ impl Completer for IdentifierCompleter<'_> {
     }
 }
 
+// Completion via gdb's own completer (requires gdb >= 7.12). Returns None if gdb does not support
+// the -complete command.
+fn gdb_complete(p: &mut ::Context, before_cursor: &str) -> Option<Vec<String>> {
+    let res = p.gdb.mi.execute(MiCommand::complete(before_cursor)).ok()?;
+    if res.class != ResultClass::Done {
+        info!(
+            "-complete failed: {}",
+            res.results["msg"].as_str().unwrap_or("unknown error")
+        );
+        return None;
+    }
+    let matches = res.results["matches"]
+        .members()
+        .filter_map(|m| m.as_str())
+        .collect::<Vec<_>>();
+    Some(find_candidates(before_cursor, &matches))
+}
+
 pub struct CmdlineCompleter<'a>(pub &'a mut ::Context);
 impl Completer for CmdlineCompleter<'_> {
     fn complete(&mut self, original: &str, cursor_pos: usize) -> CompletionState {
+        if !original.trim_start().starts_with('!') {
+            if let Some(candidates) = gdb_complete(self.0, &original[..cursor_pos]) {
+                return CompletionState::new(original.to_owned(), cursor_pos, candidates);
+            }
+        }
         if original[..cursor_pos].find(' ').is_some() {
             // gdb command already typed, try to complete identifier in expression
             IdentifierCompleter(self.0).complete(original, cursor_pos)
@@ -271,8 +316,6 @@ impl Completer for CmdlineCompleter<'_> {
             }
             .complete(original, cursor_pos)
         }
-        //TODO: path completer? not sure how to distinguish between IdentifierCompleter and path
-        //completer. Maybe based on gdb command...
     }
 }
 
@@ -530,6 +573,19 @@ mod test {
         assert_eq!(current_line(&state), "ba)");
     }
     #[test]
+    fn test_completion_option_words() {
+        let mut state = CompletionState::new(
+            "break ma".to_owned(),
+            8,
+            vec!["in".to_owned(), "lloc".to_owned()],
+        );
+        state.select_next_option();
+        assert_eq!(
+            state.option_words().collect::<Vec<_>>(),
+            vec![(false, "main".to_owned()), (true, "malloc".to_owned())]
+        );
+    }
+    #[test]
     fn test_completion_state_empty() {
         let mut state = CompletionState::new("ba)".to_owned(), 2, vec![]);
         assert_eq!(current_line(&state), "ba)");
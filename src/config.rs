@@ -0,0 +1,757 @@
+use actions;
+use encoding::FallbackEncoding;
+use executable_watch::AutoReload;
+use gdb::DisassemblyFlavor;
+use layout::TitleBars;
+use number_format::NumberFormat;
+use path_substitution::PathSubstitutions;
+use profile::Profile;
+use run_settings::RunConfiguration;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml::Value;
+use tui::expression_table::OutOfScopeStyle;
+use tui::render_profile::RenderProfile;
+use unsegen::base::Color;
+use watch_triggers::Trigger;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, String),
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "Failed to read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "Failed to parse {}: {}", path.display(), e),
+            ConfigError::Invalid(path, e) => write!(f, "Invalid config {}: {}", path.display(), e),
+        }
+    }
+}
+
+pub struct LayoutPreset {
+    pub name: String,
+    pub layout: String,
+}
+
+// Number of lines of program output that are kept for the scrollback mode of the terminal.
+const DEFAULT_TERMINAL_SCROLLBACK: usize = 10000;
+
+// Maximum number of characters of a single value shown in the expression table.
+const DEFAULT_VALUE_TRUNCATION: usize = 100;
+
+pub struct Config {
+    pub layouts: Vec<LayoutPreset>,
+    pub source_encoding: FallbackEncoding,
+    pub terminal_scrollback: usize,
+    pub value_truncation: Option<usize>, // None: Values are never truncated
+    pub out_of_scope: OutOfScopeStyle,
+    pub bindings: Vec<(String, Option<char>)>, // Changed key bindings of actions (None: unbound)
+    pub max_redraw_rate: Option<u32>,          // Redraws per second (None: unlimited)
+    pub disassembly_flavor: Option<DisassemblyFlavor>, // None: gdb's default
+    pub path_substitutions: PathSubstitutions,
+    pub title_bars: TitleBars,
+    pub dim_inactive: Option<Color>, // Text color of inactive containers (None: not dimmed)
+    pub render_profile: RenderProfile,
+    pub number_format: NumberFormat,
+    pub pretty_printers: Option<bool>, // None: gdb's default
+    pub print_pretty: Option<bool>,    // Likewise
+    pub profiles: Vec<Profile>,
+    pub run_configurations: Vec<RunConfiguration>,
+    pub auto_reload: AutoReload,
+    pub status_bar: bool,
+    pub triggers: Vec<Trigger>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            layouts: Vec::new(),
+            source_encoding: FallbackEncoding::default(),
+            terminal_scrollback: DEFAULT_TERMINAL_SCROLLBACK,
+            value_truncation: Some(DEFAULT_VALUE_TRUNCATION),
+            out_of_scope: OutOfScopeStyle::default(),
+            bindings: Vec::new(),
+            max_redraw_rate: None,
+            disassembly_flavor: None,
+            path_substitutions: PathSubstitutions::default(),
+            title_bars: TitleBars::default(),
+            dim_inactive: Some(Color::LightBlack),
+            render_profile: RenderProfile::default(),
+            number_format: NumberFormat::default(),
+            pretty_printers: None,
+            print_pretty: None,
+            profiles: Vec::new(),
+            run_configurations: Vec::new(),
+            auto_reload: AutoReload::default(),
+            status_bar: true,
+            triggers: Vec::new(),
+        }
+    }
+}
+
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("ugdb").join("config.toml"))
+}
+
+impl Config {
+    /// Load the config from the given file or the default location. A missing file at the default
+    /// location is not an error.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let (path, is_default) = match path {
+            Some(p) => (p.to_owned(), false),
+            None => match default_config_path() {
+                Some(p) => (p, true),
+                None => return Ok(Config::default()),
+            },
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(ref e) if is_default && e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Config::default())
+            }
+            Err(e) => return Err(ConfigError::Io(path, e)),
+        };
+        Self::parse(&content).map_err(|e| match e {
+            ParseError::Syntax(e) => ConfigError::Parse(path, e),
+            ParseError::Invalid(e) => ConfigError::Invalid(path, e),
+        })
+    }
+
+    fn parse(content: &str) -> Result<Self, ParseError> {
+        let value = content
+            .parse::<Value>()
+            .map_err(|e| ParseError::Syntax(e.to_string()))?;
+        let mut config = Config::default();
+        if let Some(encoding) = value.get("source_encoding") {
+            config.source_encoding = encoding
+                .as_str()
+                .ok_or_else(|| ParseError::Invalid("'source_encoding' must be a string".into()))?
+                .parse()
+                .map_err(ParseError::Invalid)?;
+        }
+        if let Some(lines) = value.get("terminal_scrollback") {
+            config.terminal_scrollback =
+                lines.as_integer().filter(|&l| l >= 0).ok_or_else(|| {
+                    ParseError::Invalid(
+                        "'terminal_scrollback' must be a non-negative integer".into(),
+                    )
+                })? as usize;
+        }
+        if let Some(rate) = value.get("max_redraw_rate") {
+            let rate = rate
+                .as_integer()
+                .filter(|&r| r >= 0 && r <= i64::from(u32::MAX))
+                .ok_or_else(|| {
+                    ParseError::Invalid("'max_redraw_rate' must be a non-negative integer".into())
+                })? as u32;
+            config.max_redraw_rate = if rate == 0 { None } else { Some(rate) };
+        }
+        if let Some(length) = value.get("value_truncation") {
+            let length = length.as_integer().filter(|&l| l >= 0).ok_or_else(|| {
+                ParseError::Invalid("'value_truncation' must be a non-negative integer".into())
+            })? as usize;
+            config.value_truncation = if length == 0 { None } else { Some(length) };
+        }
+        if let Some(style) = value.get("out_of_scope") {
+            config.out_of_scope = style
+                .as_str()
+                .ok_or_else(|| ParseError::Invalid("'out_of_scope' must be a string".into()))?
+                .parse()
+                .map_err(ParseError::Invalid)?;
+        }
+        if let Some(mode) = value.get("auto_reload") {
+            config.auto_reload = mode
+                .as_str()
+                .ok_or_else(|| ParseError::Invalid("'auto_reload' must be a string".into()))?
+                .parse()
+                .map_err(ParseError::Invalid)?;
+        }
+        if let Some(status_bar) = value.get("status_bar") {
+            config.status_bar = status_bar
+                .as_bool()
+                .ok_or_else(|| ParseError::Invalid("'status_bar' must be a boolean".into()))?;
+        }
+        if let Some(title_bars) = value.get("title_bars") {
+            config.title_bars = title_bars
+                .as_str()
+                .ok_or_else(|| ParseError::Invalid("'title_bars' must be a string".into()))?
+                .parse()
+                .map_err(ParseError::Invalid)?;
+        }
+        if let Some(dim) = value.get("dim_inactive") {
+            config.dim_inactive = match dim {
+                Value::Boolean(true) => Some(Color::LightBlack),
+                Value::Boolean(false) => None,
+                Value::String(color) => Some(parse_color(color).map_err(ParseError::Invalid)?),
+                _ => {
+                    return Err(ParseError::Invalid(
+                        "'dim_inactive' must be a boolean or a color".into(),
+                    ))
+                }
+            };
+        }
+        if let Some(profile) = value.get("render_profile") {
+            config.render_profile = profile
+                .as_str()
+                .ok_or_else(|| ParseError::Invalid("'render_profile' must be a string".into()))?
+                .parse()
+                .map_err(ParseError::Invalid)?;
+            // Dimming relies on colors.
+            if config.render_profile == RenderProfile::Monochrome
+                && value.get("dim_inactive").is_none()
+            {
+                config.dim_inactive = None;
+            }
+        }
+        if let Some(flavor) = value.get("disassembly_flavor") {
+            config.disassembly_flavor = Some(
+                flavor
+                    .as_str()
+                    .ok_or_else(|| {
+                        ParseError::Invalid("'disassembly_flavor' must be a string".into())
+                    })?
+                    .parse()
+                    .map_err(ParseError::Invalid)?,
+            );
+        }
+        for (key, option) in [
+            ("pretty_printers", &mut config.pretty_printers),
+            ("print_pretty", &mut config.print_pretty),
+        ] {
+            if let Some(enabled) = value.get(key) {
+                *option =
+                    Some(enabled.as_bool().ok_or_else(|| {
+                        ParseError::Invalid(format!("'{}' must be a boolean", key))
+                    })?);
+            }
+        }
+        if let Some(format) = value.get("number_format") {
+            config.number_format = parse_number_format_table(format)?;
+        }
+        if let Some(bindings) = value.get("bindings") {
+            let bindings = bindings
+                .as_table()
+                .ok_or_else(|| ParseError::Invalid("'bindings' must be a table".into()))?;
+            for (id, key) in bindings {
+                if actions::find(id).is_none() {
+                    return Err(ParseError::Invalid(format!("Unknown action '{}'", id)));
+                }
+                let key = key.as_str().map(|k| (k.chars().next(), k.chars().count()));
+                let key = match key {
+                    Some((key, 0)) | Some((key, 1)) => key,
+                    _ => {
+                        return Err(ParseError::Invalid(format!(
+                            "Binding of '{}' must be a single character or empty",
+                            id
+                        )))
+                    }
+                };
+                if let Some(key) = key.filter(|k| actions::SELECTION_MODE_KEYS.contains(k)) {
+                    return Err(ParseError::Invalid(format!(
+                        "Binding of '{}' must not be '{}', which selection mode uses itself",
+                        id, key
+                    )));
+                }
+                config.bindings.push((id.clone(), key));
+            }
+        }
+        if let Some(layouts) = value.get("layout") {
+            let layouts = layouts
+                .as_array()
+                .ok_or_else(|| ParseError::Invalid("'layout' must be an array of tables".into()))?;
+            for l in layouts {
+                let get = |key: &str| {
+                    l.get(key)
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_owned())
+                        .ok_or_else(|| {
+                            ParseError::Invalid(format!("Layout preset is missing '{}'", key))
+                        })
+                };
+                config.layouts.push(LayoutPreset {
+                    name: get("name")?,
+                    layout: get("layout")?,
+                });
+            }
+        }
+        if let Some(rules) = value.get("substitute_path") {
+            let rules = rules.as_array().ok_or_else(|| {
+                ParseError::Invalid("'substitute_path' must be an array of tables".into())
+            })?;
+            for rule in rules {
+                let get = |key: &str| {
+                    rule.get(key)
+                        .and_then(|v| v.as_str())
+                        .map(PathBuf::from)
+                        .ok_or_else(|| {
+                            ParseError::Invalid(format!("Path substitution is missing '{}'", key))
+                        })
+                };
+                config.path_substitutions.add(get("from")?, get("to")?);
+            }
+        }
+        if let Some(profiles) = value.get("profile") {
+            let profiles = profiles.as_array().ok_or_else(|| {
+                ParseError::Invalid("'profile' must be an array of tables".into())
+            })?;
+            for profile in profiles {
+                config
+                    .profiles
+                    .push(Profile::parse(profile).map_err(ParseError::Invalid)?);
+            }
+        }
+        if let Some(configurations) = value.get("run") {
+            let configurations = configurations
+                .as_array()
+                .ok_or_else(|| ParseError::Invalid("'run' must be an array of tables".into()))?;
+            for configuration in configurations {
+                config
+                    .run_configurations
+                    .push(RunConfiguration::parse(configuration).map_err(ParseError::Invalid)?);
+            }
+        }
+        if let Some(triggers) = value.get("trigger") {
+            let triggers = triggers.as_array().ok_or_else(|| {
+                ParseError::Invalid("'trigger' must be an array of tables".into())
+            })?;
+            for trigger in triggers {
+                config
+                    .triggers
+                    .push(Trigger::parse(trigger).map_err(ParseError::Invalid)?);
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn layout_preset(&self, name: &str) -> Option<&str> {
+        self.layouts
+            .iter()
+            .find(|l| l.name == name)
+            .map(|l| l.layout.as_str())
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+}
+
+fn parse_number_format_table(value: &Value) -> Result<NumberFormat, ParseError> {
+    let table = value
+        .as_table()
+        .ok_or_else(|| ParseError::Invalid("'number_format' must be a table".into()))?;
+    let mut format = NumberFormat::default();
+    let bool_option = |key: &str| {
+        table
+            .get(key)
+            .map(|v| {
+                v.as_bool().ok_or_else(|| {
+                    ParseError::Invalid(format!("'number_format.{}' must be a boolean", key))
+                })
+            })
+            .transpose()
+    };
+    let char_option = |key: &str| {
+        table
+            .get(key)
+            .map(|v| {
+                let s = v.as_str().unwrap_or("");
+                match (s.chars().next(), s.chars().count()) {
+                    (Some(c), 1) => Ok(c),
+                    _ => Err(ParseError::Invalid(format!(
+                        "'number_format.{}' must be a single character",
+                        key
+                    ))),
+                }
+            })
+            .transpose()
+    };
+    if let Some(uppercase) = bool_option("uppercase_hex")? {
+        format.uppercase_hex = uppercase;
+    }
+    if let Some(pad) = bool_option("pad_addresses")? {
+        format.pad_addresses = pad;
+    }
+    if let Some(grouping) = table.get("hex_grouping") {
+        format.hex_grouping = grouping.as_integer().filter(|&g| g >= 0).ok_or_else(|| {
+            ParseError::Invalid(
+                "'number_format.hex_grouping' must be a non-negative integer".into(),
+            )
+        })? as usize;
+    }
+    if let Some(separator) = char_option("hex_separator")? {
+        format.hex_separator = separator;
+    }
+    format.digit_separator = char_option("digit_separator")?;
+    Ok(format)
+}
+
+// E.g., "lightblack" or "#808080".
+fn parse_color(s: &str) -> Result<Color, String> {
+    if s.starts_with('#') && s.len() == 7 {
+        let component = |i: usize| u8::from_str_radix(&s[i..i + 2], 16);
+        if let (Ok(r), Ok(g), Ok(b)) = (component(1), component(3), component(5)) {
+            return Ok(Color::Rgb { r, g, b });
+        }
+    }
+    Ok(match s {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "lightblack" | "gray" | "grey" => Color::LightBlack,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "lightwhite" => Color::LightWhite,
+        o => return Err(format!("Unknown color '{}'", o)),
+    })
+}
+
+enum ParseError {
+    Syntax(String),
+    Invalid(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use profile::Target;
+
+    #[test]
+    fn parse_layout_presets() {
+        let config = Config::parse(
+            r#"
+            [[layout]]
+            name = "code"
+            layout = "s|c"
+
+            [[layout]]
+            name = "vars"
+            layout = "e-c"
+            "#,
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(config.layout_preset("code"), Some("s|c"));
+        assert_eq!(config.layout_preset("vars"), Some("e-c"));
+        assert_eq!(config.layout_preset("other"), None);
+        assert_eq!(config.layouts[1].name, "vars");
+    }
+    #[test]
+    fn parse_profiles() {
+        let config = Config::parse(
+            r#"
+            [[profile]]
+            name = "app"
+            type = "container"
+            runtime = "podman"
+            container = "app-dev"
+            program = "/usr/local/bin/app"
+            args = ["--verbose"]
+            symbols = "build/app"
+            "#,
+        )
+        .ok()
+        .unwrap();
+        let profile = config.profile("app").unwrap();
+        assert_eq!(
+            profile.launch().unwrap().0,
+            "| podman exec -i app-dev gdbserver - /usr/local/bin/app --verbose"
+        );
+        assert_eq!(profile.symbols, Some(PathBuf::from("build/app")));
+        assert_eq!(profile.sysroot, "target:");
+        assert!(config.profile("other").is_none());
+        let config = Config::parse(
+            r#"
+            [[profile]]
+            name = "board"
+            type = "ssh"
+            host = "pi@board"
+            program = "/home/pi/app"
+            upload = "target/debug/app"
+            "#,
+        )
+        .ok()
+        .unwrap();
+        let profile = config.profile("board").unwrap();
+        assert_eq!(
+            profile.target,
+            Target::Ssh {
+                host: "pi@board".to_owned(),
+                port: 2345,
+                upload: Some(PathBuf::from("target/debug/app")),
+            }
+        );
+        assert_eq!(profile.sysroot, "remote:");
+        assert!(Config::parse(
+            "[[profile]]\nname = \"a\"\ntype = \"ssh\"\nhost = \"b\"\nprogram = \"c\"\nport = 70000"
+        )
+        .is_err());
+        assert!(Config::parse("[[profile]]\nname = \"app\"\ntype = \"vm\"").is_err());
+        assert!(Config::parse(
+            "[[profile]]\nname = \"app\"\ntype = \"container\"\nruntime = \"lxc\""
+        )
+        .is_err());
+    }
+    #[test]
+    fn parse_run_configurations() {
+        let config = Config::parse(
+            r#"
+            [[run]]
+            name = "unit test"
+            args = "--test-threads 1 parser::"
+            env = { RUST_BACKTRACE = "1" }
+            commands = ["break rust_panic"]
+
+            [[run]]
+            name = "verbose"
+            args = "--verbose"
+            cwd = "/tmp"
+            "#,
+        )
+        .ok()
+        .unwrap();
+        let configurations = &config.run_configurations;
+        assert_eq!(configurations.len(), 2);
+        assert_eq!(
+            configurations[0].description(),
+            "unit test: args: --test-threads 1 parser::, RUST_BACKTRACE=1, break rust_panic"
+        );
+        assert_eq!(configurations[1].settings.cwd, Some(PathBuf::from("/tmp")));
+        assert!(Config::parse("[[run]]\nargs = \"-v\"").is_err());
+        assert!(Config::parse("[[run]]\nname = \"a\"\nenv = { A = 1 }").is_err());
+    }
+    #[test]
+    fn parse_source_encoding() {
+        let config = Config::parse("source_encoding = \"Latin-1\"").ok().unwrap();
+        assert_eq!(config.source_encoding, FallbackEncoding::Latin1);
+        assert!(Config::parse("source_encoding = \"shift-jis\"").is_err());
+    }
+    #[test]
+    fn parse_terminal_scrollback() {
+        let config = Config::parse("terminal_scrollback = 500").ok().unwrap();
+        assert_eq!(config.terminal_scrollback, 500);
+        assert_eq!(
+            Config::parse("").ok().unwrap().terminal_scrollback,
+            DEFAULT_TERMINAL_SCROLLBACK
+        );
+        assert!(Config::parse("terminal_scrollback = -1").is_err());
+    }
+    #[test]
+    fn parse_disassembly_flavor() {
+        let config = Config::parse("disassembly_flavor = \"intel\"")
+            .ok()
+            .unwrap();
+        assert_eq!(config.disassembly_flavor, Some(DisassemblyFlavor::Intel));
+        assert_eq!(Config::parse("").ok().unwrap().disassembly_flavor, None);
+        assert!(Config::parse("disassembly_flavor = \"masm\"").is_err());
+    }
+    #[test]
+    fn parse_pretty_printing() {
+        let config = Config::parse("pretty_printers = true\nprint_pretty = false")
+            .ok()
+            .unwrap();
+        assert_eq!(config.pretty_printers, Some(true));
+        assert_eq!(config.print_pretty, Some(false));
+        assert_eq!(Config::parse("").ok().unwrap().pretty_printers, None);
+        assert!(Config::parse("print_pretty = \"on\"").is_err());
+    }
+    #[test]
+    fn parse_substitute_path() {
+        let config = Config::parse(
+            r#"
+            [[substitute_path]]
+            from = "/build"
+            to = "/home/user/src"
+            "#,
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(
+            config.path_substitutions.rules(),
+            &[(PathBuf::from("/build"), PathBuf::from("/home/user/src"))]
+        );
+        assert!(Config::parse("[[substitute_path]]\nfrom = \"/build\"").is_err());
+    }
+    #[test]
+    fn parse_title_bars() {
+        let config = Config::parse("title_bars = \"all\"").ok().unwrap();
+        assert_eq!(config.title_bars, TitleBars::All);
+        assert_eq!(Config::parse("").ok().unwrap().title_bars, TitleBars::Pager);
+        assert!(Config::parse("title_bars = true").is_err());
+    }
+    #[test]
+    fn parse_render_profile() {
+        let config = Config::parse("render_profile = \"monochrome\"")
+            .ok()
+            .unwrap();
+        assert_eq!(config.render_profile, RenderProfile::Monochrome);
+        assert_eq!(config.dim_inactive, None);
+        let config = Config::parse("render_profile = \"high-contrast\"\ndim_inactive = true")
+            .ok()
+            .unwrap();
+        assert_eq!(config.render_profile, RenderProfile::HighContrast);
+        assert_eq!(config.dim_inactive, Some(Color::LightBlack));
+        assert_eq!(
+            Config::parse("").ok().unwrap().render_profile,
+            RenderProfile::Color
+        );
+        assert!(Config::parse("render_profile = \"sepia\"").is_err());
+    }
+    #[test]
+    fn parse_dim_inactive() {
+        let dim = |s| Config::parse(s).ok().unwrap().dim_inactive;
+        assert_eq!(dim(""), Some(Color::LightBlack));
+        assert_eq!(dim("dim_inactive = false"), None);
+        assert_eq!(dim("dim_inactive = \"blue\""), Some(Color::Blue));
+        assert_eq!(
+            dim("dim_inactive = \"#102030\""),
+            Some(Color::Rgb {
+                r: 0x10,
+                g: 0x20,
+                b: 0x30
+            })
+        );
+        assert!(Config::parse("dim_inactive = \"#1020\"").is_err());
+        assert!(Config::parse("dim_inactive = 1").is_err());
+    }
+    #[test]
+    fn parse_max_redraw_rate() {
+        let config = Config::parse("max_redraw_rate = 20").ok().unwrap();
+        assert_eq!(config.max_redraw_rate, Some(20));
+        let config = Config::parse("max_redraw_rate = 0").ok().unwrap();
+        assert_eq!(config.max_redraw_rate, None);
+        assert_eq!(Config::parse("").ok().unwrap().max_redraw_rate, None);
+        assert!(Config::parse("max_redraw_rate = -1").is_err());
+    }
+    #[test]
+    fn parse_value_truncation() {
+        let config = Config::parse("value_truncation = 20").ok().unwrap();
+        assert_eq!(config.value_truncation, Some(20));
+        let config = Config::parse("value_truncation = 0").ok().unwrap();
+        assert_eq!(config.value_truncation, None);
+        assert_eq!(
+            Config::parse("").ok().unwrap().value_truncation,
+            Some(DEFAULT_VALUE_TRUNCATION)
+        );
+        assert!(Config::parse("value_truncation = \"long\"").is_err());
+    }
+    #[test]
+    fn parse_out_of_scope() {
+        let config = Config::parse("out_of_scope = \"collapse\"").ok().unwrap();
+        assert_eq!(config.out_of_scope, OutOfScopeStyle::Collapse);
+        assert_eq!(
+            Config::parse("").ok().unwrap().out_of_scope,
+            OutOfScopeStyle::Error
+        );
+        assert!(Config::parse("out_of_scope = \"hide\"").is_err());
+    }
+    #[test]
+    fn parse_auto_reload() {
+        let config = Config::parse("auto_reload = \"always\"").ok().unwrap();
+        assert_eq!(config.auto_reload, AutoReload::Always);
+        assert_eq!(Config::parse("").ok().unwrap().auto_reload, AutoReload::Ask);
+        assert!(Config::parse("auto_reload = true").is_err());
+    }
+    #[test]
+    fn parse_status_bar() {
+        assert!(Config::parse("").ok().unwrap().status_bar);
+        assert!(!Config::parse("status_bar = false").ok().unwrap().status_bar);
+        assert!(Config::parse("status_bar = \"off\"").is_err());
+    }
+
+    #[test]
+    fn parse_triggers() {
+        let config = Config::parse(
+            r#"
+            [[trigger]]
+            expression = "state->error"
+            commands = ["!memory &buf", "!layout embedded"]
+
+            [[trigger]]
+            expression = "mode"
+            when = "change"
+            commands = ["!backtrace"]
+            "#,
+        )
+        .ok()
+        .unwrap();
+        let triggers = config
+            .triggers
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            triggers,
+            vec![
+                "nonzero state->error => !memory &buf; !layout embedded",
+                "change mode => !backtrace"
+            ]
+        );
+        assert!(Config::parse("[[trigger]]\nexpression = \"x\"").is_err());
+        assert!(Config::parse(
+            "[[trigger]]\nexpression = \"x\"\nwhen = \"zero\"\ncommands = [\"!backtrace\"]"
+        )
+        .is_err());
+    }
+    #[test]
+    fn parse_number_format() {
+        let config = Config::parse(
+            "[number_format]\nuppercase_hex = true\nhex_grouping = 4\ndigit_separator = \",\"",
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(
+            config.number_format,
+            NumberFormat {
+                uppercase_hex: true,
+                hex_grouping: 4,
+                digit_separator: Some(','),
+                ..NumberFormat::default()
+            }
+        );
+        assert_eq!(
+            Config::parse("").ok().unwrap().number_format,
+            NumberFormat::default()
+        );
+        assert!(Config::parse("[number_format]\nhex_separator = \"--\"").is_err());
+        assert!(Config::parse("[number_format]\npad_addresses = 1").is_err());
+    }
+    #[test]
+    fn parse_bindings() {
+        let config = Config::parse("[bindings]\nbreakpoints = \"b\"\ngrep = \"\"")
+            .ok()
+            .unwrap();
+        assert_eq!(
+            config.bindings,
+            vec![
+                ("breakpoints".to_owned(), Some('b')),
+                ("grep".to_owned(), None)
+            ]
+        );
+        assert!(Config::parse("[bindings]\nfoo = \"f\"").is_err());
+        assert!(Config::parse("[bindings]\ngrep = \"gr\"").is_err());
+        assert!(Config::parse("[bindings]\ngrep = \"T\"").is_err());
+    }
+    #[test]
+    fn parse_incomplete_layout_preset() {
+        assert!(Config::parse("[[layout]]\nname = \"code\"\n").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use gdb::SrcPosition;
+use json;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use unsegen::base::LineNumber;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Note => write!(f, "note"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub pos: SrcPosition,
+    pub severity: Severity,
+    pub message: String,
+}
+
+const SEVERITY_TAGS: &[(&str, Severity)] = &[
+    (": fatal error: ", Severity::Error),
+    (": error: ", Severity::Error),
+    (": warning: ", Severity::Warning),
+    (": note: ", Severity::Note),
+];
+
+// Parse a single line of gcc/clang output (file:line[:column]: severity: message). Relative paths
+// are interpreted relative to the base directory.
+fn parse_diagnostic_line(line: &str, base_dir: &Path) -> Option<Diagnostic> {
+    let (begin, tag, severity) = SEVERITY_TAGS
+        .iter()
+        .filter_map(|&(tag, severity)| line.find(tag).map(|i| (i, tag, severity)))
+        .min_by_key(|&(i, _, _)| i)?;
+    let location = &line[..begin];
+    let message = line[begin + tag.len()..].trim().to_owned();
+
+    let mut parts = location.rsplitn(3, ':');
+    let last = parts.next()?;
+    let second_to_last = parts.next()?;
+    let (file, line_number) = match parts.next() {
+        Some(file) if second_to_last.parse::<usize>().is_ok() => (file, second_to_last),
+        Some(file) => (&location[..file.len() + second_to_last.len() + 1], last),
+        None => (second_to_last, last),
+    };
+    let line_number = line_number.parse::<usize>().ok().filter(|&n| n > 0)?;
+    Some(Diagnostic {
+        pos: SrcPosition::new(base_dir.join(file), LineNumber::new(line_number)),
+        severity,
+        message,
+    })
+}
+
+pub fn parse_diagnostics(output: &str, base_dir: &Path) -> Vec<Diagnostic> {
+    output
+        .lines()
+        .filter_map(|l| parse_diagnostic_line(l, base_dir))
+        .collect()
+}
+
+// Paths are compared to those reported by gdb, which are usually canonical.
+fn canonicalize_paths(mut diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    for d in &mut diagnostics {
+        if let Ok(path) = fs::canonicalize(&d.pos.file) {
+            d.pos.file = path;
+        }
+    }
+    diagnostics
+}
+
+/// Load compiler diagnostics (e.g., a saved build log) from a file. Relative paths are interpreted
+/// relative to the directory containing the file.
+pub fn load_diagnostics_file(path: &Path) -> io::Result<Vec<Diagnostic>> {
+    let content = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    Ok(canonicalize_paths(parse_diagnostics(&content, base_dir)))
+}
+
+#[derive(Debug)]
+pub enum SyntaxCheckError {
+    Io(io::Error),
+    InvalidCompileCommands(String),
+    NoCompileCommand(PathBuf),
+}
+
+impl fmt::Display for SyntaxCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntaxCheckError::Io(e) => write!(f, "{}", e),
+            SyntaxCheckError::InvalidCompileCommands(e) => {
+                write!(f, "Invalid compilation database: {}", e)
+            }
+            SyntaxCheckError::NoCompileCommand(file) => {
+                write!(f, "No compile command for {}", file.display())
+            }
+        }
+    }
+}
+
+impl From<io::Error> for SyntaxCheckError {
+    fn from(e: io::Error) -> Self {
+        SyntaxCheckError::Io(e)
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Run the compile command for the file from a compilation database (compile_commands.json) with
+/// -fsyntax-only and collect the resulting diagnostics.
+pub fn run_syntax_check(
+    compile_commands: &Path,
+    file: &Path,
+) -> Result<Vec<Diagnostic>, SyntaxCheckError> {
+    let content = fs::read_to_string(compile_commands)?;
+    let db = json::parse(&content)
+        .map_err(|e| SyntaxCheckError::InvalidCompileCommands(e.to_string()))?;
+    for entry in db.members() {
+        let directory = PathBuf::from(entry["directory"].as_str().unwrap_or("."));
+        let entry_file = match entry["file"].as_str() {
+            Some(f) => directory.join(f),
+            None => continue,
+        };
+        if !same_file(&entry_file, file) {
+            continue;
+        }
+        // Arguments in "command" are split at whitespace, i.e., quoting is not supported.
+        let args = if entry["arguments"].is_array() {
+            entry["arguments"]
+                .members()
+                .filter_map(|a| a.as_str())
+                .map(|a| a.to_owned())
+                .collect::<Vec<_>>()
+        } else {
+            entry["command"]
+                .as_str()
+                .unwrap_or("")
+                .split_whitespace()
+                .map(|a| a.to_owned())
+                .collect::<Vec<_>>()
+        };
+        let (compiler, args) = args.split_first().ok_or_else(|| {
+            SyntaxCheckError::InvalidCompileCommands(format!(
+                "Empty command for {}",
+                entry_file.display()
+            ))
+        })?;
+        let output = Command::new(compiler)
+            .args(args)
+            .arg("-fsyntax-only")
+            .arg("-fdiagnostics-color=never")
+            .current_dir(&directory)
+            .output()?;
+        return Ok(canonicalize_paths(parse_diagnostics(
+            &String::from_utf8_lossy(&output.stderr),
+            &directory,
+        )));
+    }
+    Err(SyntaxCheckError::NoCompileCommand(file.to_owned()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_gcc_output() {
+        let output = "main.c: In function 'main':\n\
+                      main.c:4:9: warning: unused variable 'x' [-Wunused-variable]\n\
+                      \x20   4 |     int x;\n\
+                      /usr/include/foo.h:12: error: expected ';' before '}' token\n\
+                      main.c:2:5: note: declared here\n";
+        let diagnostics = parse_diagnostics(output, Path::new("/src"));
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic {
+                    pos: SrcPosition::new(PathBuf::from("/src/main.c"), LineNumber::new(4)),
+                    severity: Severity::Warning,
+                    message: "unused variable 'x' [-Wunused-variable]".to_owned(),
+                },
+                Diagnostic {
+                    pos: SrcPosition::new(PathBuf::from("/usr/include/foo.h"), LineNumber::new(12)),
+                    severity: Severity::Error,
+                    message: "expected ';' before '}' token".to_owned(),
+                },
+                Diagnostic {
+                    pos: SrcPosition::new(PathBuf::from("/src/main.c"), LineNumber::new(2)),
+                    severity: Severity::Note,
+                    message: "declared here".to_owned(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Encoding used to decode source files that are not valid UTF-8.
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum FallbackEncoding {
+    /// Replace invalid sequences with U+FFFD.
+    #[default]
+    Utf8Lossy,
+    /// ISO-8859-1: Every byte is mapped to the code point of the same value.
+    Latin1,
+}
+
+impl FromStr for FallbackEncoding {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(FallbackEncoding::Utf8Lossy),
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(FallbackEncoding::Latin1),
+            o => Err(format!(
+                "Unknown encoding '{}'. Supported: utf-8, latin1",
+                o
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FallbackEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FallbackEncoding::Utf8Lossy => write!(f, "utf-8 (lossy)"),
+            FallbackEncoding::Latin1 => write!(f, "latin1"),
+        }
+    }
+}
+
+/// Decode the content of a file. Returns the fallback encoding if it had to be applied because the
+/// content is not valid UTF-8.
+pub fn decode(bytes: Vec<u8>, fallback: FallbackEncoding) -> (String, Option<FallbackEncoding>) {
+    match String::from_utf8(bytes) {
+        Ok(s) => (s, None),
+        Err(e) => {
+            let bytes = e.into_bytes();
+            let s = match fallback {
+                FallbackEncoding::Utf8Lossy => String::from_utf8_lossy(&bytes).into_owned(),
+                FallbackEncoding::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+            };
+            (s, Some(fallback))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_valid_utf8() {
+        let (s, applied) = decode("grüße".as_bytes().to_vec(), FallbackEncoding::Latin1);
+        assert_eq!(s, "grüße");
+        assert_eq!(applied, None);
+    }
+    #[test]
+    fn decode_latin1() {
+        let (s, applied) = decode(b"gr\xfc\xdfe".to_vec(), FallbackEncoding::Latin1);
+        assert_eq!(s, "grüße");
+        assert_eq!(applied, Some(FallbackEncoding::Latin1));
+    }
+    #[test]
+    fn decode_lossy() {
+        let (s, applied) = decode(b"gr\xfc\xdfe".to_vec(), FallbackEncoding::Utf8Lossy);
+        assert_eq!(s, "gr\u{FFFD}\u{FFFD}e");
+        assert_eq!(applied, Some(FallbackEncoding::Utf8Lossy));
+    }
+}
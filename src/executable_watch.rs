@@ -0,0 +1,116 @@
+// Detect that the executable was rebuilt while ugdb is running, so that it can be loaded again
+// without restarting ugdb. The modification time of the file is polled, because the build may
+// replace the file instead of writing to it (which file watches of some platforms lose track of).
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What happens when the executable was rebuilt (and the program is not running).
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum AutoReload {
+    /// Ask whether it should be loaded again.
+    #[default]
+    Ask,
+    /// Load it again right away.
+    Always,
+    /// Only log that it changed.
+    Off,
+}
+
+impl FromStr for AutoReload {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ask" => Ok(AutoReload::Ask),
+            "always" => Ok(AutoReload::Always),
+            "off" => Ok(AutoReload::Off),
+            o => Err(format!(
+                "Unknown auto reload mode '{}'. Supported: ask, always, off",
+                o
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AutoReload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AutoReload::Ask => write!(f, "ask"),
+            AutoReload::Always => write!(f, "always"),
+            AutoReload::Off => write!(f, "off"),
+        }
+    }
+}
+
+/// Tracks the modification time of a file. A change is only reported once the time stayed the same
+/// for one poll, so that a file that is still being written (e.g., by the linker) is not read.
+#[derive(Clone, Debug, PartialEq)]
+struct ModificationWatch {
+    reported: Option<SystemTime>,
+    pending: Option<SystemTime>,
+}
+
+impl ModificationWatch {
+    fn new(modified: Option<SystemTime>) -> Self {
+        ModificationWatch {
+            reported: modified,
+            pending: None,
+        }
+    }
+
+    // Whether the file changed (and is complete) given its current modification time.
+    fn update(&mut self, modified: Option<SystemTime>) -> bool {
+        if modified.is_none() || modified == self.reported {
+            self.pending = None;
+            return false;
+        }
+        if modified != self.pending {
+            self.pending = modified;
+            return false;
+        }
+        self.reported = modified;
+        self.pending = None;
+        true
+    }
+}
+
+/// Send the event whenever the executable was modified.
+pub fn spawn<E: Send + 'static>(executable: PathBuf, sink: Sender<E>, event: fn(PathBuf) -> E) {
+    let modified = |path: &PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut watch = ModificationWatch::new(modified(&executable));
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        if watch.update(modified(&executable)) && sink.send(event(executable.clone())).is_err() {
+            break;
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_modification_watch() {
+        let time = |s| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(s));
+        let mut watch = ModificationWatch::new(time(1));
+        assert!(!watch.update(time(1)));
+        // Still being written.
+        assert!(!watch.update(time(2)));
+        assert!(!watch.update(time(3)));
+        assert!(watch.update(time(3)));
+        assert!(!watch.update(time(3)));
+        // Removed while being rebuilt.
+        assert!(!watch.update(None));
+        assert!(!watch.update(time(4)));
+        assert!(watch.update(time(4)));
+        assert_eq!("always".parse(), Ok(AutoReload::Always));
+        assert!("sometimes".parse::<AutoReload>().is_err());
+    }
+}
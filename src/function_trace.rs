@@ -0,0 +1,637 @@
+// Trace calls of a set of functions (ltrace-like, but for the functions of the program): A
+// breakpoint is inserted at the entry of every traced function. When it is hit, the call (with its
+// arguments) is recorded and the program continues right away. If returns are traced as well,
+// `finish` is used to run until the function returns, which reports the return value.
+// Calls are indented by how many traced calls of the same thread they are nested in. Since a
+// traced function that is called while finishing another one interrupts the `finish`, the
+// outstanding calls of each thread are kept on a stack together with the depth of the stack of the
+// program at their entry, which tells which function the next `finish` returns from.
+// Instead of the arguments, a list of expressions can be recorded for a function, and calls can be
+// filtered by a condition, which gdb evaluates as the condition of the breakpoint (so that the
+// program does not even stop for calls that are not recorded).
+use gdb::{BreakpointOperationError, GDB};
+use gdbmi::commands::{BreakPointNumber, MiCommand};
+use gdbmi::output::{JsonValue, Object};
+use log::warn;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::iter;
+use std::mem;
+use std::path::Path;
+use std::str::FromStr;
+use tui::event_bus::DebugEvent;
+
+// Older calls are dropped once there are more than this many.
+const MAX_ENTRIES: usize = 10000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEntry {
+    /// Number of outstanding traced calls of the thread that this call is nested in.
+    pub depth: usize,
+    pub thread: String,
+    pub function: String,
+    /// Names and values of the arguments.
+    pub args: Vec<(String, String)>,
+    /// `None` if returns are not traced or the function did not return (yet).
+    pub return_value: Option<String>,
+}
+
+impl TraceEntry {
+    /// E.g., "parse(s=0x4006f4 "1+2", len=3)".
+    pub fn call(&self) -> String {
+        let args = self
+            .args
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{}({})", self.function, args)
+    }
+}
+
+// A traced call that has not returned yet.
+#[derive(Clone, Debug, PartialEq)]
+struct Call {
+    id: usize,
+    stack_depth: u64,
+}
+
+/// What the program should do after a stop of the trace.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resume {
+    Continue,
+    /// Run until the function in the innermost frame returns.
+    Finish,
+}
+
+/// What to record of the calls of a function: `<function> [capture <expr>, ...] [if <condition>]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceSpec {
+    pub function: String,
+    /// Expressions that are recorded instead of the arguments (if there are any).
+    pub captures: Vec<String>,
+    /// Only calls for which the condition is true are recorded.
+    pub condition: Option<String>,
+}
+
+// Split at commas that are not nested in parentheses, brackets or quotes, stopping at an `if` on
+// the same level. Returns the parts and what follows the `if`.
+fn split_captures(s: &str) -> (Vec<&str>, Option<&str>) {
+    let mut parts = Vec::new();
+    let (mut depth, mut quote, mut start) = (0usize, None, 0);
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(_), '\\') => {}
+            (Some(q), c) if c == q && !s[..i].ends_with('\\') => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') | (None, '[') | (None, '{') => depth += 1,
+            (None, ')') | (None, ']') | (None, '}') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            (None, c) if depth == 0 && c.is_whitespace() && s[i + 1..].starts_with("if ") => {
+                parts.push(&s[start..i]);
+                return (parts, Some(&s[i + 4..]));
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    (parts, None)
+}
+
+impl FromStr for TraceSpec {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (function, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        if function.is_empty() {
+            return Err("No function given".to_owned());
+        }
+        let rest = format!(" {}", rest.trim());
+        let (captures, condition) = if let Some(captures) = rest.strip_prefix(" capture ") {
+            let (captures, condition) = split_captures(captures);
+            let captures = captures
+                .into_iter()
+                .map(|c| c.trim().to_owned())
+                .collect::<Vec<_>>();
+            if captures.iter().any(|c| c.is_empty()) {
+                return Err("Empty capture expression".to_owned());
+            }
+            (captures, condition)
+        } else if let Some(condition) = rest.strip_prefix(" if ") {
+            (Vec::new(), Some(condition))
+        } else if rest.trim().is_empty() {
+            (Vec::new(), None)
+        } else {
+            return Err(format!(
+                "Expected 'capture' or 'if' after the function, got '{}'",
+                rest.trim()
+            ));
+        };
+        let condition = match condition.map(str::trim) {
+            Some("") => return Err("Empty condition".to_owned()),
+            condition => condition.map(|c| c.to_owned()),
+        };
+        Ok(TraceSpec {
+            function: function.to_owned(),
+            captures,
+            condition,
+        })
+    }
+}
+
+impl fmt::Display for TraceSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.function)?;
+        if !self.captures.is_empty() {
+            write!(f, " capture {}", self.captures.join(", "))?;
+        }
+        if let Some(condition) = &self.condition {
+            write!(f, " if {}", condition)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct TracedFunction {
+    spec: TraceSpec,
+    breakpoint: usize,
+}
+
+/// The formats that the trace can be exported to (for analyzing it with other tools).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    /// One row per call with a column for every argument (or captured expression) of any function.
+    Csv,
+    /// An array with one object per call.
+    Json,
+}
+
+impl ExportFormat {
+    /// The format that corresponds to the extension of the file.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+pub struct FunctionTrace {
+    functions: Vec<TracedFunction>,
+    pub trace_returns: bool,
+    entries: VecDeque<TraceEntry>,
+    // Number of entries that were dropped, i.e., the id of the first entry.
+    dropped: usize,
+    calls: HashMap<String, Vec<Call>>,
+    // The thread whose current function is being finished by the trace (and not by the user).
+    finishing: Option<String>,
+}
+
+impl FunctionTrace {
+    pub fn new() -> Self {
+        FunctionTrace {
+            functions: Vec::new(),
+            trace_returns: false,
+            entries: VecDeque::new(),
+            dropped: 0,
+            calls: HashMap::new(),
+            finishing: None,
+        }
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &TraceSpec> {
+        self.functions.iter().map(|f| &f.spec)
+    }
+
+    // The expressions to record for calls that hit the breakpoint.
+    fn captures(&self, breakpoint: usize) -> &[String] {
+        self.functions
+            .iter()
+            .find(|f| f.breakpoint == breakpoint)
+            .map(|f| f.spec.captures.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.entries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.dropped += self.entries.len();
+        self.entries.clear();
+    }
+
+    /// Start tracing calls of the function by inserting a breakpoint at its entry. If the function
+    /// is traced already, the new spec replaces the old one.
+    pub fn trace(
+        &mut self,
+        gdb: &mut GDB,
+        spec: TraceSpec,
+    ) -> Result<(), BreakpointOperationError> {
+        if let Some(traced) = self
+            .functions
+            .iter()
+            .find(|f| f.spec.function == spec.function)
+        {
+            if traced.spec == spec {
+                return Ok(());
+            }
+            let function = spec.function.clone();
+            self.untrace(gdb, &function)?;
+        }
+        let breakpoint =
+            gdb.insert_function_breakpoint(&spec.function, spec.condition.as_deref())?;
+        gdb.breakpoints.traced.insert(breakpoint);
+        self.functions.push(TracedFunction { spec, breakpoint });
+        Ok(())
+    }
+
+    pub fn untrace(
+        &mut self,
+        gdb: &mut GDB,
+        function: &str,
+    ) -> Result<(), BreakpointOperationError> {
+        if let Some(i) = self
+            .functions
+            .iter()
+            .position(|f| f.spec.function == function)
+        {
+            let major = self.functions[i].breakpoint;
+            gdb.delete_breakpoints(iter::once(BreakPointNumber { major, minor: None }))?;
+            gdb.breakpoints.traced.remove(&major);
+            self.functions.remove(i);
+        }
+        Ok(())
+    }
+
+    /// Insert the breakpoints again (e.g., after gdb was restarted or the executable was loaded
+    /// again). Returns the functions that could not be traced anymore.
+    pub fn reinsert(&mut self, gdb: &mut GDB) -> Vec<String> {
+        self.calls.clear();
+        self.finishing = None;
+        let mut failed = Vec::new();
+        for function in mem::take(&mut self.functions) {
+            if let Err(e) = self.trace(gdb, function.spec.clone()) {
+                warn!("Could not trace {} again: {:?}", function.spec.function, e);
+                failed.push(function.spec.function);
+            }
+        }
+        failed
+    }
+
+    fn push(&mut self, entry: TraceEntry) -> usize {
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+        self.entries.push_back(entry);
+        self.dropped + self.entries.len() - 1
+    }
+
+    fn next(&mut self, thread: &str, stack_depth: u64) -> Resume {
+        // Deeper (untraced) functions are finished as well, until the traced one returns.
+        let outstanding = self.calls.get(thread).and_then(|c| c.last());
+        if self.trace_returns && outstanding.is_some_and(|c| c.stack_depth <= stack_depth) {
+            self.finishing = Some(thread.to_owned());
+            Resume::Finish
+        } else {
+            self.finishing = None;
+            Resume::Continue
+        }
+    }
+
+    /// A traced function was entered, i.e., its breakpoint was hit.
+    pub fn on_entry(
+        &mut self,
+        thread: &str,
+        stack_depth: u64,
+        function: String,
+        args: Vec<(String, String)>,
+    ) -> Resume {
+        // Calls at the same or a deeper level of the stack returned without being seen.
+        let mut calls = self.calls.remove(thread).unwrap_or_default();
+        calls.retain(|c| c.stack_depth < stack_depth);
+        let id = self.push(TraceEntry {
+            depth: calls.len(),
+            thread: thread.to_owned(),
+            function,
+            args,
+            return_value: None,
+        });
+        calls.push(Call { id, stack_depth });
+        self.calls.insert(thread.to_owned(), calls);
+        self.next(thread, stack_depth)
+    }
+
+    /// A `finish` of the trace completed, returning to a function at `stack_depth`.
+    pub fn on_finished(
+        &mut self,
+        thread: &str,
+        stack_depth: u64,
+        return_value: Option<String>,
+    ) -> Resume {
+        let calls = self.calls.entry(thread.to_owned()).or_default();
+        let returned = calls
+            .last()
+            .filter(|c| c.stack_depth == stack_depth + 1)
+            .map(|c| c.id);
+        calls.retain(|c| c.stack_depth <= stack_depth);
+        let entry = returned
+            .and_then(|id| id.checked_sub(self.dropped))
+            .and_then(|i| self.entries.get_mut(i));
+        if let Some(entry) = entry {
+            entry.return_value = Some(return_value.unwrap_or_else(|| "void".to_owned()));
+        }
+        self.next(thread, stack_depth)
+    }
+
+    /// Whether the stop (reported by gdb's `*stopped` record) belongs to the trace, i.e., was
+    /// caused by the breakpoint of a traced function or a `finish` of the trace.
+    pub fn is_trace_stop(&self, results: &Object) -> bool {
+        let thread = results["thread-id"].as_str();
+        match results["reason"].as_str() {
+            Some("breakpoint-hit") => results["bkptno"]
+                .as_str()
+                .and_then(|n| n.parse::<usize>().ok())
+                .is_some_and(|n| self.functions.iter().any(|f| f.breakpoint == n)),
+            Some("function-finished") => thread.is_some() && thread == self.finishing.as_deref(),
+            _ => false,
+        }
+    }
+
+    /// Forget about outstanding calls, e.g., because the program stopped for another reason and the
+    /// user may resume it in any way.
+    pub fn interrupt(&mut self) {
+        self.finishing = None;
+    }
+
+    pub fn to_csv(&self) -> String {
+        // The argument names in the order in which they were first seen.
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in self.entries.iter().flat_map(|e| e.args.iter()) {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+        let mut csv = ["thread", "depth", "function"]
+            .iter()
+            .copied()
+            .chain(names.iter().copied())
+            .chain(iter::once("return_value"))
+            .map(csv_field)
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push('\n');
+        for entry in &self.entries {
+            let value = |name: &&str| {
+                entry
+                    .args
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, v)| v.as_str())
+                    .unwrap_or("")
+            };
+            let row = [
+                entry.thread.as_str(),
+                &entry.depth.to_string(),
+                &entry.function,
+            ]
+            .iter()
+            .copied()
+            .chain(names.iter().map(value))
+            .chain(iter::once(entry.return_value.as_deref().unwrap_or("")))
+            .map(csv_field)
+            .collect::<Vec<_>>();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        let entries = self
+            .entries
+            .iter()
+            .map(|e| {
+                let mut args = JsonValue::new_object();
+                for (name, value) in &e.args {
+                    args[name.as_str()] = value.as_str().into();
+                }
+                object! {
+                    "thread" => e.thread.as_str(),
+                    "depth" => e.depth,
+                    "function" => e.function.as_str(),
+                    "args" => args,
+                    "return_value" => e.return_value.as_deref(),
+                }
+            })
+            .collect::<Vec<_>>();
+        JsonValue::Array(entries).pretty(2)
+    }
+
+    /// Write the recorded calls to the file.
+    pub fn export(&self, path: &Path, format: ExportFormat) -> io::Result<()> {
+        fs::write(
+            path,
+            match format {
+                ExportFormat::Csv => self.to_csv(),
+                ExportFormat::Json => self.to_json(),
+            },
+        )
+    }
+}
+
+fn frame_args(frame: &JsonValue) -> Vec<(String, String)> {
+    frame["args"]
+        .members()
+        .map(|a| {
+            (
+                a["name"].as_str().unwrap_or("?").to_owned(),
+                a["value"].as_str().unwrap_or("...").to_owned(),
+            )
+        })
+        .collect()
+}
+
+/// Record the stop if it belongs to the trace and resume the program. Returns whether it did so
+/// (otherwise the stop is shown as usual).
+pub fn handle_stop(results: &Object, p: &mut ::Context) -> bool {
+    if !p.function_trace.is_trace_stop(results) {
+        p.function_trace.interrupt();
+        return false;
+    }
+    let thread = results["thread-id"].as_str().unwrap_or("").to_owned();
+    // The snapshot was queried when the program stopped.
+    let stack_depth = match p.gdb.snapshot.stack_depth {
+        Some(depth) => depth,
+        None => {
+            p.log("Could not trace call: The depth of the stack is unknown.");
+            p.function_trace.interrupt();
+            return false;
+        }
+    };
+    let resume = if results["reason"] == "function-finished" {
+        let return_value = results["return-value"].as_str().map(|v| v.to_owned());
+        p.function_trace
+            .on_finished(&thread, stack_depth, return_value)
+    } else {
+        let frame = &results["frame"];
+        let function = frame["func"].as_str().unwrap_or("??").to_owned();
+        let breakpoint = results["bkptno"]
+            .as_str()
+            .and_then(|n| n.parse::<usize>().ok())
+            .unwrap_or(0);
+        let captures = p.function_trace.captures(breakpoint).to_vec();
+        let args = if captures.is_empty() {
+            frame_args(frame)
+        } else {
+            match p.gdb.evaluate_many(&captures) {
+                Ok(values) => captures
+                    .into_iter()
+                    .zip(values)
+                    .map(|(expr, value)| (expr, value.unwrap_or_else(|e| format!("<{}>", e))))
+                    .collect(),
+                Err(e) => {
+                    p.log(format!("Could not capture expressions: {:?}", e));
+                    Vec::new()
+                }
+            }
+        };
+        p.function_trace
+            .on_entry(&thread, stack_depth, function, args)
+    };
+    p.publish(DebugEvent::TraceChanged);
+    let command = match resume {
+        Resume::Continue => MiCommand::exec_continue(),
+        Resume::Finish => MiCommand::exec_finish(),
+    };
+    if let Err(e) = p.gdb.execute_running(command) {
+        p.log(format!("Could not resume after tracing a call: {}", e));
+        p.function_trace.interrupt();
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trace(trace: &FunctionTrace) -> Vec<String> {
+        trace
+            .entries()
+            .map(|e| {
+                format!(
+                    "{}{}{}",
+                    "  ".repeat(e.depth),
+                    e.call(),
+                    e.return_value
+                        .as_ref()
+                        .map(|v| format!(" = {}", v))
+                        .unwrap_or_default()
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_calls() {
+        let mut t = FunctionTrace::new();
+        t.trace_returns = true;
+        let arg = |v: &str| vec![("n".to_owned(), v.to_owned())];
+        // main (depth 1) -> fib(2) -> fib(1), fib(0)
+        assert_eq!(t.on_entry("1", 2, "fib".into(), arg("2")), Resume::Finish);
+        assert_eq!(t.on_entry("1", 3, "fib".into(), arg("1")), Resume::Finish);
+        assert_eq!(t.on_finished("1", 2, Some("1".into())), Resume::Finish);
+        // Called through an untraced helper (depth 3) that is finished as well.
+        assert_eq!(t.on_entry("1", 4, "fib".into(), arg("0")), Resume::Finish);
+        assert_eq!(t.on_finished("1", 3, Some("0".into())), Resume::Finish);
+        assert_eq!(t.on_finished("1", 2, Some("0".into())), Resume::Finish);
+        assert_eq!(t.on_finished("1", 1, Some("1".into())), Resume::Continue);
+        assert_eq!(
+            trace(&t),
+            vec!["fib(n=2) = 1", "  fib(n=1) = 1", "  fib(n=0) = 0"]
+        );
+
+        // Without tracing returns, outstanding calls are only known to have returned once
+        // another call happens at the same depth.
+        t.clear();
+        t.trace_returns = false;
+        assert_eq!(t.on_entry("1", 2, "f".into(), Vec::new()), Resume::Continue);
+        assert_eq!(t.on_entry("1", 3, "g".into(), Vec::new()), Resume::Continue);
+        assert_eq!(t.on_entry("2", 2, "g".into(), Vec::new()), Resume::Continue);
+        assert_eq!(t.on_entry("1", 2, "f".into(), Vec::new()), Resume::Continue);
+        assert_eq!(trace(&t), vec!["f()", "  g()", "g()", "f()"]);
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = |s: &str| s.parse::<TraceSpec>();
+        assert_eq!(
+            spec("parse"),
+            Ok(TraceSpec {
+                function: "parse".to_owned(),
+                captures: Vec::new(),
+                condition: None,
+            })
+        );
+        let parsed = spec("parse capture s[0], max(a, b), \"x,y\" if len > 3").unwrap();
+        assert_eq!(parsed.captures, vec!["s[0]", "max(a, b)", "\"x,y\""]);
+        assert_eq!(parsed.condition.as_deref(), Some("len > 3"));
+        assert_eq!(
+            parsed.to_string(),
+            "parse capture s[0], max(a, b), \"x,y\" if len > 3"
+        );
+        assert_eq!(
+            spec("f if n == 0").unwrap().condition.as_deref(),
+            Some("n == 0")
+        );
+        assert_eq!(spec("f capture *p").unwrap().captures, vec!["*p"]);
+        assert!(spec("").is_err());
+        assert!(spec("f if").is_err());
+        assert!(spec("f capture a,, b").is_err());
+        assert!(spec("f g").is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        let mut t = FunctionTrace::new();
+        t.trace_returns = true;
+        t.on_entry("1", 2, "f".into(), vec![("s".into(), "0x1 \"a,b\"".into())]);
+        t.on_finished("1", 1, Some("1".into()));
+        t.on_entry("1", 2, "g".into(), vec![("n".into(), "3".into())]);
+        assert_eq!(
+            t.to_csv(),
+            "thread,depth,function,s,n,return_value\n\
+             1,0,f,\"0x1 \"\"a,b\"\"\",,1\n\
+             1,0,g,,3,\n"
+        );
+        let json = ::json::parse(&t.to_json()).unwrap();
+        assert_eq!(json.len(), 2);
+        assert_eq!(json[0]["args"]["s"], "0x1 \"a,b\"");
+        assert_eq!(json[0]["return_value"], "1");
+        assert_eq!(json[1]["depth"], 0);
+        assert!(json[1]["return_value"].is_null());
+        assert_eq!(
+            ExportFormat::from_path(Path::new("trace.json")),
+            Some(ExportFormat::Json)
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("trace.txt")), None);
+    }
+}
@@ -1,16 +1,26 @@
 // This module encapsulates some functionality of gdb. Depending on how general this turns out, we
 // may want to move it to a separate crate or merge it with gdbmi-rs
 use gdbmi;
-use gdbmi::commands::{BreakPointLocation, BreakPointNumber, MiCommand};
-use gdbmi::output::{BreakPointEvent, JsonValue, Object, ResultClass};
+use gdbmi::capabilities::{Backend, Feature};
+use gdbmi::commands::{
+    BreakPointLocation, BreakPointNumber, DisassembleMode, MiCommand, PrintValues,
+};
+use gdbmi::output::{
+    AsyncClass, AsyncKind, JsonValue, Object, OutOfBandRecord, ResultClass, ResultRecord,
+    ThreadEvent,
+};
+use gdbmi::types::{self, Notification};
 use gdbmi::ExecuteError;
+use log::warn;
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt;
 use std::ops::{Add, Sub};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use unsegen::base::LineNumber;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SrcPosition {
     pub file: PathBuf,
     pub line: LineNumber,
@@ -53,260 +63,2471 @@ impl Sub<usize> for Address {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineTableEntry {
+    pub line: LineNumber,
+    pub begin: Address,
+    pub end: Address,          // exclusive
+    pub is_stmt: Option<bool>, // Not reported by gdb < 10
+}
+
+#[derive(Debug)]
+pub struct FunctionLineTable {
+    pub function: String,
+    pub file: PathBuf,
+    pub entries: Vec<LineTableEntry>,
+}
+
+impl FunctionLineTable {
+    /// Number of lines whose code is split into multiple non-contiguous address ranges.
+    pub fn num_split_lines(&self) -> usize {
+        let mut ranges_by_line = HashMap::new();
+        for e in &self.entries {
+            ranges_by_line
+                .entry(e.line.raw_value())
+                .or_insert_with(Vec::new)
+                .push((e.begin, e.end));
+        }
+        ranges_by_line
+            .values_mut()
+            .map(|ranges| {
+                ranges.sort();
+                ranges.windows(2).any(|w| w[0].1 != w[1].0)
+            })
+            .filter(|&split| split)
+            .count()
+    }
+
+    /// The last line of the file that has code of the function.
+    pub fn last_line(&self) -> Option<LineNumber> {
+        self.entries
+            .iter()
+            .map(|e| e.line)
+            .max_by_key(|l| l.raw_value())
+    }
+}
+
+// Parse the output of "maint info line-table", which consists of one table per symtab with rows
+// "INDEX LINE ADDRESS [IS-STMT] [...]". The address range of an entry ends at the address of the
+// next row. Rows with line "END" mark the end of a sequence.
+fn parse_line_table(output: &str) -> Vec<LineTableEntry> {
+    let mut entries = Vec::new();
+    let mut is_stmt_column = None;
+    let mut previous: Option<(Option<LineNumber>, Address, Option<bool>)> = None;
+    for row in output.lines() {
+        if row.starts_with("INDEX") {
+            is_stmt_column = row.find("IS-STMT");
+            previous = None;
+            continue;
+        }
+        let mut columns = row.split_whitespace();
+        if columns
+            .next()
+            .map(|index| index.parse::<usize>().is_err())
+            .unwrap_or(true)
+        {
+            previous = None;
+            continue;
+        }
+        let line = match columns.next() {
+            Some("END") => None,
+            Some(l) => match l.parse::<usize>() {
+                Ok(l) if l > 0 => Some(LineNumber::new(l)),
+                _ => continue,
+            },
+            None => continue,
+        };
+        let address = match columns.next().map(Address::parse) {
+            Some(Ok(a)) => a,
+            _ => continue,
+        };
+        let is_stmt = is_stmt_column.map(|c| row.get(c..c + 1).map(|s| s == "Y").unwrap_or(false));
+        if let Some((Some(line), begin, is_stmt)) = previous {
+            entries.push(LineTableEntry {
+                line,
+                begin,
+                end: address,
+                is_stmt,
+            });
+        }
+        previous = Some((line, address, is_stmt));
+    }
+    entries
+}
+
+// "maint info line-table" and "sharedlibrary" interpret their argument as a regular expression.
+fn escape_regex(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A mapped region of the address space of the inferior (see `info proc mappings`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryRegion {
+    pub begin: Address,
+    pub end: Address, // exclusive
+    pub offset: usize,
+    pub perms: Option<String>, // Not reported by gdb < 12
+    pub objfile: String,       // Empty for anonymous mappings
+}
+
+impl MemoryRegion {
+    pub fn contains(&self, address: Address) -> bool {
+        self.begin <= address && address < self.end
+    }
+}
+
+// Parse the output of "info proc mappings", i.e., a header "Start Addr End Addr Size Offset [Perms]
+// objfile" followed by one row per region.
+fn parse_proc_mappings(output: &str) -> Vec<MemoryRegion> {
+    let mut has_perms = false;
+    let mut regions = Vec::new();
+    for row in output.lines() {
+        if row.contains("Start Addr") {
+            has_perms = row.contains("Perms");
+            continue;
+        }
+        let columns = row.split_whitespace().collect::<Vec<_>>();
+        let num_fixed = if has_perms { 5 } else { 4 };
+        if columns.len() < num_fixed || !columns[0].starts_with("0x") {
+            continue;
+        }
+        let (begin, end, offset) = match (
+            Address::parse(columns[0]),
+            Address::parse(columns[1]),
+            Address::parse(columns[3]),
+        ) {
+            (Ok(begin), Ok(end), Ok(offset)) => (begin, end, offset),
+            _ => continue,
+        };
+        regions.push(MemoryRegion {
+            begin,
+            end,
+            offset: offset.0,
+            perms: if has_perms {
+                Some(columns[4].to_owned())
+            } else {
+                None
+            },
+            objfile: columns[num_fixed..].join(" "),
+        });
+    }
+    regions
+}
+
+/// Where the selected frame is on the stack.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameLayout {
+    pub sp: Address,
+    /// The canonical frame address, i.e., the value of the stack pointer in the caller before the
+    /// call ("frame at" in `info frame`).
+    pub cfa: Option<Address>,
+    /// Registers of the caller (including the return address, e.g., "rip") saved in the frame.
+    pub saved_registers: Vec<(String, Address)>,
+}
+
+// Parse the frame address and the saved registers from the output of "info frame", e.g.:
+// Stack level 0, frame at 0x7fffffffe100:
+//  rip = 0x555555555131 in main (main.c:3); saved rip = 0x7ffff7df0d90
+//  ...
+//  Saved registers:
+//   rbp at 0x7fffffffe0f0, rip at 0x7fffffffe0f8
+fn parse_info_frame(output: &str) -> (Option<Address>, Vec<(String, Address)>) {
+    let cfa = output.lines().next().and_then(|line| {
+        let rest = &line[line.find("frame at ")? + "frame at ".len()..];
+        Address::parse(rest.trim_end_matches(':').trim()).ok()
+    });
+    let saved_registers = output
+        .lines()
+        .skip_while(|line| !line.trim().starts_with("Saved registers:"))
+        .skip(1)
+        .flat_map(|line| line.split(','))
+        .filter_map(|entry| {
+            let (register, address) = entry.trim().split_once(" at ")?;
+            Some((register.to_owned(), Address::parse(address.trim()).ok()?))
+        })
+        .collect();
+    (cfa, saved_registers)
+}
+
+// Parse the output of "info auto-load local-gdbinit", e.g., 'Local .gdbinit file "/src/.gdbinit"
+// has been loaded.' (or "... has not been loaded." or "Local .gdbinit file was not found.").
+fn parse_local_gdbinit(output: &str) -> Option<PathBuf> {
+    let output = output.trim();
+    if !output.ends_with("has been loaded.") {
+        return None;
+    }
+    let begin = output.find('"')? + 1;
+    let end = output.rfind('"')?;
+    (end > begin).then(|| PathBuf::from(&output[begin..end]))
+}
+
+// Maximum number of matches of a memory search (per region).
+const MAX_MEMORY_MATCHES: usize = 1000;
+
+// The size and values that `find` searches for: A string literal (`"abc"`) as it is, a sequence of
+// hex bytes (`de ad be ef` or `0xde 0xad`) byte by byte and anything else as an expression whose
+// value (e.g., `(int)1234`) is searched for.
+fn find_values(pattern: &str) -> (&'static str, String) {
+    let pattern = pattern.trim();
+    let bytes = pattern
+        .split_whitespace()
+        .map(|b| b.strip_prefix("0x").unwrap_or(b))
+        .collect::<Vec<_>>();
+    let is_byte = |b: &&str| (1..=2).contains(&b.len()) && b.chars().all(|c| c.is_ascii_hexdigit());
+    if !pattern.starts_with('"') && bytes.len() > 1 && bytes.iter().all(is_byte) {
+        let values = bytes
+            .iter()
+            .map(|b| format!("0x{}", b))
+            .collect::<Vec<_>>()
+            .join(", ");
+        ("b", values)
+    } else {
+        ("", pattern.to_owned())
+    }
+}
+
+// Parse the output of "find": one line per match with its address and symbol (if any), e.g.,
+// "0x601040 <buf+16>", followed by the number of matches.
+fn parse_find_output(output: &str) -> Vec<(Address, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (address, symbol) = match line.find(' ') {
+                Some(i) => (&line[..i], line[i..].trim()),
+                None => (line, ""),
+            };
+            if !address.starts_with("0x") {
+                return None;
+            }
+            Some((Address::parse(address).ok()?, symbol.to_owned()))
+        })
+        .collect()
+}
+
+/// What the pattern of a skip matches (see gdb's `skip` command).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SkipKind {
+    Function,
+    FunctionRegex,
+    File,
+    FileGlob,
+}
+
+impl SkipKind {
+    fn option(self) -> &'static str {
+        match self {
+            SkipKind::Function => "-function",
+            SkipKind::FunctionRegex => "-rfunction",
+            SkipKind::File => "-file",
+            SkipKind::FileGlob => "-gfile",
+        }
+    }
+}
+
+impl fmt::Display for SkipKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SkipKind::Function => "function",
+            SkipKind::FunctionRegex => "function-regex",
+            SkipKind::File => "file",
+            SkipKind::FileGlob => "file-glob",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for SkipKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "function" => Ok(SkipKind::Function),
+            "function-regex" => Ok(SkipKind::FunctionRegex),
+            "file" => Ok(SkipKind::File),
+            "file-glob" => Ok(SkipKind::FileGlob),
+            o => Err(format!("Unknown kind of skip '{}'", o)),
+        }
+    }
+}
+
+/// A function or file that `step` does not descend into.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Skip {
+    pub number: usize, // Assigned by gdb, irrelevant for saved skips
+    pub enabled: bool,
+    pub kind: SkipKind,
+    pub pattern: String,
+}
+
+// The libraries whose symbols were read but that have no debug information according to the output
+// of "info sharedlibrary", i.e., the ones marked with "(*)":
+// "0x00007ffff7daf700  0x00007ffff7f4193d  Yes (*)     /lib/x86_64-linux-gnu/libc.so.6"
+fn parse_libraries_without_debug_info(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once("Yes (*)"))
+        .map(|(_, name)| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+// Parse the output of "maint print target-stack", which lists the targets from the top, e.g.,
+// "  - native (Native process)" followed by "  - exec (Local exec file)" and "  - None (None)".
+fn is_native_target_stack(output: &str) -> bool {
+    output.lines().any(|line| {
+        line.trim_start()
+            .strip_prefix("- ")
+            .is_some_and(|target| target.starts_with("native "))
+    })
+}
+
+// Parse the output of "info skip", i.e., a header "Num Enb Glob File RE Function" followed by one
+// row per skip. Skips that match a file and a function at once (which can only be created in the
+// console) are listed by their function.
+fn parse_skips(output: &str) -> Vec<Skip> {
+    output
+        .lines()
+        .filter_map(|row| {
+            let columns = row.split_whitespace().collect::<Vec<_>>();
+            if columns.len() < 6 {
+                return None;
+            }
+            let number = columns[0].parse::<usize>().ok()?;
+            let (file, function) = (columns[3], columns[5..].join(" "));
+            let (kind, pattern) = match (columns[2], columns[4]) {
+                (_, "y") => (SkipKind::FunctionRegex, function),
+                (_, _) if function != "<none>" => (SkipKind::Function, function),
+                ("y", _) => (SkipKind::FileGlob, file.to_owned()),
+                (_, _) => (SkipKind::File, file.to_owned()),
+            };
+            Some(Skip {
+                number,
+                enabled: columns[1] == "y",
+                kind,
+                pattern,
+            })
+        })
+        .collect()
+}
+
+// Arguments of gdb's commands are split like in a shell, so patterns are quoted.
+fn quote_argument(argument: &str) -> String {
+    format!(
+        "\"{}\"",
+        argument.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+// File names are only quoted if necessary, because older versions of gdb do not support quotes in
+// the file names of `dump` and `restore`.
+fn file_argument(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if path.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'') {
+        quote_argument(&path)
+    } else {
+        path.into_owned()
+    }
+}
+
+/// Settings of gdb that change how values are printed (and thus shown in the expression table).
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintSettings {
+    pub pretty: bool,
+    pub elements: Option<u32>, // None: unlimited
+    pub array: bool,
+    pub null_stop: bool,
+}
+
+// Values cycled through when changing the maximum number of printed elements.
+const PRINT_ELEMENTS_STEPS: &[Option<u32>] = &[Some(200), Some(1000), None, Some(20)];
+
+impl PrintSettings {
+    pub fn next_elements(&self) -> Option<u32> {
+        let i = PRINT_ELEMENTS_STEPS
+            .iter()
+            .position(|&e| e == self.elements)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        PRINT_ELEMENTS_STEPS[i % PRINT_ELEMENTS_STEPS.len()]
+    }
+}
+
+// Limits of the children of variable objects that are listed when evaluating an expression using
+// pretty printers (which may, e.g., produce the elements of a linked list forever).
+const VAR_MAX_DEPTH: usize = 6;
+const VAR_MAX_CHILDREN: u64 = 200;
+
+/// A variable object and its children (which are synthesized by pretty printers for dynamic
+/// variable objects).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VarTree {
+    pub exp: String,
+    pub value: Option<String>,
+    pub display_hint: Option<String>,
+    pub children: Vec<VarTree>,
+    pub has_more: bool, // Children were omitted
+}
+
+// Values of variable objects that only indicate that there are children, e.g., `{...}` for
+// structs and `[3]` for arrays.
+fn is_placeholder(value: &str) -> bool {
+    value == "{...}" || (value.starts_with('[') && value.ends_with(']'))
+}
+
+impl VarTree {
+    // Access specifiers of C++ classes are reported as pseudo children without a type.
+    fn is_access_specifier(&self) -> bool {
+        self.value.is_none() && ["public", "private", "protected"].contains(&self.exp.as_str())
+    }
+
+    fn members(&self) -> Vec<&VarTree> {
+        self.children
+            .iter()
+            .flat_map(|c| {
+                if c.is_access_specifier() {
+                    c.children.iter().collect()
+                } else {
+                    vec![c]
+                }
+            })
+            .collect()
+    }
+
+    /// The value in the syntax of `print`, e.g., `std::vector of length 2, capacity 2 = {1, 2}`,
+    /// so that it can be parsed like the result of evaluating an expression.
+    pub fn to_gdb_value(&self) -> String {
+        let value = self.value.as_deref().unwrap_or("");
+        if self.children.is_empty() {
+            return value.to_owned();
+        }
+        let members = self.members();
+        let mut items = match self.display_hint.as_deref() {
+            Some("map") => members
+                .chunks(2)
+                .map(|pair| match pair {
+                    [key, value] => format!("[{}] = {}", key.to_gdb_value(), value.to_gdb_value()),
+                    [key] => format!("[{}]", key.to_gdb_value()),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>(),
+            Some("array") => members.iter().map(|c| c.to_gdb_value()).collect(),
+            _ if members.iter().all(|c| c.exp.parse::<u64>().is_ok()) => {
+                members.iter().map(|c| c.to_gdb_value()).collect()
+            }
+            _ => members
+                .iter()
+                .map(|c| format!("{} = {}", c.exp, c.to_gdb_value()))
+                .collect(),
+        };
+        if self.has_more {
+            items.push("...".to_owned());
+        }
+        let children = format!("{{{}}}", items.join(", "));
+        if value.is_empty() || is_placeholder(value) {
+            children
+        } else {
+            format!("{} = {}", value, children)
+        }
+    }
+}
+
+/// Syntax of disassembled instructions (`set disassembly-flavor`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisassemblyFlavor {
+    Att,
+    Intel,
+}
+
+impl DisassemblyFlavor {
+    pub fn toggled(self) -> Self {
+        match self {
+            DisassemblyFlavor::Att => DisassemblyFlavor::Intel,
+            DisassemblyFlavor::Intel => DisassemblyFlavor::Att,
+        }
+    }
+}
+
+impl FromStr for DisassemblyFlavor {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "att" => Ok(DisassemblyFlavor::Att),
+            "intel" => Ok(DisassemblyFlavor::Intel),
+            o => Err(format!(
+                "Unknown disassembly flavor '{}'. Supported: att, intel",
+                o
+            )),
+        }
+    }
+}
+
+impl fmt::Display for DisassemblyFlavor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DisassemblyFlavor::Att => write!(f, "att"),
+            DisassemblyFlavor::Intel => write!(f, "intel"),
+        }
+    }
+}
+
+fn on_off(b: bool) -> &'static str {
+    if b {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+impl fmt::Display for PrintSettings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pretty: {}, elements: ", on_off(self.pretty))?;
+        match self.elements {
+            Some(n) => write!(f, "{}", n)?,
+            None => write!(f, "unlimited")?,
+        }
+        write!(
+            f,
+            ", array: {}, null-stop: {}",
+            on_off(self.array),
+            on_off(self.null_stop)
+        )
+    }
+}
+
 pub struct BreakPoint {
     pub number: BreakPointNumber,
     pub address: Option<Address>,
     pub enabled: bool,
     pub src_pos: Option<SrcPosition>, // May not be present if debug information is missing!
+    /// How often the breakpoint was hit. Locations of a breakpoint share its count.
+    pub hit_count: u64,
+    /// How many of the next hits are ignored.
+    pub ignore_count: u64,
+    /// Commands that gdb executes when the breakpoint is hit. Shared by its locations.
+    pub commands: Vec<String>,
+    /// Only stop if this expression is true. Shared by its locations.
+    pub condition: Option<String>,
+}
+
+impl<'a> From<&'a types::Breakpoint> for BreakPoint {
+    fn from(bkpt: &'a types::Breakpoint) -> Self {
+        BreakPoint {
+            number: bkpt
+                .number
+                .parse::<BreakPointNumber>()
+                .expect("Parse breakpoint number"),
+            address: bkpt.addr.map(Address),
+            enabled: bkpt.enabled,
+            src_pos: match (&bkpt.fullname, bkpt.line) {
+                (Some(file), Some(line)) => Some(SrcPosition::new(
+                    file.clone(),
+                    LineNumber::new(line as usize),
+                )),
+                _ => None,
+            },
+            hit_count: bkpt.times.unwrap_or(0),
+            ignore_count: bkpt.ignore.unwrap_or(0),
+            commands: bkpt.script.clone(),
+            condition: bkpt.condition.clone(),
+        }
+    }
+}
+
+/// What is needed to insert a breakpoint again, e.g., into a new gdb process after the old one
+/// crashed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedBreakPoint {
+    pub src_pos: Option<SrcPosition>,
+    pub address: Option<Address>,
+    pub enabled: bool,
+    pub condition: Option<String>,
+    pub ignore_count: u64,
+    pub commands: Vec<String>,
+}
+
+pub struct BreakPointSet {
+    map: HashMap<BreakPointNumber, BreakPoint>,
+    pub last_change: ::std::time::Instant,
+    /// Majors of the breakpoints of traced functions (see `function_trace`). They are not saved,
+    /// because the trace inserts them again.
+    pub traced: HashSet<usize>,
+}
+
+impl BreakPointSet {
+    pub fn new() -> Self {
+        BreakPointSet {
+            map: HashMap::new(),
+            last_change: ::std::time::Instant::now(),
+            traced: HashSet::new(),
+        }
+    }
+
+    fn notify_change(&mut self) {
+        self.last_change = ::std::time::Instant::now();
+    }
+
+    pub fn update_breakpoint(&mut self, new_bp: BreakPoint) {
+        let _ = self.map.insert(new_bp.number, new_bp);
+        //debug_assert!(res.is_some(), "Modified non-existent breakpoint");
+        self.notify_change();
+    }
+
+    pub fn remove_breakpoint(&mut self, bp_num: BreakPointNumber) {
+        self.map.remove(&bp_num);
+        if bp_num.minor.is_none() {
+            //TODO: ensure removal of child breakpoints
+        }
+        self.notify_change();
+    }
+
+    /// Save all breakpoints in the order of their numbers. Breakpoints with multiple locations are
+    /// saved using their first location, breakpoints without any known location are skipped.
+    pub fn save(&self) -> Vec<SavedBreakPoint> {
+        let mut majors = self
+            .map
+            .keys()
+            .map(|n| n.major)
+            .filter(|major| !self.traced.contains(major))
+            .collect::<Vec<_>>();
+        majors.sort();
+        majors.dedup();
+        majors
+            .into_iter()
+            .filter_map(|major| {
+                let mut bps = self
+                    .map
+                    .values()
+                    .filter(|bp| bp.number.major == major)
+                    .collect::<Vec<_>>();
+                bps.sort_by_key(|bp| bp.number.minor);
+                let enabled = bps[0].enabled;
+                let condition = bps[0].condition.clone();
+                let ignore_count = bps[0].ignore_count;
+                let commands = bps[0].commands.clone();
+                bps.into_iter()
+                    .find(|bp| bp.src_pos.is_some() || bp.address.is_some())
+                    .map(|bp| SavedBreakPoint {
+                        src_pos: bp.src_pos.clone(),
+                        address: bp.address,
+                        enabled,
+                        condition,
+                        ignore_count,
+                        commands,
+                    })
+            })
+            .collect()
+    }
+}
+
+impl ::std::ops::Deref for BreakPointSet {
+    type Target = HashMap<BreakPointNumber, BreakPoint>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InferiorState {
+    NotStarted,
+    Running { pid: String },
+    Exited { exit_code: Option<String> },
+}
+
+impl fmt::Display for InferiorState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InferiorState::NotStarted => write!(f, "not started"),
+            InferiorState::Running { pid } => write!(f, "process {}", pid),
+            InferiorState::Exited {
+                exit_code: Some(code),
+            } => write!(f, "exited with code {}", code),
+            InferiorState::Exited { exit_code: None } => write!(f, "exited"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inferior {
+    /// Id of the thread group as used by gdb/mi, e.g., "i1".
+    pub id: String,
+    pub state: InferiorState,
+}
+
+impl Inferior {
+    /// The number used to refer to the inferior in cli commands (e.g., `inferior 2`).
+    pub fn number(&self) -> &str {
+        self.id.trim_start_matches('i')
+    }
+}
+
+/// All inferiors (thread groups) of the session as reported by thread-group notifications, as well
+/// as the mapping of threads to inferiors.
+pub struct InferiorSet {
+    inferiors: Vec<Inferior>,
+    thread_groups: HashMap<String, String>,
+    selected: Option<String>,
+    pub last_change: ::std::time::Instant,
+}
+
+impl Default for InferiorSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InferiorSet {
+    pub fn new() -> Self {
+        InferiorSet {
+            inferiors: Vec::new(),
+            thread_groups: HashMap::new(),
+            selected: None,
+            last_change: ::std::time::Instant::now(),
+        }
+    }
+
+    fn notify_change(&mut self) {
+        self.last_change = ::std::time::Instant::now();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Inferior> {
+        self.inferiors.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inferiors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inferiors.is_empty()
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.selected.as_deref()
+    }
+
+    pub fn group_of_thread(&self, thread_id: &str) -> Option<&str> {
+        self.thread_groups.get(thread_id).map(|s| s.as_str())
+    }
+
+    fn select(&mut self, group_id: &str) {
+        if self.selected() != Some(group_id) {
+            self.selected = Some(group_id.to_owned());
+            self.notify_change();
+        }
+    }
+
+    fn set_state(&mut self, group_id: &str, state: InferiorState) {
+        match self.inferiors.iter_mut().find(|i| i.id == group_id) {
+            Some(inferior) => inferior.state = state,
+            None => self.inferiors.push(Inferior {
+                id: group_id.to_owned(),
+                state,
+            }),
+        }
+        self.notify_change();
+    }
+
+    /// Replace the inferiors and the mapping of threads to inferiors by the ones that gdb lists
+    /// (`-list-thread-groups --recurse 1`), e.g., for inferiors that were started before ugdb was
+    /// notified of them.
+    pub fn sync(&mut self, groups: &[types::ThreadGroup]) {
+        self.inferiors = groups
+            .iter()
+            .map(|g| Inferior {
+                id: g.id.clone(),
+                state: match (&g.pid, &g.exit_code) {
+                    (Some(pid), _) => InferiorState::Running { pid: pid.clone() },
+                    (None, Some(code)) => InferiorState::Exited {
+                        exit_code: Some(code.clone()),
+                    },
+                    (None, None) => InferiorState::NotStarted,
+                },
+            })
+            .collect();
+        self.thread_groups = groups
+            .iter()
+            .flat_map(|g| g.threads.iter().map(move |t| (t.id.clone(), g.id.clone())))
+            .collect();
+        if !self
+            .inferiors
+            .iter()
+            .any(|i| Some(i.id.as_str()) == self.selected())
+        {
+            self.selected = self.inferiors.first().map(|i| i.id.clone());
+        }
+        self.notify_change();
+    }
+
+    /// Update the inferiors according to thread and thread group notifications. Other
+    /// notifications are ignored.
+    pub fn handle_notification(&mut self, notification: &Notification) {
+        match notification {
+            Notification::ThreadGroupAdded { id } => {
+                self.set_state(id, InferiorState::NotStarted);
+                // gdb starts with a single inferior which is selected implicitly.
+                if self.selected.is_none() {
+                    self.select(id);
+                }
+            }
+            Notification::ThreadGroupStarted { id, pid } => {
+                self.set_state(id, InferiorState::Running { pid: pid.clone() });
+            }
+            Notification::ThreadGroupExited { id, exit_code } => {
+                self.set_state(
+                    id,
+                    InferiorState::Exited {
+                        exit_code: exit_code.clone(),
+                    },
+                );
+                self.thread_groups.retain(|_, group| group != id);
+            }
+            Notification::ThreadGroupRemoved { id } => {
+                self.inferiors.retain(|i| &i.id != id);
+                if self.selected() == Some(id) {
+                    self.selected = None;
+                }
+                self.notify_change();
+            }
+            Notification::ThreadCreated { id, group_id } => {
+                self.thread_groups.insert(id.clone(), group_id.clone());
+            }
+            Notification::ThreadExited { id, .. } => {
+                self.thread_groups.remove(id);
+            }
+            Notification::ThreadSelected { id, .. } => {
+                if let Some(group) = self.group_of_thread(id).map(|g| g.to_owned()) {
+                    self.select(&group);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Deep recursions should not make every stop slow.
+pub const MAX_BACKTRACE_FRAMES: u64 = 1000;
+
+type Threads = (Vec<types::Thread>, Option<String>);
+
+// The results of a command, or the error that gdb reported instead.
+fn done(record: ResultRecord) -> Result<Object, response::GDBResponseError> {
+    match record.class {
+        ResultClass::Error => Err(response::GDBResponseError::Other(
+            record.results["msg"]
+                .as_str()
+                .unwrap_or("Unknown error")
+                .to_owned(),
+        )),
+        _ => Ok(record.results),
+    }
+}
+
+// The results of `-thread-info`.
+fn parse_threads(results: &Object) -> Result<Threads, response::GDBResponseError> {
+    let threads = results["threads"]
+        .members()
+        .map(types::Thread::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    let current = results["current-thread-id"]
+        .as_str()
+        .map(|id| id.to_owned());
+    Ok((threads, current))
+}
+
+// The results of `-stack-list-frames`.
+fn parse_backtrace(results: &Object) -> Result<Vec<types::Frame>, response::GDBResponseError> {
+    Ok(results["stack"]
+        .members()
+        .map(types::Frame::try_from)
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+// The results of `-data-list-register-names` and `-data-list-register-values`. Registers without
+// a name (i.e., unused numbers) or value (unavailable ones) are left out.
+fn parse_registers(
+    names: &Object,
+    values: &Object,
+) -> Result<Vec<(String, String)>, response::GDBResponseError> {
+    let names = names["register-names"]
+        .members()
+        .map(|name| name.as_str().unwrap_or(""))
+        .collect::<Vec<_>>();
+    values["register-values"]
+        .members()
+        .filter_map(|register| {
+            let number = match response::get_u64(register, "number") {
+                Ok(number) => number as usize,
+                Err(e) => return Some(Err(e)),
+            };
+            let name = names.get(number).filter(|name| !name.is_empty())?;
+            let value = register["value"].as_str()?;
+            Some(Ok((name.to_string(), value.to_owned())))
+        })
+        .collect()
 }
 
-impl BreakPoint {
-    pub fn from_json(bkpt: &Object) -> Self {
-        let number = bkpt["number"]
-            .as_str()
-            .expect("find bp number")
-            .parse::<BreakPointNumber>()
-            .expect("Parse usize");
-        let enabled = bkpt["enabled"].as_str().expect("find enabled") == "y";
-        let address = bkpt["addr"]
-            .as_str()
-            .and_then(|addr| Address::parse(addr).ok()); //addr may not be present or contain
-        let src_pos = {
-            let maybe_file = bkpt["fullname"].as_str();
-            let maybe_line = bkpt["line"]
-                .as_str()
-                .map(|l_nr| LineNumber::new(l_nr.parse::<usize>().expect("Parse usize")));
-            if let (Some(file), Some(line)) = (maybe_file, maybe_line) {
-                Some(SrcPosition::new(PathBuf::from(file), line))
-            } else {
-                None
-            }
-        };
-        BreakPoint {
-            number: number,
-            address: address,
-            enabled: enabled,
-            src_pos: src_pos,
+/// The parts of the snapshot that are only queried if something shows them (e.g., the backtrace
+/// container), see `GDB::complete_snapshot`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SnapshotParts {
+    pub backtrace: bool,
+    pub threads: bool,
+    pub registers: bool,
+}
+
+impl SnapshotParts {
+    pub fn is_empty(&self) -> bool {
+        *self == SnapshotParts::default()
+    }
+
+    /// The parts that are in `self`, but not in `other`.
+    pub fn without(&self, other: &SnapshotParts) -> SnapshotParts {
+        SnapshotParts {
+            backtrace: self.backtrace && !other.backtrace,
+            threads: self.threads && !other.threads,
+            registers: self.registers && !other.registers,
+        }
+    }
+}
+
+/// State of the program at the last stop (or the last selection of a thread or frame). Containers
+/// read it instead of asking gdb themselves, so that handling input does not have to wait for
+/// gdb. The frame is queried by the output thread of gdbmi as soon as the program stops (see
+/// `queries`), so the ui does not wait for gdb either. The other parts are only queried if they
+/// are shown.
+pub struct Snapshot {
+    /// The current frame, if gdb reported one.
+    pub frame: Option<types::Frame>,
+    pub stack_level: Option<u64>,
+    pub stack_depth: Option<u64>,
+    /// All threads and the id of the current one.
+    pub threads: Result<Threads, response::GDBResponseError>,
+    /// The (at most `MAX_BACKTRACE_FRAMES`) innermost frames of the current thread.
+    pub backtrace: Result<Vec<types::Frame>, response::GDBResponseError>,
+    /// The names and (hex) values of the registers in the selected frame.
+    pub registers: Result<Vec<(String, String)>, response::GDBResponseError>,
+    // The parts that were queried since the last stop.
+    parts: SnapshotParts,
+    // The thread of the last stop or selection.
+    thread_id: Option<String>,
+}
+
+impl Snapshot {
+    fn new() -> Self {
+        Snapshot {
+            frame: None,
+            stack_level: None,
+            stack_depth: None,
+            threads: Ok((Vec::new(), None)),
+            backtrace: Ok(Vec::new()),
+            registers: Ok(Vec::new()),
+            parts: SnapshotParts::default(),
+            thread_id: None,
+        }
+    }
+
+    // Whether the record reports a stop or the selection of another thread or frame.
+    fn selects_frame(record: &OutOfBandRecord) -> bool {
+        matches!(
+            record,
+            OutOfBandRecord::AsyncRecord {
+                class: AsyncClass::Stopped | AsyncClass::Thread(ThreadEvent::Selected),
+                ..
+            }
+        )
+    }
+
+    /// What to query when the record arrives (see `gdbmi::OutOfBandRecordSink::queries`).
+    pub fn queries(record: &OutOfBandRecord, is_running: bool) -> Vec<MiCommand> {
+        match record {
+            _ if Self::selects_frame(record) => vec![
+                MiCommand::stack_info_frame(None),
+                MiCommand::stack_info_depth(),
+            ],
+            // Gdb cannot be asked for the threads while the program is running.
+            OutOfBandRecord::AsyncRecord {
+                class: AsyncClass::Thread(ThreadEvent::Created | ThreadEvent::Exited),
+                ..
+            } if !is_running => vec![MiCommand::thread_info(None)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Update the snapshot using the results of the `queries` for the record. The other parts are
+    /// outdated after a stop, so they have to be queried again (see `part_queries`). Selecting
+    /// another frame only changes the registers, selecting another thread also the backtrace.
+    pub fn update(&mut self, record: &OutOfBandRecord, results: Vec<ResultRecord>) {
+        let mut results = results.into_iter();
+        if !Self::selects_frame(record) {
+            self.threads = next_result(&mut results).and_then(|r| parse_threads(&r));
+            return;
+        }
+        let (stopped, thread_id) = match record {
+            OutOfBandRecord::AsyncRecord { class, results, .. } => {
+                self.frame = results
+                    .get("frame")
+                    .and_then(|frame| types::Frame::try_from(frame).ok());
+                let thread_id = results.get("thread-id").or_else(|| results.get("id"));
+                (
+                    *class == AsyncClass::Stopped,
+                    thread_id.and_then(|id| id.as_str()).map(str::to_owned),
+                )
+            }
+            OutOfBandRecord::StreamRecord { .. } => (true, None),
+        };
+        self.stack_level = next_result(&mut results)
+            .and_then(|r| response::get_u64(&r["frame"], "level"))
+            .ok();
+        self.stack_depth = next_result(&mut results)
+            .and_then(|r| response::get_u64_obj(&r, "depth"))
+            .ok();
+        let outdated = response::GDBResponseError::Other("Not queried".to_owned());
+        let same_thread = thread_id.is_some() && thread_id == self.thread_id;
+        if stopped {
+            self.threads = Err(outdated.clone());
+            self.parts.threads = false;
+        } else if let Ok((_, current)) = &mut self.threads {
+            current.clone_from(&thread_id);
+        }
+        if stopped || !same_thread {
+            self.backtrace = Err(outdated.clone());
+            self.parts.backtrace = false;
+        }
+        self.registers = Err(outdated);
+        self.parts.registers = false;
+        self.thread_id = thread_id;
+    }
+
+    /// The commands that query the parts.
+    pub fn part_queries(parts: &SnapshotParts) -> Vec<MiCommand> {
+        let mut queries = Vec::new();
+        if parts.backtrace {
+            queries.push(MiCommand::stack_list_frames(0, MAX_BACKTRACE_FRAMES - 1));
+        }
+        if parts.threads {
+            queries.push(MiCommand::thread_info(None));
+        }
+        if parts.registers {
+            queries.push(MiCommand::data_list_register_names());
+            queries.push(MiCommand::data_list_register_values());
+        }
+        queries
+    }
+
+    /// Update the parts using the results of their `part_queries`.
+    pub fn update_parts(&mut self, parts: &SnapshotParts, results: Vec<ResultRecord>) {
+        let mut results = results.into_iter();
+        if parts.backtrace {
+            self.backtrace = next_result(&mut results).and_then(|r| parse_backtrace(&r));
+            self.parts.backtrace = true;
+        }
+        if parts.threads {
+            self.threads = next_result(&mut results).and_then(|r| parse_threads(&r));
+            self.parts.threads = true;
+        }
+        if parts.registers {
+            let names = next_result(&mut results);
+            let values = next_result(&mut results);
+            self.registers = names.and_then(|names| parse_registers(&names, &values?));
+            self.parts.registers = true;
+        }
+    }
+}
+
+fn next_result(
+    results: &mut impl Iterator<Item = ResultRecord>,
+) -> Result<Object, response::GDBResponseError> {
+    results.next().map(done).unwrap_or_else(|| {
+        Err(response::GDBResponseError::Other(
+            "Missing result".to_owned(),
+        ))
+    })
+}
+
+pub struct GDB {
+    pub mi: gdbmi::GDB,
+    pub breakpoints: BreakPointSet,
+    pub inferiors: InferiorSet,
+    pub snapshot: Snapshot,
+    /// The skips as of the last change (or `refresh_skips`).
+    pub skips: Vec<Skip>,
+    /// Loaded shared libraries as reported by library notifications (or `sync_state`).
+    pub libraries: Vec<types::Library>,
+    pointer_size: Option<usize>,
+    print_settings: Option<PrintSettings>, // Until they are changed in the console
+}
+
+#[derive(Debug)]
+pub enum BreakpointOperationError {
+    Busy,
+    ExecutionError(String),
+    Quit(gdbmi::Error),
+}
+
+impl GDB {
+    pub fn new(mi: gdbmi::GDB) -> Self {
+        GDB {
+            mi: mi,
+            breakpoints: BreakPointSet::new(),
+            inferiors: InferiorSet::new(),
+            snapshot: Snapshot::new(),
+            skips: Vec::new(),
+            libraries: Vec::new(),
+            pointer_size: None,
+            print_settings: None,
+        }
+    }
+
+    /// Query breakpoints, inferiors, shared libraries and (if the program is stopped) threads,
+    /// e.g., when gdb was configured (by init files or by the user) before ugdb was notified of
+    /// changes. All of them are queried even if some fail, the first error is returned.
+    pub fn sync_state(&mut self) -> Result<(), response::GDBResponseError> {
+        let breakpoints = self.refresh_breakpoints();
+        let inferiors = self.refresh_inferiors();
+        let libraries = self.refresh_libraries();
+        if !self.mi.is_running() {
+            self.refresh_threads();
+        }
+        breakpoints.and(inferiors).and(libraries)
+    }
+
+    /// Send a signal to the program (instead of interrupting gdb, see
+    /// `gdbmi::GDB::interrupt_execution`).
+    ///
+    /// The signal is sent directly to the process of the selected inferior (whose id is returned)
+    /// only if ugdb started gdb and gdb runs the program natively, i.e., on this machine. Otherwise
+    /// the process id that gdb reports may belong to an unrelated process here, so the stopped
+    /// program is resumed with the signal using gdb's `signal` command instead.
+    pub fn signal_inferior(
+        &mut self,
+        signal: nix::sys::signal::Signal,
+    ) -> Result<Option<i32>, response::GDBResponseError> {
+        if self.mi.is_external() || !self.is_native_target()? {
+            if self.mi.is_running() {
+                return Err(response::GDBResponseError::Other(
+                    "The program does not run on this machine. Stop it first, so that gdb can resume it with the signal.".to_owned(),
+                ));
+            }
+            self.execute_cli(&format!("signal {}", signal.as_str()))?;
+            return Ok(None);
+        }
+        let result = self.mi.execute(MiCommand::list_thread_groups(false, &[]))?;
+        let groups = result.results["groups"]
+            .members()
+            .map(types::ThreadGroup::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let selected = self.inferiors.selected();
+        let pid = groups
+            .iter()
+            .filter(|g| selected.is_none_or(|s| s == g.id))
+            .find_map(|g| g.pid.as_ref()?.parse::<i32>().ok())
+            .ok_or_else(|| {
+                response::GDBResponseError::Other("The program is not running.".to_owned())
+            })?;
+        nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), signal)
+            .map_err(|e| response::GDBResponseError::Other(e.to_string()))?;
+        Ok(Some(pid))
+    }
+
+    // Whether gdb debugs the program using its native target (and not, e.g., gdbserver or rr).
+    fn is_native_target(&mut self) -> Result<bool, response::GDBResponseError> {
+        let output = self.execute_cli_capturing("maint print target-stack")?;
+        Ok(is_native_target_stack(&output))
+    }
+
+    fn refresh_inferiors(&mut self) -> Result<(), response::GDBResponseError> {
+        let result = self
+            .mi
+            .execute(MiCommand::list_thread_groups_with_threads())?;
+        let groups = result.results["groups"]
+            .members()
+            .map(types::ThreadGroup::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.inferiors.sync(&groups);
+        Ok(())
+    }
+
+    /// Read the symbols of a shared library (gdb's `sharedlibrary`), e.g., if gdb did not do so
+    /// when it was loaded because of `set auto-solib-add off`.
+    pub fn load_library_symbols(
+        &mut self,
+        library: &types::Library,
+    ) -> Result<(), response::GDBResponseError> {
+        self.execute_cli(&format!(
+            "sharedlibrary ^{}$",
+            escape_regex(&library.target_name)
+        ))?;
+        // gdb does not send a notification if the symbols were read.
+        self.refresh_libraries()
+    }
+
+    /// Target names of the libraries that gdb read the symbols of but found no debug information
+    /// for (neither in the library nor in a separate debug file).
+    pub fn libraries_without_debug_info(
+        &mut self,
+    ) -> Result<Vec<String>, response::GDBResponseError> {
+        let output = self.execute_cli_capturing("info sharedlibrary")?;
+        Ok(parse_libraries_without_debug_info(&output))
+    }
+
+    /// Read the symbols of the program from another file (e.g., the unstripped version of it).
+    pub fn symbol_file(&mut self, file: &Path) -> Result<(), response::GDBResponseError> {
+        self.execute_cli(&format!("symbol-file {}", file_argument(file)))?;
+        self.forget_pointer_size();
+        Ok(())
+    }
+
+    /// Read additional symbols (e.g., the separate debug file of a library) from the file. The
+    /// address is the one of its `.text` section, which gdb reads from the file if there is none.
+    pub fn add_symbol_file(
+        &mut self,
+        file: &Path,
+        address: Option<&str>,
+    ) -> Result<(), response::GDBResponseError> {
+        let mut command = format!("add-symbol-file {}", file_argument(file));
+        if let Some(address) = address {
+            command.push(' ');
+            command.push_str(address);
+        }
+        self.execute_cli(&command)?;
+        self.refresh_libraries()
+    }
+
+    /// The directories that gdb searches for separate debug files (e.g., by build-id in
+    /// `.build-id/ab/cdef.debug`).
+    pub fn get_debug_file_directories(
+        &mut self,
+    ) -> Result<Vec<PathBuf>, response::GDBResponseError> {
+        Ok(std::env::split_paths(&self.get_setting("debug-file-directory")?).collect())
+    }
+
+    pub fn add_debug_file_directory(
+        &mut self,
+        dir: &Path,
+    ) -> Result<(), response::GDBResponseError> {
+        let mut dirs = self.get_debug_file_directories()?;
+        if !dirs.iter().any(|d| d == dir) {
+            dirs.push(dir.to_owned());
+        }
+        let joined = std::env::join_paths(dirs)
+            .map_err(|e| response::GDBResponseError::Other(e.to_string()))?;
+        self.execute_cli(&format!(
+            "set debug-file-directory {}",
+            joined.to_string_lossy()
+        ))
+    }
+
+    fn refresh_libraries(&mut self) -> Result<(), response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::file_list_shared_libraries())?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
+        }
+        self.libraries = result.results["shared-libraries"]
+            .members()
+            .map(types::Library::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    pub fn handle_library_notification(&mut self, notification: &Notification) {
+        match notification {
+            Notification::LibraryLoaded(library) => {
+                self.libraries.retain(|l| l.id != library.id);
+                self.libraries.push(library.clone());
+            }
+            Notification::LibraryUnloaded(library) => {
+                self.libraries.retain(|l| l.id != library.id);
+            }
+            _ => {}
+        }
+    }
+
+    /// Query the threads (e.g., when ugdb started).
+    pub fn refresh_threads(&mut self) {
+        self.snapshot.threads = self.get_threads();
+    }
+
+    /// Query the wanted parts of the snapshot that were not queried since the last stop. Returns
+    /// whether any were queried (i.e., whether whoever shows them has to be refreshed).
+    pub fn complete_snapshot(&mut self, wanted: &SnapshotParts) -> bool {
+        let missing = wanted.without(&self.snapshot.parts);
+        if missing.is_empty() || self.mi.is_running() {
+            return false;
+        }
+        let results = Snapshot::part_queries(&missing)
+            .into_iter()
+            .map(|query| self.mi.execute(query))
+            .collect::<Result<Vec<_>, _>>();
+        match results {
+            Ok(results) => {
+                self.snapshot.update_parts(&missing, results);
+                true
+            }
+            Err(e) => {
+                warn!("Could not query the state of the program: {:?}", e);
+                false
+            }
+        }
+    }
+
+    // Execute a cli command and return its output.
+    fn execute_cli_capturing(
+        &mut self,
+        command: &str,
+    ) -> Result<String, response::GDBResponseError> {
+        let (result, output) = self
+            .mi
+            .execute_capturing_console(MiCommand::cli_exec(command))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
+        }
+        Ok(output)
+    }
+
+    /// Query the skips (e.g., because some may have been added in the console).
+    pub fn refresh_skips(&mut self) -> Result<(), response::GDBResponseError> {
+        let output = self.execute_cli_capturing("info skip")?;
+        self.skips = parse_skips(&output);
+        Ok(())
+    }
+
+    pub fn add_skip(
+        &mut self,
+        kind: SkipKind,
+        pattern: &str,
+    ) -> Result<(), response::GDBResponseError> {
+        self.execute_cli(&format!(
+            "skip {} {}",
+            kind.option(),
+            quote_argument(pattern)
+        ))?;
+        self.refresh_skips()
+    }
+
+    pub fn delete_skip(&mut self, number: usize) -> Result<(), response::GDBResponseError> {
+        self.execute_cli(&format!("skip delete {}", number))?;
+        self.refresh_skips()
+    }
+
+    pub fn set_skip_enabled(
+        &mut self,
+        number: usize,
+        enabled: bool,
+    ) -> Result<(), response::GDBResponseError> {
+        let verb = if enabled { "enable" } else { "disable" };
+        self.execute_cli(&format!("skip {} {}", verb, number))?;
+        self.refresh_skips()
+    }
+
+    /// Add skips (e.g., of a previous session) and return how many of them could be added.
+    pub fn restore_skips(&mut self, saved: &[Skip]) -> usize {
+        let mut restored = 0;
+        for skip in saved {
+            let res = self.add_skip(skip.kind, &skip.pattern).and_then(|()| {
+                match self.skips.last().map(|s| s.number) {
+                    Some(number) if !skip.enabled => self.set_skip_enabled(number, false),
+                    _ => Ok(()),
+                }
+            });
+            match res {
+                Ok(()) => restored += 1,
+                Err(e) => warn!("Could not restore skip {:?}: {:?}", skip, e),
+            }
+        }
+        restored
+    }
+
+    pub fn kill(&mut self) {
+        if let Err(e) = self.mi.interrupt_execution() {
+            warn!("Could not interrupt gdb: {}", e);
+        }
+        self.mi.execute_later(&gdbmi::commands::MiCommand::exit());
+    }
+
+    pub fn insert_breakpoint(
+        &mut self,
+        location: BreakPointLocation,
+    ) -> Result<(), BreakpointOperationError> {
+        self.execute_insert_breakpoint(MiCommand::insert_breakpoint(location))
+            .map(|_| ())
+    }
+
+    /// Insert a breakpoint at the entry of the function (e.g., "main" or "Foo::bar") and return
+    /// its number.
+    pub fn insert_function_breakpoint(
+        &mut self,
+        function: &str,
+        condition: Option<&str>,
+    ) -> Result<usize, BreakpointOperationError> {
+        let command = MiCommand::insert_breakpoint(BreakPointLocation::Symbol(function));
+        self.execute_insert_breakpoint(match condition {
+            Some(condition) => command.with_condition(condition),
+            None => command,
+        })
+    }
+
+    /// Continue until the address is reached (or another breakpoint is hit) using a temporary
+    /// breakpoint.
+    pub fn run_to_address(&mut self, address: Address) -> Result<(), BreakpointOperationError> {
+        let major = self.execute_insert_breakpoint(MiCommand::insert_temporary_breakpoint(
+            BreakPointLocation::Address(address.0),
+        ))?;
+        self.execute_running(MiCommand::exec_continue())
+            .map_err(|msg| {
+                // Otherwise, the program would stop there whenever it is continued later.
+                let number = BreakPointNumber { major, minor: None };
+                if let Err(e) = self.delete_breakpoints(std::iter::once(number)) {
+                    warn!("Could not delete temporary breakpoint {}: {:?}", major, e);
+                }
+                BreakpointOperationError::ExecutionError(msg)
+            })
+    }
+
+    /// Resume execution at the address, i.e., set the program counter to it and continue.
+    pub fn jump_to_address(&mut self, address: Address) -> Result<(), response::GDBResponseError> {
+        self.execute_running(MiCommand::exec_jump(&format!("*{}", address)))
+            .map_err(response::GDBResponseError::Other)
+    }
+
+    /// Execute a command that resumes the program (e.g., `-exec-continue`).
+    pub fn execute_running(&mut self, command: MiCommand) -> Result<(), String> {
+        let result = self.mi.execute(command).map_err(|e| format!("{:?}", e))?;
+        match result.class {
+            ResultClass::Running | ResultClass::Done => Ok(()),
+            _ => Err(result.results["msg"]
+                .as_str()
+                .unwrap_or("Unknown error")
+                .to_owned()),
+        }
+    }
+
+    /// Insert the saved breakpoints (e.g., after gdb was restarted) and return how many of them
+    /// could be restored completely, i.e., including their commands and ignore counts.
+    pub fn restore_breakpoints(&mut self, saved: &[SavedBreakPoint]) -> usize {
+        let mut restored = 0;
+        for bp in saved {
+            let location = match (&bp.src_pos, bp.address) {
+                (Some(pos), _) => BreakPointLocation::Line(&pos.file, pos.line.raw_value()),
+                (None, Some(address)) => BreakPointLocation::Address(address.0),
+                (None, None) => continue,
+            };
+            let mut command = if bp.enabled {
+                MiCommand::insert_breakpoint(location)
+            } else {
+                MiCommand::insert_disabled_breakpoint(location)
+            };
+            if let Some(ref condition) = bp.condition {
+                command = command.with_condition(condition);
+            }
+            let major = match self.execute_insert_breakpoint(command) {
+                Ok(major) => major,
+                Err(e) => {
+                    warn!("Could not restore breakpoint {:?}: {:?}", bp, e);
+                    continue;
+                }
+            };
+            // -break-insert cannot set commands, so they (and the ignore count) are set afterwards.
+            let commands = if bp.commands.is_empty() {
+                Ok(())
+            } else {
+                self.set_breakpoint_commands(major, bp.commands.clone())
+            };
+            let ignore_count = if bp.ignore_count == 0 {
+                Ok(())
+            } else {
+                self.set_ignore_count(major, bp.ignore_count)
+            };
+            match (commands, ignore_count) {
+                (Ok(()), Ok(())) => restored += 1,
+                (commands, ignore_count) => warn!(
+                    "Could not restore the commands ({:?}) or ignore count ({:?}) of breakpoint {}: {:?}",
+                    commands, ignore_count, major, bp
+                ),
+            }
+        }
+        restored
+    }
+
+    /// Load the executable again (e.g., after it was rebuilt). Breakpoints are inserted again at
+    /// their source locations, because their addresses refer to the old build (breakpoints that
+    /// only have an address are removed). Returns how many of the breakpoints were restored.
+    pub fn reload_executable(
+        &mut self,
+        executable: &Path,
+    ) -> Result<(usize, usize), response::GDBResponseError> {
+        let saved = self
+            .breakpoints
+            .save()
+            .into_iter()
+            .map(|bp| SavedBreakPoint {
+                address: None,
+                ..bp
+            })
+            .collect::<Vec<_>>();
+        // Watchpoints (which have no location) are kept.
+        let numbers = self
+            .breakpoints
+            .values()
+            .filter(|bp| bp.src_pos.is_some() || bp.address.is_some())
+            .map(|bp| bp.number.major)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|major| BreakPointNumber { major, minor: None })
+            .collect::<Vec<_>>();
+        if !numbers.is_empty() {
+            self.delete_breakpoints(numbers.into_iter()).map_err(|e| {
+                response::GDBResponseError::Other(format!("Could not delete breakpoints: {:?}", e))
+            })?;
+        }
+        self.execute_checked(MiCommand::file_exec_and_symbols(executable))?;
+        self.forget_pointer_size();
+        Ok((self.restore_breakpoints(&saved), saved.len()))
+    }
+
+    // Returns the major of the inserted breakpoint.
+    fn execute_insert_breakpoint(
+        &mut self,
+        command: MiCommand,
+    ) -> Result<usize, BreakpointOperationError> {
+        let bp_result = self.mi.execute(&command).map_err(|e| match e {
+            ExecuteError::Busy | ExecuteError::Timeout => BreakpointOperationError::Busy,
+            ExecuteError::Quit(e) => BreakpointOperationError::Quit(e),
+        })?;
+        match bp_result.class {
+            ResultClass::Done => {
+                let bkpts =
+                    types::parse_breakpoints(&bp_result.results).expect("Malformed breakpoint");
+                self.update_breakpoints(&bkpts);
+                Ok(BreakPoint::from(&bkpts[0]).number.major)
+            }
+            ResultClass::Error => Err(BreakpointOperationError::ExecutionError(
+                bp_result
+                    .results
+                    .get("msg")
+                    .and_then(|msg_obj| msg_obj.as_str())
+                    .map(|s| s.to_owned())
+                    .unwrap_or(bp_result.results.dump()),
+            )),
+            _ => {
+                panic!("Unexpected resultclass: {:?}", bp_result.class);
+            }
+        }
+    }
+
+    /// Ignore the next `count` hits of the breakpoint (and all of its locations).
+    pub fn set_ignore_count(
+        &mut self,
+        major: usize,
+        count: u64,
+    ) -> Result<(), BreakpointOperationError> {
+        let result = self
+            .mi
+            .execute(MiCommand::break_after(major, count))
+            .map_err(|e| match e {
+                ExecuteError::Busy | ExecuteError::Timeout => BreakpointOperationError::Busy,
+                ExecuteError::Quit(e) => BreakpointOperationError::Quit(e),
+            })?;
+        match result.class {
+            // gdb does not notify about changes that were made using gdb/mi.
+            ResultClass::Done => {
+                for bp in self
+                    .breakpoints
+                    .map
+                    .values_mut()
+                    .filter(|bp| bp.number.major == major)
+                {
+                    bp.ignore_count = count;
+                }
+                self.breakpoints.notify_change();
+                Ok(())
+            }
+            _ => Err(BreakpointOperationError::ExecutionError(
+                result
+                    .results
+                    .get("msg")
+                    .and_then(|msg_obj| msg_obj.as_str())
+                    .map(|s| s.to_owned())
+                    .unwrap_or_else(|| result.results.dump()),
+            )),
+        }
+    }
+
+    /// Replace the commands that are executed when the breakpoint is hit.
+    pub fn set_breakpoint_commands(
+        &mut self,
+        major: usize,
+        commands: Vec<String>,
+    ) -> Result<(), BreakpointOperationError> {
+        let result = self
+            .mi
+            .execute(MiCommand::break_commands(major, &commands))
+            .map_err(|e| match e {
+                ExecuteError::Busy | ExecuteError::Timeout => BreakpointOperationError::Busy,
+                ExecuteError::Quit(e) => BreakpointOperationError::Quit(e),
+            })?;
+        match result.class {
+            // gdb does not notify about changes that were made using gdb/mi.
+            ResultClass::Done => {
+                for bp in self
+                    .breakpoints
+                    .map
+                    .values_mut()
+                    .filter(|bp| bp.number.major == major)
+                {
+                    bp.commands = commands.clone();
+                }
+                self.breakpoints.notify_change();
+                Ok(())
+            }
+            _ => Err(BreakpointOperationError::ExecutionError(
+                result
+                    .results
+                    .get("msg")
+                    .and_then(|msg_obj| msg_obj.as_str())
+                    .map(|s| s.to_owned())
+                    .unwrap_or_else(|| result.results.dump()),
+            )),
+        }
+    }
+
+    pub fn delete_breakpoints<I: Clone + Iterator<Item = BreakPointNumber>>(
+        &mut self,
+        bp_numbers: I,
+    ) -> Result<(), BreakpointOperationError> {
+        let bp_result = self
+            .mi
+            .execute(MiCommand::delete_breakpoints(bp_numbers.clone()))
+            .map_err(|e| match e {
+                ExecuteError::Busy | ExecuteError::Timeout => BreakpointOperationError::Busy,
+                ExecuteError::Quit(e) => BreakpointOperationError::Quit(e),
+            })?;
+        match bp_result.class {
+            ResultClass::Done => {
+                let major_to_delete = bp_numbers.map(|n| n.major).collect::<HashSet<usize>>();
+                let bkpts_to_delete = self
+                    .breakpoints
+                    .map
+                    .keys()
+                    .filter_map(|&k| {
+                        if major_to_delete.contains(&k.major) {
+                            Some(k)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<BreakPointNumber>>();
+                for bkpt in bkpts_to_delete {
+                    self.breakpoints.remove_breakpoint(bkpt);
+                }
+                Ok(())
+            }
+            ResultClass::Error => Err(BreakpointOperationError::ExecutionError(
+                bp_result
+                    .results
+                    .get("msg")
+                    .and_then(|msg_obj| msg_obj.as_str())
+                    .map(|s| s.to_owned())
+                    .unwrap_or_else(|| bp_result.results.dump()),
+            )),
+            _ => {
+                panic!("Unexpected resultclass: {:?}", bp_result.class);
+            }
+        }
+    }
+
+    /// Replace the known breakpoints by the ones that gdb lists, e.g., to learn about breakpoints
+    /// that were created before ugdb was notified of them (by init files).
+    pub fn refresh_breakpoints(&mut self) -> Result<(), response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::break_list())?;
+        let table = types::BreakpointTable::try_from(&result.results)?;
+        let traced = ::std::mem::take(&mut self.breakpoints.traced);
+        self.breakpoints = BreakPointSet::new();
+        self.breakpoints.traced = traced;
+        self.update_breakpoints(&table.breakpoints);
+        Ok(())
+    }
+
+    /// The local init file (`./.gdbinit`) that gdb executed on startup, if any. gdb only executes
+    /// it if `auto-load local-gdbinit` is on and the directory is in the `auto-load safe-path`.
+    pub fn loaded_local_gdbinit(&mut self) -> Result<Option<PathBuf>, response::GDBResponseError> {
+        let output = self.execute_cli_capturing("info auto-load local-gdbinit")?;
+        Ok(parse_local_gdbinit(&output))
+    }
+
+    pub fn update_breakpoints(&mut self, bkpts: &[types::Breakpoint]) {
+        for bkpt in bkpts {
+            self.breakpoints.update_breakpoint(BreakPoint::from(bkpt));
+            // gdb only reports the counts for the breakpoint itself.
+            for location in &bkpt.locations {
+                self.breakpoints.update_breakpoint(BreakPoint {
+                    hit_count: bkpt.times.unwrap_or(0),
+                    ignore_count: bkpt.ignore.unwrap_or(0),
+                    commands: bkpt.script.clone(),
+                    condition: bkpt.condition.clone(),
+                    ..BreakPoint::from(location)
+                });
+            }
+        }
+    }
+
+    pub fn delete_breakpoint(&mut self, id: &str) {
+        let id = id.parse::<BreakPointNumber>().expect("Parse usize");
+        self.breakpoints.remove_breakpoint(id);
+    }
+
+    /// Write all breakpoints to a script (using `save breakpoints`) that plain gdb can `source`.
+    pub fn save_breakpoints(&mut self, file: &Path) -> Result<(), response::GDBResponseError> {
+        self.execute_cli(&format!("save breakpoints {}", file.display()))
+    }
+
+    /// Execute a script written by `save_breakpoints`. The breakpoints are inserted in addition to
+    /// the existing ones and reported by gdb using breakpoint-created notifications.
+    pub fn load_breakpoints(&mut self, file: &Path) -> Result<(), response::GDBResponseError> {
+        self.execute_cli(&format!("source {}", file.display()))
+    }
+
+    /// Make the inferior with the given number the current one. gdb will report the newly selected
+    /// thread (if the inferior has one) using a thread-selected notification.
+    pub fn select_inferior(&mut self, number: &str) -> Result<(), response::GDBResponseError> {
+        let capabilities = self.mi.capabilities();
+        if !capabilities.supports(Feature::GdbCommands) {
+            return Err(response::GDBResponseError::Other(
+                capabilities.unsupported(Feature::GdbCommands),
+            ));
+        }
+        self.execute_cli(&format!("inferior {}", number))
+    }
+
+    /// Make the thread with the given id the current one. As with `select_inferior`, gdb reports
+    /// the selection using a thread-selected notification. lldb-mi does not report selections, so
+    /// the notification that gdb would send is returned instead (see `selection_record`).
+    pub fn select_thread(
+        &mut self,
+        id: &str,
+    ) -> Result<Option<OutOfBandRecord>, response::GDBResponseError> {
+        if self.mi.capabilities().backend == Backend::LldbMi {
+            self.execute_checked(MiCommand::thread_select(id))?;
+            return self.selection_record().map(Some);
+        }
+        self.execute_cli(&format!("thread {}", id)).map(|()| None)
+    }
+
+    /// Make the frame with the given level (of the current thread) the current one. The selection
+    /// is reported like that of `select_thread`.
+    pub fn select_frame(
+        &mut self,
+        level: u64,
+    ) -> Result<Option<OutOfBandRecord>, response::GDBResponseError> {
+        if self.mi.capabilities().backend == Backend::LldbMi {
+            self.execute_checked(MiCommand::select_frame(level))?;
+            return self.selection_record().map(Some);
+        }
+        self.execute_cli(&format!("frame {}", level)).map(|()| None)
+    }
+
+    // The thread-selected notification for the current thread and frame.
+    fn selection_record(&mut self) -> Result<OutOfBandRecord, response::GDBResponseError> {
+        let threads = self.mi.execute(MiCommand::thread_info(None))?;
+        let frame = self.mi.execute(MiCommand::stack_info_frame(None))?;
+        let mut results = Object::new();
+        results.insert("id", threads.results["current-thread-id"].clone());
+        results.insert("frame", frame.results["frame"].clone());
+        Ok(OutOfBandRecord::AsyncRecord {
+            token: None,
+            kind: AsyncKind::Notify,
+            class: AsyncClass::Thread(ThreadEvent::Selected),
+            results,
+        })
+    }
+
+    /// Replace the prefix `from` of source paths by `to` (`set substitute-path`).
+    pub fn add_path_substitution(
+        &mut self,
+        from: &Path,
+        to: &Path,
+    ) -> Result<(), response::GDBResponseError> {
+        self.execute_cli(&format!(
+            "set substitute-path \"{}\" \"{}\"",
+            from.display(),
+            to.display()
+        ))
+    }
+
+    /// Debug the program that gdbserver started at the target (e.g., "| <command>" or
+    /// "localhost:2345", see `profile`), reading its symbols from the local file if one is given.
+    pub fn connect_remote(
+        &mut self,
+        target: &str,
+        sysroot: &str,
+        symbols: Option<&Path>,
+    ) -> Result<(), response::GDBResponseError> {
+        self.execute_cli(&format!("set sysroot {}", sysroot))?;
+        if let Some(symbols) = symbols {
+            self.execute_cli(&format!("file {}", file_argument(symbols)))?;
+        }
+        self.execute_cli(&format!("target extended-remote {}", target))
+    }
+
+    // Selections are done using the cli commands, because gdb only emits the thread-selected
+    // notification for those.
+    fn execute_cli(&mut self, command: &str) -> Result<(), response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::cli_exec(command))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// All threads (of all inferiors) and the id of the current one.
+    fn get_threads(
+        &mut self,
+    ) -> Result<(Vec<types::Thread>, Option<String>), response::GDBResponseError> {
+        parse_threads(&done(self.mi.execute(MiCommand::thread_info(None))?)?)
+    }
+
+    /// Decide whether a stop event should be shown, i.e., whether it concerns the selected
+    /// inferior. In all-stop mode gdb switches to the thread that caused the stop (and thus possibly
+    /// to another inferior), in non-stop mode the selection is left as it is. In either case the
+    /// selected inferior is updated to match gdb's state.
+    pub fn stop_concerns_selected_inferior(&mut self, stop: &Object) -> bool {
+        let thread_id = match stop["thread-id"].as_str() {
+            Some(t) => t,
+            None => return true,
+        };
+        let group = match self.inferiors.group_of_thread(thread_id) {
+            Some(g) => g.to_owned(),
+            None => return true,
+        };
+        if self.inferiors.selected().is_none() || self.inferiors.selected() == Some(&group) {
+            self.inferiors.select(&group);
+            return true;
+        }
+        let switched = match self.mi.execute(MiCommand::thread_info(None)) {
+            Ok(result) => result.results["current-thread-id"].as_str() == Some(thread_id),
+            Err(_) => true,
+        };
+        if switched {
+            self.inferiors.select(&group);
+        }
+        switched
+    }
+
+    // Warning: This is a hack, as gdbmi does not currently offer a command to query the current target
+    // May not work and can break at any time.
+    pub fn get_target(&mut self) -> Result<Option<PathBuf>, ExecuteError> {
+        let result = self.mi.execute(MiCommand::list_thread_groups(false, &[]))?;
+        if result.class == ResultClass::Done {
+            Ok(result.results["groups"]
+                .members()
+                .filter_map(|thread| thread["executable"].as_str())
+                .next()
+                .map(|exec| exec.into()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn get_source_files(&mut self) -> Result<Vec<PathBuf>, response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::file_list_exec_source_files())?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
+        }
+        Ok(result.results["files"]
+            .members()
+            .filter_map(|f| f["fullname"].as_str())
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    // Only functions with debug information are considered.
+    pub fn find_function_definition(
+        &mut self,
+        name: &str,
+    ) -> Result<Option<SrcPosition>, response::GDBResponseError> {
+        let result = self
+            .mi
+            .execute(MiCommand::symbol_info_functions(&format!("^{}$", name)))?;
+        if result.class != ResultClass::Done {
+            return Ok(None);
+        }
+        for file in result.results["symbols"]["debug"].members() {
+            let path = response::get_str(file, "fullname")?;
+            for symbol in file["symbols"].members() {
+                if let Ok(line) = response::get_u64(symbol, "line") {
+                    if line > 0 {
+                        return Ok(Some(SrcPosition::new(
+                            PathBuf::from(path),
+                            LineNumber::new(line as usize),
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the line table entries of the function of the currently selected frame.
+    pub fn get_line_table(&mut self) -> Result<FunctionLineTable, response::GDBResponseError> {
+        let frame = self.mi.execute(MiCommand::stack_info_frame(None))?;
+        if frame.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                "No frame selected".to_owned(),
+            ));
+        }
+        let frame = &frame.results["frame"];
+        let address = response::get_addr(frame, "addr")?;
+        let function = response::get_str(frame, "func")?.to_owned();
+        let file = PathBuf::from(response::get_str(frame, "fullname")?);
+
+        let disassembly = self.mi.execute(MiCommand::data_disassemble_function(
+            address.0,
+            DisassembleMode::DisassemblyOnly,
+        ))?;
+        let instructions = &disassembly.results["asm_insns"];
+        let low = response::get_addr(&instructions[0], "address")?;
+        let high = response::get_addr(&instructions[instructions.len().max(1) - 1], "address")?;
+
+        let (_, output) = self
+            .mi
+            .execute_capturing_console(MiCommand::cli_exec(&format!(
+                "maint info line-table ^{}$",
+                escape_regex(&file.to_string_lossy())
+            )))?;
+        let mut entries = parse_line_table(&output)
+            .into_iter()
+            .filter(|e| low <= e.begin && e.begin <= high)
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|e| (e.line.raw_value(), e.begin));
+        Ok(FunctionLineTable {
+            function,
+            file,
+            entries,
+        })
+    }
+
+    /// The mapped memory regions of the inferior (only available for live processes on some
+    /// targets, e.g., Linux).
+    pub fn get_memory_map(&mut self) -> Result<Vec<MemoryRegion>, response::GDBResponseError> {
+        let output = self.execute_cli_capturing("info proc mappings")?;
+        Ok(parse_proc_mappings(&output))
+    }
+
+    /// Addresses (with their symbol, if any) in `[begin, end)` at which the pattern (a string
+    /// literal, hex bytes or an expression) is found in memory.
+    pub fn find_in_memory(
+        &mut self,
+        begin: Address,
+        end: Address,
+        pattern: &str,
+    ) -> Result<Vec<(Address, String)>, response::GDBResponseError> {
+        let (size, values) = find_values(pattern);
+        let output = self.execute_cli_capturing(&format!(
+            "find /{}{} {}, +{}, {}",
+            MAX_MEMORY_MATCHES,
+            size,
+            begin,
+            end.0.saturating_sub(begin.0),
+            values
+        ))?;
+        Ok(parse_find_output(&output))
+    }
+
+    /// Write `length` bytes of memory starting at `begin` to a (raw binary) file.
+    pub fn dump_memory(
+        &mut self,
+        begin: Address,
+        length: usize,
+        path: &Path,
+    ) -> Result<(), response::GDBResponseError> {
+        self.execute_cli(&format!(
+            "dump binary memory {} {} {}",
+            file_argument(path),
+            begin,
+            Address(begin.0.wrapping_add(length))
+        ))
+    }
+
+    /// Write the contents of a (raw binary) file to memory starting at `begin`.
+    pub fn restore_memory(
+        &mut self,
+        path: &Path,
+        begin: Address,
+    ) -> Result<(), response::GDBResponseError> {
+        self.execute_cli(&format!("restore {} binary {}", file_argument(path), begin))
+    }
+
+    /// The arguments and locals of the currently selected frame (arguments first) with their
+    /// values.
+    pub fn get_frame_variables(
+        &mut self,
+    ) -> Result<Vec<types::Variable>, response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::stack_list_variables(
+            None,
+            None,
+            PrintValues::All,
+        ))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
+        }
+        Ok(result.results["variables"]
+            .members()
+            .map(types::Variable::try_from)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Execute console commands (e.g., of a run configuration) until one of them fails.
+    pub fn execute_commands(
+        &mut self,
+        commands: &[String],
+    ) -> Result<(), response::GDBResponseError> {
+        for command in commands {
+            self.execute_cli(command)?;
+        }
+        Ok(())
+    }
+
+    /// The arguments that the program is started with on the next run.
+    pub fn get_arguments(&mut self) -> Result<String, response::GDBResponseError> {
+        self.get_setting("args")
+    }
+
+    pub fn set_arguments(&mut self, args: &str) -> Result<(), response::GDBResponseError> {
+        self.execute_checked(MiCommand::exec_arguments(vec![args.into()]))
+    }
+
+    pub fn get_working_directory(&mut self) -> Result<PathBuf, response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::environment_pwd())?;
+        Ok(PathBuf::from(response::get_str_obj(
+            &result.results,
+            "cwd",
+        )?))
+    }
+
+    /// Change the working directory of gdb, which the program is started in.
+    pub fn set_working_directory(&mut self, dir: &Path) -> Result<(), response::GDBResponseError> {
+        self.execute_checked(MiCommand::environment_cd(dir))
+    }
+
+    /// Set (or unset if there is no value) an environment variable of the program.
+    pub fn set_environment_variable(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<(), response::GDBResponseError> {
+        match value {
+            Some(value) => self.execute_cli(&format!("set environment {}={}", name, value)),
+            None => self.execute_cli(&format!("unset environment {}", name)),
         }
     }
-}
-
-pub struct BreakPointSet {
-    map: HashMap<BreakPointNumber, BreakPoint>,
-    pub last_change: ::std::time::Instant,
-}
 
-impl BreakPointSet {
-    pub fn new() -> Self {
-        BreakPointSet {
-            map: HashMap::new(),
-            last_change: ::std::time::Instant::now(),
+    fn execute_checked(&mut self, command: MiCommand) -> Result<(), response::GDBResponseError> {
+        let result = self.mi.execute(command)?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
         }
+        Ok(())
     }
 
-    fn notify_change(&mut self) {
-        self.last_change = ::std::time::Instant::now();
+    fn get_setting(&mut self, variable: &str) -> Result<String, response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::gdb_show(variable))?;
+        Ok(response::get_str_obj(&result.results, "value")?.to_owned())
     }
 
-    pub fn update_breakpoint(&mut self, new_bp: BreakPoint) {
-        let _ = self.map.insert(new_bp.number, new_bp);
-        //debug_assert!(res.is_some(), "Modified non-existent breakpoint");
-        self.notify_change();
+    /// The print settings are only queried again after `forget_print_settings`.
+    pub fn get_print_settings(&mut self) -> Result<PrintSettings, response::GDBResponseError> {
+        if let Some(settings) = &self.print_settings {
+            return Ok(settings.clone());
+        }
+        let settings = PrintSettings {
+            pretty: self.get_setting("print pretty")? == "on",
+            elements: self.get_setting("print elements")?.parse::<u32>().ok(),
+            array: self.get_setting("print array")? == "on",
+            null_stop: self.get_setting("print null-stop")? == "on",
+        };
+        self.print_settings = Some(settings.clone());
+        Ok(settings)
     }
 
-    pub fn remove_breakpoint(&mut self, bp_num: BreakPointNumber) {
-        self.map.remove(&bp_num);
-        if bp_num.minor.is_none() {
-            //TODO: ensure removal of child breakpoints
+    /// Query the print settings again when they are needed, e.g., because they were changed in
+    /// the console.
+    pub fn forget_print_settings(&mut self) {
+        self.print_settings = None;
+    }
+
+    pub fn set_print_settings(
+        &mut self,
+        settings: &PrintSettings,
+    ) -> Result<(), response::GDBResponseError> {
+        // Some of them may have been changed even if setting another one fails.
+        self.print_settings = None;
+        let elements = settings
+            .elements
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| "unlimited".to_owned());
+        for &(variable, value) in &[
+            ("print pretty", on_off(settings.pretty)),
+            ("print elements", elements.as_str()),
+            ("print array", on_off(settings.array)),
+            ("print null-stop", on_off(settings.null_stop)),
+        ] {
+            let result = self.mi.execute(MiCommand::gdb_set(variable, value))?;
+            if result.class != ResultClass::Done {
+                return Err(response::GDBResponseError::Other(
+                    result.results["msg"]
+                        .as_str()
+                        .unwrap_or("Unknown error")
+                        .to_owned(),
+                ));
+            }
         }
-        self.notify_change();
+        self.print_settings = Some(settings.clone());
+        Ok(())
     }
-}
 
-impl ::std::ops::Deref for BreakPointSet {
-    type Target = HashMap<BreakPointNumber, BreakPoint>;
+    pub fn get_disassembly_flavor(
+        &mut self,
+    ) -> Result<DisassemblyFlavor, response::GDBResponseError> {
+        self.get_setting("disassembly-flavor")?
+            .parse()
+            .map_err(response::GDBResponseError::Other)
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.map
+    pub fn set_disassembly_flavor(
+        &mut self,
+        flavor: DisassemblyFlavor,
+    ) -> Result<(), response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::gdb_set(
+            "disassembly-flavor",
+            &flavor.to_string(),
+        ))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
+        }
+        Ok(())
     }
-}
 
-pub struct GDB {
-    pub mi: gdbmi::GDB,
-    pub breakpoints: BreakPointSet,
-}
+    /// Evaluate an expression without limiting the number of printed elements.
+    /// Evaluate the expressions in the selected frame (at once). Expressions that cannot be
+    /// evaluated result in the error message of gdb.
+    pub fn evaluate_many(
+        &mut self,
+        expressions: &[String],
+    ) -> Result<Vec<Result<String, String>>, ExecuteError> {
+        let results = self.mi.execute_many(
+            expressions
+                .iter()
+                .map(|e| MiCommand::data_evaluate_expression(e.clone())),
+        )?;
+        Ok(results
+            .into_iter()
+            .map(|r| match r.class {
+                ResultClass::Done => Ok(r.results["value"].as_str().unwrap_or("").to_owned()),
+                _ => Err(r.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned()),
+            })
+            .collect())
+    }
 
-pub enum BreakpointOperationError {
-    Busy,
-    ExecutionError(String),
-}
+    pub fn evaluate_full(
+        &mut self,
+        expression: &str,
+    ) -> Result<String, response::GDBResponseError> {
+        let elements = self.get_setting("print elements")?;
+        self.mi
+            .execute(MiCommand::gdb_set("print elements", "unlimited"))?;
+        let result = self
+            .mi
+            .execute(MiCommand::data_evaluate_expression(expression.to_owned()));
+        self.mi
+            .execute(MiCommand::gdb_set("print elements", &elements))?;
+        let result = result?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
+        }
+        Ok(response::get_str_obj(&result.results, "value")?.to_owned())
+    }
 
-impl GDB {
-    pub fn new(mi: gdbmi::GDB) -> Self {
-        GDB {
-            mi: mi,
-            breakpoints: BreakPointSet::new(),
+    /// Enable or disable the (Python) pretty printers that are registered in gdb. Enabling them
+    /// also makes variable objects use them.
+    pub fn set_pretty_printers(&mut self, enable: bool) -> Result<(), response::GDBResponseError> {
+        if enable {
+            self.mi.execute(MiCommand::enable_pretty_printing())?;
+        }
+        let command = if enable {
+            "enable pretty-printer"
+        } else {
+            "disable pretty-printer"
+        };
+        let result = self.mi.execute(MiCommand::cli_exec(command))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
         }
+        Ok(())
     }
 
-    pub fn kill(&mut self) {
-        self.mi.interrupt_execution().expect("interrupt worked");
-        self.mi.execute_later(&gdbmi::commands::MiCommand::exit());
+    pub fn set_print_pretty(&mut self, pretty: bool) -> Result<(), response::GDBResponseError> {
+        self.forget_print_settings();
+        let result = self
+            .mi
+            .execute(MiCommand::gdb_set("print pretty", on_off(pretty)))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
+        }
+        Ok(())
     }
 
-    pub fn insert_breakpoint(
+    fn var_tree(
         &mut self,
-        location: BreakPointLocation,
-    ) -> Result<(), BreakpointOperationError> {
-        let bp_result = self
-            .mi
-            .execute(&MiCommand::insert_breakpoint(location))
-            .map_err(|e| match e {
-                ExecuteError::Busy => BreakpointOperationError::Busy,
-                ExecuteError::Quit => panic!("Could not insert breakpoint: GDB quit"),
-            })?;
-        match bp_result.class {
-            ResultClass::Done => {
-                self.handle_breakpoint_event(BreakPointEvent::Created, &bp_result.results);
-                Ok(())
-            }
-            ResultClass::Error => Err(BreakpointOperationError::ExecutionError(
-                bp_result
-                    .results
-                    .get("msg")
-                    .and_then(|msg_obj| msg_obj.as_str())
-                    .map(|s| s.to_owned())
-                    .unwrap_or(bp_result.results.dump()),
-            )),
-            _ => {
-                panic!("Unexpected resultclass: {:?}", bp_result.class);
-            }
+        var: types::VarObject,
+        depth: usize,
+    ) -> Result<VarTree, response::GDBResponseError> {
+        let mut tree = VarTree {
+            exp: var.exp.unwrap_or_default(),
+            value: var.value,
+            display_hint: var.display_hint,
+            children: Vec::new(),
+            has_more: false,
+        };
+        // Pointers also have a child (the pointee), which `print` does not show either.
+        let expand = var.dynamic || tree.value.as_deref().is_none_or(is_placeholder);
+        if !expand || depth >= VAR_MAX_DEPTH || (!var.dynamic && var.numchild == Some(0)) {
+            return Ok(tree);
+        }
+        let result = self.mi.execute(MiCommand::var_list_children(
+            var.name,
+            true,
+            Some(0..VAR_MAX_CHILDREN),
+        ))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
         }
+        tree.has_more = result.results["has_more"].as_str() == Some("1")
+            || var.numchild.is_some_and(|n| n > VAR_MAX_CHILDREN);
+        for child in result.results["children"].members() {
+            let child = types::VarObject::try_from(child)?;
+            tree.children.push(self.var_tree(child, depth + 1)?);
+        }
+        Ok(tree)
     }
 
-    pub fn delete_breakpoints<I: Clone + Iterator<Item = BreakPointNumber>>(
+    /// Evaluate an expression in the selected frame using a variable object, which includes the
+    /// children synthesized by pretty printers (e.g., the elements of a Rust `Vec`). The result has
+    /// the syntax of `print`.
+    pub fn evaluate_with_printers(
         &mut self,
-        bp_numbers: I,
-    ) -> Result<(), BreakpointOperationError> {
-        let bp_result = self
+        expression: &str,
+    ) -> Result<String, response::GDBResponseError> {
+        let result = self
             .mi
-            .execute(MiCommand::delete_breakpoints(bp_numbers.clone()))
-            .map_err(|e| match e {
-                ExecuteError::Busy => BreakpointOperationError::Busy,
-                ExecuteError::Quit => panic!("Could not insert breakpoint: GDB quit"),
-            })?;
-        match bp_result.class {
-            ResultClass::Done => {
-                let major_to_delete = bp_numbers.map(|n| n.major).collect::<HashSet<usize>>();
-                let bkpts_to_delete = self
-                    .breakpoints
-                    .map
-                    .keys()
-                    .filter_map(|&k| {
-                        if major_to_delete.contains(&k.major) {
-                            Some(k)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<BreakPointNumber>>();
-                for bkpt in bkpts_to_delete {
-                    self.breakpoints.remove_breakpoint(bkpt);
-                }
-                Ok(())
-            }
-            ResultClass::Error => Err(BreakpointOperationError::ExecutionError(
-                bp_result
-                    .results
-                    .get("msg")
-                    .and_then(|msg_obj| msg_obj.as_str())
-                    .map(|s| s.to_owned())
-                    .unwrap_or_else(|| bp_result.results.dump()),
-            )),
-            _ => {
-                panic!("Unexpected resultclass: {:?}", bp_result.class);
-            }
+            .execute(MiCommand::var_create(None, expression, None))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
         }
+        let var = types::VarObject::try_from(&result.results)?;
+        let name = var.name.clone();
+        let tree = self.var_tree(var, 0);
+        self.mi.execute(MiCommand::var_delete(name, false))?;
+        Ok(tree?.to_gdb_value())
     }
 
-    pub fn handle_breakpoint_event(&mut self, bp_type: BreakPointEvent, info: &Object) {
-        match bp_type {
-            BreakPointEvent::Created | BreakPointEvent::Modified => {
-                match &info["bkpt"] {
-                    JsonValue::Object(ref bkpt) => {
-                        let bp = BreakPoint::from_json(&bkpt);
-                        self.breakpoints.update_breakpoint(bp);
-
-                        // If there are multiple locations (recent versions of) gdb return the
-                        // sub-breakpoints in the array "locations".
-                        if let Some(JsonValue::Array(ref bkpts)) = bkpt.get("locations") {
-                            for bkpt in bkpts {
-                                if let JsonValue::Object(ref bkpt) = bkpt {
-                                    let bp = BreakPoint::from_json(&bkpt);
-                                    self.breakpoints.update_breakpoint(bp);
-                                } else {
-                                    panic!("Malformed breakpoint list");
-                                }
-                            }
-                        }
-                    }
-                    JsonValue::Array(ref bkpts) => {
-                        // In previous versions, gdb returned multiple sub-breakpoints as a series
-                        // of objects under the "bkpt" key (thus breaking the spec). This appears
-                        // to be fixed now, but we keep the current case (for now) for users of old
-                        // gdb versions.
-                        for bkpt in bkpts {
-                            if let JsonValue::Object(ref bkpt) = bkpt {
-                                let bp = BreakPoint::from_json(&bkpt);
-                                self.breakpoints.update_breakpoint(bp);
-                            } else {
-                                panic!("Malformed breakpoint list");
-                            }
-                        }
-                    }
-                    _ => {
-                        panic!("Invalid bkpt structure");
-                    }
-                }
-            }
-            BreakPointEvent::Deleted => {
-                let id = info["id"]
+    /// Evaluate an expression (e.g., a pointer) to an address.
+    pub fn evaluate_address(
+        &mut self,
+        expression: &str,
+    ) -> Result<Address, response::GDBResponseError> {
+        // The cast avoids gdb formatting the value (e.g., as a string in the case of char*).
+        let result = self
+            .mi
+            .execute(MiCommand::data_evaluate_expression(format!(
+                "(unsigned long long)({})",
+                expression
+            )))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
                     .as_str()
-                    .expect("find id")
-                    .parse::<BreakPointNumber>()
-                    .expect("Parse usize");
-                self.breakpoints.remove_breakpoint(id);
-            }
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
         }
+        let value = response::get_str_obj(&result.results, "value")?;
+        value
+            .parse::<usize>()
+            .map(Address)
+            .map_err(|_| response::GDBResponseError::MalformedAddress(value.to_owned()))
     }
 
-    // Warning: This is a hack, as gdbmi does not currently offer a command to query the current target
-    // May not work and can break at any time.
-    pub fn get_target(&mut self) -> Result<Option<PathBuf>, ExecuteError> {
-        let result = self.mi.execute(MiCommand::list_thread_groups(false, &[]))?;
-        if result.class == ResultClass::Done {
-            Ok(result.results["groups"]
-                .members()
-                .filter_map(|thread| thread["executable"].as_str())
-                .next()
-                .map(|exec| exec.into()))
-        } else {
-            Ok(None)
+    /// Size of a pointer of the target in bytes. The size is queried once gdb is able to evaluate
+    /// expressions (i.e., not while the program is running) and forgotten if another target is
+    /// loaded.
+    pub fn pointer_size(&mut self) -> Option<usize> {
+        if self.pointer_size.is_none() {
+            self.pointer_size = self.evaluate_address("sizeof(void*)").ok().map(|a| a.0);
         }
+        self.pointer_size
     }
 
-    pub fn get_stack_level(&mut self) -> Result<u64, response::GDBResponseError> {
-        let frame = self.mi.execute(MiCommand::stack_info_frame(None))?;
-        response::get_u64(&frame.results["frame"], "level")
+    pub fn forget_pointer_size(&mut self) {
+        self.pointer_size = None;
+    }
+
+    /// The stack pointer, frame address and saved registers of the selected frame.
+    pub fn get_frame_layout(&mut self) -> Result<FrameLayout, response::GDBResponseError> {
+        let sp = self.evaluate_address("$sp")?;
+        let output = self.execute_cli_capturing("info frame")?;
+        let (cfa, saved_registers) = parse_info_frame(&output);
+        Ok(FrameLayout {
+            sp,
+            cfa,
+            saved_registers,
+        })
+    }
+
+    /// The value of the stack protector canary of the inferior (glibc on x86-64 keeps it in the
+    /// thread control block, other targets in `__stack_chk_guard`), if it can be found.
+    pub fn get_stack_canary(&mut self) -> Option<usize> {
+        ["*(unsigned long *)($fs_base + 0x28)", "__stack_chk_guard"]
+            .iter()
+            .find_map(|expression| self.evaluate_address(expression).ok())
+            .map(|value| value.0)
+            .filter(|&value| value != 0)
+    }
+
+    /// Get the address of an lvalue expression, i.e., evaluate `&expression`.
+    pub fn get_address(&mut self, expression: &str) -> Result<Address, response::GDBResponseError> {
+        self.evaluate_address(&format!("&({})", expression))
+    }
+
+    /// Read the given number of bytes of the memory of the inferior starting at address. The result
+    /// may be shorter than requested if the end of the range is not readable.
+    pub fn read_memory(
+        &mut self,
+        address: Address,
+        count: usize,
+    ) -> Result<Vec<u8>, response::GDBResponseError> {
+        let result = self
+            .mi
+            .execute(MiCommand::data_read_memory_bytes(address.0, count))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
+        }
+        // Unreadable parts of the range are omitted from the list of blocks.
+        let block = result.results["memory"]
+            .members()
+            .find(|b| b["begin"].as_str().and_then(|a| Address::parse(a).ok()) == Some(address))
+            .ok_or_else(|| {
+                response::GDBResponseError::Other(format!("Cannot access memory at {}", address))
+            })?;
+        parse_hex_bytes(response::get_str(block, "contents")?).ok_or_else(|| {
+            response::GDBResponseError::Other("Malformed memory contents".to_owned())
+        })
     }
 
-    pub fn get_stack_depth(&mut self) -> Result<u64, response::GDBResponseError> {
-        let frame = self.mi.execute(MiCommand::stack_info_depth())?;
-        response::get_u64_obj(&frame.results, "depth")
+    /// Get the static type of an expression (as reported by `whatis`).
+    pub fn get_type(&mut self, expression: &str) -> Result<String, response::GDBResponseError> {
+        let (result, output) = self
+            .mi
+            .execute_capturing_console(MiCommand::cli_exec(&format!("whatis {}", expression)))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_owned(),
+            ));
+        }
+        let output = output.trim();
+        Ok(output.strip_prefix("type = ").unwrap_or(output).to_owned())
     }
 }
 
 // Various helper for getting stuff out of gdb response values
+fn parse_hex_bytes(contents: &str) -> Option<Vec<u8>> {
+    contents
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [_, _] => u8::from_str_radix(::std::str::from_utf8(pair).ok()?, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
 pub mod response {
     use super::*;
 
@@ -328,6 +2549,11 @@ pub mod response {
             GDBResponseError::Execution(e)
         }
     }
+    impl From<types::ParseError> for GDBResponseError {
+        fn from(e: types::ParseError) -> Self {
+            GDBResponseError::Other(format!("Malformed response: {}", e))
+        }
+    }
 
     pub fn get_str<'a>(obj: &'a JsonValue, key: &'static str) -> Result<&'a str, GDBResponseError> {
         Ok(obj[key]
@@ -349,11 +2575,6 @@ pub mod response {
         Ok(Address::parse(s)?)
     }
 
-    pub fn get_addr_obj(obj: &Object, key: &'static str) -> Result<Address, GDBResponseError> {
-        let s = get_str_obj(obj, key)?;
-        Ok(Address::parse(s)?)
-    }
-
     pub fn get_u64(obj: &JsonValue, key: &'static str) -> Result<u64, GDBResponseError> {
         let s = get_str(obj, key)?;
         Ok(s.parse::<u64>().map_err(|e| {
@@ -368,3 +2589,490 @@ pub mod response {
         })?)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let log = r#"0.1 < *stopped,reason="end-stepping-range",frame={addr="0x1000",func="f",args=[]},thread-id="1"
+0.2 < 1^done,frame={level="1",addr="0x1131",func="main"}
+0.3 < 2^done,depth="3"
+0.4 < 3^done,stack=[frame={level="0",addr="0x1000",func="f"},frame={level="1",addr="0x1131",func="main"}]
+0.5 < 4^done,threads=[{id="1",target-id="process 42",frame={level="0",addr="0x1000",func="f",args=[]},state="stopped"}],current-thread-id="1"
+0.6 < 5^done,register-names=["rax","","rsp"]
+0.7 < 6^done,register-values=[{number="0",value="0x1c"},{number="1",value="0x0"},{number="2",value="0x7ffc"}]
+0.8 < =thread-created,id="2",group-id="i1"
+0.9 < 7^error,msg="No registers."
+1.0 < =thread-selected,id="1",frame={level="0",addr="0x1000",func="f"}
+1.1 < 8^done,frame={level="0",addr="0x1000",func="f"}
+1.2 < 9^done,depth="3"
+"#;
+        let mut replay = gdbmi::traffic_log::replay(log.as_bytes()).unwrap();
+        let stop = &replay.out_of_band[0];
+        let created = &replay.out_of_band[1];
+        assert_eq!(Snapshot::queries(stop, false).len(), 2);
+        assert_eq!(Snapshot::queries(created, true).len(), 0);
+        assert_eq!(Snapshot::queries(created, false).len(), 1);
+
+        let mut snapshot = Snapshot::new();
+        let selection = replay.results.split_off(7);
+        let error = replay.results.pop().unwrap();
+        let parts = replay.results.split_off(2);
+        snapshot.update(stop, replay.results);
+        assert_eq!(snapshot.frame.as_ref().unwrap().func.as_deref(), Some("f"));
+        assert_eq!(snapshot.stack_level, Some(1));
+        assert_eq!(snapshot.stack_depth, Some(3));
+        assert!(snapshot.backtrace.is_err());
+
+        let all = SnapshotParts {
+            backtrace: true,
+            threads: true,
+            registers: true,
+        };
+        assert_eq!(Snapshot::part_queries(&all).len(), 4);
+        snapshot.update_parts(&all, parts);
+        assert!(all.without(&snapshot.parts).is_empty());
+        assert_eq!(snapshot.backtrace.as_ref().unwrap().len(), 2);
+
+        let (threads, current) = snapshot.threads.as_ref().unwrap();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(current.as_deref(), Some("1"));
+        assert_eq!(
+            snapshot.registers.as_ref().unwrap(),
+            &vec![
+                ("rax".to_owned(), "0x1c".to_owned()),
+                ("rsp".to_owned(), "0x7ffc".to_owned())
+            ]
+        );
+        // Selecting another frame of the same thread keeps the backtrace and the threads.
+        snapshot.update(&replay.out_of_band[2], selection);
+        assert_eq!(snapshot.stack_level, Some(0));
+        assert_eq!(
+            all.without(&snapshot.parts),
+            SnapshotParts {
+                registers: true,
+                ..SnapshotParts::default()
+            }
+        );
+        assert_eq!(snapshot.backtrace.as_ref().unwrap().len(), 2);
+
+        let mut snapshot = Snapshot::new();
+        snapshot.update(created, vec![error]);
+        assert_eq!(
+            snapshot.threads,
+            Err(response::GDBResponseError::Other(
+                "No registers.".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_disassembly_flavor() {
+        assert_eq!("intel".parse(), Ok(DisassemblyFlavor::Intel));
+        assert_eq!(DisassemblyFlavor::Att.to_string(), "att");
+        assert_eq!(DisassemblyFlavor::Att.toggled(), DisassemblyFlavor::Intel);
+        assert!("masm".parse::<DisassemblyFlavor>().is_err());
+    }
+
+    #[test]
+    fn test_var_tree_to_gdb_value() {
+        let var = |exp: &str, value: Option<&str>, children| VarTree {
+            exp: exp.to_owned(),
+            value: value.map(|v| v.to_owned()),
+            display_hint: None,
+            children,
+            has_more: false,
+        };
+        let leaf = |exp: &str, value: &str| var(exp, Some(value), Vec::new());
+
+        let mut vec = var(
+            "v",
+            Some("Vec(size=3)"),
+            vec![leaf("[0]", "1"), leaf("[1]", "2"), leaf("[2]", "3")],
+        );
+        vec.display_hint = Some("array".to_owned());
+        vec.has_more = true;
+        assert_eq!(vec.to_gdb_value(), "Vec(size=3) = {1, 2, 3, ...}");
+
+        let mut map = var(
+            "m",
+            Some("std::map with 1 element"),
+            vec![leaf("[0]", "\"a\""), leaf("[1]", "1")],
+        );
+        map.display_hint = Some("map".to_owned());
+        assert_eq!(
+            map.to_gdb_value(),
+            "std::map with 1 element = {[\"a\"] = 1}"
+        );
+
+        let object = var(
+            "o",
+            Some("{...}"),
+            vec![
+                var("public", None, vec![leaf("x", "1"), map]),
+                leaf("p", "0x601040"),
+                var("a", Some("[2]"), vec![leaf("0", "4"), leaf("1", "5")]),
+            ],
+        );
+        assert_eq!(
+            object.to_gdb_value(),
+            "{x = 1, m = std::map with 1 element = {[\"a\"] = 1}, p = 0x601040, a = {4, 5}}"
+        );
+    }
+
+    #[test]
+    fn test_save_breakpoints() {
+        let bp = |number: &str, enabled, line: Option<usize>, address: Option<usize>| BreakPoint {
+            number: number.parse().unwrap(),
+            address: address.map(Address),
+            enabled,
+            src_pos: line.map(|l| SrcPosition::new(PathBuf::from("/tmp/a.c"), LineNumber::new(l))),
+            hit_count: 0,
+            ignore_count: if number.starts_with('2') { 5 } else { 0 },
+            commands: if number.starts_with('2') {
+                vec!["silent".to_owned(), "continue".to_owned()]
+            } else {
+                Vec::new()
+            },
+            condition: None,
+        };
+        let mut set = BreakPointSet::new();
+        set.update_breakpoint(bp("3", true, None, None));
+        set.update_breakpoint(bp("2", false, None, None));
+        set.update_breakpoint(bp("2.2", true, Some(20), Some(0x20)));
+        set.update_breakpoint(bp("2.1", true, Some(10), Some(0x10)));
+        set.update_breakpoint(bp("1", true, None, Some(0x1000)));
+
+        let saved = set.save();
+        assert_eq!(saved.len(), 2);
+        assert_eq!(saved[0].address, Some(Address(0x1000)));
+        assert_eq!(saved[0].src_pos, None);
+        assert!(saved[0].enabled);
+        assert_eq!(saved[1].src_pos.as_ref().unwrap().line, LineNumber::new(10));
+        assert!(!saved[1].enabled);
+        assert_eq!(saved[0].ignore_count, 0);
+        assert!(saved[0].commands.is_empty());
+        assert_eq!(saved[1].ignore_count, 5);
+        assert_eq!(saved[1].commands, vec!["silent", "continue"]);
+    }
+
+    #[test]
+    fn test_parse_line_table() {
+        let output = "objfile: /tmp/a.out ((struct objfile *) 0x55d0)
+compunit_symtab: a.c ((struct compunit_symtab *) 0x55d1)
+symtab: /tmp/a.c ((struct symtab *) 0x55d2)
+linetable: ((struct linetable *) 0x55d3):
+INDEX  LINE   ADDRESS            IS-STMT PROLOGUE-END
+0      3      0x0000000000001129 Y       
+1      4      0x0000000000001131 Y       Y
+2      3      0x0000000000001138                      
+3      4      0x0000000000001140 Y       
+4      END    0x0000000000001148 Y       
+";
+        let entry = |line, begin, end, is_stmt| LineTableEntry {
+            line: LineNumber::new(line),
+            begin: Address(begin),
+            end: Address(end),
+            is_stmt: Some(is_stmt),
+        };
+        assert_eq!(
+            parse_line_table(output),
+            vec![
+                entry(3, 0x1129, 0x1131, true),
+                entry(4, 0x1131, 0x1138, true),
+                entry(3, 0x1138, 0x1140, false),
+                entry(4, 0x1140, 0x1148, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_proc_mappings() {
+        let output = "process 1234
+Mapped address spaces:
+
+          Start Addr           End Addr       Size     Offset  Perms  objfile
+      0x555555554000     0x555555555000     0x1000        0x0  r--p   /tmp/my prog
+      0x7ffff7fc1000     0x7ffff7fc5000     0x4000        0x0  rw-p   
+";
+        let regions = parse_proc_mappings(output);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(
+            regions[0],
+            MemoryRegion {
+                begin: Address(0x555555554000),
+                end: Address(0x555555555000),
+                offset: 0,
+                perms: Some("r--p".to_owned()),
+                objfile: "/tmp/my prog".to_owned(),
+            }
+        );
+        assert_eq!(regions[1].objfile, "");
+        assert!(regions[1].contains(Address(0x7ffff7fc4fff)));
+        assert!(!regions[1].contains(Address(0x7ffff7fc5000)));
+
+        let old = "          Start Addr           End Addr       Size     Offset objfile
+            0x400000           0x401000     0x1000        0x0 /tmp/a.out
+";
+        assert_eq!(parse_proc_mappings(old)[0].perms, None);
+        assert_eq!(parse_proc_mappings(old)[0].objfile, "/tmp/a.out");
+    }
+
+    #[test]
+    fn test_find_in_memory() {
+        assert_eq!(
+            find_values("de ad be ef"),
+            ("b", "0xde, 0xad, 0xbe, 0xef".to_owned())
+        );
+        assert_eq!(find_values("0x7f 45"), ("b", "0x7f, 0x45".to_owned()));
+        assert_eq!(find_values("\"ab cd\""), ("", "\"ab cd\"".to_owned()));
+        assert_eq!(find_values("(int)1234"), ("", "(int)1234".to_owned()));
+        assert_eq!(find_values("ab"), ("", "ab".to_owned()));
+
+        let output = "0x601040 <buf>
+0x601050 <buf+16>
+0x7ffff7dd0000
+3 patterns found.
+";
+        assert_eq!(
+            parse_find_output(output),
+            vec![
+                (Address(0x601040), "<buf>".to_owned()),
+                (Address(0x601050), "<buf+16>".to_owned()),
+                (Address(0x7ffff7dd0000), String::new()),
+            ]
+        );
+        assert!(parse_find_output("Pattern not found.").is_empty());
+    }
+
+    #[test]
+    fn test_parse_info_frame() {
+        let output = "Stack level 0, frame at 0x7fffffffe100:
+ rip = 0x555555555131 in main (main.c:3); saved rip = 0x7ffff7df0d90
+ source language c.
+ Arglist at 0x7fffffffe0f0, args: 
+ Locals at 0x7fffffffe0f0, Previous frame's sp is 0x7fffffffe100
+ Saved registers:
+  rbx at 0x7fffffffe0e8, rbp at 0x7fffffffe0f0, rip at 0x7fffffffe0f8
+";
+        let (cfa, saved) = parse_info_frame(output);
+        assert_eq!(cfa, Some(Address(0x7fffffffe100)));
+        assert_eq!(
+            saved,
+            vec![
+                ("rbx".to_owned(), Address(0x7fffffffe0e8)),
+                ("rbp".to_owned(), Address(0x7fffffffe0f0)),
+                ("rip".to_owned(), Address(0x7fffffffe0f8)),
+            ]
+        );
+        assert_eq!(parse_info_frame("No stack.\n"), (None, Vec::new()));
+    }
+
+    #[test]
+    fn test_parse_local_gdbinit() {
+        assert_eq!(
+            parse_local_gdbinit("Local .gdbinit file \"/src/.gdbinit\" has been loaded.\n"),
+            Some(PathBuf::from("/src/.gdbinit"))
+        );
+        assert_eq!(
+            parse_local_gdbinit("Local .gdbinit file \"/src/.gdbinit\" has not been loaded.\n"),
+            None
+        );
+        assert_eq!(
+            parse_local_gdbinit("Local .gdbinit file was not found.\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_file_argument() {
+        assert_eq!(file_argument(Path::new("/tmp/heap.bin")), "/tmp/heap.bin");
+        assert_eq!(
+            file_argument(Path::new("/tmp/my dump.bin")),
+            "\"/tmp/my dump.bin\""
+        );
+    }
+
+    #[test]
+    fn test_is_native_target_stack() {
+        assert!(is_native_target_stack(
+            "The current target stack is:
+  - native (Native process)
+  - exec (Local exec file)
+  - None (None)
+"
+        ));
+        assert!(!is_native_target_stack(
+            "The current target stack is:
+  - extended-remote (Extended remote target using gdb-specific protocol)
+  - exec (Local exec file)
+  - None (None)
+"
+        ));
+    }
+
+    #[test]
+    fn test_parse_skips() {
+        let output = "Num   Enb Glob File                 RE Function
+1     y      n <none>                 y ^std::
+2     n      n <none>                 n operator new(unsigned long)
+3     y      y /usr/include/*         n <none>
+4     y      n /tmp/a.c               n <none>
+";
+        let skip = |number, enabled, kind, pattern: &str| Skip {
+            number,
+            enabled,
+            kind,
+            pattern: pattern.to_owned(),
+        };
+        assert_eq!(
+            parse_skips(output),
+            vec![
+                skip(1, true, SkipKind::FunctionRegex, "^std::"),
+                skip(2, false, SkipKind::Function, "operator new(unsigned long)"),
+                skip(3, true, SkipKind::FileGlob, "/usr/include/*"),
+                skip(4, true, SkipKind::File, "/tmp/a.c"),
+            ]
+        );
+        assert!(parse_skips("Not skipping any files or functions.").is_empty());
+        assert_eq!(quote_argument(r#"a\"b"#), r#""a\\\"b""#);
+    }
+
+    #[test]
+    fn test_parse_libraries_without_debug_info() {
+        let output = "From                To                  Syms Read   Shared Object Library
+0x00007ffff7fc5090  0x00007ffff7fee315  Yes         /lib64/ld-linux-x86-64.so.2
+0x00007ffff7daf700  0x00007ffff7f4193d  Yes (*)     /lib/x86_64-linux-gnu/libc.so.6
+                                        No          /usr/lib/libfoo.so
+(*): Shared library is missing debugging information.
+";
+        assert_eq!(
+            parse_libraries_without_debug_info(output),
+            vec!["/lib/x86_64-linux-gnu/libc.so.6"]
+        );
+    }
+
+    #[test]
+    fn test_num_split_lines() {
+        let entry = |line, begin, end| LineTableEntry {
+            line: LineNumber::new(line),
+            begin: Address(begin),
+            end: Address(end),
+            is_stmt: None,
+        };
+        let table = FunctionLineTable {
+            function: "main".to_owned(),
+            file: PathBuf::from("a.c"),
+            entries: vec![
+                entry(3, 0x10, 0x14),
+                entry(3, 0x14, 0x18),
+                entry(4, 0x18, 0x20),
+                entry(5, 0x20, 0x24),
+                entry(4, 0x24, 0x28),
+            ],
+        };
+        assert_eq!(table.num_split_lines(), 1);
+        assert_eq!(table.last_line(), Some(LineNumber::new(5)));
+    }
+
+    #[test]
+    fn test_parse_line_table_without_is_stmt() {
+        let output = "INDEX    LINE ADDRESS
+0          12 0x0000000000401126
+1         END 0x000000000040112e
+";
+        assert_eq!(
+            parse_line_table(output),
+            vec![LineTableEntry {
+                line: LineNumber::new(12),
+                begin: Address(0x401126),
+                end: Address(0x40112e),
+                is_stmt: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_print_settings() {
+        let mut settings = PrintSettings {
+            pretty: true,
+            elements: Some(200),
+            array: false,
+            null_stop: false,
+        };
+        assert_eq!(
+            settings.to_string(),
+            "pretty: on, elements: 200, array: off, null-stop: off"
+        );
+        let mut steps = Vec::new();
+        for _ in 0..4 {
+            settings.elements = settings.next_elements();
+            steps.push(settings.elements);
+        }
+        assert_eq!(steps, vec![Some(1000), None, Some(20), Some(200)]);
+        settings.elements = Some(42);
+        assert_eq!(settings.next_elements(), Some(200));
+    }
+
+    #[test]
+    fn test_inferior_set() {
+        let id = |id: &str| id.to_owned();
+        let mut inferiors = InferiorSet::new();
+        inferiors.handle_notification(&Notification::ThreadGroupAdded { id: id("i1") });
+        inferiors.handle_notification(&Notification::ThreadGroupStarted {
+            id: id("i1"),
+            pid: id("100"),
+        });
+        inferiors.handle_notification(&Notification::ThreadCreated {
+            id: id("1"),
+            group_id: id("i1"),
+        });
+        // After a fork with detach-on-fork off
+        inferiors.handle_notification(&Notification::ThreadGroupAdded { id: id("i2") });
+        inferiors.handle_notification(&Notification::ThreadGroupStarted {
+            id: id("i2"),
+            pid: id("101"),
+        });
+        inferiors.handle_notification(&Notification::ThreadCreated {
+            id: id("2"),
+            group_id: id("i2"),
+        });
+        assert_eq!(inferiors.len(), 2);
+        assert_eq!(inferiors.selected(), Some("i1"));
+        assert_eq!(inferiors.group_of_thread("2"), Some("i2"));
+
+        inferiors.handle_notification(&Notification::ThreadSelected {
+            id: id("2"),
+            frame: None,
+        });
+        assert_eq!(inferiors.selected(), Some("i2"));
+
+        inferiors.handle_notification(&Notification::ThreadGroupExited {
+            id: id("i2"),
+            exit_code: Some(id("3")),
+        });
+        assert_eq!(inferiors.group_of_thread("2"), None);
+        assert_eq!(
+            inferiors
+                .iter()
+                .map(|i| i.state.to_string())
+                .collect::<Vec<_>>(),
+            vec!["process 100", "exited with code 3"]
+        );
+
+        inferiors.handle_notification(&Notification::ThreadGroupRemoved { id: id("i2") });
+        assert_eq!(inferiors.len(), 1);
+        assert_eq!(inferiors.selected(), None);
+        assert_eq!(inferiors.iter().next().unwrap().number(), "1");
+    }
+
+    #[test]
+    fn test_parse_hex_bytes() {
+        assert_eq!(parse_hex_bytes("00ff7f"), Some(vec![0x00, 0xff, 0x7f]));
+        assert_eq!(parse_hex_bytes(""), Some(vec![]));
+        assert_eq!(parse_hex_bytes("0"), None);
+        assert_eq!(parse_hex_bytes("zz"), None);
+    }
+}
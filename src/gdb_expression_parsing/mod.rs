@@ -2,6 +2,8 @@ mod ast;
 mod lexer;
 mod parser;
 
+use number_format::NumberFormat;
+
 const ANON_KEY: &'static str = "*anon*";
 
 pub type ParseError = parser::Error;
@@ -30,6 +32,19 @@ pub enum Format {
 pub struct Value<'s> {
     pub node: &'s Node<'s>,
     pub format: Option<Format>,
+    pub truncation: Option<usize>, // Maximum number of characters of leaf values
+    pub numbers: NumberFormat,
+}
+
+fn truncate(s: String, max_chars: Option<usize>) -> String {
+    match max_chars {
+        Some(max) if s.chars().count() > max => {
+            let mut truncated = s.chars().take(max).collect::<String>();
+            truncated.push('…');
+            truncated
+        }
+        _ => s,
+    }
 }
 
 impl<'n> unsegen_jsonviewer::Value for Value<'n> {
@@ -49,7 +64,10 @@ impl<'n> unsegen_jsonviewer::Value for Value<'n> {
                 } else {
                     s.to_string()
                 };
-                unsegen_jsonviewer::ValueVariant::Scalar(res)
+                unsegen_jsonviewer::ValueVariant::Scalar(truncate(
+                    self.numbers.value(&res),
+                    self.truncation,
+                ))
             }
             Node::Map(description, items) => unsegen_jsonviewer::ValueVariant::Map(
                 description.map(|s| s.to_owned()),
@@ -59,6 +77,8 @@ impl<'n> unsegen_jsonviewer::Value for Value<'n> {
                         Value {
                             node: v,
                             format: self.format,
+                            truncation: self.truncation,
+                            numbers: self.numbers,
                         },
                     )
                 })),
@@ -68,6 +88,8 @@ impl<'n> unsegen_jsonviewer::Value for Value<'n> {
                 Box::new(items.iter().map(move |v| Value {
                     node: v,
                     format: self.format,
+                    truncation: self.truncation,
+                    numbers: self.numbers,
                 })),
             ),
         }
@@ -78,6 +100,13 @@ impl<'n> unsegen_jsonviewer::Value for Value<'n> {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("\"abcdef\"".to_owned(), Some(4)), "\"abc…");
+        assert_eq!(truncate("\"äöü\"".to_owned(), Some(5)), "\"äöü\"");
+        assert_eq!(truncate("12345".to_owned(), None), "12345");
+    }
+
     #[test]
     fn test_parse_basic() {
         assert_eq!(parse_gdb_value("true").unwrap(), Node::Leaf("true"));
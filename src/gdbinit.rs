@@ -0,0 +1,168 @@
+// Breakpoints and settings from the init files that gdb executes on startup (e.g., `~/.gdbinit`).
+// They are created before ugdb talks to gdb, so ugdb reads the same files to know about them: The
+// settings are adopted as if they were configured for ugdb (and applied again if gdb is
+// restarted) and the breakpoints are listed by gdb once it is running.
+use gdb::DisassemblyFlavor;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InitCommands {
+    /// Locations (or expressions of watchpoints) as written in the file.
+    pub breakpoints: Vec<String>,
+    pub disassembly_flavor: Option<DisassemblyFlavor>,
+    pub print_pretty: Option<bool>,
+    pub path_substitutions: Vec<(PathBuf, PathBuf)>,
+}
+
+impl InitCommands {
+    pub fn is_empty(&self) -> bool {
+        *self == InitCommands::default()
+    }
+
+    fn merge(&mut self, other: InitCommands) {
+        self.breakpoints.extend(other.breakpoints);
+        self.disassembly_flavor = other.disassembly_flavor.or(self.disassembly_flavor);
+        self.print_pretty = other.print_pretty.or(self.print_pretty);
+        self.path_substitutions.extend(other.path_substitutions);
+    }
+}
+
+// Commands that start a block of lines that are not executed right away (or only conditionally)
+// and that is terminated by "end".
+const BLOCK_COMMANDS: &[&str] = &[
+    "define", "document", "commands", "if", "while", "python", "guile", "compile",
+];
+// Block commands that are executed right away if they have arguments.
+const INLINE_COMMANDS: &[&str] = &["python", "guile", "compile"];
+// Abbreviations that gdb accepts for the commands that create breakpoints.
+const BREAK_COMMANDS: &[&str] = &[
+    "b", "br", "bre", "brea", "break", "tb", "tbr", "tbreak", "hb", "hbreak", "thb", "thbreak",
+    "watch", "rwatch", "awatch", "wa",
+];
+
+fn parse_on_off(value: &str) -> Option<bool> {
+    match value {
+        "" | "on" | "1" | "yes" | "enable" => Some(true),
+        "off" | "0" | "no" | "disable" => Some(false),
+        _ => None,
+    }
+}
+
+/// Breakpoints and settings created by the commands of an init file. Commands in definitions,
+/// breakpoint command lists and conditional blocks are ignored.
+pub fn parse(content: &str) -> InitCommands {
+    let mut commands = InitCommands::default();
+    let mut depth = 0;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (command, args) = match line.split_once(char::is_whitespace) {
+            Some((command, args)) => (command, args.trim()),
+            None => (line, ""),
+        };
+        // "python print(1)" is executed right away, a "python" line starts a block.
+        let starts_block = BLOCK_COMMANDS.contains(&command)
+            && (args.is_empty() || !INLINE_COMMANDS.contains(&command));
+        if starts_block {
+            depth += 1;
+            continue;
+        }
+        if depth > 0 {
+            if command == "end" {
+                depth -= 1;
+            }
+            continue;
+        }
+        if BREAK_COMMANDS.contains(&command) && !args.is_empty() {
+            commands.breakpoints.push(args.to_owned());
+            continue;
+        }
+        if command != "set" {
+            continue;
+        }
+        let mut words = args.split_whitespace();
+        match (words.next(), words.next(), words.next()) {
+            (Some("disassembly-flavor"), Some(flavor), None) => {
+                commands.disassembly_flavor = flavor.parse().ok().or(commands.disassembly_flavor)
+            }
+            (Some("print"), Some("pretty"), value) => {
+                commands.print_pretty = parse_on_off(value.unwrap_or("")).or(commands.print_pretty)
+            }
+            (Some("substitute-path"), Some(from), Some(to)) => commands
+                .path_substitutions
+                .push((PathBuf::from(from), PathBuf::from(to))),
+            _ => {}
+        }
+    }
+    commands
+}
+
+/// The init file in the home directory that gdb executes (the first one that exists, as in gdb
+/// 11 or newer).
+pub fn home_init_file(config_home: Option<&Path>, home: Option<&Path>) -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(config_home) = config_home {
+        candidates.push(config_home.join("gdb/gdbinit"));
+    }
+    if let Some(home) = home {
+        candidates.push(home.join(".config/gdb/gdbinit"));
+        candidates.push(home.join(".gdbinit"));
+    }
+    candidates.into_iter().find(|c| c.is_file())
+}
+
+/// Read and parse the init files. Files that cannot be read are skipped.
+pub fn read(files: &[PathBuf]) -> InitCommands {
+    let mut commands = InitCommands::default();
+    for file in files {
+        if let Ok(content) = fs::read(file) {
+            commands.merge(parse(&String::from_utf8_lossy(&content)));
+        }
+    }
+    commands
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let content = "# Breakpoints
+b main
+tbreak foo.c:12 if x > 1
+break
+watch counter
+set disassembly-flavor intel
+set print pretty
+set substitute-path /build /home/user/src
+define hook-stop
+  break bar
+  set print pretty off
+end
+commands 1
+  silent
+end
+python
+gdb.execute('break baz')
+end
+python print(1)
+set print pretty off
+";
+        let commands = parse(content);
+        assert_eq!(
+            commands.breakpoints,
+            vec!["main", "foo.c:12 if x > 1", "counter"]
+        );
+        assert_eq!(commands.disassembly_flavor, Some(DisassemblyFlavor::Intel));
+        assert_eq!(commands.print_pretty, Some(false));
+        assert_eq!(
+            commands.path_substitutions,
+            vec![(PathBuf::from("/build"), PathBuf::from("/home/user/src"))]
+        );
+        assert!(parse("set confirm off\n").is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+// What the debugger behind the MI connection can do. ugdb is written for gdb, but lldb-mi (e.g.,
+// on macOS, where gdb often does not work) implements the MI commands for the basics: running
+// the program, breakpoints, the stack, variables and memory. It does not understand gdb's console
+// commands, though, which a number of features are built on.
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Gdb,
+    LldbMi,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Backend::Gdb => write!(f, "gdb"),
+            Backend::LldbMi => write!(f, "lldb-mi"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// gdb's console commands and settings (e.g., `info frame`, `skip` or `dump`).
+    GdbCommands,
+    /// Reading raw memory (`-data-read-memory-bytes`).
+    ReadMemoryBytes,
+    /// Python pretty printers (`-enable-pretty-printing`).
+    PrettyPrinters,
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Feature::GdbCommands => write!(f, "gdb commands"),
+            Feature::ReadMemoryBytes => write!(f, "reading memory"),
+            Feature::PrettyPrinters => write!(f, "pretty printers"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    pub backend: Backend,
+    /// As reported by `-list-features`.
+    listed: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn new(backend: Backend, listed: Vec<String>) -> Self {
+        Capabilities { backend, listed }
+    }
+
+    fn is_listed(&self, feature: &str) -> bool {
+        self.listed.iter().any(|f| f == feature)
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        match (self.backend, feature) {
+            (Backend::Gdb, Feature::GdbCommands) => true,
+            (Backend::LldbMi, Feature::GdbCommands) => false,
+            // Older versions of gdb support it without listing it.
+            (Backend::Gdb, Feature::ReadMemoryBytes) => true,
+            (Backend::LldbMi, Feature::ReadMemoryBytes) => self.is_listed("data-read-memory-bytes"),
+            (_, Feature::PrettyPrinters) => self.is_listed("python"),
+        }
+    }
+
+    /// Why something that requires the feature cannot be done, e.g., for the console.
+    pub fn unsupported(&self, feature: Feature) -> String {
+        format!("{} does not support {}.", self.backend, feature)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_supports() {
+        let gdb = Capabilities::new(Backend::Gdb, vec!["python".to_owned()]);
+        assert!(gdb.supports(Feature::GdbCommands));
+        assert!(gdb.supports(Feature::ReadMemoryBytes));
+        assert!(gdb.supports(Feature::PrettyPrinters));
+
+        let lldb = Capabilities::new(Backend::LldbMi, vec!["data-read-memory-bytes".to_owned()]);
+        assert!(!lldb.supports(Feature::GdbCommands));
+        assert!(lldb.supports(Feature::ReadMemoryBytes));
+        assert!(!lldb.supports(Feature::PrettyPrinters));
+        assert_eq!(
+            lldb.unsupported(Feature::GdbCommands),
+            "lldb-mi does not support gdb commands."
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use std::ffi::OsString;
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
 use std::fmt;
 use std::io::{Error, Write};
 use std::path::Path;
@@ -23,10 +24,19 @@ pub enum WatchMode {
     Access,
 }
 
+/// Values included in lists of variables. Simple values omit the values of arrays, structs and
+/// unions (but include the types of all variables).
+pub enum PrintValues {
+    Simple,
+    All,
+}
+
 pub enum BreakPointLocation<'a> {
     Address(usize),
     Function(&'a Path, &'a str),
     Line(&'a Path, usize),
+    /// A function (or any other location that gdb understands) without a file, e.g., "main".
+    Symbol(&'a str),
 }
 
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
@@ -85,24 +95,39 @@ fn escape_command(input: &str) -> String {
     output
 }
 
+#[cfg(unix)]
+fn os_str_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(s.as_bytes())
+}
+
+// Windows paths are UTF-16 and (MinGW) gdb expects UTF-8 (or the ANSI code page, which we cannot
+// do much about anyway).
+#[cfg(windows)]
+fn os_str_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    match s.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
+
 impl MiCommand {
     pub fn write_interpreter_string<S: Write>(
         &self,
         sink: &mut S,
         token: super::Token,
     ) -> Result<(), Error> {
-        use std::os::unix::ffi::OsStrExt;
         write!(sink, "{}-{}", token, self.operation)?;
         for option in &self.options {
             write!(sink, " ")?;
-            sink.write_all(option.as_bytes())?;
+            sink.write_all(&os_str_bytes(option))?;
         }
         if !self.parameters.is_empty() && !self.options.is_empty() {
             write!(sink, " --")?;
         }
         for parameter in &self.parameters {
             write!(sink, " ")?;
-            sink.write_all(parameter.as_bytes())?;
+            sink.write_all(&os_str_bytes(parameter))?;
         }
         write!(sink, "\n")?;
         Ok(())
@@ -122,6 +147,14 @@ impl MiCommand {
         Self::interpreter_exec("console".to_owned(), escape_command(&command))
     }
 
+    pub fn complete(command: &str) -> MiCommand {
+        MiCommand {
+            operation: "complete",
+            options: vec![escape_command(command).into()],
+            parameters: Vec::new(),
+        }
+    }
+
     pub fn data_disassemble_file<P: AsRef<Path>>(
         file: P,
         linenum: usize,
@@ -159,6 +192,14 @@ impl MiCommand {
         }
     }
 
+    pub fn data_disassemble_function(address: usize, mode: DisassembleMode) -> MiCommand {
+        MiCommand {
+            operation: "data-disassemble",
+            options: vec![OsString::from("-a"), OsString::from(address.to_string())],
+            parameters: vec![OsString::from((mode as u8).to_string())],
+        }
+    }
+
     pub fn data_evaluate_expression(expression: String) -> MiCommand {
         MiCommand {
             operation: "data-evaluate-expression",
@@ -167,6 +208,17 @@ impl MiCommand {
         }
     }
 
+    pub fn data_read_memory_bytes(address: usize, count: usize) -> MiCommand {
+        MiCommand {
+            operation: "data-read-memory-bytes",
+            options: Vec::new(),
+            parameters: vec![
+                OsString::from(format!("0x{:x}", address)),
+                OsString::from(count.to_string()),
+            ],
+        }
+    }
+
     pub fn insert_breakpoint(location: BreakPointLocation) -> MiCommand {
         MiCommand {
             operation: "break-insert",
@@ -200,11 +252,64 @@ impl MiCommand {
                     //OsString::from(format!("{}", line_number)),
                     //],
                 }
+                BreakPointLocation::Symbol(location) => vec![OsString::from(location)],
             },
             parameters: Vec::new(),
         }
     }
 
+    /// Like `insert_breakpoint`, but the breakpoint is created disabled.
+    pub fn insert_disabled_breakpoint(location: BreakPointLocation) -> MiCommand {
+        let mut command = Self::insert_breakpoint(location);
+        command.options.insert(0, OsString::from("-d"));
+        command
+    }
+
+    /// Like `insert_breakpoint`, but the breakpoint is deleted once it is hit.
+    pub fn insert_temporary_breakpoint(location: BreakPointLocation) -> MiCommand {
+        let mut command = Self::insert_breakpoint(location);
+        command.options.insert(0, OsString::from("-t"));
+        command
+    }
+
+    /// Only stop at the inserted breakpoint if the condition is true.
+    pub fn with_condition(mut self, condition: &str) -> MiCommand {
+        debug_assert_eq!(self.operation, "break-insert");
+        self.options.insert(0, escape_command(condition).into());
+        self.options.insert(0, OsString::from("-c"));
+        self
+    }
+
+    pub fn break_list() -> MiCommand {
+        MiCommand {
+            operation: "break-list",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn break_after(breakpoint_major: usize, count: u64) -> MiCommand {
+        MiCommand {
+            operation: "break-after",
+            options: Vec::new(),
+            parameters: vec![
+                OsString::from(breakpoint_major.to_string()),
+                OsString::from(count.to_string()),
+            ],
+        }
+    }
+
+    /// Replace the commands of the breakpoint. No commands remove all of them.
+    pub fn break_commands(breakpoint_major: usize, commands: &[String]) -> MiCommand {
+        let mut parameters = vec![OsString::from(breakpoint_major.to_string())];
+        parameters.extend(commands.iter().map(|c| escape_command(c).into()));
+        MiCommand {
+            operation: "break-commands",
+            options: Vec::new(),
+            parameters,
+        }
+    }
+
     pub fn delete_breakpoints<I: Iterator<Item = BreakPointNumber>>(
         breakpoint_numbers: I,
     ) -> MiCommand {
@@ -235,6 +340,54 @@ impl MiCommand {
         }
     }
 
+    pub fn gdb_set(variable: &str, value: &str) -> MiCommand {
+        MiCommand {
+            operation: "gdb-set",
+            options: vec![OsString::from(variable), OsString::from(value)],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn gdb_show(variable: &str) -> MiCommand {
+        MiCommand {
+            operation: "gdb-show",
+            options: vec![OsString::from(variable)],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn inferior_tty_set(tty: &Path) -> MiCommand {
+        MiCommand {
+            operation: "inferior-tty-set",
+            options: vec![OsString::from(tty)],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn environment_cd(dir: &Path) -> MiCommand {
+        MiCommand {
+            operation: "environment-cd",
+            options: vec![OsString::from(dir)],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn target_attach(pid: u32) -> MiCommand {
+        MiCommand {
+            operation: "target-attach",
+            options: vec![pid.to_string().into()],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn list_features() -> MiCommand {
+        MiCommand {
+            operation: "list-features",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
     pub fn environment_pwd() -> MiCommand {
         MiCommand {
             operation: "environment-pwd",
@@ -243,8 +396,9 @@ impl MiCommand {
         }
     }
 
-    // Be aware: This does not seem to always interrupt execution.
-    // Use gdb.interrupt_execution instead.
+    // Be aware: This does not seem to always interrupt execution (and only works if mi-async is
+    // on). Use gdb.interrupt_execution instead, which falls back to this for gdbs that we did not
+    // start.
     pub fn exec_interrupt() -> MiCommand {
         MiCommand {
             operation: "exec-interrupt",
@@ -253,6 +407,32 @@ impl MiCommand {
         }
     }
 
+    pub fn exec_continue() -> MiCommand {
+        MiCommand {
+            operation: "exec-continue",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Run until the function of the selected frame returns.
+    pub fn exec_finish() -> MiCommand {
+        MiCommand {
+            operation: "exec-finish",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Resume execution at the location (e.g., "*0x401000").
+    pub fn exec_jump(location: &str) -> MiCommand {
+        MiCommand {
+            operation: "exec-jump",
+            options: vec![OsString::from(location)],
+            parameters: Vec::new(),
+        }
+    }
+
     // Warning: This cannot be used to pass special characters like \n to gdb because
     // (unlike it is said in the spec) there is apparently no way to pass \n unescaped
     // to gdb, and for "exec-arguments" gdb somehow does not unescape these chars...
@@ -300,9 +480,18 @@ impl MiCommand {
         }
     }
 
+    pub fn stack_list_frames(low_frame: u64, high_frame: u64) -> MiCommand {
+        MiCommand {
+            operation: "stack-list-frames",
+            options: vec![low_frame.to_string().into(), high_frame.to_string().into()],
+            parameters: Vec::new(),
+        }
+    }
+
     pub fn stack_list_variables(
         thread_number: Option<u64>,
         frame_number: Option<u64>,
+        values: PrintValues,
     ) -> MiCommand {
         let mut parameters = vec![];
         if let Some(thread_number) = thread_number {
@@ -313,7 +502,13 @@ impl MiCommand {
             parameters.push("--frame".into());
             parameters.push(frame_number.to_string().into());
         }
-        parameters.push("--simple-values".into()); //TODO: make configurable if required.
+        parameters.push(
+            match values {
+                PrintValues::Simple => "--simple-values",
+                PrintValues::All => "--all-values",
+            }
+            .into(),
+        );
         MiCommand {
             operation: "stack-list-variables",
             options: Vec::new(),
@@ -321,6 +516,47 @@ impl MiCommand {
         }
     }
 
+    pub fn file_list_exec_source_files() -> MiCommand {
+        MiCommand {
+            operation: "file-list-exec-source-files",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn symbol_info_functions(name_regex: &str) -> MiCommand {
+        MiCommand {
+            operation: "symbol-info-functions",
+            options: vec!["--name".into(), escape_command(name_regex).into()],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn data_list_register_names() -> MiCommand {
+        MiCommand {
+            operation: "data-list-register-names",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// The values of all (available) registers in hex.
+    pub fn data_list_register_values() -> MiCommand {
+        MiCommand {
+            operation: "data-list-register-values",
+            options: vec!["--skip-unavailable".into(), "x".into()],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn thread_select(thread_id: &str) -> MiCommand {
+        MiCommand {
+            operation: "thread-select",
+            options: vec![thread_id.into()],
+            parameters: Vec::new(),
+        }
+    }
+
     pub fn thread_info(thread_id: Option<u64>) -> MiCommand {
         MiCommand {
             operation: "thread-info",
@@ -368,6 +604,31 @@ impl MiCommand {
         }
     }
 
+    /// All inferiors with their threads (`--recurse 1`).
+    pub fn list_thread_groups_with_threads() -> MiCommand {
+        MiCommand {
+            operation: "list-thread-groups",
+            options: vec![OsString::from("--recurse"), OsString::from("1")],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn file_list_shared_libraries() -> MiCommand {
+        MiCommand {
+            operation: "file-list-shared-libraries",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn enable_pretty_printing() -> MiCommand {
+        MiCommand {
+            operation: "enable-pretty-printing",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
     pub fn var_create(
         name: Option<OsString>, /*none: generate name*/
         expression: &str,
@@ -1,38 +1,256 @@
+pub mod capabilities;
 pub mod commands;
 pub mod output;
+pub mod traffic_log;
+pub mod types;
+#[cfg(windows)]
+mod windows;
 
-use log::info;
-use std::ffi::OsString;
+use self::capabilities::{Backend, Capabilities};
+use log::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::io::{self, Read, Write};
 use std::path::Path;
 use std::path::PathBuf;
-use std::process::{Child, ChildStdin, Command, Stdio};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{mpsc, Arc};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
 type Token = u64;
 
+/// A command whose result is not waited for (see `GDB::execute_in_background`).
+pub struct BackgroundCommand(Token);
+
 pub struct GDB {
-    pub process: Child,
-    stdin: ChildStdin,
+    pub process: Process,
+    writer: Arc<CommandWriter>,
     is_running: Arc<AtomicBool>,
-    result_output: mpsc::Receiver<output::ResultRecord>,
-    current_command_token: Token,
+    result_output: mpsc::Receiver<output::ResultOutput>,
+    console_capture: Arc<Mutex<Option<String>>>, // Console output is collected here if set
     binary_path: PathBuf,
     init_options: Vec<OsString>,
+    pending_results: PendingResults,
+    termination: Arc<Mutex<Option<Error>>>, // Set by the output thread if it gives up on gdb
+    capabilities: Capabilities,
     //outputThread: thread::Thread,
 }
 
+/// The process that ugdb talks to.
+pub enum Process {
+    /// gdb (or rr) itself, which was started by ugdb.
+    Spawned(Child),
+    /// A command that forwards MI to and from a gdb that was started by someone else.
+    Proxy(Child),
+    /// A gdb that was started by someone else and opened an MI interpreter on a pty of ugdb.
+    Connected,
+}
+
+impl Process {
+    /// Kill the process. A connected gdb is left alone.
+    pub fn kill(&mut self) -> io::Result<()> {
+        match self {
+            Process::Spawned(child) | Process::Proxy(child) => child.kill(),
+            Process::Connected => Ok(()),
+        }
+    }
+
+    /// The exit status if the process exited. ugdb cannot wait for a connected gdb, which is
+    /// considered to be gone (successfully) once its output ends.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        match self {
+            Process::Spawned(child) | Process::Proxy(child) => child.try_wait(),
+            Process::Connected => Ok(Some(ExitStatus::default())),
+        }
+    }
+}
+
+/// How to reach a gdb that was started by someone else (e.g., `rr replay` or a flashing tool).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Connection {
+    /// Wait for gdb to open a new MI interpreter on a pty of ugdb (`new-ui mi <pty>`).
+    NewUi,
+    /// Run a shell command that forwards MI commands to gdb and its output back (e.g., `socat`).
+    Command(String),
+}
+
+/// Result records that arrived while waiting for the result of another command, keyed by the
+/// token of the command that they belong to.
+#[derive(Default)]
+struct PendingResults {
+    received: HashMap<Token, output::ResultRecord>,
+    discarded: HashSet<Token>,
+    outstanding: HashSet<Token>, // Commands that were sent, but whose result did not arrive yet
+}
+
+impl PendingResults {
+    fn sent(&mut self, token: Token) {
+        self.outstanding.insert(token);
+    }
+
+    fn take(&mut self, token: Token) -> Option<output::ResultRecord> {
+        self.received.remove(&token)
+    }
+
+    /// Do not keep the result for the token (if it already arrived or whenever it arrives).
+    fn discard(&mut self, token: Token) {
+        if self.received.remove(&token).is_none() {
+            self.discarded.insert(token);
+        }
+    }
+
+    /// Do not keep the results of any command that was sent so far.
+    fn discard_all(&mut self) {
+        self.received.clear();
+        self.discarded.extend(self.outstanding.iter().cloned());
+    }
+
+    /// Whether the results of all commands that were sent have arrived.
+    fn is_synchronized(&self) -> bool {
+        self.outstanding.is_empty()
+    }
+
+    /// Return the record if it is the result for the expected token, otherwise keep it for later.
+    fn accept(
+        &mut self,
+        record: output::ResultRecord,
+        expected: Option<Token>,
+    ) -> Option<output::ResultRecord> {
+        if let Some(token) = record.token {
+            self.outstanding.remove(&token);
+        }
+        match record.token {
+            Some(token) if Some(token) == expected => Some(record),
+            Some(token) if self.discarded.remove(&token) => None,
+            Some(token) => {
+                self.received.insert(token, record);
+                None
+            }
+            None => {
+                info!(
+                    "Record without token (expected {:?}) will be dropped: {:?}",
+                    expected, record
+                );
+                None
+            }
+        }
+    }
+}
+
 pub trait OutOfBandRecordSink: std::marker::Send {
     fn send(&self, record: output::OutOfBandRecord);
+
+    /// Commands that the output thread sends to gdb by itself when the record arrives, e.g., to
+    /// query the state of the program when it stopped. The record is then passed on together with
+    /// their results (see `send_with_results`), so that the receiver does not have to wait for gdb.
+    fn queries(
+        &self,
+        _record: &output::OutOfBandRecord,
+        _is_running: bool,
+    ) -> Vec<commands::MiCommand> {
+        Vec::new()
+    }
+
+    /// Pass on a record together with the results of its `queries` (in the same order). Records
+    /// are passed on in the order in which they arrived, i.e., records following one with queries
+    /// are held back until the results arrived.
+    fn send_with_results(
+        &self,
+        record: output::OutOfBandRecord,
+        _results: Vec<output::ResultRecord>,
+    ) {
+        self.send(record);
+    }
+}
+
+/// Writes commands to gdb. It is shared between `GDB` and the output thread, which sends the
+/// queries of the out of band record sink.
+pub struct CommandWriter {
+    stdin: Mutex<Box<dyn Write + Send>>,
+    last_token: AtomicU64,
+    traffic_log: Option<traffic_log::TrafficLog>,
+}
+
+impl CommandWriter {
+    fn new(stdin: Box<dyn Write + Send>, traffic_log: Option<traffic_log::TrafficLog>) -> Self {
+        CommandWriter {
+            stdin: Mutex::new(stdin),
+            last_token: AtomicU64::new(0),
+            traffic_log,
+        }
+    }
+
+    fn next_token(&self) -> Token {
+        self.last_token
+            .fetch_add(1, Ordering::SeqCst)
+            .wrapping_add(1)
+    }
+
+    fn write(&self, command: &commands::MiCommand, token: Token) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        command.write_interpreter_string(&mut bytes, token)?;
+        info!("Writing msg {}", String::from_utf8_lossy(&bytes));
+        if let Some(log) = &self.traffic_log {
+            log.record_command(&String::from_utf8_lossy(&bytes));
+        }
+        let mut stdin = self.stdin.lock().unwrap();
+        stdin.write_all(&bytes)?;
+        stdin.flush()
+    }
+
+    /// Write the commands with new tokens, which are returned in the same order.
+    fn write_all(&self, commands: &[commands::MiCommand]) -> io::Result<Vec<Token>> {
+        commands
+            .iter()
+            .map(|command| {
+                let token = self.next_token();
+                self.write(command, token).map(|_| token)
+            })
+            .collect()
+    }
+}
+
+/// Reasons why gdb could not be started or why communication with it broke down for good.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Spawn(String),
+    BrokenPipe(String),
+    Parse(String),
+    UnexpectedEof(Option<i32>), // Exit code of gdb, if it is known
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Spawn(msg) => write!(f, "failed to spawn gdb: {}", msg),
+            Error::BrokenPipe(msg) => write!(f, "failed to write to gdb: {}", msg),
+            Error::Parse(msg) => write!(f, "failed to read output of gdb: {}", msg),
+            Error::UnexpectedEof(Some(code)) => {
+                write!(f, "gdb exited unexpectedly (code {})", code)
+            }
+            Error::UnexpectedEof(None) => write!(f, "gdb exited unexpectedly"),
+        }
+    }
+}
+
+impl From<::std::io::Error> for Error {
+    fn from(e: ::std::io::Error) -> Self {
+        Error::Spawn(e.to_string())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ExecuteError {
     Busy,
-    Quit,
+    Quit(Error),
+    Timeout,
 }
 
+#[derive(Clone)]
 pub struct GDBBuilder {
     gdb_path: PathBuf,
     opt_nh: bool,
@@ -48,6 +266,9 @@ pub struct GDBBuilder {
     opt_args: Vec<OsString>,
     opt_program: Option<PathBuf>,
     opt_tty: Option<PathBuf>,
+    opt_traffic_log: Option<PathBuf>,
+    opt_connection: Option<Connection>,
+    backend: Backend,
     rr_args: Option<(PathBuf, Vec<OsString>)>,
 }
 impl GDBBuilder {
@@ -67,6 +288,9 @@ impl GDBBuilder {
             opt_args: Vec::new(),
             opt_program: None,
             opt_tty: None,
+            opt_traffic_log: None,
+            opt_connection: None,
+            backend: Backend::Gdb,
             rr_args: None,
         }
     }
@@ -127,7 +351,29 @@ impl GDBBuilder {
         self.opt_tty = Some(tty);
         self
     }
-    pub fn try_spawn<S>(self, oob_sink: S) -> Result<GDB, ::std::io::Error>
+    /// Record all commands and output to the given file (see `traffic_log::TrafficLog`).
+    pub fn traffic_log(mut self, file: PathBuf) -> Self {
+        self.opt_traffic_log = Some(file);
+        self
+    }
+    /// Talk to a gdb that was started by someone else instead of starting one. Options that
+    /// would be passed to gdb are ignored, except for the tty of the program.
+    pub fn connect(mut self, connection: Connection) -> Self {
+        self.opt_connection = Some(connection);
+        self
+    }
+    /// Start lldb-mi instead of gdb. The program (and its arguments, working directory and tty)
+    /// are passed using MI commands, other options of gdb are not supported.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+    /// Whether gdb can be started (or connected to) again after it terminated. A gdb that opened
+    /// an interpreter on our pty does not do so again.
+    pub fn can_restart(&self) -> bool {
+        self.opt_connection != Some(Connection::NewUi)
+    }
+    pub fn try_spawn<S>(self, oob_sink: S) -> Result<GDB, Error>
     where
         S: OutOfBandRecordSink + 'static,
     {
@@ -144,49 +390,192 @@ impl GDBBuilder {
         if self.opt_quiet {
             gdb_args.push("--quiet".into());
         }
-        if let Some(cd) = self.opt_cd {
+        if let Some(cd) = &self.opt_cd {
             gdb_args.push("--cd=".into());
-            gdb_args.last_mut().unwrap().push(&cd);
+            gdb_args.last_mut().unwrap().push(cd);
         }
         if let Some(bps) = self.opt_bps {
             gdb_args.push("-b".into());
             gdb_args.push(bps.to_string().into());
         }
-        if let Some(symbol_file) = self.opt_symbol_file {
+        if let Some(symbol_file) = &self.opt_symbol_file {
             gdb_args.push("--symbols=".into());
-            gdb_args.last_mut().unwrap().push(&symbol_file);
+            gdb_args.last_mut().unwrap().push(symbol_file);
         }
-        if let Some(core_file) = self.opt_core_file {
+        if let Some(core_file) = &self.opt_core_file {
             gdb_args.push("--core=".into());
-            gdb_args.last_mut().unwrap().push(&core_file);
+            gdb_args.last_mut().unwrap().push(core_file);
         }
         if let Some(proc_id) = self.opt_proc_id {
             gdb_args.push("--pid=".into());
             gdb_args.last_mut().unwrap().push(proc_id.to_string());
         }
-        if let Some(command) = self.opt_command {
+        if let Some(command) = &self.opt_command {
             gdb_args.push("--command=".into());
-            gdb_args.last_mut().unwrap().push(&command);
+            gdb_args.last_mut().unwrap().push(command);
         }
-        if let Some(source_dir) = self.opt_source_dir {
+        if let Some(source_dir) = &self.opt_source_dir {
             gdb_args.push("--directory=".into());
-            gdb_args.last_mut().unwrap().push(&source_dir);
+            gdb_args.last_mut().unwrap().push(source_dir);
         }
-        if let Some(tty) = self.opt_tty {
+        if let Some(tty) = &self.opt_tty {
             gdb_args.push("--tty=".into());
-            gdb_args.last_mut().unwrap().push(&tty);
+            gdb_args.last_mut().unwrap().push(tty);
         }
         if !self.opt_args.is_empty() {
             gdb_args.push("--args".into());
-            gdb_args.push(self.opt_program.unwrap().into());
-            for arg in self.opt_args {
-                gdb_args.push(arg.into());
+            gdb_args.push(self.opt_program.clone().unwrap().into());
+            for arg in &self.opt_args {
+                gdb_args.push(arg.clone());
             }
-        } else if let Some(program) = self.opt_program {
+        } else if let Some(program) = &self.opt_program {
             gdb_args.push(program.into());
         }
 
-        let mut child = if let Some(rr_args) = self.rr_args {
+        let (process, stdin, stdout): (Process, Box<dyn Write + Send>, Box<dyn Read + Send>) =
+            match &self.opt_connection {
+                Some(Connection::NewUi) => {
+                    let (stdout, stdin) = connect_new_ui()?;
+                    (Process::Connected, stdin, stdout)
+                }
+                Some(Connection::Command(command)) => {
+                    let mut child = shell_command(command)
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::piped())
+                        .spawn()?;
+                    let (stdin, stdout) = take_pipes(&mut child)?;
+                    (Process::Proxy(child), Box::new(stdin), Box::new(stdout))
+                }
+                None => {
+                    let mut child = self.spawn_gdb(gdb_args)?;
+                    let (stdin, stdout) = take_pipes(&mut child)?;
+                    (Process::Spawned(child), Box::new(stdin), Box::new(stdout))
+                }
+            };
+
+        let traffic_log =
+            match &self.opt_traffic_log {
+                Some(path) => Some(traffic_log::TrafficLog::create(path).map_err(|e| {
+                    Error::Spawn(format!("cannot create {}: {}", path.display(), e))
+                })?),
+                None => None,
+            };
+        let writer = Arc::new(CommandWriter::new(stdin, traffic_log));
+        let writer_for_thread = writer.clone();
+
+        let is_running = Arc::new(AtomicBool::new(false));
+        let is_running_for_thread = is_running.clone();
+        let (result_input, result_output) = mpsc::channel();
+        let console_capture = Arc::new(Mutex::new(None));
+        let console_capture_for_thread = console_capture.clone();
+        let termination = Arc::new(Mutex::new(None));
+        let termination_for_thread = termination.clone();
+        /*let outputThread = */
+        thread::Builder::new()
+            .name("gdbmi parser".to_owned())
+            .spawn(move || {
+                output::process_output(
+                    stdout,
+                    result_input,
+                    oob_sink,
+                    is_running_for_thread,
+                    console_capture_for_thread,
+                    Some(writer_for_thread),
+                    termination_for_thread,
+                );
+            })?;
+        let mut gdb = GDB {
+            process,
+            writer,
+            is_running,
+            result_output,
+            console_capture,
+            binary_path: self.gdb_path.clone(),
+            init_options,
+            pending_results: PendingResults::default(),
+            termination,
+            capabilities: Capabilities::new(self.backend, Vec::new()),
+            //outputThread: outputThread,
+        };
+        let setup = if self.opt_connection.is_some() {
+            // gdb was started without knowing about us: -exec-interrupt requires mi-async and the
+            // output of the program should appear in our terminal.
+            let mut commands = vec![commands::MiCommand::gdb_set("mi-async", "on")];
+            if let Some(tty) = &self.opt_tty {
+                commands.push(commands::MiCommand::inferior_tty_set(tty));
+            }
+            commands
+        } else if self.backend == Backend::LldbMi {
+            self.lldb_mi_setup()
+        } else {
+            Vec::new()
+        };
+        for command in setup {
+            match gdb.execute(&command) {
+                Ok(output::ResultRecord {
+                    class: output::ResultClass::Error,
+                    results,
+                    ..
+                }) => warn!(
+                    "Could not set up {}: {}",
+                    gdb.capabilities.backend, results["msg"]
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Could not set up {}: {:?}", gdb.capabilities.backend, e),
+            }
+        }
+        match gdb.execute(commands::MiCommand::list_features()) {
+            Ok(result) => {
+                let listed = result.results["features"]
+                    .members()
+                    .filter_map(|f| f.as_str())
+                    .map(|f| f.to_owned())
+                    .collect();
+                gdb.capabilities = Capabilities::new(self.backend, listed);
+            }
+            Err(e) => warn!("Could not list features: {:?}", e),
+        }
+        Ok(gdb)
+    }
+
+    // lldb-mi is started without arguments, so everything is passed using MI commands.
+    fn lldb_mi_setup(&self) -> Vec<commands::MiCommand> {
+        use self::commands::MiCommand;
+        let mut commands = Vec::new();
+        if let Some(cd) = &self.opt_cd {
+            commands.push(MiCommand::environment_cd(cd));
+        }
+        if let Some(program) = &self.opt_program {
+            commands.push(MiCommand::file_exec_and_symbols(program));
+        }
+        if let Some(symbol_file) = &self.opt_symbol_file {
+            commands.push(MiCommand::file_symbol_file(Some(symbol_file)));
+        }
+        if !self.opt_args.is_empty() {
+            commands.push(MiCommand::exec_arguments(self.opt_args.clone()));
+        }
+        if let Some(tty) = &self.opt_tty {
+            commands.push(MiCommand::inferior_tty_set(tty));
+        }
+        if let Some(pid) = self.opt_proc_id {
+            commands.push(MiCommand::target_attach(pid));
+        }
+        if self.opt_core_file.is_some() || self.opt_command.is_some() || self.rr_args.is_some() {
+            warn!("Core files, command files and rr are not supported by lldb-mi.");
+        }
+        commands
+    }
+
+    // Start gdb (or rr, which starts gdb) with the given arguments.
+    fn spawn_gdb(&self, gdb_args: Vec<OsString>) -> io::Result<Child> {
+        if self.backend == Backend::LldbMi {
+            return gdb_command(&self.gdb_path)
+                .arg("--interpreter")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn();
+        }
+        if let Some(rr_args) = &self.rr_args {
             // It looks like rr acts as a remote target for gdb and "runs" (or simulates) the
             // binary itself. Consequently, it also is responsible for stdin/stdout handling.
             // Without "-q" it appears to pass all stdout to the terminal/output that gdb is
@@ -203,56 +592,157 @@ impl GDBBuilder {
 
             let silence_arg = "-q";
 
-            Command::new(rr_args.0)
+            gdb_command(&rr_args.0)
                 .arg("replay")
                 .arg("--interpreter=mi")
                 .arg(silence_arg)
                 .arg("-d")
                 .arg(self.gdb_path.clone())
                 .args(args)
-                .args(rr_args.1)
+                .args(&rr_args.1)
                 .stdin(Stdio::piped())
                 .stdout(Stdio::piped())
-                .spawn()?
+                .spawn()
         } else {
-            Command::new(self.gdb_path.clone())
+            gdb_command(&self.gdb_path)
                 .arg("--interpreter=mi")
                 .args(gdb_args)
                 .stdin(Stdio::piped())
                 .stdout(Stdio::piped())
-                .spawn()?
-        };
-
-        let stdin = child.stdin.take().expect("take stdin");
-        let stdout = child.stdout.take().expect("take stdout");
-        let is_running = Arc::new(AtomicBool::new(false));
-        let is_running_for_thread = is_running.clone();
-        let (result_input, result_output) = mpsc::channel();
-        /*let outputThread = */
-        thread::Builder::new()
-            .name("gdbmi parser".to_owned())
-            .spawn(move || {
-                output::process_output(stdout, result_input, oob_sink, is_running_for_thread);
-            })?;
-        let gdb = GDB {
-            process: child,
-            stdin,
-            is_running,
-            result_output,
-            current_command_token: 0,
-            binary_path: self.gdb_path,
-            init_options,
-            //outputThread: outputThread,
-        };
-        Ok(gdb)
+                .spawn()
+        }
     }
 }
 
+#[cfg(unix)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+// gdb gets its own process group on Windows, so that we can send Ctrl-Break to it (and not to
+// ourselves), see `GDB::interrupt_execution`.
+#[cfg(unix)]
+fn gdb_command<S: AsRef<OsStr>>(program: S) -> Command {
+    Command::new(program)
+}
+
+#[cfg(windows)]
+fn gdb_command<S: AsRef<OsStr>>(program: S) -> Command {
+    use std::os::windows::process::CommandExt;
+    use winapi::um::winbase::CREATE_NEW_PROCESS_GROUP;
+    let mut command = Command::new(program);
+    command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    command
+}
+
+// The output and input of the MI interpreter of gdb.
+type MiStreams = (Box<dyn Read + Send>, Box<dyn Write + Send>);
+
+// Take the pipes that are used to talk to a spawned process.
+fn take_pipes(child: &mut Child) -> Result<(ChildStdin, ChildStdout), Error> {
+    let stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| Error::Spawn("stdin of gdb is not piped".to_owned()))?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| Error::Spawn("stdout of gdb is not piped".to_owned()))?;
+    Ok((stdin, stdout))
+}
+
+/// Open a pty and wait until gdb opens an MI interpreter on it. The user has to run `new-ui mi
+/// <pty>` in gdb, which then prints a prompt on the pty.
+#[cfg(unix)]
+fn connect_new_ui() -> Result<MiStreams, Error> {
+    use nix::fcntl::{open, OFlag};
+    use nix::pty::{grantpt, posix_openpt, ptsname, unlockpt};
+    use nix::sys::stat::Mode;
+    use nix::sys::termios::{cfmakeraw, tcgetattr, tcsetattr, SetArg};
+    use std::fs::File;
+    use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd};
+
+    let pty_error = |e: nix::Error| Error::Spawn(format!("cannot create a pty: {}", e));
+    let master = posix_openpt(OFlag::O_RDWR | OFlag::O_NOCTTY).map_err(pty_error)?;
+    grantpt(&master).map_err(pty_error)?;
+    unlockpt(&master).map_err(pty_error)?;
+    // No other thread uses ptsname (which returns a static buffer) at this point.
+    let name = unsafe { ptsname(&master) }.map_err(pty_error)?;
+    // Keep the slave open until gdb opened it: Reading from the master fails once the last slave
+    // was closed. gdb must not echo our commands, so the slave is switched to raw mode.
+    let slave = open(
+        Path::new(&name),
+        OFlag::O_RDWR | OFlag::O_NOCTTY,
+        Mode::empty(),
+    )
+    .map_err(pty_error)?;
+    let slave = unsafe { File::from_raw_fd(slave) };
+    let mut termios = tcgetattr(slave.as_raw_fd()).map_err(pty_error)?;
+    cfmakeraw(&mut termios);
+    tcsetattr(slave.as_raw_fd(), SetArg::TCSANOW, &termios).map_err(pty_error)?;
+    let mut master = unsafe { File::from_raw_fd(master.into_raw_fd()) };
+
+    eprintln!(
+        "Waiting for gdb to connect. Run in gdb:\n  new-ui mi {}",
+        name
+    );
+    let mut first = [0u8];
+    master.read_exact(&mut first)?;
+    drop(slave);
+    let writer = master.try_clone()?;
+    Ok((
+        Box::new(io::Cursor::new(first).chain(master)),
+        Box::new(writer),
+    ))
+}
+
+#[cfg(windows)]
+use self::windows::connect_new_ui;
+
+#[cfg(unix)]
+fn interrupt_process(child: &Child) -> io::Result<()> {
+    use nix::sys::signal;
+    use nix::unistd::Pid;
+    signal::kill(Pid::from_raw(child.id() as i32), signal::SIGINT).map_err(io::Error::other)
+}
+
+#[cfg(windows)]
+fn interrupt_process(child: &Child) -> io::Result<()> {
+    windows::interrupt_process_group(child.id())
+}
+
 impl GDB {
-    pub fn interrupt_execution(&self) -> Result<(), ::nix::Error> {
-        use nix::sys::signal;
-        use nix::unistd::Pid;
-        signal::kill(Pid::from_raw(self.process.id() as i32), signal::SIGINT)
+    /// Interrupt the debugged program (or the command that gdb is currently executing).
+    ///
+    /// This sends SIGINT (or Ctrl-Break on Windows) to a gdb that we started, which is what
+    /// happens when pressing Ctrl-C in a gdb console. We cannot signal a gdb that was started by
+    /// someone else, so we send `-exec-interrupt` instead, which works because `mi-async` is
+    /// turned on in that case.
+    pub fn interrupt_execution(&mut self) -> io::Result<()> {
+        if let Process::Spawned(child) = &self.process {
+            return interrupt_process(child);
+        }
+        // Tokens of regular commands start at 1, so the result of this one is simply dropped.
+        self.writer.write(&commands::MiCommand::exec_interrupt(), 0)
+    }
+
+    /// What the debugger (gdb or lldb-mi) supports.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Whether gdb was started by someone else. It keeps running when ugdb quits.
+    pub fn is_external(&self) -> bool {
+        !matches!(self.process, Process::Spawned(_))
     }
 
     pub fn binary_path(&self) -> &Path {
@@ -265,9 +755,99 @@ impl GDB {
     pub fn is_running(&self) -> bool {
         self.is_running.load(Ordering::SeqCst)
     }
-    pub fn get_usable_token(&mut self) -> Token {
-        self.current_command_token = self.current_command_token.wrapping_add(1);
-        self.current_command_token
+
+    /// Whether commands were sent whose results did not arrive yet (e.g., after a timeout).
+    pub fn has_pending_commands(&self) -> bool {
+        !self.pending_results.is_synchronized()
+    }
+
+    /// Write the command to gdb without waiting for the result. The result can be obtained using
+    /// the returned token via `wait_for_result`.
+    fn send<C: std::borrow::Borrow<commands::MiCommand>>(
+        &mut self,
+        command: C,
+    ) -> Result<Token, ExecuteError> {
+        let command_token = self.writer.next_token();
+        if let Err(e) = self.writer.write(command.borrow(), command_token) {
+            return Err(ExecuteError::Quit(
+                self.stored_termination()
+                    .unwrap_or_else(|| Error::BrokenPipe(e.to_string())),
+            ));
+        }
+        self.pending_results.sent(command_token);
+        Ok(command_token)
+    }
+
+    fn stored_termination(&self) -> Option<Error> {
+        self.termination.lock().unwrap().clone()
+    }
+
+    /// Wait (at most for the given time) for gdb to exit.
+    pub fn wait_for_exit(&mut self, timeout: Duration) -> Option<ExitStatus> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.process.try_wait() {
+                Ok(Some(status)) => return Some(status),
+                Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+                _ => return None,
+            }
+        }
+    }
+
+    /// Why gdb does not respond anymore: Either the output thread gave up on it, or it exited.
+    pub fn termination_error(&mut self) -> Error {
+        if let Some(e) = self.stored_termination() {
+            return e;
+        }
+        // The output of gdb usually ends just before the process exits.
+        Error::UnexpectedEof(
+            self.wait_for_exit(Duration::from_millis(500))
+                .and_then(|status| status.code()),
+        )
+    }
+
+    // Receive the next output of gdb, waiting at most until the deadline (if there is one).
+    fn receive(&mut self, deadline: Option<Instant>) -> Result<output::ResultOutput, ExecuteError> {
+        match deadline {
+            Some(deadline) => self
+                .result_output
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|e| match e {
+                    RecvTimeoutError::Timeout => ExecuteError::Timeout,
+                    RecvTimeoutError::Disconnected => ExecuteError::Quit(self.termination_error()),
+                }),
+            None => self
+                .result_output
+                .recv()
+                .map_err(|_| ExecuteError::Quit(self.termination_error())),
+        }
+    }
+
+    /// Wait for the result of a command that was written with the given token. Results of other
+    /// commands that are still in flight are kept until they are requested.
+    fn wait_for_result(
+        &mut self,
+        token: Token,
+        deadline: Option<Instant>,
+    ) -> Result<output::ResultRecord, ExecuteError> {
+        if let Some(record) = self.pending_results.take(token) {
+            return Ok(record);
+        }
+        loop {
+            match self.receive(deadline) {
+                Ok(output::ResultOutput::Record(record)) => {
+                    if let Some(record) = self.pending_results.accept(record, Some(token)) {
+                        return Ok(record);
+                    }
+                }
+                Ok(output::ResultOutput::Prompt) => {}
+                Err(e) => {
+                    // The result may still arrive later, but nobody is waiting for it anymore.
+                    self.pending_results.discard(token);
+                    return Err(e);
+                }
+            }
+        }
     }
 
     pub fn execute<C: std::borrow::Borrow<commands::MiCommand>>(
@@ -277,40 +857,127 @@ impl GDB {
         if self.is_running() {
             return Err(ExecuteError::Busy);
         }
-        let command_token = self.get_usable_token();
+        let command_token = self.send(command)?;
+        self.wait_for_result(command_token, None)
+    }
 
-        let mut bytes = Vec::new();
-        command
-            .borrow()
-            .write_interpreter_string(&mut bytes, command_token)
-            .expect("write interpreter command");
-
-        info!("Writing msg {}", String::from_utf8_lossy(&bytes),);
-        command
-            .borrow()
-            .write_interpreter_string(&mut self.stdin, command_token)
-            .expect("write interpreter command");
+    /// Like `execute`, but give up if gdb does not respond within the given time. Use
+    /// `cancel_pending` to interrupt the command in that case.
+    pub fn execute_with_timeout<C: std::borrow::Borrow<commands::MiCommand>>(
+        &mut self,
+        command: C,
+        timeout: Duration,
+    ) -> Result<output::ResultRecord, ExecuteError> {
+        if self.is_running() {
+            return Err(ExecuteError::Busy);
+        }
+        let command_token = self.send(command)?;
+        self.wait_for_result(command_token, Some(Instant::now() + timeout))
+    }
+
+    /// Interrupt the commands that are still in flight (e.g., after `execute_with_timeout` gave up)
+    /// by sending SIGINT to gdb and drop their results. Returns once gdb reported all of them and
+    /// printed its prompt again, so that results of subsequent commands can be matched again.
+    pub fn cancel_pending(&mut self, timeout: Duration) -> Result<(), ExecuteError> {
+        if self.pending_results.is_synchronized() {
+            return Ok(());
+        }
+        self.pending_results.discard_all();
+        self.interrupt_execution()
+            .map_err(|_| ExecuteError::Quit(self.termination_error()))?;
+        let deadline = Some(Instant::now() + timeout);
         loop {
-            match self.result_output.recv() {
-                Ok(record) => match record.token {
-                    Some(token) if token == command_token => return Ok(record),
-                    _ => info!(
-                        "Record does not match expected token ({}) and will be dropped: {:?}",
-                        command_token, record
-                    ),
-                },
-                Err(_) => return Err(ExecuteError::Quit),
+            match self.receive(deadline)? {
+                output::ResultOutput::Record(record) => {
+                    let _ = self.pending_results.accept(record, None);
+                }
+                output::ResultOutput::Prompt => {
+                    if self.pending_results.is_synchronized() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Execute several commands at once: All commands are written to gdb before waiting for the
+    /// first result, so that gdb does not have to wait for us between the commands. The results
+    /// are returned in the order of the commands.
+    pub fn execute_many<C, I>(
+        &mut self,
+        commands: I,
+    ) -> Result<Vec<output::ResultRecord>, ExecuteError>
+    where
+        C: std::borrow::Borrow<commands::MiCommand>,
+        I: IntoIterator<Item = C>,
+    {
+        if self.is_running() {
+            return Err(ExecuteError::Busy);
+        }
+        let tokens = commands
+            .into_iter()
+            .map(|command| self.send(command))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut results = Vec::with_capacity(tokens.len());
+        for (i, &token) in tokens.iter().enumerate() {
+            match self.wait_for_result(token, None) {
+                Ok(record) => results.push(record),
+                Err(e) => {
+                    // Nobody is going to ask for the remaining results anymore.
+                    for &token in &tokens[i + 1..] {
+                        self.pending_results.discard(token);
+                    }
+                    return Err(e);
+                }
             }
         }
+        Ok(results)
     }
 
+    /// Execute the command and return the console output that gdb produced for it (instead of
+    /// passing it on to the out of band record sink).
+    pub fn execute_capturing_console<C: std::borrow::Borrow<commands::MiCommand>>(
+        &mut self,
+        command: C,
+    ) -> Result<(output::ResultRecord, String), ExecuteError> {
+        *self.console_capture.lock().unwrap() = Some(String::new());
+        let res = self.execute(command);
+        let captured = self.console_capture.lock().unwrap().take();
+        res.map(|r| (r, captured.unwrap_or_default()))
+    }
+
+    /// Write the command to gdb without waiting for its result, e.g., if it takes a while, but
+    /// reports its progress using out of band records. Use `background_result` to check whether
+    /// the result arrived. Commands that are executed in the meantime wait for it to finish.
+    pub fn execute_in_background<C: std::borrow::Borrow<commands::MiCommand>>(
+        &mut self,
+        command: C,
+    ) -> Result<BackgroundCommand, ExecuteError> {
+        if self.is_running() {
+            return Err(ExecuteError::Busy);
+        }
+        self.send(command).map(BackgroundCommand)
+    }
+
+    /// The result of a command that was executed in the background, if it arrived already.
+    pub fn background_result(
+        &mut self,
+        command: &BackgroundCommand,
+    ) -> Option<output::ResultRecord> {
+        while let Ok(output) = self.result_output.try_recv() {
+            if let output::ResultOutput::Record(record) = output {
+                let _ = self.pending_results.accept(record, None);
+            }
+        }
+        self.pending_results.take(command.0)
+    }
+
+    /// Write the command to gdb without waiting for (and ignoring) its result.
     pub fn execute_later<C: std::borrow::Borrow<commands::MiCommand>>(&mut self, command: C) {
-        let command_token = self.get_usable_token();
-        command
-            .borrow()
-            .write_interpreter_string(&mut self.stdin, command_token)
-            .expect("write interpreter command");
-        let _ = self.result_output.recv();
+        match self.send(command) {
+            Ok(command_token) => self.pending_results.discard(command_token),
+            Err(e) => warn!("Could not send command to gdb: {:?}", e),
+        }
     }
 
     pub fn is_session_active(&mut self) -> Result<bool, ExecuteError> {
@@ -318,3 +985,59 @@ impl GDB {
         Ok(!res.results["threads"].is_empty())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::output::{ResultClass, ResultRecord};
+    use super::*;
+
+    fn record(token: Option<Token>) -> ResultRecord {
+        ResultRecord {
+            token,
+            class: ResultClass::Done,
+            results: json::object::Object::new(),
+        }
+    }
+
+    #[test]
+    fn test_pending_results() {
+        let mut pending = PendingResults::default();
+        pending.discard(1);
+
+        assert!(pending.accept(record(Some(1)), Some(3)).is_none());
+        assert!(pending.accept(record(None), Some(3)).is_none());
+        assert!(pending.accept(record(Some(2)), Some(3)).is_none());
+        assert_eq!(
+            pending.accept(record(Some(3)), Some(3)).unwrap().token,
+            Some(3)
+        );
+
+        assert!(pending.take(1).is_none());
+        assert_eq!(pending.take(2).unwrap().token, Some(2));
+        assert!(pending.take(2).is_none());
+
+        assert!(pending.accept(record(Some(4)), Some(5)).is_none());
+        pending.discard(4);
+        assert!(pending.take(4).is_none());
+        assert!(pending.discarded.is_empty());
+    }
+
+    #[test]
+    fn test_discard_pending_results() {
+        let mut pending = PendingResults::default();
+        pending.sent(1);
+        pending.sent(2);
+        pending.sent(3);
+        assert!(pending.accept(record(Some(1)), None).is_none());
+        assert!(!pending.is_synchronized());
+
+        pending.discard_all();
+        assert!(pending.take(1).is_none());
+        assert!(pending.accept(record(Some(3)), None).is_none());
+        assert!(!pending.is_synchronized());
+        assert!(pending.accept(record(Some(2)), None).is_none());
+        assert!(pending.is_synchronized());
+        assert!(pending.received.is_empty());
+        assert!(pending.discarded.is_empty());
+    }
+}
@@ -23,17 +23,22 @@ pub enum BreakPointEvent {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThreadEvent {
     Created,
+    GroupAdded,
     GroupStarted,
     Exited,
     GroupExited,
+    GroupRemoved,
     Selected,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AsyncClass {
     Stopped,
+    Running,
     CmdParamChanged,
     LibraryLoaded,
+    LibraryUnloaded,
+    MemoryChanged,
     Thread(ThreadEvent),
     BreakPoint(BreakPointEvent),
     Other(String), //?
@@ -60,6 +65,14 @@ pub struct ResultRecord {
     pub results: Object,
 }
 
+/// Output of gdb that is passed on to the thread that waits for the results of commands.
+#[derive(Debug)]
+pub enum ResultOutput {
+    Record(ResultRecord),
+    /// The `(gdb)` prompt, i.e., gdb is ready to read the next command.
+    Prompt,
+}
+
 #[derive(Debug)]
 pub enum OutOfBandRecord {
     AsyncRecord {
@@ -82,46 +95,121 @@ enum Output {
     SomethingElse(String), /* Debug */
 }
 
+use super::OutOfBandRecordSink;
+use super::{CommandWriter, Error};
 use nom::IResult;
+use std::collections::VecDeque;
 use std::io::{BufRead, BufReader, Read};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
-use std::sync::Arc;
-use OutOfBandRecordSink;
+use std::sync::{Arc, Mutex};
+
+// A line that cannot be parsed, but looks like the result of a command (`<token>^...`) is turned
+// into an error result. Otherwise, whoever waits for the result of the command would wait forever.
+fn malformed_result(line: &str, error: &str) -> Option<ResultRecord> {
+    let caret = line.find('^')?;
+    let token = line[..caret].parse::<Token>().ok()?;
+    let mut results = Object::new();
+    results.insert(
+        "msg",
+        format!("Malformed result of gdb ({}): {}", error, line.trim_end()).into(),
+    );
+    Some(ResultRecord {
+        token: Some(token),
+        class: ResultClass::Error,
+        results,
+    })
+}
+
+// An async record that is held back until the results of its queries (and those of the records in
+// front of it) arrived.
+struct HeldBack {
+    record: OutOfBandRecord,
+    tokens: Vec<Token>,
+    results: Vec<Option<ResultRecord>>,
+}
+
+impl HeldBack {
+    fn is_complete(&self) -> bool {
+        self.results.iter().all(Option::is_some)
+    }
+}
+
+fn pass_on<S: OutOfBandRecordSink>(held_back: HeldBack, out_of_band_pipe: &S) {
+    if held_back.tokens.is_empty() {
+        out_of_band_pipe.send(held_back.record);
+    } else {
+        let results = held_back.results.into_iter().flatten().collect();
+        out_of_band_pipe.send_with_results(held_back.record, results);
+    }
+}
 
+/// Parse the output of gdb: Results of commands are passed on through the result pipe and out of
+/// band records to the sink. If a writer is given, the output thread sends the queries of the sink
+/// for async records (see `OutOfBandRecordSink::queries`) and intercepts their results.
 pub fn process_output<T: Read, S: OutOfBandRecordSink>(
     output: T,
-    result_pipe: Sender<ResultRecord>,
+    result_pipe: Sender<ResultOutput>,
     out_of_band_pipe: S,
     is_running: Arc<AtomicBool>,
+    console_capture: Arc<Mutex<Option<String>>>,
+    writer: Option<Arc<CommandWriter>>,
+    termination: Arc<Mutex<Option<Error>>>,
 ) {
     let mut reader = BufReader::new(output);
+    let traffic_log = writer.as_ref().and_then(|w| w.traffic_log.as_ref());
+    let mut held_back = VecDeque::<HeldBack>::new();
 
     loop {
+        while held_back.front().is_some_and(HeldBack::is_complete) {
+            pass_on(held_back.pop_front().unwrap(), &out_of_band_pipe);
+        }
         let mut buffer = String::new();
         match reader.read_line(&mut buffer) {
             Ok(0) => {
+                // The results will not arrive anymore.
+                for record in held_back {
+                    out_of_band_pipe.send(record.record);
+                }
                 return;
             }
             Ok(_) => {
                 info!("{}", buffer.trim_end());
+                if let Some(log) = traffic_log {
+                    log.record_output(&buffer);
+                }
 
                 let parse_result = match Output::parse(&buffer) {
                     Ok(r) => r,
                     Err(e) => {
                         error!("PARSING ERROR: {}", e);
+                        if let Some(record) = malformed_result(&buffer, &e) {
+                            if result_pipe.send(ResultOutput::Record(record)).is_err() {
+                                return;
+                            }
+                        }
                         continue;
                     }
                 };
                 match parse_result {
                     Output::Result(record) => {
+                        let query = held_back.iter_mut().find_map(|h| {
+                            let i = h.tokens.iter().position(|&t| Some(t) == record.token)?;
+                            Some(&mut h.results[i])
+                        });
+                        if let Some(result) = query {
+                            *result = Some(record);
+                            continue;
+                        }
                         match record.class {
                             ResultClass::Running => is_running.store(true, Ordering::SeqCst),
                             //Apparently sometimes gdb first claims to be running, only to then stop again (without notifying the user)...
                             ResultClass::Error => is_running.store(false, Ordering::SeqCst),
                             _ => {}
                         }
-                        result_pipe.send(record).expect("send result to pipe");
+                        if result_pipe.send(ResultOutput::Record(record)).is_err() {
+                            return; // Nobody is interested in results anymore.
+                        }
                     }
                     Output::OutOfBand(record) => {
                         if let OutOfBandRecord::AsyncRecord {
@@ -131,9 +219,44 @@ pub fn process_output<T: Read, S: OutOfBandRecordSink>(
                         {
                             is_running.store(false, Ordering::SeqCst);
                         }
-                        out_of_band_pipe.send(record);
+                        if let OutOfBandRecord::StreamRecord {
+                            kind: StreamKind::Console,
+                            ref data,
+                        } = record
+                        {
+                            if let Some(ref mut captured) = *console_capture.lock().unwrap() {
+                                captured.push_str(data);
+                                continue;
+                            }
+                        }
+                        let queries = match (&record, &writer) {
+                            (OutOfBandRecord::AsyncRecord { .. }, Some(_)) => {
+                                out_of_band_pipe.queries(&record, is_running.load(Ordering::SeqCst))
+                            }
+                            _ => Vec::new(),
+                        };
+                        if queries.is_empty() && held_back.is_empty() {
+                            out_of_band_pipe.send(record);
+                            continue;
+                        }
+                        let tokens = match writer.as_ref().unwrap().write_all(&queries) {
+                            Ok(tokens) => tokens,
+                            Err(e) => {
+                                error!("Could not send queries to gdb: {}", e);
+                                Vec::new()
+                            }
+                        };
+                        held_back.push_back(HeldBack {
+                            record,
+                            results: tokens.iter().map(|_| None).collect(),
+                            tokens,
+                        });
+                    }
+                    Output::GDBLine => {
+                        if result_pipe.send(ResultOutput::Prompt).is_err() {
+                            return;
+                        }
                     }
-                    Output::GDBLine => {}
                     //Output::SomethingElse(_) => { /*println!("SOMETHING ELSE: {}", str);*/ }
                     Output::SomethingElse(text) => {
                         out_of_band_pipe.send(OutOfBandRecord::StreamRecord {
@@ -144,14 +267,50 @@ pub fn process_output<T: Read, S: OutOfBandRecordSink>(
                 }
             }
             Err(e) => {
-                panic!("{}", e);
+                error!("Reading output of gdb failed: {}", e);
+                *termination.lock().unwrap() = Some(Error::Parse(e.to_string()));
+                return;
             }
         }
     }
 }
 
+// Values are parsed recursively, so (malformed) lines with deeply nested values would overflow the
+// stack of the output thread. Actual output of gdb is not nested nearly as deep.
+const MAX_NESTING_DEPTH: usize = 64;
+
+// Maximum number of unclosed tuples and lists at any point in the line (outside of strings).
+fn nesting_depth(line: &str) -> usize {
+    let mut depth = 0usize;
+    let mut max_depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for byte in line.bytes() {
+        match (in_string, escaped, byte) {
+            (true, true, _) => escaped = false,
+            (true, false, b'\\') => escaped = true,
+            (true, false, b'"') => in_string = false,
+            (true, false, _) => {}
+            (false, _, b'"') => in_string = true,
+            (false, _, b'{') | (false, _, b'[') => {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            }
+            (false, _, b'}') | (false, _, b']') => depth = depth.saturating_sub(1),
+            (false, _, _) => {}
+        }
+    }
+    max_depth
+}
+
 impl Output {
     fn parse(line: &str) -> Result<Self, String> {
+        if nesting_depth(line) > MAX_NESTING_DEPTH {
+            return Err(format!(
+                "values are nested deeper than {} levels",
+                MAX_NESTING_DEPTH
+            ));
+        }
         match output(line.as_bytes()) {
             IResult::Done(_, c) => Ok(c),
             IResult::Incomplete(e) => Err(format!("parsing line: incomplete {:?}", e)), //Is it okay to read the next bytes then?
@@ -172,11 +331,10 @@ named!(
 );
 
 fn non_quote_byte(input: &[u8]) -> IResult<&[u8], u8> {
-    let byte = input[0];
-    if byte == b'\"' {
-        IResult::Error(::nom::ErrorKind::Custom(1)) //what are we supposed to return here??
-    } else {
-        IResult::Done(&input[1..], byte)
+    match input.first() {
+        None => IResult::Incomplete(::nom::Needed::Size(1)),
+        Some(&b'\"') => IResult::Error(::nom::ErrorKind::Custom(1)), //what are we supposed to return here??
+        Some(&byte) => IResult::Done(&input[1..], byte),
     }
 }
 
@@ -206,7 +364,7 @@ fn to_map(v: Vec<(String, JsonValue)>) -> Object {
     //TODO: fix this and parse the map directly
     let mut obj = Object::new();
     for (name, value) in v {
-        debug_assert!(obj.get(&name).is_none(), "Duplicate object member!");
+        // Duplicate members are not allowed, but should not stop us either. The last one wins.
         obj.insert(&name, value);
     }
     obj
@@ -228,6 +386,14 @@ named!(
                     >> tag!("}")
                     >> (JsonValue::Object(to_map(results)))
             )
+            // Older versions of gdb emit lists of values in braces (e.g., the script of a
+            // breakpoint).
+            | do_parse!(
+                tag!("{")
+                    >> values: separated_list!(tag!(","), value)
+                    >> tag!("}")
+                    >> (JsonValue::Array(values))
+            )
             | do_parse!(
                 tag!("[")
                     >> values: separated_list!(tag!(","), value)
@@ -273,10 +439,12 @@ named!(
 
 named!(
     token<Token>,
-    map!(::nom::digit, |values: &[u8]| values
-        .iter()
-        .fold(0, |acc, &ascii_digit| 10 * acc
-            + (ascii_digit - b'0') as u64))
+    map_opt!(::nom::digit, |values: &[u8]| values.iter().try_fold(
+        0 as Token,
+        |acc, &ascii_digit| acc
+            .checked_mul(10)?
+            .checked_add((ascii_digit - b'0') as Token)
+    ))
 );
 
 named!(
@@ -307,10 +475,15 @@ named!(
     async_class<AsyncClass>,
     alt!(
         value!(AsyncClass::Stopped, tag!("stopped"))
+            | value!(AsyncClass::Running, tag!("running"))
             | value!(
                 AsyncClass::Thread(ThreadEvent::Created),
                 tag!("thread-created")
             )
+            | value!(
+                AsyncClass::Thread(ThreadEvent::GroupAdded),
+                tag!("thread-group-added")
+            )
             | value!(
                 AsyncClass::Thread(ThreadEvent::GroupStarted),
                 tag!("thread-group-started")
@@ -323,12 +496,18 @@ named!(
                 AsyncClass::Thread(ThreadEvent::GroupExited),
                 tag!("thread-group-exited")
             )
+            | value!(
+                AsyncClass::Thread(ThreadEvent::GroupRemoved),
+                tag!("thread-group-removed")
+            )
             | value!(
                 AsyncClass::Thread(ThreadEvent::Selected),
                 tag!("thread-selected")
             )
             | value!(AsyncClass::CmdParamChanged, tag!("cmd-param-changed"))
             | value!(AsyncClass::LibraryLoaded, tag!("library-loaded"))
+            | value!(AsyncClass::LibraryUnloaded, tag!("library-unloaded"))
+            | value!(AsyncClass::MemoryChanged, tag!("memory-changed"))
             | value!(
                 AsyncClass::BreakPoint(BreakPointEvent::Created),
                 tag!("breakpoint-created")
@@ -421,4 +600,122 @@ mod test {
     fn test_output() {
         let _ = Output::parse("=library-loaded,ranges=[{}]\n");
     }
+
+    #[test]
+    fn test_malformed_result() {
+        let record = malformed_result("12^done,value=\"\n", "incomplete").unwrap();
+        assert_eq!(record.token, Some(12));
+        assert_eq!(record.class, ResultClass::Error);
+        assert!(record.results["msg"]
+            .as_str()
+            .unwrap()
+            .contains("incomplete"));
+
+        assert!(malformed_result("^done,value=\"\n", "incomplete").is_none());
+        assert!(malformed_result("~\"1^2\n", "incomplete").is_none());
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        assert_eq!(nesting_depth("^done\n"), 0);
+        assert_eq!(nesting_depth("^done,a=[{b=\"}\"},{c=[]}]\n"), 3);
+        assert_eq!(nesting_depth("~\"[\\\"[\"\n"), 0);
+    }
+
+    // Found by fuzzing: none of these may panic.
+    #[test]
+    fn test_malformed_output() {
+        assert!(Output::parse("~\"unterminated").is_err());
+        assert!(Output::parse("99999999999999999999999^done\n").is_err());
+        match Output::parse("^done,a=\"1\",a=\"2\"\n") {
+            Ok(Output::Result(record)) => assert_eq!(record.results["a"], "2"),
+            o => panic!("Unexpected output: {:?}", o.map(|_| ())),
+        }
+        assert!(Output::parse(&format!("^done,a={}\n", "[".repeat(100000))).is_err());
+        assert!(Output::parse(&format!("^done,a={}\n", "{a=".repeat(100000))).is_err());
+    }
+
+    use super::super::commands::MiCommand;
+
+    // Queries `-stack-info-depth` when the program stops and collects what is passed on.
+    struct QueryingSink(Arc<Mutex<Vec<(String, usize)>>>);
+
+    impl OutOfBandRecordSink for QueryingSink {
+        fn send(&self, record: OutOfBandRecord) {
+            self.send_with_results(record, Vec::new());
+        }
+
+        fn queries(&self, record: &OutOfBandRecord, _: bool) -> Vec<MiCommand> {
+            match record {
+                OutOfBandRecord::AsyncRecord {
+                    class: AsyncClass::Stopped,
+                    ..
+                } => vec![MiCommand::stack_info_depth()],
+                _ => Vec::new(),
+            }
+        }
+
+        fn send_with_results(&self, record: OutOfBandRecord, results: Vec<ResultRecord>) {
+            let name = match record {
+                OutOfBandRecord::AsyncRecord { class, .. } => format!("{:?}", class),
+                OutOfBandRecord::StreamRecord { data, .. } => data,
+            };
+            self.0.lock().unwrap().push((name, results.len()));
+        }
+    }
+
+    #[derive(Clone)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl ::std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> ::std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_queries() {
+        let input = SharedBuffer(Arc::new(Mutex::new(Vec::new())));
+        let writer = Arc::new(CommandWriter::new(Box::new(input.clone()), None));
+        let passed_on = Arc::new(Mutex::new(Vec::new()));
+        let (result_input, result_output) = ::std::sync::mpsc::channel();
+        // The results of the query (token 1) arrive after a console line and another record.
+        let output = "*stopped,reason=\"breakpoint-hit\"\n\
+                      ~\"console\"\n\
+                      *running,thread-id=\"all\"\n\
+                      2^done,value=\"1\"\n\
+                      1^done,depth=\"3\"\n\
+                      (gdb) \n";
+        process_output(
+            output.as_bytes(),
+            result_input,
+            QueryingSink(passed_on.clone()),
+            Arc::new(AtomicBool::new(true)),
+            Arc::new(Mutex::new(None)),
+            Some(writer),
+            Arc::new(Mutex::new(None)),
+        );
+        assert_eq!(&*input.0.lock().unwrap(), b"1-stack-info-depth\n");
+        // Records following the stop are held back until its results arrived (in order).
+        assert_eq!(
+            *passed_on.lock().unwrap(),
+            vec![
+                ("Stopped".to_owned(), 1),
+                ("console".to_owned(), 0),
+                ("Running".to_owned(), 0)
+            ]
+        );
+        // Only the result of the other command is passed on to whoever waits for results.
+        let results = result_output.try_iter().collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+        match &results[0] {
+            ResultOutput::Record(record) => assert_eq!(record.token, Some(2)),
+            o => panic!("Unexpected output: {:?}", o),
+        }
+    }
 }
@@ -0,0 +1,130 @@
+use super::output::{self, OutOfBandRecord, ResultOutput, ResultRecord};
+use super::OutOfBandRecordSink;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Instant;
+
+const COMMAND_MARKER: &str = ">";
+const OUTPUT_MARKER: &str = "<";
+
+/// Record of all commands sent to gdb and all lines of output received from it.
+///
+/// Each line of the log has the form "SECONDS MARKER LINE" where SECONDS is the (fractional) time
+/// since the log was created and MARKER is ">" for commands and "<" for output.
+#[derive(Clone)]
+pub struct TrafficLog {
+    writer: Arc<Mutex<BufWriter<File>>>,
+    start: Instant,
+}
+
+impl TrafficLog {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(TrafficLog {
+            writer: Arc::new(Mutex::new(BufWriter::new(File::create(path)?))),
+            start: Instant::now(),
+        })
+    }
+
+    fn record(&self, marker: &str, line: &str) {
+        let elapsed = self.start.elapsed();
+        let mut writer = self.writer.lock().unwrap();
+        // The log is a debugging aid, so failing to write it is not worth interrupting the session.
+        let _ = writeln!(
+            writer,
+            "{}.{:06} {} {}",
+            elapsed.as_secs(),
+            elapsed.subsec_micros(),
+            marker,
+            line.trim_end_matches('\n')
+        );
+        let _ = writer.flush();
+    }
+
+    pub fn record_command(&self, command: &str) {
+        self.record(COMMAND_MARKER, command);
+    }
+
+    pub fn record_output(&self, line: &str) {
+        self.record(OUTPUT_MARKER, line);
+    }
+}
+
+struct CollectingSink(Arc<Mutex<Vec<OutOfBandRecord>>>);
+
+impl OutOfBandRecordSink for CollectingSink {
+    fn send(&self, record: OutOfBandRecord) {
+        self.0.lock().unwrap().push(record);
+    }
+}
+
+/// Records that were produced by replaying a traffic log.
+pub struct Replay {
+    pub results: Vec<ResultRecord>,
+    pub out_of_band: Vec<OutOfBandRecord>,
+}
+
+/// Feed the gdb output of a traffic log through the output parser (i.e., without a running gdb).
+/// Commands in the log are ignored.
+pub fn replay<R: BufRead>(log: R) -> io::Result<Replay> {
+    let mut output = String::new();
+    for line in log.lines() {
+        let line = line?;
+        let mut parts = line.splitn(3, ' ');
+        if let (Some(_), Some(OUTPUT_MARKER), Some(data)) =
+            (parts.next(), parts.next(), parts.next())
+        {
+            output.push_str(data);
+            output.push('\n');
+        }
+    }
+
+    let (result_input, result_output) = mpsc::channel();
+    let out_of_band = Arc::new(Mutex::new(Vec::new()));
+    output::process_output(
+        output.as_bytes(),
+        result_input,
+        CollectingSink(out_of_band.clone()),
+        Arc::new(AtomicBool::new(false)),
+        Arc::new(Mutex::new(None)),
+        None,
+        Arc::new(Mutex::new(None)),
+    );
+    let out_of_band = ::std::mem::take(&mut *out_of_band.lock().unwrap());
+    Ok(Replay {
+        results: result_output
+            .try_iter()
+            .filter_map(|o| match o {
+                ResultOutput::Record(record) => Some(record),
+                ResultOutput::Prompt => None,
+            })
+            .collect(),
+        out_of_band,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use gdbmi::output::{AsyncClass, ResultClass};
+
+    #[test]
+    fn test_replay() {
+        let log = "0.000100 > 1-environment-pwd\n\
+                   0.000200 < ~\"Reading symbols...\\n\"\n\
+                   0.000300 < 1^done,cwd=\"/tmp\"\n\
+                   0.000400 < (gdb) \n\
+                   0.000500 < *stopped,reason=\"end-stepping-range\",thread-id=\"1\"\n";
+        let replay = replay(log.as_bytes()).unwrap();
+        assert_eq!(replay.results.len(), 1);
+        assert_eq!(replay.results[0].class, ResultClass::Done);
+        assert_eq!(replay.results[0].results["cwd"], "/tmp");
+        assert_eq!(replay.out_of_band.len(), 2);
+        match &replay.out_of_band[1] {
+            OutOfBandRecord::AsyncRecord { class, .. } => assert_eq!(*class, AsyncClass::Stopped),
+            o => panic!("Unexpected record: {:?}", o),
+        }
+    }
+}
@@ -0,0 +1,786 @@
+// Typed representations of common gdb/mi responses.
+//
+// All structs can be created from the corresponding (parsed) json values using TryFrom. Fields that
+// gdb only reports in some situations (e.g., "level" is missing in frames of stop records) are
+// optional.
+use super::output::{AsyncClass, BreakPointEvent, JsonValue, Object, ThreadEvent};
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    NotAnObject,
+    MissingField(&'static str),
+    MalformedField(&'static str, String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::NotAnObject => write!(f, "Expected an object"),
+            ParseError::MissingField(field) => write!(f, "Missing field '{}'", field),
+            ParseError::MalformedField(field, value) => {
+                write!(f, "Malformed value '{}' of field '{}'", value, field)
+            }
+        }
+    }
+}
+
+// Values of records (JsonValue::Object) and the results of result records (Object).
+trait Fields {
+    fn field(&self, key: &'static str) -> &JsonValue;
+
+    fn str_field(&self, key: &'static str) -> Result<&str, ParseError> {
+        self.field(key)
+            .as_str()
+            .ok_or(ParseError::MissingField(key))
+    }
+
+    fn opt_str_field(&self, key: &'static str) -> Option<String> {
+        self.field(key).as_str().map(|s| s.to_owned())
+    }
+
+    fn u64_field(&self, key: &'static str) -> Result<u64, ParseError> {
+        let s = self.str_field(key)?;
+        s.parse::<u64>()
+            .map_err(|_| ParseError::MalformedField(key, s.to_owned()))
+    }
+
+    fn opt_u64_field(&self, key: &'static str) -> Result<Option<u64>, ParseError> {
+        match self.str_field(key) {
+            Ok(_) => self.u64_field(key).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn opt_addr_field(&self, key: &'static str) -> Result<Option<usize>, ParseError> {
+        match self.field(key).as_str() {
+            Some(s) => parse_address(s)
+                .map(Some)
+                .ok_or_else(|| ParseError::MalformedField(key, s.to_owned())),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Fields for JsonValue {
+    fn field(&self, key: &'static str) -> &JsonValue {
+        &self[key]
+    }
+}
+
+impl Fields for Object {
+    fn field(&self, key: &'static str) -> &JsonValue {
+        &self[key]
+    }
+}
+
+fn parse_address(s: &str) -> Option<usize> {
+    usize::from_str_radix(s.strip_prefix("0x")?, 16).ok()
+}
+
+fn as_object(value: &JsonValue) -> Result<&JsonValue, ParseError> {
+    if value.is_object() {
+        Ok(value)
+    } else {
+        Err(ParseError::NotAnObject)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    pub level: Option<u64>,
+    pub addr: Option<usize>,
+    pub func: Option<String>,
+    pub file: Option<String>,
+    pub fullname: Option<PathBuf>,
+    pub line: Option<u64>,
+    /// Shared library of the frame (if the function is not part of the executable).
+    pub from: Option<String>,
+    /// Only reported (with their values) in stop records and thread-selected notifications.
+    pub args: Vec<Variable>,
+}
+
+impl Frame {
+    fn from_fields<F: Fields>(f: &F) -> Result<Self, ParseError> {
+        Ok(Frame {
+            level: f.opt_u64_field("level")?,
+            addr: f.opt_addr_field("addr")?,
+            func: f.opt_str_field("func"),
+            file: f.opt_str_field("file"),
+            fullname: f.opt_str_field("fullname").map(PathBuf::from),
+            line: f.opt_u64_field("line")?,
+            from: f.opt_str_field("from"),
+            args: f
+                .field("args")
+                .members()
+                .map(|a| Variable::try_from(a).map(|v| Variable { arg: true, ..v }))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a JsonValue> for Frame {
+    type Error = ParseError;
+    fn try_from(value: &'a JsonValue) -> Result<Self, ParseError> {
+        Frame::from_fields(as_object(value)?)
+    }
+}
+
+impl<'a> TryFrom<&'a Object> for Frame {
+    type Error = ParseError;
+    fn try_from(value: &'a Object) -> Result<Self, ParseError> {
+        Frame::from_fields(value)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakpoint {
+    /// Either "N" or, for one of the locations of a breakpoint, "N.M".
+    pub number: String,
+    /// E.g. "breakpoint", "watchpoint" or "catchpoint". Missing for locations.
+    pub type_: Option<String>,
+    pub enabled: bool,
+    /// Missing for pending breakpoints and breakpoints with multiple locations.
+    pub addr: Option<usize>,
+    pub func: Option<String>,
+    pub fullname: Option<PathBuf>,
+    pub line: Option<u64>,
+    pub condition: Option<String>,
+    pub ignore: Option<u64>,
+    pub times: Option<u64>,
+    /// Commands that are executed when the breakpoint is hit.
+    pub script: Vec<String>,
+    pub locations: Vec<Breakpoint>,
+}
+
+impl<'a> TryFrom<&'a JsonValue> for Breakpoint {
+    type Error = ParseError;
+    fn try_from(value: &'a JsonValue) -> Result<Self, ParseError> {
+        let f = as_object(value)?;
+        let addr = match f.field("addr").as_str() {
+            // Pending breakpoints and breakpoints with multiple locations do not have an address.
+            Some(a) if a.starts_with('<') => None,
+            _ => f.opt_addr_field("addr")?,
+        };
+        Ok(Breakpoint {
+            number: f.str_field("number")?.to_owned(),
+            type_: f.opt_str_field("type"),
+            enabled: f.str_field("enabled")? == "y",
+            addr,
+            func: f.opt_str_field("func"),
+            fullname: f.opt_str_field("fullname").map(PathBuf::from),
+            line: f.opt_u64_field("line")?,
+            condition: f.opt_str_field("cond"),
+            ignore: f.opt_u64_field("ignore")?,
+            times: f.opt_u64_field("times")?,
+            script: f
+                .field("script")
+                .members()
+                .filter_map(|c| c.as_str())
+                .map(|c| c.to_owned())
+                .collect(),
+            locations: f
+                .field("locations")
+                .members()
+                .map(Breakpoint::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Result of `-break-list`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BreakpointTable {
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+impl<'a> TryFrom<&'a Object> for BreakpointTable {
+    type Error = ParseError;
+    fn try_from(results: &'a Object) -> Result<Self, ParseError> {
+        let table = as_object(results.field("BreakpointTable"))
+            .map_err(|_| ParseError::MissingField("BreakpointTable"))?;
+        Ok(BreakpointTable {
+            breakpoints: table
+                .field("body")
+                .members()
+                .map(Breakpoint::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Thread {
+    pub id: String,
+    pub target_id: Option<String>,
+    pub name: Option<String>,
+    /// "running" or "stopped"
+    pub state: Option<String>,
+    pub frame: Option<Frame>,
+    pub core: Option<u64>,
+}
+
+impl<'a> TryFrom<&'a JsonValue> for Thread {
+    type Error = ParseError;
+    fn try_from(value: &'a JsonValue) -> Result<Self, ParseError> {
+        let f = as_object(value)?;
+        Ok(Thread {
+            id: f.str_field("id")?.to_owned(),
+            target_id: f.opt_str_field("target-id"),
+            name: f.opt_str_field("name"),
+            state: f.opt_str_field("state"),
+            frame: match f.field("frame") {
+                JsonValue::Null => None,
+                frame => Some(Frame::try_from(frame)?),
+            },
+            core: f.opt_u64_field("core")?,
+        })
+    }
+}
+
+/// An entry of the result of `-data-list-register-values`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Register {
+    pub number: u64,
+    pub value: String,
+}
+
+impl<'a> TryFrom<&'a JsonValue> for Register {
+    type Error = ParseError;
+    fn try_from(value: &'a JsonValue) -> Result<Self, ParseError> {
+        let f = as_object(value)?;
+        Ok(Register {
+            number: f.u64_field("number")?,
+            value: f.str_field("value")?.to_owned(),
+        })
+    }
+}
+
+/// An entry of the result of `-stack-list-variables`, i.e., an argument or a local of a frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub arg: bool,
+    /// Missing for `--no-values` (and for complex types with `--simple-values`).
+    pub value: Option<String>,
+    /// Only reported for `--simple-values`.
+    pub type_: Option<String>,
+}
+
+impl<'a> TryFrom<&'a JsonValue> for Variable {
+    type Error = ParseError;
+    fn try_from(value: &'a JsonValue) -> Result<Self, ParseError> {
+        let f = as_object(value)?;
+        Ok(Variable {
+            name: f.str_field("name")?.to_owned(),
+            arg: f.field("arg").as_str() == Some("1"),
+            value: f.opt_str_field("value"),
+            type_: f.opt_str_field("type"),
+        })
+    }
+}
+
+/// Result of `-var-create` or an entry of the result of `-var-list-children`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VarObject {
+    pub name: String,
+    /// Missing in the result of `-var-create`.
+    pub exp: Option<String>,
+    pub numchild: Option<u64>,
+    pub value: Option<String>,
+    /// Missing for pseudo children such as "public".
+    pub type_: Option<String>,
+    pub thread_id: Option<String>,
+    pub dynamic: bool,
+    /// Set by pretty printers, e.g., "array", "map" or "string".
+    pub display_hint: Option<String>,
+}
+
+impl VarObject {
+    fn from_fields<F: Fields>(f: &F) -> Result<Self, ParseError> {
+        Ok(VarObject {
+            name: f.str_field("name")?.to_owned(),
+            exp: f.opt_str_field("exp"),
+            numchild: f.opt_u64_field("numchild")?,
+            value: f.opt_str_field("value"),
+            type_: f.opt_str_field("type"),
+            thread_id: f.opt_str_field("thread-id"),
+            dynamic: f.field("dynamic").as_str() == Some("1"),
+            display_hint: f.opt_str_field("displayhint"),
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a JsonValue> for VarObject {
+    type Error = ParseError;
+    fn try_from(value: &'a JsonValue) -> Result<Self, ParseError> {
+        VarObject::from_fields(as_object(value)?)
+    }
+}
+
+impl<'a> TryFrom<&'a Object> for VarObject {
+    type Error = ParseError;
+    fn try_from(value: &'a Object) -> Result<Self, ParseError> {
+        VarObject::from_fields(value)
+    }
+}
+
+/// Payload of `=library-loaded` and `=library-unloaded` notifications and an entry of the result of
+/// `-file-list-shared-libraries`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Library {
+    pub id: String,
+    pub target_name: String,
+    pub host_name: Option<String>,
+    /// Only reported for loaded libraries.
+    pub symbols_loaded: Option<bool>,
+    pub thread_group: Option<String>,
+    /// Address ranges (from, to) of the code of the library. Only reported for loaded libraries.
+    pub ranges: Vec<(usize, usize)>,
+}
+
+impl Library {
+    fn from_fields<F: Fields>(f: &F) -> Result<Self, ParseError> {
+        Ok(Library {
+            id: f.str_field("id")?.to_owned(),
+            target_name: f.str_field("target-name")?.to_owned(),
+            host_name: f.opt_str_field("host-name"),
+            symbols_loaded: f.field("symbols-loaded").as_str().map(|s| s == "1"),
+            thread_group: f.opt_str_field("thread-group"),
+            ranges: f
+                .field("ranges")
+                .members()
+                .filter_map(|r| {
+                    Some((
+                        parse_address(r["from"].as_str()?)?,
+                        parse_address(r["to"].as_str()?)?,
+                    ))
+                })
+                .collect(),
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a Object> for Library {
+    type Error = ParseError;
+    fn try_from(f: &'a Object) -> Result<Self, ParseError> {
+        Library::from_fields(f)
+    }
+}
+
+impl<'a> TryFrom<&'a JsonValue> for Library {
+    type Error = ParseError;
+    fn try_from(value: &'a JsonValue) -> Result<Self, ParseError> {
+        Library::from_fields(as_object(value)?)
+    }
+}
+
+/// An entry of the result of `-list-thread-groups`, i.e., an inferior.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThreadGroup {
+    /// E.g., "i1".
+    pub id: String,
+    /// Only reported if the inferior is running.
+    pub pid: Option<String>,
+    /// Only reported if the inferior has exited.
+    pub exit_code: Option<String>,
+    pub executable: Option<PathBuf>,
+    /// Only reported with `--recurse 1`.
+    pub threads: Vec<Thread>,
+}
+
+impl<'a> TryFrom<&'a JsonValue> for ThreadGroup {
+    type Error = ParseError;
+    fn try_from(value: &'a JsonValue) -> Result<Self, ParseError> {
+        let f = as_object(value)?;
+        Ok(ThreadGroup {
+            id: f.str_field("id")?.to_owned(),
+            pid: f.opt_str_field("pid"),
+            exit_code: f.opt_str_field("exit-code"),
+            executable: f.opt_str_field("executable").map(PathBuf::from),
+            threads: f
+                .field("threads")
+                .members()
+                .map(Thread::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Typed representation of notify async records (`=...`), i.e., changes of the state of gdb that
+/// may not have been caused by commands of the frontend (but, e.g., by the user in the console).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Notification {
+    ThreadGroupAdded {
+        id: String,
+    },
+    ThreadGroupRemoved {
+        id: String,
+    },
+    ThreadGroupStarted {
+        id: String,
+        pid: String,
+    },
+    ThreadGroupExited {
+        id: String,
+        exit_code: Option<String>,
+    },
+    ThreadCreated {
+        id: String,
+        group_id: String,
+    },
+    ThreadExited {
+        id: String,
+        group_id: String,
+    },
+    ThreadSelected {
+        id: String,
+        frame: Option<Frame>,
+    },
+    LibraryLoaded(Library),
+    LibraryUnloaded(Library),
+    /// gdb versions prior to 8.3 report the locations of a breakpoint as separate breakpoints.
+    BreakpointCreated(Vec<Breakpoint>),
+    BreakpointModified(Vec<Breakpoint>),
+    BreakpointDeleted {
+        id: String,
+    },
+    CmdParamChanged {
+        param: String,
+        value: String,
+    },
+    MemoryChanged {
+        thread_group: String,
+        addr: usize,
+        len: usize,
+    },
+    /// Notifications that are not (yet) represented by one of the variants above.
+    Other(String),
+}
+
+/// Parse the "bkpt" result of `-break-insert` and breakpoint notifications.
+pub fn parse_breakpoints(results: &Object) -> Result<Vec<Breakpoint>, ParseError> {
+    match results.field("bkpt") {
+        JsonValue::Array(bkpts) => bkpts.iter().map(Breakpoint::try_from).collect(),
+        bkpt => Ok(vec![Breakpoint::try_from(bkpt)?]),
+    }
+}
+
+impl Notification {
+    pub fn parse(class: &AsyncClass, results: &Object) -> Result<Self, ParseError> {
+        let id = || results.str_field("id").map(|s| s.to_owned());
+        Ok(match class {
+            AsyncClass::Thread(ThreadEvent::GroupAdded) => {
+                Notification::ThreadGroupAdded { id: id()? }
+            }
+            AsyncClass::Thread(ThreadEvent::GroupRemoved) => {
+                Notification::ThreadGroupRemoved { id: id()? }
+            }
+            AsyncClass::Thread(ThreadEvent::GroupStarted) => Notification::ThreadGroupStarted {
+                id: id()?,
+                pid: results.str_field("pid")?.to_owned(),
+            },
+            AsyncClass::Thread(ThreadEvent::GroupExited) => Notification::ThreadGroupExited {
+                id: id()?,
+                exit_code: results.opt_str_field("exit-code"),
+            },
+            AsyncClass::Thread(ThreadEvent::Created) => Notification::ThreadCreated {
+                id: id()?,
+                group_id: results.str_field("group-id")?.to_owned(),
+            },
+            AsyncClass::Thread(ThreadEvent::Exited) => Notification::ThreadExited {
+                id: id()?,
+                group_id: results.str_field("group-id")?.to_owned(),
+            },
+            AsyncClass::Thread(ThreadEvent::Selected) => Notification::ThreadSelected {
+                id: id()?,
+                frame: match results.field("frame") {
+                    JsonValue::Null => None,
+                    frame => Some(Frame::try_from(frame)?),
+                },
+            },
+            AsyncClass::LibraryLoaded => Notification::LibraryLoaded(Library::try_from(results)?),
+            AsyncClass::LibraryUnloaded => {
+                Notification::LibraryUnloaded(Library::try_from(results)?)
+            }
+            AsyncClass::BreakPoint(BreakPointEvent::Created) => {
+                Notification::BreakpointCreated(parse_breakpoints(results)?)
+            }
+            AsyncClass::BreakPoint(BreakPointEvent::Modified) => {
+                Notification::BreakpointModified(parse_breakpoints(results)?)
+            }
+            AsyncClass::BreakPoint(BreakPointEvent::Deleted) => {
+                Notification::BreakpointDeleted { id: id()? }
+            }
+            AsyncClass::CmdParamChanged => Notification::CmdParamChanged {
+                param: results.str_field("param")?.to_owned(),
+                value: results.str_field("value")?.to_owned(),
+            },
+            AsyncClass::MemoryChanged => Notification::MemoryChanged {
+                thread_group: results.str_field("thread-group")?.to_owned(),
+                addr: results
+                    .opt_addr_field("addr")?
+                    .ok_or(ParseError::MissingField("addr"))?,
+                len: {
+                    let len = results.str_field("len")?;
+                    parse_address(len)
+                        .ok_or_else(|| ParseError::MalformedField("len", len.to_owned()))?
+                },
+            },
+            AsyncClass::Stopped | AsyncClass::Running => {
+                return Err(ParseError::MalformedField(
+                    "class",
+                    format!("{:?} is not a notification", class),
+                ))
+            }
+            AsyncClass::Other(class) => Notification::Other(class.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::output::{OutOfBandRecord, ResultRecord};
+    use super::super::traffic_log::replay;
+    use super::*;
+
+    fn parse_result(line: &str) -> ResultRecord {
+        let log = format!("0.000000 < {}", line);
+        replay(log.as_bytes()).unwrap().results.remove(0)
+    }
+
+    #[test]
+    fn test_frame() {
+        let r = parse_result("^done,frame={level=\"1\",addr=\"0x0000555555555131\",func=\"main\",file=\"main.c\",fullname=\"/tmp/main.c\",line=\"12\",arch=\"i386:x86-64\"}\n");
+        assert_eq!(
+            Frame::try_from(&r.results["frame"]),
+            Ok(Frame {
+                level: Some(1),
+                addr: Some(0x555555555131),
+                func: Some("main".to_owned()),
+                file: Some("main.c".to_owned()),
+                fullname: Some(PathBuf::from("/tmp/main.c")),
+                line: Some(12),
+                from: None,
+                args: Vec::new(),
+            })
+        );
+        let r = parse_result("^done,frame={addr=\"0x401136\",func=\"f\",args=[{name=\"x\",value=\"<optimized out>\"}],line=\"3\"}\n");
+        assert_eq!(
+            Frame::try_from(&r.results["frame"]).unwrap().args,
+            vec![Variable {
+                name: "x".to_owned(),
+                arg: true,
+                value: Some("<optimized out>".to_owned()),
+                type_: None,
+            }]
+        );
+        let r = parse_result("^done,frame={addr=\"0x7ffff7fe3290\",func=\"??\",from=\"/lib64/ld-linux-x86-64.so.2\"}\n");
+        let frame = Frame::try_from(&r.results["frame"]).unwrap();
+        assert_eq!(frame.level, None);
+        assert_eq!(frame.line, None);
+        assert_eq!(frame.from.as_deref(), Some("/lib64/ld-linux-x86-64.so.2"));
+
+        let r = parse_result("^done,frame={line=\"twelve\"}\n");
+        assert_eq!(
+            Frame::try_from(&r.results["frame"]),
+            Err(ParseError::MalformedField("line", "twelve".to_owned()))
+        );
+        assert_eq!(
+            Frame::try_from(&JsonValue::from("frame")),
+            Err(ParseError::NotAnObject)
+        );
+    }
+
+    #[test]
+    fn test_breakpoint_table() {
+        let r = parse_result("^done,BreakpointTable={nr_rows=\"2\",nr_cols=\"6\",hdr=[{width=\"7\",alignment=\"-1\",col_name=\"number\",colhdr=\"Num\"}],body=[bkpt={number=\"1\",type=\"breakpoint\",disp=\"keep\",enabled=\"y\",addr=\"0x0000000000001131\",func=\"main\",file=\"main.c\",fullname=\"/tmp/main.c\",line=\"12\",thread-groups=[\"i1\"],cond=\"x > 1\",times=\"3\",script={\"print x\",\"continue\"},original-location=\"main\"},bkpt={number=\"2\",type=\"breakpoint\",disp=\"keep\",enabled=\"n\",addr=\"<PENDING>\",pending=\"foo\",times=\"0\",original-location=\"foo\"}]}\n");
+        let table = BreakpointTable::try_from(&r.results).unwrap();
+        assert_eq!(table.breakpoints.len(), 2);
+        let first = &table.breakpoints[0];
+        assert_eq!(first.number, "1");
+        assert!(first.enabled);
+        assert_eq!(first.addr, Some(0x1131));
+        assert_eq!(first.condition.as_deref(), Some("x > 1"));
+        assert_eq!(first.times, Some(3));
+        assert_eq!(first.script, vec!["print x", "continue"]);
+        let second = &table.breakpoints[1];
+        assert!(!second.enabled);
+        assert_eq!(second.addr, None);
+        assert_eq!(second.line, None);
+        assert!(second.script.is_empty());
+
+        assert_eq!(
+            BreakpointTable::try_from(&Object::new()),
+            Err(ParseError::MissingField("BreakpointTable"))
+        );
+    }
+
+    #[test]
+    fn test_thread_groups_and_libraries() {
+        let r = parse_result("^done,groups=[{id=\"i1\",type=\"process\",pid=\"42\",executable=\"/tmp/a.out\",cores=[\"3\"],threads=[{id=\"1\",target-id=\"process 42\",state=\"stopped\"},{id=\"2\",target-id=\"Thread 43\",state=\"stopped\"}]},{id=\"i2\",type=\"process\",exit-code=\"1\"}]\n");
+        let groups = r.results["groups"]
+            .members()
+            .map(ThreadGroup::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(groups[0].pid.as_deref(), Some("42"));
+        assert_eq!(groups[0].threads.len(), 2);
+        assert_eq!(groups[0].threads[1].id, "2");
+        assert_eq!(groups[1].exit_code.as_deref(), Some("1"));
+        assert!(groups[1].threads.is_empty());
+
+        let r = parse_result("^done,shared-libraries=[{id=\"/lib/libc.so.6\",target-name=\"/lib/libc.so.6\",host-name=\"/lib/libc.so.6\",symbols-loaded=\"1\",thread-group=\"i1\",ranges=[{from=\"0x1000\",to=\"0x2000\"}]}]\n");
+        let library = Library::try_from(&r.results["shared-libraries"][0]).unwrap();
+        assert_eq!(library.symbols_loaded, Some(true));
+        assert_eq!(library.ranges, vec![(0x1000, 0x2000)]);
+    }
+
+    #[test]
+    fn test_thread_and_register() {
+        let r = parse_result("^done,threads=[{id=\"1\",target-id=\"process 42\",name=\"a.out\",frame={level=\"0\",addr=\"0x1131\",func=\"main\",args=[]},state=\"stopped\",core=\"3\"}],current-thread-id=\"1\"\n");
+        let thread = Thread::try_from(&r.results["threads"][0]).unwrap();
+        assert_eq!(thread.id, "1");
+        assert_eq!(thread.target_id.as_deref(), Some("process 42"));
+        assert_eq!(thread.frame.unwrap().func.unwrap(), "main");
+        assert_eq!(thread.core, Some(3));
+
+        let r = parse_result(
+            "^done,register-values=[{number=\"0\",value=\"0x1c\"},{number=\"1\",value=\"0\"}]\n",
+        );
+        let registers = r.results["register-values"]
+            .members()
+            .map(Register::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            registers,
+            vec![
+                Register {
+                    number: 0,
+                    value: "0x1c".to_owned()
+                },
+                Register {
+                    number: 1,
+                    value: "0".to_owned()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_variable() {
+        let r = parse_result("^done,variables=[{name=\"argc\",arg=\"1\",value=\"1\"},{name=\"p\",value=\"{x = 1, y = 2}\"},{name=\"i\",type=\"int\"}]\n");
+        let variables = r.results["variables"]
+            .members()
+            .map(Variable::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(variables.len(), 3);
+        assert!(variables[0].arg);
+        assert_eq!(variables[0].name, "argc");
+        assert!(!variables[1].arg);
+        assert_eq!(variables[1].value.as_deref(), Some("{x = 1, y = 2}"));
+        assert_eq!(variables[2].value, None);
+        assert_eq!(variables[2].type_.as_deref(), Some("int"));
+
+        let r = parse_result("^done,variables=[{arg=\"1\"}]\n");
+        assert_eq!(
+            Variable::try_from(&r.results["variables"][0]),
+            Err(ParseError::MissingField("name"))
+        );
+    }
+
+    #[test]
+    fn test_var_object() {
+        let r = parse_result("^done,name=\"var1\",numchild=\"2\",value=\"{...}\",type=\"struct foo\",thread-id=\"1\",has_more=\"0\"\n");
+        let var = VarObject::try_from(&r.results).unwrap();
+        assert_eq!(var.name, "var1");
+        assert_eq!(var.exp, None);
+        assert_eq!(var.numchild, Some(2));
+        assert_eq!(var.type_.as_deref(), Some("struct foo"));
+        assert!(!var.dynamic);
+        assert_eq!(var.display_hint, None);
+
+        let r = parse_result("^done,numchild=\"1\",children=[child={name=\"var1.public\",exp=\"public\",numchild=\"1\",thread-id=\"1\"}],has_more=\"0\"\n");
+        let child = VarObject::try_from(&r.results["children"][0]).unwrap();
+        assert_eq!(child.exp.as_deref(), Some("public"));
+        assert_eq!(child.type_, None);
+
+        let r = parse_result("^done,name=\"var2\",numchild=\"0\",value=\"std::vector of length 2, capacity 2\",type=\"std::vector<int>\",thread-id=\"1\",displayhint=\"array\",dynamic=\"1\",has_more=\"1\"\n");
+        let var = VarObject::try_from(&r.results).unwrap();
+        assert!(var.dynamic);
+        assert_eq!(var.display_hint.as_deref(), Some("array"));
+    }
+
+    #[test]
+    fn test_notification() {
+        let log = "0.1 < =thread-group-started,id=\"i1\",pid=\"42\"\n\
+                   0.2 < =breakpoint-modified,bkpt={number=\"1\",type=\"breakpoint\",enabled=\"y\",addr=\"<MULTIPLE>\",times=\"1\",locations=[{number=\"1.1\",enabled=\"y\",addr=\"0x1131\",func=\"f\",fullname=\"/tmp/main.c\",line=\"3\"}]}\n\
+                   0.3 < =breakpoint-deleted,id=\"1\"\n\
+                   0.4 < =cmd-param-changed,param=\"print pretty\",value=\"on\"\n\
+                   0.5 < =memory-changed,thread-group=\"i1\",addr=\"0x00007fffffffe024\",len=\"0x4\"\n\
+                   0.6 < =library-loaded,id=\"/lib/libc.so.6\",target-name=\"/lib/libc.so.6\",host-name=\"/lib/libc.so.6\",symbols-loaded=\"0\",thread-group=\"i1\",ranges=[{from=\"0x1000\",to=\"0x2000\"}]\n\
+                   0.7 < =tsv-created,name=\"trace\",initial=\"0\"\n\
+                   0.8 < *running,thread-id=\"all\"\n";
+        let notifications = replay(log.as_bytes())
+            .unwrap()
+            .out_of_band
+            .into_iter()
+            .map(|record| match record {
+                OutOfBandRecord::AsyncRecord { class, results, .. } => {
+                    Notification::parse(&class, &results)
+                }
+                o => panic!("Unexpected record: {:?}", o),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notifications[0],
+            Ok(Notification::ThreadGroupStarted {
+                id: "i1".to_owned(),
+                pid: "42".to_owned()
+            })
+        );
+        match &notifications[1] {
+            Ok(Notification::BreakpointModified(bkpts)) => {
+                assert_eq!(bkpts.len(), 1);
+                assert_eq!(bkpts[0].addr, None);
+                assert_eq!(bkpts[0].locations[0].addr, Some(0x1131));
+            }
+            n => panic!("Unexpected notification: {:?}", n),
+        }
+        assert_eq!(
+            notifications[2],
+            Ok(Notification::BreakpointDeleted { id: "1".to_owned() })
+        );
+        assert_eq!(
+            notifications[3],
+            Ok(Notification::CmdParamChanged {
+                param: "print pretty".to_owned(),
+                value: "on".to_owned()
+            })
+        );
+        assert_eq!(
+            notifications[4],
+            Ok(Notification::MemoryChanged {
+                thread_group: "i1".to_owned(),
+                addr: 0x7fffffffe024,
+                len: 4
+            })
+        );
+        match &notifications[5] {
+            Ok(Notification::LibraryLoaded(library)) => {
+                assert_eq!(library.target_name, "/lib/libc.so.6");
+                assert_eq!(library.symbols_loaded, Some(false));
+            }
+            n => panic!("Unexpected notification: {:?}", n),
+        }
+        assert_eq!(
+            notifications[6],
+            Ok(Notification::Other("tsv-created".to_owned()))
+        );
+        assert!(notifications[7].is_err());
+    }
+}
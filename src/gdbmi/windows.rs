@@ -0,0 +1,77 @@
+// The parts of talking to gdb that differ on Windows (with MinGW gdb): There are no signals, so gdb
+// is interrupted using console control events (which gdb forwards to the program using
+// `DebugBreakProcess`), and gdbs started by someone else connect to a named pipe instead of a pty.
+use super::{Error, MiStreams};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io;
+use std::iter;
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::io::{AsRawHandle, FromRawHandle};
+use std::process;
+use std::ptr;
+use winapi::shared::minwindef::FALSE;
+use winapi::shared::winerror::ERROR_PIPE_CONNECTED;
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::namedpipeapi::{ConnectNamedPipe, CreateNamedPipeW};
+use winapi::um::winbase::{
+    FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX, PIPE_READMODE_BYTE, PIPE_TYPE_BYTE,
+    PIPE_WAIT,
+};
+use winapi::um::wincon::{GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT};
+
+// Size of the buffers of the named pipe of new-ui connections.
+const PIPE_BUFFER_SIZE: u32 = 64 * 1024;
+
+// Send Ctrl-Break to the process group of a process that ugdb started (see `spawn_gdb`). gdb
+// handles it like Ctrl-C in its console, i.e., it breaks into the program.
+pub(super) fn interrupt_process_group(pid: u32) -> io::Result<()> {
+    if unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid) } == FALSE {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    OsStr::new(s).encode_wide().chain(iter::once(0)).collect()
+}
+
+// Create a named pipe and wait until gdb opens an MI interpreter on it. The user has to run
+// `new-ui mi <pipe>` in gdb, which opens the pipe like a tty.
+pub(super) fn connect_new_ui() -> Result<MiStreams, Error> {
+    let name = format!(r"\\.\pipe\ugdb-new-ui-{}", process::id());
+    let pipe = unsafe {
+        CreateNamedPipeW(
+            wide(&name).as_ptr(),
+            PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT,
+            1,
+            PIPE_BUFFER_SIZE,
+            PIPE_BUFFER_SIZE,
+            0,
+            ptr::null_mut(),
+        )
+    };
+    if pipe == INVALID_HANDLE_VALUE {
+        return Err(Error::Spawn(format!(
+            "cannot create a named pipe: {}",
+            io::Error::last_os_error()
+        )));
+    }
+    // The handle is closed when the file is dropped.
+    let pipe = unsafe { File::from_raw_handle(pipe as _) };
+
+    eprintln!(
+        "Waiting for gdb to connect. Run in gdb:\n  new-ui mi {}",
+        name
+    );
+    // gdb may have opened the pipe between creating and connecting it.
+    if unsafe { ConnectNamedPipe(pipe.as_raw_handle() as _, ptr::null_mut()) } == FALSE {
+        let error = io::Error::last_os_error();
+        if error.raw_os_error() != Some(ERROR_PIPE_CONNECTED as i32) {
+            return Err(Error::Spawn(format!("gdb did not connect: {}", error)));
+        }
+    }
+    let writer = pipe.try_clone()?;
+    Ok((Box::new(pipe), Box::new(writer)))
+}
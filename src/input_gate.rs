@@ -0,0 +1,109 @@
+// Keyboard input is read from stdin in a separate thread. While another program (e.g., an editor)
+// uses the terminal, that thread must not read from stdin, as it would otherwise steal the input
+// of the program. Bytes are read unbuffered, so that waiting for input using poll does not miss
+// bytes that have already been read into a buffer.
+use nix::errno::Errno;
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::unistd;
+use std::io;
+use std::os::unix::io::RawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const POLL_INTERVAL_MS: i32 = 50;
+
+#[derive(Clone, Default)]
+pub struct InputGate {
+    paused: Arc<AtomicBool>,
+}
+
+impl InputGate {
+    /// Stop reading input until `resume` is called. Input that arrives in the meantime is left
+    /// for other programs.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn reader(&self, fd: RawFd) -> GatedReader {
+        GatedReader {
+            fd,
+            paused: self.paused.clone(),
+        }
+    }
+}
+
+pub struct GatedReader {
+    fd: RawFd,
+    paused: Arc<AtomicBool>,
+}
+
+fn to_io_error(e: nix::Error) -> io::Error {
+    match e.as_errno() {
+        Some(errno) => io::Error::from_raw_os_error(errno as i32),
+        None => io::Error::other(e),
+    }
+}
+
+impl io::Read for GatedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.paused.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(POLL_INTERVAL_MS as u64));
+                continue;
+            }
+            let mut fds = [PollFd::new(self.fd, PollFlags::POLLIN)];
+            match poll(&mut fds, POLL_INTERVAL_MS) {
+                Ok(0) | Err(nix::Error::Sys(Errno::EINTR)) => continue,
+                Ok(_) => {}
+                Err(e) => return Err(to_io_error(e)),
+            }
+            // The gate may have been closed while waiting for input.
+            if self.paused.load(Ordering::SeqCst) {
+                continue;
+            }
+            match unistd::read(self.fd, buf) {
+                Err(nix::Error::Sys(Errno::EINTR)) => continue,
+                res => return res.map_err(to_io_error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Read;
+    use std::sync::mpsc;
+
+    #[test]
+    fn paused_gate_leaves_input() {
+        let (read_end, write_end) = unistd::pipe().unwrap();
+        let gate = InputGate::default();
+        gate.pause();
+        let mut reader = gate.reader(read_end);
+        let (sender, receiver) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            let mut buf = [0u8; 2];
+            let n = reader.read(&mut buf).unwrap();
+            sender.send(buf[..n].to_vec()).unwrap();
+        });
+        unistd::write(write_end, b"ab").unwrap();
+        assert!(receiver
+            .recv_timeout(Duration::from_millis(4 * POLL_INTERVAL_MS as u64))
+            .is_err());
+
+        gate.resume();
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+            b"ab".to_vec()
+        );
+        thread.join().unwrap();
+        unistd::close(read_end).unwrap();
+        unistd::close(write_end).unwrap();
+    }
+}
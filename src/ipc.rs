@@ -1,16 +1,26 @@
 use unix_socket::{UnixListener, UnixStream};
 
 use json;
+use log::warn;
+
+use actions;
 
 use gdb::BreakpointOperationError;
 use gdbmi::commands::{BreakPointLocation, MiCommand};
+use gdbmi::output::ResultClass;
 use gdbmi::ExecuteError;
 use std::ffi::OsString;
 use std::fs;
 use std::io::Read;
 use std::io::Write;
+use std::net::Shutdown;
 use std::path::{Path, PathBuf};
 use std::thread;
+use std::time::Duration;
+
+// Expressions may call functions of the debugged program, which may never return. A remote client
+// should not be able to block ugdb indefinitely that way.
+const EVALUATE_TIMEOUT: Duration = Duration::from_secs(10);
 
 struct IPCError {
     reason: &'static str,
@@ -40,7 +50,7 @@ pub struct IPCRequest {
 
 impl IPCRequest {
     pub fn respond(mut self, p: &mut ::Context) {
-        let reply = match Self::handle(p, self.raw_request) {
+        let reply = match Self::handle(p, self.raw_request, &self.response_channel) {
             Ok(reply_success) => reply_success,
             Err(reply_fail) => reply_fail.into_json(),
         };
@@ -49,7 +59,11 @@ impl IPCRequest {
         let _ = write_ipc_response(&mut self.response_channel, reply.dump().as_bytes());
     }
 
-    fn handle(p: &mut ::Context, raw_request: Vec<u8>) -> Result<json::JsonValue, IPCError> {
+    fn handle(
+        p: &mut ::Context,
+        raw_request: Vec<u8>,
+        response_channel: &UnixStream,
+    ) -> Result<json::JsonValue, IPCError> {
         let str_request = ::std::str::from_utf8(raw_request.as_slice())
             .map_err(|_| IPCError::new("Malformed utf8.", ""))?;
         let json_request =
@@ -73,7 +87,12 @@ impl IPCRequest {
                 ));
             }
         };
-        let result = Self::dispatch(function_name)?(p, parameters)?;
+        // Subscriptions are the only function that needs access to the connection itself.
+        let result = if function_name == "subscribe" {
+            Self::subscribe(p, parameters, response_channel)?
+        } else {
+            Self::dispatch(function_name)?(p, parameters)?
+        };
 
         Ok(object! {
             "type" => "success",
@@ -91,6 +110,8 @@ impl IPCRequest {
             "set_breakpoint" => Ok(Self::set_breakpoint),
             "show_file" => Ok(Self::show_file),
             "get_instance_info" => Ok(Self::get_instance_info),
+            "evaluate" => Ok(Self::evaluate),
+            "action" => Ok(Self::action),
             _ => Err(IPCError::new("unknown function", function_name)),
         }
     }
@@ -136,7 +157,28 @@ impl IPCRequest {
                 //breakpoint, and resume execution thereafter.
                 Err(IPCError::new("Could not insert breakpoint:", msg))
             }
+            Err(BreakpointOperationError::Quit(e)) => {
+                Err(IPCError::new("Could not insert breakpoint", e.to_string()))
+            }
+        }
+    }
+
+    fn action(
+        p: &mut ::Context,
+        parameters: &json::JsonValue,
+    ) -> Result<json::JsonValue, IPCError> {
+        let id = parameters["id"]
+            .as_str()
+            .ok_or(IPCError::new("Missing action id", parameters.dump()))?;
+        let args = parameters["args"].as_str().unwrap_or("");
+        let action = actions::find(id).ok_or(IPCError::new("Unknown action", id))?;
+        if let actions::Args::Required(usage) = action.args {
+            if args.is_empty() {
+                return Err(IPCError::new("Missing arguments", usage));
+            }
         }
+        action.run(args, p);
+        Ok(json::JsonValue::String(format!("Executed !{}", id)))
     }
 
     fn show_file(
@@ -190,7 +232,12 @@ impl IPCRequest {
                     //and resume execution thereafter.
                     IPCError::new("Could not get working directory", "GDB is busy")
                 }
-                ExecuteError::Quit => IPCError::new("Could not get working directory", "GDB quit"),
+                ExecuteError::Quit(e) => {
+                    IPCError::new("Could not get working directory", e.to_string())
+                }
+                ExecuteError::Timeout => {
+                    IPCError::new("Could not get working directory", "GDB did not respond")
+                }
             })?;
         let working_directory = result.results["cwd"].as_str().ok_or_else(|| {
             IPCError::new("Could not get working directory", "Malformed GDB response")
@@ -199,6 +246,127 @@ impl IPCRequest {
             "working_directory" => working_directory
         })
     }
+
+    fn evaluate(
+        p: &mut ::Context,
+        parameters: &json::JsonValue,
+    ) -> Result<json::JsonValue, IPCError> {
+        let expression = parameters["expression"]
+            .as_str()
+            .ok_or(IPCError::new("Missing expression", parameters.dump()))?;
+        let result = p
+            .gdb
+            .mi
+            .execute_with_timeout(
+                MiCommand::data_evaluate_expression(expression.to_owned()),
+                EVALUATE_TIMEOUT,
+            )
+            .map_err(|e| match e {
+                ExecuteError::Busy => IPCError::new("Could not evaluate expression", "GDB is busy"),
+                ExecuteError::Quit(e) => {
+                    IPCError::new("Could not evaluate expression", e.to_string())
+                }
+                ExecuteError::Timeout => {
+                    if let Err(e) = p.gdb.mi.cancel_pending(EVALUATE_TIMEOUT) {
+                        p.log(format!("Failed to interrupt evaluation: {:?}", e));
+                    }
+                    IPCError::new("Could not evaluate expression", "Evaluation timed out")
+                }
+            })?;
+        match result.class {
+            ResultClass::Done => Ok(object! {
+                "expression" => expression,
+                "value" => result.results["value"].as_str().unwrap_or("")
+            }),
+            _ => Err(IPCError::new(
+                "Could not evaluate expression",
+                result.results["msg"].as_str().unwrap_or("Unknown error"),
+            )),
+        }
+    }
+
+    fn subscribe(
+        p: &mut ::Context,
+        parameters: &json::JsonValue,
+        response_channel: &UnixStream,
+    ) -> Result<json::JsonValue, IPCError> {
+        if !parameters["events"].is_array() {
+            return Err(IPCError::new("Missing event list", parameters.dump()));
+        }
+        let mut events = Vec::new();
+        for event in parameters["events"].members() {
+            match event.as_str() {
+                Some(e) if NOTIFICATION_EVENTS.contains(&e) => events.push(e.to_owned()),
+                _ => return Err(IPCError::new("Unknown event", event.dump())),
+            }
+        }
+        let stream = response_channel
+            .try_clone()
+            .and_then(|stream| {
+                stream.set_write_timeout(Some(NOTIFICATION_TIMEOUT))?;
+                Ok(stream)
+            })
+            .map_err(|e| IPCError::new("Could not subscribe", e.to_string()))?;
+        p.ipc_subscribers.add(stream, events.clone());
+        Ok(json::JsonValue::String(format!(
+            "Subscribed to {}",
+            events.join(", ")
+        )))
+    }
+}
+
+/// Events that clients can subscribe to.
+const NOTIFICATION_EVENTS: &[&str] = &["stopped"];
+
+/// How long sending a notification may block the UI (i.e., if the buffer of the socket is full
+/// because a client does not read its notifications) before the client is dropped.
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_millis(50);
+
+struct Subscriber {
+    stream: UnixStream,
+    events: Vec<String>,
+}
+
+/// Connections that have subscribed to notifications about events (e.g., the program stopped).
+pub struct Subscribers {
+    subscribers: Vec<Subscriber>,
+}
+
+impl Subscribers {
+    pub fn new() -> Self {
+        Subscribers {
+            subscribers: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, stream: UnixStream, events: Vec<String>) {
+        self.subscribers.push(Subscriber { stream, events });
+    }
+
+    /// Send a notification to all clients subscribed to the event. Clients that cannot be reached
+    /// (e.g., because they closed the connection or do not read the notifications in time) are
+    /// dropped and, as the message might have been sent only partially, disconnected.
+    pub fn notify(&mut self, event: &str, details: json::JsonValue) {
+        let msg = object! {
+            "type" => "notification",
+            "event" => event,
+            "details" => details
+        }
+        .dump();
+        self.subscribers.retain(|s| {
+            if !s.events.iter().any(|e| e == event) {
+                return true;
+            }
+            match write_ipc_response(&mut &s.stream, msg.as_bytes()) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Dropping IPC subscriber: {}", e);
+                    let _ = s.stream.shutdown(Shutdown::Both);
+                    false
+                }
+            }
+        });
+    }
 }
 
 const FALLBACK_RUNTIME_DIR: &'static str = "/tmp/";
@@ -277,11 +445,15 @@ fn start_connection(mut connection: UnixStream, request_sink: std::sync::mpsc::S
         });
 }
 
+fn socket_dir() -> PathBuf {
+    let runtime_dir =
+        ::std::env::var_os("XDG_RUNTIME_DIR").unwrap_or(OsString::from(FALLBACK_RUNTIME_DIR));
+    Path::join(runtime_dir.as_ref(), RUNTIME_SUBDIR)
+}
+
 impl IPC {
     pub fn setup(request_sink: std::sync::mpsc::Sender<::Event>) -> ::std::io::Result<Self> {
-        let runtime_dir =
-            ::std::env::var_os("XDG_RUNTIME_DIR").unwrap_or(OsString::from(FALLBACK_RUNTIME_DIR));
-        let ugdb_dir = Path::join(runtime_dir.as_ref(), RUNTIME_SUBDIR);
+        let ugdb_dir = socket_dir();
         let _ = fs::create_dir(&ugdb_dir); //Ignore error if dir exists, we check if we can access it soon.
 
         use rand::Rng;
@@ -315,3 +487,61 @@ impl ::std::ops::Drop for IPC {
         let _ = fs::remove_file(&self.socket_path);
     }
 }
+
+/// Sockets of all running ugdb instances, i.e., the sockets that accept connections.
+pub fn find_sockets() -> ::std::io::Result<Vec<PathBuf>> {
+    let mut sockets = Vec::new();
+    let entries = match fs::read_dir(socket_dir()) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == ::std::io::ErrorKind::NotFound => return Ok(sockets),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        // Sockets of crashed instances may still be lying around.
+        if UnixStream::connect(&path).is_ok() {
+            sockets.push(path);
+        }
+    }
+    Ok(sockets)
+}
+
+/// Client side of a connection to the IPC socket of a running ugdb instance.
+pub struct IPCClient {
+    stream: UnixStream,
+}
+
+impl IPCClient {
+    pub fn connect(socket_path: &Path) -> ::std::io::Result<Self> {
+        Ok(IPCClient {
+            stream: UnixStream::connect(socket_path)?,
+        })
+    }
+
+    /// Send a request and wait for the response.
+    pub fn request(
+        &mut self,
+        function: &str,
+        parameters: json::JsonValue,
+    ) -> ::std::io::Result<json::JsonValue> {
+        let request = object! {
+            "function" => function,
+            "parameters" => parameters
+        };
+        write_ipc_response(&mut self.stream, request.dump().as_bytes())?;
+        self.receive()
+    }
+
+    /// Wait for the next message (response or notification) from the ugdb instance.
+    pub fn receive(&mut self) -> ::std::io::Result<json::JsonValue> {
+        let invalid = |details: &str| {
+            ::std::io::Error::new(::std::io::ErrorKind::InvalidData, details.to_owned())
+        };
+        let msg_len =
+            try_read_ipc_header(&mut self.stream).map_err(|_| invalid("Malformed ipc header"))?;
+        let mut msg_buf = vec![0u8; msg_len as usize];
+        self.stream.read_exact(&mut msg_buf)?;
+        let msg = String::from_utf8(msg_buf).map_err(|_| invalid("Malformed utf8"))?;
+        json::parse(&msg).map_err(|_| invalid("Malformed json"))
+    }
+}
@@ -1,12 +1,20 @@
-use std::str::CharIndices;
-use tui::{Tui, TuiContainerType};
-use unsegen::container::{HSplit, Layout, Leaf, VSplit};
+use std::str::{CharIndices, FromStr};
+use tui::{TitleBar, Tui, TuiContainerType};
+use unsegen::base::basic_types::*;
+use unsegen::base::{Color, Cursor, CursorTarget, StyleModifier, Window};
+use unsegen::container::{
+    ContainerProvider, HSplit, Layout, LayoutOutput, Leaf, Rectangle, VSplit, VerticalLine,
+};
+use unsegen::widget::{Demand2D, RowDemand};
 
 #[derive(Debug, PartialEq)]
 pub enum LayoutParseErrorKind {
     TooShortExpected(&'static [char]),
     ExpectedGotMany(usize, &'static [char], char),
     SplitTypeChangeFromTo(usize, char, char),
+    InvalidWeight(usize),
+    MixedWeights(usize),
+    PercentagesExceed(usize),
     NoConsole,
 }
 
@@ -24,28 +32,51 @@ impl std::fmt::Display for LayoutParseError {
             &[l] => format!("'{}'", l),
             o => format!("One of {:?}", o),
         };
+        // The layout string with a caret below the character at the (byte) index.
         let layout = &self.layout;
+        let location =
+            |at: usize| format!("{}\n{}^", layout, " ".repeat(layout[..at].chars().count()));
         match self.kind {
             LayoutParseErrorKind::ExpectedGotMany(at, expected, got) => {
                 writeln!(
                     f,
-                    "Expected {}, but got {}!\n{}☛{} ",
+                    "Expected {}, but got {}!\n{}",
                     format_expected(expected),
                     got,
-                    &layout[..at],
-                    &layout[at..]
+                    location(at)
                 )
             }
             LayoutParseErrorKind::TooShortExpected(expected) => {
                 writeln!(
                     f,
-                    "Too short! Expected at least {}.\n{}☚",
+                    "Too short! Expected at least {}.\n{}",
                     format_expected(expected),
-                    &layout
+                    location(layout.len())
                 )
             }
             LayoutParseErrorKind::SplitTypeChangeFromTo(at, from, to) => {
-                writeln!(f, "Split type cannot change from '{}' to '{}' within a node. Try to use brackets.\n{}☛{}", from, to, &layout[..at], &layout[at..])
+                writeln!(f, "Split type cannot change from '{}' to '{}' within a node. Try to use brackets.\n{}", from, to, location(at))
+            }
+            LayoutParseErrorKind::InvalidWeight(at) => {
+                writeln!(
+                    f,
+                    "Invalid weight! Expected a positive number, optionally followed by '%'.\n{}",
+                    location(at)
+                )
+            }
+            LayoutParseErrorKind::MixedWeights(at) => {
+                writeln!(
+                    f,
+                    "Percentages cannot be mixed with other weights within a node.\n{}",
+                    location(at)
+                )
+            }
+            LayoutParseErrorKind::PercentagesExceed(at) => {
+                writeln!(
+                    f,
+                    "Percentages exceed 100% (or leave no space for nodes without weight).\n{}",
+                    location(at)
+                )
             }
             LayoutParseErrorKind::NoConsole => {
                 writeln!(
@@ -57,80 +88,648 @@ impl std::fmt::Display for LayoutParseError {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutNode {
+    Leaf(TuiContainerType),
+    HSplit(Vec<(LayoutNode, f64)>),
+    VSplit(Vec<(LayoutNode, f64)>),
+    Tabs(Vec<TuiContainerType>, usize), // The index of the selected tab
+    AutoHide(TuiContainerType),         // Leaf that is only shown if it has content
+}
+
+/// Which containers have a title bar (with their name and badges describing their state) above
+/// them.
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum TitleBars {
+    None,
+    /// Only the pager, whose title bar shows the current frame.
+    #[default]
+    Pager,
+    All,
+}
+
+impl TitleBars {
+    fn has(self, container: &TuiContainerType) -> bool {
+        match self {
+            TitleBars::None => false,
+            TitleBars::Pager => *container == TuiContainerType::SrcView,
+            TitleBars::All => true,
+        }
+    }
+}
+
+impl FromStr for TitleBars {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(TitleBars::None),
+            "pager" => Ok(TitleBars::Pager),
+            "all" => Ok(TitleBars::All),
+            o => Err(format!(
+                "Unknown title bar setting '{}'. Supported: none, pager, all",
+                o
+            )),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ResizeDirection {
+    Wider,
+    Narrower,
+    Taller,
+    Shorter,
+}
+
+// Fraction of the space of the enclosing split by which a single resize step changes a node.
+const RESIZE_STEP: f64 = 0.1;
+// No node can be shrunk below this fraction of the space of the enclosing split.
+const MIN_WEIGHT_FRACTION: f64 = 0.05;
+
+impl LayoutNode {
+    pub fn to_layout<'b>(&self, title_bars: TitleBars) -> Box<dyn Layout<Tui<'b>> + 'b> {
+        let children = |nodes: &Vec<(LayoutNode, f64)>| {
+            nodes
+                .iter()
+                .map(|(n, w)| (n.to_layout(title_bars), *w))
+                .collect::<Vec<_>>()
+        };
+        match self {
+            LayoutNode::Leaf(c) | LayoutNode::AutoHide(c) if title_bars.has(c) => {
+                Box::new(BarLayout {
+                    containers: vec![c.clone()],
+                    selected: 0,
+                })
+            }
+            LayoutNode::Leaf(c) | LayoutNode::AutoHide(c) => Box::new(Leaf::new(c.clone())),
+            LayoutNode::HSplit(nodes) => Box::new(HSplit::new(children(nodes))),
+            LayoutNode::VSplit(nodes) => Box::new(VSplit::new(children(nodes))),
+            LayoutNode::Tabs(tabs, selected) => Box::new(BarLayout {
+                containers: tabs.clone(),
+                selected: *selected,
+            }),
+        }
+    }
+
+    pub fn contains(&self, container: &TuiContainerType) -> bool {
+        match self {
+            LayoutNode::Leaf(c) | LayoutNode::AutoHide(c) => c == container,
+            LayoutNode::HSplit(nodes) | LayoutNode::VSplit(nodes) => {
+                nodes.iter().any(|(n, _)| n.contains(container))
+            }
+            LayoutNode::Tabs(tabs, _) => tabs.contains(container),
+        }
+    }
+
+    /// The layout without the auto-hiding leaves of the containers that are to be hidden. Their
+    /// space is distributed among the remaining nodes of the enclosing split. Returns None if
+    /// nothing is left.
+    pub fn without_hidden(&self, hide: &dyn Fn(&TuiContainerType) -> bool) -> Option<LayoutNode> {
+        let (nodes, horizontal) = match self {
+            LayoutNode::AutoHide(c) if hide(c) => return None,
+            LayoutNode::Leaf(_) | LayoutNode::AutoHide(_) | LayoutNode::Tabs(..) => {
+                return Some(self.clone())
+            }
+            LayoutNode::HSplit(nodes) => (nodes, true),
+            LayoutNode::VSplit(nodes) => (nodes, false),
+        };
+        let mut nodes = nodes
+            .iter()
+            .filter_map(|(n, w)| n.without_hidden(hide).map(|n| (n, *w)))
+            .collect::<Vec<_>>();
+        match (nodes.len(), horizontal) {
+            (0, _) => None,
+            (1, _) => nodes.pop().map(|(n, _)| n),
+            (_, true) => Some(LayoutNode::HSplit(nodes)),
+            (_, false) => Some(LayoutNode::VSplit(nodes)),
+        }
+    }
+
+    /// Select the tab of the container (if it is part of a tab node). Returns whether the selected
+    /// tab changed.
+    pub fn select_tab(&mut self, container: &TuiContainerType) -> bool {
+        match self {
+            LayoutNode::Leaf(_) | LayoutNode::AutoHide(_) => false,
+            LayoutNode::HSplit(nodes) | LayoutNode::VSplit(nodes) => nodes
+                .iter_mut()
+                .any(|(n, _)| n.contains(container) && n.select_tab(container)),
+            LayoutNode::Tabs(tabs, selected) => match tabs.iter().position(|c| c == container) {
+                Some(i) if i != *selected => {
+                    *selected = i;
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// Select the next (or previous) tab of the tab node containing the container. Returns the
+    /// container of the now selected tab.
+    pub fn switch_tab(
+        &mut self,
+        container: &TuiContainerType,
+        forward: bool,
+    ) -> Option<TuiContainerType> {
+        match self {
+            LayoutNode::Leaf(_) | LayoutNode::AutoHide(_) => None,
+            LayoutNode::HSplit(nodes) | LayoutNode::VSplit(nodes) => nodes
+                .iter_mut()
+                .find(|(n, _)| n.contains(container))
+                .and_then(|(n, _)| n.switch_tab(container, forward)),
+            LayoutNode::Tabs(tabs, selected) => {
+                if !tabs.contains(container) {
+                    return None;
+                }
+                *selected = if forward {
+                    (*selected + 1) % tabs.len()
+                } else {
+                    (*selected + tabs.len() - 1) % tabs.len()
+                };
+                Some(tabs[*selected].clone())
+            }
+        }
+    }
+
+    /// Label the tab bars of all tab nodes and draw the title bars, once the layout has been
+    /// drawn to the window.
+    pub fn draw_bars(&self, mut window: Window, tui: &Tui, title_bars: TitleBars, running: bool) {
+        let areas = self.container_areas(&window, tui, title_bars);
+        let title_bar = |container: &TuiContainerType, width: usize| {
+            if title_bars.has(container) {
+                Some(tui.title_bar(container, width, running))
+            } else {
+                None
+            }
+        };
+        self.draw_bars_in(&areas, &mut window, &title_bar);
+    }
+
+    /// Draw the text of all containers but the active one in the color, once the layout has been
+    /// drawn to the window.
+    pub fn dim_inactive(
+        &self,
+        mut window: Window,
+        tui: &Tui,
+        title_bars: TitleBars,
+        active: &TuiContainerType,
+        color: Color,
+    ) {
+        let dimmed = StyleModifier::new().fg_color(color);
+        for (container, rect) in self.container_areas(&window, tui, title_bars) {
+            if container == *active {
+                continue;
+            }
+            for y in rect.y_range.start.raw_value()..rect.y_range.end.raw_value() {
+                for x in rect.x_range.start.raw_value()..rect.x_range.end.raw_value() {
+                    if let Some(cell) = window.get_cell_mut(ColIndex::new(x), RowIndex::new(y)) {
+                        dimmed.modify(&mut cell.style);
+                    }
+                }
+            }
+        }
+    }
+
+    // The area of each shown container within the window.
+    fn container_areas(
+        &self,
+        window: &Window,
+        tui: &Tui,
+        title_bars: TitleBars,
+    ) -> Vec<(TuiContainerType, Rectangle)> {
+        let area = Rectangle {
+            x_range: ColIndex::new(0)..window.get_width().from_origin(),
+            y_range: RowIndex::new(0)..window.get_height().from_origin(),
+        };
+        self.to_layout(title_bars).layout(area, tui).windows
+    }
+
+    fn draw_bars_in(
+        &self,
+        windows: &[(TuiContainerType, Rectangle)],
+        window: &mut Window,
+        title_bar: &dyn Fn(&TuiContainerType, usize) -> Option<TitleBar>,
+    ) {
+        let (tabs, selected) = match self {
+            LayoutNode::Leaf(c) | LayoutNode::AutoHide(c) => (std::slice::from_ref(c), 0),
+            LayoutNode::HSplit(nodes) | LayoutNode::VSplit(nodes) => {
+                for (node, _) in nodes {
+                    node.draw_bars_in(windows, window, title_bar);
+                }
+                return;
+            }
+            LayoutNode::Tabs(tabs, selected) => (&tabs[..], *selected),
+        };
+        let rect = match windows.iter().find(|(c, _)| *c == tabs[selected]) {
+            Some((_, rect)) if rect.height() > 0 => rect,
+            _ => return,
+        };
+        let is_tabs = matches!(self, LayoutNode::Tabs(..));
+        let width = rect.width().raw_value().max(0) as usize;
+        let title = title_bar(&tabs[selected], width.saturating_sub(1));
+        if !is_tabs && title.is_none() {
+            return;
+        }
+        // The bar is the row above the (selected) container.
+        let row = rect.y_range.start - 1;
+        let mut bar = window.create_subwindow(rect.x_range.clone(), row..rect.y_range.start);
+        let mut cursor = Cursor::new(&mut bar).position(ColIndex::new(1), RowIndex::new(0));
+        if is_tabs {
+            for (i, tab) in tabs.iter().enumerate() {
+                let style = if i == selected {
+                    StyleModifier::new().invert(true).bold(true)
+                } else {
+                    StyleModifier::new()
+                };
+                cursor.set_style_modifier(style);
+                cursor.write(&format!(" {} ", tab.name()));
+                cursor.move_by(ColDiff::new(1), RowDiff::new(0));
+            }
+        }
+        let title = match title {
+            Some(title) => title,
+            None => return,
+        };
+        // The name of a tab is already shown in its label.
+        if !is_tabs || title.title != tabs[selected].name() {
+            cursor.set_style_modifier(StyleModifier::new().bold(true));
+            cursor.write(&title.title);
+        }
+        for badge in title.badges {
+            let style = if badge.warning {
+                StyleModifier::new().fg_color(Color::Yellow)
+            } else {
+                StyleModifier::new()
+            };
+            cursor.set_style_modifier(style);
+            cursor.write(&format!(" [{}]", badge.text));
+        }
+    }
+
+    /// Grow or shrink the node containing the container within the innermost enclosing split of
+    /// matching orientation. Returns false if there is no such split.
+    pub fn resize(&mut self, container: &TuiContainerType, direction: ResizeDirection) -> bool {
+        let (nodes, resize_this) = match (self, direction) {
+            (LayoutNode::HSplit(nodes), ResizeDirection::Wider)
+            | (LayoutNode::HSplit(nodes), ResizeDirection::Narrower)
+            | (LayoutNode::VSplit(nodes), ResizeDirection::Taller)
+            | (LayoutNode::VSplit(nodes), ResizeDirection::Shorter) => (nodes, true),
+            (LayoutNode::HSplit(nodes), _) | (LayoutNode::VSplit(nodes), _) => (nodes, false),
+            (LayoutNode::Leaf(_), _) | (LayoutNode::AutoHide(_), _) | (LayoutNode::Tabs(..), _) => {
+                return false
+            }
+        };
+        let total: f64 = nodes.iter().map(|(_, w)| w).sum();
+        let (node, weight) = if let Some(n) = nodes.iter_mut().find(|(n, _)| n.contains(container))
+        {
+            n
+        } else {
+            return false;
+        };
+        if node.resize(container, direction) {
+            return true;
+        }
+        if !resize_this {
+            return false;
+        }
+        let step = total * RESIZE_STEP;
+        *weight = match direction {
+            ResizeDirection::Wider | ResizeDirection::Taller => *weight + step,
+            ResizeDirection::Narrower | ResizeDirection::Shorter => {
+                (*weight - step).max(total * MIN_WEIGHT_FRACTION)
+            }
+        };
+        true
+    }
+}
+
+// Shows the selected tab (or the container of a leaf with a title bar) only and reserves the
+// first row for the tab or title bar. Since layouts can only place containers and separators, the
+// row is drawn as a separator, which is then labelled (see `LayoutNode::draw_bars`).
+struct BarLayout {
+    containers: Vec<TuiContainerType>,
+    selected: usize,
+}
+
+// E.g., "[SrcView*,Console]" if the first tab is selected.
+impl std::fmt::Debug for BarLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        for (i, tab) in self.containers.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{:?}", tab)?;
+            if i == self.selected {
+                write!(f, "*")?;
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+impl<'b> Layout<Tui<'b>> for BarLayout {
+    fn space_demand(&self, containers: &Tui<'b>) -> Demand2D {
+        let demand = containers
+            .get(&self.containers[self.selected])
+            .as_widget()
+            .space_demand();
+        Demand2D {
+            width: demand.width,
+            height: demand.height + RowDemand::exact(1),
+        }
+    }
+    fn layout(&self, available_area: Rectangle, _: &Tui<'b>) -> LayoutOutput<TuiContainerType> {
+        let start = available_area.y_range.start;
+        let end = available_area.y_range.end;
+        let mut separators = Vec::new();
+        if start < end {
+            separators.push(
+                VerticalLine {
+                    x_range: available_area.x_range.clone(),
+                    y: start,
+                }
+                .into(),
+            );
+        }
+        let content = Rectangle {
+            x_range: available_area.x_range,
+            y_range: (start + 1).min(end)..end,
+        };
+        LayoutOutput {
+            windows: vec![(self.containers[self.selected].clone(), content)],
+            separators,
+        }
+    }
+}
+
+fn leaf_char(container: &TuiContainerType) -> char {
+    match container {
+        TuiContainerType::Console => 'c',
+        TuiContainerType::Terminal => 't',
+        TuiContainerType::SrcView => 's',
+        TuiContainerType::ExpressionTable => 'e',
+        TuiContainerType::SearchResults => 'r',
+        TuiContainerType::LineTable => 'l',
+        TuiContainerType::Inferiors => 'i',
+        TuiContainerType::Memory => 'm',
+        TuiContainerType::MemoryMap => 'a',
+        TuiContainerType::Breakpoints => 'b',
+        TuiContainerType::Threads => 'h',
+        TuiContainerType::Backtrace => 'k',
+        TuiContainerType::Locals => 'v',
+        TuiContainerType::Skips => 'p',
+        TuiContainerType::Libraries => 'o',
+        TuiContainerType::Trace => 'n',
+    }
+}
+
+// Weights of a layout string are integers. Fractional weights (e.g., after resizing) are scaled
+// up, which keeps their ratios (approximately).
+fn integer_weights(nodes: &[(LayoutNode, f64)]) -> Vec<u64> {
+    let scale = if nodes.iter().all(|(_, w)| w.fract() == 0.0) {
+        1.0
+    } else {
+        100.0
+    };
+    nodes
+        .iter()
+        .map(|(_, w)| ((w * scale).round() as u64).max(1))
+        .collect()
+}
+
+/// Layout string that is parsed to the same layout, e.g., to save the current layout.
+impl std::fmt::Display for LayoutNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (nodes, separator) = match self {
+            LayoutNode::Leaf(c) => return write!(f, "{}", leaf_char(c)),
+            LayoutNode::AutoHide(c) => return write!(f, "{}?", leaf_char(c)),
+            LayoutNode::Tabs(tabs, _) => {
+                let tabs = tabs
+                    .iter()
+                    .map(|c| leaf_char(c).to_string())
+                    .collect::<Vec<_>>();
+                return write!(f, "[{}]", tabs.join(","));
+            }
+            LayoutNode::HSplit(nodes) => (nodes, '|'),
+            LayoutNode::VSplit(nodes) => (nodes, '-'),
+        };
+        let weights = integer_weights(nodes);
+        for (i, ((node, _), weight)) in nodes.iter().zip(weights).enumerate() {
+            if i > 0 {
+                write!(f, "{}", separator)?;
+            }
+            match node {
+                LayoutNode::Leaf(_) | LayoutNode::AutoHide(_) | LayoutNode::Tabs(..) => {
+                    write!(f, "{}{}", weight, node)?
+                }
+                _ => write!(f, "{}({})", weight, node)?,
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Copy, Clone)]
 enum SplitType {
     H,
     V,
     None,
 }
-struct Input<'a>(std::iter::Peekable<CharIndices<'a>>);
+struct Input<'a> {
+    chars: std::iter::Peekable<CharIndices<'a>>,
+    len: usize,
+}
 
-const NODE_START_CHARS: &'static [char] = &['c', 't', 's', 'e', '('];
+const NODE_START_CHARS: &'static [char] = &[
+    'c', 't', 's', 'e', 'r', 'l', 'i', 'm', 'b', 'h', 'k', 'v', '(', '[',
+];
+const LEAF_CHARS: &[char] = &['c', 't', 's', 'e', 'r', 'l', 'i', 'm', 'b', 'h', 'k', 'v'];
 const CLOSING_BRACKET_CHARS: &'static [char] = &[')'];
+const TAB_SEPARATOR_CHARS: &[char] = &[',', ']'];
+const SEPARATOR_CHARS: &[char] = &['|', '-'];
 
 impl<'a> Input<'a> {
     fn new(s: &'a str) -> Result<Self, LayoutParseErrorKind> {
-        let mut ret = Self(s.char_indices().peekable());
+        let mut ret = Input {
+            chars: s.char_indices().peekable(),
+            len: s.len(),
+        };
         let _ = ret
-            .0
+            .chars
             .peek()
             .ok_or(LayoutParseErrorKind::TooShortExpected(NODE_START_CHARS))?;
         Ok(ret)
     }
     fn current(&mut self) -> Option<char> {
-        self.0.peek().map(|v| v.1)
+        self.chars.peek().map(|v| v.1)
     }
     fn current_index(&mut self) -> usize {
-        self.0.peek().unwrap().0
+        self.chars.peek().unwrap().0
+    }
+    // Like current_index, but also valid at the end of the input.
+    fn position(&mut self) -> usize {
+        let len = self.len;
+        self.chars.peek().map(|v| v.0).unwrap_or(len)
     }
     fn advance(&mut self) {
-        self.0.next();
+        self.chars.next();
     }
 }
 
-fn try_parse_weight<'a>(i: &mut Input<'a>) -> f64 {
-    if !i.current().map(|v| v.is_digit(10)).unwrap_or(false) {
-        return 1.0;
+#[derive(Copy, Clone)]
+enum Weight {
+    Relative(f64), // e.g., "2" or "1.5"
+    Percent(f64),  // e.g., "30%"
+}
+
+fn try_parse_weight<'a>(i: &mut Input<'a>) -> Result<Option<Weight>, LayoutParseErrorKind> {
+    let at = i.position();
+    let mut number = String::new();
+    while let Some(c) = i.current().filter(|&c| c.is_ascii_digit() || c == '.') {
+        number.push(c);
+        i.advance();
     }
-    let mut w = 0;
-    loop {
-        if let Some(i) = i.current() {
-            w = match i.to_digit(10) {
-                Some(d) => w * 10 + d,
-                None => return w as _,
-            };
-        } else {
-            return w as _;
-        }
+    if number.is_empty() {
+        return Ok(None);
+    }
+    let value = match number.parse::<f64>() {
+        Ok(v) if v > 0.0 && v.is_finite() => v,
+        _ => return Err(LayoutParseErrorKind::InvalidWeight(at)),
+    };
+    if i.current() == Some('%') {
         i.advance();
+        Ok(Some(Weight::Percent(value)))
+    } else {
+        Ok(Some(Weight::Relative(value)))
+    }
+}
+
+// Nodes without a weight have a weight of 1, unless percentages are used within a split. Then the
+// nodes without a weight share the percentage that is left equally.
+fn resolve_weights(
+    nodes: Vec<(LayoutNode, Option<Weight>, usize)>,
+    split_start: usize,
+) -> Result<Vec<(LayoutNode, f64)>, LayoutParseErrorKind> {
+    let percent_total: f64 = nodes
+        .iter()
+        .filter_map(|(_, weight, _)| match weight {
+            Some(Weight::Percent(p)) => Some(p),
+            _ => None,
+        })
+        .sum();
+    if percent_total == 0.0 {
+        return Ok(nodes
+            .into_iter()
+            .map(|(node, weight, _)| match weight {
+                Some(Weight::Relative(w)) => (node, w),
+                _ => (node, 1.0),
+            })
+            .collect());
+    }
+    if let Some((_, _, at)) = nodes
+        .iter()
+        .find(|(_, weight, _)| matches!(weight, Some(Weight::Relative(_))))
+    {
+        return Err(LayoutParseErrorKind::MixedWeights(*at));
+    }
+    let num_unweighted = nodes
+        .iter()
+        .filter(|(_, weight, _)| weight.is_none())
+        .count();
+    let rest = 100.0 - percent_total;
+    if rest < 0.0 || (num_unweighted > 0 && rest <= 0.0) {
+        return Err(LayoutParseErrorKind::PercentagesExceed(split_start));
     }
+    Ok(nodes
+        .into_iter()
+        .map(|(node, weight, _)| match weight {
+            Some(Weight::Percent(p)) | Some(Weight::Relative(p)) => (node, p),
+            None => (node, rest / num_unweighted as f64),
+        })
+        .collect())
 }
-fn try_parse_leaf<'a, 'b>(i: &mut Input<'a>) -> Option<Box<dyn Layout<Tui<'b>> + 'b>> {
+fn try_parse_leaf<'a>(i: &mut Input<'a>) -> Option<LayoutNode> {
     let ret = match i.current()? {
-        'c' => Box::new(Leaf::new(TuiContainerType::Console)),
-        't' => Box::new(Leaf::new(TuiContainerType::Terminal)),
-        's' => Box::new(Leaf::new(TuiContainerType::SrcView)),
-        'e' => Box::new(Leaf::new(TuiContainerType::ExpressionTable)),
+        'c' => LayoutNode::Leaf(TuiContainerType::Console),
+        't' => LayoutNode::Leaf(TuiContainerType::Terminal),
+        's' => LayoutNode::Leaf(TuiContainerType::SrcView),
+        'e' => LayoutNode::Leaf(TuiContainerType::ExpressionTable),
+        'r' => LayoutNode::Leaf(TuiContainerType::SearchResults),
+        'l' => LayoutNode::Leaf(TuiContainerType::LineTable),
+        'i' => LayoutNode::Leaf(TuiContainerType::Inferiors),
+        'm' => LayoutNode::Leaf(TuiContainerType::Memory),
+        'a' => LayoutNode::Leaf(TuiContainerType::MemoryMap),
+        'b' => LayoutNode::Leaf(TuiContainerType::Breakpoints),
+        'h' => LayoutNode::Leaf(TuiContainerType::Threads),
+        'k' => LayoutNode::Leaf(TuiContainerType::Backtrace),
+        'v' => LayoutNode::Leaf(TuiContainerType::Locals),
+        'p' => LayoutNode::Leaf(TuiContainerType::Skips),
+        'o' => LayoutNode::Leaf(TuiContainerType::Libraries),
+        'n' => LayoutNode::Leaf(TuiContainerType::Trace),
         _ => return None,
     };
     i.advance();
     Some(ret)
 }
 
-fn parse_node<'a, 'b>(
-    i: &mut Input<'a>,
-) -> Result<Box<dyn Layout<Tui<'b>> + 'b>, LayoutParseErrorKind> {
+// Tabs (after the opening '['), e.g., "s,e,r]"
+fn parse_tabs<'a>(i: &mut Input<'a>) -> Result<LayoutNode, LayoutParseErrorKind> {
+    let mut tabs = Vec::new();
+    loop {
+        match try_parse_leaf(i) {
+            Some(LayoutNode::Leaf(c)) => tabs.push(c),
+            _ => {
+                return Err(match i.current() {
+                    Some(o) => {
+                        LayoutParseErrorKind::ExpectedGotMany(i.current_index(), LEAF_CHARS, o)
+                    }
+                    None => LayoutParseErrorKind::TooShortExpected(LEAF_CHARS),
+                })
+            }
+        }
+        match i.current() {
+            Some(',') => i.advance(),
+            Some(']') => {
+                i.advance();
+                return Ok(LayoutNode::Tabs(tabs, 0));
+            }
+            Some(o) => {
+                return Err(LayoutParseErrorKind::ExpectedGotMany(
+                    i.current_index(),
+                    TAB_SEPARATOR_CHARS,
+                    o,
+                ))
+            }
+            None => return Err(LayoutParseErrorKind::TooShortExpected(TAB_SEPARATOR_CHARS)),
+        }
+    }
+}
+
+fn parse_node<'a>(i: &mut Input<'a>) -> Result<LayoutNode, LayoutParseErrorKind> {
     let mut nodes = Vec::new();
     let mut split_type = SplitType::None;
+    let split_start = i.position();
     loop {
-        let weight = try_parse_weight(i);
+        let weight_start = i.position();
+        let weight = try_parse_weight(i)?;
         if let Some(l) = try_parse_leaf(i) {
-            nodes.push((l, weight));
+            let l = match (l, i.current()) {
+                (LayoutNode::Leaf(c), Some('?')) => {
+                    i.advance();
+                    LayoutNode::AutoHide(c)
+                }
+                (l, _) => l,
+            };
+            nodes.push((l, weight, weight_start));
         } else {
             match i.current() {
+                Some('[') => {
+                    i.advance();
+                    nodes.push((parse_tabs(i)?, weight, weight_start));
+                }
                 Some('(') => {
                     i.advance();
-                    nodes.push((parse_node(i)?, weight));
+                    nodes.push((parse_node(i)?, weight, weight_start));
                     match i.current() {
                         Some(')') => {
                             i.advance();
@@ -190,9 +789,10 @@ fn parse_node<'a, 'b>(
         };
         i.advance();
     }
+    let mut nodes = resolve_weights(nodes, split_start)?;
     Ok(match split_type {
-        SplitType::H => Box::new(HSplit::new(nodes)),
-        SplitType::V => Box::new(VSplit::new(nodes)),
+        SplitType::H => LayoutNode::HSplit(nodes),
+        SplitType::V => LayoutNode::VSplit(nodes),
         SplitType::None => {
             assert!(nodes.len() == 1);
             nodes.pop().unwrap().0
@@ -200,7 +800,7 @@ fn parse_node<'a, 'b>(
     })
 }
 
-pub fn parse<'a>(s: String) -> Result<Box<dyn Layout<Tui<'a>> + 'a>, LayoutParseError> {
+pub fn parse(s: String) -> Result<LayoutNode, LayoutParseError> {
     if !s.contains('c') {
         return Err(LayoutParseError {
             kind: LayoutParseErrorKind::NoConsole,
@@ -211,7 +811,15 @@ pub fn parse<'a>(s: String) -> Result<Box<dyn Layout<Tui<'a>> + 'a>, LayoutParse
         kind,
         layout: s.to_owned(),
     })?;
-    parse_node(&mut i).map_err(|kind| LayoutParseError {
+    let layout = parse_node(&mut i).and_then(|layout| match i.current() {
+        Some(o) => Err(LayoutParseErrorKind::ExpectedGotMany(
+            i.current_index(),
+            SEPARATOR_CHARS,
+            o,
+        )),
+        None => Ok(layout),
+    });
+    layout.map_err(|kind| LayoutParseError {
         kind,
         layout: s.to_owned(),
     })
@@ -227,7 +835,7 @@ mod test {
     #[track_caller]
     fn expect_equal(input: &str, expected: &str) {
         let parsed = parse(input.to_owned()).unwrap();
-        assert_eq!(&stringify(&*parsed), expected);
+        assert_eq!(&stringify(&*parsed.to_layout(TitleBars::None)), expected);
     }
     #[track_caller]
     fn expect_error(input: &str, e: LayoutParseErrorKind) {
@@ -267,10 +875,280 @@ mod test {
         );
     }
     #[test]
+    fn parse_trailing() {
+        expect_error(
+            "sc",
+            LayoutParseErrorKind::ExpectedGotMany(1, SEPARATOR_CHARS, 'c'),
+        );
+        expect_error(
+            "(s|c))",
+            LayoutParseErrorKind::ExpectedGotMany(5, SEPARATOR_CHARS, ')'),
+        );
+    }
+    #[test]
+    fn parse_tabs() {
+        expect_equal(
+            "s|(c-[e,b,k])",
+            "(1SrcView|1(1Console-1[ExpressionTable*,Breakpoints,Backtrace]))",
+        );
+        expect_equal("[c]", "[Console*]");
+        expect_error(
+            "c|[e,]",
+            LayoutParseErrorKind::ExpectedGotMany(5, LEAF_CHARS, ']'),
+        );
+        expect_error(
+            "c|[e-b]",
+            LayoutParseErrorKind::ExpectedGotMany(4, TAB_SEPARATOR_CHARS, '-'),
+        );
+        expect_error(
+            "c|[e",
+            LayoutParseErrorKind::TooShortExpected(TAB_SEPARATOR_CHARS),
+        );
+        let layout = parse("2[s,c]-e".to_owned()).unwrap();
+        assert_eq!(layout.to_string(), "2[s,c]-1e");
+        assert_eq!(parse(layout.to_string()).unwrap(), layout);
+    }
+    #[test]
+    fn auto_hide() {
+        let layout = parse("(s-2b?)|(c-[e,k]-h?)".to_owned()).unwrap();
+        assert_eq!(layout.to_string(), "1(1s-2b?)|1(1c-1[e,k]-1h?)");
+        assert_eq!(parse(layout.to_string()).unwrap(), layout);
+        assert!(layout.contains(&TuiContainerType::Breakpoints));
+
+        let shown = |hidden: &'static [TuiContainerType]| {
+            layout
+                .without_hidden(&|c| hidden.contains(c))
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(shown(&[]), layout.to_string());
+        assert_eq!(
+            shown(&[TuiContainerType::Threads]),
+            "1(1s-2b?)|1(1c-1[e,k])"
+        );
+        assert_eq!(
+            shown(&[TuiContainerType::Breakpoints, TuiContainerType::Threads]),
+            "1s|1(1c-1[e,k])"
+        );
+        // Only auto-hiding leaves are hidden.
+        assert_eq!(shown(&[TuiContainerType::SrcView]), layout.to_string());
+        assert_eq!(
+            parse("c?".to_owned()).unwrap().without_hidden(&|_| true),
+            None
+        );
+    }
+    #[test]
+    fn switch_tabs() {
+        let mut layout = parse("s|[e,b,c]".to_owned()).unwrap();
+        assert!(layout.select_tab(&TuiContainerType::Console));
+        assert!(!layout.select_tab(&TuiContainerType::Console));
+        assert!(!layout.select_tab(&TuiContainerType::SrcView));
+        assert_eq!(
+            layout.switch_tab(&TuiContainerType::Console, true),
+            Some(TuiContainerType::ExpressionTable)
+        );
+        assert_eq!(
+            layout.switch_tab(&TuiContainerType::ExpressionTable, false),
+            Some(TuiContainerType::Console)
+        );
+        assert_eq!(layout.switch_tab(&TuiContainerType::SrcView, true), None);
+        assert_eq!(
+            &stringify(&*layout.to_layout(TitleBars::None)),
+            "(1SrcView|1[ExpressionTable,Breakpoints,Console*])"
+        );
+    }
+    #[test]
+    fn title_bars() {
+        let layout = parse("s|(c-[e,b])".to_owned()).unwrap();
+        assert_eq!(
+            &stringify(&*layout.to_layout(TitleBars::Pager)),
+            "(1[SrcView*]|1(1Console-1[ExpressionTable*,Breakpoints]))"
+        );
+        assert_eq!(
+            &stringify(&*layout.to_layout(TitleBars::All)),
+            "(1[SrcView*]|1(1[Console*]-1[ExpressionTable*,Breakpoints]))"
+        );
+        assert_eq!("all".parse(), Ok(TitleBars::All));
+        assert!("some".parse::<TitleBars>().is_err());
+    }
+    #[test]
     fn parse_change_split() {
         expect_error(
             "c-e|t",
             LayoutParseErrorKind::SplitTypeChangeFromTo(3, '-', '|'),
         );
     }
+    #[test]
+    fn resize_innermost_matching_split() {
+        let mut layout = parse("(s-c)|(e-t)".to_owned()).unwrap();
+        assert!(layout.resize(&TuiContainerType::Console, ResizeDirection::Wider));
+        assert!(layout.resize(&TuiContainerType::Console, ResizeDirection::Taller));
+        assert_eq!(
+            &stringify(&*layout.to_layout(TitleBars::None)),
+            "(1.2(1SrcView-1.2Console)|1(1ExpressionTable-1Terminal))"
+        );
+        assert!(layout.resize(&TuiContainerType::Terminal, ResizeDirection::Shorter));
+        assert_eq!(
+            &stringify(&*layout.to_layout(TitleBars::None)),
+            "(1.2(1SrcView-1.2Console)|1(1ExpressionTable-0.8Terminal))"
+        );
+    }
+    #[test]
+    fn display_round_trip() {
+        let layout = parse("(s|2t|c)-99e".to_owned()).unwrap();
+        assert_eq!(layout.to_string(), "1(1s|2t|1c)-99e");
+        assert_eq!(parse(layout.to_string()).unwrap(), layout);
+
+        let mut layout = parse("(s-c)|(e-t)".to_owned()).unwrap();
+        layout.resize(&TuiContainerType::Console, ResizeDirection::Taller);
+        assert_eq!(layout.to_string(), "1(100s-120c)|1(1e-1t)");
+    }
+    #[test]
+    fn resize_without_matching_split() {
+        let mut layout = parse("s|c".to_owned()).unwrap();
+        assert!(!layout.resize(&TuiContainerType::Console, ResizeDirection::Taller));
+    }
+    #[test]
+    fn parse_huge_weight() {
+        let layout = parse("99999999999c|s".to_owned()).unwrap();
+        assert_eq!(layout.to_string(), "99999999999c|1s");
+    }
+    #[test]
+    fn parse_percentages() {
+        expect_equal("30%s|70%c", "(30SrcView|70Console)");
+        expect_equal("50%s|c|e", "(50SrcView|25Console|25ExpressionTable)");
+        expect_equal("(20%s-c)|e", "(1(20SrcView-80Console)|1ExpressionTable)");
+        expect_error("60%s|50%c", LayoutParseErrorKind::PercentagesExceed(0));
+        expect_error("c|(100%s-e)", LayoutParseErrorKind::PercentagesExceed(3));
+        expect_error("30%s|2c", LayoutParseErrorKind::MixedWeights(5));
+    }
+    #[test]
+    fn parse_decimal_weights() {
+        expect_equal("1.5s|c", "(1.5SrcView|1Console)");
+        let layout = parse(".5s|c".to_owned()).unwrap();
+        assert_eq!(layout.to_string(), "50s|100c");
+        expect_error("c|1.2.3s", LayoutParseErrorKind::InvalidWeight(2));
+        expect_error("0s|c", LayoutParseErrorKind::InvalidWeight(0));
+    }
+    #[test]
+    fn error_location() {
+        let e = parse("(s-c)|x".to_owned()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Failed to parse layout string: \n\
+             Expected One of ['c', 't', 's', 'e', 'r', 'l', 'i', 'm', 'b', 'h', 'k', 'v', '(', '['], but got x!\n\
+             (s-c)|x\n      ^\n"
+        );
+        let e = parse("(s-c".to_owned()).unwrap_err();
+        assert!(e.to_string().ends_with("(s-c\n    ^\n"));
+    }
+
+    use proptest::prelude::*;
+
+    fn arb_container() -> impl Strategy<Value = TuiContainerType> {
+        prop_oneof![
+            Just(TuiContainerType::SrcView),
+            Just(TuiContainerType::Console),
+            Just(TuiContainerType::ExpressionTable),
+            Just(TuiContainerType::Terminal),
+            Just(TuiContainerType::SearchResults),
+            Just(TuiContainerType::LineTable),
+            Just(TuiContainerType::Inferiors),
+            Just(TuiContainerType::Memory),
+            Just(TuiContainerType::MemoryMap),
+            Just(TuiContainerType::Breakpoints),
+            Just(TuiContainerType::Threads),
+            Just(TuiContainerType::Backtrace),
+            Just(TuiContainerType::Locals),
+            Just(TuiContainerType::Skips),
+            Just(TuiContainerType::Libraries),
+            Just(TuiContainerType::Trace),
+        ]
+    }
+
+    // Layouts as produced by the parser: Splits have at least two children, integer weights and
+    // there is a console somewhere.
+    fn arb_layout() -> impl Strategy<Value = LayoutNode> {
+        prop_oneof![
+            arb_container().prop_map(LayoutNode::Leaf),
+            arb_container().prop_map(LayoutNode::AutoHide),
+            prop::collection::vec(arb_container(), 1..4).prop_map(|tabs| LayoutNode::Tabs(tabs, 0)),
+        ]
+        .prop_recursive(4, 32, 4, |inner| {
+            let children =
+                prop::collection::vec((inner, 1..100u32).prop_map(|(n, w)| (n, w as f64)), 2..5);
+            prop_oneof![
+                children.clone().prop_map(LayoutNode::HSplit),
+                children.prop_map(LayoutNode::VSplit),
+            ]
+        })
+        .prop_map(|layout| {
+            if layout.contains(&TuiContainerType::Console) {
+                layout
+            } else {
+                LayoutNode::HSplit(vec![
+                    (layout, 1.0),
+                    (LayoutNode::Leaf(TuiContainerType::Console), 1.0),
+                ])
+            }
+        })
+    }
+
+    fn arb_resize() -> impl Strategy<Value = (TuiContainerType, ResizeDirection)> {
+        (
+            arb_container(),
+            prop_oneof![
+                Just(ResizeDirection::Wider),
+                Just(ResizeDirection::Narrower),
+                Just(ResizeDirection::Taller),
+                Just(ResizeDirection::Shorter),
+            ],
+        )
+    }
+
+    // Same structure, ignoring the weights.
+    fn same_structure(a: &LayoutNode, b: &LayoutNode) -> bool {
+        match (a, b) {
+            (LayoutNode::Leaf(a), LayoutNode::Leaf(b)) => a == b,
+            (LayoutNode::Tabs(a, _), LayoutNode::Tabs(b, _)) => a == b,
+            (LayoutNode::AutoHide(a), LayoutNode::AutoHide(b)) => a == b,
+            (LayoutNode::HSplit(a), LayoutNode::HSplit(b))
+            | (LayoutNode::VSplit(a), LayoutNode::VSplit(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b.iter())
+                        .all(|((a, _), (b, _))| same_structure(a, b))
+            }
+            _ => false,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn display_parse_round_trip(layout in arb_layout()) {
+            prop_assert_eq!(parse(layout.to_string()).unwrap(), layout);
+        }
+
+        #[test]
+        fn display_after_resize_is_stable(
+            mut layout in arb_layout(),
+            resizes in prop::collection::vec(arb_resize(), 0..8),
+        ) {
+            for (container, direction) in resizes {
+                layout.resize(&container, direction);
+            }
+            let serialized = layout.to_string();
+            let parsed = parse(serialized.clone()).unwrap();
+            prop_assert!(same_structure(&parsed, &layout));
+            prop_assert_eq!(parsed.to_string(), serialized);
+        }
+
+        #[test]
+        fn parse_normalizes_consistently(s in "[cstelbk()\\[\\],?|0-9.%-]{1,24}") {
+            if let Ok(layout) = parse(s) {
+                let normalized = layout.to_string();
+                prop_assert_eq!(parse(normalized.clone()).unwrap().to_string(), normalized);
+            }
+        }
+    }
 }
@@ -0,0 +1,20 @@
+// The parts of ugdb that do not depend on its tui: The interface to gdb (including the parser of
+// its output) and the decorators of the lines in the source and assembly views. They are a
+// library, so that the benchmarks and the fuzz target use the same code as ugdb.
+extern crate json;
+extern crate log;
+extern crate nix;
+#[macro_use]
+extern crate nom;
+extern crate unsegen;
+extern crate unsegen_pager;
+
+pub mod diagnostics;
+pub mod gdb;
+pub mod gdbmi;
+
+pub mod tui {
+    pub mod branch_arrows;
+    pub mod line_decorators;
+    pub mod render_profile;
+}
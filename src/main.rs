@@ -1,10 +1,12 @@
 extern crate backtrace;
+extern crate base64;
 extern crate flexi_logger;
 extern crate log;
 extern crate nix;
 extern crate structopt;
 extern crate termion;
 extern crate time;
+extern crate toml;
 #[macro_use]
 extern crate derive_more;
 extern crate parse_int;
@@ -22,42 +24,69 @@ extern crate unsegen_pager;
 extern crate unsegen_signals;
 extern crate unsegen_terminal;
 
-// gdbmi
-#[macro_use]
-extern crate nom;
+extern crate ugdb;
+
+#[cfg(test)]
+extern crate proptest;
 
+mod actions;
+mod build_id;
+mod clipboard;
 mod completion;
-mod gdb;
+mod config;
+mod encoding;
+mod executable_watch;
+mod function_trace;
 mod gdb_expression_parsing;
-mod gdbmi;
+mod gdbinit;
+mod input_gate;
 mod ipc;
 mod layout;
+mod number_format;
+mod path_substitution;
+mod profile;
+mod redraw;
+mod remote;
+mod run_settings;
+mod session;
+mod session_summary;
+mod tags;
 mod tui;
+mod watch_triggers;
 
 use ipc::IPCRequest;
+use layout::{LayoutNode, ResizeDirection};
+use std::collections::HashSet;
 use std::ffi::OsString;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use ugdb::{diagnostics, gdb, gdbmi};
 
 use std::sync::mpsc::Sender;
 
 use gdb::GDB;
-use gdbmi::output::OutOfBandRecord;
-use gdbmi::{GDBBuilder, OutOfBandRecordSink};
+use gdbmi::capabilities::{Backend, Feature};
+use gdbmi::commands::MiCommand;
+use gdbmi::output::{Object, OutOfBandRecord, ResultClass, ResultRecord};
+use gdbmi::{Connection, GDBBuilder, OutOfBandRecordSink};
 use log::{debug, warn};
 use nix::sys::signal::Signal;
 use nix::sys::termios;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
+use tui::event_bus::DebugEvent;
+use tui::progress::Progress;
+use tui::render_profile::RenderProfile;
 use tui::{Tui, TuiContainerType};
-use unsegen::base::{Color, StyleModifier, Terminal};
-use unsegen::container::ContainerManager;
+use unsegen::base::{Color, RowIndex, StyleModifier, Terminal};
+use unsegen::container::{Container, ContainerManager};
 use unsegen::input::{Input, Key, NavigateBehavior, ToEvent};
 use unsegen::widget::{Blink, RenderingHints};
 
-const EVENT_BUFFER_DURATION_MS: u64 = 10;
 const FOCUS_ESCAPE_MAX_DURATION_MS: u64 = 200;
 const CURSOR_BLINK_PERIOD_MS: u64 = 500;
 const CURSOR_BLINK_TIMES: u8 = 20;
+const GDB_EXIT_WAIT_MS: u64 = 500;
+const DOWNLOAD_POLL_PERIOD_MS: u64 = 100;
 
 #[derive(StructOpt)]
 #[structopt()]
@@ -69,6 +98,11 @@ struct Options {
         parse(from_os_str)
     )]
     gdb_path: PathBuf,
+    #[structopt(
+        long = "lldb-mi",
+        help = "Use lldb-mi instead of gdb (e.g., on macOS). Features that rely on gdb's console commands are not available. The binary defaults to lldb-mi unless --gdb is given."
+    )]
+    lldb_mi: bool,
     #[structopt(long = "nh", help = "Do not execute commands from ~/.gdbinit.")]
     nh: bool,
     #[structopt(
@@ -95,6 +129,22 @@ struct Options {
         parse(from_os_str)
     )]
     rr_path: PathBuf,
+    #[structopt(
+        long = "new-ui",
+        help = "Do not start gdb, but wait for a running gdb to connect to ugdb. The command to run in gdb (new-ui mi <pty>) is printed on startup."
+    )]
+    new_ui: bool,
+    #[structopt(
+        long = "mi-command",
+        help = "Do not start gdb, but run the given shell command and talk to gdb through its stdin and stdout, e.g., a command that forwards the MI of a gdb that was started by another tool.",
+        conflicts_with = "new_ui"
+    )]
+    mi_command: Option<String>,
+    #[structopt(
+        long = "profile",
+        help = "Start the program as described by the launch profile with the given name in the config file, e.g., using gdbserver in a container."
+    )]
+    profile: Option<String>,
     #[structopt(
         long = "cd",
         help = "Run GDB using directory as its working directory, instead of the current directory.",
@@ -149,6 +199,55 @@ struct Options {
         help = "Define initial entries for the expression table."
     )]
     initial_expression_table_entries: Vec<String>,
+    #[structopt(
+        long = "config",
+        help = "Path to the config file. [default: $XDG_CONFIG_HOME/ugdb/config.toml]",
+        parse(from_os_str)
+    )]
+    config_file: Option<PathBuf>,
+    #[structopt(
+        long = "tags",
+        help = "ctags file used to look up definitions of symbols.",
+        parse(from_os_str),
+        default_value = "tags"
+    )]
+    tags_file: PathBuf,
+    #[structopt(
+        long = "diagnostics",
+        help = "File with compiler output whose warnings and errors are shown in the source view.",
+        parse(from_os_str)
+    )]
+    diagnostics_file: Option<PathBuf>,
+    #[structopt(
+        long = "compile-commands",
+        help = "Compilation database used by the !check command.",
+        parse(from_os_str),
+        default_value = "compile_commands.json"
+    )]
+    compile_commands: PathBuf,
+    #[structopt(
+        long = "mi-log",
+        help = "Record all commands sent to and output received from gdb in the given file.",
+        parse(from_os_str)
+    )]
+    mi_log: Option<PathBuf>,
+    #[structopt(
+        long = "replay-mi-log",
+        help = "Parse the gdb output recorded using --mi-log, print the resulting records and exit.",
+        parse(from_os_str)
+    )]
+    replay_mi_log: Option<PathBuf>,
+    #[structopt(
+        long = "summary",
+        help = "Write a summary of the session (breakpoint hits, running time, last backtrace, final values of expressions) to the given file on exit.",
+        parse(from_os_str)
+    )]
+    summary_file: Option<PathBuf>,
+    #[structopt(
+        long = "linear",
+        help = "Print the output of gdb and announcements of changes (e.g., where the program stopped) line by line instead of drawing the containers on the whole screen, e.g., for use with a screen reader."
+    )]
+    linear: bool,
     #[structopt(
         long = "layout",
         help = "Define the initial tui layout via a format string.",
@@ -169,7 +268,16 @@ struct Options {
 
 impl Options {
     fn create_gdb_builder(self) -> GDBBuilder {
-        let mut gdb_builder = GDBBuilder::new(self.gdb_path);
+        let mut gdb_builder = if self.lldb_mi {
+            let path = if self.gdb_path == Path::new("gdb") {
+                PathBuf::from("lldb-mi")
+            } else {
+                self.gdb_path
+            };
+            GDBBuilder::new(path).backend(Backend::LldbMi)
+        } else {
+            GDBBuilder::new(self.gdb_path)
+        };
         if self.nh {
             gdb_builder = gdb_builder.nh();
         }
@@ -200,6 +308,14 @@ impl Options {
         if let Some(src_dir) = self.source_dir {
             gdb_builder = gdb_builder.source_dir(src_dir);
         }
+        if let Some(mi_log) = self.mi_log {
+            gdb_builder = gdb_builder.traffic_log(mi_log);
+        }
+        if self.new_ui {
+            gdb_builder = gdb_builder.connect(Connection::NewUi);
+        } else if let Some(command) = self.mi_command {
+            gdb_builder = gdb_builder.connect(Connection::Command(command));
+        }
         if self.rr {
             gdb_builder = gdb_builder.rr_args(self.rr_path, self.program);
         } else {
@@ -224,6 +340,17 @@ impl OutOfBandRecordSink for MpscOobRecordSink {
     fn send(&self, data: OutOfBandRecord) {
         self.0.send(Event::OutOfBandRecord(data)).unwrap();
     }
+
+    // The state of the program is queried as soon as it stops, before the stop is handled.
+    fn queries(&self, record: &OutOfBandRecord, is_running: bool) -> Vec<MiCommand> {
+        gdb::Snapshot::queries(record, is_running)
+    }
+
+    fn send_with_results(&self, data: OutOfBandRecord, results: Vec<ResultRecord>) {
+        self.0
+            .send(Event::OutOfBandRecordWithSnapshot(data, results))
+            .unwrap();
+    }
 }
 
 impl Drop for MpscOobRecordSink {
@@ -257,7 +384,30 @@ impl MessageSink {
 
 pub struct Context {
     pub gdb: GDB,
+    gdb_builder: gdbmi::GDBBuilder, // To start gdb again after it exited unexpectedly
+    gdb_terminated: bool,
     event_sink: Sender<Event>,
+    tags: tags::Tags,
+    compile_commands: PathBuf,
+    ipc_subscribers: ipc::Subscribers,
+    action_bindings: actions::Bindings,
+    disassembly_flavor: Option<gdb::DisassemblyFlavor>, // Applied again if gdb is restarted
+    path_substitutions: path_substitution::PathSubstitutions, // Likewise
+    pretty_printers: Option<bool>,                      // Likewise
+    print_pretty: Option<bool>,                         // Likewise
+    profile: Option<profile::Profile>,                  // Likewise
+    run_settings: run_settings::RunSettings,            // Likewise
+    profile_process: Option<std::process::Child>,       // E.g., the ssh tunnel to gdbserver
+    run_configurations: Vec<run_settings::RunConfiguration>,
+    snapshot_parts: gdb::SnapshotParts, // The parts of the snapshot that are shown
+    run_baseline: Option<run_settings::RunSettings>, // Settings before the first run configuration
+    run_configuration: Option<run_settings::RunSettings>, // Of the selected run configuration
+    auto_reload: executable_watch::AutoReload,
+    function_trace: function_trace::FunctionTrace,
+    session_stats: session_summary::SessionStats,
+    number_format: number_format::NumberFormat,
+    watch_triggers: Vec<watch_triggers::Trigger>,
+    download: Option<(gdbmi::BackgroundCommand, Progress)>, // Of `load`, see `start_download`
 }
 
 impl Context {
@@ -267,6 +417,448 @@ impl Context {
             .unwrap();
     }
 
+    fn quit(&mut self) {
+        self.event_sink.send(Event::Quit).unwrap();
+    }
+
+    fn gdb_terminated(&self) -> bool {
+        self.gdb_terminated
+    }
+
+    // Keep ugdb running if gdb exits without being asked to and offer to restart it.
+    fn on_gdb_terminated(&mut self) {
+        let e = self.gdb.mi.termination_error();
+        self.gdb_terminated = true;
+        self.log(e.to_string());
+        self.show_overlay(tui::overlay::Confirmation::new(
+            "gdb terminated",
+            format!(
+                "{}.\nRestart gdb and restore {} breakpoint(s)?\n\nUse `!restart` to do so later or `quit` to exit ugdb.",
+                e,
+                self.gdb.breakpoints.save().len()
+            ),
+            |p: &mut Context| p.restart_gdb(),
+        ));
+    }
+
+    // Start a new gdb process (which loads the executable again) and insert the breakpoints of
+    // the old one. Expressions in the expression table are evaluated again once gdb is running.
+    fn restart_gdb(&mut self) {
+        if !self.gdb_terminated {
+            self.log("gdb is still running.");
+            return;
+        }
+        if !self.gdb_builder.can_restart() {
+            self.log("gdb was connected using new-ui and has to connect to a new ugdb instead.");
+            return;
+        }
+        let _ = self.gdb.mi.process.kill();
+        let breakpoints = self.gdb.breakpoints.save();
+        let skips = self.gdb.skips.clone();
+        match self
+            .gdb_builder
+            .clone()
+            .try_spawn(MpscOobRecordSink(self.event_sink.clone()))
+        {
+            Ok(mi) => {
+                self.gdb = GDB::new(mi);
+                self.gdb_terminated = false;
+                self.apply_disassembly_flavor();
+                self.apply_path_substitutions();
+                self.apply_pretty_printing();
+                self.apply_run_settings();
+                self.launch_profile();
+                let restored = self.gdb.restore_breakpoints(&breakpoints);
+                self.gdb.restore_skips(&skips);
+                self.retrace_functions();
+                watch_triggers::reinsert_watchpoints(self);
+                self.sync_gdb_state();
+                self.log(format!(
+                    "Restarted gdb and restored {} of {} breakpoint(s).",
+                    restored,
+                    breakpoints.len()
+                ));
+                self.publish(DebugEvent::SymbolsReloaded);
+            }
+            Err(e) => self.show_overlay(tui::overlay::Message::new(
+                "Could not restart gdb",
+                e.to_string(),
+            )),
+        }
+    }
+
+    fn supports(&self, feature: Feature) -> bool {
+        self.gdb.mi.capabilities().supports(feature)
+    }
+
+    fn apply_disassembly_flavor(&mut self) {
+        if !self.supports(Feature::GdbCommands) {
+            return;
+        }
+        if let Some(flavor) = self.disassembly_flavor {
+            if let Err(e) = self.gdb.set_disassembly_flavor(flavor) {
+                self.log(format!("Could not set disassembly flavor: {:?}", e));
+            }
+        }
+    }
+
+    fn apply_pretty_printing(&mut self) {
+        if let (Some(enable), true) = (self.pretty_printers, self.supports(Feature::PrettyPrinters))
+        {
+            if let Err(e) = self.gdb.set_pretty_printers(enable) {
+                self.log(format!("Could not change pretty printers: {:?}", e));
+            }
+        }
+        if let (Some(pretty), true) = (self.print_pretty, self.supports(Feature::GdbCommands)) {
+            if let Err(e) = self.gdb.set_print_pretty(pretty) {
+                self.log(format!("Could not set print pretty: {:?}", e));
+            }
+        }
+    }
+
+    // Arguments, working directory and environment that were changed using `!args`, `!cwd` and
+    // `!env` (or restored from the session).
+    fn apply_run_settings(&mut self) {
+        let settings = self.run_settings.clone();
+        if let Some(args) = &settings.args {
+            if let Err(e) = self.gdb.set_arguments(args) {
+                self.log(format!("Could not set arguments: {:?}", e));
+            }
+        }
+        if let Some(cwd) = &settings.cwd {
+            if let Err(e) = self.gdb.set_working_directory(cwd) {
+                self.log(format!("Could not change the working directory: {:?}", e));
+            }
+        }
+        if !settings.environment.is_empty() && !self.supports(Feature::GdbCommands) {
+            self.log("Cannot change environment variables of the program using lldb-mi.");
+            return;
+        }
+        for (name, value) in &settings.environment {
+            if let Err(e) = self.gdb.set_environment_variable(name, value.as_deref()) {
+                self.log(format!("Could not change {}: {:?}", name, e));
+            }
+        }
+    }
+
+    // Start the program as described by the launch profile and connect to it.
+    fn launch_profile(&mut self) {
+        let profile = match self.profile.clone() {
+            Some(profile) => profile,
+            None => return,
+        };
+        if !self.supports(Feature::GdbCommands) {
+            let reason = self.gdb.mi.capabilities().unsupported(Feature::GdbCommands);
+            self.log(format!(
+                "Cannot launch profile '{}': {}",
+                profile.name, reason
+            ));
+            return;
+        }
+        match profile.path_substitutions() {
+            Ok(rules) => {
+                for (from, to) in rules {
+                    if self
+                        .path_substitutions
+                        .rules()
+                        .iter()
+                        .any(|(f, _)| *f == from)
+                    {
+                        continue;
+                    }
+                    if let Err(e) = self.gdb.add_path_substitution(&from, &to) {
+                        self.log(format!("Could not set substitute-path: {:?}", e));
+                    }
+                    self.path_substitutions.add(from, to);
+                }
+            }
+            Err(e) => self.log(format!(
+                "Could not read the mounts of {}: {}",
+                profile.target, e
+            )),
+        }
+        self.stop_profile_process();
+        let target = match profile.launch() {
+            Ok((target, process)) => {
+                self.profile_process = process;
+                target
+            }
+            Err(e) => {
+                self.log(format!(
+                    "Could not launch profile '{}': {}",
+                    profile.name, e
+                ));
+                return;
+            }
+        };
+        match self
+            .gdb
+            .connect_remote(&target, &profile.sysroot, profile.symbols.as_deref())
+        {
+            Ok(()) => self.log(format!(
+                "Started {} in {} (launch profile '{}').",
+                profile.program, profile.target, profile.name
+            )),
+            Err(e) => self.log(format!(
+                "Could not launch profile '{}': {:?}",
+                profile.name, e
+            )),
+        }
+    }
+
+    // The executable was rebuilt (see `executable_watch`).
+    fn on_executable_changed(&mut self, executable: PathBuf) {
+        if self.gdb.mi.is_session_active().unwrap_or(true) {
+            self.log(format!(
+                "{} was rebuilt. Use !reload once the program exited.",
+                executable.display()
+            ));
+            return;
+        }
+        match self.auto_reload {
+            executable_watch::AutoReload::Off => {
+                self.log(format!("{} was rebuilt.", executable.display()))
+            }
+            executable_watch::AutoReload::Always => self.reload_executable(&executable),
+            executable_watch::AutoReload::Ask => self.show_overlay(tui::overlay::Confirmation::new(
+                "Executable rebuilt",
+                format!(
+                    "{} was rebuilt.\nLoad it again and insert the breakpoints at their source locations again?",
+                    executable.display()
+                ),
+                move |p: &mut Context| p.reload_executable(&executable),
+            )),
+        }
+    }
+
+    fn reload_executable(&mut self, executable: &Path) {
+        match self.gdb.reload_executable(executable) {
+            Ok((restored, total)) => {
+                let msg = format!(
+                    "Reloaded {} and restored {} of {} breakpoint(s).",
+                    executable.display(),
+                    restored,
+                    total
+                );
+                self.log(&msg);
+                self.toast(tui::toasts::ToastLevel::Success, msg);
+                self.retrace_functions();
+                self.publish(DebugEvent::SymbolsReloaded);
+            }
+            Err(e) => self.log(format!(
+                "Could not reload {}: {:?}",
+                executable.display(),
+                e
+            )),
+        }
+    }
+
+    // What the status bar shows besides the records of gdb.
+    fn global_state(&self) -> tui::status_bar::GlobalState {
+        let selected = self.gdb.inferiors.selected();
+        tui::status_bar::GlobalState {
+            running: self.gdb.mi.is_running(),
+            inferior: self
+                .gdb
+                .inferiors
+                .iter()
+                .find(|i| Some(i.id.as_str()) == selected)
+                .map(|i| i.state.clone()),
+            breakpoints: self
+                .gdb
+                .breakpoints
+                .keys()
+                .map(|n| n.major)
+                .collect::<HashSet<_>>()
+                .len(),
+            pending_commands: self.gdb.mi.has_pending_commands(),
+        }
+    }
+
+    // Insert the breakpoints of the traced functions again (after gdb was restarted or the
+    // executable was loaded again).
+    fn retrace_functions(&mut self) {
+        for function in self.function_trace.reinsert(&mut self.gdb) {
+            self.log(format!(
+                "Stopped tracing {}, it cannot be found anymore.",
+                function
+            ));
+        }
+    }
+
+    fn toggle_trace_returns(&mut self) {
+        self.function_trace.trace_returns = !self.function_trace.trace_returns;
+        self.toast(
+            tui::toasts::ToastLevel::Info,
+            if self.function_trace.trace_returns {
+                "Recording return values of traced calls."
+            } else {
+                "Not recording return values of traced calls."
+            },
+        );
+    }
+
+    // Warn if the executable or libraries on disk are not the files that the target (a core dump
+    // or an attached process) is running.
+    fn check_build_ids(&mut self) {
+        let executable = self.gdb.get_target().ok().and_then(|t| t);
+        match build_id::check(&mut self.gdb, executable.as_deref()) {
+            Ok(check) if check.mismatches.is_empty() => self.log(format!(
+                "Build-ids of {} file(s) match the target.",
+                check.compared
+            )),
+            Ok(check) => {
+                let text = check
+                    .mismatches
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                self.log(format!("Build-id mismatch:\n{}", text));
+                self.toast(
+                    tui::toasts::ToastLevel::Error,
+                    format!(
+                        "{} file(s) differ from the ones the target is running!",
+                        check.mismatches.len()
+                    ),
+                );
+                self.show_overlay(tui::overlay::Message::new(
+                    "Build-id mismatch",
+                    format!(
+                        "These files on disk are not the ones the target is running, so source lines, symbols and values shown for them may be wrong:\n{}",
+                        text
+                    ),
+                ));
+            }
+            Err(e) => self.log(format!("Could not compare build-ids: {:?}", e)),
+        }
+    }
+
+    fn stop_profile_process(&mut self) {
+        if let Some(mut process) = self.profile_process.take() {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+    }
+
+    // Adopt the settings of the init files that gdb executed on startup (unless they are
+    // configured for ugdb). The breakpoints that were created there are listed by `sync_gdb_state`.
+    fn import_init_files(&mut self, files: &[PathBuf]) {
+        let commands = gdbinit::read(files);
+        if commands.is_empty() {
+            return;
+        }
+        if self.disassembly_flavor.is_none() {
+            self.disassembly_flavor = commands.disassembly_flavor;
+        }
+        if self.print_pretty.is_none() {
+            self.print_pretty = commands.print_pretty;
+        }
+        for (from, to) in commands.path_substitutions {
+            if !self
+                .path_substitutions
+                .rules()
+                .iter()
+                .any(|(f, _)| *f == from)
+            {
+                self.path_substitutions.add(from, to);
+            }
+        }
+        self.log(format!(
+            "Imported {} breakpoint(s) and settings from {}.",
+            commands.breakpoints.len(),
+            files
+                .iter()
+                .map(|f| f.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    // Populate the state that ugdb keeps track of (breakpoints, inferiors, threads and shared
+    // libraries) from gdb, e.g., right after it was started, instead of waiting for notifications
+    // of changes.
+    fn sync_gdb_state(&mut self) {
+        if let Err(e) = self.gdb.sync_state() {
+            self.log(format!("Failed to query the state of gdb: {:?}", e));
+        }
+        self.publish(DebugEvent::ThreadListChanged);
+        self.publish(DebugEvent::LibrariesChanged);
+    }
+
+    fn apply_path_substitutions(&mut self) {
+        if !self.supports(Feature::GdbCommands) {
+            return;
+        }
+        for (from, to) in self.path_substitutions.rules().to_vec() {
+            if let Err(e) = self.gdb.add_path_substitution(&from, &to) {
+                self.log(format!("Could not set substitute-path: {:?}", e));
+            }
+        }
+    }
+
+    fn show_overlay(&mut self, overlay: impl tui::overlay::Overlay + 'static) {
+        self.event_sink
+            .send(Event::ShowOverlay(Box::new(overlay)))
+            .unwrap();
+    }
+
+    fn publish(&mut self, event: DebugEvent) {
+        self.event_sink.send(Event::Publish(event)).unwrap();
+    }
+
+    // Handle a selection that gdb did not report itself (see `gdb::GDB::select_thread`) like a
+    // reported one, i.e., together with the snapshot of the selected frame.
+    fn report_selection(&mut self, record: Option<OutOfBandRecord>) {
+        let record = match record {
+            Some(record) => record,
+            None => return,
+        };
+        let results = gdb::Snapshot::queries(&record, false)
+            .into_iter()
+            .map(|query| self.gdb.mi.execute(query))
+            .collect::<Result<Vec<_>, _>>();
+        match results {
+            Ok(results) => self
+                .event_sink
+                .send(Event::OutOfBandRecordWithSnapshot(record, results))
+                .unwrap(),
+            Err(e) => {
+                self.log(format!("Could not query the selected frame: {:?}", e));
+                self.event_sink
+                    .send(Event::OutOfBandRecord(record))
+                    .unwrap();
+            }
+        }
+    }
+
+    // Ask whether the last session with the program should be restored (if there is one).
+    fn offer_session_restore(&mut self, executable: &Path) {
+        match session::Session::load(executable) {
+            Ok(Some(session)) if !session.is_empty() => {
+                self.show_overlay(tui::overlay::Confirmation::new(
+                    "Restore session",
+                    format!(
+                        "Restore the last session of {}?\n{}",
+                        executable.display(),
+                        session.summary()
+                    ),
+                    move |p: &mut Context| {
+                        p.event_sink.send(Event::RestoreSession(session)).unwrap()
+                    },
+                ))
+            }
+            Ok(_) => {}
+            Err(e) => self.log(e.to_string()),
+        }
+    }
+
+    fn toast(&mut self, level: tui::toasts::ToastLevel, msg: impl Into<String>) {
+        self.event_sink
+            .send(Event::Toast(level, msg.into()))
+            .unwrap();
+    }
+
     fn try_change_layout(&mut self, layout_str: String) {
         self.event_sink
             .send(Event::ChangeLayout(layout_str))
@@ -276,6 +868,216 @@ impl Context {
     fn show_file(&mut self, file: String, line: unsegen::base::LineNumber) {
         self.event_sink.send(Event::ShowFile(file, line)).unwrap();
     }
+
+    fn insert_into_console(&mut self, text: String) {
+        self.event_sink
+            .send(Event::InsertIntoConsole(text))
+            .unwrap();
+    }
+
+    fn start_progress(&mut self, title: impl Into<String>, cancellable: bool) -> Progress {
+        Progress::start(self.event_sink.clone(), title.into(), cancellable)
+    }
+
+    fn toggle_freeze(&mut self) {
+        self.event_sink.send(Event::ToggleFreeze).unwrap();
+    }
+
+    fn open_in_editor(&mut self) {
+        self.event_sink.send(Event::OpenInEditor).unwrap();
+    }
+
+    fn cancel_tasks(&mut self) {
+        self.event_sink.send(Event::CancelTasks).unwrap();
+    }
+
+    fn load_diagnostics(&mut self, file: &Path) {
+        match diagnostics::load_diagnostics_file(file) {
+            Ok(diagnostics) => {
+                self.log(format!(
+                    "Loaded {} diagnostics from {}.",
+                    diagnostics.len(),
+                    file.display()
+                ));
+                self.event_sink
+                    .send(Event::Diagnostics(diagnostics))
+                    .unwrap();
+            }
+            Err(e) => self.log(format!("Failed to read {}: {}", file.display(), e)),
+        }
+    }
+
+    fn check_syntax(&mut self, file: Option<PathBuf>) {
+        self.event_sink.send(Event::CheckSyntax(file)).unwrap();
+    }
+
+    // Run the check in the background as compiling may take a while.
+    fn spawn_syntax_check(&mut self, file: PathBuf) {
+        let compile_commands = self.compile_commands.clone();
+        let sink = self.event_sink.clone();
+        let progress = self.start_progress(
+            format!(
+                "Checking {}",
+                file.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            true,
+        );
+        self.log(format!("Checking {}...", file.display()));
+        ::std::thread::spawn(move || {
+            let result = diagnostics::run_syntax_check(&compile_commands, &file);
+            if progress.is_cancelled() {
+                let _ = sink.send(Event::Log(format!(
+                    "Discarded the result of the syntax check of {}.\n",
+                    file.display()
+                )));
+                return;
+            }
+            match result {
+                Ok(diagnostics) => {
+                    let msg = format!(
+                        "Found {} diagnostics in {}.",
+                        diagnostics.len(),
+                        file.display()
+                    );
+                    let _ = sink.send(Event::Log(format!("{}\n", msg)));
+                    let _ = sink.send(Event::Toast(tui::toasts::ToastLevel::Info, msg));
+                    let _ = sink.send(Event::Diagnostics(diagnostics));
+                }
+                Err(e) => {
+                    let _ = sink.send(Event::Log(format!("Syntax check failed: {}\n", e)));
+                    let _ = sink.send(Event::Toast(
+                        tui::toasts::ToastLevel::Error,
+                        "Syntax check failed.".to_owned(),
+                    ));
+                }
+            }
+        });
+    }
+
+    // Large source trees may take a while to search.
+    fn spawn_source_search(&mut self, pattern: String, files: Vec<PathBuf>) {
+        let sink = self.event_sink.clone();
+        let progress = self.start_progress(format!("Searching for '{}'", pattern), true);
+        ::std::thread::spawn(move || {
+            let matches = tui::search_results::search_source_dirs(&pattern, &files, &progress);
+            let cancelled = if progress.is_cancelled() {
+                " (cancelled)"
+            } else {
+                ""
+            };
+            let _ = sink.send(Event::Log(format!(
+                "Found {} matches for '{}'{}.\n",
+                matches.len(),
+                pattern,
+                cancelled
+            )));
+            let _ = sink.send(Event::SearchResults(matches));
+        });
+    }
+
+    // Loading the program onto a target (e.g., flashing a microcontroller) takes a while, so the
+    // result is not waited for. gdb reports the progress using `+download` records.
+    fn start_download(&mut self, command: &str) {
+        if self.download.is_some() {
+            self.log("The program is already being loaded.");
+            return;
+        }
+        match self
+            .gdb
+            .mi
+            .execute_in_background(MiCommand::cli_exec(command))
+        {
+            Ok(command) => {
+                let progress = self.start_progress("Loading", false);
+                self.download = Some((command, progress));
+            }
+            Err(e) => self.log(format!("Failed to load the program: {:?}", e)),
+        }
+    }
+
+    fn report_download(&mut self, results: &Object) {
+        if let Some((_, progress)) = &self.download {
+            let number = |key: &str| results[key].as_str().and_then(|n| n.parse().ok());
+            progress.report(number("total-sent").unwrap_or(0), number("total-size"));
+        }
+    }
+
+    fn check_download(&mut self) {
+        let result = match &self.download {
+            Some((command, _)) => self.gdb.mi.background_result(command),
+            None => return,
+        };
+        if let Some(result) = result {
+            self.download = None;
+            if result.class == ResultClass::Error {
+                let msg = result.results["msg"].as_str().unwrap_or("Unknown error");
+                self.log(format!("Failed to load the program: {}", msg));
+                self.toast(
+                    tui::toasts::ToastLevel::Error,
+                    "Loading the program failed.",
+                );
+            }
+        }
+    }
+
+    fn set_search_results(&mut self, matches: Vec<tui::search_results::SearchMatch>) {
+        self.event_sink.send(Event::SearchResults(matches)).unwrap();
+    }
+
+    fn show_current_line_table(&mut self) {
+        match self.gdb.get_line_table() {
+            Ok(line_table) => {
+                self.event_sink.send(Event::LineTable(line_table)).unwrap();
+            }
+            Err(e) => self.log(format!("Failed to get line table: {:?}", e)),
+        }
+    }
+
+    // Formats addresses as configured (see `number_format` in the config file), e.g., padded to
+    // the pointer size of the target.
+    fn address_formatter(&mut self) -> impl Fn(gdb::Address) -> String {
+        let format = self.number_format;
+        let pointer_size = self.gdb.pointer_size();
+        move |address| format.address(address.0, pointer_size)
+    }
+
+    fn show_memory(&mut self, address: gdb::Address) {
+        self.event_sink.send(Event::ShowMemory(address)).unwrap();
+    }
+
+    fn show_frame_memory(&mut self) {
+        self.event_sink.send(Event::ShowFrameMemory).unwrap();
+    }
+
+    fn show_address(&mut self, address: gdb::Address) {
+        self.event_sink.send(Event::ShowAddress(address)).unwrap();
+    }
+
+    fn focus_container(&mut self, container: TuiContainerType) {
+        self.event_sink
+            .send(Event::FocusContainer(container))
+            .unwrap();
+    }
+
+    // Try the ctags file first, as gdb only knows about symbols that are part of the binary.
+    fn find_definition(&mut self, symbol: &str) -> Option<gdb::SrcPosition> {
+        if let Some(pos) = self.tags.find_definition(symbol) {
+            return Some(pos);
+        }
+        self.gdb
+            .find_function_definition(symbol)
+            .ok()
+            .and_then(|p| p)
+    }
+
+    fn go_to_definition(&mut self, symbol: &str) -> bool {
+        if let Some(pos) = self.find_definition(symbol) {
+            self.show_file(pos.file.to_string_lossy().into_owned(), pos.line);
+            true
+        } else {
+            false
+        }
+    }
 }
 
 // A timer that can be used to receive an event at any time,
@@ -339,11 +1141,23 @@ enum InputMode {
 }
 
 impl InputMode {
-    fn associated_border_style(self) -> StyleModifier {
-        match self {
-            InputMode::Normal => StyleModifier::new(),
-            InputMode::Focused => StyleModifier::new().fg_color(Color::Red),
-            InputMode::ContainerSelect => StyleModifier::new().fg_color(Color::LightYellow),
+    fn associated_border_style(self, profile: RenderProfile) -> StyleModifier {
+        match (self, profile) {
+            (InputMode::Normal, _) => StyleModifier::new(),
+            (InputMode::Focused, RenderProfile::Color) => StyleModifier::new().fg_color(Color::Red),
+            (InputMode::Focused, RenderProfile::HighContrast) => {
+                StyleModifier::new().fg_color(Color::LightRed).bold(true)
+            }
+            (InputMode::Focused, RenderProfile::Monochrome) => StyleModifier::new().bold(true),
+            (InputMode::ContainerSelect, RenderProfile::Color) => {
+                StyleModifier::new().fg_color(Color::LightYellow)
+            }
+            (InputMode::ContainerSelect, RenderProfile::HighContrast) => {
+                StyleModifier::new().fg_color(Color::LightYellow).bold(true)
+            }
+            (InputMode::ContainerSelect, RenderProfile::Monochrome) => {
+                StyleModifier::new().invert(true)
+            }
         }
     }
 }
@@ -357,19 +1171,128 @@ pub enum Event {
     RenderTimer,
     FocusEscTimer,
     OutOfBandRecord(OutOfBandRecord),
+    /// A record with the results of the queries for the snapshot (see `gdb::Snapshot::queries`).
+    OutOfBandRecordWithSnapshot(OutOfBandRecord, Vec<ResultRecord>),
     Log(String),
     ChangeLayout(String),
     ShowFile(String, unsegen::base::LineNumber),
+    InsertIntoConsole(String),
+    SearchResults(Vec<tui::search_results::SearchMatch>),
+    FocusContainer(TuiContainerType),
+    Diagnostics(Vec<diagnostics::Diagnostic>),
+    LineTable(gdb::FunctionLineTable),
+    ShowMemory(gdb::Address),
+    ShowAddress(gdb::Address),
+    ShowFrameMemory,
+    CheckSyntax(Option<PathBuf>),
     GdbShutdown,
+    Publish(DebugEvent),
+    RestoreSession(session::Session),
+    ExecutableChanged(PathBuf),
+    ShowOverlay(Box<dyn tui::overlay::Overlay>),
+    Toast(tui::toasts::ToastLevel, String),
+    ToastTimer,
+    DownloadTimer,
+    Progress(tui::progress::TaskId, tui::progress::ProgressUpdate),
+    CancelTasks,
+    ToggleFreeze,
+    OpenInEditor,
+    Quit,
     Ipc(IPCRequest),
 }
 
+// How the state is presented to the user.
+enum Frontend<'a> {
+    Screen(Terminal<'a, std::io::StdoutLock<'a>>),
+    Linear(tui::linear::LinearOutput), // --linear
+}
+
+fn replay_mi_log(path: &Path) -> i32 {
+    let replay = match std::fs::File::open(path)
+        .and_then(|f| gdbmi::traffic_log::replay(std::io::BufReader::new(f)))
+    {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            return 0xf9;
+        }
+    };
+    println!("Result records:");
+    for record in replay.results {
+        println!("{:?}", record);
+    }
+    println!("Out of band records:");
+    for record in replay.out_of_band {
+        println!("{:?}", record);
+    }
+    0
+}
+
+// Run $VISUAL or $EDITOR (falling back to vi) on the given position and wait for it to exit. The
+// variables may contain arguments, e.g., "code --wait".
+fn open_in_editor(pos: &gdb::SrcPosition) -> std::io::Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_owned());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(format!("+{}", pos.line))
+        .arg(&pos.file)
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )))
+    }
+}
+
+// The part of the layout that is shown: Only the selected container if zoomed in, otherwise the
+// whole layout except for auto-hiding containers without content (unless they are selected).
+fn shown_layout(
+    layout: &LayoutNode,
+    zoomed: bool,
+    active: &TuiContainerType,
+    tui: &Tui,
+) -> LayoutNode {
+    if zoomed {
+        return LayoutNode::Leaf(active.clone());
+    }
+    layout
+        .without_hidden(&|c| c != active && !tui.has_content(c))
+        .unwrap_or_else(|| LayoutNode::Leaf(active.clone()))
+}
+
 fn run() -> i32 {
+    // "ugdb remote ..." controls a running instance instead of starting a new one.
+    if std::env::args_os()
+        .nth(1)
+        .map(|a| a == "remote")
+        .unwrap_or(false)
+    {
+        return remote::run(remote::RemoteOptions::from_iter(
+            std::env::args_os().skip(1),
+        ));
+    }
+
+    let options = Options::from_args();
+    if let Some(mi_log) = &options.replay_mi_log {
+        return replay_mi_log(mi_log);
+    }
+
     // Setup signal piping:
     let mut signals_to_wait = nix::sys::signal::SigSet::empty();
     signals_to_wait.add(Signal::SIGWINCH);
     signals_to_wait.add(Signal::SIGTSTP);
     signals_to_wait.add(Signal::SIGTERM);
+    // The terminal is not in raw mode in linear mode, so Ctrl-C results in SIGINT.
+    if options.linear {
+        signals_to_wait.add(Signal::SIGINT);
+    }
     let mut signals_to_block = signals_to_wait.clone();
     signals_to_block.add(Signal::SIGCONT);
 
@@ -393,12 +1316,40 @@ fn run() -> i32 {
         termios::tcgetattr(STDOUT).expect("Failed to get terminal attributes"),
     );
 
-    let options = Options::from_args();
     let log_dir = options.log_dir.to_owned();
     let initial_expression_table_entries = options.initial_expression_table_entries.clone();
     let layout = options.layout.clone();
+    let tags_file = options.tags_file.clone();
+    let diagnostics_file = options.diagnostics_file.clone();
+    let compile_commands = options.compile_commands.clone();
+    let summary_file = options.summary_file.clone();
+    let linear = options.linear;
+    let examines_target = options.core_file.is_some() || options.proc_id.is_some();
+    let (nh, nx) = (options.nh, options.nx);
+    let config = match config::Config::load(options.config_file.as_deref()) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 0xfa;
+        }
+    };
+    let profile = match &options.profile {
+        Some(name) => match config.profile(name) {
+            Some(profile) => Some(profile.clone()),
+            None => {
+                eprintln!("Unknown launch profile '{}'", name);
+                return 0xfa;
+            }
+        },
+        None => None,
+    };
 
     ::std::panic::set_hook(Box::new(move |info| {
+        // The panic is caught and shown in place of the failing container.
+        if tui::error_boundary::is_catching() {
+            log::error!("{}\n{:?}", info, backtrace::Backtrace::new());
+            return;
+        }
         // Switch back to main screen
         println!("{}{}", termion::screen::ToMainScreen, termion::cursor::Show);
         // Restore old terminal behavior (will be restored later automatically, but we want to be
@@ -437,10 +1388,13 @@ fn run() -> i32 {
     let mut gdb_builder = options.create_gdb_builder();
     gdb_builder = gdb_builder.tty(tui_terminal.slave_name().into());
     let gdb = GDB::new(
-        match gdb_builder.try_spawn(MpscOobRecordSink(event_sink.clone())) {
+        match gdb_builder
+            .clone()
+            .try_spawn(MpscOobRecordSink(event_sink.clone()))
+        {
             Ok(gdb) => gdb,
             Err(e) => {
-                eprintln!("Failed to spawn gdb process (\"{}\"): {}", gdb_path, e);
+                eprintln!("{} (\"{}\")", e, gdb_path);
                 return 0xfc;
             }
         },
@@ -460,40 +1414,129 @@ fn run() -> i32 {
 
     let mut context = Context {
         gdb,
+        gdb_builder,
+        gdb_terminated: false,
         event_sink: event_sink.clone(),
+        tags: tags::Tags::new(tags_file),
+        compile_commands,
+        ipc_subscribers: ipc::Subscribers::new(),
+        action_bindings: actions::Bindings::new(&config.bindings),
+        disassembly_flavor: config.disassembly_flavor,
+        path_substitutions: config.path_substitutions.clone(),
+        pretty_printers: config.pretty_printers,
+        print_pretty: config.print_pretty,
+        profile,
+        run_settings: run_settings::RunSettings::default(),
+        profile_process: None,
+        run_configurations: config.run_configurations.clone(),
+        snapshot_parts: gdb::SnapshotParts::default(),
+        run_baseline: None,
+        run_configuration: None,
+        auto_reload: config.auto_reload,
+        function_trace: function_trace::FunctionTrace::new(),
+        session_stats: session_summary::SessionStats::new(Instant::now()),
+        number_format: config.number_format,
+        watch_triggers: config.triggers.clone(),
+        download: None,
     };
+    // lldb-mi does not read gdb's init files.
+    if !nx && context.supports(Feature::GdbCommands) {
+        let mut init_files = Vec::new();
+        if !nh {
+            let env_path = |name| std::env::var_os(name).map(PathBuf::from);
+            init_files.extend(gdbinit::home_init_file(
+                env_path("XDG_CONFIG_HOME").as_deref(),
+                env_path("HOME").as_deref(),
+            ));
+        }
+        match context.gdb.loaded_local_gdbinit() {
+            Ok(Some(file)) if !init_files.contains(&file) => init_files.push(file),
+            Ok(_) => {}
+            Err(e) => warn!("Failed to query the local init file: {:?}", e),
+        }
+        context.import_init_files(&init_files);
+    }
+    context.sync_gdb_state();
+    watch_triggers::reinsert_watchpoints(&mut context);
+    context.apply_disassembly_flavor();
+    context.apply_path_substitutions();
+    context.apply_pretty_printing();
+    context.launch_profile();
 
     {
-        let mut terminal = match Terminal::new(stdout.lock()) {
-            Ok(t) => t,
-            Err(e) => {
-                eprintln!("Unable to setup Terminal: {}", e);
-                return 0xfd;
+        let mut tui = Tui::new(
+            tui_terminal,
+            &theme_set.themes["base16-ocean.dark"],
+            &config,
+        );
+        let mut frontend = if linear {
+            Frontend::Linear(tui::linear::LinearOutput::new(&mut tui))
+        } else {
+            match Terminal::new(stdout.lock()) {
+                Ok(t) => Frontend::Screen(t),
+                Err(e) => {
+                    eprintln!("Unable to setup Terminal: {}", e);
+                    return 0xfd;
+                }
             }
         };
-        let mut tui = Tui::new(tui_terminal, &theme_set.themes["base16-ocean.dark"]);
         for entry in initial_expression_table_entries {
             tui.expression_table.add_entry(entry);
         }
+        if let Some(file) = diagnostics_file {
+            context.load_diagnostics(&file);
+        }
+        let executable = context.gdb.get_target().ok().and_then(|t| t);
+        match (&executable, &context.profile) {
+            (_, Some(profile)) => tui
+                .status_bar
+                .set_target(format!("{} ({})", profile.program, profile.target)),
+            (Some(executable), None) => tui.status_bar.set_target(
+                executable
+                    .file_name()
+                    .unwrap_or(executable.as_os_str())
+                    .to_string_lossy(),
+            ),
+            (None, None) => {}
+        }
+        if let Some(ref executable) = executable {
+            context.offer_session_restore(executable);
+            executable_watch::spawn(
+                executable.clone(),
+                event_sink.clone(),
+                Event::ExecutableChanged,
+            );
+        }
+        if examines_target && context.supports(Feature::GdbCommands) {
+            context.check_build_ids();
+        }
 
         // Start stdin thread _after_ building terminal (and setting the actual terminal to raw
         // mode to avoid race condition where the first 'set of input' is buffered
         /* let keyboard_input = */
         let keyboard_sink = event_sink.clone();
+        let input_gate = input_gate::InputGate::default();
+        let stdin = input_gate.reader(nix::libc::STDIN_FILENO);
         ::std::thread::spawn(move || {
-            let stdin = ::std::io::stdin();
-            let stdin = stdin.lock();
             for e in Input::read_all(stdin) {
                 keyboard_sink.send(Event::Input(e.expect("event"))).unwrap();
             }
         });
 
-        let mut app = ContainerManager::<Tui>::from_layout(layout);
+        let mut layout = layout;
+        layout.select_tab(&TuiContainerType::Console);
+        let mut zoomed = false;
+        // The layout is applied (again) before drawing if the shown part changes.
+        let mut applied_layout = shown_layout(&layout, zoomed, &TuiContainerType::Console, &tui);
+        let mut app =
+            ContainerManager::<Tui>::from_layout(applied_layout.to_layout(config.title_bars));
         let mut input_mode = InputMode::Normal;
         let mut focus_esc_timer =
             MpscTimer::new(event_sink.clone(), Box::new(|| Event::FocusEscTimer));
         let mut cursor_status = Blink::On;
         let mut cursor_blinks_since_last_input = 0;
+        let mut redraw_limiter = redraw::RedrawLimiter::new(config.max_redraw_rate);
+        let mut events_since_last_draw = 0;
 
         'runloop: loop {
             let mut cursor_update_timer =
@@ -502,6 +1545,18 @@ fn run() -> i32 {
                 cursor_update_timer.try_start(Duration::from_millis(CURSOR_BLINK_PERIOD_MS));
             }
 
+            let mut toast_timer =
+                MpscTimer::new(event_sink.clone(), Box::new(|| Event::ToastTimer));
+            if let Some(expiry) = tui.toasts.next_expiry() {
+                toast_timer.try_start(expiry.saturating_duration_since(Instant::now()));
+            }
+
+            let mut download_timer =
+                MpscTimer::new(event_sink.clone(), Box::new(|| Event::DownloadTimer));
+            if context.download.is_some() {
+                download_timer.try_start(Duration::from_millis(DOWNLOAD_POLL_PERIOD_MS));
+            }
+
             let mut render_delay_timer =
                 MpscTimer::new(event_sink.clone(), Box::new(|| Event::RenderTimer));
             let mut esc_timer_needs_reset = false;
@@ -527,66 +1582,212 @@ fn run() -> i32 {
                         esc_timer_needs_reset = true;
                         break 'displayloop;
                     }
+                    Event::Input(input) if linear => {
+                        // There are no containers to select in linear mode, so all input goes to
+                        // the console (or the topmost overlay).
+                        if tui.overlays.is_active() {
+                            tui.overlays.input(input, &mut context);
+                        } else {
+                            let _ = tui.console.input(input, &mut context);
+                        }
+                    }
                     Event::Input(input) => {
                         let sig_behavior = ::unsegen_signals::SignalBehavior::new()
                             .on_default::<::unsegen_signals::SIGTSTP>();
                         let input = input.chain(sig_behavior);
-                        match input_mode {
-                            InputMode::ContainerSelect => input
-                                .chain(
-                                    NavigateBehavior::new(&mut app.navigatable(&mut tui))
-                                        .up_on(Key::Char('k'))
-                                        .up_on(Key::Up)
-                                        .down_on(Key::Char('j'))
-                                        .down_on(Key::Down)
-                                        .left_on(Key::Char('h'))
-                                        .left_on(Key::Left)
-                                        .right_on(Key::Char('l'))
-                                        .right_on(Key::Right),
-                                )
-                                .chain((Key::Char('i'), || {
-                                    input_mode = InputMode::Normal;
-                                    app.set_active(TuiContainerType::Console);
-                                }))
-                                .chain((Key::Char('e'), || {
-                                    input_mode = InputMode::Normal;
-                                    app.set_active(TuiContainerType::ExpressionTable);
-                                }))
-                                .chain((Key::Char('s'), || {
-                                    input_mode = InputMode::Normal;
-                                    app.set_active(TuiContainerType::SrcView);
-                                }))
-                                .chain((Key::Char('t'), || {
-                                    input_mode = InputMode::Normal;
-                                    app.set_active(TuiContainerType::Terminal);
-                                }))
-                                .chain((Key::Char('T'), || {
-                                    input_mode = InputMode::Focused;
-                                    app.set_active(TuiContainerType::Terminal);
-                                }))
-                                .chain((Key::Char('\n'), || input_mode = InputMode::Normal)),
-                            InputMode::Normal => input
-                                .chain((Key::Esc, || input_mode = InputMode::ContainerSelect))
-                                .chain(app.active_container_behavior(&mut tui, &mut context)),
-                            InputMode::Focused => input
-                                .chain((Key::Esc, || esc_in_focused_context_pressed = true))
-                                .chain(app.active_container_behavior(&mut tui, &mut context)),
+                        let mut triggered_action = None;
+                        let action_bindings = context.action_bindings.clone();
+                        let action_behavior = |input: Input| {
+                            if input.matches(Key::Ctrl('p')) {
+                                triggered_action = actions::find("palette");
+                                return None;
+                            }
+                            if let unsegen::input::Event::Key(Key::Char(c)) = input.event {
+                                if let Some(action) = action_bindings.action(c) {
+                                    triggered_action = Some(action);
+                                    return None;
+                                }
+                            }
+                            Some(input)
+                        };
+                        let mut toggle_zoom = false;
+                        let mut resize_direction = None;
+                        let mut switch_tab = None;
+                        let layout_preset_behavior = |input: Input| {
+                            for (i, preset) in config.layouts.iter().enumerate().take(12) {
+                                if input.matches(Key::F(i as u8 + 1)) {
+                                    context.try_change_layout(preset.layout.clone());
+                                    return None;
+                                }
+                            }
+                            Some(input)
+                        };
+                        let resize_behavior = |input: Input| {
+                            let direction = if input.matches(Key::Alt('h')) {
+                                ResizeDirection::Narrower
+                            } else if input.matches(Key::Alt('l')) {
+                                ResizeDirection::Wider
+                            } else if input.matches(Key::Alt('j')) {
+                                ResizeDirection::Taller
+                            } else if input.matches(Key::Alt('k')) {
+                                ResizeDirection::Shorter
+                            } else {
+                                return Some(input);
+                            };
+                            resize_direction = Some(direction);
+                            None
+                        };
+                        if tui.overlays.is_active() {
+                            if let Some(input) = input.finish() {
+                                tui.overlays.input(input, &mut context);
+                            }
+                        } else {
+                            match input_mode {
+                                InputMode::ContainerSelect => input
+                                    .chain(
+                                        NavigateBehavior::new(&mut app.navigatable(&mut tui))
+                                            .up_on(Key::Char('k'))
+                                            .up_on(Key::Up)
+                                            .down_on(Key::Char('j'))
+                                            .down_on(Key::Down)
+                                            .left_on(Key::Char('h'))
+                                            .left_on(Key::Left)
+                                            .right_on(Key::Char('l'))
+                                            .right_on(Key::Right),
+                                    )
+                                    .chain((Key::Char('i'), || {
+                                        input_mode = InputMode::Normal;
+                                        app.set_active(TuiContainerType::Console);
+                                    }))
+                                    .chain((Key::Char('e'), || {
+                                        input_mode = InputMode::Normal;
+                                        app.set_active(TuiContainerType::ExpressionTable);
+                                    }))
+                                    .chain((Key::Char('s'), || {
+                                        input_mode = InputMode::Normal;
+                                        app.set_active(TuiContainerType::SrcView);
+                                    }))
+                                    .chain((Key::Char('t'), || {
+                                        input_mode = InputMode::Normal;
+                                        app.set_active(TuiContainerType::Terminal);
+                                    }))
+                                    .chain((Key::Char('r'), || {
+                                        input_mode = InputMode::Normal;
+                                        app.set_active(TuiContainerType::SearchResults);
+                                    }))
+                                    .chain((Key::Char('T'), || {
+                                        input_mode = InputMode::Focused;
+                                        app.set_active(TuiContainerType::Terminal);
+                                    }))
+                                    .chain((Key::Char('\n'), || input_mode = InputMode::Normal))
+                                    .chain((Key::Char('z'), || toggle_zoom = true))
+                                    .chain((Key::Char(']'), || switch_tab = Some(true)))
+                                    .chain((Key::Char('['), || switch_tab = Some(false)))
+                                    .chain(resize_behavior)
+                                    .chain(layout_preset_behavior)
+                                    .chain(action_behavior),
+                                InputMode::Normal => input
+                                    .chain((Key::Esc, || input_mode = InputMode::ContainerSelect))
+                                    .chain(layout_preset_behavior)
+                                    .chain(app.active_container_behavior(&mut tui, &mut context)),
+                                InputMode::Focused => input
+                                    .chain((Key::Esc, || esc_in_focused_context_pressed = true))
+                                    .chain(app.active_container_behavior(&mut tui, &mut context)),
+                            }
+                            .finish();
+                        }
+                        if let Some(action) = triggered_action {
+                            input_mode = InputMode::Normal;
+                            action.trigger(&mut context);
+                        }
+                        if let Some(next) =
+                            switch_tab.and_then(|forward| layout.switch_tab(&app.active(), forward))
+                        {
+                            app.set_active(next);
+                        } else if toggle_zoom {
+                            zoomed = !zoomed;
+                        } else if let (Some(direction), false) = (resize_direction, zoomed) {
+                            layout.resize(&app.active(), direction);
                         }
-                        .finish();
+                        // The selected container may be hidden behind another tab.
+                        layout.select_tab(&app.active());
                     }
                     Event::OutOfBandRecord(record) => {
                         tui.add_out_of_band_record(record, &mut context);
                     }
+                    Event::OutOfBandRecordWithSnapshot(record, results) => {
+                        // Also while updates are paused (see `Tui::toggle_freeze`), so that the
+                        // containers resume with the state at the latest stop.
+                        context.gdb.snapshot.update(&record, results);
+                        let wanted = context.snapshot_parts;
+                        context.gdb.complete_snapshot(&wanted);
+                        context
+                            .session_stats
+                            .record_backtrace(&context.gdb.snapshot);
+                        tui.add_out_of_band_record(record, &mut context);
+                    }
                     Event::Log(msg) => {
                         tui.console.write_to_gdb_log(msg);
                     }
                     Event::ShowFile(file, line) => {
                         tui.src_view.show_file(file, line, &mut context);
                     }
-                    Event::ChangeLayout(layout) => {
-                        match layout::parse(layout) {
-                            Ok(layout) => {
-                                app.set_layout(layout);
+                    Event::InsertIntoConsole(text) => {
+                        tui.console.insert_into_prompt(&text);
+                    }
+                    Event::SearchResults(matches) => {
+                        tui.search_results.set_matches(matches);
+                        context.focus_container(TuiContainerType::SearchResults);
+                    }
+                    Event::LineTable(line_table) => {
+                        let format_address = context.address_formatter();
+                        tui.line_table.set_line_table(line_table, format_address);
+                        context.focus_container(TuiContainerType::LineTable);
+                    }
+                    Event::ShowMemory(address) => {
+                        tui.memory.show(address, &mut context);
+                        context.focus_container(TuiContainerType::Memory);
+                    }
+                    Event::ShowFrameMemory => {
+                        tui.memory.show_frame(&mut context);
+                        context.focus_container(TuiContainerType::Memory);
+                    }
+                    Event::ShowAddress(address) => {
+                        tui.src_view.show_address(address, &mut context);
+                    }
+                    Event::Diagnostics(diagnostics) => {
+                        tui.src_view.set_diagnostics(diagnostics, &mut context);
+                    }
+                    Event::CheckSyntax(file) => {
+                        match file
+                            .or_else(|| tui.src_view.current_source_file().map(|f| f.to_owned()))
+                        {
+                            Some(file) => context.spawn_syntax_check(file),
+                            None => context.log("No source file to check."),
+                        }
+                    }
+                    Event::FocusContainer(container) if linear => {
+                        if let Frontend::Linear(output) = &mut frontend {
+                            output.show_container(&mut tui, &container);
+                        }
+                    }
+                    Event::FocusContainer(container) => {
+                        layout.select_tab(&container);
+                        // Containers that are not part of the layout are shown zoomed in.
+                        zoomed = !layout.contains(&container);
+                        app.set_active(container);
+                    }
+                    Event::ChangeLayout(new_layout) => {
+                        let new_layout = config
+                            .layout_preset(&new_layout)
+                            .map(|l| l.to_owned())
+                            .unwrap_or(new_layout);
+                        match layout::parse(new_layout) {
+                            Ok(mut new_layout) => {
+                                new_layout.select_tab(&TuiContainerType::Console);
+                                layout = new_layout;
+                                zoomed = false;
+                                app.set_active(TuiContainerType::Console);
                             }
                             Err(e) => {
                                 tui.console.write_to_gdb_log(e.to_string());
@@ -594,22 +1795,123 @@ fn run() -> i32 {
                         };
                     }
                     Event::GdbShutdown => {
+                        match context
+                            .gdb
+                            .mi
+                            .wait_for_exit(Duration::from_millis(GDB_EXIT_WAIT_MS))
+                        {
+                            Some(status) if status.success() => break 'runloop,
+                            _ => context.on_gdb_terminated(),
+                        }
+                    }
+                    Event::ShowOverlay(overlay) => {
+                        tui.overlays.push(overlay);
+                    }
+                    Event::Toast(level, msg) => match &mut frontend {
+                        Frontend::Screen(_) => tui.toasts.push(level, msg, Instant::now()),
+                        Frontend::Linear(output) => output.toast(level, &msg),
+                    },
+                    Event::ToastTimer => {
+                        tui.toasts.remove_expired(Instant::now());
+                        break 'displayloop;
+                    }
+                    Event::DownloadTimer => {
+                        context.check_download();
+                        break 'displayloop;
+                    }
+                    Event::Progress(id, update) => {
+                        tui.progress.update(id, update);
+                    }
+                    Event::CancelTasks => match tui.progress.cancel_all() {
+                        0 => context.log("No running task can be cancelled."),
+                        n => context.log(format!("Cancelling {} task(s)...", n)),
+                    },
+                    Event::ToggleFreeze => tui.toggle_freeze(&mut context),
+                    Event::OpenInEditor => {
+                        if let Some(pos) = tui.src_view.current_local_position() {
+                            input_gate.pause();
+                            let res = match &mut frontend {
+                                Frontend::Screen(terminal) => {
+                                    terminal.on_main_screen(|| open_in_editor(&pos))
+                                }
+                                Frontend::Linear(_) => Ok(open_in_editor(&pos)),
+                            };
+                            input_gate.resume();
+                            match res {
+                                Ok(Ok(())) => {}
+                                Ok(Err(e)) | Err(e) => {
+                                    context.log(format!("Failed to open the editor: {}", e))
+                                }
+                            }
+                            tui.src_view.reload_if_changed(&mut context);
+                        } else {
+                            context.log("No source file is shown.");
+                        }
+                    }
+                    Event::ExecutableChanged(executable) => {
+                        context.on_executable_changed(executable);
+                    }
+                    Event::Publish(event) => {
+                        tui.publish(event, &mut context);
+                    }
+                    Event::RestoreSession(session) => {
+                        let restored = context.gdb.restore_breakpoints(&session.breakpoints);
+                        if !session.skips.is_empty() {
+                            context.gdb.restore_skips(&session.skips);
+                            tui.publish(DebugEvent::SkipsChanged, &mut context);
+                        }
+                        for expression in session.expressions {
+                            tui.expression_table.add_entry(expression);
+                        }
+                        tui.expression_table.update_results(&mut context);
+                        if let Some(layout) = session.layout {
+                            context.try_change_layout(layout);
+                        }
+                        if !session.run_settings.is_empty() {
+                            context.run_settings = session.run_settings.clone();
+                            context.apply_run_settings();
+                        }
+                        if let Some(pos) = session.position {
+                            context.show_file(pos.file.to_string_lossy().into_owned(), pos.line);
+                        }
+                        context.log(format!(
+                            "Restored session with {} of {} breakpoint(s).",
+                            restored,
+                            session.breakpoints.len()
+                        ));
+                    }
+                    Event::Quit => {
                         break 'runloop;
                     }
                     Event::Ipc(request) => {
                         request.respond(&mut context);
                     }
                     Event::Pty(pty_output) => {
+                        if let Frontend::Linear(output) = &mut frontend {
+                            output.print(&String::from_utf8_lossy(&pty_output));
+                        }
                         tui.add_pty_input(&pty_output);
                     }
                     Event::Signal(signal_event) => {
                         let sig = signal_event;
                         match sig {
                             Signal::SIGWINCH => { /* Ignore, we just want to redraw */ }
-                            Signal::SIGTSTP => {
-                                if let Err(e) = terminal.handle_sigtstp() {
-                                    warn!("Unable to handle SIGTSTP: {}", e);
+                            Signal::SIGTSTP => match &mut frontend {
+                                Frontend::Screen(terminal) => {
+                                    if let Err(e) = terminal.handle_sigtstp() {
+                                        warn!("Unable to handle SIGTSTP: {}", e);
+                                    }
                                 }
+                                Frontend::Linear(_) => {
+                                    let _ = nix::sys::signal::raise(Signal::SIGSTOP);
+                                }
+                            },
+                            // gdb is in the same process group and interrupts the program itself.
+                            Signal::SIGINT => {}
+                            Signal::SIGTERM
+                                if context.gdb_terminated() || context.gdb.mi.is_external() =>
+                            {
+                                break 'runloop
                             }
                             Signal::SIGTERM => context.gdb.kill(),
                             _ => {}
@@ -626,19 +1928,133 @@ fn run() -> i32 {
                     }
                 }
                 tui.update_after_event(&mut context);
-                render_delay_timer.try_start(Duration::from_millis(EVENT_BUFFER_DURATION_MS));
+                let running = context.gdb.mi.is_running();
+                context.session_stats.update(running, Instant::now());
+                events_since_last_draw += 1;
+                render_delay_timer.try_start(redraw_limiter.delay(Instant::now()));
             }
             if esc_timer_needs_reset {
                 focus_esc_timer.reset();
             }
+            // Only what is shown (or needed for the summary) is queried at a stop.
+            let shows = |container| layout.contains(&container) || app.active() == container;
+            context.snapshot_parts = gdb::SnapshotParts {
+                backtrace: shows(TuiContainerType::Backtrace) || summary_file.is_some(),
+                threads: shows(TuiContainerType::Threads),
+                registers: shows(TuiContainerType::Memory),
+            };
+            let wanted = context.snapshot_parts;
+            if context.gdb.complete_snapshot(&wanted) {
+                tui.refresh_snapshot_views(&mut context);
+            }
+            let terminal = match &mut frontend {
+                Frontend::Screen(terminal) => terminal,
+                Frontend::Linear(output) => {
+                    output.update(&mut tui, &mut context);
+                    continue;
+                }
+            };
+            let active = app.active();
+            let shown = shown_layout(&layout, zoomed, &active, &tui);
+            if shown != applied_layout {
+                app.set_layout(shown.to_layout(config.title_bars));
+                app.set_active(active);
+                applied_layout = shown;
+            }
+            let mut root = terminal.create_root_window();
+            // The status bar takes the last row, the status line with the progress of long
+            // operations the one above it.
+            let mut height = root.get_height().from_origin();
+            if config.status_bar && height > RowIndex::new(1) {
+                tui.status_bar.draw(
+                    root.create_subwindow(.., height - 1..height),
+                    &context.global_state(),
+                    RenderingHints::default(),
+                );
+                height -= 1;
+            }
+            let app_height = if tui.progress.is_active() && height > RowIndex::new(1) {
+                tui.progress.draw(
+                    root.create_subwindow(.., height - 1..height),
+                    RenderingHints::default(),
+                );
+                height - 1
+            } else {
+                height
+            };
             app.draw(
-                terminal.create_root_window(),
+                root.create_subwindow(.., ..app_height),
                 &mut tui,
-                input_mode.associated_border_style(),
+                input_mode.associated_border_style(config.render_profile),
                 RenderingHints::default().blink(cursor_status),
             );
+            if let Some(color) = config.dim_inactive {
+                applied_layout.dim_inactive(
+                    root.create_subwindow(.., ..app_height),
+                    &tui,
+                    config.title_bars,
+                    &app.active(),
+                    color,
+                );
+            }
+            applied_layout.draw_bars(
+                root.create_subwindow(.., ..app_height),
+                &tui,
+                config.title_bars,
+                context.gdb.mi.is_running(),
+            );
+            tui.toasts.remove_expired(Instant::now());
+            tui.toasts.draw(
+                root.create_subwindow(.., ..app_height),
+                RenderingHints::default(),
+            );
+            tui.overlays
+                .draw(root, RenderingHints::default().blink(cursor_status));
             terminal.present();
+            redraw_limiter.drawn(Instant::now(), events_since_last_draw);
+            events_since_last_draw = 0;
         }
+
+        let session = session::Session {
+            breakpoints: context.gdb.breakpoints.save(),
+            expressions: tui.expression_table.expressions(),
+            position: tui.src_view.current_position(),
+            layout: Some(layout.to_string()),
+            skips: context.gdb.skips.clone(),
+            run_settings: context.run_settings.clone(),
+        };
+        if let Some(executable) = &executable {
+            if let Err(e) = session.save(executable) {
+                warn!("Could not save session: {}", e);
+            }
+        }
+
+        if let Some(summary_file) = summary_file {
+            let running = context.gdb.mi.is_running();
+            context.session_stats.update(running, Instant::now());
+            let summary = session_summary::SessionSummary {
+                program: executable.as_deref(),
+                ended: time::now().rfc3339().to_string(),
+                stats: &context.session_stats,
+                breakpoints: session_summary::breakpoint_hits(&context.gdb.breakpoints),
+                expressions: tui.expression_table.values(),
+            };
+            if let Err(e) = summary.write(&summary_file) {
+                warn!(
+                    "Could not write session summary to {}: {}",
+                    summary_file.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    context.stop_profile_process();
+
+    // A gdb that was started by someone else keeps running. Only the proxy command is stopped.
+    if context.gdb.mi.is_external() {
+        let _ = context.gdb.mi.process.kill();
+        return 0;
     }
 
     let mut join_retry_counter = 0;
@@ -0,0 +1,137 @@
+// Formatting of addresses and integers that is shared by all containers (the expression table, the
+// locals, the memory view, the backtrace, ...). The defaults match the output of gdb, e.g.,
+// `0x4005d0` and `1234567`.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberFormat {
+    /// Use uppercase hex digits (`0x4005D0`).
+    pub uppercase_hex: bool,
+    /// Pad addresses with zeros to the width of a pointer of the target (e.g., 16 digits).
+    pub pad_addresses: bool,
+    /// Number of hex digits per group (0: no grouping).
+    pub hex_grouping: usize,
+    pub hex_separator: char,
+    /// Separator of groups of three decimal digits (`1,234,567`), if any.
+    pub digit_separator: Option<char>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            uppercase_hex: false,
+            pad_addresses: false,
+            hex_grouping: 0,
+            hex_separator: '_',
+            digit_separator: None,
+        }
+    }
+}
+
+// Insert the separator between groups of `size` digits counted from the right.
+fn group(digits: &str, size: usize, separator: Option<char>) -> String {
+    let separator = match separator {
+        Some(s) if size > 0 => s,
+        _ => return digits.to_owned(),
+    };
+    let len = digits.chars().count();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (len - i).is_multiple_of(size) {
+            grouped.push(separator);
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+impl NumberFormat {
+    fn hex(&self, digits: &str) -> String {
+        let digits = if self.uppercase_hex {
+            digits.to_ascii_uppercase()
+        } else {
+            digits.to_ascii_lowercase()
+        };
+        format!(
+            "0x{}",
+            group(&digits, self.hex_grouping, Some(self.hex_separator))
+        )
+    }
+
+    /// Format an address. Addresses are only padded if the pointer size of the target is known.
+    pub fn address(&self, address: usize, pointer_size: Option<usize>) -> String {
+        let width = match (self.pad_addresses, pointer_size) {
+            (true, Some(size)) => 2 * size,
+            _ => 0,
+        };
+        self.hex(&format!("{:0width$x}", address, width = width))
+    }
+
+    /// Apply the format to the numbers of a (leaf) value as printed by gdb, e.g., `-1234567` or
+    /// `(int *) 0x601040 <global>`. Strings and characters are left as they are.
+    pub fn value(&self, value: &str) -> String {
+        let (sign, digits) = match value.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", value),
+        };
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            return format!("{}{}", sign, group(digits, 3, self.digit_separator));
+        }
+        let mut in_string = false;
+        value
+            .split(' ')
+            .map(|token| {
+                in_string |= token.contains('"') || token.contains('\'');
+                match token.strip_prefix("0x") {
+                    Some(digits)
+                        if !in_string
+                            && !digits.is_empty()
+                            && digits.bytes().all(|b| b.is_ascii_hexdigit()) =>
+                    {
+                        self.hex(digits)
+                    }
+                    _ => token.to_owned(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_format_matches_gdb() {
+        let format = NumberFormat::default();
+        assert_eq!(format.address(0x4005d0, Some(8)), "0x4005d0");
+        assert_eq!(format.value("1234567"), "1234567");
+        assert_eq!(format.value("(int *) 0x601040 <g>"), "(int *) 0x601040 <g>");
+    }
+
+    #[test]
+    fn custom_format() {
+        let format = NumberFormat {
+            uppercase_hex: true,
+            pad_addresses: true,
+            hex_grouping: 4,
+            hex_separator: '\'',
+            digit_separator: Some(','),
+        };
+        assert_eq!(format.address(0x4005d0, Some(8)), "0x0000'0000'0040'05D0");
+        assert_eq!(format.address(0x4005d0, Some(4)), "0x0040'05D0");
+        assert_eq!(format.address(0x4005d0, None), "0x40'05D0");
+        assert_eq!(format.value("-1234567"), "-1,234,567");
+        assert_eq!(format.value("123"), "123");
+        assert_eq!(format.value("1.5e10"), "1.5e10");
+        assert_eq!(
+            format.value("(int *) 0x601040 <g>"),
+            "(int *) 0x60'1040 <g>"
+        );
+        assert_eq!(
+            format.value("0x4005d0 \"see 0x10\""),
+            "0x40'05D0 \"see 0x10\""
+        );
+        assert_eq!(format.value("48 '0'"), "48 '0'");
+    }
+}
@@ -0,0 +1,67 @@
+// Mapping of source paths of the debugged program to paths on this machine, e.g., if the program
+// was built in a container or on a CI machine. The rules are passed to gdb (`set substitute-path`)
+// and used by the pager to find the files reported by gdb.
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PathSubstitutions {
+    rules: Vec<(PathBuf, PathBuf)>,
+}
+
+impl PathSubstitutions {
+    /// Add a rule that replaces the prefix `from` by `to`. An existing rule for `from` is replaced.
+    pub fn add(&mut self, from: PathBuf, to: PathBuf) {
+        if let Some(rule) = self.rules.iter_mut().find(|(f, _)| *f == from) {
+            rule.1 = to;
+        } else {
+            self.rules.push((from, to));
+        }
+    }
+
+    pub fn rules(&self) -> &[(PathBuf, PathBuf)] {
+        &self.rules
+    }
+
+    /// The path with the prefix of the first matching rule substituted (like gdb does it).
+    /// Prefixes only match whole components, i.e., "/build" matches "/build/a.c", but not
+    /// "/builds/a.c".
+    pub fn apply<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        for (from, to) in &self.rules {
+            if let Ok(rest) = path.strip_prefix(from) {
+                return Cow::Owned(if rest.as_os_str().is_empty() {
+                    to.clone()
+                } else {
+                    to.join(rest)
+                });
+            }
+        }
+        Cow::Borrowed(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn apply_rules() {
+        let mut s = PathSubstitutions::default();
+        s.add("/build".into(), "/home/user/src".into());
+        s.add("/build/vendor".into(), "/opt/vendor".into());
+        let apply = |p: &str| s.apply(Path::new(p)).into_owned();
+        assert_eq!(apply("/build/a.c"), PathBuf::from("/home/user/src/a.c"));
+        assert_eq!(apply("/build"), PathBuf::from("/home/user/src"));
+        // The first matching rule is used.
+        assert_eq!(
+            apply("/build/vendor/b.c"),
+            PathBuf::from("/home/user/src/vendor/b.c")
+        );
+        assert_eq!(apply("/builds/a.c"), PathBuf::from("/builds/a.c"));
+        assert_eq!(apply("src/a.c"), PathBuf::from("src/a.c"));
+
+        s.add("/build".into(), "/tmp".into());
+        assert_eq!(s.rules().len(), 2);
+        assert_eq!(s.apply(Path::new("/build/a.c")), Path::new("/tmp/a.c"));
+    }
+}
@@ -0,0 +1,414 @@
+// Launch profiles (`[[profile]]` in the config file, selected using `--profile <name>`) describe
+// how to debug a program that does not run on this machine directly. For a container (docker or
+// podman) or a WSL distribution, gdbserver is started inside it and gdb talks to it through a pipe
+// (`target extended-remote | ...`), so no port has to be published. Libraries are read from the
+// container (the sysroot is `target:`) and the sources of the volumes that are mounted into the
+// container are found on the host using substitute-path rules.
+// For a host that is reachable using ssh (e.g., a development board), the program is optionally
+// copied there using rsync first. gdbserver listens on a port of the host that is forwarded to this
+// machine by ssh, and libraries are read from the host (the sysroot is `remote:`).
+use log::info;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::thread;
+use toml::Value;
+
+// Port that gdbserver listens on on a remote host if the profile does not specify one.
+const DEFAULT_GDBSERVER_PORT: u16 = 2345;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+    Wsl,
+}
+
+impl FromStr for ContainerRuntime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "docker" => Ok(ContainerRuntime::Docker),
+            "podman" => Ok(ContainerRuntime::Podman),
+            "wsl" => Ok(ContainerRuntime::Wsl),
+            o => Err(format!(
+                "Unknown runtime '{}' (expected docker, podman or wsl)",
+                o
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ContainerRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContainerRuntime::Docker => write!(f, "docker"),
+            ContainerRuntime::Podman => write!(f, "podman"),
+            ContainerRuntime::Wsl => write!(f, "wsl"),
+        }
+    }
+}
+
+/// Where the program runs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    /// A running container or WSL distribution with the given name.
+    Container {
+        runtime: ContainerRuntime,
+        name: String,
+    },
+    /// A host that is reachable using ssh (e.g., "user@board").
+    Ssh {
+        host: String,
+        /// The port that gdbserver listens on on the host.
+        port: u16,
+        /// A local file that is copied to the program path on the host before it is started.
+        upload: Option<PathBuf>,
+    },
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Target::Container { runtime, name } => write!(f, "{} {}", runtime, name),
+            Target::Ssh { host, .. } => write!(f, "ssh {}", host),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub target: Target,
+    /// Path of the program on the target.
+    pub program: String,
+    pub args: Vec<String>,
+    /// A local copy of the program (with debug info) to read the symbols from. Without it, gdb
+    /// reads the program from the target.
+    pub symbols: Option<PathBuf>,
+    pub sysroot: String,
+}
+
+impl Profile {
+    /// Parse a `[[profile]]` table of the config file.
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let string = |key: &str| {
+            value
+                .get(key)
+                .map(|v| {
+                    v.as_str()
+                        .map(|v| v.to_owned())
+                        .ok_or_else(|| format!("'profile.{}' must be a string", key))
+                })
+                .transpose()
+        };
+        let required =
+            |key: &str| string(key)?.ok_or_else(|| format!("Launch profile is missing '{}'", key));
+        let name = required("name")?;
+        let target = match required("type")?.as_str() {
+            "container" => Target::Container {
+                runtime: required("runtime")?.parse()?,
+                name: required("container")?,
+            },
+            "ssh" => Target::Ssh {
+                host: required("host")?,
+                port: match value.get("port") {
+                    Some(port) => port
+                        .as_integer()
+                        .filter(|&p| p > 0 && p <= u16::MAX as i64)
+                        .ok_or_else(|| "'profile.port' must be a port number".to_owned())?
+                        as u16,
+                    None => DEFAULT_GDBSERVER_PORT,
+                },
+                upload: string("upload")?.map(PathBuf::from),
+            },
+            o => return Err(format!("Unknown type '{}' of launch profile '{}'", o, name)),
+        };
+        let default_sysroot = match target {
+            Target::Container { .. } => "target:",
+            Target::Ssh { .. } => "remote:",
+        };
+        let args = match value.get("args") {
+            Some(args) => args
+                .as_array()
+                .and_then(|args| {
+                    args.iter()
+                        .map(|a| a.as_str().map(|a| a.to_owned()))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| "'profile.args' must be an array of strings".to_owned())?,
+            None => Vec::new(),
+        };
+        Ok(Profile {
+            target,
+            program: required("program")?,
+            args,
+            symbols: string("symbols")?.map(PathBuf::from),
+            sysroot: string("sysroot")?.unwrap_or_else(|| default_sysroot.to_owned()),
+            name,
+        })
+    }
+
+    // gdbserver (and the program) on the target, e.g., listening on "-" (stdin and stdout) or
+    // ":2345".
+    fn gdbserver_words(&self, listen: &str) -> Vec<String> {
+        let mut words = vec![
+            "gdbserver".to_owned(),
+            listen.to_owned(),
+            self.program.clone(),
+        ];
+        words.extend(self.args.iter().cloned());
+        words
+    }
+
+    /// The shell command that starts gdbserver in the container, talking to gdb through stdin and
+    /// stdout.
+    fn pipe_command(&self, runtime: ContainerRuntime, name: &str) -> String {
+        let mut words = match runtime {
+            ContainerRuntime::Docker | ContainerRuntime::Podman => vec![
+                runtime.to_string(),
+                "exec".into(),
+                "-i".into(),
+                name.to_owned(),
+            ],
+            ContainerRuntime::Wsl => {
+                vec!["wsl.exe".into(), "-d".into(), name.to_owned(), "--".into()]
+            }
+        };
+        words.extend(self.gdbserver_words("-"));
+        shell_words(&words)
+    }
+
+    /// Arguments of rsync to copy the program to the host (if the profile has a file to upload).
+    fn upload_args(&self) -> Option<Vec<String>> {
+        match &self.target {
+            Target::Ssh {
+                host,
+                upload: Some(upload),
+                ..
+            } => Some(vec![
+                "-az".to_owned(),
+                upload.to_string_lossy().into_owned(),
+                format!("{}:{}", host, self.program),
+            ]),
+            _ => None,
+        }
+    }
+
+    /// Arguments of ssh to start gdbserver on the host and forward its port to `local_port`.
+    fn tunnel_args(&self, local_port: u16) -> Vec<String> {
+        match &self.target {
+            Target::Ssh { host, port, .. } => vec![
+                // There is no terminal to ask for a password once the tui is shown.
+                "-o".to_owned(),
+                "BatchMode=yes".to_owned(),
+                "-o".to_owned(),
+                "ExitOnForwardFailure=yes".to_owned(),
+                "-L".to_owned(),
+                format!("{}:localhost:{}", local_port, port),
+                host.clone(),
+                // The command is run by the shell on the host. gdbserver only listens on the
+                // loopback interface, so it is only reachable through the tunnel.
+                shell_words(&self.gdbserver_words(&format!("localhost:{}", port))),
+            ],
+            Target::Container { .. } => Vec::new(),
+        }
+    }
+
+    /// Start gdbserver on the target. The result is the argument of `target extended-remote` and
+    /// the process that has to keep running while the program is debugged (if any).
+    pub fn launch(&self) -> Result<(String, Option<Child>), String> {
+        match &self.target {
+            Target::Container { runtime, name } => {
+                Ok((format!("| {}", self.pipe_command(*runtime, name)), None))
+            }
+            Target::Ssh { .. } => {
+                if let Some(args) = self.upload_args() {
+                    let status = Command::new("rsync")
+                        .args(&args)
+                        .stdin(Stdio::null())
+                        .status()
+                        .map_err(|e| format!("Failed to run rsync: {}", e))?;
+                    if !status.success() {
+                        return Err(format!("rsync failed ({})", status));
+                    }
+                }
+                let local_port = TcpListener::bind("127.0.0.1:0")
+                    .and_then(|l| l.local_addr())
+                    .map_err(|e| format!("No free local port: {}", e))?
+                    .port();
+                let mut tunnel = Command::new("ssh")
+                    .args(self.tunnel_args(local_port))
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| format!("Failed to run ssh: {}", e))?;
+                forward_to_log(tunnel.stdout.take().unwrap());
+                let mut stderr = BufReader::new(tunnel.stderr.take().unwrap());
+                let mut output = String::new();
+                // gdbserver announces that it is ready to accept the connection of gdb.
+                loop {
+                    let mut line = String::new();
+                    match stderr.read_line(&mut line) {
+                        Ok(0) | Err(_) => {
+                            let _ = tunnel.wait();
+                            return Err(format!("gdbserver did not start: {}", output.trim()));
+                        }
+                        Ok(_) if line.contains("Listening on port") => break,
+                        Ok(_) => output.push_str(&line),
+                    }
+                }
+                forward_to_log(stderr);
+                Ok((format!("localhost:{}", local_port), Some(tunnel)))
+            }
+        }
+    }
+
+    /// Rules that map the directories on the target to the ones on this machine, i.e., the volumes
+    /// that are mounted into the container.
+    pub fn path_substitutions(&self) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+        match &self.target {
+            Target::Container { runtime, name } => match runtime {
+                // The files of the distribution are not mounted into this machine.
+                ContainerRuntime::Wsl => Ok(Vec::new()),
+                ContainerRuntime::Docker | ContainerRuntime::Podman => {
+                    let output = Command::new(runtime.to_string())
+                        .args(["inspect", "--format", "{{json .Mounts}}", name])
+                        .output()
+                        .map_err(|e| format!("Failed to run {}: {}", runtime, e))?;
+                    if !output.status.success() {
+                        return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+                    }
+                    parse_mounts(&String::from_utf8_lossy(&output.stdout))
+                }
+            },
+            // The sources are expected where they were built.
+            Target::Ssh { .. } => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Parse the mounts of a container as printed by `docker inspect --format '{{json .Mounts}}'`
+/// into (destination, source) pairs.
+pub fn parse_mounts(json: &str) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    let mounts = json::parse(json).map_err(|e| format!("Invalid mounts: {}", e))?;
+    Ok(mounts
+        .members()
+        .filter_map(|m| {
+            let source = m["Source"].as_str()?;
+            let destination = m["Destination"].as_str()?;
+            Some((PathBuf::from(destination), PathBuf::from(source)))
+        })
+        .collect())
+}
+
+// Output of gdbserver (and of the program) that is not shown anywhere else.
+fn forward_to_log(output: impl Read + Send + 'static) {
+    thread::spawn(move || {
+        for line in BufReader::new(output).lines() {
+            match line {
+                Ok(line) => info!("gdbserver: {}", line),
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+fn shell_words(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|w| shell_quote(w))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// gdb runs the command of `target remote |` using /bin/sh, ssh uses the shell of the host.
+fn shell_quote(word: &str) -> String {
+    let is_plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c);
+    if !word.is_empty() && word.chars().all(is_plain) {
+        word.to_owned()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn profile(runtime: ContainerRuntime) -> Profile {
+        Profile {
+            name: "app".to_owned(),
+            target: Target::Container {
+                runtime,
+                name: "app-dev".to_owned(),
+            },
+            program: "/usr/local/bin/app".to_owned(),
+            args: vec!["--port".to_owned(), "8080".to_owned(), "it's".to_owned()],
+            symbols: None,
+            sysroot: "target:".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_pipe_command() {
+        assert_eq!(
+            profile(ContainerRuntime::Docker).pipe_command(ContainerRuntime::Docker, "app-dev"),
+            "docker exec -i app-dev gdbserver - /usr/local/bin/app --port 8080 'it'\\''s'"
+        );
+        assert_eq!(
+            profile(ContainerRuntime::Wsl).pipe_command(ContainerRuntime::Wsl, "app-dev"),
+            "wsl.exe -d app-dev -- gdbserver - /usr/local/bin/app --port 8080 'it'\\''s'"
+        );
+    }
+
+    #[test]
+    fn test_ssh_args() {
+        let mut profile = profile(ContainerRuntime::Docker);
+        profile.target = Target::Ssh {
+            host: "pi@board".to_owned(),
+            port: 2345,
+            upload: Some(PathBuf::from("target/debug/app")),
+        };
+        assert_eq!(
+            profile.upload_args().unwrap(),
+            vec!["-az", "target/debug/app", "pi@board:/usr/local/bin/app"]
+        );
+        assert_eq!(
+            profile.tunnel_args(40000),
+            vec![
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                "ExitOnForwardFailure=yes",
+                "-L",
+                "40000:localhost:2345",
+                "pi@board",
+                "gdbserver localhost:2345 /usr/local/bin/app --port 8080 'it'\\''s'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_mounts() {
+        let json = r#"[{"Type":"bind","Source":"/home/user/app","Destination":"/src","Mode":"","RW":true},
+            {"Type":"volume","Name":"cache","Source":"/var/lib/docker/volumes/cache/_data","Destination":"/cache"}]"#;
+        assert_eq!(
+            parse_mounts(json).unwrap(),
+            vec![
+                (PathBuf::from("/src"), PathBuf::from("/home/user/app")),
+                (
+                    PathBuf::from("/cache"),
+                    PathBuf::from("/var/lib/docker/volumes/cache/_data")
+                ),
+            ]
+        );
+        assert_eq!(parse_mounts("[]").unwrap(), vec![]);
+        assert!(parse_mounts("no json").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+// Decides when the screen is drawn again after an event. Events that arrive in quick succession
+// are coalesced into a single redraw and redraws are spaced at least the configured interval apart.
+// While events keep pouring in (e.g., heavy output of the program or a script stepping through
+// it), the interval grows, so that slow connections (e.g., ssh) are not saturated with redraws.
+use std::time::{Duration, Instant};
+
+// Events that arrive within this duration are always coalesced.
+const EVENT_BUFFER_DURATION: Duration = Duration::from_millis(10);
+// The interval grows if more events than this were handled between two redraws...
+const BUSY_EVENTS_PER_FRAME: usize = 32;
+// ... but redraws are never delayed longer than this.
+const MAX_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct RedrawLimiter {
+    min_interval: Duration,
+    interval: Duration,
+    last_draw: Option<Instant>,
+}
+
+impl RedrawLimiter {
+    /// `max_rate` is the maximum number of redraws per second (no limit if not given).
+    pub fn new(max_rate: Option<u32>) -> Self {
+        let min_interval = max_rate
+            .map(|rate| Duration::from_secs(1) / rate.max(1))
+            .unwrap_or(EVENT_BUFFER_DURATION)
+            .max(EVENT_BUFFER_DURATION);
+        RedrawLimiter {
+            min_interval,
+            interval: min_interval,
+            last_draw: None,
+        }
+    }
+
+    /// How long to wait after an event before drawing the screen.
+    pub fn delay(&self, now: Instant) -> Duration {
+        let since_last_draw = self
+            .last_draw
+            .map(|t| now.saturating_duration_since(t))
+            .unwrap_or(self.interval);
+        self.interval
+            .checked_sub(since_last_draw)
+            .unwrap_or_default()
+            .max(EVENT_BUFFER_DURATION)
+    }
+
+    /// Called whenever the screen is drawn with the number of events since the previous redraw.
+    pub fn drawn(&mut self, now: Instant, events: usize) {
+        self.interval = if events > BUSY_EVENTS_PER_FRAME {
+            (self.interval * 2).min(MAX_INTERVAL.max(self.min_interval))
+        } else {
+            (self.interval / 2).max(self.min_interval)
+        };
+        self.last_draw = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit() {
+        let now = Instant::now();
+        let mut limiter = RedrawLimiter::new(Some(20));
+        assert_eq!(limiter.delay(now), EVENT_BUFFER_DURATION);
+        limiter.drawn(now, 1);
+        assert_eq!(
+            limiter.delay(now + Duration::from_millis(20)),
+            Duration::from_millis(30)
+        );
+        assert_eq!(
+            limiter.delay(now + Duration::from_millis(100)),
+            EVENT_BUFFER_DURATION
+        );
+
+        let limiter = RedrawLimiter::new(None);
+        assert_eq!(limiter.delay(now), EVENT_BUFFER_DURATION);
+    }
+
+    #[test]
+    fn test_adapts_to_busy_periods() {
+        let now = Instant::now();
+        let mut limiter = RedrawLimiter::new(None);
+        for _ in 0..10 {
+            limiter.drawn(now, 1000);
+        }
+        assert_eq!(limiter.delay(now), MAX_INTERVAL);
+        limiter.drawn(now, 1);
+        assert_eq!(limiter.delay(now), MAX_INTERVAL / 2);
+        for _ in 0..10 {
+            limiter.drawn(now, 1);
+        }
+        assert_eq!(limiter.delay(now), EVENT_BUFFER_DURATION);
+    }
+}
@@ -0,0 +1,227 @@
+// Implementation of `ugdb remote`, which controls a running ugdb instance using its ipc socket.
+use ipc::{self, IPCClient};
+use json::JsonValue;
+use std::env;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "ugdb remote",
+    bin_name = "ugdb remote",
+    about = "Control a running ugdb instance."
+)]
+pub struct RemoteOptions {
+    #[structopt(
+        long = "socket",
+        help = "IPC socket of the ugdb instance. [default: the socket of the only running instance]",
+        parse(from_os_str)
+    )]
+    socket: Option<PathBuf>,
+    #[structopt(subcommand)]
+    command: RemoteCommand,
+}
+
+#[derive(StructOpt)]
+enum RemoteCommand {
+    #[structopt(name = "break", about = "Set a breakpoint at the given location.")]
+    Break {
+        #[structopt(help = "Location in the form file:line.")]
+        location: String,
+    },
+    #[structopt(name = "show", about = "Show the given location in the pager.")]
+    Show {
+        #[structopt(help = "Location in the form file:line.")]
+        location: String,
+    },
+    #[structopt(
+        name = "eval",
+        about = "Evaluate an expression in the selected frame and print its value."
+    )]
+    Eval {
+        #[structopt(help = "Expression to evaluate.")]
+        expression: String,
+    },
+    #[structopt(
+        name = "action",
+        about = "Run an action (see !help in ugdb), e.g., \"ugdb remote action grep foo\"."
+    )]
+    Action {
+        #[structopt(help = "Id of the action.")]
+        id: String,
+        #[structopt(help = "Arguments of the action.")]
+        args: Vec<String>,
+    },
+    #[structopt(name = "info", about = "Print the working directory of the instance.")]
+    Info,
+    #[structopt(
+        name = "wait",
+        about = "Wait until the program stops and print the reason and location."
+    )]
+    Wait,
+}
+
+fn parse_location(location: &str) -> Result<(String, usize), String> {
+    let mut parts = location.rsplitn(2, ':');
+    let line = parts.next().and_then(|l| l.parse::<usize>().ok());
+    match (parts.next(), line) {
+        (Some(file), Some(line)) if line > 0 => {
+            // Relative paths are meant relative to our working directory, not the one of ugdb.
+            let path = Path::new(file);
+            let file = match env::current_dir() {
+                Ok(dir) if path.is_relative() && path.exists() => {
+                    dir.join(path).to_string_lossy().into_owned()
+                }
+                _ => file.to_owned(),
+            };
+            Ok((file, line))
+        }
+        _ => Err(format!(
+            "Invalid location '{}', expected file:line",
+            location
+        )),
+    }
+}
+
+fn find_socket() -> Result<PathBuf, String> {
+    let mut sockets =
+        ipc::find_sockets().map_err(|e| format!("Unable to search for ugdb instances: {}", e))?;
+    match sockets.len() {
+        0 => Err("No running ugdb instance found".to_owned()),
+        1 => Ok(sockets.remove(0)),
+        _ => Err(format!(
+            "Multiple running ugdb instances found, select one using --socket:\n{}",
+            sockets
+                .iter()
+                .map(|s| s.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("\n")
+        )),
+    }
+}
+
+fn success_result(response: JsonValue) -> Result<JsonValue, String> {
+    match response["type"].as_str() {
+        Some("success") => Ok(response["result"].clone()),
+        Some("error") => Err(format!(
+            "{} {}",
+            response["reason"].as_str().unwrap_or("Unknown error"),
+            response["details"].as_str().unwrap_or("")
+        )),
+        _ => Err(format!("Unexpected response: {}", response.dump())),
+    }
+}
+
+fn execute(client: &mut IPCClient, command: RemoteCommand) -> Result<String, String> {
+    let request = |client: &mut IPCClient, function: &str, parameters: JsonValue| {
+        client
+            .request(function, parameters)
+            .map_err(|e| format!("Communication with ugdb failed: {}", e))
+            .and_then(success_result)
+    };
+    match command {
+        RemoteCommand::Break { location } => {
+            let (file, line) = parse_location(&location)?;
+            let result = request(
+                client,
+                "set_breakpoint",
+                object! { "file" => file, "line" => line },
+            )?;
+            Ok(result.to_string())
+        }
+        RemoteCommand::Show { location } => {
+            let (file, line) = parse_location(&location)?;
+            let result = request(
+                client,
+                "show_file",
+                object! { "file" => file, "line" => line },
+            )?;
+            Ok(result.to_string())
+        }
+        RemoteCommand::Eval { expression } => {
+            let result = request(client, "evaluate", object! { "expression" => expression })?;
+            Ok(result["value"].to_string())
+        }
+        RemoteCommand::Action { id, args } => {
+            let result = request(
+                client,
+                "action",
+                object! { "id" => id, "args" => args.join(" ") },
+            )?;
+            Ok(result.to_string())
+        }
+        RemoteCommand::Info => {
+            let result = request(client, "get_instance_info", JsonValue::new_object())?;
+            Ok(result["working_directory"].to_string())
+        }
+        RemoteCommand::Wait => {
+            request(
+                client,
+                "subscribe",
+                object! { "events" => array!["stopped"] },
+            )?;
+            loop {
+                let msg = client
+                    .receive()
+                    .map_err(|e| format!("Communication with ugdb failed: {}", e))?;
+                if msg["type"] == "notification" && msg["event"] == "stopped" {
+                    let details = &msg["details"];
+                    let frame = &details["frame"];
+                    let location = match (frame["fullname"].as_str(), frame["line"].as_str()) {
+                        (Some(file), Some(line)) => format!("{}:{}", file, line),
+                        _ => frame["addr"].as_str().unwrap_or("?").to_owned(),
+                    };
+                    return Ok(format!(
+                        "{} {}",
+                        details["reason"].as_str().unwrap_or("stopped"),
+                        location
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Execute the command and print the result. Returns the exit code of the process.
+pub fn run(options: RemoteOptions) -> i32 {
+    let socket = match options.socket.map(Ok).unwrap_or_else(find_socket) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let mut client = match IPCClient::connect(&socket) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Unable to connect to {}: {}", socket.display(), e);
+            return 2;
+        }
+    };
+    match execute(&mut client, options.command) {
+        Ok(output) => {
+            println!("{}", output);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_location() {
+        assert_eq!(
+            parse_location("/does/not/exist.c:42"),
+            Ok(("/does/not/exist.c".to_owned(), 42))
+        );
+        assert!(parse_location("main.c").is_err());
+        assert!(parse_location("main.c:0").is_err());
+        assert!(parse_location("main.c:x").is_err());
+    }
+}
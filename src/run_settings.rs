@@ -0,0 +1,246 @@
+// How the program is started on the next run: its arguments, environment and working directory.
+// They can be changed using `!args`, `!env` and `!cwd`, are saved with the session and are applied
+// again if gdb is restarted. Settings that were not changed are left as gdb has them (e.g., the
+// arguments from the command line of ugdb).
+// Run configurations (`[[run]]` in the config file) are named sets of these settings (and gdb
+// commands) that can be switched between using `!run-config`.
+use std::path::PathBuf;
+use toml::Value;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunSettings {
+    /// As passed to `-exec-arguments`, i.e., split (and expanded) by the shell that starts the
+    /// program.
+    pub args: Option<String>,
+    pub cwd: Option<PathBuf>,
+    /// Variables that are set (or unset if there is no value) in the order they were changed.
+    pub environment: Vec<(String, Option<String>)>,
+}
+
+impl RunSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == RunSettings::default()
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: Option<String>) {
+        self.environment.retain(|(n, _)| n != name);
+        self.environment.push((name.to_owned(), value));
+    }
+
+    /// One line per changed setting, e.g., "FOO=1" or "unset BAR".
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(args) = &self.args {
+            lines.push(format!("args: {}", args));
+        }
+        if let Some(cwd) = &self.cwd {
+            lines.push(format!("cwd: {}", cwd.display()));
+        }
+        for (name, value) in &self.environment {
+            lines.push(match value {
+                Some(value) => format!("{}={}", name, value),
+                None => format!("unset {}", name),
+            });
+        }
+        lines
+    }
+
+    /// Apply the changes of `other` on top of these settings.
+    pub fn merge(&mut self, other: &RunSettings) {
+        if other.args.is_some() {
+            self.args = other.args.clone();
+        }
+        if other.cwd.is_some() {
+            self.cwd = other.cwd.clone();
+        }
+        for (name, value) in &other.environment {
+            self.set_variable(name, value.clone());
+        }
+    }
+
+    /// The settings after switching from the `previous` run configuration (if any) to `next`: The
+    /// settings of `next` on top of the `baseline`, i.e., those before the first configuration was
+    /// selected. Variables that the previous configuration set (and the baseline does not) are
+    /// unset, so that they do not leak into the next configuration.
+    pub fn switch(
+        baseline: &RunSettings,
+        previous: Option<&RunSettings>,
+        next: &RunSettings,
+    ) -> RunSettings {
+        let mut settings = baseline.clone();
+        let is_set = |settings: &RunSettings, name: &str| {
+            settings.environment.iter().any(|(n, _)| n == name)
+        };
+        for (name, _) in previous.map(|p| &p.environment[..]).unwrap_or(&[]) {
+            if !is_set(baseline, name) && !is_set(next, name) {
+                settings.set_variable(name, None);
+            }
+        }
+        settings.merge(next);
+        settings
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunConfiguration {
+    pub name: String,
+    pub settings: RunSettings,
+    /// gdb commands that are executed when the configuration is selected (e.g., to set
+    /// breakpoints or `set follow-fork-mode child`).
+    pub commands: Vec<String>,
+}
+
+impl RunConfiguration {
+    /// Parse a `[[run]]` table of the config file.
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let string = |key: &str| {
+            value
+                .get(key)
+                .map(|v| {
+                    v.as_str()
+                        .map(|v| v.to_owned())
+                        .ok_or_else(|| format!("'run.{}' must be a string", key))
+                })
+                .transpose()
+        };
+        let name = string("name")?.ok_or("Run configuration is missing 'name'")?;
+        let mut settings = RunSettings {
+            args: string("args")?,
+            cwd: string("cwd")?.map(PathBuf::from),
+            environment: Vec::new(),
+        };
+        if let Some(env) = value.get("env") {
+            let env = env
+                .as_table()
+                .ok_or("'run.env' must be a table of strings")?;
+            for (name, value) in env {
+                let value = value
+                    .as_str()
+                    .ok_or("'run.env' must be a table of strings")?;
+                settings.set_variable(name, Some(value.to_owned()));
+            }
+        }
+        let commands = match value.get("commands") {
+            Some(commands) => commands
+                .as_array()
+                .and_then(|commands| {
+                    commands
+                        .iter()
+                        .map(|c| c.as_str().map(|c| c.to_owned()))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or("'run.commands' must be an array of strings")?,
+            None => Vec::new(),
+        };
+        Ok(RunConfiguration {
+            name,
+            settings,
+            commands,
+        })
+    }
+
+    /// E.g., "unit tests: args: --test-threads 1, RUST_LOG=debug".
+    pub fn description(&self) -> String {
+        let mut lines = self.settings.lines();
+        lines.extend(self.commands.iter().cloned());
+        if lines.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}: {}", self.name, lines.join(", "))
+        }
+    }
+}
+
+/// Parse "NAME=value" (or "NAME" to unset the variable).
+pub fn parse_assignment(s: &str) -> Result<(String, Option<String>), String> {
+    let (name, value) = match s.split_once('=') {
+        Some((name, value)) => (name.trim(), Some(value.to_owned())),
+        None => (s.trim(), None),
+    };
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!("Invalid environment variable name '{}'", name));
+    }
+    Ok((name.to_owned(), value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            parse_assignment("FOO=a b=c"),
+            Ok(("FOO".to_owned(), Some("a b=c".to_owned())))
+        );
+        assert_eq!(
+            parse_assignment("FOO="),
+            Ok(("FOO".to_owned(), Some(String::new())))
+        );
+        assert_eq!(parse_assignment("FOO"), Ok(("FOO".to_owned(), None)));
+        assert!(parse_assignment("=1").is_err());
+        assert!(parse_assignment("A B=1").is_err());
+    }
+
+    #[test]
+    fn test_set_variable() {
+        let mut settings = RunSettings::default();
+        settings.set_variable("A", Some("1".to_owned()));
+        settings.set_variable("B", None);
+        settings.set_variable("A", Some("2".to_owned()));
+        assert_eq!(settings.lines(), vec!["unset B", "A=2"]);
+        assert!(!settings.is_empty());
+
+        settings.merge(&RunSettings {
+            args: Some("-v".to_owned()),
+            cwd: None,
+            environment: vec![("B".to_owned(), Some("3".to_owned()))],
+        });
+        assert_eq!(settings.lines(), vec!["args: -v", "A=2", "B=3"]);
+    }
+
+    #[test]
+    fn test_switch() {
+        let baseline = RunSettings {
+            args: Some("input.txt".to_owned()),
+            cwd: Some(PathBuf::from("/project")),
+            environment: vec![("HOME".to_owned(), Some("/home/user".to_owned()))],
+        };
+        let tests = RunSettings {
+            args: Some("--test-threads 1".to_owned()),
+            cwd: Some(PathBuf::from("/project/tests")),
+            environment: vec![
+                ("RUST_LOG".to_owned(), Some("debug".to_owned())),
+                ("HOME".to_owned(), Some("/tmp".to_owned())),
+            ],
+        };
+        let release = RunSettings {
+            args: None,
+            cwd: None,
+            environment: vec![("OPT".to_owned(), Some("3".to_owned()))],
+        };
+
+        let settings = RunSettings::switch(&baseline, None, &tests);
+        assert_eq!(
+            settings.lines(),
+            vec![
+                "args: --test-threads 1",
+                "cwd: /project/tests",
+                "RUST_LOG=debug",
+                "HOME=/tmp"
+            ]
+        );
+
+        let settings = RunSettings::switch(&baseline, Some(&tests), &release);
+        assert_eq!(
+            settings.lines(),
+            vec![
+                "args: input.txt",
+                "cwd: /project",
+                "HOME=/home/user",
+                "unset RUST_LOG",
+                "OPT=3"
+            ]
+        );
+    }
+}
@@ -0,0 +1,426 @@
+// Breakpoints, skips, expressions, the shown file, the layout and the changed run settings of the
+// last session with a program.
+// They are saved on exit and can be restored when the same program is debugged again.
+use gdb::{Address, SavedBreakPoint, Skip, SrcPosition};
+use run_settings::RunSettings;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml::value::{Table, Value};
+use unsegen::base::LineNumber;
+
+#[derive(Debug)]
+pub enum SessionError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, String),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Io(path, e) => write!(f, "Failed to access {}: {}", path.display(), e),
+            SessionError::Parse(path, e) => write!(f, "Failed to parse {}: {}", path.display(), e),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Session {
+    pub breakpoints: Vec<SavedBreakPoint>,
+    pub expressions: Vec<String>,
+    /// Where the pager was.
+    pub position: Option<SrcPosition>,
+    pub layout: Option<String>,
+    pub skips: Vec<Skip>,
+    pub run_settings: RunSettings,
+}
+
+fn sessions_dir() -> Option<PathBuf> {
+    let data_dir = match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(data_dir.join("ugdb").join("sessions"))
+}
+
+// 64 bit FNV-1a, which (unlike the hasher of std) is stable across versions of Rust.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Sessions are stored in one file per program, named after the file name and a hash of the path
+// of the program. The path is stored in the file as well (see `load`) in case of collisions.
+fn session_file_name(executable: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    let name = executable
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    format!(
+        "{}-{:016x}.toml",
+        name,
+        fnv1a(executable.as_os_str().as_bytes())
+    )
+}
+
+impl Session {
+    pub fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty()
+            && self.expressions.is_empty()
+            && self.position.is_none()
+            && self.skips.is_empty()
+            && self.run_settings.is_empty()
+    }
+
+    /// Short description of what would be restored, e.g., "2 breakpoint(s), 1 expression(s)".
+    pub fn summary(&self) -> String {
+        let mut parts = vec![
+            format!("{} breakpoint(s)", self.breakpoints.len()),
+            format!("{} expression(s)", self.expressions.len()),
+        ];
+        if !self.skips.is_empty() {
+            parts.push(format!("{} skip(s)", self.skips.len()));
+        }
+        parts.extend(self.run_settings.lines());
+        if let Some(ref pos) = self.position {
+            parts.push(format!("{}:{}", pos.file.display(), pos.line));
+        }
+        parts.join(", ")
+    }
+
+    /// The last session with the program, if there is one.
+    pub fn load(executable: &Path) -> Result<Option<Session>, SessionError> {
+        let path = match sessions_dir() {
+            Some(dir) => dir.join(session_file_name(executable)),
+            None => return Ok(None),
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(SessionError::Io(path, e)),
+        };
+        let value = content
+            .parse::<Value>()
+            .map_err(|e| SessionError::Parse(path.clone(), e.to_string()))?;
+        // Another program with the same file name and hash.
+        if value.get("executable").and_then(|e| e.as_str())
+            != Some(executable.to_string_lossy().as_ref())
+        {
+            return Ok(None);
+        }
+        Self::from_value(&value)
+            .map(Some)
+            .map_err(|e| SessionError::Parse(path, e))
+    }
+
+    /// Save the session, replacing the last one. An empty session removes the last one, so that it
+    /// is not offered again.
+    pub fn save(&self, executable: &Path) -> Result<(), SessionError> {
+        let dir = match sessions_dir() {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        let path = dir.join(session_file_name(executable));
+        if self.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                r => r.map_err(|e| SessionError::Io(path, e)),
+            };
+        }
+        fs::create_dir_all(&dir).map_err(|e| SessionError::Io(dir.clone(), e))?;
+        fs::write(&path, self.to_toml(executable)).map_err(|e| SessionError::Io(path, e))
+    }
+
+    fn to_toml(&self, executable: &Path) -> String {
+        let mut session = Table::new();
+        session.insert(
+            "executable".to_owned(),
+            Value::String(executable.to_string_lossy().into_owned()),
+        );
+        session.insert(
+            "expressions".to_owned(),
+            Value::Array(
+                self.expressions
+                    .iter()
+                    .map(|e| Value::String(e.clone()))
+                    .collect(),
+            ),
+        );
+        if let Some(ref layout) = self.layout {
+            session.insert("layout".to_owned(), Value::String(layout.clone()));
+        }
+        if let Some(ref pos) = self.position {
+            let mut position = Table::new();
+            position.insert(
+                "file".to_owned(),
+                Value::String(pos.file.to_string_lossy().into_owned()),
+            );
+            position.insert(
+                "line".to_owned(),
+                Value::Integer(pos.line.raw_value() as i64),
+            );
+            session.insert("position".to_owned(), Value::Table(position));
+        }
+        let breakpoints = self
+            .breakpoints
+            .iter()
+            .map(|bp| {
+                let mut breakpoint = Table::new();
+                if let Some(ref pos) = bp.src_pos {
+                    breakpoint.insert(
+                        "file".to_owned(),
+                        Value::String(pos.file.to_string_lossy().into_owned()),
+                    );
+                    breakpoint.insert(
+                        "line".to_owned(),
+                        Value::Integer(pos.line.raw_value() as i64),
+                    );
+                }
+                if let Some(address) = bp.address {
+                    breakpoint.insert("address".to_owned(), Value::Integer(address.0 as i64));
+                }
+                breakpoint.insert("enabled".to_owned(), Value::Boolean(bp.enabled));
+                if let Some(ref condition) = bp.condition {
+                    breakpoint.insert("condition".to_owned(), Value::String(condition.clone()));
+                }
+                if bp.ignore_count > 0 {
+                    breakpoint.insert(
+                        "ignore_count".to_owned(),
+                        Value::Integer(bp.ignore_count as i64),
+                    );
+                }
+                if !bp.commands.is_empty() {
+                    breakpoint.insert(
+                        "commands".to_owned(),
+                        Value::Array(bp.commands.iter().cloned().map(Value::String).collect()),
+                    );
+                }
+                Value::Table(breakpoint)
+            })
+            .collect();
+        session.insert("breakpoints".to_owned(), Value::Array(breakpoints));
+        let skips = self
+            .skips
+            .iter()
+            .map(|s| {
+                let mut skip = Table::new();
+                skip.insert("kind".to_owned(), Value::String(s.kind.to_string()));
+                skip.insert("pattern".to_owned(), Value::String(s.pattern.clone()));
+                skip.insert("enabled".to_owned(), Value::Boolean(s.enabled));
+                Value::Table(skip)
+            })
+            .collect();
+        session.insert("skips".to_owned(), Value::Array(skips));
+        if !self.run_settings.is_empty() {
+            let settings = &self.run_settings;
+            let mut run = Table::new();
+            if let Some(ref args) = settings.args {
+                run.insert("args".to_owned(), Value::String(args.clone()));
+            }
+            if let Some(ref cwd) = settings.cwd {
+                run.insert(
+                    "cwd".to_owned(),
+                    Value::String(cwd.to_string_lossy().into_owned()),
+                );
+            }
+            let environment = settings
+                .environment
+                .iter()
+                .map(|(name, value)| {
+                    let mut variable = Table::new();
+                    variable.insert("name".to_owned(), Value::String(name.clone()));
+                    if let Some(value) = value {
+                        variable.insert("value".to_owned(), Value::String(value.clone()));
+                    }
+                    Value::Table(variable)
+                })
+                .collect();
+            run.insert("environment".to_owned(), Value::Array(environment));
+            session.insert("run".to_owned(), Value::Table(run));
+        }
+        Value::Table(session).to_string()
+    }
+
+    #[cfg(test)]
+    fn parse(content: &str) -> Result<Self, String> {
+        Self::from_value(&content.parse::<Value>().map_err(|e| e.to_string())?)
+    }
+
+    fn from_value(value: &Value) -> Result<Self, String> {
+        let src_pos = |table: &Value| -> Result<Option<SrcPosition>, String> {
+            match (table.get("file"), table.get("line")) {
+                (Some(file), Some(line)) => Ok(Some(SrcPosition::new(
+                    PathBuf::from(file.as_str().ok_or("'file' must be a string")?),
+                    LineNumber::new(
+                        line.as_integer()
+                            .filter(|&l| l > 0)
+                            .ok_or("'line' must be a positive integer")?
+                            as usize,
+                    ),
+                ))),
+                _ => Ok(None),
+            }
+        };
+        let mut session = Session::default();
+        if let Some(expressions) = value.get("expressions").and_then(|e| e.as_array()) {
+            session.expressions = expressions
+                .iter()
+                .map(|e| e.as_str().map(|e| e.to_owned()))
+                .collect::<Option<_>>()
+                .ok_or("'expressions' must be strings")?;
+        }
+        session.layout = value
+            .get("layout")
+            .and_then(|l| l.as_str())
+            .map(|l| l.to_owned());
+        if let Some(position) = value.get("position") {
+            session.position = src_pos(position)?;
+        }
+        if let Some(breakpoints) = value.get("breakpoints").and_then(|b| b.as_array()) {
+            for bp in breakpoints {
+                session.breakpoints.push(SavedBreakPoint {
+                    src_pos: src_pos(bp)?,
+                    address: bp
+                        .get("address")
+                        .and_then(|a| a.as_integer())
+                        .map(|a| Address(a as usize)),
+                    enabled: bp.get("enabled").and_then(|e| e.as_bool()).unwrap_or(true),
+                    condition: bp
+                        .get("condition")
+                        .and_then(|c| c.as_str())
+                        .map(|c| c.to_owned()),
+                    ignore_count: bp
+                        .get("ignore_count")
+                        .and_then(|c| c.as_integer())
+                        .unwrap_or(0) as u64,
+                    commands: match bp.get("commands").and_then(|c| c.as_array()) {
+                        Some(commands) => commands
+                            .iter()
+                            .map(|c| c.as_str().map(|c| c.to_owned()))
+                            .collect::<Option<_>>()
+                            .ok_or("'commands' of breakpoints must be strings")?,
+                        None => Vec::new(),
+                    },
+                });
+            }
+        }
+        if let Some(skips) = value.get("skips").and_then(|s| s.as_array()) {
+            for skip in skips {
+                session.skips.push(Skip {
+                    number: 0,
+                    enabled: skip
+                        .get("enabled")
+                        .and_then(|e| e.as_bool())
+                        .unwrap_or(true),
+                    kind: skip
+                        .get("kind")
+                        .and_then(|k| k.as_str())
+                        .ok_or("'kind' of skips must be a string")?
+                        .parse()?,
+                    pattern: skip
+                        .get("pattern")
+                        .and_then(|p| p.as_str())
+                        .ok_or("'pattern' of skips must be a string")?
+                        .to_owned(),
+                });
+            }
+        }
+        if let Some(run) = value.get("run") {
+            let string = |key: &str| run.get(key).and_then(|v| v.as_str()).map(|v| v.to_owned());
+            session.run_settings.args = string("args");
+            session.run_settings.cwd = string("cwd").map(PathBuf::from);
+            if let Some(environment) = run.get("environment").and_then(|e| e.as_array()) {
+                for variable in environment {
+                    let name = variable
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .ok_or("'name' of environment variables must be a string")?;
+                    let value = variable
+                        .get("value")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_owned());
+                    session.run_settings.set_variable(name, value);
+                }
+            }
+        }
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use gdb::SkipKind;
+
+    #[test]
+    fn test_session_file_name() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        let name = session_file_name(Path::new("/tmp/100%/a.out"));
+        assert!(
+            name.starts_with("a.out-") && name.ends_with(".toml"),
+            "{}",
+            name
+        );
+        // Paths that only differ in characters that were escaped before.
+        assert_ne!(
+            session_file_name(Path::new("/tmp/a%/b")),
+            session_file_name(Path::new("/tmp/a/%b"))
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let session = Session {
+            breakpoints: vec![
+                SavedBreakPoint {
+                    src_pos: Some(SrcPosition::new(
+                        PathBuf::from("/tmp/a.c"),
+                        LineNumber::new(10),
+                    )),
+                    address: Some(Address(0x1131)),
+                    enabled: false,
+                    condition: Some("x > \"1\"".to_owned()),
+                    ignore_count: 3,
+                    commands: vec!["silent".to_owned(), "print \"x\"".to_owned()],
+                },
+                SavedBreakPoint {
+                    src_pos: None,
+                    address: Some(Address(0x2000)),
+                    enabled: true,
+                    condition: None,
+                    ignore_count: 0,
+                    commands: Vec::new(),
+                },
+            ],
+            expressions: vec!["# locals".to_owned(), "x".to_owned()],
+            position: Some(SrcPosition::new(
+                PathBuf::from("/tmp/a.c"),
+                LineNumber::new(3),
+            )),
+            layout: Some("1s|1c".to_owned()),
+            skips: vec![Skip {
+                number: 0,
+                enabled: false,
+                kind: SkipKind::FunctionRegex,
+                pattern: "^std::".to_owned(),
+            }],
+            run_settings: RunSettings {
+                args: Some("--input \"a b.txt\"".to_owned()),
+                cwd: Some(PathBuf::from("/tmp")),
+                environment: vec![
+                    ("RUST_LOG".to_owned(), Some("debug".to_owned())),
+                    ("LD_PRELOAD".to_owned(), None),
+                ],
+            },
+        };
+        let content = session.to_toml(Path::new("/tmp/a.out"));
+        assert_eq!(Session::parse(&content), Ok(session));
+        assert_eq!(Session::parse(""), Ok(Session::default()));
+        assert!(Session::parse("expressions = [1]").is_err());
+        assert!(Session::parse("[[skips]]\nkind = \"class\"\npattern = \"a\"").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+// Record of a debugging session that is written to a file on exit if requested (`--summary`): The
+// breakpoints that were hit, how long the program was running, the last backtrace and the final
+// values of the expressions in the expression table.
+use gdb::{BreakPointSet, Snapshot};
+use gdbmi::types::Frame;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tui::backtrace::frame_location;
+
+/// Statistics that are collected while ugdb runs.
+pub struct SessionStats {
+    last_update: Instant,
+    was_running: bool,
+    running: Duration,
+    stopped: Duration,
+    stops: usize,
+    last_backtrace: Vec<Frame>,
+}
+
+impl SessionStats {
+    pub fn new(now: Instant) -> Self {
+        SessionStats {
+            last_update: now,
+            was_running: false,
+            running: Duration::from_secs(0),
+            stopped: Duration::from_secs(0),
+            stops: 0,
+            last_backtrace: Vec::new(),
+        }
+    }
+
+    /// Attribute the time since the last update to the state (running or not) at that time.
+    pub fn update(&mut self, running: bool, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_update);
+        if self.was_running {
+            self.running += elapsed;
+        } else {
+            self.stopped += elapsed;
+        }
+        self.last_update = now;
+        self.was_running = running;
+    }
+
+    pub fn count_stop(&mut self) {
+        self.stops += 1;
+    }
+
+    pub fn stops(&self) -> usize {
+        self.stops
+    }
+
+    /// Remember the backtrace of the snapshot. The last backtrace that had any frames is kept,
+    /// e.g., when the program exits afterwards.
+    pub fn record_backtrace(&mut self, snapshot: &Snapshot) {
+        match &snapshot.backtrace {
+            Ok(frames) if !frames.is_empty() => self.last_backtrace = frames.clone(),
+            _ => {}
+        }
+    }
+}
+
+pub struct BreakpointHits {
+    pub number: usize,
+    pub location: String,
+    pub hits: u64,
+}
+
+/// Breakpoints that were hit at least once in the order of their numbers. Breakpoints with
+/// multiple locations are listed once (they share the count).
+pub fn breakpoint_hits(breakpoints: &BreakPointSet) -> Vec<BreakpointHits> {
+    let mut hits = breakpoints
+        .values()
+        .filter(|bp| bp.hit_count > 0 && bp.number.minor.is_none())
+        .map(|bp| BreakpointHits {
+            number: bp.number.major,
+            location: match (&bp.src_pos, bp.address) {
+                (Some(pos), _) => format!("{}:{}", pos.file.display(), pos.line),
+                (None, Some(address)) => address.to_string(),
+                (None, None) => String::new(),
+            },
+            hits: bp.hit_count,
+        })
+        .collect::<Vec<_>>();
+    hits.sort_by_key(|h| h.number);
+    hits
+}
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, s) => format!("{}h {}m {}s", h, m, s),
+    }
+}
+
+pub struct SessionSummary<'a> {
+    pub program: Option<&'a Path>,
+    /// When the session ended (local time).
+    pub ended: String,
+    pub stats: &'a SessionStats,
+    pub breakpoints: Vec<BreakpointHits>,
+    /// Expressions with their last successfully evaluated value.
+    pub expressions: Vec<(String, Option<String>)>,
+}
+
+impl<'a> SessionSummary<'a> {
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl<'a> fmt::Display for SessionSummary<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ugdb session summary")?;
+        if let Some(program) = self.program {
+            writeln!(f, "Program: {}", program.display())?;
+        }
+        writeln!(f, "Ended: {}", self.ended)?;
+        let stats = self.stats;
+        writeln!(
+            f,
+            "Running: {}, stopped: {}, {} stop(s)",
+            format_duration(stats.running),
+            format_duration(stats.stopped),
+            stats.stops
+        )?;
+
+        writeln!(f, "\nBreakpoints hit:")?;
+        if self.breakpoints.is_empty() {
+            writeln!(f, "  none")?;
+        }
+        for bp in &self.breakpoints {
+            writeln!(
+                f,
+                "  {:<3} {:<40} {} hit(s)",
+                bp.number, bp.location, bp.hits
+            )?;
+        }
+
+        writeln!(f, "\nLast backtrace:")?;
+        if stats.last_backtrace.is_empty() {
+            writeln!(f, "  none")?;
+        }
+        for frame in &stats.last_backtrace {
+            writeln!(
+                f,
+                "  #{:<3} {} {}",
+                frame.level.map(|l| l.to_string()).unwrap_or_default(),
+                frame.func.as_deref().unwrap_or("??"),
+                frame_location(frame)
+            )?;
+        }
+
+        writeln!(f, "\nExpressions:")?;
+        if self.expressions.is_empty() {
+            writeln!(f, "  none")?;
+        }
+        for (expression, value) in &self.expressions {
+            writeln!(
+                f,
+                "  {} = {}",
+                expression,
+                value.as_deref().unwrap_or("<not available>")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn running_time_and_summary() {
+        let start = Instant::now();
+        let mut stats = SessionStats::new(start);
+        stats.update(true, start + Duration::from_secs(5));
+        stats.update(false, start + Duration::from_secs(65));
+        stats.update(false, start + Duration::from_secs(3700));
+        stats.count_stop();
+        assert_eq!(stats.running, Duration::from_secs(60));
+        assert_eq!(stats.stopped, Duration::from_secs(3640));
+
+        stats.last_backtrace = vec![Frame {
+            level: Some(0),
+            func: Some("main".to_owned()),
+            file: Some("main.c".to_owned()),
+            line: Some(12),
+            ..Frame::default()
+        }];
+        let summary = SessionSummary {
+            program: Some(Path::new("/tmp/a.out")),
+            ended: "today".to_owned(),
+            stats: &stats,
+            breakpoints: vec![BreakpointHits {
+                number: 1,
+                location: "main.c:12".to_owned(),
+                hits: 3,
+            }],
+            expressions: vec![
+                ("x".to_owned(), Some("42".to_owned())),
+                ("y".to_owned(), None),
+            ],
+        }
+        .to_string();
+        assert!(summary.contains("Program: /tmp/a.out\n"));
+        assert!(summary.contains("Running: 1m 0s, stopped: 1h 0m 40s, 1 stop(s)\n"));
+        assert!(summary.contains("main.c:12"));
+        assert!(summary.contains("3 hit(s)\n"));
+        assert!(summary.contains("  #0   main main.c:12\n"));
+        assert!(summary.contains("  x = 42\n  y = <not available>\n"));
+    }
+}
@@ -0,0 +1,162 @@
+use gdb::SrcPosition;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use unsegen::base::LineNumber;
+
+#[derive(Clone, Debug, PartialEq)]
+enum TagAddress {
+    Line(LineNumber),
+    Pattern(String),
+}
+
+#[derive(Debug, PartialEq)]
+struct Tag<'a> {
+    name: &'a str,
+    file: &'a str,
+    address: TagAddress,
+}
+
+// Parse a single line of a ctags file (name<TAB>file<TAB>address[;"<TAB>extension fields]).
+fn parse_tag_line(line: &str) -> Option<Tag<'_>> {
+    if line.starts_with("!_TAG_") {
+        return None;
+    }
+    let mut fields = line.splitn(3, '\t');
+    let name = fields.next()?;
+    let file = fields.next()?;
+    let address = fields.next()?;
+    let address = address.split(";\"").next().unwrap_or(address);
+    let address = if let Ok(n) = address.trim().parse::<usize>() {
+        if n == 0 {
+            return None;
+        }
+        TagAddress::Line(LineNumber::new(n))
+    } else {
+        let pattern = address.trim();
+        let pattern = pattern
+            .strip_prefix('/')
+            .and_then(|p| p.strip_suffix('/'))
+            .or_else(|| pattern.strip_prefix('?').and_then(|p| p.strip_suffix('?')))?;
+        let pattern = pattern.strip_prefix('^').unwrap_or(pattern);
+        let pattern = pattern.strip_suffix('$').unwrap_or(pattern);
+        TagAddress::Pattern(pattern.replace("\\/", "/").replace("\\\\", "\\"))
+    };
+    Some(Tag {
+        name,
+        file,
+        address,
+    })
+}
+
+fn find_pattern_in_file(file: &Path, pattern: &str) -> Option<LineNumber> {
+    let content = fs::read_to_string(file).ok()?;
+    content
+        .lines()
+        .position(|l| l.contains(pattern))
+        .map(|i| LineNumber::new(i + 1))
+}
+
+/// The definitions of a ctags file. The file is parsed once and again only if it was modified.
+pub struct Tags {
+    file: PathBuf,
+    modified: Option<SystemTime>, // Of the parsed file
+    definitions: HashMap<String, Vec<(PathBuf, TagAddress)>>,
+}
+
+impl Tags {
+    pub fn new(file: PathBuf) -> Self {
+        Tags {
+            file,
+            modified: None,
+            definitions: HashMap::new(),
+        }
+    }
+
+    // Parse the file (again) if it was modified since it was parsed. File paths in the tags file
+    // are interpreted relative to the directory containing it.
+    fn reload_if_modified(&mut self) {
+        let modified = fs::metadata(&self.file).and_then(|m| m.modified()).ok();
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        self.definitions.clear();
+        let content = match fs::read_to_string(&self.file) {
+            Ok(content) => content,
+            Err(_) => return,
+        };
+        let base_dir = self.file.parent().unwrap_or(Path::new("."));
+        for tag in content.lines().filter_map(parse_tag_line) {
+            self.definitions
+                .entry(tag.name.to_owned())
+                .or_default()
+                .push((base_dir.join(tag.file), tag.address));
+        }
+    }
+
+    /// Look up the definition of the symbol.
+    pub fn find_definition(&mut self, symbol: &str) -> Option<SrcPosition> {
+        self.reload_if_modified();
+        self.definitions
+            .get(symbol)?
+            .iter()
+            .find_map(|(file, address)| {
+                let line = match address {
+                    TagAddress::Line(l) => Some(*l),
+                    TagAddress::Pattern(p) => find_pattern_in_file(file, p),
+                };
+                line.map(|line| SrcPosition::new(file.clone(), line))
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_pattern_tag() {
+        assert_eq!(
+            parse_tag_line("main\tsrc/main.c\t/^int main(int argc, char\\/* argv)$/;\"\tf"),
+            Some(Tag {
+                name: "main",
+                file: "src/main.c",
+                address: TagAddress::Pattern("int main(int argc, char/* argv)".to_owned()),
+            })
+        );
+    }
+    #[test]
+    fn parse_line_tag() {
+        assert_eq!(
+            parse_tag_line("foo\tfoo.rs\t42;\"\tf"),
+            Some(Tag {
+                name: "foo",
+                file: "foo.rs",
+                address: TagAddress::Line(LineNumber::new(42)),
+            })
+        );
+    }
+    #[test]
+    fn find_definition() {
+        let dir = std::env::temp_dir().join(format!("ugdb-tags-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.c"), "int x;\nint foo(void) {\n").unwrap();
+        fs::write(dir.join("tags"), "foo\ta.c\t/^int foo(void) {$/;\"\tf\n").unwrap();
+        let mut tags = Tags::new(dir.join("tags"));
+        assert_eq!(
+            tags.find_definition("foo"),
+            Some(SrcPosition::new(dir.join("a.c"), LineNumber::new(2)))
+        );
+        assert_eq!(tags.find_definition("bar"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn parse_pseudo_tag() {
+        assert_eq!(
+            parse_tag_line("!_TAG_FILE_FORMAT\t2\t/extended format/"),
+            None
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use super::event_bus::{DebugEvent, Subscriber};
+use super::list_filter::FilterableRow;
+use super::list_view::ListView;
+use gdb::{Address, MAX_BACKTRACE_FRAMES};
+use gdbmi::types::Frame;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::{Column, TableRow};
+use unsegen::widget::Widget;
+
+/// "file:line" of the frame, or the shared library if there is no debug information.
+pub fn frame_location(frame: &Frame) -> String {
+    match (&frame.file, frame.line, &frame.from) {
+        (Some(file), Some(line), _) => format!("{}:{}", file, line),
+        (_, _, Some(from)) => from.clone(),
+        _ => String::new(),
+    }
+}
+
+#[derive(Clone)]
+pub struct FrameRow {
+    level: Option<u64>,
+    level_str: String,
+    address: String,
+    function: String,
+    location: String,
+}
+
+impl TableRow for FrameRow {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<FrameRow>] = &[
+        Column {
+            access: |r| Box::new(r.level_str.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.address.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.function.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.location.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+    ];
+}
+
+impl FilterableRow for FrameRow {
+    fn cell_text(&self, column: usize) -> &str {
+        match column {
+            0 => &self.level_str,
+            1 => &self.address,
+            2 => &self.function,
+            _ => &self.location,
+        }
+    }
+}
+
+/// Frames of the current thread. The list is updated whenever the program stops or another
+/// thread or frame is selected.
+pub struct BacktraceView {
+    list: ListView<FrameRow>,
+}
+
+impl BacktraceView {
+    pub fn new() -> Self {
+        BacktraceView {
+            list: ListView::new("No backtrace loaded."),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.list.lines()
+    }
+
+    pub fn refresh(&mut self, p: &mut ::Context) {
+        let format_address = p.address_formatter();
+        match p.gdb.snapshot.backtrace {
+            Ok(ref frames) => {
+                self.list
+                    .set_header(if frames.len() as u64 == MAX_BACKTRACE_FRAMES {
+                        format!("{} innermost frames", frames.len())
+                    } else {
+                        format!("{} frames", frames.len())
+                    });
+                let rows = frames
+                    .iter()
+                    .map(|f| FrameRow {
+                        level: f.level,
+                        level_str: f.level.map(|l| l.to_string()).unwrap_or_default(),
+                        address: f
+                            .addr
+                            .map(|a| format_address(Address(a)))
+                            .unwrap_or_default(),
+                        function: f.func.clone().unwrap_or_else(|| "??".to_owned()),
+                        location: frame_location(f),
+                    })
+                    .collect();
+                self.list.set_rows(rows);
+            }
+            Err(ref e) => {
+                self.list.set_header(format!("No backtrace: {:?}", e));
+                self.list.set_rows(Vec::new());
+            }
+        }
+    }
+
+    fn select_frame(row: Option<&FrameRow>, p: &mut ::Context) {
+        if let Some(level) = row.and_then(|r| r.level) {
+            match p.gdb.select_frame(level) {
+                Ok(record) => p.report_selection(record),
+                Err(e) => p.log(format!("Failed to select frame {}: {:?}", level, e)),
+            }
+        }
+    }
+}
+
+impl Subscriber for BacktraceView {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        if let DebugEvent::Stopped(_) = event {
+            self.refresh(p);
+        }
+    }
+}
+
+impl Container<::Context> for BacktraceView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        self.list.input(input, |row, input| {
+            input
+                .chain((Key::Char('\n'), || Self::select_frame(row, p)))
+                .finish()
+        })
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        self.list.as_widget()
+    }
+}
@@ -0,0 +1,312 @@
+// Arrows that connect branch instructions in the assembly view with their targets, e.g.:
+//
+//   ┌─ jne    0x1150 <main+23>
+//   │  mov    $0x1,%eax
+//   └> add    $0x2,%eax
+use gdb::Address;
+use std::collections::HashMap;
+
+// Arrows are drawn in separate columns ("lanes") if their ranges overlap. Arrows that do not fit
+// into any of the lanes are not drawn.
+const MAX_LANES: usize = 6;
+
+fn is_branch(mnemonic: &str) -> bool {
+    // x86 (jmp, jne, ...; loop, loopne, ...)
+    mnemonic.starts_with('j') || mnemonic.starts_with("loop")
+        // arm/aarch64 (b, b.ne, bne, cbz, tbnz, ...). Branches with link are calls.
+        || mnemonic == "b"
+        || mnemonic.starts_with("b.")
+        || (mnemonic.len() == 3
+            && mnemonic.starts_with('b')
+            && ["eq", "ne", "cs", "hs", "cc", "lo", "mi", "pl", "vs", "vc", "hi", "ls", "ge",
+                "lt", "gt", "le"]
+                .contains(&&mnemonic[1..]))
+        || ["cbz", "cbnz", "tbz", "tbnz"].contains(&mnemonic)
+}
+
+/// The target of a (direct) branch instruction as disassembled by gdb, e.g., 0x1150 for
+/// "jne    0x1150 <main+23>".
+pub fn branch_target(instruction: &str) -> Option<Address> {
+    let mut words = without_comment(instruction).split_whitespace();
+    let mnemonic = words.next()?;
+    if !is_branch(mnemonic) {
+        return None;
+    }
+    words
+        .flat_map(|w| w.split(','))
+        .find(|w| w.starts_with("0x"))
+        .and_then(|w| Address::parse(w).ok())
+}
+
+// gdb appends comments, e.g., the address of the slot of an indirect jump: "jmp *0x2fe2(%rip)
+// # 0x4018". ("#" without a space is an immediate on arm.)
+fn without_comment(instruction: &str) -> &str {
+    let end = ["# ", "//"]
+        .iter()
+        .filter_map(|c| instruction.find(c))
+        .min()
+        .unwrap_or(instruction.len());
+    &instruction[..end]
+}
+
+fn is_call(mnemonic: &str) -> bool {
+    // x86 (call, callq, ...), arm/aarch64 (bl, blx, blr)
+    mnemonic.starts_with("call") || ["bl", "blx", "blr"].contains(&mnemonic)
+}
+
+/// The function called by a call instruction as disassembled by gdb, e.g., "puts@plt" for
+/// "call   0x1030 <puts@plt>". Indirect calls result in their operand, e.g., "*%rax".
+pub fn call_target(instruction: &str) -> Option<String> {
+    let instruction = without_comment(instruction);
+    let mut words = instruction.split_whitespace();
+    if !is_call(words.next()?) {
+        return None;
+    }
+    if let (Some(begin), Some(end)) = (instruction.find('<'), instruction.rfind('>')) {
+        if begin < end {
+            let symbol = &instruction[begin + 1..end];
+            // Calls into the middle of a function, e.g., "<f+16>"
+            let symbol = match symbol.rsplit_once('+') {
+                Some((function, offset))
+                    if !offset.is_empty() && offset.bytes().all(|b| b.is_ascii_digit()) =>
+                {
+                    function
+                }
+                _ => symbol,
+            };
+            return Some(symbol.to_owned());
+        }
+    }
+    words.next().map(|operand| operand.to_owned())
+}
+
+/// The operand of a jump or call instruction as an expression that gdb can evaluate to the target
+/// address, e.g., "0x1030" for "call   0x1030 <puts@plt>" or "$rax" for "jmp    *%rax".
+/// Memory operands (e.g., "jmp    *0x2fe2(%rip)") are not supported.
+pub fn jump_operand(instruction: &str) -> Option<String> {
+    if let Some(target) = branch_target(instruction) {
+        return Some(target.to_string());
+    }
+    let mut words = without_comment(instruction).split_whitespace();
+    let mnemonic = words.next()?;
+    if !is_branch(mnemonic) && !is_call(mnemonic) && mnemonic != "br" {
+        return None;
+    }
+    let operand = words.next()?;
+    if operand.starts_with("0x") {
+        // e.g., "bl\t0x400450 <puts@plt>"
+        return Address::parse(operand).ok().map(|a| a.to_string());
+    }
+    // Register operands: "*%rax" (AT&T), "rax" (Intel), "x16" (arm)
+    let register = operand.trim_start_matches('*').trim_start_matches('%');
+    if !register.is_empty() && register.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Some(format!("${}", register))
+    } else {
+        None
+    }
+}
+
+struct Arrow {
+    from: usize, // Indices of the lines
+    to: usize,
+    lane: usize,
+}
+
+impl Arrow {
+    fn first(&self) -> usize {
+        self.from.min(self.to)
+    }
+    fn last(&self) -> usize {
+        self.from.max(self.to)
+    }
+}
+
+#[derive(Default)]
+pub struct BranchArrows {
+    cells: HashMap<Address, String>,
+    width: usize,
+}
+
+impl BranchArrows {
+    /// Arrows for all branches between the instructions (which must be sorted by address).
+    pub fn new<'a>(instructions: impl Iterator<Item = (Address, &'a str)>) -> Self {
+        let instructions = instructions.collect::<Vec<_>>();
+        let index_of = instructions
+            .iter()
+            .enumerate()
+            .map(|(i, &(address, _))| (address, i))
+            .collect::<HashMap<_, _>>();
+        let mut arrows = instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(from, &(_, instruction))| {
+                let to = *index_of.get(&branch_target(instruction)?)?;
+                if from == to {
+                    None
+                } else {
+                    Some(Arrow { from, to, lane: 0 })
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Short arrows are drawn closest to the instructions.
+        arrows.sort_by_key(|a| a.last() - a.first());
+        let mut lanes: Vec<Vec<(usize, usize)>> = vec![Vec::new(); MAX_LANES];
+        let mut placed = Vec::new();
+        for mut arrow in arrows {
+            let free_lane = lanes.iter().position(|ranges| {
+                ranges
+                    .iter()
+                    .all(|&(first, last)| arrow.last() < first || last < arrow.first())
+            });
+            if let Some(lane) = free_lane {
+                lanes[lane].push((arrow.first(), arrow.last()));
+                arrow.lane = lane;
+                placed.push(arrow);
+            }
+        }
+        let num_lanes = placed.iter().map(|a| a.lane + 1).max().unwrap_or(0);
+        if num_lanes == 0 {
+            return BranchArrows::default();
+        }
+
+        let mut rows = vec![vec![' '; num_lanes + 1]; instructions.len()];
+        for arrow in &placed {
+            let column = num_lanes - 1 - arrow.lane;
+            for (i, row) in rows
+                .iter_mut()
+                .enumerate()
+                .take(arrow.last() + 1)
+                .skip(arrow.first())
+            {
+                if i == arrow.first() || i == arrow.last() {
+                    row[column] = if i == arrow.first() { '┌' } else { '└' };
+                    for cell in &mut row[column + 1..num_lanes] {
+                        if *cell == ' ' {
+                            *cell = '─';
+                        }
+                    }
+                    if i == arrow.to {
+                        row[num_lanes] = '>';
+                    } else if row[num_lanes] == ' ' {
+                        row[num_lanes] = '─';
+                    }
+                } else {
+                    row[column] = '│';
+                }
+            }
+        }
+        BranchArrows {
+            cells: instructions
+                .iter()
+                .zip(rows)
+                .map(|(&(address, _), row)| (address, row.into_iter().collect()))
+                .collect(),
+            width: num_lanes + 1,
+        }
+    }
+
+    /// Number of columns needed to draw the arrows.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The part of the arrows next to the instruction at the address.
+    pub fn cells(&self, address: Address) -> Option<&str> {
+        self.cells.get(&address).map(|c| c.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_branch_target() {
+        assert_eq!(
+            branch_target("jne    0x1150 <main+23>"),
+            Some(Address(0x1150))
+        );
+        assert_eq!(branch_target("jmp    *%rax"), None);
+        assert_eq!(branch_target("call   0x1030 <puts@plt>"), None);
+        assert_eq!(branch_target("mov    0x10(%rbp),%eax"), None);
+        assert_eq!(
+            branch_target("b.ne\t0x4005d0 <main+16>  // b.any"),
+            Some(Address(0x4005d0))
+        );
+        assert_eq!(
+            branch_target("cbz\tw0, 0x400600 <main+64>"),
+            Some(Address(0x400600))
+        );
+        assert_eq!(branch_target("bl\t0x400450 <puts@plt>"), None);
+        assert_eq!(branch_target("jmp    *0x2fe2(%rip)        # 0x4018"), None);
+        assert_eq!(
+            branch_target("tbz\tw0, #0x3, 0x400600 <main+64>"),
+            Some(Address(0x400600))
+        );
+    }
+
+    #[test]
+    fn test_jump_operand() {
+        let operand = |i| jump_operand(i);
+        assert_eq!(
+            operand("jne    0x1150 <main+23>"),
+            Some("0x1150".to_owned())
+        );
+        assert_eq!(
+            operand("call   0x1030 <puts@plt>"),
+            Some("0x1030".to_owned())
+        );
+        assert_eq!(
+            operand("bl\t0x400450 <puts@plt>"),
+            Some("0x400450".to_owned())
+        );
+        assert_eq!(operand("jmp    *%rax"), Some("$rax".to_owned()));
+        assert_eq!(operand("call   rdx"), Some("$rdx".to_owned()));
+        assert_eq!(operand("br\tx16"), Some("$x16".to_owned()));
+        assert_eq!(operand("jmp    *0x2fe2(%rip)        # 0x4018"), None);
+        assert_eq!(operand("mov    %rsp,%rbp"), None);
+        assert_eq!(operand("ret"), None);
+    }
+
+    #[test]
+    fn test_call_target() {
+        assert_eq!(
+            call_target("call   0x1030 <puts@plt>"),
+            Some("puts@plt".to_owned())
+        );
+        assert_eq!(
+            call_target("bl\t0x400450 <helper+16>"),
+            Some("helper".to_owned())
+        );
+        assert_eq!(
+            call_target("call   0x1160 <std::vector<int>::operator+>"),
+            Some("std::vector<int>::operator+".to_owned())
+        );
+        assert_eq!(call_target("call   *%rax"), Some("*%rax".to_owned()));
+        assert_eq!(call_target("jne    0x1150 <main+23>"), None);
+    }
+
+    #[test]
+    fn test_arrows() {
+        let instructions = [
+            (Address(0), "jne    0x3 <f+3>"),
+            (Address(1), "jmp    0x2 <f+2>"),
+            (Address(2), "nop"),
+            (Address(3), "nop"),
+            (Address(4), "jmp    0x100 <g>"),
+        ];
+        let arrows = BranchArrows::new(instructions.iter().cloned());
+        let cells = (0..5)
+            .map(|i| arrows.cells(Address(i)).unwrap())
+            .collect::<Vec<_>>();
+        // The shorter arrow is drawn closer to the instructions.
+        assert_eq!(arrows.width(), 3);
+        assert_eq!(cells, vec!["┌──", "│┌─", "│└>", "└─>", "   "]);
+
+        assert_eq!(
+            BranchArrows::new([(Address(0), "nop")].iter().cloned()).width(),
+            0
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use super::event_bus::{DebugEvent, Subscriber};
+use super::list_filter::FilterableRow;
+use super::list_view::ListView;
+use gdb::{Address, BreakPointSet, BreakpointOperationError, SrcPosition};
+use tui::overlay::{Prompt, TextEditor};
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::{Column, TableRow};
+use unsegen::widget::Widget;
+
+#[derive(Clone)]
+pub struct BreakpointRow {
+    number: String,
+    enabled: &'static str,
+    address: String,
+    location: String,
+    hits: String,
+    ignore: String,
+    commands: String,
+    command_lines: Vec<String>,
+    src_pos: Option<SrcPosition>,
+    major: usize,
+    ignore_count: u64,
+}
+
+impl TableRow for BreakpointRow {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<BreakpointRow>] = &[
+        Column {
+            access: |r| Box::new(r.number.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.enabled),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.address.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.location.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.hits.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.ignore.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.commands.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+    ];
+}
+
+impl FilterableRow for BreakpointRow {
+    fn cell_text(&self, column: usize) -> &str {
+        match column {
+            0 => &self.number,
+            1 => self.enabled,
+            2 => &self.address,
+            3 => &self.location,
+            4 => &self.hits,
+            5 => &self.ignore,
+            _ => &self.commands,
+        }
+    }
+}
+
+/// List of all breakpoints (and their locations) known to gdb.
+pub struct BreakpointsView {
+    list: ListView<BreakpointRow>,
+}
+
+impl BreakpointsView {
+    pub fn new() -> Self {
+        BreakpointsView {
+            list: ListView::new("0 breakpoint(s)"),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.list.lines()
+    }
+
+    fn set_breakpoints(
+        &mut self,
+        breakpoints: &BreakPointSet,
+        format_address: impl Fn(Address) -> String,
+    ) {
+        let mut breakpoints = breakpoints.values().collect::<Vec<_>>();
+        breakpoints.sort_by_key(|bp| (bp.number.major, bp.number.minor));
+        let rows = breakpoints
+            .into_iter()
+            .map(|bp| BreakpointRow {
+                number: bp.number.to_string(),
+                enabled: if bp.enabled { "y" } else { "n" },
+                address: bp.address.map(&format_address).unwrap_or_default(),
+                location: bp
+                    .src_pos
+                    .as_ref()
+                    .map(|pos| format!("{}:{}", pos.file.display(), pos.line))
+                    .unwrap_or_default(),
+                // Locations share the counts of their breakpoint.
+                hits: if bp.number.minor.is_none() {
+                    format!("{} hits", bp.hit_count)
+                } else {
+                    String::new()
+                },
+                ignore: if bp.number.minor.is_none() && bp.ignore_count > 0 {
+                    format!("ignore next {}", bp.ignore_count)
+                } else {
+                    String::new()
+                },
+                commands: if bp.number.minor.is_none() {
+                    bp.commands.join(COMMAND_SEPARATOR)
+                } else {
+                    String::new()
+                },
+                command_lines: bp.commands.clone(),
+                src_pos: bp.src_pos.clone(),
+                major: bp.number.major,
+                ignore_count: bp.ignore_count,
+            })
+            .collect::<Vec<_>>();
+        self.list
+            .set_header(format!("{} breakpoint(s)", rows.len()));
+        self.list.set_rows(rows);
+    }
+
+    fn show_breakpoint(row: Option<&BreakpointRow>, p: &mut ::Context) {
+        if let Some(pos) = row.and_then(|r| r.src_pos.as_ref()) {
+            p.show_file(pos.file.to_string_lossy().into_owned(), pos.line);
+            p.focus_container(::tui::TuiContainerType::SrcView);
+        }
+    }
+}
+
+/// Ask for the number of upcoming hits of the breakpoint that gdb should ignore.
+pub fn prompt_ignore_count(major: usize, current: u64, p: &mut ::Context) {
+    p.show_overlay(Prompt::new(
+        format!("Ignore next hits of breakpoint {}", major),
+        &current.to_string(),
+        move |count: String, p: &mut ::Context| match count.trim().parse::<u64>() {
+            Ok(count) => match p.gdb.set_ignore_count(major, count) {
+                Ok(()) => {}
+                Err(BreakpointOperationError::Busy) => {
+                    p.log("Cannot set ignore count: Gdb is busy.");
+                }
+                Err(BreakpointOperationError::ExecutionError(msg)) => {
+                    p.log(format!("Cannot set ignore count: {}", msg));
+                }
+                Err(BreakpointOperationError::Quit(e)) => {
+                    p.log(format!("Cannot set ignore count: {}", e));
+                }
+            },
+            Err(_) => p.log(format!("Not a count: {}", count)),
+        },
+    ));
+}
+
+// Separates the commands of a breakpoint when they are shown in a single line.
+const COMMAND_SEPARATOR: &str = "; ";
+
+// Commands entered in the editor, one per line. Blank lines are ignored.
+fn clean_commands(lines: Vec<String>) -> Vec<String> {
+    lines
+        .iter()
+        .map(|l| l.trim())
+        .filter(|c| !c.is_empty())
+        .map(|c| c.to_owned())
+        .collect()
+}
+
+/// Edit the commands that gdb executes whenever the breakpoint is hit (one per line). Removing all
+/// lines removes all commands.
+fn edit_commands(major: usize, current: &[String], p: &mut ::Context) {
+    p.show_overlay(TextEditor::new(
+        format!("Commands of breakpoint {} (one per line)", major),
+        current,
+        move |lines: Vec<String>, p: &mut ::Context| match p
+            .gdb
+            .set_breakpoint_commands(major, clean_commands(lines))
+        {
+            Ok(()) => {}
+            Err(BreakpointOperationError::Busy) => {
+                p.log("Cannot set commands: Gdb is busy.");
+            }
+            Err(BreakpointOperationError::ExecutionError(msg)) => {
+                p.log(format!("Cannot set commands: {}", msg));
+            }
+            Err(BreakpointOperationError::Quit(e)) => {
+                p.log(format!("Cannot set commands: {}", e));
+            }
+        },
+    ));
+}
+
+impl Subscriber for BreakpointsView {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        if let DebugEvent::BreakpointsChanged = event {
+            let format_address = p.address_formatter();
+            self.set_breakpoints(&p.gdb.breakpoints, format_address);
+        }
+    }
+}
+
+impl Container<::Context> for BreakpointsView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        self.list.input(input, |row, input| {
+            input
+                .chain((Key::Char('\n'), || Self::show_breakpoint(row, p)))
+                .chain((Key::Char('i'), || {
+                    if let Some(row) = row {
+                        prompt_ignore_count(row.major, row.ignore_count, p);
+                    }
+                }))
+                .chain((Key::Char('c'), || {
+                    if let Some(row) = row {
+                        edit_commands(row.major, &row.command_lines, p);
+                    }
+                }))
+                .finish()
+        })
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        self.list.as_widget()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_clean_commands() {
+        let lines = |l: &[&str]| l.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            clean_commands(lines(&["print x", "continue"])),
+            vec!["print x", "continue"]
+        );
+        assert_eq!(
+            clean_commands(lines(&[" silent", "", "printf \"%d;\", y ", ""])),
+            vec!["silent", "printf \"%d;\", y"]
+        );
+        assert!(clean_commands(lines(&["  "])).is_empty());
+    }
+}
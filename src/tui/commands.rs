@@ -12,15 +12,6 @@ impl Command {
     fn new(cmd: Box<dyn FnMut(&mut ::Context) -> Result<(), ExecuteError>>) -> Command {
         Command { cmd: cmd }
     }
-    fn from_mi_with_msg(cmd: MiCommand, success_msg: &'static str) -> Command {
-        Command::new(Box::new(move |p: &mut ::Context| {
-            let res = p.gdb.mi.execute(cmd.clone()).map(|_| ());
-            if res.is_ok() {
-                p.log(success_msg);
-            }
-            res
-        }))
-    }
     fn from_mi(cmd: MiCommand) -> Command {
         Command::new(Box::new(move |p: &mut ::Context| {
             p.gdb.mi.execute(cmd.clone()).map(|_| ())
@@ -59,8 +50,9 @@ impl CommandState {
 
     fn print_execute_error(e: ExecuteError, p: &mut ::Context) {
         match e {
-            ExecuteError::Quit => p.log("quit"),
+            ExecuteError::Quit(e) => p.log(e.to_string()),
             ExecuteError::Busy => p.log("GDB is running!"),
+            ExecuteError::Timeout => p.log("GDB did not respond in time."),
         }
     }
 
@@ -102,41 +94,6 @@ impl CommandState {
         let args_begin = (cmd_end + 1).min(line.len());
         let args_str = &line[args_begin..];
         match cmd {
-            "!stop" => {
-                p.gdb.mi.interrupt_execution().expect("interrupted gdb");
-                // This does not always seem to unblock gdb, but only hang it
-                //gdb.execute(&MiCommand::exec_interrupt()).expect("Interrupt");
-
-                CommandState::Idle
-            }
-            "!layout" => {
-                p.try_change_layout(args_str.to_owned());
-
-                CommandState::Idle
-            }
-            "!show" => {
-                p.show_file(args_str.to_owned(), unsegen::base::LineNumber::new(1));
-
-                CommandState::Idle
-            }
-            "!reload" => match p.gdb.get_target() {
-                Ok(Some(target)) => Self::ask_if_session_active(
-                    Command::from_mi_with_msg(
-                        MiCommand::file_exec_and_symbols(&target),
-                        "Reloaded target.",
-                    ),
-                    "Reload anyway?",
-                    p,
-                ),
-                Ok(None) => {
-                    p.log("No target. Use the 'file' command to specify one.");
-                    CommandState::Idle
-                }
-                Err(e) => {
-                    Self::print_execute_error(e, p);
-                    CommandState::Idle
-                }
-            },
             "shell" => {
                 // This command does not work, because gdb breaks the gdbmi protocol (because it
                 // likely just gives up stdout to the shell process until it terminates). This
@@ -145,9 +102,27 @@ impl CommandState {
                 p.log("The `shell` command is not supported in ugdb. Consider suspending the process with Ctrl-z instead.");
                 CommandState::Idle
             }
+            _ if cmd.starts_with('!') => {
+                match ::actions::find(&cmd[1..]) {
+                    Some(action) => action.run(args_str, p),
+                    None => p.log(format!(
+                        "Unknown command '{}'. Use !help to list all commands.",
+                        cmd
+                    )),
+                }
+                CommandState::Idle
+            }
+            "q" | "quit" if p.gdb_terminated() || p.gdb.mi.is_external() => {
+                p.quit();
+                CommandState::Idle
+            }
             "q" => {
                 Self::ask_if_session_active(Command::from_mi(MiCommand::exit()), "Quit anyway?", p)
             }
+            "load" => {
+                p.start_download(line);
+                CommandState::Idle
+            }
             // Gdb commands
             _ => {
                 match p.gdb.mi.execute(MiCommand::cli_exec(line)) {
@@ -1,10 +1,11 @@
 use tui::commands::CommandState;
 
 use unsegen::base::GraphemeCluster;
+use unsegen::base::StyleModifier;
 use unsegen::container::Container;
 use unsegen::input::{EditBehavior, Input, Key, ScrollBehavior};
 use unsegen::widget::builtin::{LogViewer, PromptLine};
-use unsegen::widget::{VLayout, Widget};
+use unsegen::widget::{Demand, Demand2D, VLayout, Widget, WidgetExt};
 
 use completion::{CmdlineCompleter, Completer, CompletionState};
 
@@ -20,6 +21,7 @@ pub struct Console {
     last_gdb_state: GDBState,
     command_state: CommandState,
     completion_state: Option<CompletionState>,
+    transcript: Option<String>, // Text written to the log that has not been taken yet
 }
 
 static STOPPED_PROMPT: &'static str = "(gdb) ";
@@ -38,21 +40,52 @@ impl Console {
             last_gdb_state: GDBState::Stopped,
             command_state: CommandState::Idle,
             completion_state: None,
+            transcript: None,
         }
     }
 
     pub fn write_to_gdb_log<S: AsRef<str>>(&mut self, msg: S) {
         use std::fmt::Write;
+        if let Some(transcript) = &mut self.transcript {
+            transcript.push_str(msg.as_ref());
+        }
         write!(self.gdb_log, "{}", msg.as_ref()).expect("Write Message");
     }
 
+    /// Collect the text that is written to the log from now on, see `take_transcript`.
+    pub fn record_transcript(&mut self) {
+        self.transcript = Some(String::new());
+    }
+
+    /// Text that was written to the log since the last call, if it is recorded.
+    pub fn take_transcript(&mut self) -> String {
+        self.transcript
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Insert text at the cursor position of the prompt line.
+    pub fn insert_into_prompt(&mut self, text: &str) {
+        let line = self.prompt_line.active_line().to_owned();
+        let pos = self.prompt_line.cursor_pos();
+        self.prompt_line
+            .set(format!("{}{}{}", &line[..pos], text, &line[pos..]));
+        let _ = self.prompt_line.set_cursor_pos(pos + text.len());
+    }
+
     fn handle_newline(&mut self, p: &mut ::Context) {
         let line = if self.prompt_line.active_line().is_empty() {
             self.prompt_line.previous_line(1).unwrap_or("").to_owned()
         } else {
             self.prompt_line.finish_line().to_owned()
         };
-        self.write_to_gdb_log(format!("{}{}\n", STOPPED_PROMPT, line));
+        // Not part of the transcript, which is read in linear mode where the terminal echoes the
+        // command anyway.
+        {
+            use std::fmt::Write;
+            writeln!(self.gdb_log, "{}{}", STOPPED_PROMPT, line).expect("Write Message");
+        }
         self.command_state.handle_input_line(&line, p);
     }
     pub fn update_after_event(&mut self, p: &mut ::Context) {
@@ -139,11 +172,41 @@ impl Container<::Context> for Console {
         }
     }
     fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
-        Box::new(
-            VLayout::new()
-                .separator(GraphemeCluster::try_from('=').unwrap())
-                .widget(self.gdb_log.as_widget())
-                .widget(self.prompt_line.as_widget()),
-        )
+        let mut layout = VLayout::new()
+            .separator(GraphemeCluster::try_from('=').unwrap())
+            .widget(self.gdb_log.as_widget());
+        if let Some(completion) = self
+            .completion_state
+            .as_ref()
+            .filter(|c| c.num_candidates() > 1)
+        {
+            // Show all candidates (the selected one in brackets) when there is a choice.
+            let candidates = completion
+                .option_words()
+                .map(
+                    |(selected, w)| {
+                        if selected {
+                            format!("[{}]", w)
+                        } else {
+                            w
+                        }
+                    },
+                )
+                .collect::<Vec<_>>()
+                .join("  ");
+            layout = layout.widget(
+                candidates
+                    .with_demand(|d| Demand2D {
+                        width: Demand::at_least(0),
+                        height: d.height,
+                    })
+                    .with_window(|w, _| {
+                        let mut w = w;
+                        w.modify_default_style(StyleModifier::new().bold(true));
+                        w
+                    }),
+            );
+        }
+        Box::new(layout.widget(self.prompt_line.as_widget()))
     }
 }
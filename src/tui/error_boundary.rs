@@ -0,0 +1,185 @@
+use super::event_bus::{DebugEvent, Subscriber};
+use log::error;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::ops::{Deref, DerefMut};
+use std::panic::{self, AssertUnwindSafe};
+use unsegen::base::Window;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::{Demand2D, RenderingHints, Widget};
+
+thread_local! {
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether a panic happens inside an error boundary (and will thus be caught). The panic hook only
+/// logs those instead of tearing down the whole tui.
+pub fn is_catching() -> bool {
+    CATCHING.with(|c| c.get())
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        (*msg).to_owned()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown error".to_owned()
+    }
+}
+
+fn catch<R>(f: impl FnOnce() -> R) -> Result<R, String> {
+    let was_catching = CATCHING.with(|c| c.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.with(|c| c.set(was_catching));
+    result.map_err(|payload| panic_message(&*payload))
+}
+
+/// Wrapper around a container that catches panics while the container is drawn, handles input or
+/// is notified about an event. From then on, the error is shown in place of the container (which
+/// is not used anymore) until it is reset using `r`, so that a bug in one container does not take
+/// down the whole tui.
+pub struct ErrorBoundary<C> {
+    inner: C,
+    name: &'static str,
+    error: RefCell<Option<String>>,
+}
+
+impl<C> ErrorBoundary<C> {
+    pub fn new(name: &'static str, inner: C) -> Self {
+        ErrorBoundary {
+            inner,
+            name,
+            error: RefCell::new(None),
+        }
+    }
+
+    fn fail(&self, msg: String) {
+        error!("{} failed: {}", self.name, msg);
+        *self.error.borrow_mut() = Some(msg);
+    }
+
+    fn placeholder(&self) -> Option<String> {
+        self.error.borrow().as_ref().map(|msg| {
+            format!(
+                "{} failed: {}\nSee the log for details. Press 'r' to try again.",
+                self.name, msg
+            )
+        })
+    }
+}
+
+impl<C> Deref for ErrorBoundary<C> {
+    type Target = C;
+    fn deref(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C> DerefMut for ErrorBoundary<C> {
+    fn deref_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+}
+
+impl<C: Container<::Context>> Container<::Context> for ErrorBoundary<C> {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        if self.error.borrow().is_some() {
+            let error = &self.error;
+            return input
+                .chain((Key::Char('r'), || *error.borrow_mut() = None))
+                .finish();
+        }
+        let inner = &mut self.inner;
+        match catch(|| inner.input(input, p)) {
+            Ok(rest) => rest,
+            Err(msg) => {
+                self.fail(msg);
+                None
+            }
+        }
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        if let Some(placeholder) = self.placeholder() {
+            return Box::new(placeholder);
+        }
+        match catch(|| self.inner.as_widget()) {
+            Ok(widget) => Box::new(GuardedWidget {
+                widget,
+                boundary: self,
+            }),
+            Err(msg) => {
+                self.fail(msg);
+                Box::new(self.placeholder().unwrap_or_default())
+            }
+        }
+    }
+}
+
+impl<C: Subscriber> Subscriber for ErrorBoundary<C> {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        if self.error.borrow().is_some() {
+            return;
+        }
+        let inner = &mut self.inner;
+        if let Err(msg) = catch(|| inner.on_event(event, p)) {
+            self.fail(msg);
+        }
+    }
+}
+
+struct GuardedWidget<'a, C> {
+    widget: Box<dyn Widget + 'a>,
+    boundary: &'a ErrorBoundary<C>,
+}
+
+impl<'a, C> Widget for GuardedWidget<'a, C> {
+    fn space_demand(&self) -> Demand2D {
+        if let Some(placeholder) = self.boundary.placeholder() {
+            return placeholder.space_demand();
+        }
+        match catch(|| self.widget.space_demand()) {
+            Ok(demand) => demand,
+            Err(msg) => {
+                self.boundary.fail(msg);
+                self.boundary
+                    .placeholder()
+                    .unwrap_or_default()
+                    .space_demand()
+            }
+        }
+    }
+
+    fn draw(&self, mut window: Window, hints: RenderingHints) {
+        if self.boundary.error.borrow().is_none() {
+            let result = catch(|| self.widget.draw(window.create_subwindow(.., ..), hints));
+            match result {
+                Ok(()) => return,
+                Err(msg) => self.boundary.fail(msg),
+            }
+        }
+        window.clear();
+        self.boundary
+            .placeholder()
+            .unwrap_or_default()
+            .draw(window, hints);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_catch() {
+        assert_eq!(catch(|| 1), Ok(1));
+        assert_eq!(catch(|| -> () { panic!("oops") }), Err("oops".to_owned()));
+        assert_eq!(
+            catch(|| -> () { panic!("{} failed", 1) }),
+            Err("1 failed".to_owned())
+        );
+        assert!(!is_catching());
+    }
+}
@@ -0,0 +1,65 @@
+use gdbmi::types::Frame;
+use std::time::Instant;
+
+/// Changes of the state of gdb (or the debugged program) that containers react to. Events are
+/// published to all subscribers (see `Tui::publish`), which ignore the ones they are not
+/// interested in.
+#[derive(Clone, Debug)]
+pub enum DebugEvent {
+    /// The program stopped (in the selected inferior) or another thread was selected.
+    Stopped(Option<Frame>),
+    BreakpointsChanged,
+    InferiorsChanged,
+    /// A thread was created or exited (which usually happens while the program is running).
+    ThreadListChanged,
+    /// The memory of the program was modified, e.g., using `set var x = 1` in the console.
+    MemoryWritten,
+    /// One of gdb's print settings was changed.
+    PrintSettingsChanged,
+    /// Instructions are disassembled using another syntax (`set disassembly-flavor`).
+    DisassemblyFlavorChanged,
+    /// A path substitution rule was added (`!substitute-path`).
+    PathSubstitutionsChanged,
+    /// A skip was added, removed, enabled or disabled (`!skip` or the skips container).
+    SkipsChanged,
+    /// A shared library was loaded or unloaded (or its symbols were read).
+    LibrariesChanged,
+    /// The executable was loaded again (`!reload` or after gdb was restarted).
+    SymbolsReloaded,
+    /// A call of a traced function was recorded (or the trace was cleared).
+    TraceChanged,
+}
+
+pub trait Subscriber {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context);
+}
+
+/// Breakpoints and inferiors are modified in many places (e.g., breakpoints are inserted from the
+/// pager, the console and via ipc), so changes of them are detected after every event instead of
+/// being published at every such place.
+pub struct ChangeDetector {
+    breakpoints: Instant,
+    inferiors: Instant,
+}
+
+impl ChangeDetector {
+    pub fn new() -> Self {
+        ChangeDetector {
+            breakpoints: Instant::now(),
+            inferiors: Instant::now(),
+        }
+    }
+
+    pub fn changes(&mut self, p: &::Context) -> Vec<DebugEvent> {
+        let mut events = Vec::new();
+        if p.gdb.breakpoints.last_change > self.breakpoints {
+            self.breakpoints = p.gdb.breakpoints.last_change;
+            events.push(DebugEvent::BreakpointsChanged);
+        }
+        if p.gdb.inferiors.last_change > self.inferiors {
+            self.inferiors = p.gdb.inferiors.last_change;
+            events.push(DebugEvent::InferiorsChanged);
+        }
+        events
+    }
+}
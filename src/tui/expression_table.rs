@@ -1,24 +1,137 @@
 use crate::gdb_expression_parsing::Format;
+use clipboard;
+use gdb::PrintSettings;
 use gdbmi::commands::MiCommand;
-use gdbmi::output::ResultClass;
+use gdbmi::output::{ResultClass, ResultRecord};
 use gdbmi::ExecuteError;
+use number_format::NumberFormat;
+use std::fmt;
+use std::str::FromStr;
+use tui::event_bus::{DebugEvent, Subscriber};
+use tui::sparkline::{self, History, PlotView};
 use unsegen::base::{Color, GraphemeCluster, StyleModifier};
 use unsegen::container::Container;
-use unsegen::input::{EditBehavior, Input, Key, NavigateBehavior, ScrollBehavior};
+use unsegen::input::{EditBehavior, Input, Key, Navigatable, NavigateBehavior, ScrollBehavior};
 use unsegen::widget::builtin::{Column, LineEdit, Table, TableRow};
-use unsegen::widget::{SeparatingStyle, Widget};
+use unsegen::widget::{SeparatingStyle, VLayout, Widget};
 use unsegen_jsonviewer::JsonViewer;
+use unsegen_pager::{Pager, PagerContent};
 
 use completion::{Completer, CompletionState, IdentifierCompleter};
 
+const GROUP_HEADER_PREFIX: &str = "#";
+const OUT_OF_SCOPE_MARKER: &str = "<out of scope>";
+
+/// How expressions that cannot be evaluated in the selected frame (e.g., locals of another
+/// function) are shown.
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum OutOfScopeStyle {
+    /// Show the error message of gdb.
+    #[default]
+    Error,
+    /// Show a short marker instead of the error message.
+    Mark,
+    /// Hide the row until the expression can be evaluated again.
+    Collapse,
+}
+
+impl FromStr for OutOfScopeStyle {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(OutOfScopeStyle::Error),
+            "mark" => Ok(OutOfScopeStyle::Mark),
+            "collapse" => Ok(OutOfScopeStyle::Collapse),
+            o => Err(format!(
+                "Unknown out of scope style '{}'. Supported: error, mark, collapse",
+                o
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutOfScopeStyle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutOfScopeStyle::Error => write!(f, "error"),
+            OutOfScopeStyle::Mark => write!(f, "mark"),
+            OutOfScopeStyle::Collapse => write!(f, "collapse"),
+        }
+    }
+}
+
+impl OutOfScopeStyle {
+    fn next(self) -> Self {
+        match self {
+            OutOfScopeStyle::Error => OutOfScopeStyle::Mark,
+            OutOfScopeStyle::Mark => OutOfScopeStyle::Collapse,
+            OutOfScopeStyle::Collapse => OutOfScopeStyle::Error,
+        }
+    }
+}
+
+fn is_out_of_scope_error(msg: &str) -> bool {
+    (msg.starts_with("No symbol ") && msg.ends_with(" in current context."))
+        || msg == "No frame selected."
+}
+
+// Split `ptr@16` into the pointer and the number of elements. The `@` must not be part of a
+// subexpression (e.g., an index) or a literal. Expressions that already dereference the pointer
+// (`*ptr@16`) are left to gdb.
+fn split_artificial_array(expression: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut at = None;
+    for (i, c) in expression.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '@' if depth == 0 => at = Some(i),
+            _ => {}
+        }
+    }
+    let at = at?;
+    let (pointer, count) = (expression[..at].trim(), expression[at + 1..].trim());
+    if pointer.is_empty() || count.is_empty() || pointer.starts_with('*') {
+        None
+    } else {
+        Some((pointer, count))
+    }
+}
+
 pub struct ExpressionRow {
     expression: LineEdit,
     completion_state: Option<CompletionState>,
     result: JsonViewer,
+    value: Option<String>, // Unparsed result of the last successful evaluation
     format: Option<crate::gdb_expression_parsing::Format>,
+    truncation: Option<usize>,
+    show_type: bool,
+    type_name: String,
+    show_address: bool,
+    address: String,
+    out_of_scope: bool,
+    out_of_scope_style: OutOfScopeStyle,
+    numbers: NumberFormat,
+    failed: bool,     // Whether gdb reported an error for the last evaluation
+    history: History, // Numeric values at the stops since the expression was last changed
+    show_history: bool,
+    sparkline: String,
 }
 
-fn next_format(f: Option<Format>) -> Option<Format> {
+pub fn next_format(f: Option<Format>) -> Option<Format> {
     match f {
         None => Some(Format::Hex),
         Some(Format::Hex) => Some(Format::Decimal),
@@ -29,54 +142,146 @@ fn next_format(f: Option<Format>) -> Option<Format> {
 }
 
 impl ExpressionRow {
-    fn new() -> Self {
+    fn new(truncation: Option<usize>) -> Self {
         ExpressionRow {
             expression: LineEdit::new(),
             completion_state: None,
             result: JsonViewer::new(" "),
+            value: None,
             format: None,
+            truncation,
+            show_type: false,
+            type_name: String::new(),
+            show_address: false,
+            address: String::new(),
+            out_of_scope: false,
+            out_of_scope_style: OutOfScopeStyle::default(),
+            numbers: NumberFormat::default(),
+            failed: false,
+            history: History::default(),
+            show_history: false,
+            sparkline: String::new(),
         }
     }
 
     fn is_empty(&self) -> bool {
         self.expression.get().is_empty()
     }
+    // Rows starting with '#' are not evaluated, but serve as headers for groups of expressions.
+    fn is_group_header(&self) -> bool {
+        self.expression.get().starts_with(GROUP_HEADER_PREFIX)
+    }
+    // The expression that is passed to gdb: `ptr@16` is shown as an array of the 16 elements that
+    // `ptr` points to (gdb's artificial array `*ptr@16`). For anything but pointers (e.g.,
+    // `arr[0]@16`), `@` keeps gdb's meaning.
+    fn gdb_expression(&self, p: &mut ::Context) -> String {
+        let expression = self.expression.get();
+        if let Some((pointer, count)) = split_artificial_array(expression) {
+            if p.gdb.get_type(pointer).is_ok_and(|t| t.ends_with('*')) {
+                return format!("*({})@{}", pointer, count);
+            }
+        }
+        expression.to_owned()
+    }
     fn update_result(&mut self, p: &mut ::Context) {
-        let expr = self.expression.get().to_owned();
+        if let Some(command) = self.prepare_update(p) {
+            let res = p.gdb.mi.execute(command);
+            self.apply_result(res);
+        }
+    }
+    // Update everything but the value itself and return the command that evaluates the
+    // expression, if there is anything to evaluate.
+    fn prepare_update(&mut self, p: &mut ::Context) -> Option<MiCommand> {
+        let expr = if self.is_group_header() {
+            self.expression.get().to_owned()
+        } else {
+            self.gdb_expression(p)
+        };
+        self.value = None;
+        self.out_of_scope = false;
+        self.failed = false;
+        self.type_name = if self.show_type && !expr.is_empty() && !self.is_group_header() {
+            p.gdb.get_type(&expr).unwrap_or_default()
+        } else {
+            String::new()
+        };
+        // Not every expression is addressable (e.g., `a+b`), in which case the column stays empty.
+        self.address = if self.show_address && !expr.is_empty() && !self.is_group_header() {
+            let format_address = p.address_formatter();
+            p.gdb
+                .get_address(&expr)
+                .map(format_address)
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
         if expr.is_empty() {
             self.result.update(" ");
+            None
+        } else if self.is_group_header() {
+            self.result.update("────────");
+            None
+        } else {
+            Some(MiCommand::data_evaluate_expression(expr))
+        }
+    }
+    fn show_value(&mut self, value: String) {
+        match crate::gdb_expression_parsing::parse_gdb_value(&value) {
+            Ok(n) => {
+                let v = crate::gdb_expression_parsing::Value {
+                    node: &n,
+                    format: self.format,
+                    truncation: self.truncation,
+                    numbers: self.numbers,
+                };
+                self.result.update(v);
+            }
+            Err(_) => {
+                self.result
+                    .update(format!("*Error parsing*: {}", value).as_str());
+            }
+        }
+        self.value = Some(value);
+    }
+    // Record the current value (if it is a number) in the history.
+    fn record_value(&mut self) {
+        if let Some(number) = self.value.as_deref().and_then(sparkline::parse_number) {
+            self.history.push(number);
+        }
+        self.update_sparkline();
+    }
+    fn update_sparkline(&mut self) {
+        self.sparkline = if self.show_history {
+            sparkline::sparkline(&self.history.values(), sparkline::SPARKLINE_WIDTH)
         } else {
-            match p.gdb.mi.execute(MiCommand::data_evaluate_expression(expr)) {
-                Ok(res) => match res.class {
-                    ResultClass::Error => {
+            String::new()
+        };
+    }
+    fn apply_result(&mut self, res: Result<ResultRecord, ExecuteError>) {
+        match res {
+            Ok(res) => match res.class {
+                ResultClass::Error => {
+                    self.failed = true;
+                    let msg = res.results["msg"].as_str().unwrap_or("");
+                    self.out_of_scope = is_out_of_scope_error(msg);
+                    if self.out_of_scope && self.out_of_scope_style != OutOfScopeStyle::Error {
+                        self.result.update(OUT_OF_SCOPE_MARKER);
+                    } else {
                         self.result.update(&res.results["msg"]);
                     }
-                    ResultClass::Done => {
-                        let to_parse = res.results["value"].as_str().expect("value present");
-                        match crate::gdb_expression_parsing::parse_gdb_value(to_parse) {
-                            Ok(n) => {
-                                let v = crate::gdb_expression_parsing::Value {
-                                    node: &n,
-                                    format: self.format,
-                                };
-                                self.result.update(v);
-                            }
-                            Err(_) => {
-                                self.result
-                                    .update(format!("*Error parsing*: {}", to_parse).as_str());
-                            }
-                        }
-                    }
-                    other => panic!("unexpected result class: {:?}", other),
-                },
-                Err(ExecuteError::Busy) => {
-                    return;
                 }
-                Err(ExecuteError::Quit) => {
-                    panic!("GDB quit!");
+                ResultClass::Done => {
+                    let value = res.results["value"].as_str().expect("value present");
+                    self.show_value(value.to_owned());
                 }
+                other => panic!("unexpected result class: {:?}", other),
+            },
+            Err(ExecuteError::Busy) | Err(ExecuteError::Timeout) => {}
+            Err(ExecuteError::Quit(e)) => {
+                self.failed = true;
+                self.result.update(format!("*{}*", e).as_str());
             }
-        };
+        }
     }
 }
 impl TableRow for ExpressionRow {
@@ -125,8 +330,9 @@ impl TableRow for ExpressionRow {
                     }))
                     .if_not_consumed(|| r.completion_state = None)
                     .chain((Key::Ctrl('w'), || {
+                        let expression = r.gdb_expression(p);
                         match p.gdb.mi.execute(MiCommand::insert_watchpoing(
-                            r.expression.get(),
+                            &expression,
                             crate::gdbmi::commands::WatchMode::Access,
                         )) {
                             Ok(o) => match o.class {
@@ -163,6 +369,10 @@ impl TableRow for ExpressionRow {
                     )
                     .finish();
 
+                if r.expression.get() != prev_content {
+                    r.history.clear();
+                    r.update_sparkline();
+                }
                 if r.expression.get() != &prev_content || format_changed {
                     r.update_result(p);
                 }
@@ -196,30 +406,308 @@ impl TableRow for ExpressionRow {
                     .finish()
             },
         },
+        Column {
+            access: |r| Box::new(r.type_name.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.address.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.sparkline.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
     ];
 }
 
+// Lines of the full value are wrapped after this number of characters.
+const FULL_VALUE_WRAP_WIDTH: usize = 100;
+
+// Shows the complete (i.e., not truncated by us or gdb) value of an expression.
+pub struct FullValueView {
+    expression: String,
+    pager: Pager<String>,
+}
+
+impl FullValueView {
+    pub fn new(expression: String, value: &str) -> Self {
+        let lines = value
+            .lines()
+            .flat_map(|line| {
+                let chars = line.chars().collect::<Vec<_>>();
+                if chars.is_empty() {
+                    vec![String::new()]
+                } else {
+                    chars
+                        .chunks(FULL_VALUE_WRAP_WIDTH)
+                        .map(|c| c.iter().collect::<String>())
+                        .collect()
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut pager = Pager::new();
+        pager.load(PagerContent::from_lines(lines));
+        FullValueView { expression, pager }
+    }
+
+    // Returns false if the view should be closed.
+    pub fn input(&mut self, input: Input) -> (Option<Input>, bool) {
+        let mut close = false;
+        let rest = input
+            .chain((&[Key::Esc, Key::Char('q')][..], || close = true))
+            .chain(
+                ScrollBehavior::new(&mut self.pager)
+                    .forwards_on(Key::Down)
+                    .forwards_on(Key::Char('j'))
+                    .forwards_on(Key::PageDown)
+                    .backwards_on(Key::Up)
+                    .backwards_on(Key::Char('k'))
+                    .backwards_on(Key::PageUp)
+                    .to_beginning_on(Key::Home)
+                    .to_end_on(Key::End),
+            )
+            .finish();
+        (rest, !close)
+    }
+
+    pub fn as_widget<'a>(&'a self) -> impl Widget + 'a {
+        VLayout::new()
+            .widget(format!("Full value of {} (q: close)", self.expression))
+            .widget(self.pager.as_widget())
+    }
+}
+
+// Copy to the clipboard and insert into the console prompt, so that it can be used in commands.
+pub fn copy_to_clipboard(p: &mut ::Context, what: &str, text: Option<String>) {
+    let text = match text {
+        Some(t) if !t.is_empty() => t,
+        _ => return,
+    };
+    match clipboard::copy(&text) {
+        Ok(()) => p.log(format!("Copied {} to the clipboard.", what)),
+        Err(e) => p.log(format!("Failed to copy to clipboard: {}", e)),
+    }
+    p.insert_into_console(text.replace('\n', " "));
+}
+
+/// Change gdb's print settings, starting from `current` if they are known. All containers that
+/// show values are updated afterwards.
+pub fn change_print_settings(
+    p: &mut ::Context,
+    current: Option<PrintSettings>,
+    change: impl FnOnce(&mut PrintSettings),
+) {
+    let mut settings = match current {
+        Some(s) => s,
+        None => match p.gdb.get_print_settings() {
+            Ok(s) => s,
+            Err(e) => {
+                p.log(format!("Failed to get print settings: {:?}", e));
+                return;
+            }
+        },
+    };
+    change(&mut settings);
+    match p.gdb.set_print_settings(&settings) {
+        Ok(()) => p.publish(DebugEvent::PrintSettingsChanged),
+        Err(e) => p.log(format!("Failed to change print settings: {:?}", e)),
+    }
+}
+
 pub struct ExpressionTable {
     table: Table<ExpressionRow>,
+    print_settings: Option<PrintSettings>,
+    truncation: Option<usize>,
+    show_types: bool,
+    show_addresses: bool,
+    out_of_scope_style: OutOfScopeStyle,
+    numbers: NumberFormat,
+    // Rows hidden because they are out of scope and their index in the list of all rows.
+    hidden: Vec<(usize, ExpressionRow)>,
+    selection_anchor: Option<usize>, // Index of the row where the current selection started
+    full_value: Option<FullValueView>,
+    show_history: bool,
+    plot: Option<PlotView>,
 }
 
 impl ExpressionTable {
-    pub fn new() -> Self {
+    pub fn new(
+        truncation: Option<usize>,
+        out_of_scope_style: OutOfScopeStyle,
+        numbers: NumberFormat,
+    ) -> Self {
         let mut table = Table::new();
-        table.rows_mut().push(ExpressionRow::new()); //Invariant: always at least one line
-        ExpressionTable { table: table }
+        let mut row = ExpressionRow::new(truncation);
+        row.out_of_scope_style = out_of_scope_style;
+        row.numbers = numbers;
+        table.rows_mut().push(row); //Invariant: always at least one line
+        ExpressionTable {
+            table: table,
+            print_settings: None,
+            truncation,
+            show_types: false,
+            show_addresses: false,
+            out_of_scope_style,
+            numbers,
+            hidden: Vec::new(),
+            selection_anchor: None,
+            full_value: None,
+            show_history: false,
+            plot: None,
+        }
+    }
+
+    fn new_row(&self) -> ExpressionRow {
+        let mut row = ExpressionRow::new(self.truncation);
+        row.show_type = self.show_types;
+        row.show_address = self.show_addresses;
+        row.out_of_scope_style = self.out_of_scope_style;
+        row.numbers = self.numbers;
+        row.show_history = self.show_history;
+        row
+    }
+
+    fn change_out_of_scope_style(&mut self, p: &mut ::Context) {
+        self.out_of_scope_style = self.out_of_scope_style.next();
+        for row in self.table.rows_mut().iter_mut() {
+            row.out_of_scope_style = self.out_of_scope_style;
+        }
+        for (_, row) in self.hidden.iter_mut() {
+            row.out_of_scope_style = self.out_of_scope_style;
+        }
+        p.log(format!(
+            "Expressions that are out of scope: {}",
+            self.out_of_scope_style
+        ));
+        self.update_results(p);
     }
+
+    fn reveal_hidden_rows(&mut self) {
+        // Indices of the rows change, so the selection would not cover the same rows anymore.
+        if !self.hidden.is_empty() {
+            self.selection_anchor = None;
+        }
+        let mut rows = self.table.rows_mut();
+        // Ascending order, so that every row ends up at its previous index.
+        for (index, row) in self.hidden.drain(..) {
+            // The trailing empty row stays last.
+            let index = index.min(rows.len() - 1);
+            rows.insert(index, row);
+        }
+    }
+
+    fn hide_out_of_scope_rows(&mut self) {
+        let mut rows = self.table.rows_mut();
+        let mut index = 0;
+        while index < rows.len() {
+            if rows[index].out_of_scope {
+                self.selection_anchor = None;
+                let row = rows.remove(index);
+                self.hidden.push((index + self.hidden.len(), row));
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    fn toggle_types(&mut self, p: &mut ::Context) {
+        self.show_types = !self.show_types;
+        for row in self.table.rows_mut().iter_mut() {
+            row.show_type = self.show_types;
+        }
+        self.update_results(p);
+    }
+
+    fn toggle_addresses(&mut self, p: &mut ::Context) {
+        self.show_addresses = !self.show_addresses;
+        for row in self.table.rows_mut().iter_mut() {
+            row.show_address = self.show_addresses;
+        }
+        self.update_results(p);
+    }
+
+    fn toggle_history(&mut self) {
+        self.show_history = !self.show_history;
+        for row in self.table.rows_mut().iter_mut() {
+            row.show_history = self.show_history;
+            row.update_sparkline();
+        }
+        for (_, row) in self.hidden.iter_mut() {
+            row.show_history = self.show_history;
+            row.update_sparkline();
+        }
+    }
+
+    fn show_plot(&mut self, p: &mut ::Context) {
+        let row = match self.table.current_row() {
+            Some(row) if !row.is_empty() && !row.is_group_header() => row,
+            _ => return,
+        };
+        let expression = row.expression.get().to_owned();
+        if row.history.is_empty() {
+            p.log(format!(
+                "No numeric values of \"{}\" recorded yet.",
+                expression
+            ));
+        } else {
+            self.plot = Some(PlotView::new(expression, row.history.values()));
+        }
+    }
+
+    fn show_in_memory_view(&mut self, p: &mut ::Context) {
+        let expression = match self.table.current_row() {
+            Some(row) if !row.is_empty() && !row.is_group_header() => row.gdb_expression(p),
+            _ => return,
+        };
+        match p.gdb.get_address(&expression) {
+            Ok(address) => p.show_memory(address),
+            Err(e) => p.log(format!(
+                "Failed to get address of \"{}\": {:?}",
+                expression, e
+            )),
+        }
+    }
+    // All non-empty rows (including hidden ones) in order.
+    fn all_rows(&mut self) -> Vec<&ExpressionRow> {
+        let mut rows = self.table.rows().iter().collect::<Vec<_>>();
+        // Same as reveal_hidden_rows
+        for (index, row) in &self.hidden {
+            let index = (*index).min(rows.len().saturating_sub(1));
+            rows.insert(index, row);
+        }
+        rows.retain(|r| !r.is_empty());
+        rows
+    }
+
+    /// Expressions of all rows (including hidden ones) in order, e.g., to save them.
+    pub fn expressions(&mut self) -> Vec<String> {
+        self.all_rows()
+            .into_iter()
+            .map(|r| r.expression.get().to_owned())
+            .collect()
+    }
+
+    /// Expressions (without group headers) and the result of their last successful evaluation.
+    pub fn values(&mut self) -> Vec<(String, Option<String>)> {
+        self.all_rows()
+            .into_iter()
+            .filter(|r| !r.is_group_header())
+            .map(|r| (r.expression.get().to_owned(), r.value.clone()))
+            .collect()
+    }
+
     pub fn add_entry(&mut self, entry: String) {
         {
+            let mut new_row = self.new_row();
             let mut rows = self.table.rows_mut();
             match rows.last_mut() {
                 Some(row) if row.is_empty() => {
                     row.expression.set(entry);
                 }
                 _ => {
-                    let mut row = ExpressionRow::new();
-                    row.expression.set(entry);
-                    rows.push(row);
+                    new_row.expression.set(entry);
+                    rows.push(new_row);
                 }
             }
         }
@@ -235,21 +723,267 @@ impl ExpressionTable {
                 0
             }
         };
+        let new_row = self.new_row();
         let mut rows = self.table.rows_mut();
         rows.drain(begin_of_empty_range..);
-        rows.push(ExpressionRow::new());
+        rows.push(new_row);
     }
 
     pub fn update_results(&mut self, p: &mut ::Context) {
-        for row in self.table.rows_mut().iter_mut() {
-            row.update_result(p);
+        // The settings may also have been changed in the console.
+        if let Ok(settings) = p.gdb.get_print_settings() {
+            self.print_settings = Some(settings);
+        }
+        self.reveal_hidden_rows();
+        let mut rows = self.table.rows_mut();
+        let commands = rows
+            .iter_mut()
+            .map(|row| row.prepare_update(p))
+            .collect::<Vec<_>>();
+        // Evaluate all expressions at once instead of waiting for gdb after each one.
+        match p.gdb.mi.execute_many(commands.iter().flatten()) {
+            Ok(results) => {
+                let mut results = results.into_iter();
+                for (row, command) in rows.iter_mut().zip(&commands) {
+                    if command.is_some() {
+                        row.apply_result(Ok(results.next().expect("result for every command")));
+                    }
+                }
+            }
+            Err(e) => {
+                for (row, command) in rows.iter_mut().zip(&commands) {
+                    if command.is_some() {
+                        row.apply_result(Err(e.clone()));
+                    }
+                }
+            }
+        }
+        // Variable objects include the children synthesized by pretty printers (e.g., the
+        // elements of a `std::vector`), but every row needs several round trips to gdb. If that
+        // fails, the result of the evaluation above stays.
+        if p.pretty_printers == Some(true) {
+            for row in rows.iter_mut().filter(|r| r.value.is_some()) {
+                let expression = row.gdb_expression(p);
+                if let Ok(value) = p.gdb.evaluate_with_printers(&expression) {
+                    row.show_value(value);
+                }
+            }
+        }
+        drop(rows);
+        if self.out_of_scope_style == OutOfScopeStyle::Collapse {
+            self.hide_out_of_scope_rows();
+        }
+    }
+
+    fn current_row_index(&mut self) -> Option<usize> {
+        let current = self.table.current_row()? as *const ExpressionRow;
+        self.table
+            .rows()
+            .iter()
+            .position(|r| ::std::ptr::eq(r, current))
+    }
+
+    // The (inclusive) range of rows between the start of the selection and the current row.
+    fn selected_range(&mut self) -> Option<(usize, usize)> {
+        let anchor = self.selection_anchor?;
+        let current = self.current_row_index()?;
+        // The trailing empty row cannot be deleted anyway.
+        let last = self.table.rows().len().saturating_sub(2);
+        Some((anchor.min(current).min(last), anchor.max(current).min(last)))
+    }
+
+    fn toggle_selection(&mut self) {
+        self.selection_anchor = if self.selection_anchor.is_some() {
+            None
+        } else {
+            self.current_row_index()
+        };
+    }
+
+    // Delete the selected rows, or only the current one if there is no selection.
+    fn delete_selected_rows(&mut self, p: &mut ::Context) {
+        let (begin, end) = match self.selected_range() {
+            Some(range) => range,
+            None => match self.current_row_index() {
+                Some(i) => (i, i),
+                None => return,
+            },
+        };
+        self.selection_anchor = None;
+        let mut rows = self.table.rows_mut();
+        if end + 1 >= rows.len() {
+            return;
+        }
+        rows.drain(begin..=end);
+        p.log(format!("Removed {} expression(s).", end - begin + 1));
+    }
+
+    fn delete_failed_rows(&mut self, p: &mut ::Context) {
+        self.selection_anchor = None;
+        // Hidden rows are out of scope, i.e., their evaluation failed as well.
+        let mut removed = self.hidden.len();
+        self.hidden.clear();
+        let mut rows = self.table.rows_mut();
+        let before = rows.len();
+        rows.retain(|r| !r.failed);
+        removed += before - rows.len();
+        p.log(format!("Removed {} expression(s) with errors.", removed));
+    }
+
+    fn clear(&mut self, p: &mut ::Context) {
+        self.selection_anchor = None;
+        self.hidden.clear();
+        self.table.rows_mut().clear();
+        p.log("Removed all expressions.");
+    }
+
+    // Insert a copy of the current row below it and start editing the copy.
+    fn duplicate_current_row(&mut self, p: &mut ::Context) {
+        let index = match self.current_row_index() {
+            Some(i) => i,
+            None => return,
+        };
+        let mut row = self.new_row();
+        {
+            let original = &self.table.rows()[index];
+            if original.is_empty() {
+                return;
+            }
+            row.expression.set(original.expression.get());
+            row.format = original.format;
+        }
+        row.update_result(p);
+        self.table.rows_mut().insert(index + 1, row);
+        let _ = self.table.move_down();
+        while self.table.move_left().is_ok() {}
+    }
+
+    // Swap the current row with the one above (or below) it. The trailing empty row stays in place.
+    fn move_current_row(&mut self, up: bool) {
+        let index = match self.current_row_index() {
+            Some(i) => i,
+            None => return,
+        };
+        let other = if up {
+            match index.checked_sub(1) {
+                Some(i) => i,
+                None => return,
+            }
+        } else {
+            index + 1
+        };
+        {
+            let mut rows = self.table.rows_mut();
+            if other >= rows.len() || rows[index].is_empty() || rows[other].is_empty() {
+                return;
+            }
+            rows.swap(index, other);
+        }
+        let _ = if up {
+            self.table.move_up()
+        } else {
+            self.table.move_down()
+        };
+    }
+
+    // Insert a group header above the current row and start editing it.
+    fn insert_group_header(&mut self) {
+        let index = self.current_row_index().unwrap_or(0);
+        let mut row = self.new_row();
+        row.expression.set(format!("{} ", GROUP_HEADER_PREFIX));
+        row.result.update("────────");
+        self.table.rows_mut().insert(index, row);
+        while self.table.move_left().is_ok() {}
+    }
+
+    fn show_full_value(&mut self, p: &mut ::Context) {
+        let expression = match self.table.current_row() {
+            Some(row) if !row.is_empty() && !row.is_group_header() => row.gdb_expression(p),
+            _ => return,
+        };
+        match p.gdb.evaluate_full(&expression) {
+            Ok(value) => self.full_value = Some(FullValueView::new(expression, &value)),
+            Err(e) => p.log(format!("Failed to evaluate \"{}\": {:?}", expression, e)),
+        }
+    }
+}
+
+impl Subscriber for ExpressionTable {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        match event {
+            DebugEvent::Stopped(_) => {
+                self.update_results(p);
+                for row in self.table.rows_mut().iter_mut() {
+                    row.record_value();
+                }
+            }
+            DebugEvent::MemoryWritten
+            | DebugEvent::PrintSettingsChanged
+            | DebugEvent::SymbolsReloaded => self.update_results(p),
+            _ => {}
         }
     }
 }
 
 impl Container<::Context> for ExpressionTable {
     fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        if let Some(view) = &mut self.full_value {
+            let (rest, stay) = view.input(input);
+            if !stay {
+                self.full_value = None;
+            }
+            return rest;
+        }
+        if let Some(view) = &mut self.plot {
+            let (rest, stay) = view.input(input);
+            if !stay {
+                self.plot = None;
+            }
+            return rest;
+        }
         let res = input
+            .chain((Key::Ctrl('o'), || self.show_full_value(p)))
+            .chain((Key::Alt('d'), || self.duplicate_current_row(p)))
+            .chain((Key::Alt('K'), || self.move_current_row(true)))
+            .chain((Key::Alt('J'), || self.move_current_row(false)))
+            .chain((Key::Alt('g'), || self.insert_group_header()))
+            .chain((Key::Alt('t'), || self.toggle_types(p)))
+            .chain((Key::Alt('x'), || self.toggle_addresses(p)))
+            .chain((Key::Alt('s'), || self.toggle_history()))
+            .chain((Key::Alt('P'), || self.show_plot(p)))
+            .chain((Key::Alt('m'), || self.show_in_memory_view(p)))
+            .chain((Key::Alt('o'), || self.change_out_of_scope_style(p)))
+            .chain((Key::Alt(' '), || self.toggle_selection()))
+            .chain((Key::Alt('r'), || self.delete_selected_rows(p)))
+            .chain((Key::Alt('E'), || self.delete_failed_rows(p)))
+            .chain((Key::Alt('C'), || self.clear(p)))
+            .chain((Key::Alt('c'), || {
+                let expression = self
+                    .table
+                    .current_row()
+                    .map(|r| r.expression.get().to_owned());
+                copy_to_clipboard(p, "expression", expression)
+            }))
+            .chain((Key::Alt('v'), || {
+                let value = self.table.current_row().and_then(|r| r.value.clone());
+                copy_to_clipboard(p, "value", value)
+            }))
+            .chain((Key::Alt('p'), || {
+                change_print_settings(p, self.print_settings.clone(), |s| s.pretty = !s.pretty)
+            }))
+            .chain((Key::Alt('e'), || {
+                change_print_settings(p, self.print_settings.clone(), |s| {
+                    s.elements = s.next_elements()
+                })
+            }))
+            .chain((Key::Alt('a'), || {
+                change_print_settings(p, self.print_settings.clone(), |s| s.array = !s.array)
+            }))
+            .chain((Key::Alt('n'), || {
+                change_print_settings(p, self.print_settings.clone(), |s| {
+                    s.null_stop = !s.null_stop
+                })
+            }))
             .chain(
                 NavigateBehavior::new(&mut self.table) //TODO: Fix this properly in lineedit
                     .down_on(Key::Char('\n')),
@@ -268,16 +1002,54 @@ impl Container<::Context> for ExpressionTable {
     }
 
     fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        if let Some(view) = &self.full_value {
+            return Box::new(view.as_widget());
+        }
+        if let Some(view) = &self.plot {
+            return Box::new(view.as_widget());
+        }
+        let mut settings = match &self.print_settings {
+            Some(s) => format!("{} (M-p/M-e/M-a/M-n to change)", s),
+            None => "print settings: unknown".to_owned(),
+        };
+        if !self.hidden.is_empty() {
+            settings += &format!(", {} out of scope (hidden)", self.hidden.len());
+        }
+        if let Some(anchor) = self.selection_anchor {
+            settings += &format!(", selection from row {} (M-r: remove)", anchor + 1);
+        }
         Box::new(
-            self.table
-                .as_widget()
-                .row_separation(SeparatingStyle::AlternatingStyle(
-                    StyleModifier::new().bg_color(Color::Black),
-                ))
-                .col_separation(SeparatingStyle::Draw(
-                    GraphemeCluster::try_from('│').unwrap(),
-                ))
-                .focused(StyleModifier::new().bold(true)),
+            VLayout::new().widget(settings).widget(
+                self.table
+                    .as_widget()
+                    .row_separation(SeparatingStyle::AlternatingStyle(
+                        StyleModifier::new().bg_color(Color::Black),
+                    ))
+                    .col_separation(SeparatingStyle::Draw(
+                        GraphemeCluster::try_from('│').unwrap(),
+                    ))
+                    .focused(StyleModifier::new().bold(true)),
+            ),
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_artificial_array() {
+        assert_eq!(split_artificial_array("ptr@16"), Some(("ptr", "16")));
+        assert_eq!(
+            split_artificial_array("s.data + 1 @ n * 2"),
+            Some(("s.data + 1", "n * 2"))
+        );
+        assert_eq!(split_artificial_array("*ptr@16"), None);
+        assert_eq!(split_artificial_array("ptr"), None);
+        assert_eq!(split_artificial_array("@16"), None);
+        assert_eq!(split_artificial_array("f(a@2)"), None);
+        assert_eq!(split_artificial_array("strchr(s, '@')"), None);
+        assert_eq!(split_artificial_array("\"a@b\""), None);
+    }
+}
@@ -0,0 +1,116 @@
+use super::event_bus::{DebugEvent, Subscriber};
+use super::list_filter::FilterableRow;
+use super::list_view::ListView;
+use gdb::InferiorSet;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::{Column, TableRow};
+use unsegen::widget::Widget;
+
+#[derive(Clone)]
+pub struct InferiorRow {
+    number: String,
+    selected: &'static str,
+    state: String,
+}
+
+impl TableRow for InferiorRow {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<InferiorRow>] = &[
+        Column {
+            access: |r| Box::new(r.selected),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.number.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.state.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+    ];
+}
+
+impl FilterableRow for InferiorRow {
+    fn cell_text(&self, column: usize) -> &str {
+        match column {
+            0 => self.selected,
+            1 => &self.number,
+            _ => &self.state,
+        }
+    }
+}
+
+/// List of the inferiors (e.g., the processes created by a fork with `detach-on-fork off`) of the
+/// session. The selected inferior is marked with `*`.
+pub struct InferiorsView {
+    list: ListView<InferiorRow>,
+}
+
+impl InferiorsView {
+    pub fn new() -> Self {
+        InferiorsView {
+            list: ListView::new("0 inferior(s)"),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.list.lines()
+    }
+
+    fn set_inferiors(&mut self, inferiors: &InferiorSet) {
+        let rows = inferiors
+            .iter()
+            .map(|i| InferiorRow {
+                number: i.number().to_owned(),
+                selected: if inferiors.selected() == Some(i.id.as_str()) {
+                    "*"
+                } else {
+                    " "
+                },
+                state: i.state.to_string(),
+            })
+            .collect::<Vec<_>>();
+        self.list.set_header(format!("{} inferior(s)", rows.len()));
+        self.list.set_rows(rows);
+    }
+
+    fn select_inferior(row: Option<&InferiorRow>, p: &mut ::Context) {
+        if let Some(row) = row {
+            if let Err(e) = p.gdb.select_inferior(&row.number) {
+                p.log(format!("Failed to select inferior {}: {:?}", row.number, e));
+            }
+        }
+    }
+}
+
+impl Subscriber for InferiorsView {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        if let DebugEvent::InferiorsChanged = event {
+            self.set_inferiors(&p.gdb.inferiors);
+        }
+    }
+}
+
+impl Container<::Context> for InferiorsView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        self.list.input(input, |row, input| {
+            input
+                .chain((Key::Char('\n'), || Self::select_inferior(row, p)))
+                .finish()
+        })
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        self.list.as_widget()
+    }
+}
@@ -0,0 +1,202 @@
+use super::event_bus::{DebugEvent, Subscriber};
+use super::list_filter::FilterableRow;
+use super::list_view::ListView;
+use super::overlay::Prompt;
+use gdb::Address;
+use gdbmi::capabilities::Feature;
+use gdbmi::types::Library;
+use std::path::PathBuf;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::{Column, TableRow};
+use unsegen::widget::Widget;
+
+#[derive(Clone)]
+pub struct LibraryRow {
+    range: String,
+    symbols: &'static str,
+    name: String,
+    library: Library,
+}
+
+impl TableRow for LibraryRow {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<LibraryRow>] = &[
+        Column {
+            access: |r| Box::new(r.range.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.symbols),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.name.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+    ];
+}
+
+impl FilterableRow for LibraryRow {
+    fn cell_text(&self, column: usize) -> &str {
+        match column {
+            0 => &self.range,
+            1 => self.symbols,
+            _ => &self.name,
+        }
+    }
+}
+
+/// The shared libraries that are loaded by the program (as reported by gdb's `=library-loaded`
+/// and `=library-unloaded` notifications) with the address range of their code and whether gdb
+/// read their symbols (and found debug information for them).
+pub struct LibrariesView {
+    list: ListView<LibraryRow>,
+    without_debug_info: Vec<String>,
+    // Libraries changed while the program was running, so gdb could not be asked which of them
+    // have debug information.
+    debug_info_stale: bool,
+}
+
+impl LibrariesView {
+    pub fn new() -> Self {
+        LibrariesView {
+            list: ListView::new(Self::header(0)),
+            without_debug_info: Vec::new(),
+            debug_info_stale: false,
+        }
+    }
+
+    fn header(len: usize) -> String {
+        format!(
+            "{} shared librar(y/ies) (y*: no debug info; Enter: read symbols, d: add debug file)",
+            len
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.list.lines()
+    }
+
+    fn set_libraries(&mut self, libraries: &[Library], format_address: impl Fn(Address) -> String) {
+        let rows = libraries
+            .iter()
+            .map(|l| LibraryRow {
+                range: match (l.ranges.first(), l.ranges.last()) {
+                    (Some(&(from, _)), Some(&(_, to))) => format!(
+                        "{}-{}",
+                        format_address(Address(from)),
+                        format_address(Address(to))
+                    ),
+                    _ => String::new(),
+                },
+                symbols: match l.symbols_loaded {
+                    Some(true) if self.without_debug_info.contains(&l.target_name) => "y*",
+                    Some(true) => "y",
+                    Some(false) => "n",
+                    None => "?",
+                },
+                name: l.host_name.clone().unwrap_or_else(|| l.target_name.clone()),
+                library: l.clone(),
+            })
+            .collect::<Vec<_>>();
+        self.list.set_header(Self::header(rows.len()));
+        self.list.set_rows(rows);
+    }
+
+    // "info sharedlibrary" cannot be executed while the program is running.
+    fn refresh_debug_info(&mut self, p: &mut ::Context) {
+        if !p.supports(Feature::GdbCommands) {
+            return;
+        }
+        if p.gdb.mi.is_running() {
+            self.debug_info_stale = true;
+            return;
+        }
+        match p.gdb.libraries_without_debug_info() {
+            Ok(libraries) => {
+                self.without_debug_info = libraries;
+                self.debug_info_stale = false;
+            }
+            Err(_) => self.debug_info_stale = true,
+        }
+    }
+
+    fn update(&mut self, p: &mut ::Context) {
+        self.refresh_debug_info(p);
+        let format_address = p.address_formatter();
+        self.set_libraries(&p.gdb.libraries, format_address);
+    }
+
+    // Read a separate debug file (e.g., "libfoo.so.debug") for the library at the address of its
+    // code.
+    fn add_debug_file(row: Option<&LibraryRow>, p: &mut ::Context) {
+        if let Some(row) = row {
+            let name = row.name.clone();
+            let address = row.library.ranges.first().map(|&(from, _)| from);
+            p.show_overlay(Prompt::new(
+                format!("Debug file for {}", name),
+                "",
+                move |file: String, p: &mut ::Context| {
+                    let file = file.trim();
+                    if file.is_empty() {
+                        return;
+                    }
+                    let address = address.map(|a| format!("{:#x}", a));
+                    match p
+                        .gdb
+                        .add_symbol_file(&PathBuf::from(file), address.as_deref())
+                    {
+                        Ok(()) => {
+                            p.publish(DebugEvent::LibrariesChanged);
+                            p.publish(DebugEvent::SymbolsReloaded);
+                        }
+                        Err(e) => p.log(format!("Cannot read {}: {:?}", file, e)),
+                    }
+                },
+            ));
+        }
+    }
+
+    fn load_symbols(row: Option<&LibraryRow>, p: &mut ::Context) {
+        if let Some(row) = row {
+            match p.gdb.load_library_symbols(&row.library) {
+                Ok(()) => p.publish(DebugEvent::LibrariesChanged),
+                Err(e) => p.log(format!("Cannot read symbols of {}: {:?}", row.name, e)),
+            }
+        }
+    }
+}
+
+impl Subscriber for LibrariesView {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        match event {
+            DebugEvent::LibrariesChanged => self.update(p),
+            DebugEvent::Stopped(_) if self.debug_info_stale => self.update(p),
+            _ => {}
+        }
+    }
+}
+
+impl Container<::Context> for LibrariesView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        self.list.input(input, |row, input| {
+            input
+                .chain((Key::Char('\n'), || Self::load_symbols(row, p)))
+                .chain((Key::Char('d'), || Self::add_debug_file(row, p)))
+                .finish()
+        })
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        self.list.as_widget()
+    }
+}
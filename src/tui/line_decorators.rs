@@ -0,0 +1,497 @@
+// The decorators of the lines in the source and assembly views, i.e., everything left of the
+// text: Line numbers (or addresses), the stop position, breakpoints with their hit counts, and
+// diagnostics.
+use diagnostics::{Diagnostic, Severity};
+use gdb::{Address, BreakPoint, SrcPosition};
+use gdbmi::output::JsonValue;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use tui::branch_arrows::BranchArrows;
+use tui::render_profile::{Marker, RenderProfile};
+use unsegen::base::basic_types::*;
+use unsegen::base::{BoolModifyMode, Color, Cursor, GraphemeCluster, StyleModifier, Window};
+use unsegen::widget::{text_width, ColDemand, Demand};
+use unsegen_pager::{LineDecorator, PagerLine};
+
+/// Where an instruction is in its function.
+#[derive(Clone)]
+pub struct AssemblyDebugLocation {
+    pub func_name: String,
+    pub offset: usize,
+}
+
+impl AssemblyDebugLocation {
+    pub fn try_from_value(val: &JsonValue) -> Option<Self> {
+        let func_name = val["func-name"].as_str()?;
+        let offset = val["offset"].as_str()?.parse::<usize>().ok()?;
+        Some(AssemblyDebugLocation {
+            func_name: func_name.to_owned(),
+            offset,
+        })
+    }
+}
+
+/// An instruction in the assembly view.
+#[derive(Clone)]
+pub struct AssemblyLine {
+    pub content: String,
+    pub address: Address,
+    pub src_position: Option<SrcPosition>,
+    pub debug_location: Option<AssemblyDebugLocation>,
+    pub opcodes: Option<String>, // Raw bytes of the instruction, e.g., "48 89 e5"
+}
+
+impl AssemblyLine {
+    pub fn new(
+        content: String,
+        address: Address,
+        src_position: Option<SrcPosition>,
+        debug_location: Option<AssemblyDebugLocation>,
+        opcodes: Option<String>,
+    ) -> Self {
+        AssemblyLine {
+            content: content,
+            address: address,
+            src_position: src_position,
+            debug_location: debug_location,
+            opcodes,
+        }
+    }
+}
+
+/// Width of the opcodes column (if it is shown) that fits the raw bytes of all instructions.
+pub fn opcodes_width<'a>(lines: impl Iterator<Item = &'a AssemblyLine>) -> usize {
+    lines
+        .filter_map(|l| l.opcodes.as_ref())
+        .map(|o| text_width(o).into())
+        .max()
+        .unwrap_or(0)
+}
+
+impl PagerLine for AssemblyLine {
+    fn get_content(&self) -> &str {
+        &self.content
+    }
+}
+
+/// Shows the address (or offset), breakpoints, opcodes and branch arrows of instructions.
+pub struct AssemblyDecorator {
+    stop_position: Option<Address>,
+    breakpoint_addresses: HashMap<Address, u64>, // Hit counts
+    disabled_addresses: HashSet<Address>,        // Only if the profile marks them
+    hits_width: usize,
+    opcodes_width: Option<usize>, // None: Opcodes are not shown
+    arrows: BranchArrows,
+    format_address: Box<dyn Fn(Address) -> String>,
+    profile: RenderProfile,
+}
+
+impl AssemblyDecorator {
+    pub fn new<'a, I: Iterator<Item = &'a BreakPoint>>(
+        address_range: Range<Address>,
+        stop_position: Option<Address>,
+        breakpoints: I,
+        opcodes_width: Option<usize>,
+        arrows: BranchArrows,
+        format_address: Box<dyn Fn(Address) -> String>,
+        profile: RenderProfile,
+    ) -> Self {
+        let mut addresses = HashMap::new();
+        let mut disabled_addresses = HashSet::new();
+        for bp in breakpoints {
+            if let Some(addr) = bp.address {
+                if address_range.start <= addr && addr < address_range.end {
+                    if bp.enabled {
+                        *addresses.entry(addr).or_insert(0) += bp.hit_count;
+                    } else if profile.marks_disabled_breakpoints() {
+                        disabled_addresses.insert(addr);
+                    }
+                }
+            }
+        }
+        let stop_position = if let Some(p) = stop_position {
+            if address_range.start <= p && p < address_range.end {
+                Some(p)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+        AssemblyDecorator {
+            stop_position: stop_position,
+            hits_width: hits_width(addresses.values()),
+            breakpoint_addresses: addresses,
+            disabled_addresses,
+            opcodes_width,
+            arrows,
+            format_address,
+            profile,
+        }
+    }
+
+    // The opcodes are separated from the address and the instruction by a space each.
+    fn opcodes_column_width(&self) -> usize {
+        self.opcodes_width.map(|w| w + 2).unwrap_or(0)
+    }
+}
+
+impl LineDecorator for AssemblyDecorator {
+    type Line = AssemblyLine;
+    fn horizontal_space_demand<'a, 'b: 'a>(
+        &'a self,
+        lines: impl DoubleEndedIterator<Item = (LineIndex, &'b Self::Line)> + 'b,
+    ) -> ColDemand {
+        let max_space = lines
+            .last()
+            .map(|(_, l)| text_width(format!(" {} ", (self.format_address)(l.address)).as_str()))
+            .unwrap_or(Width::new(0).unwrap());
+        Demand::exact(
+            max_space
+                + Width::new(self.hits_width as i32).unwrap()
+                + Width::new(self.opcodes_column_width() as i32).unwrap()
+                + Width::new(self.arrows.width() as i32).unwrap(),
+        )
+    }
+    fn decorate(
+        &self,
+        line: &Self::Line,
+        current_line: LineIndex,
+        active_line: LineIndex,
+        mut window: Window,
+    ) {
+        let width = window.get_width()
+            - self.hits_width as i32
+            - self.opcodes_column_width() as i32
+            - self.arrows.width() as i32;
+        let mut cursor = Cursor::new(&mut window).position(ColIndex::new(0), RowIndex::new(0));
+
+        let at_stop_position = self
+            .stop_position
+            .map(|p| p == line.address)
+            .unwrap_or(false);
+        let hits = self.breakpoint_addresses.get(&line.address).cloned();
+        let at_breakpoint_position = hits.is_some();
+
+        let at_disabled_breakpoint = self.disabled_addresses.contains(&line.address);
+        let (right_border, style_modifier) = marker(
+            self.profile,
+            at_stop_position,
+            at_breakpoint_position,
+            at_disabled_breakpoint,
+        );
+
+        use std::fmt::Write;
+        write_hits(&mut cursor, hits, self.hits_width);
+        cursor.set_style_modifier(style_modifier);
+
+        if let (false, Some(offset)) = (
+            current_line == active_line,
+            line.debug_location
+                .iter()
+                .map(|l| l.offset)
+                .filter(|&offset| offset != 0)
+                .next(),
+        ) {
+            let formatted_offset = format!("<+{}>", offset);
+            write!(
+                cursor,
+                "{:>width$}{}",
+                formatted_offset,
+                right_border,
+                width = (width - 1).positive_or_zero().into()
+            )
+            .unwrap();
+        } else {
+            write!(
+                cursor,
+                " {:>width$}{}",
+                (self.format_address)(line.address),
+                right_border,
+                width = (width - 2).positive_or_zero().into()
+            )
+            .unwrap();
+        }
+        if let Some(opcodes_width) = self.opcodes_width {
+            cursor.set_style_modifier(StyleModifier::new().fg_color(Color::LightBlack));
+            write!(
+                cursor,
+                " {:<width$} ",
+                line.opcodes.as_deref().unwrap_or(""),
+                width = opcodes_width
+            )
+            .unwrap();
+        }
+        if let Some(arrows) = self.arrows.cells(line.address) {
+            cursor.set_style_modifier(StyleModifier::new().fg_color(Color::Cyan).bold(false));
+            write!(cursor, "{}", arrows).unwrap();
+        }
+    }
+}
+
+/// A line (or a fold of lines) in the source view.
+#[derive(Clone)]
+pub struct SourceLine {
+    pub content: String,
+    pub number: LineNumber,
+    pub num_folded: usize, // Number of lines hidden behind this one
+}
+
+impl SourceLine {
+    pub fn folded_range(&self) -> Range<usize> {
+        let begin: usize = self.number.into();
+        begin..(begin + self.num_folded + 1)
+    }
+}
+
+impl PagerLine for SourceLine {
+    fn get_content(&self) -> &str {
+        &self.content
+    }
+}
+
+// Width of the column with the hit counts of breakpoints, which is only shown if any breakpoint
+// was hit.
+fn hits_width<'a>(hits: impl Iterator<Item = &'a u64>) -> usize {
+    match hits.max() {
+        Some(&max) if max > 0 => max.to_string().len() + 1,
+        _ => 0,
+    }
+}
+
+// Glyph and style of the marker after the line number (or address) of a line.
+fn marker(
+    profile: RenderProfile,
+    at_stop_position: bool,
+    at_breakpoint: bool,
+    at_disabled_breakpoint: bool,
+) -> (char, StyleModifier) {
+    let marker = match (at_stop_position, at_breakpoint, at_disabled_breakpoint) {
+        (true, true, _) => Marker::StopAtBreakpoint,
+        (true, false, _) => Marker::Stop,
+        (false, true, _) => Marker::Breakpoint,
+        (false, false, true) => Marker::DisabledBreakpoint,
+        (false, false, false) => return (' ', StyleModifier::new()),
+    };
+    profile.marker(marker)
+}
+
+fn write_hits(cursor: &mut Cursor, hits: Option<u64>, width: usize) {
+    use std::fmt::Write;
+    if width == 0 {
+        return;
+    }
+    cursor.set_style_modifier(StyleModifier::new().fg_color(Color::Red));
+    match hits {
+        Some(hits) if hits > 0 => write!(cursor, "{:>width$} ", hits, width = width - 1),
+        _ => write!(cursor, "{:width$}", "", width = width),
+    }
+    .unwrap();
+}
+
+/// Shows the line number, breakpoints and diagnostics of source lines.
+pub struct SourceDecorator {
+    stop_position: Option<LineNumber>,
+    breakpoint_lines: HashMap<LineNumber, u64>, // Hit counts
+    disabled_lines: HashSet<LineNumber>,        // Only if the profile marks them
+    hits_width: usize,
+    diagnostic_lines: HashMap<LineNumber, Severity>, // Most severe diagnostic of each line
+    copy_anchor: Option<Range<usize>>,               // Line numbers where the copy mode was entered
+    profile: RenderProfile,
+}
+
+impl SourceDecorator {
+    pub fn new<'a, I: Iterator<Item = &'a BreakPoint>>(
+        file: &Path,
+        stop_position: Option<LineNumber>,
+        breakpoints: I,
+        diagnostics: &[Diagnostic],
+        profile: RenderProfile,
+    ) -> Self {
+        let mut diagnostic_lines = HashMap::new();
+        for d in diagnostics.iter().filter(|d| d.pos.file == file) {
+            let severity = diagnostic_lines.entry(d.pos.line).or_insert(d.severity);
+            *severity = d.severity.max(*severity);
+        }
+        let mut lines = HashMap::new();
+        let mut disabled_lines = HashSet::new();
+        for bp in breakpoints {
+            if let Some(ref pos) = bp.src_pos {
+                if pos.file != file {
+                    continue;
+                }
+                if bp.enabled {
+                    *lines.entry(pos.line).or_insert(0) += bp.hit_count;
+                } else if profile.marks_disabled_breakpoints() {
+                    disabled_lines.insert(pos.line);
+                }
+            }
+        }
+        SourceDecorator {
+            stop_position: stop_position,
+            hits_width: hits_width(lines.values()),
+            breakpoint_lines: lines,
+            disabled_lines,
+            diagnostic_lines,
+            copy_anchor: None,
+            profile,
+        }
+    }
+
+    /// Invert the line numbers of the lines between the anchor and the current line.
+    pub fn with_copy_anchor(mut self, copy_anchor: Option<Range<usize>>) -> Self {
+        self.copy_anchor = copy_anchor;
+        self
+    }
+
+    // The line number of the current line is not known, so the lines above (below) it are
+    // selected if they are below (above) the start (end) of the anchor.
+    fn is_selected(
+        &self,
+        folded_range: &Range<usize>,
+        current_index: LineIndex,
+        active_index: LineIndex,
+    ) -> bool {
+        match &self.copy_anchor {
+            Some(_) if current_index == active_index => true,
+            Some(anchor) if current_index < active_index => folded_range.start >= anchor.start,
+            Some(anchor) => folded_range.end <= anchor.end,
+            None => false,
+        }
+    }
+}
+
+impl LineDecorator for SourceDecorator {
+    type Line = SourceLine;
+    fn horizontal_space_demand<'a, 'b: 'a>(
+        &'a self,
+        lines: impl DoubleEndedIterator<Item = (LineIndex, &'b Self::Line)> + 'b,
+    ) -> ColDemand {
+        let max_space = lines
+            .last()
+            .map(|(_, l)| text_width(format!(" {} ", l.number).as_str()))
+            .unwrap_or(Width::new(0).unwrap());
+        Demand::exact(max_space + Width::new(self.hits_width as i32).unwrap())
+    }
+    fn decorate(
+        &self,
+        line: &Self::Line,
+        current_index: LineIndex,
+        active_index: LineIndex,
+        mut window: Window,
+    ) {
+        let width = (window.get_width() - 2 - self.hits_width as i32).positive_or_zero();
+        let line_number = line.number;
+        // Markers of all lines hidden in a fold are shown at the line representing the fold.
+        let folded_range = line.folded_range();
+        if self.is_selected(&folded_range, current_index, active_index) {
+            window.modify_default_style(StyleModifier::new().invert(BoolModifyMode::Toggle));
+            window.fill(GraphemeCluster::space());
+        }
+        let mut cursor = Cursor::new(&mut window).position(ColIndex::new(0), RowIndex::new(0));
+
+        let at_stop_position = self
+            .stop_position
+            .map(|p| folded_range.contains(&p.raw_value()))
+            .unwrap_or(false);
+        let hits = self
+            .breakpoint_lines
+            .iter()
+            .filter(|(l, _)| folded_range.contains(&l.raw_value()))
+            .map(|(_, &hits)| hits)
+            .fold(None, |sum: Option<u64>, hits| Some(sum.unwrap_or(0) + hits));
+        let at_breakpoint_position = hits.is_some();
+
+        let at_disabled_breakpoint = self
+            .disabled_lines
+            .iter()
+            .any(|l| folded_range.contains(&l.raw_value()));
+        let (right_border, style_modifier) = marker(
+            self.profile,
+            at_stop_position,
+            at_breakpoint_position,
+            at_disabled_breakpoint,
+        );
+
+        // Diagnostics are shown in front of the line number.
+        let diagnostic = self
+            .diagnostic_lines
+            .iter()
+            .filter(|(l, _)| folded_range.contains(&l.raw_value()))
+            .map(|(_, &s)| s)
+            .max();
+        let (left_border, diagnostic_style) = match diagnostic {
+            Some(Severity::Error) => ('E', StyleModifier::new().fg_color(Color::Red).bold(true)),
+            Some(Severity::Warning) => ('W', StyleModifier::new().fg_color(Color::Yellow)),
+            Some(Severity::Note) => ('N', StyleModifier::new().fg_color(Color::Blue)),
+            None => (' ', StyleModifier::new()),
+        };
+
+        use std::fmt::Write;
+        cursor.set_style_modifier(diagnostic_style);
+        write!(cursor, "{}", left_border).unwrap();
+        write_hits(&mut cursor, hits, self.hits_width);
+        cursor.set_style_modifier(style_modifier);
+        write!(
+            cursor,
+            "{:width$}{}",
+            line_number,
+            right_border,
+            width = width.into()
+        )
+        .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hits_width() {
+        assert_eq!(hits_width([].iter()), 0);
+        assert_eq!(hits_width([0, 0].iter()), 0);
+        assert_eq!(hits_width([3, 120, 0].iter()), 4);
+    }
+
+    #[test]
+    fn test_opcodes_width() {
+        let line = |opcodes: Option<&str>| {
+            AssemblyLine::new(
+                "nop".to_owned(),
+                Address(0),
+                None,
+                None,
+                opcodes.map(|o| o.to_owned()),
+            )
+        };
+        assert_eq!(opcodes_width([].iter()), 0);
+        assert_eq!(
+            opcodes_width([line(Some("55")), line(None), line(Some("48 89 e5"))].iter()),
+            8
+        );
+    }
+
+    #[test]
+    fn test_copy_selection() {
+        let decorator = SourceDecorator::new(
+            Path::new("main.c"),
+            None,
+            [].iter(),
+            &[],
+            RenderProfile::default(),
+        );
+        let index = LineIndex::new;
+        assert!(!decorator.is_selected(&(5..6), index(4), index(4)));
+        // Anchor at line 5 (folded up to line 7), current line at index 9.
+        let decorator = decorator.with_copy_anchor(Some(5..8));
+        assert!(decorator.is_selected(&(5..8), index(4), index(9)));
+        assert!(decorator.is_selected(&(12..13), index(9), index(9)));
+        assert!(!decorator.is_selected(&(4..5), index(3), index(9)));
+        assert!(!decorator.is_selected(&(13..14), index(10), index(9)));
+        // Current line above the anchor.
+        assert!(decorator.is_selected(&(6..7), index(5), index(2)));
+        assert!(!decorator.is_selected(&(8..9), index(6), index(2)));
+    }
+}
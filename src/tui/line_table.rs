@@ -0,0 +1,138 @@
+use super::list_filter::FilterableRow;
+use super::list_view::ListView;
+use gdb::{Address, FunctionLineTable, LineTableEntry};
+use std::path::PathBuf;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::{Column, TableRow};
+use unsegen::widget::Widget;
+
+#[derive(Clone)]
+pub struct LineTableRow {
+    entry: LineTableEntry,
+    line: String,
+    range: String,
+    size: String,
+    is_stmt: &'static str,
+}
+
+impl TableRow for LineTableRow {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<LineTableRow>] = &[
+        Column {
+            access: |r| Box::new(r.line.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.range.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.size.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.is_stmt),
+            behavior: |_, input, _| Some(input),
+        },
+    ];
+}
+
+impl FilterableRow for LineTableRow {
+    fn cell_text(&self, column: usize) -> &str {
+        match column {
+            0 => &self.line,
+            1 => &self.range,
+            2 => &self.size,
+            _ => self.is_stmt,
+        }
+    }
+}
+
+/// Mapping of source lines to address ranges for the function of the selected frame.
+pub struct LineTableView {
+    list: ListView<LineTableRow>,
+    file: Option<PathBuf>,
+}
+
+impl LineTableView {
+    pub fn new() -> Self {
+        LineTableView {
+            list: ListView::new(
+                "No line table loaded. Use !linetable to show the current function.",
+            ),
+            file: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.list.lines()
+    }
+
+    pub fn set_line_table(
+        &mut self,
+        line_table: FunctionLineTable,
+        format_address: impl Fn(Address) -> String,
+    ) {
+        let entries = &line_table.entries;
+        let rows = entries
+            .iter()
+            .map(|e| {
+                let num_ranges_of_line = entries.iter().filter(|o| o.line == e.line).count();
+                LineTableRow {
+                    line: if num_ranges_of_line > 1 {
+                        format!("{} ({} ranges)", e.line, num_ranges_of_line)
+                    } else {
+                        e.line.to_string()
+                    },
+                    range: format!("{}-{}", format_address(e.begin), format_address(e.end)),
+                    size: (e.end.0.saturating_sub(e.begin.0)).to_string(),
+                    is_stmt: match e.is_stmt {
+                        Some(true) => "stmt",
+                        Some(false) => "",
+                        None => "?",
+                    },
+                    entry: e.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+        self.list.set_header(format!(
+            "{} ({}): {} entries",
+            line_table.function,
+            line_table.file.display(),
+            rows.len()
+        ));
+        self.file = Some(line_table.file);
+        self.list.reset_rows(rows);
+    }
+
+    fn show_line(row: Option<&LineTableRow>, file: &Option<PathBuf>, p: &mut ::Context) {
+        if let (Some(row), Some(file)) = (row, file) {
+            p.show_file(file.to_string_lossy().into_owned(), row.entry.line);
+            p.focus_container(::tui::TuiContainerType::SrcView);
+        }
+    }
+}
+
+impl Container<::Context> for LineTableView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        let file = &self.file;
+        self.list.input(input, |row, input| {
+            input
+                .chain((Key::Char('\n'), || Self::show_line(row, file, p)))
+                .finish()
+        })
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        self.list.as_widget()
+    }
+}
@@ -0,0 +1,265 @@
+// Front-end for screen readers (`--linear`): Instead of drawing the containers on the whole
+// screen, the output of gdb and of the program is printed line by line together with
+// announcements of changes of the state (e.g., where the program stopped or which values in the
+// expression table changed). Input goes to the console.
+use super::toasts::ToastLevel;
+use super::{Tui, TuiContainerType};
+use gdb::Address;
+use gdbmi::types::Frame;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+// Number of lines before and after the current line that are printed for the pager.
+const SOURCE_CONTEXT_LINES: usize = 5;
+
+pub struct LinearOutput {
+    running: bool,
+    stops: usize,
+    location: Option<String>, // Of the selected frame
+    values: Vec<(String, Option<String>)>,
+    overlays: usize, // Number of open overlays that have been announced
+}
+
+fn frame_description(frame: &Frame, format_address: impl Fn(Address) -> String) -> String {
+    let function = frame.func.as_deref().unwrap_or("??");
+    match (&frame.file, frame.line) {
+        (Some(file), Some(line)) => format!("{}:{} in {}", file, line, function),
+        _ => match frame.addr {
+            Some(addr) => format!("{} in {}", format_address(Address(addr)), function),
+            None => function.to_owned(),
+        },
+    }
+}
+
+// Lines around `line` (1-based) with line numbers. The current line is marked by `>`.
+fn source_excerpt(path: &Path, line: usize, context: usize) -> Vec<String> {
+    let content = match fs::read(path) {
+        Ok(c) => String::from_utf8_lossy(&c).into_owned(),
+        Err(e) => return vec![format!("Failed to read {}: {}", path.display(), e)],
+    };
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, text)| (i + 1, text))
+        .skip(line.saturating_sub(context + 1))
+        .take(2 * context + 1)
+        .map(|(number, text)| {
+            let marker = if number == line { '>' } else { ' ' };
+            format!("{}{:>5} {}", marker, number, text)
+        })
+        .collect()
+}
+
+/// Announcements of values of expressions that changed, e.g., `x = 2 (was 1)`. Expressions that
+/// were added or could not be evaluated before are not announced.
+pub fn changed_values(
+    previous: &[(String, Option<String>)],
+    current: &[(String, Option<String>)],
+) -> Vec<String> {
+    current
+        .iter()
+        .filter_map(|(expression, value)| {
+            let value = value.as_ref()?;
+            let old = previous
+                .iter()
+                .find(|(e, _)| e == expression)
+                .and_then(|(_, v)| v.as_ref())?;
+            if old == value {
+                None
+            } else {
+                Some(format!("{} = {} (was {})", expression, value, old))
+            }
+        })
+        .collect()
+}
+
+/// The content of a container as plain lines of text.
+pub fn container_text(tui: &mut Tui, container: &TuiContainerType) -> Vec<String> {
+    match container {
+        TuiContainerType::SrcView => match tui.src_view.current_local_position() {
+            Some(pos) => {
+                let mut lines = vec![format!("{}:{}", pos.file.display(), pos.line)];
+                lines.extend(source_excerpt(
+                    &pos.file,
+                    pos.line.into(),
+                    SOURCE_CONTEXT_LINES,
+                ));
+                lines
+            }
+            None => vec!["No source file is shown.".to_owned()],
+        },
+        TuiContainerType::ExpressionTable => tui
+            .expression_table
+            .values()
+            .into_iter()
+            .filter(|(expression, _)| !expression.is_empty())
+            .map(|(expression, value)| match value {
+                Some(value) => format!("{} = {}", expression, value),
+                None => format!("{}: <not available>", expression),
+            })
+            .collect(),
+        TuiContainerType::Console => vec!["The output of gdb is printed as it arrives.".to_owned()],
+        TuiContainerType::Terminal => {
+            vec!["The output of the program is printed as it arrives.".to_owned()]
+        }
+        TuiContainerType::SearchResults => tui.search_results.lines(),
+        TuiContainerType::LineTable => tui.line_table.lines(),
+        TuiContainerType::Inferiors => tui.inferiors.lines(),
+        TuiContainerType::Memory => tui.memory.lines(),
+        TuiContainerType::MemoryMap => tui.memory_map.lines(),
+        TuiContainerType::Breakpoints => tui.breakpoints.lines(),
+        TuiContainerType::Threads => tui.threads.lines(),
+        TuiContainerType::Backtrace => tui.backtrace.lines(),
+        TuiContainerType::Locals => tui.locals.lines(),
+        TuiContainerType::Skips => tui.skips.lines(),
+        TuiContainerType::Libraries => tui.libraries.lines(),
+        TuiContainerType::Trace => tui.trace.lines(),
+    }
+}
+
+impl LinearOutput {
+    pub fn new(tui: &mut Tui) -> Self {
+        tui.console.record_transcript();
+        LinearOutput {
+            running: false,
+            stops: 0,
+            location: None,
+            values: Vec::new(),
+            overlays: 0,
+        }
+    }
+
+    pub fn print(&mut self, text: &str) {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        let _ = stdout.write_all(text.as_bytes());
+        let _ = stdout.flush();
+    }
+
+    fn announce(&mut self, text: impl AsRef<str>) {
+        self.print(&format!("{}\n", text.as_ref()));
+    }
+
+    pub fn toast(&mut self, level: ToastLevel, text: &str) {
+        match level {
+            ToastLevel::Warning => self.announce(format!("Warning: {}", text)),
+            ToastLevel::Error => self.announce(format!("Error: {}", text)),
+            ToastLevel::Info | ToastLevel::Success => self.announce(text),
+        }
+    }
+
+    /// Print a container (e.g., if it was selected using a command).
+    pub fn show_container(&mut self, tui: &mut Tui, container: &TuiContainerType) {
+        self.announce(format!("{}:", container.name()));
+        for line in container_text(tui, container) {
+            self.announce(line);
+        }
+    }
+
+    /// Print the output of gdb and announce changes since the last update.
+    pub fn update(&mut self, tui: &mut Tui, p: &mut ::Context) {
+        let transcript = tui.console.take_transcript();
+        self.print(&transcript);
+
+        let running = p.gdb.mi.is_running();
+        if running && !self.running {
+            self.announce("Running.");
+        }
+        self.running = running;
+        if !running {
+            let format_address = p.address_formatter();
+            let location = p
+                .gdb
+                .snapshot
+                .frame
+                .as_ref()
+                .map(|f| frame_description(f, &format_address));
+            let stops = p.session_stats.stops();
+            if stops != self.stops || location != self.location {
+                if let Some(location) = &location {
+                    let verb = if stops != self.stops {
+                        "Stopped at"
+                    } else {
+                        "Selected frame at"
+                    };
+                    self.announce(format!("{} {}.", verb, location));
+                    let frame = p.gdb.snapshot.frame.as_ref();
+                    if let Some((path, line)) =
+                        frame.and_then(|f| Some((f.fullname.as_ref()?, f.line?)))
+                    {
+                        for line in source_excerpt(path, line as usize, 0) {
+                            self.announce(line);
+                        }
+                    }
+                }
+                self.stops = stops;
+                self.location = location;
+            }
+            let values = tui.expression_table.values();
+            for change in changed_values(&self.values, &values) {
+                self.announce(change);
+            }
+            self.values = values;
+        }
+
+        if tui.overlays.len() > self.overlays {
+            if let Some(overlay) = tui.overlays.top() {
+                let text = format!(
+                    "{}: {}\n({})",
+                    overlay.title(),
+                    overlay.text(),
+                    overlay.hint()
+                );
+                self.announce(text);
+            }
+        }
+        self.overlays = tui.overlays.len();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_changed_values() {
+        let entry = |e: &str, v: Option<&str>| (e.to_owned(), v.map(|v| v.to_owned()));
+        let previous = vec![
+            entry("x", Some("1")),
+            entry("y", Some("2")),
+            entry("z", None),
+        ];
+        let current = vec![
+            entry("x", Some("3")),
+            entry("y", Some("2")),
+            entry("z", Some("4")),
+            entry("w", Some("5")),
+        ];
+        assert_eq!(
+            changed_values(&previous, &current),
+            vec!["x = 3 (was 1)".to_owned()]
+        );
+    }
+
+    #[test]
+    fn test_frame_description() {
+        let mut frame = Frame {
+            func: Some("main".to_owned()),
+            file: Some("main.c".to_owned()),
+            line: Some(12),
+            addr: Some(0x401136),
+            ..Frame::default()
+        };
+        let format_address = |a: Address| a.to_string();
+        assert_eq!(
+            frame_description(&frame, format_address),
+            "main.c:12 in main"
+        );
+        frame.file = None;
+        assert_eq!(
+            frame_description(&frame, format_address),
+            "0x401136 in main"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::cmp::Ordering;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::TableRow;
+
+/// Rows of list panels that can be filtered and sorted by the text of their columns.
+pub trait FilterableRow: TableRow {
+    fn cell_text(&self, column: usize) -> &str;
+}
+
+// Cells starting with a number (decimal or hexadecimal with `0x` prefix) are sorted by its value
+// and before all other cells, which are sorted as text.
+fn sort_key(cell: &str) -> (bool, u64, &str) {
+    let cell = cell.trim();
+    let number = if let Some(hex) = cell.strip_prefix("0x") {
+        let end = hex
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(hex.len());
+        u64::from_str_radix(&hex[..end], 16).ok()
+    } else {
+        let end = cell
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(cell.len());
+        cell[..end].parse::<u64>().ok()
+    };
+    (number.is_none(), number.unwrap_or(0), cell)
+}
+
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    sort_key(a).cmp(&sort_key(b))
+}
+
+/// Filter (`/pattern`) and sort order (by column number `1`-`9`, `0` for the original order) of a
+/// list panel.
+#[derive(Default)]
+pub struct ListFilter {
+    pattern: String,
+    editing: bool,
+    sort_column: Option<usize>,
+    descending: bool,
+}
+
+impl ListFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Rows match if any of their cells contains the pattern (ignoring case).
+    fn matches<R: FilterableRow>(&self, row: &R) -> bool {
+        let pattern = self.pattern.to_lowercase();
+        (0..R::num_columns()).any(|c| row.cell_text(c).to_lowercase().contains(&pattern))
+    }
+
+    /// Select the rows that match the filter in the current sort order.
+    pub fn apply<'r, R: FilterableRow>(&self, rows: &'r [R]) -> Vec<&'r R> {
+        let mut rows = rows.iter().filter(|r| self.matches(*r)).collect::<Vec<_>>();
+        if let Some(column) = self.sort_column {
+            // Stable, so that rows with equal cells keep their original order.
+            rows.sort_by(|a, b| {
+                let ordering = compare_cells(a.cell_text(column), b.cell_text(column));
+                if self.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        rows
+    }
+
+    /// Handle keys that change the filter or the sort order. Returns the input if it was not
+    /// consumed and whether the rows have to be filtered again.
+    pub fn input<R: FilterableRow>(&mut self, input: Input) -> (Option<Input>, bool) {
+        let key = match input.event {
+            ::unsegen::input::Event::Key(key) => key,
+            _ => return (Some(input), false),
+        };
+        if self.editing {
+            match key {
+                Key::Char('\n') => self.editing = false,
+                Key::Backspace => {
+                    if self.pattern.pop().is_none() {
+                        self.editing = false;
+                    }
+                }
+                Key::Char(c) => self.pattern.push(c),
+                _ => return (Some(input), false),
+            }
+            return (None, true);
+        }
+        match key {
+            Key::Char('/') => {
+                self.pattern.clear();
+                self.editing = true;
+            }
+            Key::Char('0') => self.sort_column = None,
+            Key::Char(c @ '1'..='9') => {
+                let column = c as usize - '1' as usize;
+                if column >= R::num_columns() {
+                    return (Some(input), false);
+                }
+                if self.sort_column == Some(column) {
+                    self.descending = !self.descending;
+                } else {
+                    self.sort_column = Some(column);
+                    self.descending = false;
+                }
+            }
+            _ => return (Some(input), false),
+        }
+        (None, true)
+    }
+
+    /// Short description of the filter and sort order for the header of the panel.
+    pub fn description(&self) -> String {
+        let mut description = String::new();
+        if self.editing {
+            description += &format!(" [/{}_]", self.pattern);
+        } else if !self.pattern.is_empty() {
+            description += &format!(" [/{}]", self.pattern);
+        }
+        if let Some(column) = self.sort_column {
+            description += &format!(
+                " [column {} {}]",
+                column + 1,
+                if self.descending { "▼" } else { "▲" }
+            );
+        }
+        description
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use unsegen::input::Event;
+    use unsegen::widget::builtin::Column;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Row(&'static str, &'static str);
+
+    impl TableRow for Row {
+        type BehaviorContext = ();
+        const COLUMNS: &'static [Column<Row>] = &[
+            Column {
+                access: |r| Box::new(r.0),
+                behavior: |_, input, _| Some(input),
+            },
+            Column {
+                access: |r| Box::new(r.1),
+                behavior: |_, input, _| Some(input),
+            },
+        ];
+    }
+
+    impl FilterableRow for Row {
+        fn cell_text(&self, column: usize) -> &str {
+            match column {
+                0 => self.0,
+                _ => self.1,
+            }
+        }
+    }
+
+    fn press(filter: &mut ListFilter, keys: &str) {
+        for c in keys.chars() {
+            let input = Input {
+                event: Event::Key(Key::Char(c)),
+                raw: Vec::new(),
+            };
+            let (rest, _) = filter.input::<Row>(input);
+            assert!(rest.is_none(), "key {:?} was not consumed", c);
+        }
+    }
+
+    #[test]
+    fn test_sort_key() {
+        assert_eq!(compare_cells("9", "10"), Ordering::Less);
+        assert_eq!(compare_cells("0x10", "0x9"), Ordering::Greater);
+        assert_eq!(compare_cells("3 (2 ranges)", "12"), Ordering::Less);
+        assert_eq!(compare_cells("123", "main"), Ordering::Less);
+        assert_eq!(compare_cells("bar", "foo"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_filter_and_sort() {
+        let rows = [
+            Row("2", "main"),
+            Row("10", "Worker"),
+            Row("1", "worker"),
+            Row("3", "idle"),
+        ];
+        let mut filter = ListFilter::new();
+        assert_eq!(filter.apply(&rows).len(), 4);
+
+        press(&mut filter, "/work");
+        assert_eq!(filter.description(), " [/work_]");
+        assert_eq!(filter.apply(&rows), vec![&rows[1], &rows[2]]);
+
+        press(&mut filter, "\n1");
+        assert_eq!(filter.description(), " [/work] [column 1 ▲]");
+        assert_eq!(filter.apply(&rows), vec![&rows[2], &rows[1]]);
+
+        press(&mut filter, "1");
+        assert_eq!(filter.apply(&rows), vec![&rows[1], &rows[2]]);
+
+        press(&mut filter, "/\n0");
+        assert_eq!(filter.description(), "");
+        assert_eq!(filter.apply(&rows).len(), 4);
+    }
+}
@@ -0,0 +1,128 @@
+use super::list_filter::{FilterableRow, ListFilter};
+use unsegen::base::{Color, GraphemeCluster, StyleModifier};
+use unsegen::input::{Input, Key, Navigatable, NavigateBehavior, ScrollBehavior};
+use unsegen::widget::builtin::Table;
+use unsegen::widget::{SeparatingStyle, VLayout, Widget};
+
+/// Table with a line of text above it that can be scrolled, filtered and sorted. This is the base
+/// of all list containers (e.g., breakpoints or threads), which only have to provide the rows
+/// and the actions that can be applied to the selected row.
+pub struct ListView<R: FilterableRow + Clone> {
+    rows: Vec<R>,
+    table: Table<R>,
+    filter: ListFilter,
+    header: String,
+}
+
+impl<R: FilterableRow + Clone> ListView<R> {
+    pub fn new(header: impl Into<String>) -> Self {
+        ListView {
+            rows: Vec::new(),
+            table: Table::new(),
+            filter: ListFilter::new(),
+            header: header.into(),
+        }
+    }
+
+    /// Number of rows (regardless of the filter).
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether there are no rows (regardless of the filter).
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn set_header(&mut self, header: impl Into<String>) {
+        self.header = header.into();
+    }
+
+    /// Replace all rows. The cursor stays in the same line if possible.
+    pub fn set_rows(&mut self, rows: Vec<R>) {
+        self.rows = rows;
+        self.refill();
+    }
+
+    /// Replace all rows and move the cursor to the first one.
+    pub fn reset_rows(&mut self, rows: Vec<R>) {
+        self.table = Table::new();
+        self.set_rows(rows);
+    }
+
+    fn refill(&mut self) {
+        let previous_row = self
+            .table
+            .current_row()
+            .map(|c| c as *const R)
+            .and_then(|c| self.table.rows().iter().position(|r| ::std::ptr::eq(r, c)))
+            .unwrap_or(0);
+        self.table = Table::new();
+        self.table
+            .rows_mut()
+            .extend(self.filter.apply(&self.rows).into_iter().cloned());
+        for _ in 0..previous_row {
+            let _ = self.table.move_down();
+        }
+    }
+
+    /// Handle input in the following order: changes of the filter or sort order, the actions of
+    /// the container (which get the selected row), and finally movement of the cursor.
+    pub fn input(
+        &mut self,
+        input: Input,
+        actions: impl FnOnce(Option<&R>, Input) -> Option<Input>,
+    ) -> Option<Input> {
+        let (input, filter_changed) = self.filter.input::<R>(input);
+        if filter_changed {
+            self.refill();
+        }
+        actions(self.table.current_row(), input?)?
+            .chain(
+                ScrollBehavior::new(&mut self.table)
+                    .forwards_on(Key::Down)
+                    .forwards_on(Key::Char('j'))
+                    .backwards_on(Key::Up)
+                    .backwards_on(Key::Char('k'))
+                    .to_beginning_on(Key::Home)
+                    .to_end_on(Key::End),
+            )
+            .chain(
+                NavigateBehavior::new(&mut self.table)
+                    .left_on(Key::Left)
+                    .right_on(Key::Right),
+            )
+            .finish()
+    }
+
+    /// The header and the (filtered) rows as plain text with the cells separated by commas.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{}{}", self.header, self.filter.description())];
+        lines.extend(self.filter.apply(&self.rows).into_iter().map(|r| {
+            (0..R::num_columns())
+                .map(|c| r.cell_text(c).trim())
+                .filter(|c| !c.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }));
+        lines
+    }
+
+    pub fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        Box::new(
+            VLayout::new()
+                .widget(format!("{}{}", self.header, self.filter.description()))
+                .widget(
+                    self.table
+                        .as_widget()
+                        .row_separation(SeparatingStyle::AlternatingStyle(
+                            StyleModifier::new().bg_color(Color::Black),
+                        ))
+                        .col_separation(SeparatingStyle::Draw(
+                            GraphemeCluster::try_from('│').unwrap(),
+                        ))
+                        .focused(StyleModifier::new().bold(true)),
+                ),
+        )
+    }
+}
@@ -0,0 +1,350 @@
+use super::event_bus::{DebugEvent, Subscriber};
+use super::expression_table::{
+    change_print_settings, copy_to_clipboard, next_format, FullValueView,
+};
+use crate::gdb_expression_parsing::{self, Format};
+use gdb::response::GDBResponseError;
+use gdb::PrintSettings;
+use gdbmi::types::Variable;
+use log::warn;
+use number_format::NumberFormat;
+use std::collections::HashMap;
+use unsegen::base::{Color, GraphemeCluster, StyleModifier};
+use unsegen::container::Container;
+use unsegen::input::{Input, Key, NavigateBehavior, ScrollBehavior};
+use unsegen::widget::builtin::{Column, Table, TableRow};
+use unsegen::widget::{SeparatingStyle, VLayout, Widget};
+use unsegen_jsonviewer::JsonViewer;
+
+pub struct LocalRow {
+    name: String,
+    arg: bool,
+    header: bool, // "Arguments" or "Locals"
+    value: Option<String>,
+    result: JsonViewer,
+    format: Option<Format>,
+}
+
+impl LocalRow {
+    fn header(title: &str) -> Self {
+        LocalRow {
+            name: title.to_owned(),
+            arg: false,
+            header: true,
+            value: None,
+            result: JsonViewer::new("────────"),
+            format: None,
+        }
+    }
+
+    fn variable(name: String, arg: bool) -> Self {
+        LocalRow {
+            name,
+            arg,
+            header: false,
+            value: None,
+            result: JsonViewer::new(" "),
+            format: None,
+        }
+    }
+
+    // Updating (instead of replacing) the viewer keeps expanded members expanded and highlights
+    // changed values.
+    fn show_value(&mut self, truncation: Option<usize>, numbers: NumberFormat) {
+        let value = match &self.value {
+            Some(v) => v,
+            None => {
+                self.result.update("<no value>");
+                return;
+            }
+        };
+        match gdb_expression_parsing::parse_gdb_value(value) {
+            Ok(n) => self.result.update(gdb_expression_parsing::Value {
+                node: &n,
+                format: self.format,
+                truncation,
+                numbers,
+            }),
+            Err(_) => self
+                .result
+                .update(format!("*Error parsing*: {}", value).as_str()),
+        }
+    }
+}
+
+impl TableRow for LocalRow {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<LocalRow>] = &[
+        Column {
+            access: |r| Box::new(r.name.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.result.as_widget()),
+            behavior: |r, input, _| {
+                input
+                    .chain(
+                        ScrollBehavior::new(&mut r.result)
+                            .forwards_on(Key::PageDown)
+                            .backwards_on(Key::PageUp)
+                            .forwards_on(Key::Down)
+                            .backwards_on(Key::Up)
+                            .to_beginning_on(Key::Home)
+                            .to_end_on(Key::End),
+                    )
+                    .chain(|evt: Input| {
+                        if evt.matches(Key::Char(' ')) {
+                            if r.result.toggle_active_element().is_ok() {
+                                None
+                            } else {
+                                Some(evt)
+                            }
+                        } else {
+                            Some(evt)
+                        }
+                    })
+                    .finish()
+            },
+        },
+    ];
+}
+
+/// Arguments and locals of the selected frame, which are listed again whenever the program stops
+/// or another frame is selected. Values are shown like in the expression table, but rows cannot
+/// be added or edited.
+pub struct LocalsView {
+    table: Table<LocalRow>,
+    num_variables: usize,
+    message: Option<String>, // Shown instead of the table, e.g., if there is no frame
+    print_settings: Option<PrintSettings>,
+    truncation: Option<usize>,
+    numbers: NumberFormat,
+    // Formats chosen for variables (by name), which are kept when the program stops again.
+    formats: HashMap<String, Format>,
+    full_value: Option<FullValueView>,
+}
+
+impl LocalsView {
+    pub fn new(truncation: Option<usize>, numbers: NumberFormat) -> Self {
+        LocalsView {
+            table: Table::new(),
+            num_variables: 0,
+            message: Some("No frame selected.".to_owned()),
+            print_settings: None,
+            truncation,
+            numbers,
+            formats: HashMap::new(),
+            full_value: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.num_variables
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn lines(&mut self) -> Vec<String> {
+        if let Some(message) = &self.message {
+            return vec![message.clone()];
+        }
+        self.table
+            .rows_mut()
+            .iter()
+            .map(|r| match (r.header, &r.value) {
+                (true, _) => format!("{}:", r.name),
+                (false, Some(value)) => format!("{} = {}", r.name, value),
+                (false, None) => format!("{}: <no value>", r.name),
+            })
+            .collect()
+    }
+
+    pub fn update(&mut self, p: &mut ::Context) {
+        if let Ok(settings) = p.gdb.get_print_settings() {
+            self.print_settings = Some(settings);
+        }
+        match p.gdb.get_frame_variables() {
+            Ok(variables) => {
+                self.message = None;
+                self.set_variables(variables);
+            }
+            // E.g., "No frame selected."
+            Err(GDBResponseError::Other(msg)) => {
+                self.message = Some(msg);
+                self.num_variables = 0;
+                self.table.rows_mut().clear();
+            }
+            // The previous values stay (marked as stale) if gdb is busy.
+            Err(e) => warn!("Failed to list variables: {:?}", e),
+        }
+    }
+
+    fn set_variables(&mut self, variables: Vec<Variable>) {
+        self.num_variables = variables.len();
+        let (args, locals): (Vec<_>, Vec<_>) = variables.into_iter().partition(|v| v.arg);
+        let mut previous = self.table.rows_mut().drain(..).collect::<Vec<_>>();
+        let mut rows = Vec::new();
+        for (title, group) in [("Arguments", args), ("Locals", locals)] {
+            if group.is_empty() {
+                continue;
+            }
+            rows.push(LocalRow::header(title));
+            for variable in group {
+                // Rows of variables that are still there are reused (if the program stopped in
+                // the same function). Shadowed variables appear multiple times in order.
+                let mut row = match previous
+                    .iter()
+                    .position(|r| !r.header && r.arg == variable.arg && r.name == variable.name)
+                {
+                    Some(i) => previous.remove(i),
+                    None => LocalRow::variable(variable.name, variable.arg),
+                };
+                row.format = self.formats.get(&row.name).copied();
+                row.value = variable.value;
+                row.show_value(self.truncation, self.numbers);
+                rows.push(row);
+            }
+        }
+        *self.table.rows_mut() = rows;
+    }
+
+    fn current_variable(&self) -> Option<&LocalRow> {
+        self.table.current_row().filter(|r| !r.header)
+    }
+
+    // The format applies to all variables of that name, e.g., to shadowed ones as well.
+    fn change_format(&mut self) {
+        let name = match self.current_variable() {
+            Some(r) => r.name.clone(),
+            None => return,
+        };
+        let format = next_format(self.formats.get(&name).copied());
+        match format {
+            Some(f) => self.formats.insert(name.clone(), f),
+            None => self.formats.remove(&name),
+        };
+        let (truncation, numbers) = (self.truncation, self.numbers);
+        for row in self
+            .table
+            .rows_mut()
+            .iter_mut()
+            .filter(|r| !r.header && r.name == name)
+        {
+            row.format = format;
+            row.show_value(truncation, numbers);
+        }
+    }
+
+    fn show_full_value(&mut self, p: &mut ::Context) {
+        let name = match self.current_variable() {
+            Some(r) => r.name.clone(),
+            None => return,
+        };
+        match p.gdb.evaluate_full(&name) {
+            Ok(value) => self.full_value = Some(FullValueView::new(name, &value)),
+            Err(e) => p.log(format!("Failed to evaluate \"{}\": {:?}", name, e)),
+        }
+    }
+
+    fn show_in_memory_view(&mut self, p: &mut ::Context) {
+        let name = match self.current_variable() {
+            Some(r) => r.name.clone(),
+            None => return,
+        };
+        match p.gdb.get_address(&name) {
+            Ok(address) => p.show_memory(address),
+            Err(e) => p.log(format!("Failed to get address of \"{}\": {:?}", name, e)),
+        }
+    }
+}
+
+impl Subscriber for LocalsView {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        match event {
+            DebugEvent::Stopped(_)
+            | DebugEvent::MemoryWritten
+            | DebugEvent::PrintSettingsChanged
+            | DebugEvent::SymbolsReloaded => self.update(p),
+            _ => {}
+        }
+    }
+}
+
+impl Container<::Context> for LocalsView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        if let Some(view) = &mut self.full_value {
+            let (rest, stay) = view.input(input);
+            if !stay {
+                self.full_value = None;
+            }
+            return rest;
+        }
+        input
+            .chain((Key::Ctrl('f'), || self.change_format()))
+            .chain((Key::Ctrl('o'), || self.show_full_value(p)))
+            .chain((Key::Alt('m'), || self.show_in_memory_view(p)))
+            .chain((Key::Alt('c'), || {
+                let name = self.current_variable().map(|r| r.name.clone());
+                copy_to_clipboard(p, "name", name)
+            }))
+            .chain((Key::Alt('v'), || {
+                let value = self.current_variable().and_then(|r| r.value.clone());
+                copy_to_clipboard(p, "value", value)
+            }))
+            .chain((Key::Alt('p'), || {
+                change_print_settings(p, self.print_settings.clone(), |s| s.pretty = !s.pretty)
+            }))
+            .chain((Key::Alt('e'), || {
+                change_print_settings(p, self.print_settings.clone(), |s| {
+                    s.elements = s.next_elements()
+                })
+            }))
+            .chain((Key::Alt('a'), || {
+                change_print_settings(p, self.print_settings.clone(), |s| s.array = !s.array)
+            }))
+            .chain((Key::Alt('n'), || {
+                change_print_settings(p, self.print_settings.clone(), |s| {
+                    s.null_stop = !s.null_stop
+                })
+            }))
+            .chain(self.table.current_cell_behavior(p))
+            .chain(
+                NavigateBehavior::new(&mut self.table)
+                    .up_on(Key::Up)
+                    .down_on(Key::Down)
+                    .up_on(Key::Char('k'))
+                    .down_on(Key::Char('j'))
+                    .left_on(Key::Left)
+                    .right_on(Key::Right),
+            )
+            .finish()
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        if let Some(view) = &self.full_value {
+            return Box::new(view.as_widget());
+        }
+        if let Some(message) = &self.message {
+            return Box::new(message.as_str());
+        }
+        let settings = match &self.print_settings {
+            Some(s) => format!("{} (M-p/M-e/M-a/M-n to change)", s),
+            None => "print settings: unknown".to_owned(),
+        };
+        Box::new(
+            VLayout::new().widget(settings).widget(
+                self.table
+                    .as_widget()
+                    .row_separation(SeparatingStyle::AlternatingStyle(
+                        StyleModifier::new().bg_color(Color::Black),
+                    ))
+                    .col_separation(SeparatingStyle::Draw(
+                        GraphemeCluster::try_from('│').unwrap(),
+                    ))
+                    .focused(StyleModifier::new().bold(true)),
+            ),
+        )
+    }
+}
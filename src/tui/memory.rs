@@ -0,0 +1,308 @@
+use super::event_bus::{DebugEvent, Subscriber};
+use super::overlay::Form;
+use super::srcview::{hexdump_lines_with, HEXDUMP_BYTES_PER_LINE};
+use super::stack_frame::{frame_lines, Annotation, MAX_FRAME_BYTES};
+use super::toasts::ToastLevel;
+use gdb::{Address, SnapshotParts};
+use std::path::Path;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key, ScrollBehavior};
+use unsegen::widget::{VLayout, Widget};
+use unsegen_pager::{Pager, PagerContent};
+
+// Number of bytes that are read and shown at once.
+const MEMORY_VIEW_BYTES: usize = 512;
+
+/// Ask for the address, length and file of a memory dump (see `dump_memory`).
+pub fn prompt_dump(address: &str, length: &str, p: &mut ::Context) {
+    p.show_overlay(Form::new(
+        "Dump memory to file",
+        &[("Address", address), ("Length", length), ("File", "")],
+        |values: Vec<String>, p: &mut ::Context| dump_memory(&values[0], &values[1], &values[2], p),
+    ));
+}
+
+/// Write memory to a raw binary file. The address and length may be expressions.
+pub fn dump_memory(address: &str, length: &str, file: &str, p: &mut ::Context) {
+    let address = match p.gdb.evaluate_address(address) {
+        Ok(address) => address,
+        Err(e) => return p.log(format!("Invalid address '{}': {:?}", address, e)),
+    };
+    let length = match p.gdb.evaluate_address(length) {
+        Ok(length) => length.0,
+        Err(e) => return p.log(format!("Invalid length '{}': {:?}", length, e)),
+    };
+    let file = file.trim();
+    if file.is_empty() {
+        return p.log("No file to dump the memory to.");
+    }
+    let format_address = p.address_formatter();
+    match p.gdb.dump_memory(address, length, Path::new(file)) {
+        Ok(()) => p.toast(
+            ToastLevel::Success,
+            format!(
+                "Wrote {} bytes at {} to {}.",
+                length,
+                format_address(address),
+                file
+            ),
+        ),
+        Err(e) => p.log(format!("Failed to dump memory: {:?}", e)),
+    }
+}
+
+/// Ask for the file and address of a memory restore (see `restore_memory`).
+pub fn prompt_restore(address: &str, p: &mut ::Context) {
+    p.show_overlay(Form::new(
+        "Restore memory from file",
+        &[("File", ""), ("Address", address)],
+        |values: Vec<String>, p: &mut ::Context| restore_memory(&values[0], &values[1], p),
+    ));
+}
+
+/// Write the contents of a raw binary file (e.g., a modified dump) to memory at the address.
+pub fn restore_memory(file: &str, address: &str, p: &mut ::Context) {
+    let address = match p.gdb.evaluate_address(address) {
+        Ok(address) => address,
+        Err(e) => return p.log(format!("Invalid address '{}': {:?}", address, e)),
+    };
+    let file = file.trim();
+    let format_address = p.address_formatter();
+    match p.gdb.restore_memory(Path::new(file), address) {
+        Ok(()) => {
+            p.toast(
+                ToastLevel::Success,
+                format!("Restored {} at {}.", file, format_address(address)),
+            );
+            p.publish(DebugEvent::MemoryWritten);
+        }
+        Err(e) => p.log(format!("Failed to restore memory: {:?}", e)),
+    }
+}
+
+/// Hexdump of the memory of the inferior starting at an address or, in frame mode, the annotated
+/// stack memory of the selected frame. The contents are read again after every stop of the
+/// program.
+pub struct MemoryView {
+    address: Option<Address>,
+    frame_mode: bool,
+    pager: Pager<String>,
+    lines: Vec<String>, // Shown in the pager
+    header: String,
+}
+
+impl MemoryView {
+    pub fn new() -> Self {
+        MemoryView {
+            address: None,
+            frame_mode: false,
+            pager: Pager::new(),
+            lines: Vec::new(),
+            header: "No memory loaded. Use !memory <address>, M-m in the expression table or f for the stack frame."
+                .to_owned(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.address.is_none() && !self.frame_mode
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![self.header.clone()];
+        lines.extend(self.lines.iter().cloned());
+        lines
+    }
+
+    pub fn show(&mut self, address: Address, p: &mut ::Context) {
+        self.address = Some(address);
+        self.frame_mode = false;
+        self.refresh(p);
+    }
+
+    /// Show the stack memory of the selected frame (and follow the selected frame from now on).
+    pub fn show_frame(&mut self, p: &mut ::Context) {
+        self.frame_mode = true;
+        self.refresh(p);
+    }
+
+    fn toggle_frame_mode(&mut self, p: &mut ::Context) {
+        self.frame_mode = !self.frame_mode;
+        self.refresh(p);
+    }
+
+    pub fn refresh(&mut self, p: &mut ::Context) {
+        if self.frame_mode {
+            return self.refresh_frame(p);
+        }
+        let address = match self.address {
+            Some(a) => a,
+            None => return,
+        };
+        let format_address = p.address_formatter();
+        let lines = match p.gdb.read_memory(address, MEMORY_VIEW_BYTES) {
+            Ok(bytes) => {
+                self.header = format!(
+                    "{} bytes at {} ([/]: previous/next block, D/R: dump/restore)",
+                    bytes.len(),
+                    format_address(address)
+                );
+                hexdump_lines_with(&bytes, address.0, |a| format_address(Address(a)))
+            }
+            Err(e) => {
+                self.header = format!(
+                    "{} ([/]: previous/next block, D/R: dump/restore)",
+                    format_address(address)
+                );
+                vec![format!("Failed to read memory: {:?}", e)]
+            }
+        };
+        self.lines = lines.clone();
+        self.pager.load(PagerContent::from_lines(lines));
+    }
+
+    // One annotated word per line from the stack pointer up to the frame address.
+    fn refresh_frame(&mut self, p: &mut ::Context) {
+        let format_address = p.address_formatter();
+        let layout = match p.gdb.get_frame_layout() {
+            Ok(layout) => layout,
+            Err(e) => {
+                self.header = "No frame (f: show memory at address)".to_owned();
+                self.lines = vec![format!("Failed to get the frame: {:?}", e)];
+                self.pager
+                    .load(PagerContent::from_lines(self.lines.clone()));
+                return;
+            }
+        };
+        let sp = layout.sp;
+        self.address = Some(sp);
+        let length = match layout.cfa {
+            Some(cfa) if cfa > sp => (cfa.0 - sp.0).min(MAX_FRAME_BYTES),
+            _ => MEMORY_VIEW_BYTES,
+        };
+        let word_size = p.gdb.pointer_size().unwrap_or(8);
+        let mut annotations = layout
+            .saved_registers
+            .iter()
+            .map(|(register, address)| {
+                Annotation::new(*address, word_size, format!("saved {}", register))
+            })
+            .collect::<Vec<_>>();
+        // Registers (e.g., the frame pointer) that point into the frame. They are only queried at
+        // a stop if the memory view is shown.
+        p.gdb.complete_snapshot(&SnapshotParts {
+            registers: true,
+            ..SnapshotParts::default()
+        });
+        if let Ok(registers) = &p.gdb.snapshot.registers {
+            for (register, value) in registers {
+                match Address::parse(value) {
+                    Ok(address) if sp <= address && address.0 < sp.0 + length => {
+                        annotations.push(Annotation::new(address, 1, format!("${}", register)))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        // Variables in registers have no address.
+        for variable in p.gdb.get_frame_variables().unwrap_or_default() {
+            if let Ok(address) = p.gdb.get_address(&variable.name) {
+                let size = p
+                    .gdb
+                    .evaluate_address(&format!("sizeof({})", variable.name))
+                    .map_or(1, |s| s.0);
+                let kind = if variable.arg { "argument" } else { "local" };
+                annotations.push(Annotation::new(
+                    address,
+                    size,
+                    format!("{} ({})", variable.name, kind),
+                ));
+            }
+        }
+        let canary = p.gdb.get_stack_canary();
+        self.header = format!(
+            "Frame from {} to {} ({} bytes, f: show memory at address)",
+            format_address(sp),
+            format_address(Address(sp.0 + length)),
+            length
+        );
+        self.lines = match p.gdb.read_memory(sp, length) {
+            Ok(bytes) => frame_lines(&bytes, sp, word_size, &annotations, canary, &format_address),
+            Err(e) => vec![format!("Failed to read memory: {:?}", e)],
+        };
+        self.pager
+            .load(PagerContent::from_lines(self.lines.clone()));
+    }
+
+    fn move_block(&mut self, forwards: bool, p: &mut ::Context) {
+        if let Some(address) = self.address {
+            self.frame_mode = false;
+            self.address = Some(if forwards {
+                address + MEMORY_VIEW_BYTES
+            } else {
+                // Keep the lines aligned if we hit the beginning of the address space.
+                Address(
+                    address.0.saturating_sub(MEMORY_VIEW_BYTES) / HEXDUMP_BYTES_PER_LINE
+                        * HEXDUMP_BYTES_PER_LINE,
+                )
+            });
+            self.refresh(p);
+        }
+    }
+
+    fn dump_block(&self, p: &mut ::Context) {
+        if let Some(address) = self.address {
+            prompt_dump(&address.to_string(), &MEMORY_VIEW_BYTES.to_string(), p);
+        }
+    }
+
+    fn restore_block(&self, p: &mut ::Context) {
+        if let Some(address) = self.address {
+            prompt_restore(&address.to_string(), p);
+        }
+    }
+}
+
+impl Subscriber for MemoryView {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        match event {
+            DebugEvent::Stopped(_) | DebugEvent::MemoryWritten => self.refresh(p),
+            _ => {}
+        }
+    }
+}
+
+impl Container<::Context> for MemoryView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        let mut block_change = None;
+        let rest = input
+            .chain((Key::Char(']'), || block_change = Some(true)))
+            .chain((Key::Char('['), || block_change = Some(false)))
+            .chain((Key::Char('f'), || self.toggle_frame_mode(p)))
+            .chain((Key::Char('D'), || self.dump_block(p)))
+            .chain((Key::Char('R'), || self.restore_block(p)))
+            .chain(
+                ScrollBehavior::new(&mut self.pager)
+                    .forwards_on(Key::Down)
+                    .forwards_on(Key::Char('j'))
+                    .forwards_on(Key::PageDown)
+                    .backwards_on(Key::Up)
+                    .backwards_on(Key::Char('k'))
+                    .backwards_on(Key::PageUp)
+                    .to_beginning_on(Key::Home)
+                    .to_end_on(Key::End),
+            )
+            .finish();
+        if let Some(forwards) = block_change {
+            self.move_block(forwards, p);
+        }
+        rest
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        Box::new(
+            VLayout::new()
+                .widget(self.header.as_str())
+                .widget(self.pager.as_widget()),
+        )
+    }
+}
@@ -0,0 +1,239 @@
+use super::event_bus::{DebugEvent, Subscriber};
+use super::list_filter::FilterableRow;
+use super::list_view::ListView;
+use super::memory::prompt_dump;
+use super::overlay::Prompt;
+use super::search_results::SearchMatch;
+use gdb::{Address, MemoryRegion};
+use gdbmi::capabilities::Feature;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::{Column, TableRow};
+use unsegen::widget::Widget;
+
+#[derive(Clone)]
+pub struct MemoryRegionRow {
+    region: MemoryRegion,
+    start: String,
+    end: String,
+    size: String,
+    offset: String,
+    perms: String,
+    objfile: String,
+}
+
+impl TableRow for MemoryRegionRow {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<MemoryRegionRow>] = &[
+        Column {
+            access: |r| Box::new(r.start.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.end.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.size.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.offset.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.perms.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.objfile.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+    ];
+}
+
+impl FilterableRow for MemoryRegionRow {
+    fn cell_text(&self, column: usize) -> &str {
+        match column {
+            0 => &self.start,
+            1 => &self.end,
+            2 => &self.size,
+            3 => &self.offset,
+            4 => &self.perms,
+            _ => &self.objfile,
+        }
+    }
+}
+
+/// Short description of the region that contains an address, e.g., for `!memorymap <address>`.
+pub fn describe_region(
+    region: &MemoryRegion,
+    format_address: impl Fn(Address) -> String,
+) -> String {
+    let objfile = if region.objfile.is_empty() {
+        "anonymous mapping"
+    } else {
+        region.objfile.as_str()
+    };
+    format!(
+        "{}-{} {}{} (offset {:#x})",
+        format_address(region.begin),
+        format_address(region.end),
+        region
+            .perms
+            .as_ref()
+            .map(|p| format!("{} ", p))
+            .unwrap_or_default(),
+        objfile,
+        region.offset
+    )
+}
+
+/// Search the readable regions for a pattern (see `GDB::find_in_memory`) and list the matches in
+/// the search results.
+pub fn search_memory(regions: &[MemoryRegion], pattern: &str, p: &mut ::Context) {
+    let format_address = p.address_formatter();
+    let mut matches = Vec::new();
+    for region in regions
+        .iter()
+        .filter(|r| r.perms.as_ref().is_none_or(|perms| perms.contains('r')))
+    {
+        match p.gdb.find_in_memory(region.begin, region.end, pattern) {
+            Ok(found) => matches.extend(found.into_iter().map(|(address, symbol)| {
+                let text = match (symbol.is_empty(), region.objfile.is_empty()) {
+                    (false, false) => format!("{} in {}", symbol, region.objfile),
+                    (false, true) => symbol,
+                    (true, _) => region.objfile.clone(),
+                };
+                SearchMatch::memory(address, format_address(address), text)
+            })),
+            Err(e) => {
+                return p.log(format!(
+                    "Failed to search for '{}' in {}: {:?}",
+                    pattern,
+                    describe_region(region, &format_address),
+                    e
+                ))
+            }
+        }
+    }
+    p.log(format!(
+        "Found {} matches for '{}' in memory.\n",
+        matches.len(),
+        pattern
+    ));
+    p.set_search_results(matches);
+}
+
+/// Mapped memory regions of the inferior (`info proc mappings`), which are listed again whenever
+/// the program stops.
+pub struct MemoryMapView {
+    list: ListView<MemoryRegionRow>,
+}
+
+impl MemoryMapView {
+    pub fn new() -> Self {
+        MemoryMapView {
+            list: ListView::new("No process."),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.list.lines()
+    }
+
+    pub fn refresh(&mut self, p: &mut ::Context) {
+        let capabilities = p.gdb.mi.capabilities();
+        if !capabilities.supports(Feature::GdbCommands) {
+            let reason = capabilities.unsupported(Feature::GdbCommands);
+            return self.list.set_header(format!("No memory map: {}", reason));
+        }
+        let format_address = p.address_formatter();
+        match p.gdb.get_memory_map() {
+            Ok(regions) => {
+                self.list.set_header(format!(
+                    "{} region(s) (Enter: show in memory view, f: find in region, D: dump)",
+                    regions.len()
+                ));
+                let rows = regions
+                    .iter()
+                    .map(|r| MemoryRegionRow {
+                        region: r.clone(),
+                        start: format_address(r.begin),
+                        end: format_address(r.end),
+                        size: format!("{:#x}", r.end.0.saturating_sub(r.begin.0)),
+                        offset: format!("{:#x}", r.offset),
+                        perms: r.perms.clone().unwrap_or_default(),
+                        objfile: r.objfile.clone(),
+                    })
+                    .collect();
+                self.list.set_rows(rows);
+            }
+            Err(e) => {
+                self.list.set_header(format!("No memory map: {:?}", e));
+                self.list.set_rows(Vec::new());
+            }
+        }
+    }
+
+    fn show_region(row: Option<&MemoryRegionRow>, p: &mut ::Context) {
+        if let Some(row) = row {
+            p.show_memory(row.region.begin);
+            p.focus_container(::tui::TuiContainerType::Memory);
+        }
+    }
+
+    fn dump_region(row: Option<&MemoryRegionRow>, p: &mut ::Context) {
+        if let Some(row) = row {
+            prompt_dump(&row.region.begin.to_string(), &row.size, p);
+        }
+    }
+
+    fn find_in_region(row: Option<&MemoryRegionRow>, p: &mut ::Context) {
+        if let Some(row) = row {
+            let region = row.region.clone();
+            p.show_overlay(Prompt::new(
+                format!("Find in {} (string, hex bytes or expression)", row.start),
+                "",
+                move |pattern: String, p: &mut ::Context| {
+                    if !pattern.trim().is_empty() {
+                        search_memory(&[region], &pattern, p);
+                    }
+                },
+            ));
+        }
+    }
+}
+
+impl Subscriber for MemoryMapView {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        match event {
+            DebugEvent::Stopped(_) | DebugEvent::SymbolsReloaded => self.refresh(p),
+            _ => {}
+        }
+    }
+}
+
+impl Container<::Context> for MemoryMapView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        self.list.input(input, |row, input| {
+            input
+                .chain((Key::Char('\n'), || Self::show_region(row, p)))
+                .chain((Key::Char('f'), || Self::find_in_region(row, p)))
+                .chain((Key::Char('D'), || Self::dump_region(row, p)))
+                .finish()
+        })
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        self.list.as_widget()
+    }
+}
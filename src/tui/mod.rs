@@ -1,7 +1,33 @@
+pub use ugdb::tui::{branch_arrows, line_decorators, render_profile};
+
+pub mod backtrace;
+pub mod breakpoints;
 pub mod commands;
 pub mod console;
+pub mod error_boundary;
+pub mod event_bus;
 pub mod expression_table;
+pub mod inferiors;
+pub mod libraries;
+pub mod line_table;
+pub mod linear;
+pub mod list_filter;
+pub mod list_view;
+pub mod locals;
+pub mod memory;
+pub mod memory_map;
+pub mod overlay;
+pub mod progress;
+pub mod search_results;
+pub mod skips;
+pub mod sparkline;
 pub mod srcview;
+pub mod stack_frame;
+pub mod status_bar;
+pub mod terminal;
+pub mod threads;
+pub mod toasts;
+pub mod trace;
 pub mod tui;
 
 pub use self::tui::*;
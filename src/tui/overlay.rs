@@ -0,0 +1,751 @@
+use unsegen::base::{ColIndex, Cursor, RowIndex, StyleModifier, Window};
+use unsegen::input::{EditBehavior, Input, Key};
+use unsegen::widget::builtin::LineEdit;
+use unsegen::widget::{HLayout, RenderingHints, VLayout, Widget};
+
+// Popups are not narrower than this (unless the terminal is).
+const MIN_WIDTH: i32 = 40;
+// Number of items of a picker that are shown at once.
+const PICKER_ROWS: usize = 15;
+
+type Action = Box<dyn FnOnce(&mut ::Context) + Send>;
+type Callback<T> = Box<dyn FnOnce(T, &mut ::Context) + Send>;
+
+/// Whether an overlay stays open after handling input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlayStatus {
+    Open,
+    Closed,
+}
+
+/// Modal window that is drawn centered on top of the containers. While an overlay is open, it
+/// receives all input. `Esc` always closes (i.e., cancels) the topmost overlay.
+pub trait Overlay: Send {
+    fn title(&self) -> String;
+    /// Keys that can be used in the overlay, shown below its content.
+    fn hint(&self) -> &'static str;
+    /// The content as plain text, e.g., to be read out in linear mode.
+    fn text(&self) -> String;
+    fn input(&mut self, input: Input, p: &mut ::Context) -> OverlayStatus;
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a>;
+}
+
+impl ::std::fmt::Debug for dyn Overlay {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "Overlay({:?})", self.title())
+    }
+}
+
+/// Text that is shown until it is dismissed.
+pub struct Message {
+    title: String,
+    text: String,
+}
+
+impl Message {
+    pub fn new(title: impl Into<String>, text: impl Into<String>) -> Self {
+        Message {
+            title: title.into(),
+            text: text.into(),
+        }
+    }
+}
+
+impl Overlay for Message {
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+    fn hint(&self) -> &'static str {
+        "Enter/Esc: close"
+    }
+    fn text(&self) -> String {
+        self.text.clone()
+    }
+    fn input(&mut self, input: Input, _: &mut ::Context) -> OverlayStatus {
+        if input.matches(Key::Char('\n')) {
+            OverlayStatus::Closed
+        } else {
+            OverlayStatus::Open
+        }
+    }
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        Box::new(self.text.as_str())
+    }
+}
+
+/// Yes/no question. The action is only executed if the question is answered with yes.
+pub struct Confirmation {
+    title: String,
+    question: String,
+    on_confirm: Option<Action>,
+}
+
+impl Confirmation {
+    pub fn new(
+        title: impl Into<String>,
+        question: impl Into<String>,
+        on_confirm: impl FnOnce(&mut ::Context) + Send + 'static,
+    ) -> Self {
+        Confirmation {
+            title: title.into(),
+            question: question.into(),
+            on_confirm: Some(Box::new(on_confirm)),
+        }
+    }
+}
+
+impl Overlay for Confirmation {
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+    fn hint(&self) -> &'static str {
+        "y/Enter: yes, n/Esc: no"
+    }
+    fn text(&self) -> String {
+        self.question.clone()
+    }
+    fn input(&mut self, input: Input, p: &mut ::Context) -> OverlayStatus {
+        if input.matches(Key::Char('y'))
+            || input.matches(Key::Char('Y'))
+            || input.matches(Key::Char('\n'))
+        {
+            if let Some(on_confirm) = self.on_confirm.take() {
+                on_confirm(p);
+            }
+            OverlayStatus::Closed
+        } else if input.matches(Key::Char('n')) || input.matches(Key::Char('N')) {
+            OverlayStatus::Closed
+        } else {
+            OverlayStatus::Open
+        }
+    }
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        Box::new(self.question.as_str())
+    }
+}
+
+fn edit_line(line: &mut LineEdit, input: Input) {
+    let _ = input.chain(
+        EditBehavior::new(line)
+            .left_on(Key::Left)
+            .right_on(Key::Right)
+            .delete_forwards_on(Key::Delete)
+            .delete_backwards_on(Key::Backspace)
+            .go_to_beginning_of_line_on(Key::Home)
+            .go_to_end_of_line_on(Key::End)
+            .clear_on(Key::Ctrl('c')),
+    );
+}
+
+/// Single line of text that is passed to the action when it is submitted using `Enter`.
+pub struct Prompt {
+    title: String,
+    line: LineEdit,
+    on_submit: Option<Callback<String>>,
+}
+
+impl Prompt {
+    pub fn new(
+        title: impl Into<String>,
+        initial: &str,
+        on_submit: impl FnOnce(String, &mut ::Context) + Send + 'static,
+    ) -> Self {
+        let mut line = LineEdit::new();
+        line.set(initial);
+        line.move_cursor_to_end_of_line();
+        Prompt {
+            title: title.into(),
+            line,
+            on_submit: Some(Box::new(on_submit)),
+        }
+    }
+}
+
+impl Overlay for Prompt {
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+    fn hint(&self) -> &'static str {
+        "Enter: submit, Esc: cancel"
+    }
+    fn text(&self) -> String {
+        self.line.get().to_owned()
+    }
+    fn input(&mut self, input: Input, p: &mut ::Context) -> OverlayStatus {
+        if input.matches(Key::Char('\n')) {
+            if let Some(on_submit) = self.on_submit.take() {
+                on_submit(self.line.get().to_owned(), p);
+            }
+            return OverlayStatus::Closed;
+        }
+        edit_line(&mut self.line, input);
+        OverlayStatus::Open
+    }
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        Box::new(
+            self.line
+                .as_widget()
+                .cursor_blink_on(StyleModifier::new().invert(true))
+                .cursor_inactive(StyleModifier::new().invert(true)),
+        )
+    }
+}
+
+/// Several lines of text (e.g., the commands of a breakpoint) that are passed to the action when
+/// they are submitted using `Ctrl-d`. `Enter` starts a new line.
+pub struct TextEditor {
+    title: String,
+    lines: Vec<LineEdit>,
+    current: usize,
+    on_submit: Option<Callback<Vec<String>>>,
+}
+
+impl TextEditor {
+    pub fn new(
+        title: impl Into<String>,
+        initial: &[String],
+        on_submit: impl FnOnce(Vec<String>, &mut ::Context) + Send + 'static,
+    ) -> Self {
+        let mut editor = TextEditor {
+            title: title.into(),
+            lines: Vec::new(),
+            current: 0,
+            on_submit: Some(Box::new(on_submit)),
+        };
+        for text in initial {
+            let mut line = LineEdit::new();
+            line.set(text.as_str());
+            editor.lines.push(line);
+        }
+        // Start in an empty line after the existing ones.
+        editor.lines.push(LineEdit::new());
+        editor.current = editor.lines.len() - 1;
+        editor
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.lines.iter().map(|l| l.get().to_owned()).collect()
+    }
+
+    fn go_to_line(&mut self, index: usize) {
+        let pos = self.lines[self.current].cursor_pos();
+        self.current = index;
+        let line = &mut self.lines[index];
+        // The byte position may be in the middle of a character of the other line.
+        if line.set_cursor_pos(pos).is_err() {
+            line.move_cursor_to_end_of_line();
+        }
+    }
+
+    // Move the text after the cursor to a new line below the current one.
+    fn split_line(&mut self) {
+        let line = &mut self.lines[self.current];
+        let pos = line.cursor_pos();
+        let text = line.get().to_owned();
+        line.set(&text[..pos]);
+        let mut rest = LineEdit::new();
+        rest.set(&text[pos..]);
+        rest.move_cursor_to_beginning_of_line();
+        self.current += 1;
+        self.lines.insert(self.current, rest);
+    }
+
+    // Append the line below the given one to it and place the cursor at the junction.
+    fn join_with_next(&mut self, index: usize) {
+        let next = self.lines.remove(index + 1);
+        let line = &mut self.lines[index];
+        let pos = line.get().len();
+        let text = format!("{}{}", line.get(), next.get());
+        line.set(text);
+        let _ = line.set_cursor_pos(pos);
+        self.current = index;
+    }
+
+    fn edit(&mut self, input: Input) {
+        let line = &self.lines[self.current];
+        let at_beginning = line.cursor_pos() == 0;
+        let at_end = line.cursor_pos() == line.get().len();
+        if input.matches(Key::Char('\n')) {
+            self.split_line();
+        } else if input.matches(Key::Up) && self.current > 0 {
+            self.go_to_line(self.current - 1);
+        } else if input.matches(Key::Down) && self.current + 1 < self.lines.len() {
+            self.go_to_line(self.current + 1);
+        } else if input.matches(Key::Backspace) && at_beginning && self.current > 0 {
+            self.join_with_next(self.current - 1);
+        } else if input.matches(Key::Delete) && at_end && self.current + 1 < self.lines.len() {
+            self.join_with_next(self.current);
+        } else {
+            edit_line(&mut self.lines[self.current], input);
+        }
+    }
+}
+
+impl Overlay for TextEditor {
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+    fn hint(&self) -> &'static str {
+        "Ctrl-d: submit, Esc: cancel"
+    }
+    fn text(&self) -> String {
+        self.lines().join("\n")
+    }
+    fn input(&mut self, input: Input, p: &mut ::Context) -> OverlayStatus {
+        if input.matches(Key::Ctrl('d')) {
+            if let Some(on_submit) = self.on_submit.take() {
+                on_submit(self.lines(), p);
+            }
+            return OverlayStatus::Closed;
+        }
+        self.edit(input);
+        OverlayStatus::Open
+    }
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        let mut layout = VLayout::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            layout = if i == self.current {
+                layout.widget(
+                    line.as_widget()
+                        .cursor_blink_on(StyleModifier::new().invert(true))
+                        .cursor_inactive(StyleModifier::new().invert(true)),
+                )
+            } else if line.get().is_empty() {
+                // Empty text would not take up a row.
+                layout.widget(" ")
+            } else {
+                layout.widget(line.get())
+            };
+        }
+        Box::new(layout)
+    }
+}
+
+/// Labeled fields (e.g., the address, length and file of a memory dump) whose values are passed to
+/// the action when they are submitted using `Enter`. `Tab`/`Down` and `Up` move between fields.
+pub struct Form {
+    title: String,
+    labels: Vec<String>,
+    fields: Vec<LineEdit>,
+    current: usize,
+    on_submit: Option<Callback<Vec<String>>>,
+}
+
+impl Form {
+    pub fn new(
+        title: impl Into<String>,
+        fields: &[(&str, &str)],
+        on_submit: impl FnOnce(Vec<String>, &mut ::Context) + Send + 'static,
+    ) -> Self {
+        let mut form = Form {
+            title: title.into(),
+            labels: Vec::new(),
+            fields: Vec::new(),
+            current: 0,
+            on_submit: Some(Box::new(on_submit)),
+        };
+        for (label, initial) in fields {
+            let mut field = LineEdit::new();
+            field.set(*initial);
+            field.move_cursor_to_end_of_line();
+            form.labels.push((*label).to_owned());
+            form.fields.push(field);
+        }
+        // Start in the first field that has not been filled in.
+        form.current = form
+            .fields
+            .iter()
+            .position(|f| f.get().is_empty())
+            .unwrap_or(0);
+        form
+    }
+
+    fn values(&self) -> Vec<String> {
+        self.fields.iter().map(|f| f.get().to_owned()).collect()
+    }
+
+    fn edit(&mut self, input: Input) {
+        if input.matches(Key::Char('\t')) || input.matches(Key::Down) {
+            self.current = (self.current + 1) % self.fields.len();
+        } else if input.matches(Key::Up) {
+            self.current = (self.current + self.fields.len() - 1) % self.fields.len();
+        } else {
+            edit_line(&mut self.fields[self.current], input);
+        }
+    }
+}
+
+impl Overlay for Form {
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+    fn hint(&self) -> &'static str {
+        "Tab/Up/Down: next/previous field, Enter: submit, Esc: cancel"
+    }
+    fn text(&self) -> String {
+        self.labels
+            .iter()
+            .zip(self.values())
+            .map(|(label, value)| format!("{}: {}", label, value))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    fn input(&mut self, input: Input, p: &mut ::Context) -> OverlayStatus {
+        if input.matches(Key::Char('\n')) {
+            if let Some(on_submit) = self.on_submit.take() {
+                on_submit(self.values(), p);
+            }
+            return OverlayStatus::Closed;
+        }
+        self.edit(input);
+        OverlayStatus::Open
+    }
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        let label_width = self.labels.iter().map(|l| l.len()).max().unwrap_or(0);
+        let mut layout = VLayout::new();
+        for (i, (label, field)) in self.labels.iter().zip(&self.fields).enumerate() {
+            let label = format!("{:<w$} ", format!("{}:", label), w = label_width + 1);
+            let row = HLayout::new().widget(label);
+            layout = layout.widget(if i == self.current {
+                row.widget(
+                    field
+                        .as_widget()
+                        .cursor_blink_on(StyleModifier::new().invert(true))
+                        .cursor_inactive(StyleModifier::new().invert(true)),
+                )
+            } else {
+                row.widget(field.get())
+            });
+        }
+        Box::new(layout)
+    }
+}
+
+// Indices of the items that contain the filter (ignoring case).
+fn matching_items(items: &[String], filter: &str) -> Vec<usize> {
+    let filter = filter.to_lowercase();
+    items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.to_lowercase().contains(&filter))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// Score of the item if the characters of the filter (without whitespace) occur in it in the same
+// order (ignoring case). Runs of consecutive characters and matches at the start of words score
+// higher, gaps between the matched characters lower.
+fn fuzzy_score(item: &str, filter: &str) -> Option<i64> {
+    let item = item.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut pos = 0;
+    for c in filter.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = pos + item[pos..].iter().position(|&x| x == c)?;
+        score += 1;
+        if pos > 0 && i == pos {
+            score += 4;
+        }
+        if i == 0 || !item[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (i - pos).min(3) as i64;
+        pos = i + 1;
+    }
+    Some(score)
+}
+
+// Indices of the items that fuzzily match the filter, best matches first.
+fn fuzzy_matching_items(items: &[String], filter: &str) -> Vec<usize> {
+    let mut matches = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| fuzzy_score(item, filter).map(|score| (i, score)))
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|&(_, score)| -score);
+    matches.into_iter().map(|(i, _)| i).collect()
+}
+
+/// List of items of which one can be selected. The list is narrowed down by typing. The action
+/// gets the index of the selected item.
+pub struct Picker {
+    title: String,
+    items: Vec<String>,
+    filter: LineEdit,
+    matches: Vec<usize>,
+    selected: usize, // Index into matches
+    fuzzy: bool,
+    on_pick: Option<Callback<usize>>,
+}
+
+impl Picker {
+    pub fn new(
+        title: impl Into<String>,
+        items: Vec<String>,
+        on_pick: impl FnOnce(usize, &mut ::Context) + Send + 'static,
+    ) -> Self {
+        Picker {
+            title: title.into(),
+            matches: (0..items.len()).collect(),
+            items,
+            filter: LineEdit::new(),
+            selected: 0,
+            fuzzy: false,
+            on_pick: Some(Box::new(on_pick)),
+        }
+    }
+
+    /// Match the filter fuzzily (e.g., "tgbp" matches "toggle-breakpoint") and order the items by
+    /// how well they match instead of only keeping those that contain it.
+    pub fn fuzzy(mut self) -> Self {
+        self.fuzzy = true;
+        self
+    }
+}
+
+impl Overlay for Picker {
+    fn title(&self) -> String {
+        format!(
+            "{} ({}/{})",
+            self.title,
+            self.matches.len(),
+            self.items.len()
+        )
+    }
+    fn hint(&self) -> &'static str {
+        "type to filter, Up/Down: select, Enter: pick, Esc: cancel"
+    }
+    fn text(&self) -> String {
+        self.matches
+            .iter()
+            .map(|&i| self.items[i].as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    fn input(&mut self, input: Input, p: &mut ::Context) -> OverlayStatus {
+        if input.matches(Key::Char('\n')) {
+            if let Some(&item) = self.matches.get(self.selected) {
+                if let Some(on_pick) = self.on_pick.take() {
+                    on_pick(item, p);
+                }
+                return OverlayStatus::Closed;
+            }
+        } else if input.matches(Key::Up) {
+            self.selected = self.selected.saturating_sub(1);
+        } else if input.matches(Key::Down) {
+            self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+        } else {
+            edit_line(&mut self.filter, input);
+            self.matches = if self.fuzzy {
+                fuzzy_matching_items(&self.items, self.filter.get())
+            } else {
+                matching_items(&self.items, self.filter.get())
+            };
+            self.selected = 0;
+        }
+        OverlayStatus::Open
+    }
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        let first = self.selected.saturating_sub(PICKER_ROWS - 1);
+        let list = self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(PICKER_ROWS)
+            .map(|(i, &item)| {
+                let marker = if i == self.selected { "> " } else { "  " };
+                format!("{}{}", marker, self.items[item])
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Box::new(
+            VLayout::new()
+                .widget(
+                    self.filter
+                        .as_widget()
+                        .cursor_blink_on(StyleModifier::new().invert(true))
+                        .cursor_inactive(StyleModifier::new().invert(true)),
+                )
+                .widget(list),
+        )
+    }
+}
+
+// Position and size (x, y, width, height) of a popup with the given content size, centered in
+// the window. There is room for the title above and the hint below the content.
+fn popup_area(
+    window_width: i32,
+    window_height: i32,
+    content_width: i32,
+    content_height: i32,
+) -> (i32, i32, i32, i32) {
+    let width = (content_width + 2).max(MIN_WIDTH).min(window_width);
+    let height = (content_height + 2).min(window_height);
+    (
+        (window_width - width) / 2,
+        (window_height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Stack of open overlays of which only the topmost one is shown and receives input.
+#[derive(Default)]
+pub struct Overlays {
+    stack: Vec<Box<dyn Overlay>>,
+}
+
+impl Overlays {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, overlay: Box<dyn Overlay>) {
+        self.stack.push(overlay);
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.stack.is_empty()
+    }
+
+    /// Number of open overlays.
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn top(&self) -> Option<&dyn Overlay> {
+        self.stack.last().map(|o| o.as_ref())
+    }
+
+    pub fn input(&mut self, input: Input, p: &mut ::Context) {
+        let status = match self.stack.last_mut() {
+            None => return,
+            Some(_) if input.matches(Key::Esc) => OverlayStatus::Closed,
+            Some(overlay) => overlay.input(input, p),
+        };
+        if status == OverlayStatus::Closed {
+            self.stack.pop();
+        }
+    }
+
+    pub fn draw(&self, mut window: Window, hints: RenderingHints) {
+        let overlay = match self.stack.last() {
+            Some(overlay) => overlay,
+            None => return,
+        };
+        let content = overlay.as_widget();
+        let demand = content.space_demand();
+        let (x, y, width, height) = popup_area(
+            window.get_width().raw_value(),
+            window.get_height().raw_value(),
+            demand.width.min.raw_value(),
+            demand.height.min.raw_value(),
+        );
+        if height < 3 || width < 2 {
+            return;
+        }
+        let mut popup = window.create_subwindow(
+            ColIndex::new(x)..ColIndex::new(x + width),
+            RowIndex::new(y)..RowIndex::new(y + height),
+        );
+        popup.clear();
+        let (mut title, rest) = match popup.split(RowIndex::new(1)) {
+            Ok(windows) => windows,
+            Err(_) => return,
+        };
+        let (mut body, mut hint) = match rest.split(RowIndex::new(height - 2)) {
+            Ok(windows) => windows,
+            Err(_) => return,
+        };
+        title.modify_default_style(StyleModifier::new().invert(true).bold(true));
+        title.clear();
+        Cursor::new(&mut title).write(&format!(" {}", overlay.title()));
+        hint.modify_default_style(StyleModifier::new().invert(true));
+        hint.clear();
+        Cursor::new(&mut hint).write(&format!(" {}", overlay.hint()));
+        let body_width = body.get_width().from_origin();
+        content.draw(
+            body.create_subwindow(ColIndex::new(1)..body_width, ..),
+            hints.active(true),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matching_items() {
+        let items = vec!["src/main.rs".to_owned(), "src/gdb.rs".to_owned()];
+        assert_eq!(matching_items(&items, ""), vec![0, 1]);
+        assert_eq!(matching_items(&items, "GDB"), vec![1]);
+        assert!(matching_items(&items, "foo").is_empty());
+    }
+
+    #[test]
+    fn test_fuzzy_matching_items() {
+        let items = vec![
+            "!trace-export <file>".to_owned(),
+            "!toggle-breakpoint".to_owned(),
+            "!trace [<function>]".to_owned(),
+            "!memory-map".to_owned(),
+        ];
+        assert_eq!(fuzzy_matching_items(&items, ""), vec![0, 1, 2, 3]);
+        assert_eq!(fuzzy_matching_items(&items, "tgbp"), vec![1]);
+        // Equally good matches keep their order.
+        assert_eq!(fuzzy_matching_items(&items, "trace"), vec![0, 2]);
+        assert_eq!(fuzzy_matching_items(&items, "tr ex"), vec![0]);
+        assert_eq!(fuzzy_matching_items(&items, "MM"), vec![3]);
+        assert!(fuzzy_matching_items(&items, "xyz").is_empty());
+        // Word starts and consecutive characters rank higher.
+        assert!(fuzzy_score("!trace", "trace") > fuzzy_score("!the-trace", "trace"));
+        assert!(fuzzy_score("!step-back", "sb") > fuzzy_score("!disable", "sb"));
+    }
+
+    #[test]
+    fn test_text_editor() {
+        let key = |k| Input {
+            event: ::unsegen::input::Event::Key(k),
+            raw: Vec::new(),
+        };
+        let mut editor = TextEditor::new("", &["print x".to_owned()], |_, _| {});
+        assert_eq!(editor.lines(), vec!["print x", ""]);
+        for c in "continue".chars() {
+            editor.edit(key(Key::Char(c)));
+        }
+        editor.edit(key(Key::Up));
+        editor.edit(key(Key::Left));
+        editor.edit(key(Key::Char('\n')));
+        assert_eq!(editor.lines(), vec!["print ", "x", "continue"]);
+        editor.edit(key(Key::Backspace));
+        assert_eq!(editor.lines(), vec!["print x", "continue"]);
+        editor.edit(key(Key::End));
+        editor.edit(key(Key::Delete));
+        assert_eq!(editor.lines(), vec!["print xcontinue"]);
+    }
+
+    #[test]
+    fn test_form() {
+        let key = |k| Input {
+            event: ::unsegen::input::Event::Key(k),
+            raw: Vec::new(),
+        };
+        let mut form = Form::new("", &[("Address", "0x1000"), ("File", "")], |_, _| {});
+        assert_eq!(form.current, 1);
+        form.edit(key(Key::Char('a')));
+        form.edit(key(Key::Char('\t')));
+        form.edit(key(Key::Backspace));
+        assert_eq!(form.values(), vec!["0x100", "a"]);
+        assert_eq!(form.text(), "Address: 0x100\nFile: a");
+        form.edit(key(Key::Up));
+        assert_eq!(form.current, 1);
+    }
+
+    #[test]
+    fn test_popup_area() {
+        assert_eq!(popup_area(100, 50, 10, 1), (30, 23, 40, 3));
+        assert_eq!(popup_area(100, 50, 80, 10), (9, 19, 82, 12));
+        assert_eq!(popup_area(30, 2, 80, 10), (0, 0, 30, 2));
+    }
+}
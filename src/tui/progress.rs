@@ -0,0 +1,230 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use unsegen::base::{Cursor, StyleModifier, Window};
+use unsegen::widget::RenderingHints;
+
+// Width of the bar itself, i.e., without the brackets.
+const BAR_WIDTH: u64 = 20;
+
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(0);
+
+pub type TaskId = u64;
+
+#[derive(Debug)]
+pub enum ProgressUpdate {
+    Started {
+        title: String,
+        cancel: Option<Arc<AtomicBool>>,
+    },
+    Advanced {
+        done: u64,
+        total: Option<u64>,
+    },
+    Finished,
+}
+
+/// Handle that a long operation uses to report its progress. It can be moved to another thread.
+/// The task is removed from the status line when the handle is dropped.
+pub struct Progress {
+    id: TaskId,
+    sink: Sender<::Event>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Progress {
+    pub fn start(sink: Sender<::Event>, title: String, cancellable: bool) -> Self {
+        let id = NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let cancel = if cancellable {
+            Some(cancelled.clone())
+        } else {
+            None
+        };
+        let _ = sink.send(::Event::Progress(
+            id,
+            ProgressUpdate::Started { title, cancel },
+        ));
+        Progress {
+            id,
+            sink,
+            cancelled,
+        }
+    }
+
+    /// Report that `done` of `total` units of work are done. Without a total, only the number of
+    /// units is shown.
+    pub fn report(&self, done: u64, total: Option<u64>) {
+        let _ = self.sink.send(::Event::Progress(
+            self.id,
+            ProgressUpdate::Advanced { done, total },
+        ));
+    }
+
+    /// Whether the user asked to cancel the operation (only for cancellable tasks). The operation
+    /// itself is responsible for stopping (or at least discarding its result).
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        let _ = self
+            .sink
+            .send(::Event::Progress(self.id, ProgressUpdate::Finished));
+    }
+}
+
+struct Task {
+    id: TaskId,
+    title: String,
+    done: u64,
+    total: Option<u64>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Task {
+    fn status(&self) -> String {
+        let cancelled = self
+            .cancel
+            .as_ref()
+            .map(|c| c.load(Ordering::Relaxed))
+            .unwrap_or(false);
+        let state = match self.total {
+            _ if cancelled => "cancelling...".to_owned(),
+            Some(total) if total > 0 => {
+                let done = self.done.min(total);
+                let filled = (done * BAR_WIDTH / total) as usize;
+                format!(
+                    "[{}{}] {:3}%",
+                    "#".repeat(filled),
+                    "-".repeat(BAR_WIDTH as usize - filled),
+                    done * 100 / total
+                )
+            }
+            _ if self.done > 0 => format!("{}...", self.done),
+            _ => "...".to_owned(),
+        };
+        format!("{} {}", self.title, state)
+    }
+}
+
+/// Long operations (e.g., loading a program onto a target or checking the syntax of a file) that
+/// are currently running. They are shown in a status line at the bottom of the screen.
+#[derive(Default)]
+pub struct ProgressTasks {
+    tasks: Vec<Task>,
+}
+
+impl ProgressTasks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, id: TaskId, update: ProgressUpdate) {
+        match update {
+            ProgressUpdate::Started { title, cancel } => self.tasks.push(Task {
+                id,
+                title,
+                done: 0,
+                total: None,
+                cancel,
+            }),
+            ProgressUpdate::Advanced { done, total } => {
+                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+                    task.done = done;
+                    task.total = total;
+                }
+            }
+            ProgressUpdate::Finished => self.tasks.retain(|t| t.id != id),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.tasks.is_empty()
+    }
+
+    /// Ask all cancellable tasks to stop. Returns the number of tasks that can be cancelled.
+    pub fn cancel_all(&mut self) -> usize {
+        let mut cancelled = 0;
+        for cancel in self.tasks.iter().filter_map(|t| t.cancel.as_ref()) {
+            cancel.store(true, Ordering::Relaxed);
+            cancelled += 1;
+        }
+        cancelled
+    }
+
+    // The most recent task is shown in full, the others are only counted.
+    fn status_line(&self) -> String {
+        let task = match self.tasks.last() {
+            Some(task) => task,
+            None => return String::new(),
+        };
+        let mut line = task.status();
+        if self.tasks.len() > 1 {
+            line.push_str(&format!(" (+{} more)", self.tasks.len() - 1));
+        }
+        if self.tasks.iter().any(|t| t.cancel.is_some()) {
+            line.push_str(" | !cancel to stop");
+        }
+        line
+    }
+
+    pub fn draw(&self, mut window: Window, _hints: RenderingHints) {
+        window.modify_default_style(StyleModifier::new().invert(true));
+        window.clear();
+        Cursor::new(&mut window).write(&format!(" {}", self.status_line()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_status_line() {
+        let mut tasks = ProgressTasks::new();
+        assert!(!tasks.is_active());
+        tasks.update(
+            1,
+            ProgressUpdate::Started {
+                title: "Loading".to_owned(),
+                cancel: None,
+            },
+        );
+        assert_eq!(tasks.status_line(), "Loading ...");
+        tasks.update(
+            1,
+            ProgressUpdate::Advanced {
+                done: 50,
+                total: Some(200),
+            },
+        );
+        assert_eq!(tasks.status_line(), "Loading [#####---------------]  25%");
+        assert_eq!(tasks.cancel_all(), 0);
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        tasks.update(
+            2,
+            ProgressUpdate::Started {
+                title: "Checking".to_owned(),
+                cancel: Some(cancel.clone()),
+            },
+        );
+        assert_eq!(
+            tasks.status_line(),
+            "Checking ... (+1 more) | !cancel to stop"
+        );
+        assert_eq!(tasks.cancel_all(), 1);
+        assert!(cancel.load(Ordering::Relaxed));
+        assert_eq!(
+            tasks.status_line(),
+            "Checking cancelling... (+1 more) | !cancel to stop"
+        );
+
+        tasks.update(2, ProgressUpdate::Finished);
+        tasks.update(1, ProgressUpdate::Finished);
+        assert!(!tasks.is_active());
+    }
+}
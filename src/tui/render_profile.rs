@@ -0,0 +1,116 @@
+// How states (e.g., the stop position and breakpoints in the pager) are marked. The default
+// profile distinguishes some of them by color only (a green or red arrow at the stop position),
+// which does not work on monochrome terminals or for users who cannot tell the colors apart. The
+// other profiles use a distinct glyph for every state.
+use std::fmt;
+use std::str::FromStr;
+use unsegen::base::{Color, StyleModifier};
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum RenderProfile {
+    #[default]
+    Color,
+    /// Distinct glyphs in bright colors.
+    HighContrast,
+    /// Distinct glyphs, emphasized by bold and inverted text instead of colors.
+    Monochrome,
+}
+
+/// Markers next to the line numbers (or addresses) in the pager.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Marker {
+    Stop,
+    StopAtBreakpoint,
+    Breakpoint,
+    DisabledBreakpoint,
+}
+
+impl RenderProfile {
+    pub fn marker(self, marker: Marker) -> (char, StyleModifier) {
+        let style = StyleModifier::new();
+        match (self, marker) {
+            (RenderProfile::Color, Marker::Stop) => ('▶', style.fg_color(Color::Green).bold(true)),
+            (RenderProfile::Color, Marker::StopAtBreakpoint) => {
+                ('▶', style.fg_color(Color::Red).bold(true))
+            }
+            (RenderProfile::Color, Marker::Breakpoint) => ('●', style.fg_color(Color::Red)),
+            (RenderProfile::Color, Marker::DisabledBreakpoint) => (' ', style),
+            (RenderProfile::HighContrast, Marker::Stop) => {
+                ('▶', style.fg_color(Color::LightGreen).bold(true))
+            }
+            (RenderProfile::HighContrast, Marker::StopAtBreakpoint) => {
+                ('◉', style.fg_color(Color::LightYellow).bold(true))
+            }
+            (RenderProfile::HighContrast, Marker::Breakpoint) => {
+                ('●', style.fg_color(Color::LightRed).bold(true))
+            }
+            (RenderProfile::HighContrast, Marker::DisabledBreakpoint) => {
+                ('○', style.fg_color(Color::White))
+            }
+            (RenderProfile::Monochrome, Marker::Stop) => ('▶', style.bold(true)),
+            (RenderProfile::Monochrome, Marker::StopAtBreakpoint) => {
+                ('◉', style.bold(true).invert(true))
+            }
+            (RenderProfile::Monochrome, Marker::Breakpoint) => ('●', style.bold(true)),
+            (RenderProfile::Monochrome, Marker::DisabledBreakpoint) => ('○', style),
+        }
+    }
+
+    /// Whether disabled breakpoints are marked in the pager (the color profile only marks enabled
+    /// ones).
+    pub fn marks_disabled_breakpoints(self) -> bool {
+        self != RenderProfile::Color
+    }
+}
+
+impl fmt::Display for RenderProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            RenderProfile::Color => "color",
+            RenderProfile::HighContrast => "high-contrast",
+            RenderProfile::Monochrome => "monochrome",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for RenderProfile {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "color" => Ok(RenderProfile::Color),
+            "high-contrast" => Ok(RenderProfile::HighContrast),
+            "monochrome" => Ok(RenderProfile::Monochrome),
+            o => Err(format!(
+                "Unknown render profile '{}'. Supported: color, high-contrast, monochrome",
+                o
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn glyphs_are_distinct_without_colors() {
+        let markers = [
+            Marker::Stop,
+            Marker::StopAtBreakpoint,
+            Marker::Breakpoint,
+            Marker::DisabledBreakpoint,
+        ];
+        for profile in [RenderProfile::HighContrast, RenderProfile::Monochrome] {
+            let mut glyphs = markers
+                .iter()
+                .map(|&m| profile.marker(m).0)
+                .collect::<Vec<_>>();
+            glyphs.sort();
+            glyphs.dedup();
+            assert_eq!(glyphs.len(), markers.len(), "{}", profile);
+        }
+        assert_eq!("high-contrast".parse(), Ok(RenderProfile::HighContrast));
+        assert!("mono".parse::<RenderProfile>().is_err());
+    }
+}
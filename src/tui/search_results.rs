@@ -0,0 +1,183 @@
+use super::list_filter::FilterableRow;
+use super::list_view::ListView;
+use super::progress::Progress;
+use gdb::{Address, SrcPosition};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use unsegen::base::LineNumber;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::{Column, TableRow};
+use unsegen::widget::Widget;
+
+// Searching huge source trees should not make the ui unusable.
+const MAX_MATCHES: usize = 1000;
+
+/// Where a match was found: in a source file (`!grep`) or in memory (`!find`).
+#[derive(Clone, Debug)]
+pub enum MatchTarget {
+    Source(SrcPosition),
+    Memory(Address),
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchMatch {
+    target: MatchTarget,
+    location: String,
+    text: String,
+}
+
+impl SearchMatch {
+    /// A match of a memory search at the address, described by the symbol and the region.
+    pub fn memory(address: Address, location: String, text: String) -> Self {
+        SearchMatch {
+            target: MatchTarget::Memory(address),
+            location,
+            text,
+        }
+    }
+}
+
+impl TableRow for SearchMatch {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<SearchMatch>] = &[
+        Column {
+            access: |r| Box::new(r.location.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.text.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+    ];
+}
+
+impl FilterableRow for SearchMatch {
+    fn cell_text(&self, column: usize) -> &str {
+        match column {
+            0 => &self.location,
+            _ => &self.text,
+        }
+    }
+}
+
+/// Search all files in the directories containing the given source files for lines containing
+/// the pattern. The search stops early (with the matches so far) if it is cancelled.
+pub fn search_source_dirs(
+    pattern: &str,
+    source_files: &[PathBuf],
+    progress: &Progress,
+) -> Vec<SearchMatch> {
+    let dirs = source_files
+        .iter()
+        .filter_map(|f| f.parent())
+        .collect::<BTreeSet<_>>();
+    let mut matches = Vec::new();
+    let num_dirs = dirs.len() as u64;
+    for (i, dir) in dirs.into_iter().enumerate() {
+        if progress.is_cancelled() {
+            break;
+        }
+        progress.report(i as u64, Some(num_dirs));
+        let mut files = if let Ok(entries) = fs::read_dir(dir) {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file())
+                .collect::<Vec<_>>()
+        } else {
+            continue;
+        };
+        files.sort();
+        for file in files {
+            // Binary or otherwise unreadable files are silently skipped.
+            let content = if let Ok(c) = fs::read_to_string(&file) {
+                c
+            } else {
+                continue;
+            };
+            for (i, line) in content.lines().enumerate() {
+                if !line.contains(pattern) {
+                    continue;
+                }
+                if matches.len() == MAX_MATCHES {
+                    return matches;
+                }
+                let line_number = LineNumber::new(i + 1);
+                matches.push(SearchMatch {
+                    location: format!("{}:{}", file.display(), line_number),
+                    text: line.trim().to_owned(),
+                    target: MatchTarget::Source(SrcPosition::new(file.clone(), line_number)),
+                });
+            }
+        }
+    }
+    matches
+}
+
+pub struct SearchResults {
+    list: ListView<SearchMatch>,
+}
+
+impl SearchResults {
+    pub fn new() -> Self {
+        SearchResults {
+            list: ListView::new("0 match(es)"),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.list.lines()
+    }
+
+    pub fn set_matches(&mut self, matches: Vec<SearchMatch>) {
+        self.list.set_header(format!("{} match(es)", matches.len()));
+        self.list.reset_rows(matches);
+    }
+
+    fn show_match(m: Option<&SearchMatch>, p: &mut ::Context) {
+        match m.map(|m| &m.target) {
+            Some(MatchTarget::Source(pos)) => {
+                p.show_file(pos.file.to_string_lossy().into_owned(), pos.line);
+                p.focus_container(::tui::TuiContainerType::SrcView);
+            }
+            Some(MatchTarget::Memory(address)) => {
+                p.show_memory(*address);
+                p.focus_container(::tui::TuiContainerType::Memory);
+            }
+            None => {}
+        }
+    }
+
+    // Matches in memory can also be shown as instructions, e.g., if they are in code.
+    fn disassemble_match(m: Option<&SearchMatch>, p: &mut ::Context) {
+        if let Some(MatchTarget::Memory(address)) = m.map(|m| &m.target) {
+            p.show_address(*address);
+            p.focus_container(::tui::TuiContainerType::SrcView);
+        }
+    }
+}
+
+impl Container<::Context> for SearchResults {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        self.list.input(input, |row, input| {
+            input
+                .chain((Key::Char('\n'), || Self::show_match(row, p)))
+                .chain((Key::Char('d'), || Self::disassemble_match(row, p)))
+                .finish()
+        })
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        self.list.as_widget()
+    }
+}
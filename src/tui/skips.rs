@@ -0,0 +1,177 @@
+use super::event_bus::{DebugEvent, Subscriber};
+use super::list_filter::FilterableRow;
+use super::list_view::ListView;
+use super::overlay::Prompt;
+use gdb::{Skip, SkipKind};
+use gdbmi::capabilities::Feature;
+use log::warn;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::{Column, TableRow};
+use unsegen::widget::Widget;
+
+#[derive(Clone)]
+pub struct SkipRow {
+    number: String,
+    enabled: &'static str,
+    kind: String,
+    pattern: String,
+    skip: Skip,
+}
+
+impl TableRow for SkipRow {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<SkipRow>] = &[
+        Column {
+            access: |r| Box::new(r.number.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.enabled),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.kind.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.pattern.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+    ];
+}
+
+impl FilterableRow for SkipRow {
+    fn cell_text(&self, column: usize) -> &str {
+        match column {
+            0 => &self.number,
+            1 => self.enabled,
+            2 => &self.kind,
+            _ => &self.pattern,
+        }
+    }
+}
+
+/// Ask for the pattern of a new skip and add it.
+pub fn prompt_skip(kind: SkipKind, p: &mut ::Context) {
+    p.show_overlay(Prompt::new(
+        format!("Skip {} when stepping", kind),
+        "",
+        move |pattern: String, p: &mut ::Context| {
+            let pattern = pattern.trim();
+            if !pattern.is_empty() {
+                add_skip(kind, pattern, p);
+            }
+        },
+    ));
+}
+
+pub fn add_skip(kind: SkipKind, pattern: &str, p: &mut ::Context) {
+    match p.gdb.add_skip(kind, pattern) {
+        Ok(()) => p.publish(DebugEvent::SkipsChanged),
+        Err(e) => p.log(format!("Cannot add skip: {:?}", e)),
+    }
+}
+
+/// Functions and files that `step` does not descend into (gdb's `skip`), e.g., the internals of
+/// the standard library. The list is saved with the session.
+pub struct SkipsView {
+    list: ListView<SkipRow>,
+}
+
+impl SkipsView {
+    pub fn new() -> Self {
+        SkipsView {
+            list: ListView::new(Self::header(0)),
+        }
+    }
+
+    fn header(len: usize) -> String {
+        format!(
+            "{} skip(s) (f/r/i/g: add function/function regex/file/file glob, Enter: toggle, d: delete)",
+            len
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.list.lines()
+    }
+
+    fn set_skips(&mut self, skips: &[Skip]) {
+        let rows = skips
+            .iter()
+            .map(|s| SkipRow {
+                number: s.number.to_string(),
+                enabled: if s.enabled { "y" } else { "n" },
+                kind: s.kind.to_string(),
+                pattern: s.pattern.clone(),
+                skip: s.clone(),
+            })
+            .collect::<Vec<_>>();
+        self.list.set_header(Self::header(rows.len()));
+        self.list.set_rows(rows);
+    }
+
+    fn toggle_skip(row: Option<&SkipRow>, p: &mut ::Context) {
+        if let Some(row) = row {
+            match p.gdb.set_skip_enabled(row.skip.number, !row.skip.enabled) {
+                Ok(()) => p.publish(DebugEvent::SkipsChanged),
+                Err(e) => p.log(format!("Cannot change skip {}: {:?}", row.number, e)),
+            }
+        }
+    }
+
+    fn delete_skip(row: Option<&SkipRow>, p: &mut ::Context) {
+        if let Some(row) = row {
+            match p.gdb.delete_skip(row.skip.number) {
+                Ok(()) => p.publish(DebugEvent::SkipsChanged),
+                Err(e) => p.log(format!("Cannot delete skip {}: {:?}", row.number, e)),
+            }
+        }
+    }
+}
+
+impl Subscriber for SkipsView {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        match event {
+            // Skips may have been added or changed in the console in the meantime.
+            DebugEvent::Stopped(_) | DebugEvent::SymbolsReloaded
+                if p.gdb.mi.capabilities().supports(Feature::GdbCommands) =>
+            {
+                if let Err(e) = p.gdb.refresh_skips() {
+                    warn!("Failed to list skips: {:?}", e);
+                }
+                self.set_skips(&p.gdb.skips);
+            }
+            DebugEvent::SkipsChanged => self.set_skips(&p.gdb.skips),
+            _ => {}
+        }
+    }
+}
+
+impl Container<::Context> for SkipsView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        self.list.input(input, |row, input| {
+            input
+                .chain((Key::Char('\n'), || Self::toggle_skip(row, p)))
+                .chain((Key::Char('d'), || Self::delete_skip(row, p)))
+                .chain((Key::Char('f'), || prompt_skip(SkipKind::Function, p)))
+                .chain((Key::Char('r'), || prompt_skip(SkipKind::FunctionRegex, p)))
+                .chain((Key::Char('i'), || prompt_skip(SkipKind::File, p)))
+                .chain((Key::Char('g'), || prompt_skip(SkipKind::FileGlob, p)))
+                .finish()
+        })
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        self.list.as_widget()
+    }
+}
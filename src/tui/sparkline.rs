@@ -0,0 +1,231 @@
+// History of numeric values of expressions in the expression table, which is recorded whenever the
+// program stops. It is shown as a sparkline (`▁▃▅█`) next to the value or as a plot that fills the
+// whole container.
+use std::collections::VecDeque;
+use unsegen::base::{Cursor, Window};
+use unsegen::input::{Input, Key};
+use unsegen::widget::{ColDemand, Demand2D, RenderingHints, RowDemand, VLayout, Widget};
+
+// Older values are dropped.
+const MAX_HISTORY: usize = 500;
+/// Number of (most recent) values that are shown in the sparkline column.
+pub const SPARKLINE_WIDTH: usize = 16;
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    values: VecDeque<f64>,
+}
+
+impl History {
+    pub fn push(&mut self, value: f64) {
+        if self.values.len() == MAX_HISTORY {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn values(&self) -> Vec<f64> {
+        self.values.iter().copied().collect()
+    }
+}
+
+/// The number in a value as printed by gdb, e.g., `42`, `-1.5`, `0x1f` or `65 'A'` (a character).
+/// Anything else (structs, pointers with symbols, strings, ...) is not numeric.
+pub fn parse_number(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (number, rest) = match value.find(' ') {
+        Some(i) => (&value[..i], value[i..].trim()),
+        None => (value, ""),
+    };
+    if !rest.is_empty() && !rest.starts_with('\'') {
+        return None;
+    }
+    if let Some(digits) = number.strip_prefix("0x") {
+        return u64::from_str_radix(digits, 16).ok().map(|v| v as f64);
+    }
+    number.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+fn bounds(values: &[f64]) -> (f64, f64) {
+    values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        })
+}
+
+// Level (0..levels) of the value between min and max. Constant values are at the lowest level.
+fn level(value: f64, min: f64, max: f64, levels: usize) -> usize {
+    if max > min {
+        ((value - min) / (max - min) * (levels - 1) as f64).round() as usize
+    } else {
+        0
+    }
+}
+
+fn last(values: &[f64], count: usize) -> &[f64] {
+    &values[values.len().saturating_sub(count)..]
+}
+
+/// The last `width` values as a sparkline, scaled between their minimum and maximum.
+pub fn sparkline(values: &[f64], width: usize) -> String {
+    let values = last(values, width);
+    let (min, max) = bounds(values);
+    values
+        .iter()
+        .map(|&v| LEVELS[level(v, min, max, LEVELS.len())])
+        .collect()
+}
+
+/// Rows (top to bottom) of a bar plot of the last `width` values with a resolution of eighths of a
+/// row. The minimum is drawn as the smallest bar, so that every value is visible.
+pub fn plot(values: &[f64], width: usize, height: usize) -> Vec<String> {
+    let values = last(values, width);
+    let (min, max) = bounds(values);
+    let eighths = values
+        .iter()
+        .map(|&v| level(v, min, max, height * LEVELS.len()) + 1)
+        .collect::<Vec<_>>();
+    (0..height)
+        .rev()
+        .map(|row| {
+            let base = row * LEVELS.len();
+            eighths
+                .iter()
+                .map(|&e| {
+                    if e >= base + LEVELS.len() {
+                        LEVELS[LEVELS.len() - 1]
+                    } else if e > base {
+                        LEVELS[e - base - 1]
+                    } else {
+                        ' '
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+// Shows the history of an expression as a plot with the minimum and maximum as labels.
+pub struct PlotView {
+    expression: String,
+    values: Vec<f64>,
+}
+
+impl PlotView {
+    pub fn new(expression: String, values: Vec<f64>) -> Self {
+        PlotView { expression, values }
+    }
+
+    // Returns false if the view should be closed.
+    pub fn input(&mut self, input: Input) -> (Option<Input>, bool) {
+        let mut close = false;
+        let rest = input
+            .chain((&[Key::Esc, Key::Char('q')][..], || close = true))
+            .finish();
+        (rest, !close)
+    }
+
+    pub fn as_widget<'a>(&'a self) -> impl Widget + 'a {
+        let (min, max) = bounds(&self.values);
+        let last = self.values.last().copied().unwrap_or_default();
+        VLayout::new()
+            .widget(format!(
+                "History of {}: {} stop(s), min {}, max {}, last {} (q: close)",
+                self.expression,
+                self.values.len(),
+                format_number(min),
+                format_number(max),
+                format_number(last)
+            ))
+            .widget(PlotWidget { inner: self })
+    }
+}
+
+struct PlotWidget<'a> {
+    inner: &'a PlotView,
+}
+
+impl<'a> Widget for PlotWidget<'a> {
+    fn space_demand(&self) -> Demand2D {
+        Demand2D {
+            width: ColDemand::at_least(1),
+            height: RowDemand::at_least(1),
+        }
+    }
+
+    fn draw(&self, mut window: Window, _: RenderingHints) {
+        let values = &self.inner.values;
+        let (min, max) = bounds(values);
+        let labels = (format_number(max), format_number(min));
+        let label_width = labels.0.chars().count().max(labels.1.chars().count());
+        let width = (window.get_width().raw_value() as usize).saturating_sub(label_width + 1);
+        let height = window.get_height().raw_value() as usize;
+        if width == 0 || height == 0 {
+            return;
+        }
+        let rows = plot(values, width, height);
+        let mut cursor = Cursor::new(&mut window);
+        for (i, row) in rows.iter().enumerate() {
+            let label = if i == 0 {
+                labels.0.as_str()
+            } else if i + 1 == height {
+                labels.1.as_str()
+            } else {
+                ""
+            };
+            cursor.writeln(&format!("{:>w$}│{}", label, row, w = label_width));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("42"), Some(42.0));
+        assert_eq!(parse_number("-1.5"), Some(-1.5));
+        assert_eq!(parse_number("0x1f"), Some(31.0));
+        assert_eq!(parse_number("65 'A'"), Some(65.0));
+        assert_eq!(parse_number("(int *) 0x601040 <g>"), None);
+        assert_eq!(parse_number("0x601040 <g>"), None);
+        assert_eq!(parse_number("{a = 1}"), None);
+        assert_eq!(parse_number("inf"), None);
+        assert_eq!(parse_number("true"), None);
+    }
+
+    #[test]
+    fn test_sparkline_and_plot() {
+        assert_eq!(sparkline(&[1.0, 2.0, 3.0, 8.0], 10), "▁▂▃█");
+        assert_eq!(sparkline(&[1.0, 2.0, 3.0, 8.0], 2), "▁█");
+        assert_eq!(sparkline(&[5.0, 5.0], 10), "▁▁");
+        assert_eq!(sparkline(&[], 10), "");
+        assert_eq!(plot(&[0.0, 1.0, 2.0], 10, 2), vec![" ▁█", "▁██"]);
+
+        let mut history = History::default();
+        for i in 0..MAX_HISTORY + 1 {
+            history.push(i as f64);
+        }
+        assert_eq!(history.values().len(), MAX_HISTORY);
+        assert_eq!(history.values()[0], 1.0);
+    }
+}
@@ -1,24 +1,37 @@
+use clipboard;
+use diagnostics::Diagnostic;
+use encoding::{self, FallbackEncoding};
 use gdb::{response::*, Address, BreakPoint, BreakpointOperationError, SrcPosition};
 use gdbmi::commands::{BreakPointLocation, BreakPointNumber, DisassembleMode, MiCommand};
 use gdbmi::output::{JsonValue, Object, ResultClass};
+use gdbmi::types::Frame;
 use gdbmi::ExecuteError;
 use log::warn;
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tui::branch_arrows::{call_target, jump_operand, BranchArrows};
+use tui::event_bus::{DebugEvent, Subscriber};
+use tui::line_decorators::{
+    opcodes_width, AssemblyDebugLocation, AssemblyDecorator, AssemblyLine, SourceDecorator,
+    SourceLine,
+};
+use tui::overlay::{Confirmation, Message, Prompt};
+use tui::render_profile::RenderProfile;
+use tui::{Badge, TitleBar, TuiContainerType};
+use unicode_segmentation::UnicodeSegmentation;
 use unsegen::base::basic_types::*;
-use unsegen::base::{Color, Cursor, GraphemeCluster, StyleModifier, Window};
+use unsegen::base::{Color, GraphemeCluster, StyleModifier};
 use unsegen::container::Container;
 use unsegen::input::{Input, Key, ScrollBehavior};
 use unsegen::widget::{
-    text_width, ColDemand, Demand, Demand2D, HLayout, RenderingHints, RowDemand, VLayout, Widget,
-    WidgetExt,
-};
-use unsegen_pager::{
-    LineDecorator, Pager, PagerContent, PagerError, PagerLine, SyntectHighlighter,
+    text_width, ColDemand, Demand2D, HLayout, RowDemand, VLayout, Widget, WidgetExt,
 };
+use unsegen_pager::{Pager, PagerContent, PagerError, SyntectHighlighter};
 use unsegen_pager::{SyntaxSet, Theme};
 
 #[derive(Debug)]
@@ -26,157 +39,11 @@ pub enum PagerShowError {
     CouldNotOpenFile(PathBuf, io::Error),
 }
 
-#[derive(Clone)]
-struct AssemblyDebugLocation {
-    func_name: String,
-    offset: usize,
-}
-
-impl AssemblyDebugLocation {
-    fn try_from_value(val: &JsonValue) -> Option<Self> {
-        let func_name = val["func-name"].as_str()?;
-        let offset = val["offset"].as_str()?.parse::<usize>().ok()?;
-        Some(AssemblyDebugLocation {
-            func_name: func_name.to_owned(),
-            offset,
-        })
-    }
-}
-
-#[derive(Clone)]
-struct AssemblyLine {
-    content: String,
-    address: Address,
-    src_position: Option<SrcPosition>,
-    debug_location: Option<AssemblyDebugLocation>,
-}
-
-impl AssemblyLine {
-    fn new(
-        content: String,
-        address: Address,
-        src_position: Option<SrcPosition>,
-        debug_location: Option<AssemblyDebugLocation>,
-    ) -> Self {
-        AssemblyLine {
-            content: content,
-            address: address,
-            src_position: src_position,
-            debug_location: debug_location,
-        }
-    }
-}
-
-impl PagerLine for AssemblyLine {
-    fn get_content(&self) -> &str {
-        &self.content
-    }
-}
-
-struct AssemblyDecorator {
-    stop_position: Option<Address>,
-    breakpoint_addresses: HashSet<Address>,
-}
-
-impl AssemblyDecorator {
-    fn new<'a, I: Iterator<Item = &'a BreakPoint>>(
-        address_range: Range<Address>,
-        stop_position: Option<Address>,
-        breakpoints: I,
-    ) -> Self {
-        let addresses = breakpoints
-            .filter_map(|bp| {
-                bp.address.and_then(|addr| {
-                    if bp.enabled && address_range.start <= addr && addr < address_range.end {
-                        Some(addr)
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect();
-        let stop_position = if let Some(p) = stop_position {
-            if address_range.start <= p && p < address_range.end {
-                Some(p)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-        AssemblyDecorator {
-            stop_position: stop_position,
-            breakpoint_addresses: addresses,
-        }
-    }
-}
-
-impl LineDecorator for AssemblyDecorator {
-    type Line = AssemblyLine;
-    fn horizontal_space_demand<'a, 'b: 'a>(
-        &'a self,
-        lines: impl DoubleEndedIterator<Item = (LineIndex, &'b Self::Line)> + 'b,
-    ) -> ColDemand {
-        let max_space = lines
-            .last()
-            .map(|(_, l)| text_width(format!(" 0x{:x} ", l.address.0).as_str()))
-            .unwrap_or(Width::new(0).unwrap());
-        Demand::exact(max_space)
-    }
-    fn decorate(
-        &self,
-        line: &Self::Line,
-        current_line: LineIndex,
-        active_line: LineIndex,
-        mut window: Window,
-    ) {
-        let width = window.get_width();
-        let mut cursor = Cursor::new(&mut window).position(ColIndex::new(0), RowIndex::new(0));
-
-        let at_stop_position = self
-            .stop_position
-            .map(|p| p == line.address)
-            .unwrap_or(false);
-        let at_breakpoint_position = self.breakpoint_addresses.contains(&line.address);
-
-        let (right_border, style_modifier) = match (at_stop_position, at_breakpoint_position) {
-            (true, true) => ('▶', StyleModifier::new().fg_color(Color::Red).bold(true)),
-            (true, false) => ('▶', StyleModifier::new().fg_color(Color::Green).bold(true)),
-            (false, true) => ('●', StyleModifier::new().fg_color(Color::Red)),
-            (false, false) => (' ', StyleModifier::new()),
-        };
-
-        cursor.set_style_modifier(style_modifier);
-
-        use std::fmt::Write;
-        if let (false, Some(offset)) = (
-            current_line == active_line,
-            line.debug_location
-                .iter()
-                .map(|l| l.offset)
-                .filter(|&offset| offset != 0)
-                .next(),
-        ) {
-            let formatted_offset = format!("<+{}>", offset);
-            write!(
-                cursor,
-                "{:>width$}{}",
-                formatted_offset,
-                right_border,
-                width = (width - 1).positive_or_zero().into()
-            )
-            .unwrap();
-        } else {
-            write!(
-                cursor,
-                " 0x{:0>width$x}{}",
-                line.address.0,
-                right_border,
-                width = (width - 4).positive_or_zero().into()
-            )
-            .unwrap();
-        }
-    }
+// What was disassembled to get the content of the assembly view.
+#[derive(Clone, PartialEq)]
+enum AssemblyOrigin {
+    File(PathBuf, LineNumber),
+    Addresses(Address, Address),
 }
 
 pub struct AssemblyView<'a> {
@@ -184,6 +51,10 @@ pub struct AssemblyView<'a> {
     syntax_set: SyntaxSet,
     pager: Pager<AssemblyLine, AssemblyDecorator>,
     last_stop_position: Option<Address>,
+    origin: Option<AssemblyOrigin>,
+    show_opcodes: bool,
+    jump_history: Vec<(AssemblyOrigin, Address)>, // Instructions from which jumps were followed
+    profile: RenderProfile,
 }
 
 #[derive(Debug, From)]
@@ -194,12 +65,16 @@ enum GotoError {
 }
 
 impl<'a> AssemblyView<'a> {
-    pub fn new(highlighting_theme: &'a Theme) -> Self {
+    pub fn new(highlighting_theme: &'a Theme, profile: RenderProfile) -> Self {
         AssemblyView {
             highlighting_theme: highlighting_theme,
             syntax_set: SyntaxSet::load_defaults_nonewlines(),
             pager: Pager::new(),
             last_stop_position: None,
+            origin: None,
+            show_opcodes: false,
+            jump_history: Vec::new(),
+            profile,
         }
     }
     fn set_last_stop_position(&mut self, pos: Address) {
@@ -234,6 +109,7 @@ impl<'a> AssemblyView<'a> {
     }
 
     fn update_decoration(&mut self, p: &mut ::Context) {
+        let format_address = p.address_formatter();
         if let Some(ref mut content) = self.pager.content_mut() {
             let first_line_address = content.view_line(LineIndex::new(0)).map(|l| l.address);
             if let Some(min_address) = first_line_address {
@@ -245,10 +121,26 @@ impl<'a> AssemblyView<'a> {
                         .1
                         .address
                 };
+                let opcodes_width = if self.show_opcodes {
+                    Some(opcodes_width(
+                        content.view(LineIndex::new(0)..).map(|(_, l)| l),
+                    ))
+                } else {
+                    None
+                };
+                let arrows = BranchArrows::new(
+                    content
+                        .view(LineIndex::new(0)..)
+                        .map(|(_, l)| (l.address, l.content.as_str())),
+                );
                 content.set_decorator(AssemblyDecorator::new(
                     min_address..max_address,
                     self.last_stop_position,
                     p.gdb.breakpoints.values(),
+                    opcodes_width,
+                    arrows,
+                    Box::new(format_address),
+                    self.profile,
                 ));
             }
         }
@@ -266,6 +158,13 @@ impl<'a> AssemblyView<'a> {
             .syntax_set
             .find_syntax_by_extension("s")
             .unwrap_or(self.syntax_set.find_syntax_plain_text());
+        let opcodes_width = if self.show_opcodes {
+            Some(opcodes_width(lines.iter()))
+        } else {
+            None
+        };
+        let arrows = BranchArrows::new(lines.iter().map(|l| (l.address, l.content.as_str())));
+        let format_address = p.address_formatter();
         self.pager.load(
             PagerContent::from_lines(lines)
                 .with_highlighter(&SyntectHighlighter::new(syntax, self.highlighting_theme))
@@ -273,6 +172,10 @@ impl<'a> AssemblyView<'a> {
                     min_address..max_address,
                     self.last_stop_position,
                     p.gdb.breakpoints.values(),
+                    opcodes_width,
+                    arrows,
+                    Box::new(format_address),
+                    self.profile,
                 )),
         );
     }
@@ -297,6 +200,7 @@ impl<'a> AssemblyView<'a> {
                         address,
                         src_pos.clone(),
                         AssemblyDebugLocation::try_from_value(tuple),
+                        tuple["opcodes"].as_str().map(|o| o.to_owned()),
                     ));
                 }
             }
@@ -316,7 +220,8 @@ impl<'a> AssemblyView<'a> {
         line: L,
         p: &mut ::Context,
     ) -> Result<(), DisassembleError> {
-        let line_u: usize = line.into().into();
+        let line: LineNumber = line.into();
+        let line_u: usize = line.into();
         let disass_results = p
             .gdb
             .mi
@@ -324,12 +229,13 @@ impl<'a> AssemblyView<'a> {
                 file.as_ref(),
                 line_u,
                 None,
-                DisassembleMode::MixedSourceAndDisassembly,
+                DisassembleMode::MixedSourceAndDisassemblyWithRawOpcodes,
             ))?
             .results;
 
         let lines = Self::get_instructions(&disass_results)?;
         self.show_lines(lines, p);
+        self.origin = Some(AssemblyOrigin::File(file.as_ref().to_owned(), line));
         Ok(())
     }
 
@@ -339,7 +245,12 @@ impl<'a> AssemblyView<'a> {
         address_end: Address,
         p: &mut ::Context,
     ) -> Result<(), DisassembleError> {
-        let line_objs = disassemble_address(address_start, address_end, p)?;
+        let line_objs = disassemble_address(
+            address_start,
+            address_end,
+            DisassembleMode::DisassemblyWithRawOpcodes,
+            p,
+        )?;
 
         let mut lines = Vec::<AssemblyLine>::new();
         for line_tuple in line_objs {
@@ -350,12 +261,130 @@ impl<'a> AssemblyView<'a> {
                 address,
                 None,
                 AssemblyDebugLocation::try_from_value(&line_tuple),
+                line_tuple["opcodes"].as_str().map(|o| o.to_owned()),
             ));
         }
         self.show_lines(lines, p);
+        self.origin = Some(AssemblyOrigin::Addresses(address_start, address_end));
+        Ok(())
+    }
+
+    /// Disassemble the shown instructions again (e.g., using another flavor), staying at the
+    /// current instruction.
+    fn reload(&mut self, p: &mut ::Context) -> Result<(), DisassembleError> {
+        let current_address = self.pager.current_line().map(|l| l.address);
+        match self.origin.clone() {
+            Some(AssemblyOrigin::File(file, line)) => self.show_file(file, line, p)?,
+            Some(AssemblyOrigin::Addresses(begin, end)) => self.show_address(begin, end, p)?,
+            None => return Ok(()),
+        }
+        if let Some(address) = current_address {
+            let _ = self.go_to_address(address);
+        }
         Ok(())
     }
 
+    /// Go to the target of the jump or call instruction under the cursor, disassembling the
+    /// function containing it if it is not shown already.
+    fn follow_jump(&mut self, p: &mut ::Context) {
+        let (operand, from) = match (self.pager.current_line(), self.origin.clone()) {
+            (Some(line), Some(origin)) => match jump_operand(&line.content) {
+                Some(operand) => (operand, (origin, line.address)),
+                None => {
+                    p.log(format!("Not a jump or call: {}", line.content));
+                    return;
+                }
+            },
+            _ => return,
+        };
+        let target = match p.gdb.evaluate_address(&operand) {
+            Ok(target) => target,
+            Err(e) => {
+                p.log(format!("Cannot resolve jump target '{}': {:?}", operand, e));
+                return;
+            }
+        };
+        if self.go_to_address(target).is_err() {
+            let range = CodeWindow::find_function_range(target, p)
+                .or_else(|_| CodeWindow::find_valid_address_range(target, 128, p).map_err(|_| ()));
+            let result = match range {
+                Ok((begin, end)) => self.show_address(begin, end, p),
+                Err(()) => Err(DisassembleError::Other("No instructions found".to_owned())),
+            };
+            if let Err(e) = result {
+                p.log(format!("Cannot disassemble at {}: {:?}", target, e));
+                return;
+            }
+            let _ = self.go_to_address(target);
+        }
+        self.jump_history.push(from);
+    }
+
+    /// Return to the instruction from which the last jump was followed.
+    fn jump_back(&mut self, p: &mut ::Context) {
+        let (origin, address) = match self.jump_history.pop() {
+            Some(entry) => entry,
+            None => return,
+        };
+        if self.origin.as_ref() != Some(&origin) {
+            let result = match origin {
+                AssemblyOrigin::File(file, line) => self.show_file(file, line, p),
+                AssemblyOrigin::Addresses(begin, end) => self.show_address(begin, end, p),
+            };
+            if let Err(e) = result {
+                p.log(format!("Cannot disassemble again: {:?}", e));
+                return;
+            }
+        }
+        let _ = self.go_to_address(address);
+    }
+
+    /// Continue until the instruction under the cursor is reached.
+    fn run_to_cursor(&self, p: &mut ::Context) {
+        if let Some(line) = self.pager.current_line() {
+            match p.gdb.run_to_address(line.address) {
+                Ok(()) => {}
+                Err(BreakpointOperationError::Busy) => {
+                    p.log("Cannot run to address: Gdb is busy.");
+                }
+                Err(BreakpointOperationError::ExecutionError(msg)) => {
+                    p.log(format!("Cannot run to address: {}", msg));
+                }
+                Err(BreakpointOperationError::Quit(e)) => {
+                    p.log(format!("Cannot run to address: {}", e));
+                }
+            }
+        }
+    }
+
+    /// Set the program counter to the instruction under the cursor (after asking for
+    /// confirmation, because skipping instructions easily corrupts the state of the program).
+    fn jump_to_cursor(&self, p: &mut ::Context) {
+        let line = match self.pager.current_line() {
+            Some(line) => line,
+            None => return,
+        };
+        let address = line.address;
+        p.show_overlay(Confirmation::new(
+            "Jump",
+            format!(
+                "Resume execution at {} ({})?\n\nInstructions in between are skipped (or executed again).",
+                address,
+                line.content.trim()
+            ),
+            move |p: &mut ::Context| {
+                if let Err(e) = p.gdb.jump_to_address(address) {
+                    p.log(format!("Cannot jump to {}: {:?}", address, e));
+                }
+            },
+        ));
+    }
+
+    fn toggle_opcodes(&mut self, p: &mut ::Context) {
+        self.show_opcodes = !self.show_opcodes;
+        self.update_decoration(p);
+    }
+
     fn toggle_breakpoint(&self, p: &mut ::Context) {
         if let Some(line) = self.pager.current_line() {
             let active_bps: Vec<BreakPointNumber> = p
@@ -386,6 +415,9 @@ impl<'a> AssemblyView<'a> {
                     Err(BreakpointOperationError::ExecutionError(msg)) => {
                         p.log(format!("Cannot insert breakpoint: {}", msg));
                     }
+                    Err(BreakpointOperationError::Quit(e)) => {
+                        p.log(format!("Cannot insert breakpoint: {}", e));
+                    }
                 }
             } else {
                 match p.gdb.delete_breakpoints(active_bps.into_iter()) {
@@ -396,6 +428,9 @@ impl<'a> AssemblyView<'a> {
                     Err(BreakpointOperationError::ExecutionError(msg)) => {
                         p.log(format!("Cannot remove breakpoint: {}", msg));
                     }
+                    Err(BreakpointOperationError::Quit(e)) => {
+                        p.log(format!("Cannot remove breakpoint: {}", e));
+                    }
                 }
             }
         }
@@ -412,101 +447,364 @@ impl<'a> AssemblyView<'a> {
                     .to_end_on(Key::End),
             )
             .chain((Key::Char(' '), || self.toggle_breakpoint(p)))
+            .chain((Key::Char('o'), || self.toggle_opcodes(p)))
+            .chain((Key::Char('\n'), || self.follow_jump(p)))
+            .chain((Key::Backspace, || self.jump_back(p)))
+            .chain((Key::Char('u'), || self.run_to_cursor(p)))
+            .chain((Key::Char('J'), || self.jump_to_cursor(p)))
             .finish()
     }
 }
 
-struct SourceDecorator {
-    stop_position: Option<LineNumber>,
-    breakpoint_lines: HashSet<LineNumber>,
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
 }
 
-impl SourceDecorator {
-    fn new<'a, I: Iterator<Item = &'a BreakPoint>>(
-        file: &Path,
-        stop_position: Option<LineNumber>,
-        breakpoints: I,
-    ) -> Self {
-        let addresses = breakpoints
-            .filter_map(|bp| {
-                bp.src_pos.clone().and_then(|pos| {
-                    if bp.enabled && pos.file == file {
-                        Some(pos.line)
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect();
-        SourceDecorator {
-            stop_position: stop_position,
-            breakpoint_lines: addresses,
+// The tab stops of the source view (the default of the cursor of unsegen).
+const TAB_WIDTH: usize = 4;
+
+// Make a source line safe for display: Control characters do not occupy a cell (or mess with the
+// terminal) and bidi overrides would reorder the text relative to the gutter, so both are replaced
+// by visible placeholders. Tabs are expanded to spaces at the tab stops of the line (rather than
+// those of the wrapped row, which the cursor would use), taking the display width of wide (e.g.,
+// CJK or emoji) and combining characters in front of them into account, so that every grapheme
+// cluster of the result occupies as many cells as unicode-width says.
+fn display_content(line: &str) -> Cow<'_, str> {
+    if !line.chars().any(|c| c.is_control() || is_bidi_control(c)) {
+        return Cow::Borrowed(line);
+    }
+    let mut out = String::with_capacity(line.len());
+    let mut column = 0;
+    for grapheme in line.graphemes(true) {
+        let mut chars = grapheme.chars();
+        let c = chars.next().unwrap();
+        if chars.next().is_some() || !(c.is_control() || is_bidi_control(c)) {
+            out.push_str(grapheme);
+            column += text_width(grapheme).raw_value() as usize;
+            continue;
         }
+        let placeholder = if c == '\t' {
+            " ".repeat(TAB_WIDTH - column % TAB_WIDTH)
+        } else if (c as u32) < 0x20 {
+            format!("^{}", (c as u8 + b'@') as char)
+        } else if c == '\u{7F}' {
+            "^?".to_owned()
+        } else {
+            format!("<U+{:04X}>", c as u32)
+        };
+        column += placeholder.len();
+        out.push_str(&placeholder);
     }
+    Cow::Owned(out)
 }
 
-impl LineDecorator for SourceDecorator {
-    type Line = String;
-    fn horizontal_space_demand<'a, 'b: 'a>(
-        &'a self,
-        lines: impl DoubleEndedIterator<Item = (LineIndex, &'b Self::Line)> + 'b,
-    ) -> ColDemand {
-        let max_space = lines
-            .last()
-            .map(|(i, _)| text_width(format!(" {} ", i).as_str()))
-            .unwrap_or(Width::new(0).unwrap());
-        Demand::exact(max_space)
-    }
-    fn decorate(
-        &self,
-        _: &Self::Line,
-        current_index: LineIndex,
-        _active_index: LineIndex,
-        mut window: Window,
-    ) {
-        let width = (window.get_width() - 2).positive_or_zero();
-        let line_number = LineNumber::from(current_index);
-        let mut cursor = Cursor::new(&mut window).position(ColIndex::new(0), RowIndex::new(0));
-
-        let at_stop_position = self
-            .stop_position
-            .map(|p| p == current_index.into())
-            .unwrap_or(false);
-        let at_breakpoint_position = self.breakpoint_lines.contains(&current_index.into());
-
-        let (right_border, style_modifier) = match (at_stop_position, at_breakpoint_position) {
-            (true, true) => ('▶', StyleModifier::new().fg_color(Color::Red).bold(true)),
-            (true, false) => ('▶', StyleModifier::new().fg_color(Color::Green).bold(true)),
-            (false, true) => ('●', StyleModifier::new().fg_color(Color::Red)),
-            (false, false) => (' ', StyleModifier::new()),
-        };
+// Length of the char literal at the beginning of `chars` (e.g., 'a', '\n' or '\u{7f}'), or `None`
+// if the quote does not start one (e.g., the lifetime in Rust's `&'a str`).
+fn char_literal_len(chars: &[char]) -> Option<usize> {
+    if chars.get(1) == Some(&'\\') {
+        (3..chars.len().min(12))
+            .find(|&i| chars[i] == '\'')
+            .map(|i| i + 1)
+    } else if chars.get(2) == Some(&'\'') {
+        Some(3)
+    } else {
+        None
+    }
+}
 
-        cursor.set_style_modifier(style_modifier);
+// The braces of the line that are not within string/char literals or comments. `in_comment` tells
+// whether a block comment is open at the beginning of the line and is updated for the next one.
+fn code_braces(line: &str, in_comment: &mut bool) -> Vec<char> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut braces = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let next = chars.get(i + 1).copied();
+        if *in_comment {
+            if chars[i] == '*' && next == Some('/') {
+                *in_comment = false;
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+        match chars[i] {
+            '/' if next == Some('/') => break,
+            '/' if next == Some('*') => {
+                *in_comment = true;
+                i += 1;
+            }
+            '"' => {
+                // Skip to the closing quote (strings do not span lines).
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+            }
+            '\'' => {
+                if let Some(len) = char_literal_len(&chars[i..]) {
+                    i += len - 1;
+                }
+            }
+            c @ '{' | c @ '}' => braces.push(c),
+            _ => {}
+        }
+        i += 1;
+    }
+    braces
+}
 
-        use std::fmt::Write;
-        write!(
-            cursor,
-            " {:width$}{}",
-            line_number,
-            right_border,
-            width = width.into()
-        )
-        .unwrap();
+// Find the innermost brace-delimited block spanning multiple lines that contains the line with
+// the given index. Returns the (0-based) index of the line containing the opening brace and the
+// line containing the matching closing brace.
+fn find_enclosing_block<S: AsRef<str>>(lines: &[S], line: usize) -> Option<(usize, usize)> {
+    let mut open = Vec::new();
+    let mut in_comment = false;
+    for (i, l) in lines.iter().enumerate() {
+        for c in code_braces(l.as_ref(), &mut in_comment) {
+            if c == '{' {
+                open.push(i);
+            } else if let Some(begin) = open.pop() {
+                if begin <= line && line <= i && begin < i {
+                    // Blocks are closed from the inside out, so the first match is the innermost.
+                    return Some((begin, i));
+                }
+            }
+        }
+        if i > line && open.is_empty() {
+            break;
+        }
     }
+    None
+}
+
+// Identifiers in the line, with the ones that look like function calls first.
+fn identifiers_by_relevance(line: &str) -> Vec<&str> {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut called = Vec::new();
+    let mut others = Vec::new();
+    let mut rest = line;
+    while let Some(begin) = rest.find(is_ident_char) {
+        let after_begin = &rest[begin..];
+        let len = after_begin
+            .find(|c: char| !is_ident_char(c))
+            .unwrap_or(after_begin.len());
+        let ident = &after_begin[..len];
+        rest = &after_begin[len..];
+        // Numbers (e.g., `0x1f` or `1u`) and keywords are never defined anywhere.
+        if ident.starts_with(|c: char| c.is_numeric())
+            || KEYWORDS.contains(&ident)
+            || called.contains(&ident)
+            || others.contains(&ident)
+        {
+            continue;
+        }
+        if rest.trim_start().starts_with('(') {
+            called.push(ident);
+        } else {
+            others.push(ident);
+        }
+    }
+    called.extend(others);
+    called
+}
+
+// Keywords of C, C++ and Rust (and types that are keywords in C), which look like identifiers.
+const KEYWORDS: &[&str] = &[
+    "as",
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "fn",
+    "for",
+    "goto",
+    "if",
+    "impl",
+    "in",
+    "int",
+    "let",
+    "long",
+    "loop",
+    "match",
+    "mod",
+    "mut",
+    "namespace",
+    "new",
+    "nullptr",
+    "private",
+    "protected",
+    "pub",
+    "public",
+    "ref",
+    "return",
+    "self",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "use",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "where",
+    "while",
+];
+
+// Expressions in the line that can be evaluated without side effects, i.e., identifiers including
+// member accesses (`a.b`, `p->x`) in the order of their first appearance. Called functions are
+// skipped.
+fn inspectable_expressions(line: &str) -> Vec<String> {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    let ident_len = |s: &str| s.find(|c| !is_ident_char(c)).unwrap_or(s.len());
+    let mut expressions = Vec::<String>::new();
+    let mut pos = 0;
+    while pos < line.len() {
+        let rest = &line[pos..];
+        let c = rest.chars().next().unwrap();
+        let preceded_by_ident = line[..pos].chars().next_back().is_some_and(is_ident_char);
+        if !(c.is_alphabetic() || c == '_') || preceded_by_ident {
+            pos += c.len_utf8();
+            continue;
+        }
+        let mut len = ident_len(rest);
+        loop {
+            let after = &rest[len..];
+            let sep = if after.starts_with("->") {
+                2
+            } else if after.starts_with('.') {
+                1
+            } else {
+                break;
+            };
+            match ident_len(&after[sep..]) {
+                0 => break,
+                member => len += sep + member,
+            }
+        }
+        let expression = &rest[..len];
+        pos += len;
+        let called = line[pos..].trim_start().starts_with('(');
+        if !called && !expressions.iter().any(|e| e == expression) {
+            expressions.push(expression.to_owned());
+        }
+    }
+    expressions
+}
+
+// Files larger than this are not loaded at all.
+const MAX_SOURCE_FILE_SIZE: u64 = 64 * 1024 * 1024;
+// Only the beginning of binary files is shown as a hexdump.
+const MAX_HEXDUMP_BYTES: usize = 64 * 1024;
+pub const HEXDUMP_BYTES_PER_LINE: usize = 16;
+
+// Same heuristic as git: A file is considered binary if it contains a NUL byte near the beginning.
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|&b| b == 0)
+}
+
+// Offsets shown in front of each line start at base.
+pub fn hexdump_lines(bytes: &[u8], base: usize) -> Vec<String> {
+    hexdump_lines_with(bytes, base, |offset| format!("{:08x}", offset))
+}
+
+// Like `hexdump_lines`, but the offsets (e.g., addresses in memory) are formatted by the caller.
+pub fn hexdump_lines_with(
+    bytes: &[u8],
+    base: usize,
+    format_offset: impl Fn(usize) -> String,
+) -> Vec<String> {
+    bytes
+        .chunks(HEXDUMP_BYTES_PER_LINE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut hex = String::new();
+            for j in 0..HEXDUMP_BYTES_PER_LINE {
+                if j == HEXDUMP_BYTES_PER_LINE / 2 {
+                    hex.push(' ');
+                }
+                match chunk.get(j) {
+                    Some(b) => hex.push_str(&format!("{:02x} ", b)),
+                    None => hex.push_str("   "),
+                }
+            }
+            let ascii = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            format!(
+                "{}  {} |{}|",
+                format_offset(base + i * HEXDUMP_BYTES_PER_LINE),
+                hex,
+                ascii
+            )
+        })
+        .collect()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum SourceFileKind {
+    Text,
+    Binary,
+    TooLarge,
 }
 
 #[derive(Clone)]
 struct FileInfo {
-    path: PathBuf,
-    modified: ::std::time::SystemTime,
+    path: PathBuf,       // As reported by gdb
+    local_path: PathBuf, // With the path substitutions applied
+    modified: SystemTime,
 }
 
 pub struct SourceView<'a> {
     highlighting_theme: &'a Theme,
     syntax_set: SyntaxSet,
-    pager: Pager<String, SourceDecorator>,
+    pager: Pager<SourceLine, SourceDecorator>,
     file_info: Option<FileInfo>,
     last_stop_position: Option<SrcPosition>,
+    lines: Vec<String>,
+    folds: Vec<(usize, usize)>, // (0-based) indices of the first and last line of each fold
+    fallback_encoding: FallbackEncoding,
+    applied_encoding: Option<FallbackEncoding>, // Set if the file is not valid UTF-8
+    file_kind: SourceFileKind,
+    copy_anchor: Option<Range<usize>>, // Line numbers of the line where the copy mode was entered
+    diagnostics: Vec<Diagnostic>,
+    profile: RenderProfile,
 }
 
 macro_rules! current_file_and_content_mut {
@@ -521,21 +819,37 @@ macro_rules! current_file_and_content_mut {
 }
 
 impl<'a> SourceView<'a> {
-    pub fn new(highlighting_theme: &'a Theme) -> Self {
+    pub fn new(
+        highlighting_theme: &'a Theme,
+        fallback_encoding: FallbackEncoding,
+        profile: RenderProfile,
+    ) -> Self {
         SourceView {
             highlighting_theme: highlighting_theme,
             syntax_set: SyntaxSet::load_defaults_nonewlines(),
             pager: Pager::new(),
             file_info: None,
             last_stop_position: None,
+            lines: Vec::new(),
+            folds: Vec::new(),
+            fallback_encoding,
+            applied_encoding: None,
+            file_kind: SourceFileKind::Text,
+            copy_anchor: None,
+            diagnostics: Vec::new(),
+            profile,
         }
     }
     fn set_last_stop_position<P: AsRef<Path>>(&mut self, file: P, pos: LineNumber) {
         self.last_stop_position = Some(SrcPosition::new(file.as_ref().to_path_buf(), pos));
     }
 
+    // Lines hidden in a fold are represented by the first line of the fold.
     fn go_to_line<L: Into<LineNumber>>(&mut self, line: L) -> Result<(), GotoError> {
-        Ok(self.pager.go_to_line(line.into())?)
+        let line = line.into();
+        Ok(self.pager.go_to_line_if(|_, l| {
+            l.number == line || (l.num_folded > 0 && l.folded_range().contains(&line.raw_value()))
+        })?)
     }
 
     fn go_to_last_stop_position(&mut self) -> Result<(), GotoError> {
@@ -577,20 +891,25 @@ impl<'a> SourceView<'a> {
                     None
                 }
             });
-            content.set_decorator(SourceDecorator::new(
-                file_path,
-                last_line_number,
-                p.gdb.breakpoints.values(),
-            ));
+            content.set_decorator(
+                SourceDecorator::new(
+                    file_path,
+                    last_line_number,
+                    p.gdb.breakpoints.values(),
+                    &self.diagnostics,
+                    self.profile,
+                )
+                .with_copy_anchor(self.copy_anchor.clone()),
+            );
         }
     }
 
-    fn need_to_load_file(&self, path: &Path) -> bool {
+    fn need_to_load_file(&self, path: &Path, local_path: &Path) -> bool {
         if let Some(ref loaded_file_info) = self.file_info {
-            if loaded_file_info.path != path {
+            if loaded_file_info.path != path || loaded_file_info.local_path != local_path {
                 return true;
             }
-            if let Ok(modified_new) = fs::metadata(path).and_then(|m| m.modified()) {
+            if let Ok(modified_new) = fs::metadata(local_path).and_then(|m| m.modified()) {
                 modified_new > loaded_file_info.modified
             } else {
                 true
@@ -605,18 +924,23 @@ impl<'a> SourceView<'a> {
         path: P,
         p: &mut ::Context,
     ) -> Result<(), PagerShowError> {
-        if self.need_to_load_file(path.as_ref()) {
-            let path_ref = path.as_ref();
-            self.load(path_ref, p.gdb.breakpoints.values())
-                .map_err(|e| PagerShowError::CouldNotOpenFile(path_ref.to_path_buf(), e))?;
+        let local_path = p.path_substitutions.apply(path.as_ref()).into_owned();
+        if self.need_to_load_file(path.as_ref(), &local_path) {
+            self.load(path.as_ref(), &local_path, p.gdb.breakpoints.values())
+                .map_err(|e| PagerShowError::CouldNotOpenFile(local_path, e))?;
         } else {
             let last_line_number = self.get_last_line_number_for(path.as_ref());
             if let Some(ref mut content) = self.pager.content_mut() {
-                content.set_decorator(SourceDecorator::new(
-                    path.as_ref(),
-                    last_line_number,
-                    p.gdb.breakpoints.values(),
-                ));
+                content.set_decorator(
+                    SourceDecorator::new(
+                        path.as_ref(),
+                        last_line_number,
+                        p.gdb.breakpoints.values(),
+                        &self.diagnostics,
+                        self.profile,
+                    )
+                    .with_copy_anchor(self.copy_anchor.clone()),
+                );
             }
         }
         Ok(())
@@ -631,7 +955,7 @@ impl<'a> SourceView<'a> {
 
     fn content_is_stale(&self) -> bool {
         if let Some(i) = &self.file_info {
-            self.need_to_load_file(&i.path)
+            self.need_to_load_file(&i.path, &i.local_path)
         } else {
             true
         }
@@ -640,33 +964,268 @@ impl<'a> SourceView<'a> {
     fn load<'b, P: AsRef<Path>, I: Iterator<Item = &'b BreakPoint>>(
         &mut self,
         path: P,
+        local_path: &Path,
         breakpoints: I,
     ) -> io::Result<()> {
-        let pager_content = PagerContent::from_file(path.as_ref())?;
-        let syntax = self
-            .syntax_set
-            .find_syntax_for_file(path.as_ref())
-            .expect("file IS openable, see pager content")
-            .unwrap_or(self.syntax_set.find_syntax_plain_text());
-        let last_line_number = self.get_last_line_number_for(path.as_ref());
-        self.pager.load(
-            pager_content
-                .with_highlighter(&SyntectHighlighter::new(syntax, self.highlighting_theme))
-                .with_decorator(SourceDecorator::new(
-                    path.as_ref(),
-                    last_line_number,
-                    breakpoints,
-                )),
-        );
+        let size = fs::metadata(local_path)?.len();
+        self.applied_encoding = None;
+        if size > MAX_SOURCE_FILE_SIZE {
+            self.file_kind = SourceFileKind::TooLarge;
+            self.lines = vec![format!(
+                "File is too large to be displayed ({} bytes, limit is {} bytes).",
+                size, MAX_SOURCE_FILE_SIZE
+            )];
+        } else {
+            let bytes = fs::read(local_path)?;
+            if is_binary(&bytes) {
+                self.file_kind = SourceFileKind::Binary;
+                self.lines = hexdump_lines(&bytes[..bytes.len().min(MAX_HEXDUMP_BYTES)], 0);
+                if bytes.len() > MAX_HEXDUMP_BYTES {
+                    self.lines.push(format!(
+                        "... ({} more bytes)",
+                        bytes.len() - MAX_HEXDUMP_BYTES
+                    ));
+                }
+            } else {
+                self.file_kind = SourceFileKind::Text;
+                let (content, applied_encoding) = encoding::decode(bytes, self.fallback_encoding);
+                self.lines = content.lines().map(|l| l.to_owned()).collect();
+                self.applied_encoding = applied_encoding;
+            }
+        }
+        self.folds.clear();
+        self.copy_anchor = None;
+        self.update_content(path.as_ref(), breakpoints);
         self.file_info = Some(FileInfo {
             path: path.as_ref().to_owned(),
-            modified: fs::metadata(path)?.modified()?,
+            local_path: local_path.to_owned(),
+            modified: fs::metadata(local_path)?.modified()?,
         });
         Ok(())
     }
 
+    fn update_content<'b, I: Iterator<Item = &'b BreakPoint>>(
+        &mut self,
+        path: &Path,
+        breakpoints: I,
+    ) {
+        let mut visible_lines = Vec::new();
+        let mut i = 0;
+        while i < self.lines.len() {
+            let fold_end = self
+                .folds
+                .iter()
+                .find(|&&(begin, _)| begin == i)
+                .map(|&(_, end)| end);
+            let line = if let Some(end) = fold_end {
+                let num_folded = end - i;
+                SourceLine {
+                    content: format!(
+                        "{} ⋯ ({} lines)",
+                        display_content(&self.lines[i]),
+                        num_folded
+                    ),
+                    number: LineIndex::new(i).into(),
+                    num_folded,
+                }
+            } else {
+                SourceLine {
+                    content: display_content(&self.lines[i]).into_owned(),
+                    number: LineIndex::new(i).into(),
+                    num_folded: 0,
+                }
+            };
+            i += line.num_folded + 1;
+            visible_lines.push(line);
+        }
+        // Do not try to highlight hexdumps or messages.
+        let syntax = if self.file_kind == SourceFileKind::Text {
+            self.syntax_set
+                .find_syntax_for_file(path)
+                .ok()
+                .and_then(|s| s)
+                .unwrap_or(self.syntax_set.find_syntax_plain_text())
+        } else {
+            self.syntax_set.find_syntax_plain_text()
+        };
+        let last_line_number = self.get_last_line_number_for(path);
+        self.pager.load(
+            PagerContent::from_lines(visible_lines)
+                .with_highlighter(&SyntectHighlighter::new(syntax, self.highlighting_theme))
+                .with_decorator(
+                    SourceDecorator::new(
+                        path,
+                        last_line_number,
+                        breakpoints,
+                        &self.diagnostics,
+                        self.profile,
+                    )
+                    .with_copy_anchor(self.copy_anchor.clone()),
+                ),
+        );
+    }
+
+    fn toggle_fold(&mut self, p: &mut ::Context) {
+        let line = self.current_line_number();
+        let index = line.raw_value() - 1;
+        if let Some(pos) = self.folds.iter().position(|&(begin, _)| begin == index) {
+            self.folds.remove(pos);
+        } else if let Some((begin, end)) = find_enclosing_block(&self.lines, index) {
+            self.folds.retain(|&(b, e)| e < begin || end < b);
+            self.folds.push((begin, end));
+        } else {
+            return;
+        }
+        self.refresh_folds(line, p);
+    }
+
+    fn unfold_all(&mut self, p: &mut ::Context) {
+        if !self.folds.is_empty() {
+            let line = self.current_line_number();
+            self.folds.clear();
+            self.refresh_folds(line, p);
+        }
+    }
+
+    fn refresh_folds(&mut self, line: LineNumber, p: &mut ::Context) {
+        if let Some(path) = self.file_info.as_ref().map(|i| i.path.clone()) {
+            self.update_content(&path, p.gdb.breakpoints.values());
+            let _ = self.go_to_line(line);
+        }
+    }
+
     fn current_line_number(&self) -> LineNumber {
-        self.pager.current_line_index().into()
+        self.pager
+            .current_line()
+            .map(|l| l.number)
+            .unwrap_or_else(|| self.pager.current_line_index().into())
+    }
+
+    fn set_diagnostics(&mut self, diagnostics: Vec<Diagnostic>, p: &mut ::Context) {
+        self.diagnostics = diagnostics;
+        self.update_decoration(p);
+    }
+
+    // The most severe diagnostic for the current line (or fold).
+    fn current_diagnostic(&self) -> Option<&Diagnostic> {
+        let file = &self.file_info.as_ref()?.path;
+        let range = self.current_line_range();
+        self.diagnostics
+            .iter()
+            .filter(|d| d.pos.file == *file && range.contains(&d.pos.line.raw_value()))
+            .max_by_key(|d| d.severity)
+    }
+
+    fn current_line_range(&self) -> Range<usize> {
+        self.pager
+            .current_line()
+            .map(|l| l.folded_range())
+            .unwrap_or_else(|| {
+                let n = self.current_line_number().raw_value();
+                n..n + 1
+            })
+    }
+
+    // Range of (1-based) line numbers between the copy anchor and the current line, including
+    // all lines of folds at the ends of the selection.
+    fn copy_selection(&self) -> Option<Range<usize>> {
+        self.copy_anchor.as_ref().map(|anchor| {
+            let current = self.current_line_range();
+            anchor.start.min(current.start)..anchor.end.max(current.end)
+        })
+    }
+
+    fn toggle_copy_mode(&mut self, p: &mut ::Context) {
+        self.copy_anchor = if self.copy_anchor.is_some() {
+            None
+        } else {
+            Some(self.current_line_range())
+        };
+        self.update_decoration(p);
+    }
+
+    fn yank(&mut self, p: &mut ::Context) {
+        let selection = self
+            .copy_selection()
+            .unwrap_or_else(|| self.current_line_range());
+        let text = self
+            .lines
+            .get(selection.start - 1..(selection.end - 1).min(self.lines.len()))
+            .unwrap_or(&[])
+            .join("\n");
+        match clipboard::copy(&text) {
+            Ok(()) => p.log(format!("Copied {} lines.", selection.len())),
+            Err(e) => p.log(format!("Failed to copy to clipboard: {}", e)),
+        }
+        self.copy_anchor = None;
+        self.update_decoration(p);
+    }
+
+    // Show the values of the variables in the current line (or the selected lines) in the
+    // selected frame, e.g., to quickly inspect them without adding them to the expression table.
+    fn inspect_values(&mut self, p: &mut ::Context) {
+        let selection = self
+            .copy_selection()
+            .unwrap_or_else(|| self.current_line_range());
+        let mut expressions = Vec::<String>::new();
+        for line in self
+            .lines
+            .get(selection.start - 1..(selection.end - 1).min(self.lines.len()))
+            .unwrap_or(&[])
+        {
+            for expression in inspectable_expressions(line) {
+                if !expressions.contains(&expression) {
+                    expressions.push(expression);
+                }
+            }
+        }
+        if expressions.is_empty() {
+            p.log("There is nothing to evaluate in the current line.");
+            return;
+        }
+        let values = match p.gdb.evaluate_many(&expressions) {
+            Ok(values) => values,
+            Err(ExecuteError::Busy) => {
+                p.log("Cannot evaluate: Gdb is busy.");
+                return;
+            }
+            Err(e) => {
+                p.log(format!("Failed to evaluate: {:?}", e));
+                return;
+            }
+        };
+        // Errors are expected for keywords, types and names from other scopes.
+        let text = expressions
+            .iter()
+            .zip(values)
+            .filter_map(|(expression, value)| Some(format!("{} = {}", expression, value.ok()?)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if text.is_empty() {
+            p.log("None of the names can be evaluated in the selected frame.");
+        } else {
+            p.show_overlay(Message::new("Values", text));
+        }
+    }
+
+    fn go_to_definition(&mut self, p: &mut ::Context) {
+        let line = self.current_line_number();
+        let content = if let Some(l) = self.lines.get(line.raw_value() - 1) {
+            l.clone()
+        } else {
+            return;
+        };
+        let current_file = self.current_file().map(|f| f.to_owned());
+        for ident in identifiers_by_relevance(&content) {
+            if let Some(pos) = p.find_definition(ident) {
+                if Some(&pos.file) == current_file.as_ref() && pos.line == line {
+                    continue;
+                }
+                p.show_file(pos.file.to_string_lossy().into_owned(), pos.line);
+                return;
+            }
+        }
+        p.log("Could not find a definition for any symbol in the current line.");
     }
 
     fn current_file(&self) -> Option<&Path> {
@@ -711,6 +1270,28 @@ impl<'a> SourceView<'a> {
         }
     }
 
+    fn prompt_ignore_count(&self, p: &mut ::Context) {
+        let line = self.current_line_number();
+        let breakpoint = self.current_file().and_then(|path| {
+            p.gdb
+                .breakpoints
+                .values()
+                .find(|bp| {
+                    bp.src_pos
+                        .as_ref()
+                        .map(|pos| pos.file == path && pos.line == line)
+                        .unwrap_or(false)
+                })
+                .map(|bp| (bp.number.major, bp.ignore_count))
+        });
+        match breakpoint {
+            Some((major, ignore_count)) => {
+                ::tui::breakpoints::prompt_ignore_count(major, ignore_count, p)
+            }
+            None => p.log("There is no breakpoint in the current line."),
+        }
+    }
+
     fn event(&mut self, event: Input, p: &mut ::Context) -> Option<Input> {
         event
             .chain(
@@ -723,6 +1304,13 @@ impl<'a> SourceView<'a> {
                     .to_end_on(Key::End),
             )
             .chain((Key::Char(' '), || self.toggle_breakpoint(p)))
+            .chain((Key::Char('i'), || self.prompt_ignore_count(p)))
+            .chain((Key::Char('z'), || self.toggle_fold(p)))
+            .chain((Key::Char('Z'), || self.unfold_all(p)))
+            .chain((Key::Char('g'), || self.go_to_definition(p)))
+            .chain((Key::Char('p'), || self.inspect_values(p)))
+            .chain((Key::Char('v'), || self.toggle_copy_mode(p)))
+            .chain((Key::Char('y'), || self.yank(p)))
             .finish()
     }
 }
@@ -757,75 +1345,65 @@ struct StackInfo {
     function: Option<String>,
 }
 
-impl<'a> Widget for &'a StackInfo {
-    fn space_demand(&self) -> Demand2D {
-        Demand2D {
-            // TODO: reenable this once configurable layouts are a thing.
-            /*
-            width: Demand::at_least(
-                Width::new(
-                    (self
-                        .file_path
-                        .as_ref()
-                        .map(|p| p.to_string_lossy().len())
-                        .unwrap_or(0)
-                        + self.function.as_ref().map(|p| p.len()).unwrap_or(0))
-                        as i32,
-                )
-                .unwrap(),
-            ),
-            */
-            width: Demand::at_least(Width::new(1).unwrap()),
-            height: Demand::exact(Height::new(1).unwrap()),
-        }
-    }
-    fn draw(&self, mut window: Window, _hints: RenderingHints) {
-        use std::fmt::Write;
-        let width = window.get_width();
-        let mut cursor = Cursor::new(&mut window).style_modifier(StyleModifier::new().bold(true));
-        let _ = write!(cursor, "[");
-        if let Some(l) = self.stack_level {
-            let _ = write!(cursor, "{}", l);
-        } else {
-            let _ = write!(cursor, "?");
+// Number of lines with code in multiple non-contiguous address ranges from which on a function is
+// considered to be optimized.
+const SPLIT_LINES_OPTIMIZATION_THRESHOLD: usize = 3;
+
+/// Heuristic indications that the function of the current frame was compiled with optimizations.
+struct OptimizationHints {
+    optimized_out: Vec<String>,
+    num_split_lines: usize,
+}
+
+impl OptimizationHints {
+    fn is_optimized(&self) -> bool {
+        !self.optimized_out.is_empty() || self.num_split_lines >= SPLIT_LINES_OPTIMIZATION_THRESHOLD
+    }
+
+    fn badge(&self) -> String {
+        let mut reasons = Vec::new();
+        if !self.optimized_out.is_empty() {
+            reasons.push(format!("{} values optimized out", self.optimized_out.len()));
         }
-        let _ = write!(cursor, "/");
-        if let Some(l) = self.stack_depth {
-            let _ = write!(cursor, "{}", l);
-        } else {
-            let _ = write!(cursor, "?");
+        if self.num_split_lines >= SPLIT_LINES_OPTIMIZATION_THRESHOLD {
+            reasons.push(format!("{} split lines", self.num_split_lines));
         }
-        let _ = write!(cursor, "] ");
+        format!(
+            "optimized? {}; stepping and values may be misleading, L: line table",
+            reasons.join(", ")
+        )
+    }
+}
 
-        if let Some(f) = &self.function {
-            let _ = write!(cursor, "{}", f);
-        } else {
-            let _ = write!(cursor, "?");
-        }
-        {
-            let mut cursor = cursor.save().style_modifier();
-            cursor.set_style_modifier(StyleModifier::new().bold(false));
-            let _ = write!(cursor, " @ ");
-        }
-
-        if let Some(f) = &self.file_path {
-            let path_str = f.to_string_lossy();
-            let remaining_space = (width.raw_value() as usize)
-                .checked_sub(cursor.get_col().raw_value() as _)
-                .unwrap_or(0);
-            if remaining_space >= text_width(path_str.as_ref()).raw_value() as _ {
-                let _ = write!(cursor, "{}", path_str);
-            } else {
-                // Not enough space, only show file itself
-                if let Some(n) = f.file_name() {
-                    let _ = write!(cursor, "{}", n.to_string_lossy());
+impl StackInfo {
+    // E.g., "[0/3] main @ /home/user/src/main.c" or "[0/3] main @ main.c" if the path does not fit
+    // into the width.
+    fn title(&self, width: usize) -> String {
+        let unknown = || "?".to_owned();
+        let level = self.stack_level.map(|l| l.to_string());
+        let depth = self.stack_depth.map(|d| d.to_string());
+        let prefix = format!(
+            "[{}/{}] {} @ ",
+            level.unwrap_or_else(unknown),
+            depth.unwrap_or_else(unknown),
+            self.function.clone().unwrap_or_else(unknown)
+        );
+        let path = match &self.file_path {
+            Some(f) => {
+                let path_str = f.to_string_lossy();
+                let path_width = text_width(prefix.as_str()) + text_width(path_str.as_ref());
+                if path_width.raw_value() as usize <= width {
+                    path_str.into_owned()
                 } else {
-                    let _ = write!(cursor, "?");
+                    // Not enough space, only show file itself
+                    f.file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_else(unknown)
                 }
             }
-        } else {
-            let _ = write!(cursor, "?");
-        }
+            None => unknown(),
+        };
+        prefix + &path
     }
 }
 
@@ -843,12 +1421,13 @@ impl From<ExecuteError> for DisassembleError {
 fn disassemble_address(
     address_start: Address,
     address_end: Address,
+    mode: DisassembleMode,
     p: &mut ::Context,
 ) -> Result<Vec<JsonValue>, DisassembleError> {
     let mut disass_results = match p.gdb.mi.execute(MiCommand::data_disassemble_address(
         address_start.0,
         address_end.0,
-        DisassembleMode::DisassemblyOnly,
+        mode,
     )) {
         Ok(o) => {
             if o.class == ResultClass::Error {
@@ -882,26 +1461,152 @@ fn disassemble_address(
     }
 }
 
+// Number of bytes after the stop position that are disassembled to find the calls of the line.
+const STEP_HINT_RANGE: usize = 512;
+
+// The functions called by the instructions of the line from the stop position on (up to the first
+// instruction of another line).
+fn calls_of_line(pc: Address, line: LineNumber, p: &mut ::Context) -> Vec<String> {
+    let result = match p.gdb.mi.execute(MiCommand::data_disassemble_address(
+        pc.0,
+        pc.0 + STEP_HINT_RANGE,
+        DisassembleMode::MixedSourceAndDisassembly,
+    )) {
+        Ok(result) if result.class == ResultClass::Done => result.results,
+        _ => return Vec::new(),
+    };
+    AssemblyView::get_instructions(&result)
+        .unwrap_or_default()
+        .iter()
+        .skip_while(|i| i.address < pc)
+        .take_while(|i| i.src_position.as_ref().map(|p| p.line) == Some(line))
+        .filter_map(|i| call_target(&i.content))
+        .collect()
+}
+
+/// What `step` and `next` would do with the calls of the current line: `step` descends into the
+/// first call (unless it goes through the PLT, i.e., to a function without debug information in
+/// a shared library), `next` executes all of them.
+fn step_hint(calls: &[String]) -> Option<String> {
+    let mut unique = Vec::new();
+    for call in calls {
+        if !unique.contains(call) {
+            unique.push(call.clone());
+        }
+    }
+    if unique.is_empty() {
+        return None;
+    }
+    let over = unique.join(", ");
+    Some(match calls.iter().find(|c| !c.ends_with("@plt")) {
+        Some(into) => format!("step: into {}; next: over {}", into, over),
+        None => format!("step/next: over {}", over),
+    })
+}
+
 pub struct CodeWindow<'a> {
     src_view: SourceView<'a>,
     asm_view: AssemblyView<'a>,
     preferred_mode: DisplayMode,
     src_state: SrcContentState,
     asm_state: AsmContentState,
-    last_bp_update: ::std::time::Instant,
     stack_info: StackInfo,
+    optimization_hints: Option<OptimizationHints>,
+    line_table_cache: HashMap<(PathBuf, String), LineTableSummary>,
+    function_lines: Option<(PathBuf, LineNumber)>, // Last line of the function of the frame
+    executable_modified: Option<Option<SystemTime>>, // None: Not yet requested
+    step_hint: Option<(SrcPosition, String)>,      // Shown below the source of the stop position
+    step_hint_cache: HashMap<(Address, LineNumber), Option<String>>,
+}
+
+// What is used of the line table of a function.
+#[derive(Clone, Copy, Default)]
+struct LineTableSummary {
+    num_split_lines: usize,
+    last_line: Option<LineNumber>,
 }
 
 impl<'a> CodeWindow<'a> {
-    pub fn new(highlighting_theme: &'a Theme, welcome_msg: &'static str) -> Self {
+    pub fn new(
+        highlighting_theme: &'a Theme,
+        welcome_msg: &'static str,
+        fallback_encoding: FallbackEncoding,
+        profile: RenderProfile,
+    ) -> Self {
         CodeWindow {
-            src_view: SourceView::new(highlighting_theme),
-            asm_view: AssemblyView::new(highlighting_theme),
+            src_view: SourceView::new(highlighting_theme, fallback_encoding, profile),
+            asm_view: AssemblyView::new(highlighting_theme, profile),
             preferred_mode: DisplayMode::Message(welcome_msg.to_owned()),
             src_state: SrcContentState::Unavailable,
             asm_state: AsmContentState::Unavailable,
-            last_bp_update: ::std::time::Instant::now(),
             stack_info: Default::default(),
+            optimization_hints: None,
+            line_table_cache: HashMap::new(),
+            function_lines: None,
+            executable_modified: None,
+            step_hint: None,
+            step_hint_cache: HashMap::new(),
+        }
+    }
+
+    // The line table of a function only changes if the executable is loaded again, so it is only
+    // requested once until then. The last line of the function is remembered to detect source
+    // files that do not match the executable. Of the variables, only the arguments are checked,
+    // because the stop record contains their values.
+    fn detect_optimization(
+        &mut self,
+        frame: &Frame,
+        file: PathBuf,
+        function: String,
+        p: &mut ::Context,
+    ) -> OptimizationHints {
+        let optimized_out = frame
+            .args
+            .iter()
+            .filter(|a| a.value.as_deref() == Some("<optimized out>"))
+            .map(|a| a.name.clone())
+            .collect();
+        let summary = *self
+            .line_table_cache
+            .entry((file.clone(), function))
+            .or_insert_with(|| {
+                p.gdb
+                    .get_line_table()
+                    .map(|t| LineTableSummary {
+                        num_split_lines: t.num_split_lines(),
+                        last_line: t.last_line(),
+                    })
+                    .unwrap_or_default()
+            });
+        self.function_lines = summary.last_line.map(|line| (file, line));
+        OptimizationHints {
+            optimized_out,
+            num_split_lines: summary.num_split_lines,
+        }
+    }
+
+    /// Why the shown source file probably does not match the executable (if it does not).
+    fn source_mismatch(&self) -> Option<String> {
+        let file_info = self.src_view.file_info.as_ref()?;
+        if let Some(Some(executable_modified)) = self.executable_modified {
+            if file_info.modified > executable_modified {
+                return Some(
+                    "Source file is newer than the executable: Lines may not match the program (rebuild and !reload)."
+                        .to_owned(),
+                );
+            }
+        }
+        match &self.function_lines {
+            Some((file, last_line))
+                if *file == file_info.path && last_line.raw_value() > self.src_view.lines.len() =>
+            {
+                Some(format!(
+                    "The line table refers to line {}, but the source file only has {} lines: It does not match the executable.",
+                    last_line,
+                    self.src_view.lines.len()
+                ))
+            }
+            _ => None,
         }
     }
 
@@ -1000,7 +1705,8 @@ impl<'a> CodeWindow<'a> {
     }
 
     fn find_function_range(at: Address, p: &mut ::Context) -> Result<(Address, Address), ()> {
-        let first_lines = disassemble_address(at, at + 16, p).map_err(|_| ())?;
+        let first_lines = disassemble_address(at, at + 16, DisassembleMode::DisassemblyOnly, p)
+            .map_err(|_| ())?;
         let current = first_lines.first().ok_or(())?;
         let asm_debug_location = AssemblyDebugLocation::try_from_value(current).ok_or(())?;
         let begin = at - asm_debug_location.offset;
@@ -1008,8 +1714,13 @@ impl<'a> CodeWindow<'a> {
         let block_size = 128;
         let mut current = at;
         let func_change_block = loop {
-            let current_block_lines =
-                disassemble_address(current, current + block_size, p).map_err(|_| ())?;
+            let current_block_lines = disassemble_address(
+                current,
+                current + block_size,
+                DisassembleMode::DisassemblyOnly,
+                p,
+            )
+            .map_err(|_| ())?;
             {
                 let penultimate_index = current_block_lines.len().checked_sub(2).ok_or(())?;
                 let penultimate = current_block_lines
@@ -1038,7 +1749,12 @@ impl<'a> CodeWindow<'a> {
         approx_byte_size: usize,
         p: &mut ::Context,
     ) -> Result<(Address, Address), DisassembleError> {
-        let block_lines = disassemble_address(at, at + approx_byte_size, p)?;
+        let block_lines = disassemble_address(
+            at,
+            at + approx_byte_size,
+            DisassembleMode::DisassemblyOnly,
+            p,
+        )?;
 
         let penultimate_index = block_lines
             .len()
@@ -1051,14 +1767,103 @@ impl<'a> CodeWindow<'a> {
         Ok((at, end_address))
     }
 
+    pub fn set_diagnostics(&mut self, diagnostics: Vec<Diagnostic>, p: &mut ::Context) {
+        self.src_view.set_diagnostics(diagnostics, p);
+    }
+
+    pub fn current_source_file(&self) -> Option<&Path> {
+        self.src_view.file_info.as_ref().map(|i| i.path.as_path())
+    }
+
+    pub fn current_position(&self) -> Option<SrcPosition> {
+        self.current_source_file()
+            .map(|file| SrcPosition::new(file.to_owned(), self.src_view.current_line_number()))
+    }
+
+    /// Like `current_position`, but with the path substitutions applied, i.e., the file on this
+    /// machine.
+    pub fn current_local_position(&self) -> Option<SrcPosition> {
+        self.src_view
+            .file_info
+            .as_ref()
+            .map(|i| SrcPosition::new(i.local_path.clone(), self.src_view.current_line_number()))
+    }
+
+    /// Load the shown source file again if it was modified (e.g., in an editor) and stay on the
+    /// current line.
+    pub fn reload_if_changed(&mut self, p: &mut ::Context) {
+        if self.src_view.file_info.is_none() || !self.src_view.content_is_stale() {
+            return;
+        }
+        let line = self.src_view.current_line_number();
+        match self.src_view.reload(p) {
+            Ok(()) => {
+                let _ = self.src_view.go_to_line(line);
+                self.src_view.update_decoration(p);
+            }
+            Err(e) => warn!("Failed to reload file: {:?}", e),
+        }
+    }
+
+    /// The current frame and notes on the shown file, e.g., its encoding.
+    pub fn title_bar(&self, width: usize) -> TitleBar {
+        let mode = self.available_display_mode();
+        if let DisplayMode::Message(_) = mode {
+            return TitleBar {
+                title: TuiContainerType::SrcView.name().to_owned(),
+                badges: Vec::new(),
+            };
+        }
+        let mut badges = Vec::new();
+        match (
+            &mode,
+            self.src_view.file_kind,
+            self.src_view.applied_encoding,
+        ) {
+            (DisplayMode::Assembly, _, _) => {}
+            (_, SourceFileKind::Binary, _) => badges.push(Badge::info("binary")),
+            (_, SourceFileKind::TooLarge, _) => badges.push(Badge::info("too large")),
+            (_, SourceFileKind::Text, Some(e)) => badges.push(Badge::info(e.to_string())),
+            (_, SourceFileKind::Text, None) => {}
+        }
+        if let DisplayMode::Source | DisplayMode::SideBySide = mode {
+            if let Some(s) = self.src_view.copy_selection() {
+                badges.push(Badge::info(format!("copy: {} lines", s.len())));
+            }
+            if let Some(d) = self.src_view.current_diagnostic() {
+                badges.push(Badge::info(format!("{}: {}", d.severity, d.message)));
+            }
+        }
+        match &self.optimization_hints {
+            Some(h) if h.is_optimized() => badges.push(Badge::warning(h.badge())),
+            _ => {}
+        }
+        TitleBar {
+            title: self.stack_info.title(width),
+            badges,
+        }
+    }
+
     pub fn show_file(&mut self, file: String, line: LineNumber, p: &mut ::Context) {
-        let mut object = Object::new();
-        object.insert("fullname", JsonValue::String(file));
-        object.insert("line", JsonValue::String(line.to_string()));
-        self.show_frame(&object, p);
+        let frame = Frame {
+            fullname: Some(PathBuf::from(file)),
+            line: Some(line.raw_value() as u64),
+            ..Frame::default()
+        };
+        self.show_frame(&frame, p);
+    }
+
+    /// Show the instructions at an address, e.g., a match of a memory search.
+    pub fn show_address(&mut self, address: Address, p: &mut ::Context) {
+        let frame = Frame {
+            addr: Some(address.0),
+            ..Frame::default()
+        };
+        self.show_frame(&frame, p);
+        let _ = self.asm_view.go_to_address(address);
     }
 
-    pub fn show_frame(&mut self, frame: &Object, p: &mut ::Context) {
+    pub fn show_frame(&mut self, frame: &Frame, p: &mut ::Context) {
         // Always try to switch away from (relatively unhelpful) message to srcview:
         if let DisplayMode::Message(_) = self.preferred_mode {
             self.preferred_mode = DisplayMode::Source;
@@ -1067,21 +1872,31 @@ impl<'a> CodeWindow<'a> {
         self.src_state = SrcContentState::Unavailable;
         self.asm_state = AsmContentState::Unavailable;
 
-        self.stack_info.stack_level = p.gdb.get_stack_level().ok();
-        self.stack_info.stack_depth = p.gdb.get_stack_depth().ok();
-        self.stack_info.file_path = frame["fullname"].as_str().map(|s| PathBuf::from(s));
-        self.stack_info.function = frame["func"].as_str().map(|s| s.to_owned());
-
-        if let Some(path) = frame["fullname"].as_str() {
-            let path = PathBuf::from(path);
+        self.stack_info.stack_level = p.gdb.snapshot.stack_level;
+        self.stack_info.stack_depth = p.gdb.snapshot.stack_depth;
+        self.stack_info.file_path = frame.fullname.clone();
+        self.stack_info.function = frame.func.clone();
+        if self.executable_modified.is_none() {
+            let executable = p.gdb.get_target().ok().and_then(|t| t);
+            self.executable_modified =
+                Some(executable.and_then(|e| fs::metadata(e).and_then(|m| m.modified()).ok()));
+        }
+        self.function_lines = None;
+        self.optimization_hints = match (&self.stack_info.file_path, &self.stack_info.function) {
+            (Some(file), Some(function)) => {
+                Some(self.detect_optimization(frame, file.clone(), function.clone(), p))
+            }
+            _ => None,
+        };
 
+        if let Some(path) = frame.fullname.clone() {
             self.src_state = match self.src_view.current_file() {
                 Some(f) if f == path => SrcContentState::Available,
                 _ => SrcContentState::NotYetLoaded(path.clone()),
             };
 
-            match get_u64_obj(frame, "line") {
-                Ok(line) => {
+            match frame.line {
+                Some(line) => {
                     let line = LineNumber::new(line as usize);
 
                     self.src_view.set_last_stop_position(path.clone(), line);
@@ -1095,20 +1910,20 @@ impl<'a> CodeWindow<'a> {
                     } else {
                         AsmContentState::NotYetLoadedFile(path, line.into())
                     };
-                    match get_addr_obj(frame, "addr") {
-                        Ok(address) => self.asm_view.set_last_stop_position(address),
-                        Err(e) => warn!("Failed get address from frame: {:?}", e),
+                    match frame.addr {
+                        Some(address) => self.asm_view.set_last_stop_position(Address(address)),
+                        None => warn!("Missing address in frame: {:?}", frame),
                     }
                 }
-                Err(e) => warn!("Failed get line from frame: {:?}", e),
+                None => warn!("Missing line in frame: {:?}", frame),
             }
         };
 
         // If we were not able to load asm via file information, try loading from the address.
         // This may be the case for jit compiled code or PLT entries or something like that.
         if self.asm_state == AsmContentState::Unavailable {
-            match get_addr_obj(frame, "addr") {
-                Ok(address) => {
+            match frame.addr.map(Address) {
+                Some(address) => {
                     if self.asm_view.go_to_address(address).is_ok() {
                         self.asm_state = AsmContentState::Available;
                     } else {
@@ -1125,10 +1940,30 @@ impl<'a> CodeWindow<'a> {
                     }
                     self.asm_view.set_last_stop_position(address);
                 }
-                Err(e) => warn!("Failed get address from frame: {:?}", e),
+                None => warn!("Missing address in frame: {:?}", frame),
             }
         }
 
+        // Only the innermost frame is stepped. The instructions at a position only change if the
+        // executable is loaded again, so the line is only disassembled once until then (e.g., in
+        // a loop).
+        self.step_hint = match (
+            &frame.fullname,
+            frame.line,
+            frame.addr,
+            self.stack_info.stack_level,
+        ) {
+            (Some(path), Some(line), Some(address), Some(0)) => {
+                let line = LineNumber::new(line as usize);
+                self.step_hint_cache
+                    .entry((Address(address), line))
+                    .or_insert_with(|| step_hint(&calls_of_line(Address(address), line, p)))
+                    .clone()
+                    .map(|hint| (SrcPosition::new(path.clone(), line), hint))
+            }
+            _ => None,
+        };
+
         self.try_load_active_content(p);
         let _ = self.asm_view.go_to_last_stop_position();
         let _ = self.src_view.go_to_last_stop_position();
@@ -1179,41 +2014,43 @@ impl<'a> CodeWindow<'a> {
         p: &mut ::Context,
         up: bool,
     ) -> Result<(), GDBResponseError> {
-        let level = p.gdb.get_stack_level()?;
+        let level = match p.gdb.snapshot.stack_level {
+            Some(level) => level,
+            None => return Ok(()),
+        };
 
         let new_level = if up {
-            let depth = p.gdb.get_stack_depth()?;
+            let depth = p.gdb.snapshot.stack_depth.unwrap_or(0);
             (level + 1).min(depth.checked_sub(1).unwrap_or(0))
         } else {
             level.checked_sub(1).unwrap_or(0)
         };
 
         if level != new_level {
-            p.gdb.mi.execute_later(MiCommand::select_frame(new_level));
-
-            match p.gdb.mi.execute(MiCommand::stack_info_frame(None)) {
-                Ok(o) => {
-                    if o.class == ResultClass::Done {
-                        if let JsonValue::Object(ref frame) = o.results["frame"] {
-                            self.show_frame(frame, p);
-                        } else {
-                            return Err(GDBResponseError::MissingField(
-                                "frame",
-                                JsonValue::Object(o.results.clone()),
-                            ));
-                        }
-                    } else {
-                        return Err(GDBResponseError::Other(format!(
-                            "Unexpected result class: {:?}",
-                            o.class
-                        )));
-                    }
-                }
-                Err(_) => return Ok(()), //Ignore
-            };
+            // gdb reports the selected frame, which is then shown like a stop.
+            let record = p.gdb.select_frame(new_level)?;
+            p.report_selection(record);
         }
         Ok(())
     }
+    fn prompt_go_to_line(&mut self, p: &mut ::Context) {
+        let file = match self.current_source_file() {
+            Some(file) => file.to_string_lossy().into_owned(),
+            None => {
+                p.log("No source file is shown.");
+                return;
+            }
+        };
+        p.show_overlay(Prompt::new(
+            "Go to line",
+            "",
+            move |line: String, p: &mut ::Context| match line.trim().parse::<usize>() {
+                Ok(line) if line > 0 => p.show_file(file, LineNumber::new(line)),
+                _ => p.log(format!("Not a line number: {}", line)),
+            },
+        ));
+    }
+
     fn switch_stackframe(&mut self, p: &mut ::Context, up: bool) {
         match self.try_switch_stackframe(p, up) {
             Ok(_) => {}
@@ -1222,12 +2059,38 @@ impl<'a> CodeWindow<'a> {
             }
         }
     }
+}
 
-    pub fn update_after_event(&mut self, p: &mut ::Context) {
-        if p.gdb.breakpoints.last_change > self.last_bp_update {
-            self.asm_view.update_decoration(p);
-            self.src_view.update_decoration(p);
-            self.last_bp_update = p.gdb.breakpoints.last_change;
+impl<'a> Subscriber for CodeWindow<'a> {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        match event {
+            DebugEvent::Stopped(Some(frame)) => self.show_frame(frame, p),
+            DebugEvent::BreakpointsChanged => {
+                self.asm_view.update_decoration(p);
+                self.src_view.update_decoration(p);
+            }
+            DebugEvent::DisassemblyFlavorChanged => {
+                if let Err(e) = self.asm_view.reload(p) {
+                    warn!("Failed to reload assembly: {:?}", e);
+                }
+            }
+            DebugEvent::SymbolsReloaded => {
+                self.executable_modified = None;
+                self.line_table_cache.clear();
+                self.step_hint_cache.clear();
+            }
+            DebugEvent::PathSubstitutionsChanged => {
+                // The file may be found now (or another one is found at its new location).
+                let file = match self.src_state {
+                    SrcContentState::NotYetLoaded(ref file) => Some(file.clone()),
+                    SrcContentState::Available => self.src_view.current_file().map(Path::to_owned),
+                    SrcContentState::Unavailable => self.stack_info.file_path.clone(),
+                };
+                if let Some(file) = file {
+                    self.src_state = SrcContentState::NotYetLoaded(file);
+                }
+            }
+            _ => {}
         }
     }
 }
@@ -1236,6 +2099,8 @@ impl<'a> Container<::Context> for CodeWindow<'a> {
     fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
         input
             .chain((Key::Char('d'), || self.toggle_mode(p)))
+            .chain((Key::Char('L'), || p.show_current_line_table()))
+            .chain((Key::Char(':'), || self.prompt_go_to_line(p)))
             .chain((Key::PageUp, || self.switch_stackframe(p, true)))
             .chain((Key::PageDown, || self.switch_stackframe(p, false)))
             .chain(|i: Input| match self.available_display_mode() {
@@ -1260,12 +2125,33 @@ impl<'a> Container<::Context> for CodeWindow<'a> {
     }
     fn as_widget<'e>(&'e self) -> Box<dyn Widget + 'e> {
         let mode = self.available_display_mode();
+        let step_hint = match (&self.step_hint, &mode) {
+            (Some((pos, hint)), DisplayMode::Source | DisplayMode::SideBySide)
+                if self.src_view.current_file() == Some(pos.file.as_path()) =>
+            {
+                Some(format!("{} ◂ {}", pos.line, hint))
+            }
+            _ => None,
+        };
 
         let mut r = VLayout::new();
-        if let DisplayMode::Assembly | DisplayMode::Source | DisplayMode::SideBySide = mode {
-            r = r.widget(&self.stack_info)
+        if let DisplayMode::Source | DisplayMode::SideBySide = mode {
+            if let Some(warning) = self.source_mismatch() {
+                r = r.widget(warning.with_window(|w, _| {
+                    let mut w = w;
+                    w.set_default_style(
+                        StyleModifier::new()
+                            .fg_color(Color::Black)
+                            .bg_color(Color::Yellow)
+                            .bold(true)
+                            .apply_to_default(),
+                    );
+                    w.clear();
+                    w
+                }));
+            }
         }
-        r = match mode {
+        let r = match mode {
             DisplayMode::Assembly => r.widget(self.asm_view.pager.as_widget()),
             DisplayMode::SideBySide => r.widget(
                 HLayout::new()
@@ -1279,6 +2165,133 @@ impl<'a> Container<::Context> for CodeWindow<'a> {
                 height: RowDemand::at_least(d.height.min),
             })),
         };
+        let r = match step_hint {
+            Some(hint) => r.widget(hint.with_window(|w, _| {
+                let mut w = w;
+                w.set_default_style(StyleModifier::new().italic(true).apply_to_default());
+                w.clear();
+                w
+            })),
+            None => r,
+        };
         Box::new(r)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_step_hint() {
+        let calls = |c: &[&str]| c.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(step_hint(&[]), None);
+        assert_eq!(
+            step_hint(&calls(&["printf@plt", "compute", "printf@plt"])),
+            Some("step: into compute; next: over printf@plt, compute".to_owned())
+        );
+        assert_eq!(
+            step_hint(&calls(&["puts@plt"])),
+            Some("step/next: over puts@plt".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_display_content() {
+        // Wide, combining, and emoji (zwj) sequences are passed through unchanged.
+        let wide = "let s = \"日本語 e\u{301} 👩\u{200D}💻\"; // ok";
+        assert_eq!(display_content(wide), wide);
+        assert_eq!(text_width(&display_content("日本語")).raw_value(), 6);
+        assert_eq!(text_width(&display_content("e\u{301}")).raw_value(), 1);
+
+        // Tabs are expanded to the tab stops according to the display width of the text in front.
+        assert_eq!(display_content("\tx"), "    x");
+        assert_eq!(display_content("ab\tx"), "ab  x");
+        assert_eq!(display_content("日本\tx"), "日本    x");
+        assert_eq!(display_content("日\tx"), "日  x");
+        assert_eq!(display_content("e\u{301}\tx"), "e\u{301}   x");
+        assert_eq!(display_content("🦀\tx"), "🦀  x");
+        assert_eq!(display_content("\x01\tx"), "^A  x");
+        // The columns of code after tabs line up independently of the characters in front.
+        let column = |line: &str| {
+            let content = display_content(line);
+            text_width(&content[..content.find('x').unwrap()]).raw_value()
+        };
+        for line in ["a\tx", "日\tx", "e\u{301}\u{301}\tx", "🦀\tx", "abc\tx"] {
+            assert_eq!(column(line), 4, "{:?}", line);
+        }
+
+        assert_eq!(display_content("a\x0cb\x7f"), "a^Lb^?");
+        assert_eq!(
+            display_content("x = 1; \u{202E} } \u{2066}"),
+            "x = 1; <U+202E> } <U+2066>"
+        );
+        assert_eq!(display_content("\u{85}"), "<U+0085>");
+    }
+
+    #[test]
+    fn test_identifiers_by_relevance() {
+        assert_eq!(
+            identifiers_by_relevance("let x_1 = foo (bar(2), baz, 0x1f, x_1);"),
+            vec!["foo", "bar", "x_1", "baz"]
+        );
+    }
+
+    #[test]
+    fn test_inspectable_expressions() {
+        assert_eq!(
+            inspectable_expressions("s->len = foo(a.b, 0x1f) + x_1 * s->len;"),
+            vec!["s->len", "a.b", "x_1"]
+        );
+        assert_eq!(
+            inspectable_expressions("if (p.next->value > 2.5f) return;"),
+            vec!["p.next->value", "return"]
+        );
+        assert_eq!(inspectable_expressions("a. b"), vec!["a", "b"]);
+        assert!(inspectable_expressions("// 42").is_empty());
+    }
+
+    #[test]
+    fn test_find_enclosing_block() {
+        let lines = [
+            "fn main() {",
+            "    let s = \"{\";",
+            "    if true {",
+            "        foo(); // }",
+            "    }",
+            "}",
+            "fn bar() { }",
+        ];
+        assert_eq!(find_enclosing_block(&lines, 0), Some((0, 5)));
+        assert_eq!(find_enclosing_block(&lines, 1), Some((0, 5)));
+        assert_eq!(find_enclosing_block(&lines, 3), Some((2, 4)));
+        assert_eq!(find_enclosing_block(&lines, 5), Some((0, 5)));
+        assert_eq!(find_enclosing_block(&lines, 6), None);
+
+        let lines = [
+            "fn f<'a>(x: &'a str) -> char {",
+            "    /* {",
+            "       } } */",
+            "    if x.is_empty() { '{' } else { '\\'' }",
+            "}",
+        ];
+        assert_eq!(find_enclosing_block(&lines, 1), Some((0, 4)));
+        assert_eq!(find_enclosing_block(&lines, 3), Some((0, 4)));
+    }
+
+    #[test]
+    fn test_hexdump() {
+        assert!(is_binary(b"\x7fELF\x02\x01\x01\x00"));
+        assert!(!is_binary("int main() {}\n".as_bytes()));
+        assert_eq!(
+            hexdump_lines(
+                b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00abc",
+                0
+            ),
+            vec![
+                "00000000  7f 45 4c 46 02 01 01 00  00 00 00 00 00 00 00 00  |.ELF............|",
+                "00000010  61 62 63                                          |abc|",
+            ]
+        );
+    }
+}
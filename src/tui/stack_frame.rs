@@ -0,0 +1,102 @@
+// The memory of the selected frame (from the stack pointer up to the frame address) as one word
+// per line, annotated with what is stored there: saved registers (e.g., the return address), the
+// stack canary and the arguments and locals of the frame.
+use gdb::Address;
+
+// Frames larger than this are cut off (e.g., if a function has a large array on the stack).
+pub const MAX_FRAME_BYTES: usize = 4096;
+
+/// Something that is stored in `[begin, end)`, e.g., a local variable.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub begin: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl Annotation {
+    pub fn new(begin: Address, size: usize, text: impl Into<String>) -> Self {
+        Annotation {
+            begin: begin.0,
+            end: begin.0 + size.max(1),
+            text: text.into(),
+        }
+    }
+}
+
+/// One line per (little endian) word of the memory starting at `begin` with its address, value
+/// and the annotations that overlap it. Words with the value of the canary are marked as well.
+pub fn frame_lines(
+    bytes: &[u8],
+    begin: Address,
+    word_size: usize,
+    annotations: &[Annotation],
+    canary: Option<usize>,
+    format_address: impl Fn(Address) -> String,
+) -> Vec<String> {
+    bytes
+        .chunks(word_size)
+        .enumerate()
+        .map(|(i, word)| {
+            let address = begin.0 + i * word_size;
+            let value = word
+                .iter()
+                .rev()
+                .fold(0usize, |value, &b| value << 8 | b as usize);
+            let mut notes = annotations
+                .iter()
+                .filter(|a| a.begin < address + word.len() && address < a.end)
+                .map(|a| a.text.as_str())
+                .collect::<Vec<_>>();
+            if canary == Some(value) {
+                notes.push("stack canary");
+            }
+            let line = format!(
+                "{}  0x{:0w$x}",
+                format_address(Address(address)),
+                value,
+                w = 2 * word.len()
+            );
+            if notes.is_empty() {
+                line
+            } else {
+                format!("{}  {}", line, notes.join(", "))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_frame_lines() {
+        let bytes = [
+            0x05, 0, 0, 0, 0x07, 0, 0, 0, // int x, int y
+            0xef, 0xbe, 0xad, 0xde, 0, 0, 0, 0, // canary
+            0x31, 0x11, 0, 0, 0, 0, 0, 0, // return address
+        ];
+        let annotations = vec![
+            Annotation::new(Address(0x1000), 4, "x"),
+            Annotation::new(Address(0x1004), 4, "y"),
+            Annotation::new(Address(0x1010), 8, "saved rip"),
+        ];
+        let lines = frame_lines(
+            &bytes,
+            Address(0x1000),
+            8,
+            &annotations,
+            Some(0xdeadbeef),
+            |a| a.to_string(),
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "0x1000  0x0000000700000005  x, y",
+                "0x1008  0x00000000deadbeef  stack canary",
+                "0x1010  0x0000000000001131  saved rip",
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use gdb::InferiorState;
+use gdbmi::output::Object;
+use gdbmi::types::Frame;
+use std::convert::TryFrom;
+use unsegen::base::{Cursor, StyleModifier, Window};
+use unsegen::widget::RenderingHints;
+
+/// What the status bar shows besides what it learns from the records of gdb.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlobalState {
+    pub running: bool,
+    /// State of the selected inferior (if gdb reported one).
+    pub inferior: Option<InferiorState>,
+    pub breakpoints: usize,
+    /// Whether commands were sent to gdb whose results did not arrive yet.
+    pub pending_commands: bool,
+}
+
+/// One line at the bottom of the screen with the global state of the debugger: the target, whether
+/// the program is running, the selected thread and frame, the pid, the number of breakpoints and
+/// why the program stopped the last time. Thread, frame and stop reason are taken from gdb's
+/// `*stopped` records and `=thread-selected` notifications.
+pub struct StatusBar {
+    target: Option<String>,
+    thread: Option<String>,
+    frame: Option<String>,
+    last_stop: Option<String>,
+}
+
+// E.g., "main at main.c:12" or "0x401000 in ??".
+fn describe_frame(frame: &Frame) -> String {
+    let func = frame.func.as_deref().unwrap_or("??");
+    match (&frame.file, frame.line, frame.addr) {
+        (Some(file), Some(line), _) => format!("{} at {}:{}", func, file, line),
+        (_, _, Some(addr)) => format!("0x{:x} in {}", addr, func),
+        _ => func.to_owned(),
+    }
+}
+
+// Why the program stopped according to the `*stopped` record.
+fn describe_stop(results: &Object) -> String {
+    let field = |key: &str| results[key].as_str().unwrap_or("?").to_owned();
+    match results["reason"].as_str() {
+        Some("breakpoint-hit") => format!("breakpoint {}", field("bkptno")),
+        Some("watchpoint-trigger")
+        | Some("read-watchpoint-trigger")
+        | Some("access-watchpoint-trigger") => {
+            format!(
+                "watchpoint {}",
+                results["wpt"]["number"].as_str().unwrap_or("?")
+            )
+        }
+        Some("end-stepping-range") => "step".to_owned(),
+        Some("function-finished") => "finish".to_owned(),
+        Some("location-reached") => "location reached".to_owned(),
+        Some("signal-received") => format!("signal {}", field("signal-name")),
+        Some("exited-normally") => "exited normally".to_owned(),
+        Some("exited") => format!("exited with code {}", field("exit-code")),
+        Some("exited-signalled") => format!("terminated by {}", field("signal-name")),
+        Some(other) => other.to_owned(),
+        // E.g., after attaching or `interrupt`.
+        None => "stopped".to_owned(),
+    }
+}
+
+impl StatusBar {
+    pub fn new() -> Self {
+        StatusBar {
+            target: None,
+            thread: None,
+            frame: None,
+            last_stop: None,
+        }
+    }
+
+    /// Set the name of the program (or of the remote target) that is debugged.
+    pub fn set_target(&mut self, target: impl Into<String>) {
+        self.target = Some(target.into());
+    }
+
+    pub fn on_stopped(&mut self, results: &Object) {
+        self.last_stop = Some(describe_stop(results));
+        if let Some(thread) = results["thread-id"].as_str() {
+            self.thread = Some(thread.to_owned());
+        }
+        self.frame = Frame::try_from(&results["frame"])
+            .ok()
+            .map(|f| describe_frame(&f));
+    }
+
+    pub fn on_thread_selected(&mut self, thread: &str, frame: Option<&Frame>) {
+        self.thread = Some(thread.to_owned());
+        self.frame = frame.map(describe_frame);
+    }
+
+    pub fn line(&self, state: &GlobalState) -> String {
+        let mut parts = vec![self
+            .target
+            .clone()
+            .unwrap_or_else(|| "no target".to_owned())];
+        parts.push(
+            match (&state.inferior, state.running) {
+                (_, true) => "running",
+                (Some(InferiorState::Exited { .. }), _) => "exited",
+                (Some(InferiorState::NotStarted), _) | (None, _) => "not started",
+                (Some(InferiorState::Running { .. }), false) => "stopped",
+            }
+            .to_owned(),
+        );
+        if let Some(InferiorState::Running { pid }) = &state.inferior {
+            parts.push(format!("pid {}", pid));
+            if let Some(thread) = &self.thread {
+                parts.push(format!("thread {}", thread));
+            }
+            if let (Some(frame), false) = (&self.frame, state.running) {
+                parts.push(frame.clone());
+            }
+        }
+        parts.push(format!("{} breakpoint(s)", state.breakpoints));
+        if let Some(last_stop) = &self.last_stop {
+            parts.push(format!("last stop: {}", last_stop));
+        }
+        if state.pending_commands {
+            parts.push("gdb busy".to_owned());
+        }
+        parts.join(" | ")
+    }
+
+    pub fn draw(&self, mut window: Window, state: &GlobalState, _hints: RenderingHints) {
+        window.modify_default_style(StyleModifier::new().invert(true));
+        window.clear();
+        Cursor::new(&mut window).write(&format!(" {}", self.line(state)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use gdbmi::output::JsonValue;
+
+    #[test]
+    fn test_line() {
+        let mut bar = StatusBar::new();
+        let mut state = GlobalState {
+            running: false,
+            inferior: None,
+            breakpoints: 2,
+            pending_commands: false,
+        };
+        assert_eq!(
+            bar.line(&state),
+            "no target | not started | 2 breakpoint(s)"
+        );
+
+        bar.set_target("prog");
+        let results = object! {
+            "reason" => "breakpoint-hit",
+            "bkptno" => "1",
+            "thread-id" => "1",
+            "frame" => object! { "func" => "main", "file" => "main.c", "line" => "12" },
+        };
+        if let JsonValue::Object(results) = results {
+            bar.on_stopped(&results);
+        }
+        state.inferior = Some(InferiorState::Running {
+            pid: "42".to_owned(),
+        });
+        assert_eq!(
+            bar.line(&state),
+            "prog | stopped | pid 42 | thread 1 | main at main.c:12 | 2 breakpoint(s) | last stop: breakpoint 1"
+        );
+
+        state.running = true;
+        state.pending_commands = true;
+        assert_eq!(
+            bar.line(&state),
+            "prog | running | pid 42 | thread 1 | 2 breakpoint(s) | last stop: breakpoint 1 | gdb busy"
+        );
+    }
+}
@@ -0,0 +1,524 @@
+use clipboard;
+use std::collections::VecDeque;
+use std::iter;
+use unsegen::base::{
+    BoolModifyMode, Cursor, GraphemeCluster, RowIndex, StyleModifier, Window, WrappingMode,
+};
+use unsegen::container::Container;
+use unsegen::input::{Event, Input, Key, OperationResult, ScrollBehavior, Scrollable};
+use unsegen::widget::{ColDemand, Demand2D, RenderingHints, RowDemand, VLayout, Widget, WidgetExt};
+use unsegen_terminal::Terminal;
+
+// Number of lines to move on PageUp/PageDown in scrollback mode.
+const PAGE_STEP: usize = 20;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum EscapeState {
+    None,
+    Escape,
+    Csi,
+    Osc,
+    OscEscape,
+}
+
+/// Plain text copy of the output of the inferior terminal, bounded to a maximum number of lines.
+///
+/// Escape sequences are stripped, i.e., cursor movement is not interpreted.
+pub struct Scrollback {
+    lines: VecDeque<String>,
+    current_line: Vec<u8>,
+    carriage_return: bool,
+    escape_state: EscapeState,
+    max_lines: usize,
+    num_dropped: usize,
+}
+
+impl Scrollback {
+    pub fn new(max_lines: usize) -> Self {
+        Scrollback {
+            lines: VecDeque::new(),
+            current_line: Vec::new(),
+            carriage_return: false,
+            escape_state: EscapeState::None,
+            max_lines,
+            num_dropped: 0,
+        }
+    }
+
+    pub fn add_bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.escape_state = match (self.escape_state, b) {
+                (EscapeState::None, 0x1b) => EscapeState::Escape,
+                (EscapeState::None, b'\n') => {
+                    self.finish_line();
+                    EscapeState::None
+                }
+                (EscapeState::None, b'\r') => {
+                    self.carriage_return = true;
+                    EscapeState::None
+                }
+                (EscapeState::None, b) => {
+                    // A carriage return without newline means that the line will be overwritten.
+                    if self.carriage_return {
+                        self.current_line.clear();
+                        self.carriage_return = false;
+                    }
+                    if b == b'\t' || b >= 0x20 {
+                        self.current_line.push(b);
+                    }
+                    EscapeState::None
+                }
+                (EscapeState::Escape, b'[') => EscapeState::Csi,
+                (EscapeState::Escape, b']') => EscapeState::Osc,
+                (EscapeState::Escape, _) => EscapeState::None,
+                (EscapeState::Csi, 0x40..=0x7e) => EscapeState::None,
+                (EscapeState::Csi, _) => EscapeState::Csi,
+                (EscapeState::Osc, 0x07) => EscapeState::None,
+                (EscapeState::Osc, 0x1b) => EscapeState::OscEscape,
+                (EscapeState::Osc, _) => EscapeState::Osc,
+                (EscapeState::OscEscape, _) => EscapeState::None,
+            };
+        }
+    }
+
+    fn finish_line(&mut self) {
+        let line = String::from_utf8_lossy(&self.current_line).into_owned();
+        self.current_line.clear();
+        self.carriage_return = false;
+        self.lines.push_back(line);
+        while self.lines.len() > self.max_lines {
+            self.lines.pop_front();
+            self.num_dropped += 1;
+        }
+    }
+
+    // Complete lines followed by the (possibly empty) line that is currently being written.
+    #[cfg(test)]
+    fn lines(&self) -> Vec<String> {
+        self.lines
+            .iter()
+            .cloned()
+            .chain(iter::once(self.current_line()))
+            .collect()
+    }
+
+    fn current_line(&self) -> String {
+        String::from_utf8_lossy(&self.current_line).into_owned()
+    }
+
+    /// Total number of lines that have been removed from the front of the buffer so far.
+    pub fn num_dropped(&self) -> usize {
+        self.num_dropped
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum SearchDirection {
+    Forwards,
+    Backwards,
+}
+
+// Copy of the lines of the scrollback that is shown in scrollback mode. Complete lines do not
+// change anymore, so only new ones are copied when the program produces more output.
+#[derive(Default)]
+struct ScrollbackLines {
+    complete: VecDeque<String>,
+    partial: String,    // The line that is currently being written
+    num_dropped: usize, // Value of Scrollback::num_dropped at the last update
+    current: usize,     // Index of the selected line
+}
+
+impl ScrollbackLines {
+    fn len(&self) -> usize {
+        self.complete.len() + 1
+    }
+
+    fn get(&self, i: usize) -> &str {
+        self.complete.get(i).unwrap_or(&self.partial)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &str> {
+        self.complete
+            .iter()
+            .map(String::as_str)
+            .chain(iter::once(self.partial.as_str()))
+    }
+
+    // Returns the number of lines that were dropped from the front since the last update.
+    fn update(&mut self, scrollback: &Scrollback) -> usize {
+        let newly_dropped = scrollback.num_dropped() - self.num_dropped;
+        let end = self.num_dropped + self.complete.len();
+        let num_remove = newly_dropped.min(self.complete.len());
+        self.complete.drain(..num_remove);
+        self.num_dropped = scrollback.num_dropped();
+        self.complete.extend(
+            scrollback
+                .lines
+                .iter()
+                .skip(end.saturating_sub(self.num_dropped))
+                .cloned(),
+        );
+        self.partial = scrollback.current_line();
+        self.current = self.current.saturating_sub(newly_dropped);
+        newly_dropped
+    }
+
+    fn go_to_line(&mut self, line: usize) {
+        self.current = line.min(self.len() - 1);
+    }
+}
+
+impl Scrollable for ScrollbackLines {
+    fn scroll_backwards(&mut self) -> OperationResult {
+        if self.current == 0 {
+            return Err(());
+        }
+        self.current -= 1;
+        Ok(())
+    }
+    fn scroll_forwards(&mut self) -> OperationResult {
+        if self.current + 1 >= self.len() {
+            return Err(());
+        }
+        self.current += 1;
+        Ok(())
+    }
+    fn scroll_to_beginning(&mut self) -> OperationResult {
+        if self.current == 0 {
+            return Err(());
+        }
+        self.current = 0;
+        Ok(())
+    }
+    fn scroll_to_end(&mut self) -> OperationResult {
+        if self.current + 1 == self.len() {
+            return Err(());
+        }
+        self.current = self.len() - 1;
+        Ok(())
+    }
+}
+
+// Lines are not wrapped. The selected line is centered unless the end of the output is visible.
+// The lines that are selected in copy mode are inverted.
+struct ScrollbackWidget<'a> {
+    lines: &'a ScrollbackLines,
+    copy_selection: Option<(usize, usize)>,
+}
+
+impl<'a> Widget for ScrollbackWidget<'a> {
+    fn space_demand(&self) -> Demand2D {
+        Demand2D {
+            width: ColDemand::at_least(1),
+            height: RowDemand::at_least(1),
+        }
+    }
+
+    fn draw(&self, mut window: Window, _: RenderingHints) {
+        let height = window.get_height().raw_value() as usize;
+        let first = self
+            .lines
+            .current
+            .saturating_sub(height / 2)
+            .min(self.lines.len().saturating_sub(height));
+        for (row, (i, line)) in self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(height)
+            .enumerate()
+        {
+            let row = RowIndex::new(row as i32);
+            let mut line_window = window.create_subwindow(.., row..row + 1);
+            let selected = match self.copy_selection {
+                Some((begin, end)) => begin <= i && i <= end,
+                None => false,
+            };
+            if selected || i == self.lines.current {
+                line_window.modify_default_style(
+                    StyleModifier::new()
+                        .invert(BoolModifyMode::Toggle)
+                        .bold(i == self.lines.current),
+                );
+                line_window.fill(GraphemeCluster::space());
+            }
+            Cursor::new(&mut line_window)
+                .wrapping_mode(WrappingMode::NoWrap)
+                .write(line);
+        }
+    }
+}
+
+struct ScrollbackView {
+    lines: ScrollbackLines,
+    search_input: Option<String>,
+    last_search: Option<String>,
+    message: Option<String>,
+    copy_anchor: Option<usize>, // Index of the line where the copy mode was entered
+}
+
+impl ScrollbackView {
+    fn new(scrollback: &Scrollback) -> Self {
+        let mut view = ScrollbackView {
+            lines: ScrollbackLines::default(),
+            search_input: None,
+            last_search: None,
+            message: None,
+            copy_anchor: None,
+        };
+        view.update(scrollback);
+        let _ = view.lines.scroll_to_end();
+        view
+    }
+
+    // Keep the current line in place while new output arrives (or old lines are dropped).
+    fn update(&mut self, scrollback: &Scrollback) {
+        let newly_dropped = self.lines.update(scrollback);
+        self.copy_anchor = self.copy_anchor.map(|a| a.saturating_sub(newly_dropped));
+    }
+
+    fn scroll(&mut self, forwards: bool, num_lines: usize) {
+        for _ in 0..num_lines {
+            let res = if forwards {
+                self.lines.scroll_forwards()
+            } else {
+                self.lines.scroll_backwards()
+            };
+            if res.is_err() {
+                break;
+            }
+        }
+    }
+
+    fn copy_selection(&self) -> Option<(usize, usize)> {
+        let current = self.lines.current;
+        self.copy_anchor
+            .map(|anchor| (anchor.min(current), anchor.max(current)))
+    }
+
+    fn toggle_copy_mode(&mut self) {
+        self.copy_anchor = if self.copy_anchor.is_some() {
+            None
+        } else {
+            Some(self.lines.current)
+        };
+        self.message = None;
+    }
+
+    fn yank(&mut self) {
+        let current = self.lines.current;
+        let (begin, end) = self.copy_selection().unwrap_or((current, current));
+        let text = self
+            .lines
+            .iter()
+            .skip(begin)
+            .take(end - begin + 1)
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.message = Some(match clipboard::copy(&text) {
+            Ok(()) => format!("Copied {} lines.", end - begin + 1),
+            Err(e) => format!("Failed to copy to clipboard: {}", e),
+        });
+        self.copy_anchor = None;
+    }
+
+    fn search(&mut self, direction: SearchDirection) {
+        let pattern = if let Some(p) = self.last_search.as_ref() {
+            p
+        } else {
+            return;
+        };
+        let current = self.lines.current;
+        let lines = &self.lines;
+        let matches = |&i: &usize| lines.get(i).contains(pattern.as_str());
+        let found = match direction {
+            SearchDirection::Forwards => (current + 1..lines.len()).find(matches),
+            SearchDirection::Backwards => (0..current).rev().find(matches),
+        };
+        match found {
+            Some(i) => {
+                self.lines.go_to_line(i);
+                self.message = None;
+            }
+            None => {
+                self.message = Some(format!("Pattern not found: {}", pattern));
+            }
+        }
+    }
+
+    // Returns false if the scrollback mode should be left.
+    fn input(&mut self, input: Input) -> (Option<Input>, bool) {
+        if let Some(query) = &mut self.search_input {
+            match input.event {
+                Event::Key(Key::Char('\n')) => {
+                    if !query.is_empty() {
+                        self.last_search = Some(query.clone());
+                        self.search(SearchDirection::Forwards);
+                    }
+                    self.search_input = None;
+                }
+                Event::Key(Key::Esc) => self.search_input = None,
+                Event::Key(Key::Backspace) => {
+                    query.pop();
+                }
+                Event::Key(Key::Char(c)) => query.push(c),
+                _ => return (Some(input), true),
+            }
+            return (None, true);
+        }
+        let mut leave = false;
+        let rest = input
+            .chain((&[Key::Esc, Key::Char('q')][..], || leave = true))
+            .chain((Key::Char('/'), || {
+                self.search_input = Some(String::new());
+                self.message = None;
+            }))
+            .chain((Key::Char('v'), || self.toggle_copy_mode()))
+            .chain((Key::Char('y'), || self.yank()))
+            .chain((Key::Char('n'), || self.search(SearchDirection::Forwards)))
+            .chain((Key::Char('N'), || self.search(SearchDirection::Backwards)))
+            .chain((Key::PageUp, || self.scroll(false, PAGE_STEP)))
+            .chain((Key::PageDown, || self.scroll(true, PAGE_STEP)))
+            .chain(
+                ScrollBehavior::new(&mut self.lines)
+                    .forwards_on(Key::Down)
+                    .forwards_on(Key::Char('j'))
+                    .backwards_on(Key::Up)
+                    .backwards_on(Key::Char('k'))
+                    .to_beginning_on(Key::Home)
+                    .to_end_on(Key::End),
+            )
+            .finish();
+        (rest, !leave)
+    }
+
+    fn status_line(&self) -> String {
+        if let Some(query) = &self.search_input {
+            format!("/{}", query)
+        } else if let Some(message) = &self.message {
+            message.clone()
+        } else if let Some((begin, end)) = self.copy_selection() {
+            format!(
+                "-- copy -- {} lines selected (y: copy, v: cancel)",
+                end - begin + 1
+            )
+        } else {
+            "-- scrollback -- (/: search, n/N: next/previous match, v: copy mode, q: leave)"
+                .to_owned()
+        }
+    }
+}
+
+/// The pseudo terminal of the debugged program.
+///
+/// In addition to the live terminal there is a scrollback mode (entered using PageUp) which shows
+/// the plain text output of the program and can be searched. It stays at the current position
+/// while the program produces new output.
+pub struct TerminalPane {
+    terminal: Terminal,
+    scrollback: Scrollback,
+    scrollback_view: Option<ScrollbackView>,
+}
+
+impl TerminalPane {
+    pub fn new(terminal: Terminal, scrollback_lines: usize) -> Self {
+        TerminalPane {
+            terminal,
+            scrollback: Scrollback::new(scrollback_lines),
+            scrollback_view: None,
+        }
+    }
+
+    pub fn add_byte_input(&mut self, bytes: &[u8]) {
+        self.terminal.add_byte_input(bytes);
+        self.scrollback.add_bytes(bytes);
+        if let Some(view) = &mut self.scrollback_view {
+            view.update(&self.scrollback);
+        }
+    }
+}
+
+impl Container<::Context> for TerminalPane {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        if let Some(view) = &mut self.scrollback_view {
+            let (rest, stay) = view.input(input);
+            if !stay {
+                self.scrollback_view = None;
+            }
+            return rest;
+        }
+        let scrollback = &self.scrollback;
+        let scrollback_view = &mut self.scrollback_view;
+        let rest = input
+            .chain((Key::PageUp, || {
+                let mut view = ScrollbackView::new(scrollback);
+                view.scroll(false, PAGE_STEP);
+                *scrollback_view = Some(view);
+            }))
+            .finish();
+        if let Some(rest) = rest {
+            self.terminal.input(rest, p)
+        } else {
+            None
+        }
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        if let Some(view) = &self.scrollback_view {
+            let lines = ScrollbackWidget {
+                lines: &view.lines,
+                copy_selection: view.copy_selection(),
+            };
+            Box::new(
+                VLayout::new()
+                    .widget(lines)
+                    .widget(view.status_line().with_window(|w, _| {
+                        let mut w = w;
+                        w.modify_default_style(StyleModifier::new().invert(BoolModifyMode::Toggle));
+                        w
+                    })),
+            )
+        } else {
+            Container::<::Context>::as_widget(&self.terminal)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scrollback_strips_escape_sequences() {
+        let mut s = Scrollback::new(100);
+        s.add_bytes(
+            b"\x1b[1;31mred\x1b[0m text\r\n\x1b]0;title\x07progress 10%\rprogress 20%\npart",
+        );
+        assert_eq!(s.lines(), vec!["red text", "progress 20%", "part"]);
+    }
+
+    #[test]
+    fn scrollback_is_bounded() {
+        let mut s = Scrollback::new(2);
+        s.add_bytes(b"1\n2\n3\n4\n");
+        assert_eq!(s.lines(), vec!["3", "4", ""]);
+        assert_eq!(s.num_dropped(), 2);
+    }
+
+    #[test]
+    fn scrollback_lines_are_appended() {
+        let mut s = Scrollback::new(3);
+        let mut lines = ScrollbackLines::default();
+        s.add_bytes(b"1\n2\npart");
+        lines.update(&s);
+        lines.go_to_line(1);
+        s.add_bytes(b"ial\n4\n5\n");
+        assert_eq!(lines.update(&s), 2);
+        assert_eq!(lines.iter().collect::<Vec<_>>(), s.lines());
+        assert_eq!(lines.current, 0);
+        s.add_bytes(b"6\n7\n8\n9\n");
+        assert_eq!(lines.update(&s), 4);
+        assert_eq!(lines.iter().collect::<Vec<_>>(), s.lines());
+    }
+}
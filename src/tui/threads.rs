@@ -0,0 +1,156 @@
+use super::backtrace::frame_location;
+use super::event_bus::{DebugEvent, Subscriber};
+use super::list_filter::FilterableRow;
+use super::list_view::ListView;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::{Column, TableRow};
+use unsegen::widget::Widget;
+
+#[derive(Clone)]
+pub struct ThreadRow {
+    selected: &'static str,
+    id: String,
+    name: String,
+    state: String,
+    function: String,
+    location: String,
+}
+
+impl TableRow for ThreadRow {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<ThreadRow>] = &[
+        Column {
+            access: |r| Box::new(r.selected),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.id.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.name.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.state.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.function.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.location.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+    ];
+}
+
+impl FilterableRow for ThreadRow {
+    fn cell_text(&self, column: usize) -> &str {
+        match column {
+            0 => self.selected,
+            1 => &self.id,
+            2 => &self.name,
+            3 => &self.state,
+            4 => &self.function,
+            _ => &self.location,
+        }
+    }
+}
+
+/// Threads of all inferiors. The current thread is marked with `*`. The list is updated whenever
+/// the program stops, another thread is selected or threads are created or exit while the program
+/// is stopped (e.g., in non-stop mode).
+pub struct ThreadsView {
+    list: ListView<ThreadRow>,
+}
+
+impl ThreadsView {
+    pub fn new() -> Self {
+        ThreadsView {
+            list: ListView::new("No threads."),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.list.lines()
+    }
+
+    pub fn refresh(&mut self, p: &::Context) {
+        match p.gdb.snapshot.threads {
+            Ok((ref threads, ref current)) => {
+                self.list.set_header(format!("{} thread(s)", threads.len()));
+                let rows = threads
+                    .iter()
+                    .map(|t| ThreadRow {
+                        selected: if current.as_ref() == Some(&t.id) {
+                            "*"
+                        } else {
+                            " "
+                        },
+                        name: t
+                            .name
+                            .clone()
+                            .or_else(|| t.target_id.clone())
+                            .unwrap_or_default(),
+                        state: t.state.clone().unwrap_or_default(),
+                        function: t
+                            .frame
+                            .as_ref()
+                            .and_then(|f| f.func.clone())
+                            .unwrap_or_default(),
+                        location: t.frame.as_ref().map(frame_location).unwrap_or_default(),
+                        id: t.id.clone(),
+                    })
+                    .collect();
+                self.list.set_rows(rows);
+            }
+            Err(ref e) => {
+                self.list.set_header(format!("No threads: {:?}", e));
+                self.list.set_rows(Vec::new());
+            }
+        }
+    }
+
+    fn select_thread(row: Option<&ThreadRow>, p: &mut ::Context) {
+        if let Some(row) = row {
+            match p.gdb.select_thread(&row.id) {
+                Ok(record) => p.report_selection(record),
+                Err(e) => p.log(format!("Failed to select thread {}: {:?}", row.id, e)),
+            }
+        }
+    }
+}
+
+impl Subscriber for ThreadsView {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        match event {
+            DebugEvent::Stopped(_) | DebugEvent::ThreadListChanged => self.refresh(p),
+            _ => {}
+        }
+    }
+}
+
+impl Container<::Context> for ThreadsView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        self.list.input(input, |row, input| {
+            input
+                .chain((Key::Char('\n'), || Self::select_thread(row, p)))
+                .finish()
+        })
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        self.list.as_widget()
+    }
+}
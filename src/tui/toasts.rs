@@ -0,0 +1,169 @@
+use super::render_profile::RenderProfile;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use unsegen::base::{ColIndex, Color, Cursor, RowIndex, StyleModifier, Window};
+use unsegen::widget::{count_grapheme_clusters, RenderingHints};
+
+// Older toasts are dropped if there are more than this.
+const MAX_TOASTS: usize = 5;
+// Toasts are not wider than this fraction of the window.
+const MAX_WIDTH_FRACTION: i32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    fn color(self) -> Color {
+        match self {
+            ToastLevel::Info => Color::Blue,
+            ToastLevel::Success => Color::Green,
+            ToastLevel::Warning => Color::Yellow,
+            ToastLevel::Error => Color::Red,
+        }
+    }
+
+    pub fn style(self) -> StyleModifier {
+        StyleModifier::new()
+            .bg_color(self.color())
+            .fg_color(Color::Black)
+    }
+
+    // Problems are shown a little longer.
+    fn duration(self) -> Duration {
+        match self {
+            ToastLevel::Info | ToastLevel::Success => Duration::from_secs(4),
+            ToastLevel::Warning | ToastLevel::Error => Duration::from_secs(8),
+        }
+    }
+
+    // Prefix and style of a toast. Without colors, the level is spelled out.
+    fn label_and_style(self, profile: RenderProfile) -> (&'static str, StyleModifier) {
+        let label = match self {
+            ToastLevel::Info => "",
+            ToastLevel::Success => "OK: ",
+            ToastLevel::Warning => "Warning: ",
+            ToastLevel::Error => "Error: ",
+        };
+        match profile {
+            RenderProfile::Color => ("", self.style()),
+            RenderProfile::HighContrast => (label, self.style().bold(true)),
+            RenderProfile::Monochrome => (label, StyleModifier::new().invert(true)),
+        }
+    }
+}
+
+struct Toast {
+    level: ToastLevel,
+    text: String,
+    expires: Instant,
+}
+
+/// Short messages that are shown in the bottom right corner on top of the containers and
+/// disappear on their own after a few seconds. They are meant for things that are good to know,
+/// but do not require any reaction (unlike popups).
+pub struct Toasts {
+    toasts: VecDeque<Toast>,
+    profile: RenderProfile,
+}
+
+impl Toasts {
+    pub fn new(profile: RenderProfile) -> Self {
+        Toasts {
+            toasts: VecDeque::new(),
+            profile,
+        }
+    }
+
+    pub fn push(&mut self, level: ToastLevel, text: impl Into<String>, now: Instant) {
+        let text = text.into();
+        // Multiple lines would not fit into a single row.
+        let text = text.lines().next().unwrap_or("").to_owned();
+        self.toasts.push_back(Toast {
+            level,
+            text,
+            expires: now + level.duration(),
+        });
+        while self.toasts.len() > MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+    }
+
+    /// Remove all toasts that have expired. Returns whether any were removed.
+    pub fn remove_expired(&mut self, now: Instant) -> bool {
+        let before = self.toasts.len();
+        self.toasts.retain(|t| t.expires > now);
+        self.toasts.len() != before
+    }
+
+    /// When the next toast expires (i.e., when the screen has to be drawn again).
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.toasts.iter().map(|t| t.expires).min()
+    }
+
+    pub fn draw(&self, mut window: Window, _hints: RenderingHints) {
+        let window_width = window.get_width().raw_value();
+        let window_height = window.get_height().raw_value();
+        let max_width = window_width / MAX_WIDTH_FRACTION;
+        if max_width < 4 {
+            return;
+        }
+        let num_rows = (self.toasts.len() as i32).min(window_height);
+        for (row, toast) in self
+            .toasts
+            .iter()
+            .skip(self.toasts.len() - num_rows as usize)
+            .enumerate()
+        {
+            let (label, style) = toast.level.label_and_style(self.profile);
+            let text = format!(" {}{} ", label, toast.text);
+            let width = (count_grapheme_clusters(&text) as i32).min(max_width);
+            let y = window_height - num_rows + row as i32;
+            let mut toast_window = window.create_subwindow(
+                ColIndex::new(window_width - width)..ColIndex::new(window_width),
+                RowIndex::new(y)..RowIndex::new(y + 1),
+            );
+            toast_window.modify_default_style(style);
+            toast_window.clear();
+            Cursor::new(&mut toast_window).write(&text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_toasts_expire() {
+        let now = Instant::now();
+        let mut toasts = Toasts::new(RenderProfile::default());
+        assert_eq!(toasts.next_expiry(), None);
+
+        toasts.push(ToastLevel::Error, "failed\nwith details", now);
+        toasts.push(ToastLevel::Info, "done", now);
+        assert_eq!(toasts.toasts[0].text, "failed");
+        assert_eq!(toasts.next_expiry(), Some(now + Duration::from_secs(4)));
+
+        assert!(!toasts.remove_expired(now + Duration::from_secs(1)));
+        assert!(toasts.remove_expired(now + Duration::from_secs(5)));
+        assert_eq!(toasts.toasts.len(), 1);
+        assert!(toasts.remove_expired(now + Duration::from_secs(9)));
+        assert_eq!(toasts.next_expiry(), None);
+    }
+
+    #[test]
+    fn test_toasts_are_limited() {
+        let now = Instant::now();
+        let mut toasts = Toasts::new(RenderProfile::default());
+        for i in 0..MAX_TOASTS + 2 {
+            toasts.push(ToastLevel::Info, i.to_string(), now);
+        }
+        assert_eq!(toasts.toasts.len(), MAX_TOASTS);
+        assert_eq!(toasts.toasts[0].text, "2");
+    }
+}
@@ -0,0 +1,136 @@
+use super::event_bus::{DebugEvent, Subscriber};
+use super::list_filter::FilterableRow;
+use super::list_view::ListView;
+use super::toasts::ToastLevel;
+use function_trace::TraceEntry;
+use unsegen::container::Container;
+use unsegen::input::{Input, Key};
+use unsegen::widget::builtin::{Column, TableRow};
+use unsegen::widget::Widget;
+
+#[derive(Clone)]
+pub struct TraceRow {
+    thread: String,
+    call: String,
+    return_value: String,
+    function: String,
+}
+
+impl TableRow for TraceRow {
+    type BehaviorContext = ::Context;
+    const COLUMNS: &'static [Column<TraceRow>] = &[
+        Column {
+            access: |r| Box::new(r.thread.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.call.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+        Column {
+            access: |r| Box::new(r.return_value.as_str()),
+            behavior: |_, input, _| Some(input),
+        },
+    ];
+}
+
+impl FilterableRow for TraceRow {
+    fn cell_text(&self, column: usize) -> &str {
+        match column {
+            0 => &self.thread,
+            1 => &self.call,
+            _ => &self.return_value,
+        }
+    }
+}
+
+/// The calls of the traced functions (`!trace`) with their arguments (and return values, see
+/// `!trace-returns`), indented by how deeply they are nested in other traced calls.
+pub struct TraceView {
+    list: ListView<TraceRow>,
+}
+
+impl TraceView {
+    pub fn new() -> Self {
+        TraceView {
+            list: ListView::new(Self::header(0)),
+        }
+    }
+
+    fn header(len: usize) -> String {
+        format!(
+            "{} call(s) (c: clear, d: stop tracing the function, r: toggle tracing returns)",
+            len
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.list.lines()
+    }
+
+    fn set_entries<'a>(&mut self, entries: impl Iterator<Item = &'a TraceEntry>) {
+        let rows = entries
+            .map(|e| TraceRow {
+                thread: e.thread.clone(),
+                call: format!("{}{}", "  ".repeat(e.depth), e.call()),
+                return_value: e
+                    .return_value
+                    .as_ref()
+                    .map(|v| format!("= {}", v))
+                    .unwrap_or_default(),
+                function: e.function.clone(),
+            })
+            .collect::<Vec<_>>();
+        self.list.set_header(Self::header(rows.len()));
+        self.list.set_rows(rows);
+    }
+
+    fn clear(p: &mut ::Context) {
+        p.function_trace.clear();
+        p.publish(DebugEvent::TraceChanged);
+    }
+
+    fn untrace(row: Option<&TraceRow>, p: &mut ::Context) {
+        if let Some(row) = row {
+            match p.function_trace.untrace(&mut p.gdb, &row.function) {
+                Ok(()) => p.toast(
+                    ToastLevel::Info,
+                    format!("Stopped tracing {}.", row.function),
+                ),
+                Err(e) => p.log(format!("Could not stop tracing {}: {:?}", row.function, e)),
+            }
+        }
+    }
+}
+
+impl Subscriber for TraceView {
+    fn on_event(&mut self, event: &DebugEvent, p: &mut ::Context) {
+        if let DebugEvent::TraceChanged = event {
+            self.set_entries(p.function_trace.entries());
+        }
+    }
+}
+
+impl Container<::Context> for TraceView {
+    fn input(&mut self, input: Input, p: &mut ::Context) -> Option<Input> {
+        self.list.input(input, |row, input| {
+            input
+                .chain((Key::Char('c'), || Self::clear(p)))
+                .chain((Key::Char('d'), || Self::untrace(row, p)))
+                .chain((Key::Char('r'), || p.toggle_trace_returns()))
+                .finish()
+        })
+    }
+
+    fn as_widget<'a>(&'a self) -> Box<dyn Widget + 'a> {
+        self.list.as_widget()
+    }
+}
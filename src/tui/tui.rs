@@ -1,19 +1,65 @@
 use unsegen_pager::Theme;
 
-use gdbmi::output::{AsyncClass, AsyncKind, JsonValue, Object, OutOfBandRecord, ThreadEvent};
+use config::Config;
+use function_trace;
 
+use gdb::Address;
+use gdbmi::commands::BreakPointNumber;
+use gdbmi::output::{AsyncClass, AsyncKind, JsonValue, Object, OutOfBandRecord};
+use gdbmi::types::{self, Frame, Notification};
+use std::convert::TryFrom;
+use std::mem::discriminant;
+use watch_triggers;
+
+use super::backtrace::BacktraceView;
+use super::breakpoints::BreakpointsView;
 use super::console::Console;
+use super::error_boundary::ErrorBoundary;
+use super::event_bus::{ChangeDetector, DebugEvent, Subscriber};
 use super::expression_table::ExpressionTable;
+use super::inferiors::InferiorsView;
+use super::libraries::LibrariesView;
+use super::line_table::LineTableView;
+use super::locals::LocalsView;
+use super::memory::MemoryView;
+use super::memory_map::MemoryMapView;
+use super::overlay::Overlays;
+use super::progress::ProgressTasks;
+use super::search_results::SearchResults;
+use super::skips::SkipsView;
 use super::srcview::CodeWindow;
-use log::{debug, info};
+use super::status_bar::StatusBar;
+use super::terminal::TerminalPane;
+use super::threads::ThreadsView;
+use super::toasts::{ToastLevel, Toasts};
+use super::trace::TraceView;
+use log::{debug, info, warn};
 use unsegen::container::{Container, ContainerProvider};
 use unsegen_terminal::Terminal;
 
 pub struct Tui<'a> {
-    pub console: Console,
-    pub expression_table: ExpressionTable,
-    process_pty: Terminal,
-    pub src_view: CodeWindow<'a>,
+    pub console: ErrorBoundary<Console>,
+    pub expression_table: ErrorBoundary<ExpressionTable>,
+    process_pty: ErrorBoundary<TerminalPane>,
+    pub src_view: ErrorBoundary<CodeWindow<'a>>,
+    pub search_results: ErrorBoundary<SearchResults>,
+    pub line_table: ErrorBoundary<LineTableView>,
+    pub inferiors: ErrorBoundary<InferiorsView>,
+    pub memory: ErrorBoundary<MemoryView>,
+    pub memory_map: ErrorBoundary<MemoryMapView>,
+    pub breakpoints: ErrorBoundary<BreakpointsView>,
+    pub threads: ErrorBoundary<ThreadsView>,
+    pub backtrace: ErrorBoundary<BacktraceView>,
+    pub locals: ErrorBoundary<LocalsView>,
+    pub skips: ErrorBoundary<SkipsView>,
+    pub libraries: ErrorBoundary<LibrariesView>,
+    pub trace: ErrorBoundary<TraceView>,
+    pub overlays: Overlays,
+    pub toasts: Toasts,
+    pub progress: ProgressTasks,
+    pub status_bar: StatusBar,
+    change_detector: ChangeDetector,
+    frozen: Option<Vec<DebugEvent>>, // Events that are withheld while updates are paused
 }
 
 const WELCOME_MSG: &str = concat!(
@@ -30,13 +76,173 @@ revision         "#,
     env!("REVISION")
 );
 
+// Where a breakpoint that was pending (i.e., without any location) before the modification now
+// is, if it is not pending anymore.
+fn resolved_location(bkpt: &types::Breakpoint, p: &mut ::Context) -> Option<String> {
+    let was_pending = bkpt
+        .number
+        .parse::<BreakPointNumber>()
+        .ok()
+        .and_then(|n| p.gdb.breakpoints.get(&n))
+        .map(|bp| bp.address.is_none() && bp.src_pos.is_none())
+        .unwrap_or(false);
+    if !was_pending {
+        return None;
+    }
+    let location = bkpt.locations.first().unwrap_or(bkpt);
+    match (
+        &location.fullname,
+        location.line,
+        &location.func,
+        location.addr,
+    ) {
+        (Some(file), Some(line), _, _) => Some(format!("{}:{}", file.display(), line)),
+        (_, _, Some(func), _) => Some(func.clone()),
+        (_, _, _, Some(addr)) => Some(p.address_formatter()(Address(addr))),
+        _ => None,
+    }
+}
+
 impl<'a> Tui<'a> {
-    pub fn new(terminal: Terminal, highlighting_theme: &'a Theme) -> Self {
+    pub fn new(terminal: Terminal, highlighting_theme: &'a Theme, config: &Config) -> Self {
+        let value_truncation = config.value_truncation;
         Tui {
-            console: Console::new(),
-            expression_table: ExpressionTable::new(),
-            process_pty: terminal,
-            src_view: CodeWindow::new(highlighting_theme, WELCOME_MSG),
+            console: ErrorBoundary::new(TuiContainerType::Console.name(), Console::new()),
+            expression_table: ErrorBoundary::new(
+                TuiContainerType::ExpressionTable.name(),
+                ExpressionTable::new(value_truncation, config.out_of_scope, config.number_format),
+            ),
+            process_pty: ErrorBoundary::new(
+                TuiContainerType::Terminal.name(),
+                TerminalPane::new(terminal, config.terminal_scrollback),
+            ),
+            src_view: ErrorBoundary::new(
+                TuiContainerType::SrcView.name(),
+                CodeWindow::new(
+                    highlighting_theme,
+                    WELCOME_MSG,
+                    config.source_encoding,
+                    config.render_profile,
+                ),
+            ),
+            search_results: ErrorBoundary::new(
+                TuiContainerType::SearchResults.name(),
+                SearchResults::new(),
+            ),
+            line_table: ErrorBoundary::new(
+                TuiContainerType::LineTable.name(),
+                LineTableView::new(),
+            ),
+            inferiors: ErrorBoundary::new(TuiContainerType::Inferiors.name(), InferiorsView::new()),
+            memory: ErrorBoundary::new(TuiContainerType::Memory.name(), MemoryView::new()),
+            memory_map: ErrorBoundary::new(
+                TuiContainerType::MemoryMap.name(),
+                MemoryMapView::new(),
+            ),
+            breakpoints: ErrorBoundary::new(
+                TuiContainerType::Breakpoints.name(),
+                BreakpointsView::new(),
+            ),
+            threads: ErrorBoundary::new(TuiContainerType::Threads.name(), ThreadsView::new()),
+            backtrace: ErrorBoundary::new(TuiContainerType::Backtrace.name(), BacktraceView::new()),
+            locals: ErrorBoundary::new(
+                TuiContainerType::Locals.name(),
+                LocalsView::new(value_truncation, config.number_format),
+            ),
+            skips: ErrorBoundary::new(TuiContainerType::Skips.name(), SkipsView::new()),
+            libraries: ErrorBoundary::new(TuiContainerType::Libraries.name(), LibrariesView::new()),
+            trace: ErrorBoundary::new(TuiContainerType::Trace.name(), TraceView::new()),
+            overlays: Overlays::new(),
+            toasts: Toasts::new(config.render_profile),
+            progress: ProgressTasks::new(),
+            status_bar: StatusBar::new(),
+            change_detector: ChangeDetector::new(),
+            frozen: None,
+        }
+    }
+
+    // Containers that are interested in changes of the state of gdb. A new container only has to
+    // implement `Subscriber` and be added here.
+    fn subscribers(&mut self) -> [&mut dyn Subscriber; 12] {
+        [
+            &mut self.src_view,
+            &mut self.expression_table,
+            &mut self.memory,
+            &mut self.memory_map,
+            &mut self.threads,
+            &mut self.backtrace,
+            &mut self.locals,
+            &mut self.breakpoints,
+            &mut self.inferiors,
+            &mut self.skips,
+            &mut self.libraries,
+            &mut self.trace,
+        ]
+    }
+
+    pub fn publish(&mut self, event: DebugEvent, p: &mut ::Context) {
+        if let Some(ref mut withheld) = self.frozen {
+            // Only the latest event of each kind matters once updates are resumed.
+            withheld.retain(|e| discriminant(e) != discriminant(&event));
+            withheld.push(event);
+            return;
+        }
+        debug!("publish {:?}", event);
+        for subscriber in self.subscribers().iter_mut() {
+            subscriber.on_event(&event, p);
+        }
+    }
+
+    fn handle_notification(&mut self, notification: Notification, p: &mut ::Context) {
+        p.gdb.inferiors.handle_notification(&notification);
+        p.gdb.handle_library_notification(&notification);
+        match notification {
+            Notification::ThreadSelected { id, frame } => {
+                self.status_bar.on_thread_selected(&id, frame.as_ref());
+                self.publish(DebugEvent::Stopped(frame), p);
+            }
+            Notification::BreakpointCreated(bkpts) => {
+                p.gdb.update_breakpoints(&bkpts);
+            }
+            Notification::BreakpointModified(bkpts) => {
+                for bkpt in &bkpts {
+                    if let Some(location) = resolved_location(bkpt, p) {
+                        p.toast(
+                            ToastLevel::Success,
+                            format!("Breakpoint {} resolved at {}", bkpt.number, location),
+                        );
+                    }
+                }
+                p.gdb.update_breakpoints(&bkpts);
+            }
+            Notification::BreakpointDeleted { id } => {
+                p.gdb.delete_breakpoint(&id);
+            }
+            // E.g., `set var x = 1` in the console
+            Notification::MemoryChanged { .. } => {
+                self.publish(DebugEvent::MemoryWritten, p);
+            }
+            // The print settings may have been changed in the console.
+            Notification::CmdParamChanged { ref param, .. } if param.starts_with("print") => {
+                p.gdb.forget_print_settings();
+                self.publish(DebugEvent::PrintSettingsChanged, p);
+            }
+            Notification::CmdParamChanged { ref param, .. } if param == "disassembly-flavor" => {
+                self.publish(DebugEvent::DisassemblyFlavorChanged, p);
+            }
+            Notification::ThreadCreated { .. } | Notification::ThreadExited { .. } => {
+                self.publish(DebugEvent::ThreadListChanged, p);
+            }
+            Notification::ThreadGroupAdded { .. }
+            | Notification::ThreadGroupRemoved { .. }
+            | Notification::ThreadGroupStarted { .. }
+            | Notification::ThreadGroupExited { .. } => {}
+            Notification::LibraryLoaded(_) | Notification::LibraryUnloaded(_) => {
+                self.publish(DebugEvent::LibrariesChanged, p);
+            }
+            other => {
+                info!("unhandled notification: {:?}", other);
+            }
         }
     }
 
@@ -48,21 +254,67 @@ impl<'a> Tui<'a> {
         p: &mut ::Context,
     ) {
         match (kind, class) {
-            (AsyncKind::Exec, AsyncClass::Stopped)
-            | (AsyncKind::Notify, AsyncClass::Thread(ThreadEvent::Selected)) => {
+            (AsyncKind::Exec, AsyncClass::Stopped) => {
                 debug!("stopped: {}", JsonValue::Object(results.clone()).pretty(2));
-                if let JsonValue::Object(ref frame) = results["frame"] {
-                    self.src_view.show_frame(frame, p);
+                if function_trace::handle_stop(results, p) {
+                    return;
+                }
+                self.status_bar.on_stopped(results);
+                p.session_stats.count_stop();
+                p.ipc_subscribers
+                    .notify("stopped", JsonValue::Object(results.clone()));
+                match results["reason"].as_str() {
+                    Some("exited-normally") => {
+                        p.toast(ToastLevel::Info, "Program exited normally.")
+                    }
+                    Some("exited") => p.toast(
+                        ToastLevel::Warning,
+                        format!("Program exited with code {}.", results["exit-code"]),
+                    ),
+                    Some("exited-signalled") => p.toast(
+                        ToastLevel::Error,
+                        format!("Program terminated by {}.", results["signal-name"]),
+                    ),
+                    _ => {}
+                }
+                if !p.gdb.stop_concerns_selected_inferior(results) {
+                    // Do not switch away from the frame of the selected inferior (non-stop mode).
+                    p.log(format!(
+                        "Thread {} of another inferior stopped ({}).",
+                        results["thread-id"],
+                        results["reason"].as_str().unwrap_or("unknown reason")
+                    ));
+                    return;
+                }
+                if watch_triggers::handle_stop(results, p) {
+                    return;
                 }
-                self.expression_table.update_results(p);
+                let frame = if results.get("frame").is_some() {
+                    match Frame::try_from(&results["frame"]) {
+                        Ok(frame) => Some(frame),
+                        Err(e) => {
+                            warn!("Invalid frame in stop record: {}", e);
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+                self.publish(DebugEvent::Stopped(frame), p);
             }
-            (AsyncKind::Notify, AsyncClass::BreakPoint(event)) => {
+            (AsyncKind::Status, AsyncClass::Other(ref class)) if class == "download" => {
+                p.report_download(results);
+            }
+            (AsyncKind::Notify, class) => {
                 debug!(
-                    "bkpoint {:?}: {}",
-                    event,
+                    "notification {:?}: {}",
+                    class,
                     JsonValue::Object(results.clone()).pretty(2)
                 );
-                p.gdb.handle_breakpoint_event(event, &results);
+                match Notification::parse(&class, results) {
+                    Ok(notification) => self.handle_notification(notification, p),
+                    Err(e) => warn!("Malformed {:?} notification: {}", class, e),
+                }
             }
             (kind, class) => {
                 info!(
@@ -96,8 +348,140 @@ impl<'a> Tui<'a> {
     }
 
     pub fn update_after_event(&mut self, p: &mut ::Context) {
-        self.src_view.update_after_event(p);
         self.console.update_after_event(p);
+        let changes = self.change_detector.changes(p);
+        for event in changes {
+            self.publish(event, p);
+        }
+    }
+
+    /// Pause (or resume) updates of the containers, e.g., to read a value without it being replaced
+    /// when the program stops the next time. gdb keeps running and the snapshot of the program
+    /// state is still taken at every stop, only the notifications of the containers are withheld.
+    /// Once updates are resumed, the latest notification of each kind is delivered, so that the
+    /// containers catch up with the snapshot of the latest stop.
+    pub fn toggle_freeze(&mut self, p: &mut ::Context) {
+        match self.frozen.take() {
+            Some(withheld) => {
+                p.toast(ToastLevel::Info, "Resumed updates.");
+                for event in withheld {
+                    self.publish(event, p);
+                }
+            }
+            None => {
+                self.frozen = Some(Vec::new());
+                p.toast(ToastLevel::Info, "Paused updates (resume using !freeze).");
+            }
+        }
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    /// Show the parts of the snapshot that were queried after the stop (see
+    /// `gdb::GDB::complete_snapshot`), unless updates are paused.
+    pub fn refresh_snapshot_views(&mut self, p: &mut ::Context) {
+        if self.frozen.is_none() {
+            self.backtrace.refresh(p);
+            self.threads.refresh(p);
+        }
+    }
+
+    /// Whether the container has something to show. Auto-hiding containers of the layout are
+    /// hidden otherwise (unless they are selected).
+    pub fn has_content(&self, container: &TuiContainerType) -> bool {
+        match container {
+            TuiContainerType::SearchResults => !self.search_results.is_empty(),
+            TuiContainerType::LineTable => !self.line_table.is_empty(),
+            TuiContainerType::Inferiors => !self.inferiors.is_empty(),
+            TuiContainerType::Memory => !self.memory.is_empty(),
+            TuiContainerType::MemoryMap => !self.memory_map.is_empty(),
+            TuiContainerType::Breakpoints => !self.breakpoints.is_empty(),
+            TuiContainerType::Threads => !self.threads.is_empty(),
+            TuiContainerType::Backtrace => !self.backtrace.is_empty(),
+            TuiContainerType::Locals => !self.locals.is_empty(),
+            TuiContainerType::Skips => !self.skips.is_empty(),
+            TuiContainerType::Libraries => !self.libraries.is_empty(),
+            TuiContainerType::Trace => !self.trace.is_empty(),
+            TuiContainerType::SrcView
+            | TuiContainerType::Console
+            | TuiContainerType::ExpressionTable
+            | TuiContainerType::Terminal => true,
+        }
+    }
+
+    /// The title bar of the container (with at most `width` columns, which only matters for the
+    /// pager). Containers that show the state of the program are marked as stale while it is
+    /// running, because they are only updated once it stops.
+    pub fn title_bar(&self, container: &TuiContainerType, width: usize, running: bool) -> TitleBar {
+        let count = |n: usize, what: &str| vec![Badge::info(format!("{} {}", n, what))];
+        let mut title = container.name().to_owned();
+        let mut badges = match container {
+            TuiContainerType::SrcView => {
+                let bar = self.src_view.title_bar(width);
+                title = bar.title;
+                bar.badges
+            }
+            TuiContainerType::SearchResults => count(self.search_results.len(), "matches"),
+            TuiContainerType::LineTable => count(self.line_table.len(), "entries"),
+            TuiContainerType::Inferiors => count(self.inferiors.len(), "inferiors"),
+            TuiContainerType::MemoryMap => count(self.memory_map.len(), "regions"),
+            TuiContainerType::Breakpoints => count(self.breakpoints.len(), "breakpoints"),
+            TuiContainerType::Threads => count(self.threads.len(), "threads"),
+            TuiContainerType::Backtrace => count(self.backtrace.len(), "frames"),
+            TuiContainerType::Locals => count(self.locals.len(), "variables"),
+            TuiContainerType::Skips => count(self.skips.len(), "skips"),
+            TuiContainerType::Libraries => count(self.libraries.len(), "libraries"),
+            TuiContainerType::Trace => count(self.trace.len(), "calls"),
+            TuiContainerType::Console
+            | TuiContainerType::ExpressionTable
+            | TuiContainerType::Memory
+            | TuiContainerType::Terminal => Vec::new(),
+        };
+        let shows_program_state = matches!(
+            container,
+            TuiContainerType::ExpressionTable
+                | TuiContainerType::Memory
+                | TuiContainerType::MemoryMap
+                | TuiContainerType::Threads
+                | TuiContainerType::Backtrace
+                | TuiContainerType::Locals
+        );
+        if running && shows_program_state {
+            badges.push(Badge::warning("stale"));
+        }
+        if self.is_frozen() {
+            badges.push(Badge::warning("frozen"));
+        }
+        TitleBar { title, badges }
+    }
+}
+
+/// The bar above a container (see `LayoutNode::draw_bars`).
+pub struct TitleBar {
+    pub title: String,
+    pub badges: Vec<Badge>,
+}
+
+/// Short note on the state of a container, e.g., the number of breakpoints.
+pub struct Badge {
+    pub text: String,
+    pub warning: bool,
+}
+
+impl Badge {
+    pub fn info(text: impl Into<String>) -> Self {
+        Badge {
+            text: text.into(),
+            warning: false,
+        }
+    }
+    pub fn warning(text: impl Into<String>) -> Self {
+        Badge {
+            text: text.into(),
+            warning: true,
+        }
     }
 }
 
@@ -107,6 +491,42 @@ pub enum TuiContainerType {
     Console,
     ExpressionTable,
     Terminal,
+    SearchResults,
+    LineTable,
+    Inferiors,
+    Memory,
+    MemoryMap,
+    Breakpoints,
+    Threads,
+    Backtrace,
+    Locals,
+    Skips,
+    Libraries,
+    Trace,
+}
+
+impl TuiContainerType {
+    /// Human readable name of the container, e.g., for tab bars and error messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TuiContainerType::SrcView => "Pager",
+            TuiContainerType::Console => "Console",
+            TuiContainerType::ExpressionTable => "Expression table",
+            TuiContainerType::Terminal => "Terminal",
+            TuiContainerType::SearchResults => "Search results",
+            TuiContainerType::LineTable => "Line table",
+            TuiContainerType::Inferiors => "Inferiors",
+            TuiContainerType::Memory => "Memory view",
+            TuiContainerType::MemoryMap => "Memory map",
+            TuiContainerType::Breakpoints => "Breakpoints",
+            TuiContainerType::Threads => "Threads",
+            TuiContainerType::Backtrace => "Backtrace",
+            TuiContainerType::Locals => "Locals",
+            TuiContainerType::Skips => "Skips",
+            TuiContainerType::Libraries => "Shared libraries",
+            TuiContainerType::Trace => "Trace",
+        }
+    }
 }
 
 impl<'t> ContainerProvider for Tui<'t> {
@@ -118,6 +538,18 @@ impl<'t> ContainerProvider for Tui<'t> {
             &TuiContainerType::Console => &self.console,
             &TuiContainerType::ExpressionTable => &self.expression_table,
             &TuiContainerType::Terminal => &self.process_pty,
+            &TuiContainerType::SearchResults => &self.search_results,
+            &TuiContainerType::LineTable => &self.line_table,
+            &TuiContainerType::Inferiors => &self.inferiors,
+            &TuiContainerType::Memory => &self.memory,
+            &TuiContainerType::MemoryMap => &self.memory_map,
+            &TuiContainerType::Breakpoints => &self.breakpoints,
+            &TuiContainerType::Threads => &self.threads,
+            &TuiContainerType::Backtrace => &self.backtrace,
+            &TuiContainerType::Locals => &self.locals,
+            &TuiContainerType::Skips => &self.skips,
+            &TuiContainerType::Libraries => &self.libraries,
+            &TuiContainerType::Trace => &self.trace,
         }
     }
     fn get_mut<'a, 'b: 'a>(
@@ -129,6 +561,18 @@ impl<'t> ContainerProvider for Tui<'t> {
             &TuiContainerType::Console => &mut self.console,
             &TuiContainerType::ExpressionTable => &mut self.expression_table,
             &TuiContainerType::Terminal => &mut self.process_pty,
+            &TuiContainerType::SearchResults => &mut self.search_results,
+            &TuiContainerType::LineTable => &mut self.line_table,
+            &TuiContainerType::Inferiors => &mut self.inferiors,
+            &TuiContainerType::Memory => &mut self.memory,
+            &TuiContainerType::MemoryMap => &mut self.memory_map,
+            &TuiContainerType::Breakpoints => &mut self.breakpoints,
+            &TuiContainerType::Threads => &mut self.threads,
+            &TuiContainerType::Backtrace => &mut self.backtrace,
+            &TuiContainerType::Locals => &mut self.locals,
+            &TuiContainerType::Skips => &mut self.skips,
+            &TuiContainerType::Libraries => &mut self.libraries,
+            &TuiContainerType::Trace => &mut self.trace,
         }
     }
     const DEFAULT_CONTAINER: TuiContainerType = TuiContainerType::Console;
@@ -0,0 +1,439 @@
+// Run actions when the value of an expression changes, e.g., open the memory view at a buffer and
+// switch to another layout once an error flag is set, so that long runs do not have to be watched.
+// gdb cannot evaluate expressions while the program is running (in all-stop mode), so they are
+// evaluated in the selected frame every time the program stops. Stops that gdb resumes on its own
+// (e.g., at a breakpoint whose commands `continue`) are over before ugdb handles them, so triggers
+// are not checked there.
+// To not depend on other stops, a watchpoint is inserted for each `nonzero` trigger, so that gdb
+// stops whenever the value changes. If the trigger does not fire there (e.g., because the value
+// changed from one non-zero value to another), the program is continued right away. Watchpoints on
+// locals are deleted by gdb when they go out of scope, they are inserted again at the next stop.
+use actions;
+use gdbmi::commands::{BreakPointNumber, MiCommand, WatchMode};
+use gdbmi::output::{Object, ResultClass};
+use log::warn;
+use std::fmt;
+use std::iter;
+use std::str::FromStr;
+use toml::Value;
+use tui::toasts::ToastLevel;
+
+/// When a trigger fires.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Condition {
+    /// The value becomes non-zero (or true, or a non-null pointer).
+    #[default]
+    NonZero,
+    /// The value differs from the one at the previous stop.
+    Change,
+}
+
+impl FromStr for Condition {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nonzero" => Ok(Condition::NonZero),
+            "change" => Ok(Condition::Change),
+            o => Err(format!(
+                "Unknown trigger condition '{}'. Supported: nonzero, change",
+                o
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Condition::NonZero => write!(f, "nonzero"),
+            Condition::Change => write!(f, "change"),
+        }
+    }
+}
+
+// Whether the value (as printed by gdb, e.g., "0", "0x0", "false", "(char *) 0x0" or
+// "0 '\000'") is zero.
+fn is_zero(value: &str) -> bool {
+    let value = value.trim();
+    // The type that gdb prints in front of pointers.
+    let value = match (value.starts_with('('), value.find(") ")) {
+        (true, Some(end)) => &value[end + 2..],
+        _ => value,
+    };
+    let number = value.split_whitespace().next().unwrap_or("");
+    let number = number.strip_prefix('-').unwrap_or(number);
+    if number == "false" {
+        return true;
+    }
+    if let Some(hex) = number.strip_prefix("0x") {
+        return !hex.is_empty() && hex.chars().all(|c| c == '0');
+    }
+    number.parse::<f64>().map(|n| n == 0.0).unwrap_or(false)
+}
+
+// Only actions are run (gdb's commands can be run at every stop using its own `hook-stop`).
+fn check_command(command: &str) -> Result<(), String> {
+    let id = command
+        .strip_prefix('!')
+        .map(|c| c.split_whitespace().next().unwrap_or(""))
+        .ok_or_else(|| format!("Trigger command '{}' must be an action (!<id>)", command))?;
+    match actions::find(id) {
+        Some(_) => Ok(()),
+        None => Err(format!("Unknown action '!{}' in trigger", id)),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trigger {
+    pub expression: String,
+    pub condition: Condition,
+    /// Actions (e.g., `!layout debug`) that are run in order when the trigger fires.
+    pub commands: Vec<String>,
+    // The last value of the expression that could be evaluated.
+    last_value: Option<String>,
+    // Number of the watchpoint on the expression (only for `nonzero` triggers).
+    watchpoint: Option<usize>,
+}
+
+impl Trigger {
+    pub fn new(
+        expression: String,
+        condition: Condition,
+        commands: Vec<String>,
+    ) -> Result<Self, String> {
+        if expression.trim().is_empty() {
+            return Err("Trigger has no expression".to_owned());
+        }
+        if commands.is_empty() {
+            return Err("Trigger has no commands".to_owned());
+        }
+        for command in &commands {
+            check_command(command)?;
+        }
+        Ok(Trigger {
+            expression,
+            condition,
+            commands,
+            last_value: None,
+            watchpoint: None,
+        })
+    }
+
+    /// Parse a `[[trigger]]` table of the config file.
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        let expression = value
+            .get("expression")
+            .map(|v| v.as_str().ok_or("'trigger.expression' must be a string"))
+            .transpose()?
+            .ok_or("Trigger is missing 'expression'")?;
+        let condition = match value.get("when") {
+            Some(when) => when
+                .as_str()
+                .ok_or("'trigger.when' must be a string")?
+                .parse()?,
+            None => Condition::default(),
+        };
+        let commands = value
+            .get("commands")
+            .ok_or("Trigger is missing 'commands'")?
+            .as_array()
+            .and_then(|commands| {
+                commands
+                    .iter()
+                    .map(|c| c.as_str().map(|c| c.to_owned()))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or("'trigger.commands' must be an array of strings")?;
+        Trigger::new(expression.to_owned(), condition, commands)
+    }
+
+    /// Whether the trigger fires, given the value of the expression at the current stop (`None`
+    /// if it cannot be evaluated, e.g., because it is out of scope).
+    fn update(&mut self, value: Option<String>) -> bool {
+        let value = match value {
+            Some(value) => value,
+            None => return false,
+        };
+        let fires = match self.condition {
+            Condition::NonZero => {
+                !is_zero(&value) && self.last_value.as_deref().is_none_or(is_zero)
+            }
+            Condition::Change => self.last_value.as_ref().is_some_and(|last| *last != value),
+        };
+        self.last_value = Some(value);
+        fires
+    }
+
+    /// Forget the last value, e.g., because the program exited.
+    pub fn reset(&mut self) {
+        self.last_value = None;
+    }
+}
+
+/// `[nonzero|change] <expression> => <command>; <command>...`
+impl FromStr for Trigger {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (expression, commands) = s
+            .split_once("=>")
+            .ok_or("Expected '<expression> => <command>; ...'")?;
+        let expression = expression.trim();
+        let (condition, expression) = match expression.split_once(char::is_whitespace) {
+            Some((condition, rest)) if condition == "nonzero" || condition == "change" => {
+                (condition.parse()?, rest.trim())
+            }
+            _ => (Condition::default(), expression),
+        };
+        let commands = commands
+            .split(';')
+            .map(|c| c.trim().to_owned())
+            .filter(|c| !c.is_empty())
+            .collect();
+        Trigger::new(expression.to_owned(), condition, commands)
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} => {}",
+            self.condition,
+            self.expression,
+            self.commands.join("; ")
+        )
+    }
+}
+
+fn run_command(command: &str, p: &mut ::Context) {
+    let command = command.strip_prefix('!').unwrap_or(command);
+    let (id, args) = command.split_once(' ').unwrap_or((command, ""));
+    match actions::find(id) {
+        Some(action) => action.run(args.trim(), p),
+        None => p.log(format!("Unknown action '!{}' in trigger", id)),
+    }
+}
+
+// Insert the watchpoint of the trigger. Fails if the expression cannot be watched (yet), e.g.,
+// because it refers to locals that are not in scope.
+fn insert_watchpoint(trigger: &mut Trigger, p: &mut ::Context) -> Result<(), String> {
+    if trigger.condition != Condition::NonZero || trigger.watchpoint.is_some() {
+        return Ok(());
+    }
+    let result = p
+        .gdb
+        .mi
+        .execute(MiCommand::insert_watchpoing(
+            &trigger.expression,
+            WatchMode::Write,
+        ))
+        .map_err(|e| format!("{:?}", e))?;
+    if result.class != ResultClass::Done {
+        return Err(result.results["msg"]
+            .as_str()
+            .unwrap_or("Unknown error")
+            .to_owned());
+    }
+    trigger.watchpoint = result.results["wpt"]["number"]
+        .as_str()
+        .and_then(|n| n.parse().ok());
+    Ok(())
+}
+
+// Watchpoints that could not be inserted before are tried again (silently) at every stop.
+fn insert_watchpoints(p: &mut ::Context) {
+    let mut triggers = std::mem::take(&mut p.watch_triggers);
+    for trigger in triggers.iter_mut() {
+        if let Err(e) = insert_watchpoint(trigger, p) {
+            warn!("Could not watch {}: {}", trigger.expression, e);
+        }
+    }
+    p.watch_triggers = triggers;
+}
+
+/// Insert the watchpoints of all triggers, e.g., after gdb was (re)started.
+pub fn reinsert_watchpoints(p: &mut ::Context) {
+    for trigger in p.watch_triggers.iter_mut() {
+        trigger.watchpoint = None;
+    }
+    insert_watchpoints(p);
+}
+
+/// Add a trigger (and insert its watchpoint).
+pub fn add(mut trigger: Trigger, p: &mut ::Context) {
+    if let Err(e) = insert_watchpoint(&mut trigger, p) {
+        p.log(format!(
+            "Could not watch {} (trying again at the next stop): {}",
+            trigger.expression, e
+        ));
+    }
+    p.watch_triggers.push(trigger);
+}
+
+/// Remove a trigger (and delete its watchpoint).
+pub fn remove(index: usize, p: &mut ::Context) -> Trigger {
+    let trigger = p.watch_triggers.remove(index);
+    if let Some(major) = trigger.watchpoint {
+        let number = BreakPointNumber { major, minor: None };
+        if let Err(e) = p.gdb.delete_breakpoints(iter::once(number)) {
+            p.log(format!("Could not delete watchpoint {}: {:?}", major, e));
+        }
+    }
+    trigger
+}
+
+/// Evaluate the expressions of the triggers after the program stopped (the `*stopped` record of
+/// gdb) and run the commands of those that fire. Returns whether the program was continued
+/// because it only stopped at the watchpoint of a trigger that did not fire (otherwise the stop is
+/// shown as usual).
+pub fn handle_stop(results: &Object, p: &mut ::Context) -> bool {
+    // The program was resumed already, e.g., by the commands of a breakpoint.
+    if p.watch_triggers.is_empty() || p.gdb.mi.is_running() {
+        return false;
+    }
+    let reason = results["reason"].as_str().unwrap_or("");
+    if reason.starts_with("exited") {
+        p.watch_triggers.iter_mut().for_each(Trigger::reset);
+        return false;
+    }
+    let watchpoint = match reason {
+        "watchpoint-trigger" => results["wpt"]["number"].as_str(),
+        // gdb deleted the watchpoint because the frame of its expression was left.
+        "watchpoint-scope" => results["wpnum"].as_str(),
+        _ => None,
+    }
+    .and_then(|n| n.parse::<usize>().ok());
+    let at_own_watchpoint =
+        watchpoint.is_some() && p.watch_triggers.iter().any(|t| t.watchpoint == watchpoint);
+    if reason == "watchpoint-scope" {
+        for trigger in p.watch_triggers.iter_mut() {
+            if trigger.watchpoint == watchpoint {
+                trigger.watchpoint = None;
+            }
+        }
+    }
+    insert_watchpoints(p);
+    let expressions = p
+        .watch_triggers
+        .iter()
+        .map(|t| t.expression.clone())
+        .collect::<Vec<_>>();
+    let values = match p.gdb.evaluate_many(&expressions) {
+        Ok(values) => values,
+        Err(e) => {
+            p.log(format!("Could not evaluate triggers: {:?}", e));
+            return false;
+        }
+    };
+    let fired = p
+        .watch_triggers
+        .iter_mut()
+        .zip(values)
+        .filter_map(|(trigger, value)| {
+            if trigger.update(value.ok()) {
+                Some((trigger.to_string(), trigger.commands.clone()))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    if at_own_watchpoint && fired.is_empty() {
+        match p.gdb.execute_running(MiCommand::exec_continue()) {
+            Ok(()) => return true,
+            Err(e) => p.log(format!("Could not continue after a trigger: {}", e)),
+        }
+    }
+    for (trigger, commands) in fired {
+        p.toast(ToastLevel::Info, format!("Trigger: {}", trigger));
+        for command in commands {
+            run_command(&command, p);
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_zero() {
+        for zero in [
+            "0",
+            "-0",
+            "0x0",
+            "0x0000",
+            "false",
+            "0.0",
+            "(char *) 0x0",
+            "0 '\\000'",
+        ] {
+            assert!(is_zero(zero), "{}", zero);
+        }
+        for non_zero in [
+            "1",
+            "0x10",
+            "true",
+            "-3",
+            "(int *) 0x7ffc",
+            "97 'a'",
+            "{a = 0}",
+        ] {
+            assert!(!is_zero(non_zero), "{}", non_zero);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let trigger = "state->error => !memory &buf; !layout embedded"
+            .parse::<Trigger>()
+            .unwrap();
+        assert_eq!(trigger.expression, "state->error");
+        assert_eq!(trigger.condition, Condition::NonZero);
+        assert_eq!(trigger.commands, vec!["!memory &buf", "!layout embedded"]);
+        assert_eq!(
+            trigger.to_string(),
+            "nonzero state->error => !memory &buf; !layout embedded"
+        );
+        let trigger = "change n + 1 => !backtrace".parse::<Trigger>().unwrap();
+        assert_eq!(trigger.condition, Condition::Change);
+        assert_eq!(trigger.expression, "n + 1");
+        assert!("x => ".parse::<Trigger>().is_err());
+        assert!(" => !backtrace".parse::<Trigger>().is_err());
+        assert!("x => print x".parse::<Trigger>().is_err());
+        assert!("x => !no-such-action".parse::<Trigger>().is_err());
+        assert!("x".parse::<Trigger>().is_err());
+    }
+
+    #[test]
+    fn test_update() {
+        let mut nonzero = "x => !backtrace".parse::<Trigger>().unwrap();
+        let mut change = "change x => !backtrace".parse::<Trigger>().unwrap();
+        let values = [
+            Some("0"),
+            Some("1"),
+            None,
+            Some("2"),
+            Some("0"),
+            Some("3"),
+            Some("3"),
+        ];
+        let fired = |t: &mut Trigger| {
+            values
+                .iter()
+                .map(|v| t.update(v.map(|v| v.to_owned())))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            fired(&mut nonzero),
+            vec![false, true, false, false, false, true, false]
+        );
+        assert_eq!(
+            fired(&mut change),
+            vec![false, true, false, true, true, true, false]
+        );
+        // Non-zero from the start (e.g., in the next run) fires right away.
+        nonzero.reset();
+        assert!(nonzero.update(Some("5".to_owned())));
+    }
+}